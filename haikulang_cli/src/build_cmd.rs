@@ -0,0 +1,90 @@
+use crate::check::{check_file, print_errors};
+use crate::error_reporting::AriadneErrorReporter;
+use clap::{Args, ValueEnum};
+use haikulang_compiler::bytecode::compile::compile_module as compile_bytecode;
+use haikulang_compiler::bytecode::disasm::disassemble;
+use haikulang_compiler::bytecode::format::{EXTENSION, encode};
+use haikulang_compiler::codegen::c::emit::compile_module as compile_c;
+use haikulang_compiler::codegen::wasm::lower::compile_module as compile_wasm;
+use haikulang_compiler::db::database::{Database, FileId};
+use haikulang_compiler::error::CompilerError;
+use haikulang_compiler::stdlib::add_std;
+use haikulang_parser::span::Spanned;
+use std::fs::{read_to_string, write};
+use std::path::PathBuf;
+use std::process::exit;
+
+#[derive(Args)]
+pub struct BuildCommand {
+    /// The file to compile.
+    file: PathBuf,
+
+    /// The platform to compile for.
+    #[arg(long, value_enum)]
+    target: Target,
+
+    /// The file to write the output to. Defaults to the input file with the extension for
+    /// the target.
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+
+    /// Write the output in a human-readable text format, where the target has one.
+    #[arg(long)]
+    text: bool,
+
+    /// Leave out the #line directives that point C compilers back at the original source.
+    #[arg(long)]
+    no_line_directives: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Target {
+    /// A WebAssembly module, importing extern functions from the "env" module.
+    Wasm32,
+    /// A C source file, declaring extern functions as prototypes.
+    C,
+    /// A bytecode program for the haikulang virtual machine, which `run` can execute.
+    Bytecode,
+}
+
+pub fn invoke_build(args: BuildCommand) {
+    let mut db = Database::new();
+    add_std(&mut db);
+    let text = read_to_string(&args.file).unwrap();
+    let file = db.add_file(&args.file, &text);
+
+    if check_file(&mut db, file) {
+        exit(2);
+    }
+
+    let (output, extension) = match args.target {
+        Target::Wasm32 => match compile_wasm(&mut db, file) {
+            Ok(module) if args.text => (module.to_text().into_bytes(), "wat"),
+            Ok(module) => (module.to_binary(), "wasm"),
+            Err(errors) => fail(&db, file, &errors),
+        },
+        Target::C => match compile_c(&mut db, file, !args.no_line_directives) {
+            Ok(source) => (source.into_bytes(), "c"),
+            Err(errors) => fail(&db, file, &errors),
+        },
+        Target::Bytecode => match compile_bytecode(&mut db, file) {
+            Ok(program) if args.text => (disassemble(&program).into_bytes(), "txt"),
+            Ok(program) => (encode(&program), EXTENSION),
+            Err(errors) => fail(&db, file, &errors),
+        },
+    };
+
+    let path = args
+        .output
+        .unwrap_or_else(|| args.file.with_extension(extension));
+    write(&path, output).unwrap();
+}
+
+pub fn fail(db: &Database, file: FileId, errors: &[Spanned<CompilerError>]) -> ! {
+    let mut reporter = AriadneErrorReporter::new();
+    for error in errors {
+        reporter.push(error);
+    }
+    print_errors(db, file, &reporter);
+    exit(2);
+}
@@ -0,0 +1,42 @@
+use crate::error_reporting::AriadneErrorReporter;
+use haikulang_compiler::db::database::{Database, FileId, FunctionId};
+
+/// Report every error and warning in a file, returning true if there were any errors.
+pub fn check_file(db: &mut Database, file: FileId) -> bool {
+    let mut reporter = AriadneErrorReporter::new();
+
+    let parsed = db.parse(file);
+    for error in &parsed.errors {
+        reporter.push(error);
+    }
+    for warning in &parsed.warnings {
+        reporter.push_warning(warning);
+    }
+
+    for error in &db.module_errors(file) {
+        reporter.push(error);
+    }
+    for warning in &db.module_warnings(file) {
+        reporter.push_warning(warning);
+    }
+
+    let item_tree = db.item_tree(file);
+    for function in item_tree.functions() {
+        if let Some(result) = db.type_of(&FunctionId::new(file, &function.name)) {
+            for error in &result.errors {
+                reporter.push(error);
+            }
+            for warning in &result.warnings {
+                reporter.push_warning(warning);
+            }
+        }
+    }
+
+    print_errors(db, file, &reporter)
+}
+
+/// Print everything that was reported for a file, returning true if there were any errors.
+pub fn print_errors(db: &Database, file: FileId, reporter: &AriadneErrorReporter) -> bool {
+    let path = db.file_path(file).display().to_string();
+    reporter.print(&path, &db.file_text(file))
+}
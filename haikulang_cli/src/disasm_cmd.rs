@@ -0,0 +1,33 @@
+use clap::Args;
+use haikulang_compiler::bytecode::disasm::disassemble;
+use haikulang_compiler::bytecode::format::decode;
+use haikulang_compiler::bytecode::verify::verify;
+use std::fs::read;
+use std::path::PathBuf;
+use std::process::exit;
+
+#[derive(Args)]
+pub struct DisasmCommand {
+    /// The bytecode file to show.
+    file: PathBuf,
+}
+
+pub fn invoke_disasm(args: DisasmCommand) {
+    let bytes = read(&args.file).unwrap();
+    let program = match decode(&bytes) {
+        Ok(program) => program,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            exit(2);
+        }
+    };
+
+    print!("{}", disassemble(&program));
+
+    // Programs that fail verification are still shown, as that is usually why someone
+    // wants to look at them.
+    if let Err(err) = verify(&program) {
+        eprintln!("error: {}", err);
+        exit(1);
+    }
+}
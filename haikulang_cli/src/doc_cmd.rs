@@ -0,0 +1,64 @@
+use crate::error_reporting::AriadneErrorReporter;
+use crate::files::find_source_files;
+use clap::{Args, ValueEnum};
+use haikulang_compiler::doc::{DocFormat, Documentation};
+use haikulang_parser::lexer::token_stream::TokenStream;
+use haikulang_parser::parser::core::Parser;
+use std::fs::{create_dir_all, read_to_string, write};
+use std::path::PathBuf;
+use std::process::exit;
+
+#[derive(Args)]
+pub struct DocCommand {
+    /// The files or directories to document.
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// The directory to write the documentation to.
+    #[arg(long, short, default_value = "doc")]
+    output: PathBuf,
+
+    /// The format to write the documentation in.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Html)]
+    format: OutputFormat,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Html,
+    Markdown,
+}
+
+pub fn invoke_doc(args: DocCommand) {
+    let mut units = Vec::new();
+    let mut failed = false;
+
+    for file in args.paths.iter().flat_map(|path| find_source_files(path)) {
+        let source = read_to_string(&file).unwrap();
+        let mut error_reporter = AriadneErrorReporter::new();
+        let mut parser = Parser::new(TokenStream::new(&source), &file, &mut error_reporter);
+
+        if let Ok(unit) = parser.parse() {
+            units.push(unit.value());
+        }
+
+        failed |= error_reporter.print(file.to_str().unwrap(), &source);
+    }
+
+    if failed {
+        exit(2);
+    }
+
+    let format = match args.format {
+        OutputFormat::Html => DocFormat::Html,
+        OutputFormat::Markdown => DocFormat::Markdown,
+    };
+
+    for output in Documentation::new(&units).render(format) {
+        let path = args.output.join(&output.path);
+        create_dir_all(path.parent().unwrap()).unwrap();
+        write(&path, output.content).unwrap();
+    }
+
+    println!("Documentation written to {}", args.output.display());
+}
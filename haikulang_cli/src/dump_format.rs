@@ -0,0 +1,13 @@
+use clap::ValueEnum;
+
+/// How the lexer and parser commands show what they produce.
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum DumpFormat {
+    /// JSON following the versioned schema described in `haikulang_parser::dump`.
+    Json,
+    /// S-expressions following the same schema as the JSON output.
+    Sexpr,
+    /// Rust debug output, which is not stable and is only meant for reading.
+    #[default]
+    Debug,
+}
@@ -1,49 +1,83 @@
-use ariadne::{Color, Config, Label, Report, ReportKind, Source};
-use haikulang_parser::error::{ErrorReporter, ParserError};
+use ariadne::{Color, Config, IndexType, Label, Report, ReportKind, Source};
+use haikulang_parser::error::{ErrorReporter, ParserError, ParserWarning};
 use haikulang_parser::span::Spanned;
+use std::fmt::Display;
 
 pub struct AriadneErrorReporter {
-    errors: Vec<Spanned<ParserError>>,
+    errors: Vec<Spanned<String>>,
+    warnings: Vec<Spanned<String>>,
 }
 
 impl AriadneErrorReporter {
     pub fn new() -> Self {
-        Self { errors: Vec::new() }
+        Self {
+            errors: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Record any kind of error, such as those produced by the compiler or interpreter.
+    pub fn push<E: Display + Clone>(&mut self, error: &Spanned<E>) {
+        self.errors
+            .push(Spanned::new(error.value().to_string(), error.span()));
     }
 
+    /// Record a warning, which is shown alongside errors but does not cause a failure.
+    pub fn push_warning<W: Display + Clone>(&mut self, warning: &Spanned<W>) {
+        self.warnings
+            .push(Spanned::new(warning.value().to_string(), warning.span()));
+    }
+
+    // Print all warnings and errors, returning true if there were any errors.
     pub fn print(&self, file: &str, content: &str) -> bool {
-        if self.errors.is_empty() {
-            return false;
+        for warning in &self.warnings {
+            print_report(file, content, ReportKind::Warning, warning);
         }
 
         for error in &self.errors {
-            let mut reporter = Report::build(ReportKind::Error, (file, error.span().range()))
-                .with_message(format!("{}", error.value()))
-                .with_config(
-                    Config::new()
-                        .with_compact(false)
-                        .with_tab_width(4)
-                        .with_multiline_arrows(true)
-                        .with_underlines(true),
-                );
-
-            let label = Label::new((file, error.span().range()))
-                .with_message("error occurred here!")
-                .with_color(Color::BrightRed);
-            reporter = reporter.with_label(label);
-
-            reporter
-                .finish()
-                .print((file, Source::from(content)))
-                .unwrap();
+            print_report(file, content, ReportKind::Error, error);
         }
 
-        true
+        !self.errors.is_empty()
     }
 }
 
 impl ErrorReporter for AriadneErrorReporter {
     fn report(&mut self, error: &Spanned<ParserError>) {
-        self.errors.push(error.clone());
+        self.push(error);
+    }
+
+    fn warn(&mut self, warning: &Spanned<ParserWarning>) {
+        self.push_warning(warning);
     }
 }
+
+fn print_report(file: &str, content: &str, kind: ReportKind, message: &Spanned<String>) {
+    let (label, color) = match kind {
+        ReportKind::Warning => ("warning occurred here!", Color::BrightYellow),
+        _ => ("error occurred here!", Color::BrightRed),
+    };
+
+    let mut reporter = Report::build(kind, (file, message.span().range()))
+        .with_message(message.value())
+        .with_config(
+            Config::new()
+                .with_compact(false)
+                // Spans are byte offsets, which differ from character offsets once
+                // identifiers or strings contain non-ASCII characters.
+                .with_index_type(IndexType::Byte)
+                .with_tab_width(4)
+                .with_multiline_arrows(true)
+                .with_underlines(true),
+        );
+
+    let label = Label::new((file, message.span().range()))
+        .with_message(label)
+        .with_color(color);
+    reporter = reporter.with_label(label);
+
+    reporter
+        .finish()
+        .print((file, Source::from(content)))
+        .unwrap();
+}
@@ -0,0 +1,22 @@
+use std::fs::read_dir;
+use std::path::{Path, PathBuf};
+
+/// Find every source file at the given path. Directories are searched recursively.
+pub fn find_source_files(path: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    collect_source_files(path, &mut files);
+    files.sort();
+    files
+}
+
+fn collect_source_files(path: &Path, files: &mut Vec<PathBuf>) {
+    if path.is_dir() {
+        if let Ok(entries) = read_dir(path) {
+            for entry in entries.flatten() {
+                collect_source_files(&entry.path(), files);
+            }
+        }
+    } else if path.extension().is_some_and(|extension| extension == "hkl") {
+        files.push(path.to_path_buf());
+    }
+}
@@ -1,5 +1,7 @@
+use crate::dump_format::DumpFormat;
 use crate::error_reporting::AriadneErrorReporter;
 use clap::Args;
+use haikulang_parser::dump::token::dump_tokens;
 use haikulang_parser::error::ErrorReporter;
 use haikulang_parser::lexer::token::Token;
 use haikulang_parser::lexer::token_stream::TokenStream;
@@ -10,6 +12,10 @@ use std::process::exit;
 #[derive(Args)]
 pub struct LexerCommand {
     file: PathBuf,
+
+    /// The format to show the tokens in.
+    #[arg(long, value_enum, default_value_t)]
+    format: DumpFormat,
 }
 
 pub fn invoke_lexer(args: LexerCommand) {
@@ -18,20 +24,34 @@ pub fn invoke_lexer(args: LexerCommand) {
     let mut token_stream = TokenStream::new(&source);
     let mut error_reporter = AriadneErrorReporter::new();
 
-    let mut index = 0;
+    let mut tokens = Vec::new();
     loop {
         match token_stream.current() {
             Ok(token) => {
-                println!("{}: {}: {:?}", index, token.span(), token.value());
-                index += 1;
+                let eof = token.value() == Token::Eof;
+                tokens.push(token);
 
-                if token.value() == Token::Eof {
+                if eof {
                     break;
                 } else {
                     token_stream.advance();
                 }
             }
-            Err(err) => error_reporter.report(&err),
+            Err(err) => {
+                // Skip over the bad input so that we can report anything after it too.
+                error_reporter.report(&err);
+                token_stream.advance();
+            }
+        }
+    }
+
+    match args.format {
+        DumpFormat::Json => print!("{}", dump_tokens(&tokens).to_json()),
+        DumpFormat::Sexpr => print!("{}", dump_tokens(&tokens).to_sexpr()),
+        DumpFormat::Debug => {
+            for (index, token) in tokens.iter().enumerate() {
+                println!("{}: {}: {:?}", index, token.span(), token.value());
+            }
         }
     }
 
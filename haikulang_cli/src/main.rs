@@ -1,6 +1,16 @@
+mod build_cmd;
+mod check;
+mod disasm_cmd;
+mod doc_cmd;
+mod dump_format;
 mod error_reporting;
+mod files;
 mod lexer_cmd;
 mod parser_cmd;
+mod repl_cmd;
+mod run_cmd;
+mod test_cmd;
+mod watch_cmd;
 
 use clap::{Parser, Subcommand};
 
@@ -15,18 +25,47 @@ struct MainCommand {
 
 #[derive(Subcommand)]
 enum MainSubCommand {
+    /// Compile the given file into a form that can run without the interpreter.
+    Build(build_cmd::BuildCommand),
+
+    /// Show the instructions of a compiled bytecode file.
+    Disasm(disasm_cmd::DisasmCommand),
+
+    /// Generate documentation for the given files.
+    Doc(doc_cmd::DocCommand),
+
     /// Invoke the lexer across a given file and show the token stream output.
     Lexer(lexer_cmd::LexerCommand),
 
     /// Invoke the parser across a given file and show the AST output.
     Parser(parser_cmd::ParserCommand),
+
+    /// Start an interactive session that runs code as it is entered.
+    Repl(repl_cmd::ReplCommand),
+
+    /// Run the main function of a source file with the interpreter, or of a bytecode file with
+    /// the virtual machine.
+    Run(run_cmd::RunCommand),
+
+    /// Run the functions marked with #[test] in the given files, reporting any that fail.
+    Test(test_cmd::TestCommand),
+
+    /// Watch files for changes, reporting any errors each time they are modified.
+    Watch(watch_cmd::WatchCommand),
 }
 
 fn main() {
     let cli = MainCommand::parse();
 
     match cli.command {
+        MainSubCommand::Build(args) => build_cmd::invoke_build(args),
+        MainSubCommand::Disasm(args) => disasm_cmd::invoke_disasm(args),
+        MainSubCommand::Doc(args) => doc_cmd::invoke_doc(args),
         MainSubCommand::Lexer(args) => lexer_cmd::invoke_lexer(args),
         MainSubCommand::Parser(args) => parser_cmd::invoke_parser(args),
+        MainSubCommand::Repl(args) => repl_cmd::invoke_repl(args),
+        MainSubCommand::Run(args) => run_cmd::invoke_run(args),
+        MainSubCommand::Test(args) => test_cmd::invoke_test(args),
+        MainSubCommand::Watch(args) => watch_cmd::invoke_watch(args),
     }
 }
@@ -1,5 +1,8 @@
+use crate::dump_format::DumpFormat;
 use crate::error_reporting::AriadneErrorReporter;
 use clap::Args;
+use haikulang_parser::cst::parse_lossless;
+use haikulang_parser::dump::ast::dump_compilation_unit;
 use haikulang_parser::lexer::token_stream::TokenStream;
 use haikulang_parser::parser::core::Parser;
 use std::fs::read_to_string;
@@ -9,20 +12,38 @@ use std::process::exit;
 #[derive(Args)]
 pub struct ParserCommand {
     file: PathBuf,
+
+    /// Show the lossless syntax tree, including whitespace and comments, instead of the AST.
+    #[arg(long, conflicts_with = "format")]
+    lossless: bool,
+
+    /// The format to show the AST in.
+    #[arg(long, value_enum, default_value_t)]
+    format: DumpFormat,
 }
 
 pub fn invoke_parser(args: ParserCommand) {
     let path = args.file.as_path();
     let source = read_to_string(path).unwrap();
-    let token_stream = TokenStream::new(&source);
     let mut error_reporter = AriadneErrorReporter::new();
-    let mut parser = Parser::new(token_stream, path, &mut error_reporter);
 
-    match parser.parse() {
-        Ok(ast) => println!("{:#?}", ast),
-        // Errors imply reporting took place, handle that below.
-        Err(err) => eprintln!("Encountered an error {}", err.value()),
-    };
+    if args.lossless {
+        let tree = parse_lossless(&source, path, &mut error_reporter);
+        print!("{}", tree.debug_tree());
+    } else {
+        let token_stream = TokenStream::new(&source);
+        let mut parser = Parser::new(token_stream, path, &mut error_reporter);
+
+        match parser.parse() {
+            Ok(ast) => match args.format {
+                DumpFormat::Json => print!("{}", dump_compilation_unit(&ast).to_json()),
+                DumpFormat::Sexpr => print!("{}", dump_compilation_unit(&ast).to_sexpr()),
+                DumpFormat::Debug => println!("{:#?}", ast),
+            },
+            // Errors imply reporting took place, handle that below.
+            Err(err) => eprintln!("Encountered an error {}", err.value()),
+        };
+    }
 
     if error_reporter.print(path.to_str().unwrap(), &source) {
         exit(2);
@@ -0,0 +1,119 @@
+use crate::error_reporting::AriadneErrorReporter;
+use clap::Args;
+use haikulang_compiler::interp::repl::{ReplOutcome, ReplSession};
+use haikulang_compiler::interp::value::Value;
+use haikulang_parser::span::Spanned;
+use std::io::{Write, stdin, stdout};
+
+const PROMPT: &str = ">> ";
+const CONTINUATION_PROMPT: &str = ".. ";
+const HELP: &str = "\
+Enter items, statements or an expression to run them. Commands:
+  :ast [input]     show the syntax tree of the input, or of the last input
+  :hir [input]     show the lowered form of the input, or of the last input
+  :tokens [input]  show the tokens of the input, or of the last input
+  :vars            show every variable that has been declared
+  :help            show this message
+  :quit            leave the session";
+
+#[derive(Args)]
+pub struct ReplCommand {}
+
+pub fn invoke_repl(_args: ReplCommand) {
+    let mut session = ReplSession::new();
+    let mut buffer = String::new();
+    let mut last_input = String::new();
+
+    println!(
+        "haikulang {} (type :help for help)",
+        env!("CARGO_PKG_VERSION")
+    );
+    prompt(PROMPT);
+
+    for line in stdin().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+
+        if buffer.is_empty() && line.trim_start().starts_with(':') {
+            let (command, argument) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+            let input = if argument.is_empty() {
+                last_input.as_str()
+            } else {
+                argument
+            };
+            match command {
+                ":ast" => print_stage(session.ast(input), input),
+                ":hir" => print_stage(session.hir(input), input),
+                ":tokens" => println!("{}", session.tokens(input)),
+                ":vars" => {
+                    for variable in session.variables() {
+                        println!(
+                            "{}: {} = {}",
+                            variable.name,
+                            variable.ty,
+                            show(&variable.value)
+                        );
+                    }
+                }
+                ":help" => println!("{}", HELP),
+                ":quit" | ":q" => return,
+                _ => println!("unknown command {}, type :help for help", command),
+            }
+            prompt(PROMPT);
+            continue;
+        }
+
+        buffer.push_str(&line);
+        buffer.push('\n');
+        if buffer.trim().is_empty() {
+            buffer.clear();
+            prompt(PROMPT);
+            continue;
+        }
+
+        match session.eval(&buffer, &mut stdout()) {
+            ReplOutcome::Incomplete => {
+                prompt(CONTINUATION_PROMPT);
+                continue;
+            }
+            ReplOutcome::Errors(errors) => print_errors(&errors, &buffer),
+            ReplOutcome::Done => {}
+            ReplOutcome::Value { value, ty } => println!("{}: {}", show(&value), ty),
+        }
+
+        last_input = std::mem::take(&mut buffer);
+        prompt(PROMPT);
+    }
+
+    println!();
+}
+
+fn prompt(text: &str) {
+    print!("{}", text);
+    stdout().flush().unwrap();
+}
+
+fn print_stage(stage: Result<String, ReplOutcome>, input: &str) {
+    match stage {
+        Ok(text) => println!("{}", text),
+        Err(ReplOutcome::Errors(errors)) => print_errors(&errors, input),
+        Err(_) => println!("the input is incomplete"),
+    }
+}
+
+fn print_errors(errors: &[Spanned<String>], input: &str) {
+    let mut reporter = AriadneErrorReporter::new();
+    for error in errors {
+        reporter.push(error);
+    }
+    reporter.print("<repl>", input);
+}
+
+// Strings are quoted so that they can be told apart from other values.
+fn show(value: &Value) -> String {
+    match value {
+        Value::String(text) => format!("{:?}", text),
+        other => other.to_string(),
+    }
+}
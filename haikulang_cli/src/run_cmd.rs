@@ -0,0 +1,105 @@
+use crate::build_cmd::fail;
+use crate::check::{check_file, print_errors};
+use crate::error_reporting::AriadneErrorReporter;
+use clap::Args;
+use haikulang_compiler::bytecode::compile::compile_module;
+use haikulang_compiler::bytecode::format::{EXTENSION, decode};
+use haikulang_compiler::bytecode::program::Program;
+use haikulang_compiler::bytecode::vm::Vm;
+use haikulang_compiler::db::database::Database;
+use haikulang_compiler::error::{RuntimeError, RuntimeResult};
+use haikulang_compiler::interp::interpreter::Interpreter;
+use haikulang_compiler::interp::value::Value;
+use haikulang_compiler::stdlib::add_std;
+use haikulang_parser::span::Span;
+use std::fs::{read, read_to_string};
+use std::io::stdout;
+use std::path::PathBuf;
+use std::process::exit;
+
+#[derive(Args)]
+pub struct RunCommand {
+    /// The file to run. Files with the .hkb extension are loaded as bytecode, and anything
+    /// else is treated as source code.
+    file: PathBuf,
+
+    /// Compile source code to bytecode and run it with the virtual machine instead of the
+    /// interpreter, which is faster.
+    #[arg(long)]
+    vm: bool,
+}
+
+pub fn invoke_run(args: RunCommand) {
+    if args.file.extension().is_some_and(|ext| ext == EXTENSION) {
+        let program = match decode(&read(&args.file).unwrap()) {
+            Ok(program) => program,
+            Err(err) => {
+                eprintln!("error: {}", err);
+                exit(2);
+            }
+        };
+        // There is no source code to show errors against.
+        match run_bytecode(&program) {
+            Ok(value) => finish(value),
+            Err(err) => {
+                exit_if_requested(err.value_ref());
+                eprintln!("error: {}", err.value_ref());
+                exit(1);
+            }
+        }
+        return;
+    }
+
+    let mut db = Database::new();
+    add_std(&mut db);
+    let text = read_to_string(&args.file).unwrap();
+    let file = db.add_file(&args.file, &text);
+    if check_file(&mut db, file) {
+        exit(2);
+    }
+
+    let result = if args.vm {
+        match compile_module(&mut db, file) {
+            Ok(program) => run_bytecode(&program),
+            Err(errors) => fail(&db, file, &errors),
+        }
+    } else {
+        let mut output = stdout();
+        Interpreter::new(&mut db, file, &mut output).call("main", Vec::new(), Span::UNSET)
+    };
+
+    match result {
+        Ok(value) => finish(value),
+        Err(err) => {
+            exit_if_requested(err.value_ref());
+            let mut reporter = AriadneErrorReporter::new();
+            reporter.push(&err);
+            print_errors(&db, file, &reporter);
+            exit(1);
+        }
+    }
+}
+
+fn run_bytecode(program: &Program) -> RuntimeResult<Value> {
+    let mut output = stdout();
+    match Vm::new(program, &mut output) {
+        Ok(mut vm) => vm.call("main", Vec::new()),
+        Err(err) => {
+            eprintln!("error: {}", err);
+            exit(2);
+        }
+    }
+}
+
+// Show what main returned, unless it was nothing at all.
+fn finish(value: Value) {
+    if !matches!(value, Value::Unit) {
+        println!("main returned {}", value);
+    }
+}
+
+fn exit_if_requested(err: &RuntimeError) {
+    if let RuntimeError::Exit(code) = err {
+        exit(*code);
+    }
+}
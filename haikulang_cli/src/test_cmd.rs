@@ -0,0 +1,126 @@
+use crate::check::{check_file, print_errors};
+use crate::error_reporting::AriadneErrorReporter;
+use crate::files::find_source_files;
+use clap::Args;
+use haikulang_compiler::db::database::{Database, FileId};
+use haikulang_compiler::interp::testing::{
+    TestCase, TestOutcome, TestReport, discover_tests, run_test,
+};
+use haikulang_compiler::stdlib::add_std;
+use std::fs::read_to_string;
+use std::path::PathBuf;
+use std::process::exit;
+
+#[derive(Args)]
+pub struct TestCommand {
+    /// The files or directories to search for tests.
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Only run tests whose names contain this text.
+    #[arg(long, short)]
+    filter: Option<String>,
+}
+
+pub fn invoke_test(args: TestCommand) {
+    let mut db = Database::new();
+    add_std(&mut db);
+    let files: Vec<FileId> = args
+        .paths
+        .iter()
+        .flat_map(|path| find_source_files(path))
+        .map(|path| {
+            let text = read_to_string(&path).unwrap();
+            db.add_file(path, &text)
+        })
+        .collect();
+
+    // Tests are only run once everything compiles, as a broken file could make any of
+    // them fail for reasons unrelated to what they test.
+    let mut failed = false;
+    for file in &files {
+        failed |= check_file(&mut db, *file);
+    }
+    if failed {
+        exit(2);
+    }
+
+    let mut tests = Vec::new();
+    let mut filtered_out = 0;
+    for file in &files {
+        for test in discover_tests(&mut db, *file) {
+            let matches = args
+                .filter
+                .as_ref()
+                .is_none_or(|filter| qualified_name(&db, &test).contains(filter.as_str()));
+            if matches {
+                tests.push(test);
+            } else {
+                filtered_out += 1;
+            }
+        }
+    }
+
+    println!("running {} test(s)", tests.len());
+
+    let mut passed = 0;
+    let mut failures = Vec::new();
+    for test in tests {
+        let report = run_test(&mut db, &test);
+        let status = match report.outcome {
+            TestOutcome::Passed => "ok",
+            TestOutcome::Failed(_) => "FAILED",
+            TestOutcome::Panicked(_) => "PANICKED",
+        };
+        println!("test {} ... {}", qualified_name(&db, &test), status);
+
+        if report.outcome == TestOutcome::Passed {
+            passed += 1;
+        } else {
+            failures.push((test, report));
+        }
+    }
+
+    for (test, report) in &failures {
+        print_failure(&db, test, report);
+    }
+
+    let panicked = failures
+        .iter()
+        .filter(|(_, report)| matches!(report.outcome, TestOutcome::Panicked(_)))
+        .count();
+    println!();
+    println!(
+        "test result: {}. {} passed; {} failed; {} panicked; {} filtered out",
+        if failures.is_empty() { "ok" } else { "FAILED" },
+        passed,
+        failures.len() - panicked,
+        panicked,
+        filtered_out
+    );
+
+    if !failures.is_empty() {
+        exit(1);
+    }
+}
+
+// Tests are named after the module that declares them, so tests with the same name in
+// different files can be told apart.
+fn qualified_name(db: &Database, test: &TestCase) -> String {
+    format!("{}::{}", db.module_name(test.file), test.name)
+}
+
+fn print_failure(db: &Database, test: &TestCase, report: &TestReport) {
+    println!();
+    println!("---- {} ----", qualified_name(db, test));
+    if !report.output.is_empty() {
+        print!("{}", report.output);
+    }
+
+    let mut reporter = AriadneErrorReporter::new();
+    match &report.outcome {
+        TestOutcome::Failed(err) | TestOutcome::Panicked(err) => reporter.push(err),
+        TestOutcome::Passed => {}
+    }
+    print_errors(db, test.file, &reporter);
+}
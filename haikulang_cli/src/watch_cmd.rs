@@ -0,0 +1,133 @@
+use crate::check::{check_file, print_errors};
+use crate::error_reporting::AriadneErrorReporter;
+use crate::files::find_source_files;
+use clap::Args;
+use haikulang_compiler::db::database::{Database, FileId};
+use haikulang_compiler::interp::interpreter::Interpreter;
+use haikulang_compiler::stdlib::add_std;
+use haikulang_parser::span::Span;
+use std::collections::BTreeMap;
+use std::fs::read_to_string;
+use std::io::stdout;
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::{Duration, SystemTime};
+
+#[derive(Args)]
+pub struct WatchCommand {
+    /// The file or directory to watch for changes.
+    path: PathBuf,
+
+    /// Run the main function after each successful check.
+    #[arg(long)]
+    run: bool,
+
+    /// How often to check for changes, in milliseconds.
+    #[arg(long, default_value_t = 500)]
+    interval: u64,
+}
+
+type Snapshot = BTreeMap<PathBuf, SystemTime>;
+
+pub fn invoke_watch(args: WatchCommand) {
+    let mut db = Database::new();
+    add_std(&mut db);
+    let mut files: BTreeMap<PathBuf, FileId> = BTreeMap::new();
+    let mut previous: Option<Snapshot> = None;
+
+    loop {
+        let snapshot = take_snapshot(&args.path);
+
+        if previous.as_ref() != Some(&snapshot) {
+            // Deleted files must stop resolving as modules, or code that still uses them
+            // would keep passing its checks.
+            let removed: Vec<PathBuf> = files
+                .keys()
+                .filter(|path| !snapshot.contains_key(*path))
+                .cloned()
+                .collect();
+            for path in removed {
+                if let Some(file) = files.remove(&path) {
+                    db.remove_file(file);
+                }
+            }
+
+            for path in snapshot.keys() {
+                // Files can vanish between taking the snapshot and reading them. We will pick
+                // up the change on the next poll instead.
+                if let Ok(text) = read_to_string(path) {
+                    match files.get(path) {
+                        Some(file) => db.set_file_text(*file, &text),
+                        None => {
+                            files.insert(path.clone(), db.add_file(path, &text));
+                        }
+                    }
+                }
+            }
+
+            // Clear the screen and move the cursor to the top-left corner.
+            print!("\x1b[2J\x1b[H");
+
+            let watched: Vec<FileId> = snapshot
+                .keys()
+                .filter_map(|path| files.get(path).copied())
+                .collect();
+            check_and_run(&mut db, &watched, args.run);
+
+            previous = Some(snapshot);
+        }
+
+        sleep(Duration::from_millis(args.interval));
+    }
+}
+
+fn check_and_run(db: &mut Database, files: &[FileId], run: bool) {
+    let mut failed = false;
+
+    for file in files {
+        failed |= check_file(db, *file);
+    }
+
+    if failed {
+        println!("Checking failed, waiting for changes...");
+        return;
+    }
+
+    println!("Checked {} file(s) successfully.", files.len());
+
+    if run {
+        for file in files {
+            if db.item_tree(*file).function("main").is_some() {
+                run_main(db, *file);
+            }
+        }
+    }
+}
+
+fn run_main(db: &mut Database, file: FileId) {
+    let path = db.file_path(file).display().to_string();
+    println!("Running {}...", path);
+
+    let mut output = stdout();
+    let result = Interpreter::new(db, file, &mut output).call("main", Vec::new(), Span::UNSET);
+
+    match result {
+        Ok(value) => println!("main returned {}", value),
+        Err(err) => {
+            let mut reporter = AriadneErrorReporter::new();
+            reporter.push(&err);
+            print_errors(db, file, &reporter);
+        }
+    }
+}
+
+// Find the modification time of every source file under the given path.
+fn take_snapshot(path: &Path) -> Snapshot {
+    find_source_files(path)
+        .into_iter()
+        .filter_map(|file| {
+            let modified = file.metadata().and_then(|metadata| metadata.modified());
+            modified.ok().map(|modified| (file, modified))
+        })
+        .collect()
+}
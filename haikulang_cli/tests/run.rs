@@ -0,0 +1,74 @@
+use std::fs::write;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+// Write a source file to a fresh temporary location and run it with `haikulang run`.
+fn run(name: &str, source: &str, extra_args: &[&str]) -> Output {
+    let path: PathBuf =
+        std::env::temp_dir().join(format!("haikulang_run_{}_{}.hkl", name, std::process::id()));
+    write(&path, source).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_haikulang_cli"))
+        .arg("run")
+        .args(extra_args)
+        .arg(&path)
+        .output()
+        .unwrap();
+
+    std::fs::remove_file(&path).unwrap();
+    output
+}
+
+#[test]
+fn closures_run_with_the_interpreter() {
+    // Given
+    let source = "fn apply(f: fn(i32) -> i32, x: i32) -> i32 { return f(x); } \
+                  fn main() -> i32 { let n = 2; return apply(|x| x * n, 21); }";
+
+    // When
+    let output = run("closures", source, &[]);
+
+    // Then
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "main returned 42\n"
+    );
+}
+
+#[test]
+fn programs_run_with_the_virtual_machine() {
+    // Given
+    let source = "fn main() -> i32 { let total = 0; let i = 0; \
+                  while (i < 10) { total += i; i += 1; } return total; }";
+
+    // When
+    let output = run("vm", source, &["--vm"]);
+
+    // Then
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "main returned 45\n"
+    );
+}
+
+#[test]
+fn exit_codes_are_passed_on() {
+    // Given
+    let source = "use std; fn main() { std::exit(3); }";
+
+    // When
+    let output = run("exit", source, &[]);
+
+    // Then
+    assert_eq!(output.status.code(), Some(3));
+}
@@ -0,0 +1,106 @@
+//! Compares how long the tree-walking interpreter and the bytecode virtual machine take to
+//! run the same programs. Run with `cargo bench -p haikulang_compiler`.
+use haikulang_compiler::bytecode::compile::compile_module;
+use haikulang_compiler::bytecode::vm::Vm;
+use haikulang_compiler::db::database::Database;
+use haikulang_compiler::interp::interpreter::Interpreter;
+use haikulang_compiler::interp::value::Value;
+use haikulang_parser::span::Span;
+use std::hint::black_box;
+use std::io::sink;
+use std::time::{Duration, Instant};
+
+const RUNS: u32 = 5;
+
+const PROGRAMS: &[(&str, &str)] = &[
+    (
+        "recursion",
+        "fn fib(n: i32) -> i32 { if (n < 2) { return n; } return fib(n - 1) + fib(n - 2); }
+         fn main() -> i32 { return fib(24); }",
+    ),
+    (
+        "loops",
+        "fn main() -> i64 {
+             let total: i64 = 0;
+             let i: i64 = 0;
+             while (i < 300000) {
+                 if (i % 3 == 0 || i % 5 == 0) { total += i; }
+                 i += 1;
+             }
+             return total;
+         }",
+    ),
+    (
+        "structs",
+        "struct V { x: f64; y: f64; }
+         fn step(v: V, dx: f64) -> V { return V(v.x + dx, v.y * 0.5 + 1.0); }
+         fn main() -> f64 {
+             let v = V(0.0, 0.0);
+             let i = 0;
+             while (i < 100000) { v = step(v, 0.25); v.y -= 0.125; i += 1; }
+             return v.x + v.y;
+         }",
+    ),
+    (
+        "strings",
+        "fn main() -> u64 {
+             let count: u64 = 0;
+             let i = 0;
+             while (i < 20000) {
+                 let s = format(\"{}-{}\", i, i * 2);
+                 if (s[0] == 49u8) { count += 1; }
+                 i += 1;
+             }
+             return count;
+         }",
+    ),
+];
+
+fn main() {
+    println!(
+        "{:<12} {:>14} {:>14} {:>9}",
+        "program", "interpreter", "bytecode", "speedup"
+    );
+
+    for (name, source) in PROGRAMS {
+        let mut db = Database::new();
+        let file = db.add_file("bench.hkl", source);
+        let program = compile_module(&mut db, file).expect("benchmarks compile");
+
+        let (interpreted, expected) = fastest(|| {
+            Interpreter::new(&mut db, file, &mut sink()).call("main", vec![], Span::UNSET)
+        });
+        let (compiled, actual) = fastest(|| {
+            let mut output = sink();
+            let mut vm = Vm::new(&program, &mut output).expect("benchmarks verify");
+            vm.call("main", vec![])
+        });
+
+        assert_eq!(actual, expected, "{} produced a different value", name);
+
+        println!(
+            "{:<12} {:>12.2?} {:>14.2?} {:>8.1}x",
+            name,
+            interpreted,
+            compiled,
+            interpreted.as_secs_f64() / compiled.as_secs_f64()
+        );
+    }
+}
+
+// The fastest of several runs, which is the least affected by anything else that the
+// machine is doing, along with the value that every run produced.
+fn fastest<E: std::fmt::Debug>(mut run: impl FnMut() -> Result<Value, E>) -> (Duration, Value) {
+    let mut best = Duration::MAX;
+    let mut expected = None;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        let value = black_box(run()).expect("benchmarks run successfully");
+        best = best.min(start.elapsed());
+        if let Some(expected) = &expected {
+            assert_eq!(&value, expected);
+        }
+        expected = Some(value);
+    }
+    (best, expected.expect("there is at least one run"))
+}
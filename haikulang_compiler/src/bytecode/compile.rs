@@ -0,0 +1,847 @@
+//! Compilation of type checked HIR into bytecode.
+use crate::bytecode::program::{Constant, Extern, Function, Instruction, Program, Struct};
+use crate::db::database::{Database, FileId, FunctionId};
+use crate::db::item_tree::ItemKind;
+use crate::error::CompilerError;
+use crate::hir::arena::{ArenaMap, InterningArena};
+use crate::hir::context::HirModuleContext;
+use crate::hir::nodes::*;
+use crate::hir::ty::HirType;
+use crate::hir::typeck::HirTypeckResult;
+use crate::interp::value::Value;
+use haikulang_parser::span::{Span, Spanned};
+use num_bigint::BigInt;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+const TARGET: &str = "bytecode";
+
+/// Compile every function in a file into a bytecode program, together with the structs and
+/// extern functions that they use.
+///
+/// Every module that the file calls into, such as the standard library, is compiled into the
+/// same program, along with the modules that those call into in turn. Closures are compiled
+/// into functions of their own, which come after all of the named functions.
+///
+/// Only files that passed checking should be compiled, as the types that checking inferred
+/// decide which instructions and constants are emitted. The errors returned here name the
+/// features that bytecode cannot express yet.
+pub fn compile_module(
+    db: &mut Database,
+    file: FileId,
+) -> Result<Program, Vec<Spanned<CompilerError>>> {
+    // Find the modules that are called into as each one is read, so that only the ones that
+    // are needed end up in the program.
+    let mut files = vec![file];
+    let mut functions = Vec::new();
+    let mut next = 0;
+    while let Some(&current) = files.get(next) {
+        next += 1;
+        for item in db.item_tree(current).functions() {
+            let id = FunctionId::new(current, &item.name);
+            let (Some(function), Some(types)) = (db.lower_function(&id), db.type_of(&id)) else {
+                continue;
+            };
+            for (_, expr) in function.expr_arena.iter() {
+                if let HirExprKind::Unresolved(path) = &expr.kind
+                    && let Some((module, _)) = path.split_once("::")
+                    && let Some(found) = db.find_module(module)
+                    && !files.contains(&found)
+                {
+                    files.push(found);
+                }
+            }
+            functions.push(ModuleFunction {
+                file: current,
+                function,
+                types,
+            });
+        }
+    }
+
+    let mut constants = InterningArena::new();
+    let mut intern = |constant| constants.intern(constant).into_raw().into_u32();
+    let mut program = Program {
+        module: intern(Constant::String(db.module_name(file))),
+        ..Program::default()
+    };
+    let mut items = Items {
+        entry: file,
+        modules: HashMap::new(),
+        callees: HashMap::new(),
+        structs: HashMap::new(),
+        closures_start: functions.len() as u32,
+    };
+
+    for &module in &files {
+        let module_name = db.module_name(module);
+        let item_tree = db.item_tree(module);
+        let context = db.module_context(module);
+        items.modules.insert(module_name, module);
+
+        for item in &item_tree.items {
+            match item.kind {
+                ItemKind::ExternFunction => {
+                    let Some(header) = context.lookup_function_by_name(&item.name) else {
+                        continue;
+                    };
+                    let link_name = header
+                        .attributes
+                        .link_name
+                        .clone()
+                        .unwrap_or_else(|| item.name.clone());
+                    items.callees.insert(
+                        (module, item.name.clone()),
+                        Instruction::CallExtern(program.externs.len() as u32),
+                    );
+                    program.externs.push(Extern {
+                        name: intern(Constant::String(items.qualified_name(module, &item.name))),
+                        link_name: intern(Constant::String(link_name)),
+                        arity: header.parameters.len() as u32,
+                    });
+                }
+                // Struct values are named without their module, as they are in the
+                // interpreter, so structs keep their own names.
+                ItemKind::Struct => {
+                    let Some(header) = context.lookup_struct_by_name(&item.name) else {
+                        continue;
+                    };
+                    let members: Vec<HirString> = header
+                        .members
+                        .iter()
+                        .map(|member| context.get_string(member.name).clone())
+                        .collect();
+                    program.structs.push(Struct {
+                        name: intern(Constant::String(item.name.clone())),
+                        members: members
+                            .iter()
+                            .map(|member| intern(Constant::String(member.clone())))
+                            .collect(),
+                    });
+                    items.structs.insert(
+                        (module, item.name.clone()),
+                        StructItem {
+                            index: program.structs.len() as u32 - 1,
+                            members,
+                        },
+                    );
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Call instructions name their callee by its position in the function table, so every
+    // position is handed out first, letting functions call ones declared later on.
+    for (index, item) in functions.iter().enumerate() {
+        let name = db
+            .module_context(item.file)
+            .get_string(item.function.header.name)
+            .clone();
+        items
+            .callees
+            .insert((item.file, name), Instruction::Call(index as u32));
+    }
+
+    // Closures are compiled after every named function, in the order that they were found.
+    // Compiling one can find more closures within it, which are compiled after it in turn.
+    let mut closures = Vec::new();
+    let mut errors = Vec::new();
+    for position in 0usize.. {
+        let (owner, closure) = match position.checked_sub(functions.len()) {
+            None => (position, None),
+            Some(number) => match closures.get(number) {
+                Some(&PendingClosure { function, closure }) => (function, Some((number, closure))),
+                None => break,
+            },
+        };
+
+        let item = &functions[owner];
+        let text = db.file_text(item.file);
+        let compiler = FunctionCompiler {
+            items: &items,
+            file: item.file,
+            module: db.module_context(item.file),
+            text: &text,
+            function: &item.function,
+            types: &item.types,
+            owner,
+            constants: &mut constants,
+            closures: &mut closures,
+            locals: ArenaMap::new(),
+            code: Vec::new(),
+            spans: Vec::new(),
+            loops: Vec::new(),
+            errors: Vec::new(),
+        };
+        let result = match closure {
+            Some((number, closure)) => compiler.compile_closure(number, closure),
+            None => compiler.compile_function(),
+        };
+        match result {
+            Ok(function) => program.functions.push(function),
+            Err(function_errors) => errors.extend(function_errors),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    program.constants = constants
+        .iter()
+        .map(|(_, constant)| constant.clone())
+        .collect();
+    Ok(program)
+}
+
+// A function with a body, from any of the modules that make up the program.
+struct ModuleFunction {
+    file: FileId,
+    function: Rc<HirFunctionData>,
+    types: Rc<HirTypeckResult>,
+}
+
+// A struct along with its position in the struct table.
+struct StructItem {
+    index: u32,
+    members: Vec<HirString>,
+}
+
+// A closure that was found within a function, whose body still has to be compiled.
+#[derive(Clone, Copy)]
+struct PendingClosure {
+    function: usize,
+    closure: HirExprId,
+}
+
+// Everything that functions can refer to, from whichever module declared it.
+struct Items {
+    entry: FileId,
+    modules: HashMap<String, FileId>,
+    callees: HashMap<(FileId, HirString), Instruction>,
+    structs: HashMap<(FileId, HirString), StructItem>,
+    closures_start: u32,
+}
+
+impl Items {
+    // The module and name of the item that a qualified path refers to.
+    fn resolve<'p>(&self, path: &'p str) -> Option<(FileId, &'p str)> {
+        let (module, name) = path.split_once("::")?;
+        Some((*self.modules.get(module)?, name))
+    }
+
+    // Items from the module being compiled keep their own names, and anything else is
+    // qualified with the name of its module.
+    fn qualified_name(&self, file: FileId, name: &str) -> String {
+        if file == self.entry {
+            return name.to_string();
+        }
+        let module = self
+            .modules
+            .iter()
+            .find(|(_, candidate)| **candidate == file)
+            .map(|(module, _)| module.as_str())
+            .unwrap_or_default();
+        format!("{}::{}", module, name)
+    }
+}
+
+// The loop that break and continue statements refer to.
+struct Loop {
+    start: u32,
+    breaks: Vec<usize>,
+}
+
+struct FunctionCompiler<'a> {
+    items: &'a Items,
+    file: FileId,
+    module: &'a HirModuleContext,
+    text: &'a str,
+    function: &'a HirFunctionData,
+    types: &'a HirTypeckResult,
+    // The position of the function in the function table, which closures within it refer
+    // to so that they can be compiled later.
+    owner: usize,
+    constants: &'a mut InterningArena<Constant>,
+    closures: &'a mut Vec<PendingClosure>,
+    locals: ArenaMap<HirVariableId, u32>,
+    code: Vec<Instruction>,
+    spans: Vec<Span>,
+    loops: Vec<Loop>,
+    errors: Vec<Spanned<CompilerError>>,
+}
+
+impl FunctionCompiler<'_> {
+    fn compile_function(self) -> Result<Function, Vec<Spanned<CompilerError>>> {
+        let function = self.function;
+        let name = self.module.get_string(function.header.name);
+        let name = self.items.qualified_name(self.file, name);
+        self.compile(
+            name,
+            &[],
+            &function.parameters,
+            HirClosureBody::Block(function.root_statement),
+            function.header.span,
+        )
+    }
+
+    fn compile_closure(
+        self,
+        number: usize,
+        id: HirExprId,
+    ) -> Result<Function, Vec<Spanned<CompilerError>>> {
+        let expr = self.function.get_expr(id);
+        let HirExprKind::Closure(closure) = &expr.kind else {
+            unreachable!("only closure expressions are queued for compilation");
+        };
+        let owner = self.module.get_string(self.function.header.name);
+        let name = format!(
+            "<closure {} in {}>",
+            number,
+            self.items.qualified_name(self.file, owner)
+        );
+        self.compile(
+            name,
+            &closure.captures,
+            &closure.parameters,
+            closure.body,
+            expr.span,
+        )
+    }
+
+    fn compile(
+        mut self,
+        name: String,
+        captures: &[HirVariableId],
+        parameters: &[HirVariableId],
+        body: HirClosureBody,
+        span: Span,
+    ) -> Result<Function, Vec<Spanned<CompilerError>>> {
+        // Captured values and parameters come first, so that they become the first locals of
+        // the call, in the order that calls push them.
+        let variables = captures
+            .iter()
+            .chain(parameters)
+            .copied()
+            .chain(
+                self.function
+                    .variable_arena
+                    .iter()
+                    .map(|(variable, _)| variable)
+                    .filter(|variable| {
+                        !captures.contains(variable) && !parameters.contains(variable)
+                    }),
+            )
+            .collect::<Vec<_>>();
+        for (local, variable) in variables.iter().enumerate() {
+            self.locals.insert(*variable, local as u32);
+        }
+
+        // Functions that fall off the end return the unit value. Jumps can lead past the last
+        // statement even when it returns, so this is always emitted.
+        match body {
+            HirClosureBody::Expr(expr) => {
+                self.expr(expr);
+                self.emit(Instruction::Return, span);
+            }
+            HirClosureBody::Block(statement) => {
+                self.statement(statement);
+                self.emit(Instruction::Unit, span);
+                self.emit(Instruction::Return, span);
+            }
+        }
+
+        if !self.errors.is_empty() {
+            return Err(self.errors);
+        }
+
+        Ok(Function {
+            name: self.constant(Constant::String(name)),
+            arity: parameters.len() as u32,
+            captures: captures.len() as u32,
+            locals: variables.len() as u32,
+            code: self.code,
+            spans: self.spans,
+        })
+    }
+
+    fn statement(&mut self, id: HirStatementId) {
+        let statement = self.function.get_statement(id);
+        let span = statement.span;
+
+        match &statement.kind {
+            HirStatementKind::Empty => {}
+            HirStatementKind::VarDecl { variable, expr } => {
+                match expr {
+                    Some(expr) => self.expr(*expr),
+                    None => self.emit(Instruction::Unit, span),
+                }
+                self.emit(Instruction::StoreLocal(self.locals[*variable]), span);
+            }
+            HirStatementKind::Destructure { pattern, expr, .. } => {
+                self.expr(*expr);
+                self.bind(pattern);
+            }
+            // Assignments are almost always statements, so they skip producing a unit value
+            // that would only be discarded.
+            HirStatementKind::Expr(expr) => match &self.function.get_expr(*expr).kind {
+                HirExprKind::Assign { target, op, value } => {
+                    self.assign(*target, *op, *value, self.function.get_expr(*expr).span);
+                }
+                _ => {
+                    self.expr(*expr);
+                    self.emit(Instruction::Pop, span);
+                }
+            },
+            HirStatementKind::Return(expr) => {
+                match expr {
+                    Some(expr) => self.expr(*expr),
+                    None => self.emit(Instruction::Unit, span),
+                }
+                self.emit(Instruction::Return, span);
+            }
+            HirStatementKind::Continue => match self.loops.last() {
+                Some(current) => self.emit(Instruction::Jump(current.start), span),
+                None => self.unsupported("continue outside of a loop", span),
+            },
+            HirStatementKind::Break => {
+                let position = self.code.len();
+                match self.loops.last_mut() {
+                    Some(current) => current.breaks.push(position),
+                    None => return self.unsupported("break outside of a loop", span),
+                }
+                self.emit(Instruction::Jump(0), span);
+            }
+            HirStatementKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.expr(*condition);
+                let skip_then = self.emit_jump(Instruction::JumpIfFalse(0), span);
+                self.statement(*then);
+                match otherwise {
+                    Some(otherwise) => {
+                        let skip_otherwise = self.emit_jump(Instruction::Jump(0), span);
+                        self.patch(skip_then);
+                        self.statement(*otherwise);
+                        self.patch(skip_otherwise);
+                    }
+                    None => self.patch(skip_then),
+                }
+            }
+            HirStatementKind::While { condition, body } => {
+                let start = self.position();
+                self.expr(*condition);
+                let exit = self.emit_jump(Instruction::JumpIfFalse(0), span);
+                self.loops.push(Loop {
+                    start,
+                    breaks: Vec::new(),
+                });
+                self.statement(*body);
+                self.emit(Instruction::Jump(start), span);
+                let current = self.loops.pop().expect("the loop was pushed above");
+                self.patch(exit);
+                for position in current.breaks {
+                    self.patch(position);
+                }
+            }
+            HirStatementKind::Block(statements) => {
+                for statement in statements {
+                    self.statement(*statement);
+                }
+            }
+        }
+    }
+
+    // Store the value on top of the stack into the variables of a pattern. Tuples are
+    // unpacked with their last element on top, so their elements are bound in reverse.
+    fn bind(&mut self, pattern: &HirPattern) {
+        match pattern {
+            HirPattern::Variable(variable) => {
+                let span = self.function.get_variable(*variable).location;
+                self.emit(Instruction::StoreLocal(self.locals[*variable]), span);
+            }
+            HirPattern::Tuple { elements, span } => {
+                self.emit(Instruction::Unpack(elements.len() as u32), *span);
+                for element in elements.iter().rev() {
+                    self.bind(element);
+                }
+            }
+        }
+    }
+
+    fn expr(&mut self, id: HirExprId) {
+        let expr = self.function.get_expr(id);
+        let span = expr.span;
+
+        match &expr.kind {
+            HirExprKind::LoadLiteral(literal) => self.literal(id, &literal.kind, span),
+            HirExprKind::LoadVariable(variable) => {
+                self.emit(Instruction::LoadLocal(self.locals[*variable]), span)
+            }
+            HirExprKind::LoadFunction(name) => {
+                let name = self.module.get_string(*name).clone();
+                self.function_value(self.file, &name, span);
+            }
+            HirExprKind::LoadStruct(_) => self.unsupported("structs as values", span),
+            HirExprKind::LoadBuiltin(_) => self.unsupported("builtin function values", span),
+            // Boolean operators short-circuit, leaving the left side as the result if the
+            // right side does not need to be evaluated.
+            HirExprKind::BinaryOp {
+                left,
+                op: op @ (HirExprBinaryOp::BoolAnd | HirExprBinaryOp::BoolOr),
+                right,
+            } => {
+                self.expr(*left);
+                self.emit(Instruction::Dup, span);
+                let skip = if *op == HirExprBinaryOp::BoolAnd {
+                    self.emit_jump(Instruction::JumpIfFalse(0), span)
+                } else {
+                    self.emit_jump(Instruction::JumpIfTrue(0), span)
+                };
+                self.emit(Instruction::Pop, span);
+                self.expr(*right);
+                self.patch(skip);
+            }
+            HirExprKind::BinaryOp { left, op, right } => {
+                self.expr(*left);
+                self.expr(*right);
+                self.emit(Instruction::Binary(*op), span);
+            }
+            HirExprKind::UnaryOp { op, value } => {
+                // Fold negated literals into a single constant, which also lets the most
+                // negative value of a type be written, as its magnitude alone is out of range.
+                if *op == HirExprUnaryOp::Negate
+                    && let HirExprKind::LoadLiteral(HirLiteral {
+                        kind: HirLiteralKind::Int(literal),
+                        ..
+                    }) = &self.function.get_expr(*value).kind
+                {
+                    return self.int_literal(id, -BigInt::from(literal.clone()), span);
+                }
+
+                self.expr(*value);
+                self.emit(Instruction::Unary(*op), span);
+            }
+            HirExprKind::Assign { target, op, value } => {
+                self.assign(*target, *op, *value, span);
+                self.emit(Instruction::Unit, span);
+            }
+            HirExprKind::MemberAccess { owner, member } => {
+                self.expr(*owner);
+                if let Some(position) = self.member_position(*owner, *member, span) {
+                    self.emit(Instruction::GetField(position), span);
+                }
+            }
+            HirExprKind::Index { owner, index } => {
+                self.expr(*owner);
+                self.expr(*index);
+                self.emit(Instruction::Index, span);
+            }
+            HirExprKind::Call { callee, arguments } => self.call(*callee, arguments, span),
+            // The closure takes the values of the variables that it captures as it is made,
+            // while its body is compiled into a function of its own later on.
+            HirExprKind::Closure(closure) => {
+                for capture in &closure.captures {
+                    self.emit(Instruction::LoadLocal(self.locals[*capture]), span);
+                }
+                let index = self.items.closures_start + self.closures.len() as u32;
+                self.closures.push(PendingClosure {
+                    function: self.owner,
+                    closure: id,
+                });
+                self.emit(Instruction::MakeClosure(index), span);
+            }
+            HirExprKind::Tuple(elements) => {
+                for element in elements {
+                    self.expr(*element);
+                }
+                self.emit(Instruction::MakeTuple(elements.len() as u32), span);
+            }
+            HirExprKind::Format(parts) => {
+                for part in parts {
+                    match part {
+                        HirFormatPart::Text(text) => {
+                            let text = self.module.get_string(*text).clone();
+                            self.string(text, span);
+                        }
+                        HirFormatPart::Value(value) => self.expr(*value),
+                    }
+                }
+                self.emit(Instruction::Concat(parts.len() as u32), span);
+            }
+            HirExprKind::Unresolved(path) => match self.items.resolve(path) {
+                Some((file, name)) => self.function_value(file, name, span),
+                None => self.unsupported(format!("the function {}", path), span),
+            },
+        }
+    }
+
+    // Assignments to members load the variable that holds them along with everything
+    // between it and the member, update the member, and then store each of them back.
+    fn assign(
+        &mut self,
+        target: HirExprId,
+        op: Option<HirExprBinaryOp>,
+        value: HirExprId,
+        span: Span,
+    ) {
+        let mut path = Vec::new();
+        let mut root = target;
+        while let HirExprKind::MemberAccess { owner, member } = &self.function.get_expr(root).kind {
+            match self.member_position(*owner, *member, span) {
+                Some(position) => path.push(position),
+                None => return,
+            }
+            root = *owner;
+        }
+        let HirExprKind::LoadVariable(variable) = &self.function.get_expr(root).kind else {
+            return self.unsupported("assignments to this kind of expression", span);
+        };
+        let local = self.locals[*variable];
+        path.reverse();
+
+        if let Some((last, prefix)) = path.split_last() {
+            self.emit(Instruction::LoadLocal(local), span);
+            for position in prefix {
+                self.emit(Instruction::Dup, span);
+                self.emit(Instruction::GetField(*position), span);
+            }
+            if op.is_some() {
+                self.emit(Instruction::Dup, span);
+                self.emit(Instruction::GetField(*last), span);
+            }
+        } else if op.is_some() {
+            self.emit(Instruction::LoadLocal(local), span);
+        }
+
+        self.expr(value);
+        if let Some(op) = op {
+            self.emit(Instruction::Binary(op), span);
+        }
+        for position in path.iter().rev() {
+            self.emit(Instruction::SetField(*position), span);
+        }
+        self.emit(Instruction::StoreLocal(local), span);
+    }
+
+    fn call(&mut self, callee: HirExprId, arguments: &[HirExprId], span: Span) {
+        match &self.function.get_expr(callee).kind {
+            HirExprKind::LoadFunction(name) => {
+                let name = self.module.get_string(*name).clone();
+                self.call_item(self.file, &name, arguments, span);
+            }
+            HirExprKind::LoadStruct(name) => {
+                let name = self.module.get_string(*name).clone();
+                self.call_item(self.file, &name, arguments, span);
+            }
+            HirExprKind::LoadBuiltin(HirBuiltin::Assert) => {
+                self.expr(arguments[0]);
+                let text = self.source_text(arguments[0]);
+                let text = self.constant(Constant::String(text));
+                self.emit(Instruction::Assert(text), span);
+            }
+            HirExprKind::LoadBuiltin(HirBuiltin::AssertEq) => {
+                self.expr(arguments[0]);
+                self.expr(arguments[1]);
+                let text = format!(
+                    "{} == {}",
+                    self.source_text(arguments[0]),
+                    self.source_text(arguments[1])
+                );
+                let text = self.constant(Constant::String(text));
+                self.emit(Instruction::AssertEq(text), span);
+            }
+            HirExprKind::LoadBuiltin(builtin) => {
+                // The format string has already been lowered into a single string.
+                match arguments.first() {
+                    Some(argument) => self.expr(*argument),
+                    None => self.string(String::new(), span),
+                }
+                match builtin {
+                    HirBuiltin::Print => self.emit(Instruction::Print, span),
+                    HirBuiltin::Println => self.emit(Instruction::Println, span),
+                    _ => {}
+                }
+            }
+            HirExprKind::Unresolved(path) => match self.items.resolve(path) {
+                Some((file, name)) => self.call_item(file, name, arguments, span),
+                None => self.unsupported(format!("the function {}", path), span),
+            },
+            _ => {
+                self.expr(callee);
+                for argument in arguments {
+                    self.expr(*argument);
+                }
+                self.emit(Instruction::CallValue(arguments.len() as u32), span);
+            }
+        }
+    }
+
+    // Calls to functions and structs that are known by name go straight to them, rather
+    // than through a function value. Constructing a struct pops one value per member, so the
+    // arguments are pushed in the same order as its members.
+    fn call_item(&mut self, file: FileId, name: &str, arguments: &[HirExprId], span: Span) {
+        let key = (file, name.to_string());
+        let instruction = match (self.items.callees.get(&key), self.items.structs.get(&key)) {
+            (Some(instruction), _) => *instruction,
+            (None, Some(item)) => Instruction::MakeStruct(item.index),
+            (None, None) => {
+                let name = self.items.qualified_name(file, name);
+                return self.unsupported(format!("the function {}", name), span);
+            }
+        };
+        for argument in arguments {
+            self.expr(*argument);
+        }
+        self.emit(instruction, span);
+    }
+
+    fn function_value(&mut self, file: FileId, name: &str, span: Span) {
+        let name = self.items.qualified_name(file, name);
+        let constant = self.constant(Constant::Function(name));
+        self.emit(Instruction::Constant(constant), span);
+    }
+
+    fn literal(&mut self, id: HirExprId, literal: &HirLiteralKind, span: Span) {
+        let constant = match literal {
+            HirLiteralKind::Bool(value) => Constant::Bool(*value),
+            HirLiteralKind::I8(value) => Constant::I8(*value),
+            HirLiteralKind::I16(value) => Constant::I16(*value),
+            HirLiteralKind::I32(value) => Constant::I32(*value),
+            HirLiteralKind::I64(value) => Constant::I64(*value),
+            HirLiteralKind::I128(value) => Constant::I128(*value),
+            HirLiteralKind::ISize(value) => Constant::ISize(*value as i64),
+            HirLiteralKind::U8(value) => Constant::U8(*value),
+            HirLiteralKind::U16(value) => Constant::U16(*value),
+            HirLiteralKind::U32(value) => Constant::U32(*value),
+            HirLiteralKind::U64(value) => Constant::U64(*value),
+            HirLiteralKind::U128(value) => Constant::U128(*value),
+            HirLiteralKind::USize(value) => Constant::USize(*value as u64),
+            HirLiteralKind::F32(value) => Constant::F32(value.to_bits()),
+            HirLiteralKind::F64(value) => Constant::F64(value.to_bits()),
+            HirLiteralKind::String(value) => {
+                Constant::String(self.module.get_string(*value).clone())
+            }
+            HirLiteralKind::Char(value) => Constant::Char(*value),
+            HirLiteralKind::Bytes(value) => Constant::Bytes(value.clone()),
+            HirLiteralKind::Int(value) => {
+                return self.int_literal(id, BigInt::from(value.clone()), span);
+            }
+        };
+        let constant = self.constant(constant);
+        self.emit(Instruction::Constant(constant), span);
+    }
+
+    // Unsuffixed literals become constants of whichever integer type the checker settled on.
+    fn int_literal(&mut self, id: HirExprId, value: BigInt, span: Span) {
+        let ty = self.types.type_of_expr(id);
+        let constant = Value::from_integer(&value, ty).and_then(Constant::from_integer_value);
+
+        match constant {
+            Some(constant) => {
+                let constant = self.constant(constant);
+                self.emit(Instruction::Constant(constant), span);
+            }
+            None => self.errors.push(Spanned::new(
+                CompilerError::IntLiteralOutOfRange {
+                    literal: value.to_string(),
+                    ty: ty.to_string(),
+                },
+                span,
+            )),
+        }
+    }
+
+    // Members are accessed by their position, which is known from the type of their owner.
+    fn member_position(
+        &mut self,
+        owner: HirExprId,
+        member: HirStringId,
+        span: Span,
+    ) -> Option<u32> {
+        let member = self.module.get_string(member);
+        let position = match self.types.type_of_expr(owner) {
+            // Structs from other modules are named by their qualified names.
+            HirType::Struct(name) => {
+                let (file, name) = self.items.resolve(name).unwrap_or((self.file, name));
+                self.items
+                    .structs
+                    .get(&(file, name.to_string()))
+                    .and_then(|item| {
+                        item.members
+                            .iter()
+                            .position(|candidate| candidate == member)
+                    })
+            }
+            HirType::Tuple(elements) => member
+                .parse::<usize>()
+                .ok()
+                .filter(|position| *position < elements.len()),
+            _ => None,
+        };
+
+        if position.is_none() {
+            self.unsupported(format!("the member {}", member), span);
+        }
+        position.map(|position| position as u32)
+    }
+
+    // The text that assertion failures quote, which is stored as a string constant.
+    fn source_text(&self, id: HirExprId) -> String {
+        let range = self.function.get_expr(id).span.range();
+        self.text.get(range).unwrap_or("<unknown>").to_string()
+    }
+
+    fn string(&mut self, text: String, span: Span) {
+        let constant = self.constant(Constant::String(text));
+        self.emit(Instruction::Constant(constant), span);
+    }
+
+    fn constant(&mut self, constant: Constant) -> u32 {
+        self.constants.intern(constant).into_raw().into_u32()
+    }
+
+    fn position(&self) -> u32 {
+        self.code.len() as u32
+    }
+
+    // Spans from other modules would point into source code that errors are not shown
+    // against, so their functions are left without spans.
+    fn emit(&mut self, instruction: Instruction, span: Span) {
+        self.code.push(instruction);
+        self.spans.push(if self.file == self.items.entry {
+            span
+        } else {
+            Span::UNSET
+        });
+    }
+
+    // Emit a jump whose target is filled in later by patch, returning where it is.
+    fn emit_jump(&mut self, instruction: Instruction, span: Span) -> usize {
+        self.emit(instruction, span);
+        self.code.len() - 1
+    }
+
+    // Make a jump go to the next instruction to be emitted.
+    fn patch(&mut self, position: usize) {
+        let target = self.position();
+        match &mut self.code[position] {
+            Instruction::Jump(destination)
+            | Instruction::JumpIfFalse(destination)
+            | Instruction::JumpIfTrue(destination) => *destination = target,
+            other => unreachable!("only jumps are patched, not {:?}", other),
+        }
+    }
+
+    fn unsupported(&mut self, feature: impl Into<String>, span: Span) {
+        self.errors.push(Spanned::new(
+            CompilerError::UnsupportedByTarget {
+                feature: feature.into(),
+                target: TARGET.to_string(),
+            },
+            span,
+        ));
+    }
+}
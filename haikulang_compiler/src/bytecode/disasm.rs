@@ -0,0 +1,213 @@
+//! A readable listing of a bytecode program, for seeing what the compiler produced.
+use crate::bytecode::format::FORMAT_VERSION;
+use crate::bytecode::program::{Instruction, Program};
+use std::fmt::Write;
+
+/// Describe every constant, struct, extern and function in a program.
+///
+/// Programs that have not been verified can still be disassembled, so that broken ones can
+/// be inspected. Names that do not refer to strings are shown as their constant index.
+pub fn disassemble(program: &Program) -> String {
+    let mut out = String::new();
+    let name = |index: u32| match program.string(index) {
+        Some(name) => name.to_string(),
+        None => format!("#{}", index),
+    };
+
+    writeln!(
+        out,
+        "; haikulang bytecode, format version {}",
+        FORMAT_VERSION
+    )
+    .unwrap();
+    writeln!(out, "module {}", name(program.module)).unwrap();
+
+    writeln!(out).unwrap();
+    writeln!(out, "constants:").unwrap();
+    for (index, constant) in program.constants.iter().enumerate() {
+        writeln!(out, "  #{:<4} {}", index, constant).unwrap();
+    }
+
+    for (index, item) in program.structs.iter().enumerate() {
+        let members: Vec<String> = item.members.iter().map(|member| name(*member)).collect();
+        writeln!(out).unwrap();
+        writeln!(
+            out,
+            "struct #{} {} {{ {} }}",
+            index,
+            name(item.name),
+            members.join(", ")
+        )
+        .unwrap();
+    }
+
+    for (index, item) in program.externs.iter().enumerate() {
+        writeln!(out).unwrap();
+        writeln!(
+            out,
+            "extern #{} {} = {:?} ({} parameters)",
+            index,
+            name(item.name),
+            name(item.link_name),
+            item.arity
+        )
+        .unwrap();
+    }
+
+    for (index, function) in program.functions.iter().enumerate() {
+        writeln!(out).unwrap();
+        let captures = match function.captures {
+            0 => String::new(),
+            captures => format!("{} captures, ", captures),
+        };
+        writeln!(
+            out,
+            "fn #{} {} ({}{} parameters, {} locals):",
+            index,
+            name(function.name),
+            captures,
+            function.arity,
+            function.locals
+        )
+        .unwrap();
+        for (position, instruction) in function.code.iter().enumerate() {
+            let text = instruction_text(*instruction);
+            match comment(program, *instruction) {
+                Some(comment) => writeln!(out, "  {:>5}  {:<24} ; {}", position, text, comment),
+                None => writeln!(out, "  {:>5}  {}", position, text),
+            }
+            .unwrap();
+        }
+    }
+
+    out
+}
+
+fn instruction_text(instruction: Instruction) -> String {
+    match instruction {
+        Instruction::Constant(index) => format!("constant #{}", index),
+        Instruction::Unit => "unit".to_string(),
+        Instruction::Pop => "pop".to_string(),
+        Instruction::Dup => "dup".to_string(),
+        Instruction::LoadLocal(local) => format!("load_local {}", local),
+        Instruction::StoreLocal(local) => format!("store_local {}", local),
+        Instruction::Binary(op) => format!("binary {}", snake_case(&format!("{:?}", op))),
+        Instruction::Unary(op) => format!("unary {}", snake_case(&format!("{:?}", op))),
+        Instruction::Index => "index".to_string(),
+        Instruction::GetField(position) => format!("get_field {}", position),
+        Instruction::SetField(position) => format!("set_field {}", position),
+        Instruction::MakeTuple(length) => format!("make_tuple {}", length),
+        Instruction::MakeStruct(index) => format!("make_struct #{}", index),
+        Instruction::Unpack(length) => format!("unpack {}", length),
+        Instruction::Concat(count) => format!("concat {}", count),
+        Instruction::Jump(target) => format!("jump {}", target),
+        Instruction::JumpIfFalse(target) => format!("jump_if_false {}", target),
+        Instruction::JumpIfTrue(target) => format!("jump_if_true {}", target),
+        Instruction::Call(index) => format!("call #{}", index),
+        Instruction::CallExtern(index) => format!("call_extern #{}", index),
+        Instruction::CallValue(count) => format!("call_value {}", count),
+        Instruction::MakeClosure(index) => format!("make_closure #{}", index),
+        Instruction::Return => "return".to_string(),
+        Instruction::Print => "print".to_string(),
+        Instruction::Println => "println".to_string(),
+        Instruction::Assert(text) => format!("assert #{}", text),
+        Instruction::AssertEq(text) => format!("assert_eq #{}", text),
+    }
+}
+
+// What an instruction refers to, where that is not obvious from the instruction itself.
+fn comment(program: &Program, instruction: Instruction) -> Option<String> {
+    match instruction {
+        Instruction::Constant(index)
+        | Instruction::Assert(index)
+        | Instruction::AssertEq(index) => program
+            .constants
+            .get(index as usize)
+            .map(ToString::to_string),
+        Instruction::MakeStruct(index) => program
+            .structs
+            .get(index as usize)
+            .and_then(|item| program.string(item.name))
+            .map(ToString::to_string),
+        Instruction::Call(index) | Instruction::MakeClosure(index) => program
+            .functions
+            .get(index as usize)
+            .and_then(|function| program.string(function.name))
+            .map(ToString::to_string),
+        Instruction::CallExtern(index) => program
+            .externs
+            .get(index as usize)
+            .and_then(|item| program.string(item.name))
+            .map(ToString::to_string),
+        _ => None,
+    }
+}
+
+fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for (index, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() && index > 0 {
+            out.push('_');
+        }
+        out.push(c.to_ascii_lowercase());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytecode::compile::compile_module;
+    use crate::db::database::Database;
+
+    #[test]
+    fn programs_are_disassembled() {
+        // Given
+        let mut db = Database::new();
+        let file = db.add_file(
+            "test.hkl",
+            "struct P { x: i32; } extern fn exit(code: i32); fn main() { let p = P(1); if (p.x <= 1) { exit(p.x); } }",
+        );
+        let program = compile_module(&mut db, file).unwrap();
+
+        // When
+        let text = disassemble(&program);
+
+        // Then
+        assert_eq!(
+            text,
+            "\
+; haikulang bytecode, format version 2
+module test
+
+constants:
+  #0    string \"test\"
+  #1    string \"P\"
+  #2    string \"x\"
+  #3    string \"exit\"
+  #4    i32 1
+  #5    string \"main\"
+
+struct #0 P { x }
+
+extern #0 exit = \"exit\" (1 parameters)
+
+fn #0 main (0 parameters, 1 locals):
+      0  constant #4              ; i32 1
+      1  make_struct #0           ; P
+      2  store_local 0
+      3  load_local 0
+      4  get_field 0
+      5  constant #4              ; i32 1
+      6  binary less_eq
+      7  jump_if_false 12
+      8  load_local 0
+      9  get_field 0
+     10  call_extern #0           ; exit
+     11  pop
+     12  unit
+     13  return
+"
+        );
+    }
+}
@@ -0,0 +1,600 @@
+//! The on-disk format of bytecode programs, which are usually kept in `.hkb` files.
+//!
+//! Files start with the magic bytes `HKB\0` and a little-endian u16 format version, which is
+//! increased whenever the layout changes. Everything after that is made up of bytes and of
+//! integers in LEB128, which are signed for signed integer constants and unsigned elsewhere.
+//! Lists are written as their length followed by their elements.
+//!
+//! ```text
+//! program   = magic version module:u constants structs externs functions
+//! constant  = tag:byte payload
+//! struct    = name:u members:list(u)
+//! extern    = name:u link_name:u arity:u
+//! function  = name:u arity:u captures:u locals:u code:list(instruction) spans
+//! spans     = (start:u length:u) for each instruction
+//! ```
+//!
+//! Names are indices into the constant pool, which must refer to string constants.
+use crate::bytecode::program::{Constant, Extern, Function, Instruction, Program, Struct};
+use crate::error::BytecodeError;
+use crate::hir::nodes::{HirExprBinaryOp, HirExprUnaryOp};
+use haikulang_parser::span::Span;
+use num_bigint::BigInt;
+
+/// The bytes that every bytecode file starts with.
+pub const MAGIC: &[u8; 4] = b"HKB\0";
+
+/// The version of the format that is written, and the only one that can be read.
+pub const FORMAT_VERSION: u16 = 2;
+
+/// The extension that bytecode files are given.
+pub const EXTENSION: &str = "hkb";
+
+// Operators are written as their position in these lists.
+const BINARY_OPERATORS: &[HirExprBinaryOp] = &[
+    HirExprBinaryOp::Add,
+    HirExprBinaryOp::Sub,
+    HirExprBinaryOp::Mul,
+    HirExprBinaryOp::Div,
+    HirExprBinaryOp::Mod,
+    HirExprBinaryOp::Pow,
+    HirExprBinaryOp::BinaryAnd,
+    HirExprBinaryOp::BinaryOr,
+    HirExprBinaryOp::BinaryXor,
+    HirExprBinaryOp::BinaryShl,
+    HirExprBinaryOp::BinaryShr,
+    HirExprBinaryOp::BoolAnd,
+    HirExprBinaryOp::BoolOr,
+    HirExprBinaryOp::Eq,
+    HirExprBinaryOp::NotEq,
+    HirExprBinaryOp::Less,
+    HirExprBinaryOp::LessEq,
+    HirExprBinaryOp::Greater,
+    HirExprBinaryOp::GreaterEq,
+];
+
+const UNARY_OPERATORS: &[HirExprUnaryOp] = &[
+    HirExprUnaryOp::Negate,
+    HirExprUnaryOp::Not,
+    HirExprUnaryOp::Invert,
+];
+
+/// Write a program in the bytecode format.
+pub fn encode(program: &Program) -> Vec<u8> {
+    let mut out = Writer(Vec::new());
+    out.0.extend_from_slice(MAGIC);
+    out.0.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    out.unsigned(program.module);
+
+    out.usize(program.constants.len());
+    for constant in &program.constants {
+        out.constant(constant);
+    }
+
+    out.usize(program.structs.len());
+    for item in &program.structs {
+        out.unsigned(item.name);
+        out.usize(item.members.len());
+        for member in &item.members {
+            out.unsigned(*member);
+        }
+    }
+
+    out.usize(program.externs.len());
+    for item in &program.externs {
+        out.unsigned(item.name);
+        out.unsigned(item.link_name);
+        out.unsigned(item.arity);
+    }
+
+    out.usize(program.functions.len());
+    for function in &program.functions {
+        out.unsigned(function.name);
+        out.unsigned(function.arity);
+        out.unsigned(function.captures);
+        out.unsigned(function.locals);
+        out.usize(function.code.len());
+        for instruction in &function.code {
+            out.instruction(instruction);
+        }
+        for span in &function.spans {
+            out.usize(span.start());
+            out.usize(span.end().wrapping_sub(span.start()));
+        }
+    }
+
+    out.0
+}
+
+/// Read a program in the bytecode format.
+///
+/// This only checks that the program is well formed. It still has to be verified before it
+/// can be run.
+pub fn decode(bytes: &[u8]) -> Result<Program, BytecodeError> {
+    let mut input = Reader { bytes, position: 0 };
+    if input.take(MAGIC.len())? != MAGIC {
+        return Err(BytecodeError::BadMagic);
+    }
+    let version = u16::from_le_bytes([input.byte()?, input.byte()?]);
+    if version != FORMAT_VERSION {
+        return Err(BytecodeError::UnsupportedVersion(version));
+    }
+
+    let mut program = Program {
+        module: input.u32()?,
+        ..Program::default()
+    };
+
+    for _ in 0..input.length()? {
+        program.constants.push(input.constant()?);
+    }
+
+    for _ in 0..input.length()? {
+        let name = input.u32()?;
+        let mut members = Vec::new();
+        for _ in 0..input.length()? {
+            members.push(input.u32()?);
+        }
+        program.structs.push(Struct { name, members });
+    }
+
+    for _ in 0..input.length()? {
+        program.externs.push(Extern {
+            name: input.u32()?,
+            link_name: input.u32()?,
+            arity: input.u32()?,
+        });
+    }
+
+    for _ in 0..input.length()? {
+        let name = input.u32()?;
+        let arity = input.u32()?;
+        let captures = input.u32()?;
+        let locals = input.u32()?;
+        let length = input.length()?;
+        let mut code = Vec::new();
+        for _ in 0..length {
+            code.push(input.instruction()?);
+        }
+        let mut spans = Vec::new();
+        for _ in 0..length {
+            let start = input.usize()?;
+            let end = start.wrapping_add(input.usize()?);
+            spans.push(Span::new(start, end));
+        }
+        program.functions.push(Function {
+            name,
+            arity,
+            captures,
+            locals,
+            code,
+            spans,
+        });
+    }
+
+    if input.position != bytes.len() {
+        return Err(BytecodeError::TrailingData);
+    }
+    Ok(program)
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn unsigned(&mut self, value: impl Into<u128>) {
+        let mut value = value.into();
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.0.push(byte);
+                return;
+            }
+            self.0.push(byte | 0x80);
+        }
+    }
+
+    fn signed(&mut self, value: impl Into<i128>) {
+        let mut value = value.into();
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            let sign_bit = byte & 0x40 != 0;
+            if (value == 0 && !sign_bit) || (value == -1 && sign_bit) {
+                self.0.push(byte);
+                return;
+            }
+            self.0.push(byte | 0x80);
+        }
+    }
+
+    fn usize(&mut self, value: usize) {
+        self.unsigned(value as u128);
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.usize(bytes.len());
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn constant(&mut self, constant: &Constant) {
+        match constant {
+            Constant::Bool(value) => {
+                self.0.push(0x00);
+                self.0.push(*value as u8);
+            }
+            Constant::I8(value) => self.tagged_signed(0x01, *value),
+            Constant::I16(value) => self.tagged_signed(0x02, *value),
+            Constant::I32(value) => self.tagged_signed(0x03, *value),
+            Constant::I64(value) => self.tagged_signed(0x04, *value),
+            Constant::I128(value) => self.tagged_signed(0x05, *value),
+            Constant::ISize(value) => self.tagged_signed(0x06, *value),
+            Constant::U8(value) => self.tagged_unsigned(0x07, *value),
+            Constant::U16(value) => self.tagged_unsigned(0x08, *value),
+            Constant::U32(value) => self.tagged_unsigned(0x09, *value),
+            Constant::U64(value) => self.tagged_unsigned(0x0a, *value),
+            Constant::U128(value) => self.tagged_unsigned(0x0b, *value),
+            Constant::USize(value) => self.tagged_unsigned(0x0c, *value),
+            Constant::F32(bits) => {
+                self.0.push(0x0d);
+                self.0.extend_from_slice(&bits.to_le_bytes());
+            }
+            Constant::F64(bits) => {
+                self.0.push(0x0e);
+                self.0.extend_from_slice(&bits.to_le_bytes());
+            }
+            Constant::String(value) => {
+                self.0.push(0x0f);
+                self.bytes(value.as_bytes());
+            }
+            Constant::Char(value) => self.tagged_unsigned(0x10, *value),
+            Constant::Bytes(value) => {
+                self.0.push(0x11);
+                self.bytes(value);
+            }
+            Constant::BigInt(value) => {
+                self.0.push(0x12);
+                self.bytes(&value.to_signed_bytes_le());
+            }
+            Constant::Function(name) => {
+                self.0.push(0x13);
+                self.bytes(name.as_bytes());
+            }
+        }
+    }
+
+    fn tagged_signed(&mut self, tag: u8, value: impl Into<i128>) {
+        self.0.push(tag);
+        self.signed(value);
+    }
+
+    fn tagged_unsigned(&mut self, tag: u8, value: impl Into<u128>) {
+        self.0.push(tag);
+        self.unsigned(value);
+    }
+
+    fn instruction(&mut self, instruction: &Instruction) {
+        let (opcode, operand) = match *instruction {
+            Instruction::Constant(index) => (0x00, Some(index)),
+            Instruction::Unit => (0x01, None),
+            Instruction::Pop => (0x02, None),
+            Instruction::Dup => (0x03, None),
+            Instruction::LoadLocal(local) => (0x04, Some(local)),
+            Instruction::StoreLocal(local) => (0x05, Some(local)),
+            Instruction::Binary(op) => (0x06, Some(operator_code(BINARY_OPERATORS, op))),
+            Instruction::Unary(op) => (0x07, Some(operator_code(UNARY_OPERATORS, op))),
+            Instruction::Index => (0x08, None),
+            Instruction::GetField(position) => (0x09, Some(position)),
+            Instruction::SetField(position) => (0x0a, Some(position)),
+            Instruction::MakeTuple(length) => (0x0b, Some(length)),
+            Instruction::MakeStruct(index) => (0x0c, Some(index)),
+            Instruction::Unpack(length) => (0x0d, Some(length)),
+            Instruction::Concat(count) => (0x0e, Some(count)),
+            Instruction::Jump(target) => (0x0f, Some(target)),
+            Instruction::JumpIfFalse(target) => (0x10, Some(target)),
+            Instruction::JumpIfTrue(target) => (0x11, Some(target)),
+            Instruction::Call(index) => (0x12, Some(index)),
+            Instruction::CallExtern(index) => (0x13, Some(index)),
+            Instruction::CallValue(count) => (0x14, Some(count)),
+            Instruction::Return => (0x15, None),
+            Instruction::Print => (0x16, None),
+            Instruction::Println => (0x17, None),
+            Instruction::Assert(text) => (0x18, Some(text)),
+            Instruction::AssertEq(text) => (0x19, Some(text)),
+            Instruction::MakeClosure(index) => (0x1a, Some(index)),
+        };
+        self.0.push(opcode);
+        if let Some(operand) = operand {
+            self.unsigned(operand);
+        }
+    }
+}
+
+fn operator_code<T: PartialEq>(operators: &[T], op: T) -> u32 {
+    operators
+        .iter()
+        .position(|candidate| *candidate == op)
+        .expect("every operator has a code") as u32
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> Result<u8, BytecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn take(&mut self, length: usize) -> Result<&[u8], BytecodeError> {
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(BytecodeError::UnexpectedEnd)?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn unsigned(&mut self) -> Result<u128, BytecodeError> {
+        let mut value = 0u128;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            if shift >= 128 || (shift == 126 && byte & 0x7c != 0) {
+                return Err(too_large());
+            }
+            value |= u128::from(byte & 0x7f) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn signed(&mut self) -> Result<i128, BytecodeError> {
+        let mut value = 0i128;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            if shift >= 128 {
+                return Err(too_large());
+            }
+            value |= i128::from(byte & 0x7f) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if shift < 128 && byte & 0x40 != 0 {
+                    value |= -1 << shift;
+                }
+                return Ok(value);
+            }
+        }
+    }
+
+    fn u32(&mut self) -> Result<u32, BytecodeError> {
+        u32::try_from(self.unsigned()?).map_err(|_| too_large())
+    }
+
+    fn usize(&mut self) -> Result<usize, BytecodeError> {
+        usize::try_from(self.unsigned()?).map_err(|_| too_large())
+    }
+
+    // Lists can never have more elements than there are bytes left, so larger lengths are
+    // rejected before anything is allocated for them.
+    fn length(&mut self) -> Result<usize, BytecodeError> {
+        let length = self.usize()?;
+        if length > self.bytes.len() - self.position {
+            return Err(BytecodeError::UnexpectedEnd);
+        }
+        Ok(length)
+    }
+
+    fn bytes(&mut self) -> Result<&[u8], BytecodeError> {
+        let length = self.length()?;
+        self.take(length)
+    }
+
+    fn text(&mut self) -> Result<String, BytecodeError> {
+        let bytes = self.bytes()?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| BytecodeError::InvalidText)
+    }
+
+    fn constant(&mut self) -> Result<Constant, BytecodeError> {
+        let tag = self.byte()?;
+        Ok(match tag {
+            0x00 => match self.byte()? {
+                0 => Constant::Bool(false),
+                1 => Constant::Bool(true),
+                _ => return Err(BytecodeError::InvalidConstantTag(tag)),
+            },
+            0x01 => Constant::I8(self.signed_as()?),
+            0x02 => Constant::I16(self.signed_as()?),
+            0x03 => Constant::I32(self.signed_as()?),
+            0x04 => Constant::I64(self.signed_as()?),
+            0x05 => Constant::I128(self.signed()?),
+            0x06 => Constant::ISize(self.signed_as()?),
+            0x07 => Constant::U8(self.unsigned_as()?),
+            0x08 => Constant::U16(self.unsigned_as()?),
+            0x09 => Constant::U32(self.unsigned_as()?),
+            0x0a => Constant::U64(self.unsigned_as()?),
+            0x0b => Constant::U128(self.unsigned()?),
+            0x0c => Constant::USize(self.unsigned_as()?),
+            0x0d => Constant::F32(u32::from_le_bytes(self.array()?)),
+            0x0e => Constant::F64(u64::from_le_bytes(self.array()?)),
+            0x0f => Constant::String(self.text()?),
+            0x10 => {
+                let value = self.unsigned_as::<u32>()?;
+                Constant::Char(char::from_u32(value).ok_or(BytecodeError::InvalidText)?)
+            }
+            0x11 => Constant::Bytes(Box::from(self.bytes()?)),
+            0x12 => Constant::BigInt(BigInt::from_signed_bytes_le(self.bytes()?)),
+            0x13 => Constant::Function(self.text()?),
+            _ => return Err(BytecodeError::InvalidConstantTag(tag)),
+        })
+    }
+
+    fn signed_as<T: TryFrom<i128>>(&mut self) -> Result<T, BytecodeError> {
+        T::try_from(self.signed()?).map_err(|_| too_large())
+    }
+
+    fn unsigned_as<T: TryFrom<u128>>(&mut self) -> Result<T, BytecodeError> {
+        T::try_from(self.unsigned()?).map_err(|_| too_large())
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], BytecodeError> {
+        Ok(self
+            .take(N)?
+            .try_into()
+            .expect("exactly N bytes were taken"))
+    }
+
+    fn instruction(&mut self) -> Result<Instruction, BytecodeError> {
+        let opcode = self.byte()?;
+        Ok(match opcode {
+            0x00 => Instruction::Constant(self.u32()?),
+            0x01 => Instruction::Unit,
+            0x02 => Instruction::Pop,
+            0x03 => Instruction::Dup,
+            0x04 => Instruction::LoadLocal(self.u32()?),
+            0x05 => Instruction::StoreLocal(self.u32()?),
+            0x06 => Instruction::Binary(self.operator(BINARY_OPERATORS)?),
+            0x07 => Instruction::Unary(self.operator(UNARY_OPERATORS)?),
+            0x08 => Instruction::Index,
+            0x09 => Instruction::GetField(self.u32()?),
+            0x0a => Instruction::SetField(self.u32()?),
+            0x0b => Instruction::MakeTuple(self.u32()?),
+            0x0c => Instruction::MakeStruct(self.u32()?),
+            0x0d => Instruction::Unpack(self.u32()?),
+            0x0e => Instruction::Concat(self.u32()?),
+            0x0f => Instruction::Jump(self.u32()?),
+            0x10 => Instruction::JumpIfFalse(self.u32()?),
+            0x11 => Instruction::JumpIfTrue(self.u32()?),
+            0x12 => Instruction::Call(self.u32()?),
+            0x13 => Instruction::CallExtern(self.u32()?),
+            0x14 => Instruction::CallValue(self.u32()?),
+            0x15 => Instruction::Return,
+            0x16 => Instruction::Print,
+            0x17 => Instruction::Println,
+            0x18 => Instruction::Assert(self.u32()?),
+            0x19 => Instruction::AssertEq(self.u32()?),
+            0x1a => Instruction::MakeClosure(self.u32()?),
+            _ => return Err(BytecodeError::InvalidOpcode(opcode)),
+        })
+    }
+
+    fn operator<T: Copy>(&mut self, operators: &[T]) -> Result<T, BytecodeError> {
+        let code = self.u32()?;
+        operators
+            .get(code as usize)
+            .copied()
+            .ok_or_else(|| BytecodeError::Invalid {
+                location: format!("byte {}", self.position),
+                reason: format!("unknown operator {}", code),
+            })
+    }
+}
+
+fn too_large() -> BytecodeError {
+    BytecodeError::Invalid {
+        location: "the encoding".to_string(),
+        reason: "an integer is too large".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytecode::compile::compile_module;
+    use crate::db::database::Database;
+    use test_case::test_case;
+
+    fn compile(source: &str) -> Program {
+        let mut db = Database::new();
+        let file = db.add_file("test.hkl", source);
+        compile_module(&mut db, file).unwrap()
+    }
+
+    #[test]
+    fn programs_survive_a_round_trip() {
+        // Given
+        let program = compile(
+            "struct P { x: i32; y: string; } extern fn to_string(value: i64) -> string; fn main() -> bool { let p = P(-1, \"é\"); let t = (1u128 << 100u128, 2.5f32, -3.25, 'x', b\"\\xff\", to_string); let big: std::BigInt = -123456789012345678901234567890; return p.x < 0 && big < 0 && -9223372036854775808i64 < 0; }",
+        );
+
+        // When
+        let decoded = decode(&encode(&program));
+
+        // Then
+        assert_eq!(decoded, Ok(program));
+    }
+
+    #[test]
+    fn files_start_with_the_magic_and_version() {
+        // When
+        let bytes = encode(&compile("fn main() {}"));
+
+        // Then
+        assert_eq!(&bytes[..6], b"HKB\0\x02\0");
+    }
+
+    #[test_case(b"HKA\0\x01\0",        BytecodeError::BadMagic               ; "wrong magic")]
+    #[test_case(b"HK",                  BytecodeError::UnexpectedEnd          ; "too short for the magic")]
+    #[test_case(b"HKB\0\x03\0",        BytecodeError::UnsupportedVersion(3)  ; "newer version")]
+    #[test_case(b"HKB\0\x02\0\0",      BytecodeError::UnexpectedEnd          ; "missing sections")]
+    #[test_case(b"HKB\0\x02\0\0\x01\x20", BytecodeError::InvalidConstantTag(0x20) ; "unknown constant")]
+    #[test_case(b"HKB\0\x02\0\0\x01\x0f\x01\xff", BytecodeError::InvalidText ; "invalid utf-8")]
+    #[test_case(b"HKB\0\x02\0\0\x01\x0f\x05a", BytecodeError::UnexpectedEnd ; "string longer than the file")]
+    #[test_case(b"HKB\0\x02\0\0\0\0\0\x01\0\0\0\0\x01\x7f", BytecodeError::InvalidOpcode(0x7f) ; "unknown opcode")]
+    #[test_case(b"HKB\0\x02\0\0\0\0\0\0\0",  BytecodeError::TrailingData           ; "trailing data")]
+    fn malformed_files_are_rejected(bytes: &[u8], expected: BytecodeError) {
+        // When
+        let result = decode(bytes);
+
+        // Then
+        assert_eq!(result, Err(expected));
+    }
+
+    #[test_case(             0 ; "zero")]
+    #[test_case(           127 ; "largest single byte")]
+    #[test_case(           128 ; "smallest two bytes")]
+    #[test_case(     u128::MAX ; "largest")]
+    fn unsigned_integers_survive_a_round_trip(value: u128) {
+        // Given
+        let mut out = Writer(Vec::new());
+        out.unsigned(value);
+
+        // When
+        let decoded = Reader {
+            bytes: &out.0,
+            position: 0,
+        }
+        .unsigned();
+
+        // Then
+        assert_eq!(decoded, Ok(value));
+    }
+
+    #[test_case(             0 ; "zero")]
+    #[test_case(            -1 ; "minus one")]
+    #[test_case(            63 ; "largest positive single byte")]
+    #[test_case(           -64 ; "smallest negative single byte")]
+    #[test_case(     i128::MAX ; "largest")]
+    #[test_case(     i128::MIN ; "smallest")]
+    fn signed_integers_survive_a_round_trip(value: i128) {
+        // Given
+        let mut out = Writer(Vec::new());
+        out.signed(value);
+
+        // When
+        let decoded = Reader {
+            bytes: &out.0,
+            position: 0,
+        }
+        .signed();
+
+        // Then
+        assert_eq!(decoded, Ok(value));
+    }
+}
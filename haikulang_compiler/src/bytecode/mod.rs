@@ -0,0 +1,15 @@
+//! A compact bytecode that HIR can be compiled into, along with a virtual machine that runs
+//! it much faster than the tree-walking interpreter can.
+//!
+//! Programs are made up of stack-based instructions, which refer to values through a
+//! constant pool. They can be written to disk in a versioned format, and are verified
+//! before they run so that the virtual machine does not need to check them as it goes.
+//!
+//! Values behave exactly as they do in the interpreter. A program holds everything that it
+//! needs from other modules, such as the standard library, so it can be run on its own.
+pub mod compile;
+pub mod disasm;
+pub mod format;
+pub mod program;
+pub mod verify;
+pub mod vm;
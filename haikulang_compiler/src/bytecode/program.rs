@@ -0,0 +1,214 @@
+//! The in-memory form of a compiled bytecode program.
+//!
+//! Every name that a program refers to is kept in its constant pool, and everything else
+//! refers to names by their index in the pool. Functions, structs and externs are numbered by
+//! their position in their own tables.
+//!
+//! A program holds one module along with every module that it calls into. Functions and
+//! externs from those other modules are named by their qualified names, such as `std::len`,
+//! so that they cannot clash with the ones from the module itself.
+use crate::hir::nodes::{HirExprBinaryOp, HirExprUnaryOp, HirString};
+use crate::interp::value::Value;
+use haikulang_parser::span::Span;
+use num_bigint::BigInt;
+use std::fmt::{Display, Formatter};
+
+/// A value that is known when the program is compiled.
+///
+/// Floats are kept as their bits, so that constants can be compared and hashed when they
+/// are deduplicated.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Constant {
+    Bool(bool),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    I128(i128),
+    ISize(i64),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    USize(u64),
+    F32(u32),
+    F64(u64),
+    String(HirString),
+    Char(char),
+    Bytes(Box<[u8]>),
+    BigInt(BigInt),
+    // A function or extern in the same program, referred to by its name.
+    Function(HirString),
+}
+
+impl Constant {
+    /// The constant holding an integer value, or None if the value is not an integer.
+    pub fn from_integer_value(value: Value) -> Option<Self> {
+        Some(match value {
+            Value::I8(value) => Self::I8(value),
+            Value::I16(value) => Self::I16(value),
+            Value::I32(value) => Self::I32(value),
+            Value::I64(value) => Self::I64(value),
+            Value::I128(value) => Self::I128(value),
+            Value::ISize(value) => Self::ISize(value as i64),
+            Value::U8(value) => Self::U8(value),
+            Value::U16(value) => Self::U16(value),
+            Value::U32(value) => Self::U32(value),
+            Value::U64(value) => Self::U64(value),
+            Value::U128(value) => Self::U128(value),
+            Value::USize(value) => Self::USize(value as u64),
+            Value::BigInt(value) => Self::BigInt(value.as_ref().clone()),
+            _ => return None,
+        })
+    }
+}
+
+impl Display for Constant {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bool(value) => write!(f, "bool {}", value),
+            Self::I8(value) => write!(f, "i8 {}", value),
+            Self::I16(value) => write!(f, "i16 {}", value),
+            Self::I32(value) => write!(f, "i32 {}", value),
+            Self::I64(value) => write!(f, "i64 {}", value),
+            Self::I128(value) => write!(f, "i128 {}", value),
+            Self::ISize(value) => write!(f, "isize {}", value),
+            Self::U8(value) => write!(f, "u8 {}", value),
+            Self::U16(value) => write!(f, "u16 {}", value),
+            Self::U32(value) => write!(f, "u32 {}", value),
+            Self::U64(value) => write!(f, "u64 {}", value),
+            Self::U128(value) => write!(f, "u128 {}", value),
+            Self::USize(value) => write!(f, "usize {}", value),
+            Self::F32(bits) => write!(f, "f32 {:?}", f32::from_bits(*bits)),
+            Self::F64(bits) => write!(f, "f64 {:?}", f64::from_bits(*bits)),
+            Self::String(value) => write!(f, "string {:?}", value),
+            Self::Char(value) => write!(f, "char {:?}", value),
+            Self::Bytes(value) => write!(f, "bytes b\"{}\"", value.escape_ascii()),
+            Self::BigInt(value) => write!(f, "bigint {}", value),
+            Self::Function(name) => write!(f, "fn {}", name),
+        }
+    }
+}
+
+/// A single operation of the virtual machine.
+///
+/// Operands are taken from the top of the stack, with the last operand on top, and results
+/// are pushed back onto it. Jumps refer to the position of an instruction within the same
+/// function.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Instruction {
+    /// Push a value from the constant pool.
+    Constant(u32),
+    /// Push the unit value.
+    Unit,
+    /// Discard the value on top of the stack.
+    Pop,
+    /// Push a copy of the value on top of the stack.
+    Dup,
+    LoadLocal(u32),
+    StoreLocal(u32),
+    Binary(HirExprBinaryOp),
+    Unary(HirExprUnaryOp),
+    /// Index a string or byte string.
+    Index,
+    /// Replace a struct or tuple with the member at the given position.
+    GetField(u32),
+    /// Set the member at the given position of the struct or tuple beneath the value on top
+    /// of the stack, leaving just the updated struct or tuple.
+    SetField(u32),
+    /// Collect the given number of values into a tuple.
+    MakeTuple(u32),
+    /// Collect the members of the given struct into an instance of it.
+    MakeStruct(u32),
+    /// Replace a tuple with the given number of elements by its elements.
+    Unpack(u32),
+    /// Join the given number of values into a string, as they would be formatted.
+    Concat(u32),
+    Jump(u32),
+    /// Jump if the value on top of the stack is false, removing it.
+    JumpIfFalse(u32),
+    /// Jump if the value on top of the stack is true, removing it.
+    JumpIfTrue(u32),
+    /// Call a function, which takes as many values from the stack as it has parameters.
+    Call(u32),
+    /// Call an extern function, which takes as many values from the stack as it has
+    /// parameters.
+    CallExtern(u32),
+    /// Call a function value that is beneath the given number of arguments.
+    CallValue(u32),
+    /// Make a closure from the given function, taking the values that it captures from the
+    /// stack.
+    MakeClosure(u32),
+    Return,
+    Print,
+    Println,
+    /// Fail if the value on top of the stack is false. The operand is the source code of the
+    /// condition, which is used to describe the failure.
+    Assert(u32),
+    /// Fail if the two values on top of the stack are not equal. The operand is the source
+    /// code of the comparison, which is used to describe the failure.
+    AssertEq(u32),
+}
+
+/// A struct, along with the names of its members in declaration order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Struct {
+    pub name: u32,
+    pub members: Vec<u32>,
+}
+
+/// A function that the virtual machine provides, which is called by its link name.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Extern {
+    pub name: u32,
+    pub link_name: u32,
+    pub arity: u32,
+}
+
+/// A compiled function.
+///
+/// Parameters are the first locals, followed by every other variable that the function
+/// declares. The bodies of closures are compiled into functions as well, whose first locals
+/// are the values that they captured, followed by their parameters.
+///
+/// Each instruction has the span of the source code that it was compiled from, so that
+/// errors can be reported there. Functions from other modules have no spans, as errors
+/// within them are reported at the call that led to them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Function {
+    pub name: u32,
+    pub arity: u32,
+    pub captures: u32,
+    pub locals: u32,
+    pub code: Vec<Instruction>,
+    pub spans: Vec<Span>,
+}
+
+/// A compiled module, along with the parts of other modules that it uses.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Program {
+    pub module: u32,
+    pub constants: Vec<Constant>,
+    pub structs: Vec<Struct>,
+    pub externs: Vec<Extern>,
+    pub functions: Vec<Function>,
+}
+
+impl Program {
+    /// The string in the constant pool at the given index, if there is one.
+    pub fn string(&self, index: u32) -> Option<&str> {
+        match self.constants.get(index as usize) {
+            Some(Constant::String(value)) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// The index of the function with the given name.
+    pub fn function_index(&self, name: &str) -> Option<u32> {
+        self.functions
+            .iter()
+            .position(|function| self.string(function.name) == Some(name))
+            .map(|index| index as u32)
+    }
+}
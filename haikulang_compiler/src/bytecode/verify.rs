@@ -0,0 +1,369 @@
+//! Verification of bytecode programs, so that the virtual machine does not need to check
+//! anything that could be known before a program runs.
+//!
+//! Every name has to refer to a string constant, and every instruction has to refer to
+//! constants, locals, structs, functions and jump targets that exist. The stack must never
+//! underflow, and must have the same depth every time an instruction is reached, however it
+//! was reached. Execution must not be able to run past the end of a function.
+use crate::bytecode::program::{Constant, Function, Instruction, Program};
+use crate::error::BytecodeError;
+
+/// Check that a program can be run safely.
+pub fn verify(program: &Program) -> Result<(), BytecodeError> {
+    let name = |index: u32, location: &dyn Fn() -> String| match program.string(index) {
+        Some(_) => Ok(()),
+        None => Err(invalid(location(), "the name is not a string constant")),
+    };
+
+    name(program.module, &|| "the module name".to_string())?;
+    for (index, item) in program.structs.iter().enumerate() {
+        let location = || format!("struct #{}", index);
+        name(item.name, &location)?;
+        for member in &item.members {
+            name(*member, &location)?;
+        }
+    }
+    for (index, item) in program.externs.iter().enumerate() {
+        let location = || format!("extern #{}", index);
+        name(item.name, &location)?;
+        name(item.link_name, &location)?;
+    }
+    for (index, function) in program.functions.iter().enumerate() {
+        name(function.name, &|| format!("function #{}", index))?;
+        FunctionVerifier::new(program, function).verify()?;
+    }
+    Ok(())
+}
+
+struct FunctionVerifier<'a> {
+    program: &'a Program,
+    function: &'a Function,
+    // The depth of the stack before each instruction, once it is known to be reachable.
+    depths: Vec<Option<u32>>,
+}
+
+impl<'a> FunctionVerifier<'a> {
+    fn new(program: &'a Program, function: &'a Function) -> Self {
+        Self {
+            program,
+            function,
+            depths: vec![None; function.code.len()],
+        }
+    }
+
+    fn verify(mut self) -> Result<(), BytecodeError> {
+        if self.function.spans.len() != self.function.code.len() {
+            return Err(invalid(
+                self.name(),
+                "every instruction needs exactly one span",
+            ));
+        }
+        if self.function.arity.saturating_add(self.function.captures) > self.function.locals {
+            return Err(invalid(
+                self.name(),
+                "it has more parameters and captures than locals",
+            ));
+        }
+
+        // Follow every path through the function, starting with an empty stack.
+        let mut pending = vec![(0, 0)];
+        while let Some((position, depth)) = pending.pop() {
+            let Some(instruction) = self.function.code.get(position) else {
+                return Err(invalid(self.name(), "execution can run past the end"));
+            };
+            match self.depths[position] {
+                Some(known) if known == depth => continue,
+                Some(known) => {
+                    return Err(self.error(
+                        position,
+                        format!(
+                            "the stack can hold {} or {} values here",
+                            known.min(depth),
+                            known.max(depth)
+                        ),
+                    ));
+                }
+                None => self.depths[position] = Some(depth),
+            }
+
+            let (pops, pushes) = self.effect(position, *instruction)?;
+            let Some(remaining) = depth.checked_sub(pops) else {
+                return Err(self.error(position, "the stack underflows".to_string()));
+            };
+            let Some(depth) = remaining.checked_add(pushes) else {
+                return Err(self.error(position, "the stack overflows".to_string()));
+            };
+
+            match *instruction {
+                Instruction::Jump(target) => {
+                    pending.push((self.target(position, target)?, depth));
+                }
+                Instruction::JumpIfFalse(target) | Instruction::JumpIfTrue(target) => {
+                    pending.push((self.target(position, target)?, depth));
+                    pending.push((position + 1, depth));
+                }
+                Instruction::Return => {}
+                _ => pending.push((position + 1, depth)),
+            }
+        }
+        Ok(())
+    }
+
+    // How many values an instruction takes from the stack and how many it leaves, checking
+    // its operands along the way.
+    fn effect(
+        &self,
+        position: usize,
+        instruction: Instruction,
+    ) -> Result<(u32, u32), BytecodeError> {
+        let program = self.program;
+        let check = |valid: bool, reason: &str| {
+            if valid {
+                Ok(())
+            } else {
+                Err(self.error(position, reason.to_string()))
+            }
+        };
+
+        Ok(match instruction {
+            Instruction::Constant(index) => {
+                check(
+                    (index as usize) < program.constants.len(),
+                    "the constant does not exist",
+                )?;
+                (0, 1)
+            }
+            Instruction::Unit => (0, 1),
+            Instruction::Pop => (1, 0),
+            Instruction::Dup => (1, 2),
+            Instruction::LoadLocal(local) => {
+                check(local < self.function.locals, "the local does not exist")?;
+                (0, 1)
+            }
+            Instruction::StoreLocal(local) => {
+                check(local < self.function.locals, "the local does not exist")?;
+                (1, 0)
+            }
+            Instruction::Binary(_) | Instruction::Index | Instruction::SetField(_) => (2, 1),
+            Instruction::Unary(_) | Instruction::GetField(_) => (1, 1),
+            Instruction::MakeTuple(length) | Instruction::Concat(length) => (length, 1),
+            Instruction::MakeStruct(index) => match program.structs.get(index as usize) {
+                Some(item) => (item.members.len() as u32, 1),
+                None => return Err(self.error(position, "the struct does not exist".to_string())),
+            },
+            Instruction::Unpack(length) => (1, length),
+            Instruction::Jump(_) => (0, 0),
+            Instruction::JumpIfFalse(_) | Instruction::JumpIfTrue(_) => (1, 0),
+            // Closures can only be called through values, which hold what they captured.
+            Instruction::Call(index) => match program.functions.get(index as usize) {
+                Some(function) if function.captures == 0 => (function.arity, 1),
+                Some(_) => {
+                    return Err(self.error(position, "the function is a closure".to_string()));
+                }
+                None => {
+                    return Err(self.error(position, "the function does not exist".to_string()));
+                }
+            },
+            Instruction::CallExtern(index) => match program.externs.get(index as usize) {
+                Some(item) => (item.arity, 1),
+                None => return Err(self.error(position, "the extern does not exist".to_string())),
+            },
+            Instruction::CallValue(count) => (count.saturating_add(1), 1),
+            Instruction::MakeClosure(index) => match program.functions.get(index as usize) {
+                Some(function) => (function.captures, 1),
+                None => {
+                    return Err(self.error(position, "the function does not exist".to_string()));
+                }
+            },
+            Instruction::Return => (1, 0),
+            Instruction::Print | Instruction::Println => (1, 1),
+            Instruction::Assert(text) | Instruction::AssertEq(text) => {
+                check(
+                    matches!(
+                        program.constants.get(text as usize),
+                        Some(Constant::String(_))
+                    ),
+                    "the description is not a string constant",
+                )?;
+                if matches!(instruction, Instruction::Assert(_)) {
+                    (1, 1)
+                } else {
+                    (2, 1)
+                }
+            }
+        })
+    }
+
+    fn target(&self, position: usize, target: u32) -> Result<usize, BytecodeError> {
+        if (target as usize) < self.function.code.len() {
+            Ok(target as usize)
+        } else {
+            Err(self.error(position, "the jump leaves the function".to_string()))
+        }
+    }
+
+    fn name(&self) -> String {
+        format!(
+            "fn {}",
+            self.program.string(self.function.name).unwrap_or("?")
+        )
+    }
+
+    fn error(&self, position: usize, reason: String) -> BytecodeError {
+        invalid(format!("{} at {}", self.name(), position), &reason)
+    }
+}
+
+fn invalid(location: String, reason: &str) -> BytecodeError {
+    BytecodeError::Invalid {
+        location,
+        reason: reason.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytecode::compile::compile_module;
+    use crate::bytecode::program::Struct;
+    use crate::db::database::Database;
+    use crate::stdlib::add_std;
+    use haikulang_parser::span::Span;
+    use test_case::test_case;
+
+    fn program(code: Vec<Instruction>) -> Program {
+        Program {
+            module: 0,
+            constants: vec![Constant::String("main".to_string()), Constant::I32(1)],
+            structs: vec![Struct {
+                name: 0,
+                members: vec![0, 0],
+            }],
+            externs: Vec::new(),
+            functions: vec![Function {
+                name: 0,
+                arity: 0,
+                captures: 0,
+                locals: 1,
+                spans: vec![Span::UNSET; code.len()],
+                code,
+            }],
+        }
+    }
+
+    #[test]
+    fn compiled_programs_are_valid() {
+        // Given
+        let mut db = Database::new();
+        add_std(&mut db);
+        let file = db.add_file(
+            "test.hkl",
+            "struct P { x: i32; } fn main() -> i32 { let p = P(1); let i = 0; while (i < 10 && p.x > 0) { if (i == 5) { break; } i += 1; } let (a, b) = (i, p); b.x += a; assert(b.x == 6); let f = |x: i32| -> i32 { if (x > a) { return x; } return b.x; }; std::println(\"{}\", std::abs(-1)); return f(a + 1); }",
+        );
+        let program = compile_module(&mut db, file).unwrap();
+
+        // When
+        let result = verify(&program);
+
+        // Then
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test_case(
+        vec![Instruction::Constant(1), Instruction::Return],
+        None
+        ; "valid"
+    )]
+    #[test_case(
+        vec![Instruction::Pop, Instruction::Unit, Instruction::Return],
+        Some("invalid bytecode in fn main at 0: the stack underflows")
+        ; "underflow"
+    )]
+    #[test_case(
+        vec![Instruction::Constant(9), Instruction::Return],
+        Some("invalid bytecode in fn main at 0: the constant does not exist")
+        ; "missing constant"
+    )]
+    #[test_case(
+        vec![Instruction::LoadLocal(1), Instruction::Return],
+        Some("invalid bytecode in fn main at 0: the local does not exist")
+        ; "missing local"
+    )]
+    #[test_case(
+        vec![Instruction::Jump(7)],
+        Some("invalid bytecode in fn main at 0: the jump leaves the function")
+        ; "jump out of the function"
+    )]
+    #[test_case(
+        vec![Instruction::Unit],
+        Some("invalid bytecode in fn main: execution can run past the end")
+        ; "falling off the end"
+    )]
+    #[test_case(
+        vec![Instruction::Unit, Instruction::Unit, Instruction::JumpIfFalse(0), Instruction::Return],
+        Some("invalid bytecode in fn main at 0: the stack can hold 0 or 1 values here")
+        ; "inconsistent depth"
+    )]
+    #[test_case(
+        vec![Instruction::Unit, Instruction::MakeStruct(0), Instruction::Return],
+        Some("invalid bytecode in fn main at 1: the stack underflows")
+        ; "struct missing members"
+    )]
+    #[test_case(
+        vec![Instruction::Unit, Instruction::Assert(1), Instruction::Return],
+        Some("invalid bytecode in fn main at 1: the description is not a string constant")
+        ; "assertion without a description"
+    )]
+    #[test_case(
+        vec![Instruction::Call(3), Instruction::Return],
+        Some("invalid bytecode in fn main at 0: the function does not exist")
+        ; "missing function"
+    )]
+    #[test_case(
+        vec![Instruction::MakeClosure(3), Instruction::Return],
+        Some("invalid bytecode in fn main at 0: the function does not exist")
+        ; "closure of a missing function"
+    )]
+    fn functions_are_verified(code: Vec<Instruction>, expected: Option<&str>) {
+        // When
+        let result = verify(&program(code));
+
+        // Then
+        assert_eq!(
+            result.map_err(|err| err.to_string()).err().as_deref(),
+            expected
+        );
+    }
+
+    #[test]
+    fn closures_are_not_called_directly() {
+        // Given
+        let mut program = program(vec![Instruction::Call(0), Instruction::Return]);
+        program.functions[0].captures = 1;
+
+        // When
+        let result = verify(&program);
+
+        // Then
+        assert_eq!(
+            result.map_err(|err| err.to_string()),
+            Err("invalid bytecode in fn main at 0: the function is a closure".to_string())
+        );
+    }
+
+    #[test]
+    fn names_must_be_strings() {
+        // Given
+        let mut program = program(vec![Instruction::Unit, Instruction::Return]);
+        program.structs[0].members[1] = 1;
+
+        // When
+        let result = verify(&program);
+
+        // Then
+        assert_eq!(
+            result.map_err(|err| err.to_string()),
+            Err("invalid bytecode in struct #0: the name is not a string constant".to_string())
+        );
+    }
+}
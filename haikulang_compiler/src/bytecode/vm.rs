@@ -0,0 +1,835 @@
+//! A virtual machine that runs verified bytecode programs.
+//!
+//! Every call shares a single stack of values. The locals of a call are kept at the bottom of
+//! its part of the stack, starting with the arguments that it was given, and the values that
+//! its instructions work on are kept above them. Calls do not use the native stack, so much
+//! deeper recursion is possible than in the tree-walking interpreter.
+use crate::bytecode::program::{Constant, Function, Instruction, Program};
+use crate::bytecode::verify::verify;
+use crate::error::{BytecodeError, RuntimeError, RuntimeResult};
+use crate::hir::nodes::{HirExprBinaryOp, HirString};
+use crate::interp::builtins::call_builtin;
+use crate::interp::heap::Heap;
+use crate::interp::interpreter::{binary_op, index_value, unary_op};
+use crate::interp::value::{CompiledClosure, StructValue, Value};
+use haikulang_parser::span::{Span, Spanned};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::Write;
+use std::rc::Rc;
+
+const MAX_CALL_DEPTH: usize = 10_000;
+
+// What a function value can refer to.
+#[derive(Clone, Copy)]
+enum Callee {
+    Function(u32),
+    Extern(u32),
+}
+
+// A call that is waiting for the function it made to return.
+struct Frame<'p> {
+    function: &'p Function,
+    position: usize,
+    base: usize,
+}
+
+/// Runs the functions of a bytecode program.
+pub struct Vm<'p, 'o> {
+    program: &'p Program,
+    output: &'o mut dyn Write,
+    heap: Heap,
+    // Constants are converted to values once, so that pushing one only needs a clone.
+    constants: Vec<Value>,
+    module: String,
+    callees: HashMap<String, Callee>,
+    stack: Vec<Value>,
+}
+
+impl<'p, 'o> Vm<'p, 'o> {
+    /// Prepare to run a program, verifying it first.
+    pub fn new(program: &'p Program, output: &'o mut dyn Write) -> Result<Self, BytecodeError> {
+        verify(program)?;
+
+        let module = program
+            .string(program.module)
+            .unwrap_or_default()
+            .to_string();
+        let constants = program
+            .constants
+            .iter()
+            .map(|constant| constant_value(&module, constant))
+            .collect();
+
+        let name = |index: u32| program.string(index).unwrap_or_default().to_string();
+        let mut callees = HashMap::new();
+        for (index, item) in program.externs.iter().enumerate() {
+            callees.insert(name(item.name), Callee::Extern(index as u32));
+        }
+        for (index, function) in program.functions.iter().enumerate() {
+            callees.insert(name(function.name), Callee::Function(index as u32));
+        }
+
+        Ok(Self {
+            program,
+            output,
+            heap: Heap::new(),
+            constants,
+            module,
+            callees,
+            stack: Vec::new(),
+        })
+    }
+
+    /// Run a function from the function table on a fresh stack and return its result.
+    /// Externs cannot be called this way, as they are not part of the program.
+    pub fn call(&mut self, name: &str, arguments: Vec<Value>) -> RuntimeResult<Value> {
+        let unknown_function =
+            || Spanned::new(RuntimeError::UnknownFunction(name.to_string()), Span::UNSET);
+        let program = self.program;
+        let index = program.function_index(name).ok_or_else(unknown_function)?;
+        let function = &program.functions[index as usize];
+        if function.arity as usize != arguments.len() {
+            return Err(Spanned::new(
+                RuntimeError::Unsupported(format!(
+                    "{} expects {} arguments but was given {}",
+                    name,
+                    function.arity,
+                    arguments.len()
+                )),
+                Span::UNSET,
+            ));
+        }
+
+        self.stack.clear();
+        self.stack.extend(arguments);
+        let result = self.run(function);
+        self.stack.clear();
+        result
+    }
+
+    // The dispatch loop. The function being run and the position within it are kept in
+    // locals rather than in a frame, as they are used by every instruction.
+    fn run(&mut self, entry: &'p Function) -> RuntimeResult<Value> {
+        let program = self.program;
+        let mut frames: Vec<Frame<'p>> = Vec::new();
+        let mut function = entry;
+        let mut position = 0;
+        let mut base = 0;
+        self.stack.resize(function.locals as usize, Value::Unit);
+
+        loop {
+            let instruction = function.code[position];
+            position += 1;
+            let fail =
+                |err: RuntimeError| Spanned::new(err, error_span(&frames, function, position));
+
+            match instruction {
+                Instruction::Constant(index) => {
+                    self.stack.push(self.constants[index as usize].clone());
+                }
+                Instruction::Unit => self.stack.push(Value::Unit),
+                Instruction::Pop => {
+                    self.pop();
+                }
+                Instruction::Dup => {
+                    let value = self.peek().clone();
+                    self.stack.push(value);
+                }
+                Instruction::LoadLocal(local) => {
+                    let value = self.stack[base + local as usize].clone();
+                    self.stack.push(value);
+                }
+                Instruction::StoreLocal(local) => {
+                    let value = self.pop();
+                    self.stack[base + local as usize] = value;
+                }
+                Instruction::Binary(op) => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    let value = fast_binary_op(op, &left, &right)
+                        .map_or_else(|| binary_op(op, &left, &right), Ok)
+                        .map_err(fail)?;
+                    self.stack.push(value);
+                }
+                Instruction::Unary(op) => {
+                    let value = self.pop();
+                    let value = unary_op(op, &value).map_err(fail)?;
+                    self.stack.push(value);
+                }
+                Instruction::Index => {
+                    let index = self.pop();
+                    let owner = self.pop();
+                    let value = index_value(&owner, &index).map_err(fail)?;
+                    self.stack.push(value);
+                }
+                Instruction::GetField(field) => {
+                    let owner = self.pop();
+                    let value = take_field(owner, field as usize).map_err(fail)?;
+                    self.stack.push(value);
+                }
+                Instruction::SetField(field) => {
+                    let value = self.pop();
+                    let mut owner = self.pop();
+                    *field_mut(&mut owner, field as usize).map_err(fail)? = value;
+                    self.stack.push(owner);
+                }
+                Instruction::MakeTuple(length) => {
+                    let elements = self.pop_many(length as usize);
+                    self.stack.push(Value::tuple(elements));
+                }
+                Instruction::MakeStruct(index) => {
+                    let item = &program.structs[index as usize];
+                    let values = self.pop_many(item.members.len());
+                    let name = |index: u32| program.string(index).unwrap_or_default().to_string();
+                    let members = item.members.iter().map(|member| name(*member));
+                    self.stack.push(Value::Struct(Box::new(StructValue {
+                        name: name(item.name),
+                        members: members.zip(values).collect(),
+                    })));
+                }
+                Instruction::Unpack(length) => match self.pop() {
+                    Value::Tuple(elements) if elements.len() == length as usize => {
+                        self.stack.extend(elements);
+                    }
+                    Value::Unit if length == 0 => {}
+                    other => {
+                        return Err(fail(RuntimeError::Unsupported(format!(
+                            "{} does not match the pattern",
+                            other
+                        ))));
+                    }
+                },
+                Instruction::Concat(count) => {
+                    let start = self.stack.len() - count as usize;
+                    let mut text = String::new();
+                    for value in self.stack.drain(start..) {
+                        match value {
+                            Value::String(value) => text.push_str(&value),
+                            other => write!(text, "{}", other).expect("strings can always grow"),
+                        }
+                    }
+                    self.stack.push(Value::String(Rc::from(text)));
+                }
+                Instruction::Jump(target) => position = target as usize,
+                Instruction::JumpIfFalse(target) => {
+                    if !self.pop_condition().map_err(fail)? {
+                        position = target as usize;
+                    }
+                }
+                Instruction::JumpIfTrue(target) => {
+                    if self.pop_condition().map_err(fail)? {
+                        position = target as usize;
+                    }
+                }
+                Instruction::Call(index) => {
+                    let callee = &program.functions[index as usize];
+                    if frames.len() >= MAX_CALL_DEPTH {
+                        return Err(fail(RuntimeError::StackOverflow));
+                    }
+                    frames.push(Frame {
+                        function,
+                        position,
+                        base,
+                    });
+                    base = self.stack.len() - callee.arity as usize;
+                    self.stack
+                        .resize(base + callee.locals as usize, Value::Unit);
+                    function = callee;
+                    position = 0;
+                }
+                Instruction::CallExtern(index) => {
+                    let arity = program.externs[index as usize].arity as usize;
+                    let arguments = self.pop_many(arity);
+                    let span = error_span(&frames, function, position);
+                    let value = self.call_extern(index, &arguments, span)?;
+                    self.stack.push(value);
+                }
+                Instruction::CallValue(count) => {
+                    let callee_position = self.stack.len() - count as usize - 1;
+                    let callee = match self.stack.remove(callee_position) {
+                        Value::Function(name) => self.resolve(&name).map_err(fail)?,
+                        // The values that a closure captured become its first locals, ahead
+                        // of the arguments that it was given.
+                        Value::CompiledClosure(closure) => {
+                            let callee = &program.functions[closure.function as usize];
+                            if callee.arity != count {
+                                return Err(fail(RuntimeError::Unsupported(format!(
+                                    "closure expects {} arguments but was given {}",
+                                    callee.arity, count
+                                ))));
+                            }
+                            self.stack.splice(
+                                callee_position..callee_position,
+                                closure.captures.iter().cloned(),
+                            );
+                            Callee::Function(closure.function)
+                        }
+                        other => {
+                            return Err(fail(RuntimeError::Unsupported(format!(
+                                "{} is not callable",
+                                other
+                            ))));
+                        }
+                    };
+
+                    match callee {
+                        Callee::Function(index) => {
+                            let callee = &program.functions[index as usize];
+                            if callee.arity != count {
+                                return Err(fail(RuntimeError::Unsupported(format!(
+                                    "{} expects {} arguments but was given {}",
+                                    program.string(callee.name).unwrap_or_default(),
+                                    callee.arity,
+                                    count
+                                ))));
+                            }
+                            if frames.len() >= MAX_CALL_DEPTH {
+                                return Err(fail(RuntimeError::StackOverflow));
+                            }
+                            frames.push(Frame {
+                                function,
+                                position,
+                                base,
+                            });
+                            base = self.stack.len() - (callee.captures + callee.arity) as usize;
+                            self.stack
+                                .resize(base + callee.locals as usize, Value::Unit);
+                            function = callee;
+                            position = 0;
+                        }
+                        Callee::Extern(index) => {
+                            let arguments = self.pop_many(count as usize);
+                            let span = error_span(&frames, function, position);
+                            let value = self.call_extern(index, &arguments, span)?;
+                            self.stack.push(value);
+                        }
+                    }
+                }
+                Instruction::MakeClosure(index) => {
+                    let captures = program.functions[index as usize].captures;
+                    let captures = self.pop_many(captures as usize);
+                    self.stack
+                        .push(Value::CompiledClosure(Rc::new(CompiledClosure {
+                            function: index,
+                            captures,
+                        })));
+                }
+                Instruction::Return => {
+                    let value = self.pop();
+                    self.stack.truncate(base);
+                    let Some(frame) = frames.pop() else {
+                        return Ok(value);
+                    };
+                    self.stack.push(value);
+                    function = frame.function;
+                    position = frame.position;
+                    base = frame.base;
+                }
+                Instruction::Print | Instruction::Println => {
+                    let value = self.pop();
+                    let Value::String(text) = value else {
+                        return Err(fail(RuntimeError::Unsupported(format!(
+                            "cannot print {}",
+                            value
+                        ))));
+                    };
+                    let newline = if instruction == Instruction::Println {
+                        "\n"
+                    } else {
+                        ""
+                    };
+                    write!(self.output, "{}{}", text, newline).map_err(|err| {
+                        fail(RuntimeError::Unsupported(format!(
+                            "failed to write output: {}",
+                            err
+                        )))
+                    })?;
+                    self.stack.push(Value::Unit);
+                }
+                Instruction::Assert(text) => match self.pop() {
+                    Value::Bool(true) => self.stack.push(Value::Unit),
+                    Value::Bool(false) => {
+                        let text = self.text(text);
+                        return Err(fail(RuntimeError::AssertionFailed(text)));
+                    }
+                    _ => {
+                        return Err(fail(RuntimeError::Unsupported(
+                            "invalid arguments for builtin assert".to_string(),
+                        )));
+                    }
+                },
+                Instruction::AssertEq(text) => {
+                    let right = self.pop();
+                    let left = self.pop();
+                    match binary_op(HirExprBinaryOp::Eq, &left, &right).map_err(fail)? {
+                        Value::Bool(true) => self.stack.push(Value::Unit),
+                        _ => {
+                            let text =
+                                format!("{} (left: {}, right: {})", self.text(text), left, right);
+                            return Err(fail(RuntimeError::AssertionFailed(text)));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn call_extern(&mut self, index: u32, arguments: &[Value], span: Span) -> RuntimeResult<Value> {
+        let link_name = self.text(self.program.externs[index as usize].link_name);
+        match call_builtin(&link_name, arguments, self.output, &mut self.heap) {
+            Some(result) => result.map_err(|err| Spanned::new(err, span)),
+            None => Err(Spanned::new(RuntimeError::UnknownFunction(link_name), span)),
+        }
+    }
+
+    // Function values always use qualified names, and only functions in this program can
+    // be called through them. Those from the module that the program was compiled from are
+    // named without it in the function table.
+    fn resolve(&self, name: &HirString) -> Result<Callee, RuntimeError> {
+        let unqualified = name
+            .strip_prefix(&self.module)
+            .and_then(|name| name.strip_prefix("::"));
+        unqualified
+            .and_then(|name| self.callees.get(name))
+            .or_else(|| self.callees.get(name))
+            .copied()
+            .ok_or_else(|| RuntimeError::UnknownFunction(name.clone()))
+    }
+
+    fn text(&self, index: u32) -> String {
+        self.program.string(index).unwrap_or_default().to_string()
+    }
+
+    fn pop_condition(&mut self) -> Result<bool, RuntimeError> {
+        match self.pop() {
+            Value::Bool(value) => Ok(value),
+            other => Err(RuntimeError::Unsupported(format!(
+                "expected a bool condition, found {}",
+                other
+            ))),
+        }
+    }
+
+    // Verified programs never take more from the stack than they have put on it.
+    fn pop(&mut self) -> Value {
+        self.stack.pop().expect("verified programs never underflow")
+    }
+
+    fn peek(&self) -> &Value {
+        self.stack
+            .last()
+            .expect("verified programs never underflow")
+    }
+
+    fn pop_many(&mut self, count: usize) -> Vec<Value> {
+        self.stack.split_off(self.stack.len() - count)
+    }
+}
+
+fn constant_value(module: &str, constant: &Constant) -> Value {
+    match constant {
+        Constant::Bool(value) => Value::Bool(*value),
+        Constant::I8(value) => Value::I8(*value),
+        Constant::I16(value) => Value::I16(*value),
+        Constant::I32(value) => Value::I32(*value),
+        Constant::I64(value) => Value::I64(*value),
+        Constant::I128(value) => Value::I128(*value),
+        Constant::ISize(value) => Value::ISize(*value as isize),
+        Constant::U8(value) => Value::U8(*value),
+        Constant::U16(value) => Value::U16(*value),
+        Constant::U32(value) => Value::U32(*value),
+        Constant::U64(value) => Value::U64(*value),
+        Constant::U128(value) => Value::U128(*value),
+        Constant::USize(value) => Value::USize(*value as usize),
+        Constant::F32(bits) => Value::F32(f32::from_bits(*bits)),
+        Constant::F64(bits) => Value::F64(f64::from_bits(*bits)),
+        Constant::String(value) => Value::String(Rc::from(value.as_str())),
+        Constant::Char(value) => Value::Char(*value),
+        Constant::Bytes(value) => Value::Bytes(Rc::from(&**value)),
+        Constant::BigInt(value) => Value::BigInt(Rc::new(value.clone())),
+        Constant::Function(name) if name.contains("::") => Value::Function(name.clone()),
+        Constant::Function(name) => Value::Function(format!("{}::{}", module, name)),
+    }
+}
+
+// Functions from other modules have no spans, so errors within them are reported at the call
+// that led to them from the module that the program was compiled from, as the interpreter
+// does.
+fn error_span(frames: &[Frame], function: &Function, position: usize) -> Span {
+    let calls = frames
+        .iter()
+        .rev()
+        .map(|frame| frame.function.spans[frame.position - 1]);
+    std::iter::once(function.spans[position - 1])
+        .chain(calls)
+        .find(|span| *span != Span::UNSET)
+        .unwrap_or(Span::UNSET)
+}
+
+// Most arithmetic in loops is on i32, i64 and f64 values, so those are handled here without
+// going through every case that the general implementation checks first.
+fn fast_binary_op(op: HirExprBinaryOp, left: &Value, right: &Value) -> Option<Value> {
+    macro_rules! compare {
+        ($a:expr, $b:expr) => {
+            match op {
+                HirExprBinaryOp::Eq => Some(Value::Bool($a == $b)),
+                HirExprBinaryOp::NotEq => Some(Value::Bool($a != $b)),
+                HirExprBinaryOp::Less => Some(Value::Bool($a < $b)),
+                HirExprBinaryOp::LessEq => Some(Value::Bool($a <= $b)),
+                HirExprBinaryOp::Greater => Some(Value::Bool($a > $b)),
+                HirExprBinaryOp::GreaterEq => Some(Value::Bool($a >= $b)),
+                _ => None,
+            }
+        };
+    }
+
+    // Anything that overflows or divides by zero falls back to the general implementation,
+    // which reports it.
+    macro_rules! integer {
+        ($variant:ident, $a:expr, $b:expr) => {
+            match op {
+                HirExprBinaryOp::Add => $a.checked_add($b).map(Value::$variant),
+                HirExprBinaryOp::Sub => $a.checked_sub($b).map(Value::$variant),
+                HirExprBinaryOp::Mul => $a.checked_mul($b).map(Value::$variant),
+                HirExprBinaryOp::Div => $a.checked_div($b).map(Value::$variant),
+                HirExprBinaryOp::Mod => $a.checked_rem($b).map(Value::$variant),
+                _ => compare!($a, $b),
+            }
+        };
+    }
+
+    match (left, right) {
+        (Value::I32(a), Value::I32(b)) => integer!(I32, *a, *b),
+        (Value::I64(a), Value::I64(b)) => integer!(I64, *a, *b),
+        (Value::F64(a), Value::F64(b)) => match op {
+            HirExprBinaryOp::Add => Some(Value::F64(a + b)),
+            HirExprBinaryOp::Sub => Some(Value::F64(a - b)),
+            HirExprBinaryOp::Mul => Some(Value::F64(a * b)),
+            HirExprBinaryOp::Div => Some(Value::F64(a / b)),
+            _ => compare!(a, b),
+        },
+        _ => None,
+    }
+}
+
+fn take_field(owner: Value, field: usize) -> Result<Value, RuntimeError> {
+    match owner {
+        Value::Struct(mut value) if field < value.members.len() => {
+            Ok(value.members.swap_remove(field).1)
+        }
+        Value::Tuple(elements) if field < elements.len() => {
+            Ok(elements.into_vec().swap_remove(field))
+        }
+        other => Err(no_field(&other, field)),
+    }
+}
+
+fn field_mut(owner: &mut Value, field: usize) -> Result<&mut Value, RuntimeError> {
+    match owner {
+        Value::Struct(value) => value.members.get_mut(field).map(|(_, member)| member),
+        Value::Tuple(elements) => elements.get_mut(field),
+        _ => None,
+    }
+    .ok_or_else(|| RuntimeError::Unsupported(format!("value has no member at position {}", field)))
+}
+
+fn no_field(owner: &Value, field: usize) -> RuntimeError {
+    RuntimeError::Unsupported(format!("{} has no member at position {}", owner, field))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytecode::compile::compile_module;
+    use crate::bytecode::format::{decode, encode};
+    use crate::db::database::Database;
+    use crate::interp::interpreter::Interpreter;
+    use crate::stdlib::add_std;
+    use test_case::test_case;
+
+    fn compile(source: &str) -> Program {
+        let mut db = Database::new();
+        add_std(&mut db);
+        let file = db.add_file("test.hkl", source);
+        compile_module(&mut db, file).unwrap()
+    }
+
+    fn run(source: &str) -> (RuntimeResult<Value>, String) {
+        let program = compile(source);
+        let mut output: Vec<u8> = Vec::new();
+        let result = Vm::new(&program, &mut output).unwrap().call("main", vec![]);
+        (result, String::from_utf8(output).unwrap())
+    }
+
+    fn interpret(source: &str) -> (RuntimeResult<Value>, String) {
+        let mut db = Database::new();
+        add_std(&mut db);
+        let file = db.add_file("test.hkl", source);
+        let mut output: Vec<u8> = Vec::new();
+        let result = Interpreter::new(&mut db, file, &mut output).call("main", vec![], Span::UNSET);
+        (result, String::from_utf8(output).unwrap())
+    }
+
+    #[test_case("fn main() -> i32 { return 1 + 2 * 3; }", Value::I32(7) ; "arithmetic precedence")]
+    #[test_case("fn main() -> i32 { return 2 ** 3 ** 2; }", Value::I32(512) ; "right associative power")]
+    #[test_case("fn main() -> bool { return 1 < 2 && !(3 <= 2); }", Value::Bool(true) ; "comparisons")]
+    #[test_case("fn main() -> bool { return false || 1 > 2 || true; }", Value::Bool(true) ; "boolean or")]
+    #[test_case("fn main() -> u8 { return ~0u8 >> 4u8; }", Value::U8(15) ; "bitwise operators")]
+    #[test_case("fn main() -> f64 { return -1.5 * 2.0; }", Value::F64(-3.0) ; "floats")]
+    #[test_case(
+        "fn main() -> i32 { let x = 0; let i = 0; while (true) { i += 1; if (i > 10) { break; } if (i % 2 == 0) { continue; } x += i; } return x; }",
+        Value::I32(25)
+        ; "loops with break and continue"
+    )]
+    #[test_case(
+        "fn main() -> i32 { let total = 0; let i = 0; while (i < 3) { let j = 0; while (j < 3) { j += 1; if (j == 2) { continue; } total += j; } i += 1; } return total; }",
+        Value::I32(12)
+        ; "nested loops"
+    )]
+    #[test_case(
+        "fn fib(n: i32) -> i32 { if (n < 2) { return n; } return fib(n - 1) + fib(n - 2); } fn main() -> i32 { return fib(15); }",
+        Value::I32(610)
+        ; "recursion"
+    )]
+    #[test_case(
+        "fn depth(n: i32) -> i32 { if (n == 0) { return 0; } return 1 + depth(n - 1); } fn main() -> i32 { return depth(5000); }",
+        Value::I32(5000)
+        ; "recursion deeper than the interpreter allows"
+    )]
+    #[test_case(
+        "struct P { x: i32; y: i32; } fn main() -> i32 { let p = P(1, 2); p.y = 40; return p.x + p.y + 1; }",
+        Value::I32(42)
+        ; "struct members"
+    )]
+    #[test_case(
+        "struct I { v: i32; } struct O { i: I; n: i32; } fn main() -> i32 { let o = O(I(1), 2); o.i.v += 39; o.n *= 1; return o.i.v + o.n; }",
+        Value::I32(42)
+        ; "nested struct members"
+    )]
+    #[test_case(
+        "fn twice(x: i32) -> i32 { return x * 2; } fn main() -> i32 { let f = twice; return f(21); }",
+        Value::I32(42)
+        ; "function values"
+    )]
+    #[test_case(
+        "fn inc(x: i32) -> i32 { return x + 1; } fn twice(f: fn(i32) -> i32, x: i32) -> i32 { return f(f(x)); } fn main() -> i32 { return twice(inc, 40); }",
+        Value::I32(42)
+        ; "passing named functions"
+    )]
+    #[test_case(
+        "fn main() -> i32 { let k = 40; let f = |x: i32| x + k; k = 0; return f(2); }",
+        Value::I32(42)
+        ; "closures capture values when they are made"
+    )]
+    #[test_case(
+        "fn apply(f: fn(i32) -> i32, x: i32) -> i32 { return f(x); } fn main() -> i32 { let k = 2; return apply(|x: i32| -> i32 { let y = x * k; return y + 1; }, 20) + 1; }",
+        Value::I32(42)
+        ; "closures with blocks"
+    )]
+    #[test_case(
+        "fn main() -> i32 { let a = 1; let outer = |b: i32| -> i32 { let inner = |c: i32| a + b + c; return inner(b * 10); }; return outer(2) + outer(0) + 18; }",
+        Value::I32(42)
+        ; "nested closures"
+    )]
+    #[test_case("fn main() -> usize { return std::len(\"hello\") + std::char_count(\"héllo\"); }", Value::USize(10) ; "externs from other modules")]
+    #[test_case(
+        "fn main() -> i64 { let a = std::array_new(); std::array_push(a, 40); std::array_push(a, std::abs(-2)); return std::array_pop(a) + std::array_get(a, 0); }",
+        Value::I64(42)
+        ; "structs from other modules"
+    )]
+    #[test_case("fn main() -> i64 { let f = std::max; let g = std::gcd; return f(g(84, 126), 1); }", Value::I64(42) ; "function values from other modules")]
+    #[test_case("fn main() -> u64 { return 0xdead_beef_cafe; }", Value::U64(0xdead_beef_cafe) ; "wide literals")]
+    #[test_case("fn main() -> i8 { return -128; }", Value::I8(-128) ; "minimum negative literal")]
+    #[test_case("fn main() -> isize { return -(1isize << 40); }", Value::ISize(-(1 << 40)) ; "isize")]
+    #[test_case("fn main() -> char { return '\\u{263A}'; }", Value::Char('\u{263A}') ; "characters")]
+    #[test_case("fn main() -> u8 { let s = b\"hi\\n\"; return s[1] + s[2] - b'\\n'; }", Value::U8(b'i') ; "indexing bytes")]
+    #[test_case(
+        "fn divmod(a: i32, b: i32) -> (i32, i32) { return (a / b, a % b); } fn main() -> i32 { let (q, r) = divmod(47, 5); return q * 10 + r; }",
+        Value::I32(92)
+        ; "multiple return values"
+    )]
+    #[test_case(
+        "fn main() -> (bool, (u8,)) { let t = (1u8, (true, 2u8)); let (a, (b, c)) = t; t.0 = 5; return (b, (c + t.0 + a,)); }",
+        Value::Tuple(Box::new([Value::Bool(true), Value::Tuple(Box::new([Value::U8(8)]))]))
+        ; "tuples"
+    )]
+    #[test_case("fn main() -> string { let s = \"hé\"; s += \"llo\"; return s + \"!\"; }", Value::String(Rc::from("héllo!")) ; "string concatenation")]
+    #[test_case(
+        "fn main() -> std::BigInt { let a: std::BigInt = 18446744073709551616; return (a * a - 1) / 3 % 1000000000000000000000; }",
+        Value::BigInt(Rc::new("154458202477256070485".parse().unwrap()))
+        ; "big integer arithmetic"
+    )]
+    #[test_case("fn main() -> bool { let a: std::BigInt = 1; let n = 1; while (n <= 30) { a *= n; n += 1; } return a == 265252859812191058636308480000000 && n < a; }", Value::Bool(true) ; "big integers mixed with fixed width integers")]
+    #[test_case("fn main() { let x = 1; }", Value::Unit ; "falling off the end")]
+    fn programs_evaluate_correctly(source: &str, expected: Value) {
+        // When
+        let (result, _) = run(source);
+
+        // Then
+        assert_eq!(result, Ok(expected));
+    }
+
+    #[test_case("fn main() -> i32 { return 2147483647 + 1; }", RuntimeError::ArithmeticOverflow("2147483647 Add 1".to_string()) ; "overflow")]
+    #[test_case("fn main() -> i32 { return 1 / 0; }", RuntimeError::DivisionByZero ; "division by zero")]
+    #[test_case("fn main() { main(); }", RuntimeError::StackOverflow ; "unbounded recursion")]
+    #[test_case("extern fn nope(); fn main() { nope(); }", RuntimeError::UnknownFunction("nope".to_string()) ; "missing builtin")]
+    #[test_case("extern fn exit(code: i32); fn main() { exit(3); }", RuntimeError::Exit(3) ; "exiting")]
+    #[test_case(
+        "fn main() -> u8 { return \"hi\"[5]; }",
+        RuntimeError::IndexOutOfBounds { index: "5".to_string(), length: 2 }
+        ; "index out of bounds"
+    )]
+    #[test_case(
+        "fn main() { let x = 1; assert(x + 1 == 3); }",
+        RuntimeError::AssertionFailed("x + 1 == 3".to_string())
+        ; "failed assertion"
+    )]
+    #[test_case(
+        "fn main() { assert_eq((1, 'a'), (1, 'b')); }",
+        RuntimeError::AssertionFailed("(1, 'a') == (1, 'b') (left: (1, a), right: (1, b))".to_string())
+        ; "failed equality assertion"
+    )]
+    fn programs_report_runtime_errors(source: &str, expected: RuntimeError) {
+        // When
+        let (result, _) = run(source);
+
+        // Then
+        assert_eq!(result.map_err(|err| err.value()), Err(expected));
+    }
+
+    #[test]
+    fn errors_are_reported_where_they_happen() {
+        // Given
+        let source = "fn main() -> i32 { let x = 0; return 10 / x; }";
+
+        // When
+        let (result, _) = run(source);
+
+        // Then
+        let span = result.unwrap_err().span();
+        assert_eq!(&source[span.range()], "10 / x");
+    }
+
+    #[test]
+    fn format_strings_are_filled_in() {
+        // When
+        let (result, output) = run(
+            "fn main() -> string { let x = 2; println(\"x = {}, {{x}}\", x * 3); print(\"{}\", 'a'); println(); return format(\"{}-{}\", true, (1, \"{}\")); }",
+        );
+
+        // Then
+        assert_eq!(result, Ok(Value::String(Rc::from("true-(1, {})"))));
+        assert_eq!(output, "x = 6, {x}\na\n");
+    }
+
+    #[test]
+    fn externs_call_builtins() {
+        // When
+        let (result, output) = run(
+            "#[link_name(\"println\")] extern fn show(fmt: string, value: i32); fn main() { show(\"{}!\", 3); let f = show; f(\"{}?\", 4); }",
+        );
+
+        // Then
+        assert_eq!(result, Ok(Value::Unit));
+        assert_eq!(output, "3!\n4?\n");
+    }
+
+    #[test]
+    fn programs_can_be_run_after_a_round_trip() {
+        // Given
+        let program = compile(
+            "struct P { name: string; } fn greet(p: P) -> string { return format(\"hello {}\", p.name); } fn main() -> string { return greet(P(\"world\")); }",
+        );
+        let program = decode(&encode(&program)).unwrap();
+        let mut output: Vec<u8> = Vec::new();
+
+        // When
+        let result = Vm::new(&program, &mut output).unwrap().call("main", vec![]);
+
+        // Then
+        assert_eq!(result, Ok(Value::String(Rc::from("hello world"))));
+    }
+
+    #[test]
+    fn functions_can_be_called_with_arguments() {
+        // Given
+        let program = compile("pub fn add(a: i64, b: i64) -> i64 { return a + b; }");
+        let mut output: Vec<u8> = Vec::new();
+        let mut vm = Vm::new(&program, &mut output).unwrap();
+
+        // When
+        let result = vm.call("add", vec![Value::I64(40), Value::I64(2)]);
+
+        // Then
+        assert_eq!(result, Ok(Value::I64(42)));
+    }
+
+    #[test]
+    fn invalid_programs_are_not_run() {
+        // Given
+        let mut program = compile("fn main() {}");
+        program.functions[0].code.insert(0, Instruction::Pop);
+        program.functions[0].spans.insert(0, Span::UNSET);
+        let mut output: Vec<u8> = Vec::new();
+
+        // When
+        let result = Vm::new(&program, &mut output).map(|_| ());
+
+        // Then
+        assert_eq!(
+            result.map_err(|err| err.to_string()),
+            Err("invalid bytecode in fn main at 0: the stack underflows".to_string())
+        );
+    }
+
+    #[test_case(
+        "fn main() -> i32 { let total = 0; let i: i32 = 0; while (i < 100) { if (i % 3 == 0 || i % 5 == 0) { total += i; } i += 1; } return total; }"
+        ; "loops"
+    )]
+    #[test_case(
+        "struct V { x: f64; y: f64; } fn add(a: V, b: V) -> V { return V(a.x + b.x, a.y + b.y); } fn main() -> string { let v = V(0.5, 1.0); let i = 0; while (i < 4) { v = add(v, v); i += 1; } return format(\"{}\", v); }"
+        ; "structs"
+    )]
+    #[test_case(
+        "fn main() { let (a, b) = (\"x\", 'y'); println(\"{} {} {}\", a, b, (1u8, -2i16, b\"z\")); assert_eq(a + \"1\", \"x1\"); }"
+        ; "output"
+    )]
+    #[test_case("fn main() -> u128 { return 340282366920938463463374607431768211455 + 1; }" ; "overflow")]
+    #[test_case(
+        "use std; fn main() { let m = std::map_new(); std::map_insert(m, \"a\", 1); std::println(\"{} {} {}\", std::map_len(m), std::repeat(\"ab\", 2), std::array_new()); }"
+        ; "standard library"
+    )]
+    #[test_case("fn main() { let f = |x: i32| x; std::println(\"{}\", f); f(1, 2); }" ; "closures")]
+    #[test_case("fn main() { std::substring(\"héllo\", 0, 2); }" ; "errors within other modules")]
+    fn programs_match_the_interpreter(source: &str) {
+        // When
+        let (result, output) = run(source);
+
+        // Then
+        assert_eq!((result, output), interpret(source));
+    }
+
+    #[test]
+    fn errors_within_other_modules_are_reported_at_the_call() {
+        // Given
+        let source = "fn main() { let a = std::array_new(); std::array_pop(a); }";
+
+        // When
+        let (result, _) = run(source);
+
+        // Then
+        let span = result.unwrap_err().span();
+        assert_eq!(&source[span.range()], "std::array_pop(a)");
+    }
+
+    #[test]
+    fn only_the_modules_that_are_used_are_compiled() {
+        // When
+        let program = compile("fn main() -> i32 { return 1; }");
+
+        // Then
+        assert_eq!(program.functions.len(), 1);
+        assert_eq!(program.externs, vec![]);
+    }
+}
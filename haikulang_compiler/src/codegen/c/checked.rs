@@ -0,0 +1,184 @@
+//! Checked integer arithmetic for the generated C code.
+//!
+//! C leaves signed overflow undefined and lets unsigned arithmetic wrap around, while the
+//! interpreter stops the program in both cases. Integer arithmetic is therefore written as
+//! calls to small static functions that check their operands first, and that abort with the
+//! message the interpreter would have given when the operation cannot be done.
+use crate::codegen::c::names::PREFIX;
+use crate::hir::nodes::{HirExprBinaryOp, HirExprUnaryOp};
+use crate::hir::ty::HirType;
+use std::fmt::Write;
+
+/// A function that performs one operator on one type of integer.
+pub struct Helper {
+    pub name: String,
+    pub definition: String,
+}
+
+// What the generated code needs to know about an integer type.
+struct IntegerType {
+    c_type: &'static str,
+    name: &'static str,
+    specifier: &'static str,
+    // The macro holding the smallest value of a signed type.
+    min: Option<&'static str>,
+}
+
+/// The function that applies a binary operator to two integers of the given type, or None if
+/// the operator is written as it is. Only the operators that can overflow or divide by zero
+/// are checked, and only for integers.
+pub fn binary(op: HirExprBinaryOp, ty: &HirType) -> Option<Helper> {
+    let integer = integer_type(ty)?;
+    let (name, builtin, operator) = match op {
+        HirExprBinaryOp::Add => ("add", Some("__builtin_add_overflow"), "+"),
+        HirExprBinaryOp::Sub => ("sub", Some("__builtin_sub_overflow"), "-"),
+        HirExprBinaryOp::Mul => ("mul", Some("__builtin_mul_overflow"), "*"),
+        HirExprBinaryOp::Div => ("div", None, "/"),
+        HirExprBinaryOp::Mod => ("mod", None, "%"),
+        _ => return None,
+    };
+
+    let name = format!("{}{}_{}", PREFIX, name, integer.name);
+    let t = integer.c_type;
+    let overflow = format!(
+        "fprintf(stderr, \"arithmetic overflow: %\" {} \" {:?} %\" {} \"\\n\", a, b);",
+        integer.specifier, op, integer.specifier
+    );
+
+    let mut definition = format!("static {} {}({} a, {} b) {{\n", t, name, t, t);
+    match builtin {
+        Some(builtin) => {
+            writeln!(definition, "    {} result;", t).unwrap();
+            writeln!(definition, "    if ({}(a, b, &result)) {{", builtin).unwrap();
+            failure(&mut definition, &overflow);
+            definition.push_str("    return result;\n");
+        }
+        None => {
+            definition.push_str("    if (b == 0) {\n");
+            failure(
+                &mut definition,
+                "fputs(\"attempted to divide by zero\\n\", stderr);",
+            );
+            // The quotient of the smallest value and -1 is one larger than the largest.
+            if let Some(min) = integer.min {
+                writeln!(definition, "    if (a == {} && b == -1) {{", min).unwrap();
+                failure(&mut definition, &overflow);
+            }
+            writeln!(definition, "    return a {} b;", operator).unwrap();
+        }
+    }
+    definition.push_str("}\n");
+
+    Some(Helper { name, definition })
+}
+
+/// The function that applies a unary operator to an integer of the given type, or None if
+/// the operator is written as it is. Only negation can overflow.
+pub fn unary(op: HirExprUnaryOp, ty: &HirType) -> Option<Helper> {
+    let integer = integer_type(ty)?;
+    let min = integer.min.filter(|_| op == HirExprUnaryOp::Negate)?;
+
+    let name = format!("{}neg_{}", PREFIX, integer.name);
+    let t = integer.c_type;
+    let mut definition = format!("static {} {}({} a) {{\n", t, name, t);
+    writeln!(definition, "    if (a == {}) {{", min).unwrap();
+    failure(
+        &mut definition,
+        &format!(
+            "fprintf(stderr, \"arithmetic overflow: {:?} %\" {} \"\\n\", a);",
+            op, integer.specifier
+        ),
+    );
+    writeln!(definition, "    return ({})-a;", t).unwrap();
+    definition.push_str("}\n");
+
+    Some(Helper { name, definition })
+}
+
+fn failure(definition: &mut String, report: &str) {
+    writeln!(definition, "        {}", report).unwrap();
+    definition.push_str("        abort();\n");
+    definition.push_str("    }\n");
+}
+
+fn integer_type(ty: &HirType) -> Option<IntegerType> {
+    let (c_type, name, specifier, min) = match ty {
+        HirType::I8 => ("int8_t", "i8", "PRId8", Some("INT8_MIN")),
+        HirType::I16 => ("int16_t", "i16", "PRId16", Some("INT16_MIN")),
+        HirType::I32 => ("int32_t", "i32", "PRId32", Some("INT32_MIN")),
+        HirType::I64 => ("int64_t", "i64", "PRId64", Some("INT64_MIN")),
+        HirType::ISize => ("intptr_t", "isize", "PRIdPTR", Some("INTPTR_MIN")),
+        HirType::U8 => ("uint8_t", "u8", "PRIu8", None),
+        HirType::U16 => ("uint16_t", "u16", "PRIu16", None),
+        HirType::U32 => ("uint32_t", "u32", "PRIu32", None),
+        HirType::U64 => ("uint64_t", "u64", "PRIu64", None),
+        HirType::USize => ("uintptr_t", "usize", "PRIuPTR", None),
+        _ => return None,
+    };
+    Some(IntegerType {
+        c_type,
+        name,
+        specifier,
+        min,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overflowing_operators_are_checked() {
+        // When
+        let helper = binary(HirExprBinaryOp::Add, &HirType::U8).unwrap();
+
+        // Then
+        assert_eq!(helper.name, "hk_add_u8");
+        assert_eq!(
+            helper.definition,
+            concat!(
+                "static uint8_t hk_add_u8(uint8_t a, uint8_t b) {\n",
+                "    uint8_t result;\n",
+                "    if (__builtin_add_overflow(a, b, &result)) {\n",
+                "        fprintf(stderr, \"arithmetic overflow: %\" PRIu8 \" Add %\" PRIu8 \"\\n\", a, b);\n",
+                "        abort();\n",
+                "    }\n",
+                "    return result;\n",
+                "}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn signed_division_checks_for_the_smallest_value() {
+        // When
+        let helper = binary(HirExprBinaryOp::Div, &HirType::I32).unwrap();
+
+        // Then
+        assert_eq!(
+            helper.definition,
+            concat!(
+                "static int32_t hk_div_i32(int32_t a, int32_t b) {\n",
+                "    if (b == 0) {\n",
+                "        fputs(\"attempted to divide by zero\\n\", stderr);\n",
+                "        abort();\n",
+                "    }\n",
+                "    if (a == INT32_MIN && b == -1) {\n",
+                "        fprintf(stderr, \"arithmetic overflow: %\" PRId32 \" Div %\" PRId32 \"\\n\", a, b);\n",
+                "        abort();\n",
+                "    }\n",
+                "    return a / b;\n",
+                "}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn other_operators_are_not_checked() {
+        // Then
+        assert!(binary(HirExprBinaryOp::BinaryAnd, &HirType::I32).is_none());
+        assert!(binary(HirExprBinaryOp::Add, &HirType::F64).is_none());
+        assert!(unary(HirExprUnaryOp::Negate, &HirType::F32).is_none());
+        assert!(unary(HirExprUnaryOp::Invert, &HirType::I32).is_none());
+    }
+}
@@ -0,0 +1,1346 @@
+//! Emission of C source code from type checked HIR.
+use crate::codegen::c::checked;
+use crate::codegen::c::names::mangle;
+use crate::db::database::{Database, FileId, FunctionId};
+use crate::db::item_tree::ItemKind;
+use crate::error::CompilerError;
+use crate::hir::context::HirModuleContext;
+use crate::hir::nodes::*;
+use crate::hir::ty::HirType;
+use crate::hir::typeck::HirTypeckResult;
+use haikulang_parser::span::{Span, Spanned};
+use num_bigint::BigInt;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::Write;
+
+const TARGET: &str = "C";
+const INDENT: &str = "    ";
+
+/// Compile every struct and function in a file into a single C source file.
+///
+/// Anything that has no direct equivalent in C is rejected with an error naming the
+/// feature, including closures, function values, tuples, indexing, strings, big integers,
+/// 128-bit integers, the `**` operator, comparisons between structs and calls to other
+/// modules.
+/// Every function is still emitted after an error so that all of them are reported at once.
+///
+/// When line directives are enabled, each statement is preceded by a `#line` directive
+/// naming the line of the source file that it came from. Directives are only written when
+/// the line differs from the one that the C compiler would assume by counting the lines
+/// since the last directive, so consecutive statements from consecutive lines share one.
+pub fn compile_module(
+    db: &mut Database,
+    file: FileId,
+    line_directives: bool,
+) -> Result<String, Vec<Spanned<CompilerError>>> {
+    let item_tree = db.item_tree(file);
+    let mut functions = Vec::new();
+    for item in item_tree.functions() {
+        let id = FunctionId::new(file, &item.name);
+        if let (Some(function), Some(types)) = (db.lower_function(&id), db.type_of(&id)) {
+            functions.push((function, types));
+        }
+    }
+
+    let source = SourceFile {
+        path: db.file_path(file).display().to_string(),
+        text: db.file_text(file).to_string(),
+        line_directives,
+    };
+    let context = db.module_context(file);
+    let mut errors = Vec::new();
+    let mut headers = BTreeSet::from(["stdbool.h", "stdint.h"]);
+
+    // Calls to extern functions use the names that they are linked by, and everything else
+    // uses its mangled name.
+    let mut callees = HashMap::new();
+    let mut prototypes = String::new();
+    for item in &item_tree.items {
+        if item.kind != ItemKind::ExternFunction {
+            continue;
+        }
+        let Some(header) = context.lookup_function_by_name(&item.name) else {
+            continue;
+        };
+        let link_name = header
+            .attributes
+            .link_name
+            .clone()
+            .unwrap_or_else(|| item.name.clone());
+        match prototype(context, header, &link_name) {
+            Ok(prototype) => writeln!(prototypes, "{};", prototype).unwrap(),
+            Err(error) => errors.push(error),
+        }
+        callees.insert(item.name.clone(), link_name);
+    }
+    if !prototypes.is_empty() {
+        prototypes.push('\n');
+    }
+
+    let mut entry_point = None;
+    for (function, types) in &functions {
+        let header = &function.header;
+        let name = context.get_string(header.name);
+        let c_name = mangle(name);
+        // Anything unsupported in the signature is reported when the function is emitted.
+        match prototype(context, header, &c_name) {
+            Ok(prototype) if header.is_pub => writeln!(prototypes, "{};", prototype).unwrap(),
+            Ok(prototype) => writeln!(prototypes, "static {};", prototype).unwrap(),
+            Err(_) => {}
+        }
+
+        // C programs start at a function named main, which has to return an int.
+        if name == "main" && types.signature.parameters.is_empty() {
+            entry_point = match types.signature.return_type {
+                HirType::Unit => Some(format!("{}();\n{}return 0;", c_name, INDENT)),
+                HirType::I32 => Some(format!("return {}();", c_name)),
+                _ => None,
+            };
+        }
+        callees.insert(name.clone(), c_name);
+    }
+
+    let structs = match struct_definitions(context, &item_tree.items, &source) {
+        Ok(structs) => structs,
+        Err(struct_errors) => {
+            errors.extend(struct_errors);
+            String::new()
+        }
+    };
+
+    // Checked arithmetic is shared by every function that uses the same operator and type.
+    let mut helpers = BTreeMap::new();
+    let mut definitions = String::new();
+    for (function, types) in &functions {
+        let emitter = FunctionEmitter::new(context, function, types, &source, &callees);
+        match emitter.emit() {
+            Ok(emitted) => {
+                definitions.push('\n');
+                definitions.push_str(&emitted.definition);
+                headers.extend(emitted.headers);
+                helpers.extend(emitted.helpers);
+            }
+            Err(function_errors) => errors.extend(function_errors),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut output = String::new();
+    writeln!(output, "/* Generated by haikulang from {}. */", source.path).unwrap();
+    for header in headers {
+        writeln!(output, "#include <{}>", header).unwrap();
+    }
+    output.push('\n');
+    output.push_str(&structs);
+    output.push_str(&prototypes);
+    for helper in helpers.values() {
+        output.push('\n');
+        output.push_str(helper);
+    }
+    output.push_str(&definitions);
+    if let Some(entry_point) = entry_point {
+        write!(
+            output,
+            "\nint main(void) {{\n{}{}\n}}\n",
+            INDENT, entry_point
+        )
+        .unwrap();
+    }
+    Ok(output)
+}
+
+// What we need to know about the file being compiled in order to refer back to it.
+struct SourceFile {
+    path: String,
+    text: String,
+    line_directives: bool,
+}
+
+impl SourceFile {
+    fn line_of(&self, span: Span) -> usize {
+        let start = span.start().min(self.text.len());
+        self.text[..start].matches('\n').count() + 1
+    }
+
+    fn text_of(&self, span: Span) -> &str {
+        self.text.get(span.range()).unwrap_or("<unknown>")
+    }
+
+    fn line_directive(&self, line: usize) -> String {
+        format!("#line {} {}", line, c_string(&self.path))
+    }
+}
+
+fn prototype(
+    context: &HirModuleContext,
+    header: &HirFunctionHeader,
+    name: &str,
+) -> Result<String, Spanned<CompilerError>> {
+    let resolve = |type_ref: &HirTypeRef| {
+        let ty = context.resolve_type(type_ref).unwrap_or(HirType::Unknown);
+        c_type(&ty).map_err(|feature| unsupported(feature, type_ref.span))
+    };
+
+    let return_type = match &header.return_type {
+        Some(return_type) => resolve(return_type)?,
+        None => "void".to_string(),
+    };
+    let mut parameters = Vec::new();
+    for parameter in &header.parameters {
+        let ty = resolve(&parameter.type_ref)?;
+        parameters.push(format!(
+            "{} {}",
+            ty,
+            mangle(context.get_string(parameter.name))
+        ));
+    }
+    if parameters.is_empty() {
+        parameters.push("void".to_string());
+    }
+
+    Ok(format!(
+        "{} {}({})",
+        return_type,
+        name,
+        parameters.join(", ")
+    ))
+}
+
+// Structs can hold other structs, which have to be defined first. Structs that hold
+// themselves would be infinitely large, so they are reported as unsupported.
+fn struct_definitions(
+    context: &HirModuleContext,
+    items: &[crate::db::item_tree::Item],
+    source: &SourceFile,
+) -> Result<String, Vec<Spanned<CompilerError>>> {
+    struct Sorter<'a> {
+        context: &'a HirModuleContext,
+        source: &'a SourceFile,
+        visiting: HashSet<String>,
+        defined: HashSet<String>,
+        output: String,
+        errors: Vec<Spanned<CompilerError>>,
+    }
+
+    impl Sorter<'_> {
+        fn define(&mut self, name: &str) {
+            if self.defined.contains(name) {
+                return;
+            }
+            let Some(header) = self.context.lookup_struct_by_name(name) else {
+                return;
+            };
+            if !self.visiting.insert(name.to_string()) {
+                self.errors
+                    .push(unsupported("structs that contain themselves", header.span));
+                return;
+            }
+
+            let mut members = Vec::new();
+            for member in &header.members {
+                let ty = self
+                    .context
+                    .resolve_type(&member.type_ref)
+                    .unwrap_or(HirType::Unknown);
+                if let HirType::Struct(member_struct) = &ty {
+                    self.define(member_struct);
+                }
+                match c_type(&ty) {
+                    Ok(c_type) => members.push(format!(
+                        "{}{} {};",
+                        INDENT,
+                        c_type,
+                        mangle(self.context.get_string(member.name))
+                    )),
+                    Err(feature) => self.errors.push(unsupported(feature, member.type_ref.span)),
+                }
+            }
+
+            if self.source.line_directives {
+                let line = self.source.line_of(header.span);
+                writeln!(self.output, "{}", self.source.line_directive(line)).unwrap();
+            }
+            writeln!(self.output, "struct {} {{", mangle(name)).unwrap();
+            for member in members {
+                writeln!(self.output, "{}", member).unwrap();
+            }
+            self.output.push_str("};\n\n");
+
+            self.visiting.remove(name);
+            self.defined.insert(name.to_string());
+        }
+    }
+
+    let mut sorter = Sorter {
+        context,
+        source,
+        visiting: HashSet::new(),
+        defined: HashSet::new(),
+        output: String::new(),
+        errors: Vec::new(),
+    };
+    for item in items {
+        if item.kind == ItemKind::Struct {
+            sorter.define(&item.name);
+        }
+    }
+
+    if sorter.errors.is_empty() {
+        Ok(sorter.output)
+    } else {
+        Err(sorter.errors)
+    }
+}
+
+struct FunctionEmitter<'a> {
+    module: &'a HirModuleContext,
+    function: &'a HirFunctionData,
+    types: &'a HirTypeckResult,
+    source: &'a SourceFile,
+    callees: &'a HashMap<String, String>,
+    names: HashMap<HirVariableId, String>,
+    used_names: HashSet<String>,
+    output: String,
+    depth: usize,
+    loops: usize,
+    // The line of the source file that the next line of output is attributed to by the
+    // most recent line directive.
+    next_line: Option<usize>,
+    headers: BTreeSet<&'static str>,
+    // The definitions of the checked arithmetic functions used so far, by name.
+    helpers: BTreeMap<String, String>,
+    errors: Vec<Spanned<CompilerError>>,
+}
+
+// A function definition along with everything that has to be written before it.
+struct EmittedFunction {
+    definition: String,
+    headers: BTreeSet<&'static str>,
+    helpers: BTreeMap<String, String>,
+}
+
+impl<'a> FunctionEmitter<'a> {
+    fn new(
+        module: &'a HirModuleContext,
+        function: &'a HirFunctionData,
+        types: &'a HirTypeckResult,
+        source: &'a SourceFile,
+        callees: &'a HashMap<String, String>,
+    ) -> Self {
+        Self {
+            module,
+            function,
+            types,
+            source,
+            callees,
+            names: HashMap::new(),
+            // Locals would hide any function with the same name.
+            used_names: callees.values().cloned().collect(),
+            output: String::new(),
+            depth: 0,
+            loops: 0,
+            next_line: None,
+            headers: BTreeSet::new(),
+            helpers: BTreeMap::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    fn emit(mut self) -> Result<EmittedFunction, Vec<Spanned<CompilerError>>> {
+        let header = &self.function.header;
+        let signature = &self.types.signature;
+
+        let return_type = self.c_type(&signature.return_type, header.span);
+        let mut parameters = Vec::new();
+        for (variable, ty) in self.function.parameters.iter().zip(&signature.parameters) {
+            let location = self.function.get_variable(*variable).location;
+            let ty = self.c_type(ty, location);
+            let name = self.declare(*variable);
+            parameters.push(format!("{} {}", ty, name));
+        }
+        if parameters.is_empty() {
+            parameters.push("void".to_string());
+        }
+
+        let name = &self.callees[self.module.get_string(header.name)];
+        let storage = if header.is_pub { "" } else { "static " };
+        self.line_at(
+            &format!(
+                "{}{} {}({}) {{",
+                storage,
+                return_type,
+                name,
+                parameters.join(", ")
+            ),
+            header.span,
+        );
+        self.body(self.function.root_statement);
+        self.line("}");
+
+        if self.errors.is_empty() {
+            Ok(EmittedFunction {
+                definition: self.output,
+                headers: self.headers,
+                helpers: self.helpers,
+            })
+        } else {
+            Err(self.errors)
+        }
+    }
+
+    // Write the statements of a block, or a single statement, at one level deeper.
+    fn body(&mut self, id: HirStatementId) {
+        self.depth += 1;
+        match &self.function.get_statement(id).kind {
+            HirStatementKind::Block(statements) => {
+                for statement in statements {
+                    self.statement(*statement);
+                }
+            }
+            _ => self.statement(id),
+        }
+        self.depth -= 1;
+    }
+
+    fn statement(&mut self, id: HirStatementId) {
+        let statement = self.function.get_statement(id);
+        let span = statement.span;
+
+        match &statement.kind {
+            HirStatementKind::Empty => {}
+            HirStatementKind::VarDecl { variable, expr } => {
+                let location = self.function.get_variable(*variable).location;
+                let ty = self.types.type_of_variable(*variable);
+                if *ty == HirType::Unit {
+                    return self.unsupported("variables of the unit type", location);
+                }
+                // A value that cannot be held in the variable is only reported once.
+                let ty = match c_type(ty) {
+                    Ok(ty) => ty,
+                    Err(feature) => return self.unsupported(feature, location),
+                };
+                let name = self.declare(*variable);
+                match expr {
+                    Some(expr) => {
+                        let value = self.expr(*expr);
+                        self.line_at(&format!("{} {} = {};", ty, name, value), span);
+                    }
+                    None => self.line_at(&format!("{} {};", ty, name), span),
+                }
+            }
+            HirStatementKind::Destructure { .. } => self.unsupported("destructuring", span),
+            HirStatementKind::Expr(expr) => {
+                if let HirExprKind::Call { callee, arguments } = &self.function.get_expr(*expr).kind
+                    && let HirExprKind::LoadBuiltin(
+                        builtin @ (HirBuiltin::Assert | HirBuiltin::AssertEq),
+                    ) = self.function.get_expr(*callee).kind
+                {
+                    return self.assertion(builtin, arguments, span);
+                }
+                let expr = self.expr(*expr);
+                self.line_at(&format!("{};", expr), span);
+            }
+            HirStatementKind::Return(Some(expr)) => {
+                let expr = self.expr(*expr);
+                self.line_at(&format!("return {};", expr), span);
+            }
+            HirStatementKind::Return(None) => self.line_at("return;", span),
+            HirStatementKind::Continue | HirStatementKind::Break if self.loops == 0 => {
+                self.unsupported("break and continue outside of loops", span)
+            }
+            HirStatementKind::Continue => self.line_at("continue;", span),
+            HirStatementKind::Break => self.line_at("break;", span),
+            HirStatementKind::If { .. } => {
+                self.if_statement(id, "");
+                self.line("}");
+            }
+            HirStatementKind::While { condition, body } => {
+                let condition = self.expr(*condition);
+                self.line_at(&format!("while ({}) {{", condition), span);
+                self.loops += 1;
+                self.body(*body);
+                self.loops -= 1;
+                self.line("}");
+            }
+            HirStatementKind::Block(_) => {
+                self.line_at("{", span);
+                self.body(id);
+                self.line("}");
+            }
+        }
+    }
+
+    // Chains of if and else are written out flat, leaving the final closing brace to the
+    // caller.
+    fn if_statement(&mut self, id: HirStatementId, prefix: &str) {
+        let statement = self.function.get_statement(id);
+        let HirStatementKind::If {
+            condition,
+            then,
+            otherwise,
+        } = &statement.kind
+        else {
+            unreachable!("only if statements are written as if statements");
+        };
+
+        let condition = self.expr(*condition);
+        self.line_at(&format!("{}if ({}) {{", prefix, condition), statement.span);
+        self.body(*then);
+
+        if let Some(otherwise) = otherwise {
+            if let HirStatementKind::If { .. } = self.function.get_statement(*otherwise).kind {
+                self.if_statement(*otherwise, "} else ");
+            } else {
+                self.line("} else {");
+                self.body(*otherwise);
+            }
+        }
+    }
+
+    fn expr(&mut self, id: HirExprId) -> String {
+        let expr = self.function.get_expr(id);
+        let span = expr.span;
+        let ty = self.types.type_of_expr(id);
+
+        match &expr.kind {
+            HirExprKind::LoadLiteral(literal) => self.literal(&literal.kind, ty, false, span),
+            HirExprKind::LoadVariable(variable) => self.variable_name(*variable),
+            HirExprKind::LoadFunction(_) => self.unsupported_expr("function values", span),
+            HirExprKind::LoadStruct(_) => self.unsupported_expr("struct values", span),
+            HirExprKind::LoadBuiltin(_) => self.unsupported_expr("builtin function values", span),
+            HirExprKind::BinaryOp { left, op, right } => {
+                let left_type = self.types.type_of_expr(*left);
+                let Some(operator) = self.binary_operator(*op, left_type, span) else {
+                    return String::new();
+                };
+                if let Some(helper) = checked::binary(*op, left_type) {
+                    let name = self.use_helper(helper);
+                    let left = self.expr(*left);
+                    let right = self.expr(*right);
+                    return format!("{}({}, {})", name, left, right);
+                }
+                let left = self.operand(*left);
+                let right = self.operand(*right);
+                format!("{} {} {}", left, operator, right)
+            }
+            HirExprKind::UnaryOp { op, value } => {
+                // Negative literals are written as they are, as negating the largest
+                // literal that fits in a type would overflow it.
+                if *op == HirExprUnaryOp::Negate
+                    && let HirExprKind::LoadLiteral(literal) = &self.function.get_expr(*value).kind
+                {
+                    return self.literal(&literal.kind, ty, true, span);
+                }
+                if let Some(helper) = checked::unary(*op, ty) {
+                    let name = self.use_helper(helper);
+                    return format!("{}({})", name, self.expr(*value));
+                }
+
+                let operator = match op {
+                    HirExprUnaryOp::Negate => "-",
+                    HirExprUnaryOp::Not => "!",
+                    HirExprUnaryOp::Invert => "~",
+                };
+                format!("{}{}", operator, self.operand(*value))
+            }
+            HirExprKind::Assign { target, op, value } => {
+                // Checked compound assignments are written out in full, which is fine as
+                // assignment targets are only ever variables and their members.
+                let target_type = self.types.type_of_expr(*target);
+                if let Some(op) = op
+                    && self.binary_operator(*op, target_type, span).is_some()
+                    && let Some(helper) = checked::binary(*op, target_type)
+                {
+                    let name = self.use_helper(helper);
+                    let target = self.expr(*target);
+                    let value = self.expr(*value);
+                    return format!("{} = {}({}, {})", target, name, target, value);
+                }
+
+                let operator = match op {
+                    Some(op) => {
+                        let target_type = self.types.type_of_expr(*target);
+                        match self.binary_operator(*op, target_type, span) {
+                            Some(operator) => format!("{}=", operator),
+                            None => return String::new(),
+                        }
+                    }
+                    None => "=".to_string(),
+                };
+                let target = self.expr(*target);
+                let value = self.expr(*value);
+                format!("{} {} {}", target, operator, value)
+            }
+            HirExprKind::MemberAccess { owner, member } => {
+                let owner = self.operand(*owner);
+                format!("{}.{}", owner, mangle(self.module.get_string(*member)))
+            }
+            HirExprKind::Index { .. } => self.unsupported_expr("indexing", span),
+            HirExprKind::Call { callee, arguments } => self.call(*callee, arguments, span),
+            HirExprKind::Closure(_) => self.unsupported_expr("closures", span),
+            HirExprKind::Tuple(_) => self.unsupported_expr("tuples", span),
+            HirExprKind::Format(_) => self.unsupported_expr("format strings", span),
+            HirExprKind::Unresolved(_) => self.unsupported_expr("calls to other modules", span),
+        }
+    }
+
+    // Record that a checked arithmetic function is needed, returning its name.
+    fn use_helper(&mut self, helper: checked::Helper) -> String {
+        self.headers.extend(["inttypes.h", "stdio.h", "stdlib.h"]);
+        self.helpers
+            .entry(helper.name.clone())
+            .or_insert(helper.definition);
+        helper.name
+    }
+
+    // An expression that is part of a larger one, which is wrapped in parentheses unless it
+    // binds more tightly than anything that it could be part of.
+    fn operand(&mut self, id: HirExprId) -> String {
+        let expr = self.expr(id);
+        match &self.function.get_expr(id).kind {
+            // Checked arithmetic is written as calls, which bind tightly already.
+            HirExprKind::BinaryOp { left, op, .. }
+                if checked::binary(*op, self.types.type_of_expr(*left)).is_some() =>
+            {
+                expr
+            }
+            HirExprKind::BinaryOp { .. }
+            | HirExprKind::UnaryOp { .. }
+            | HirExprKind::Assign { .. } => format!("({})", expr),
+            HirExprKind::LoadLiteral(_) if expr.starts_with('-') => format!("({})", expr),
+            _ => expr,
+        }
+    }
+
+    fn binary_operator(
+        &mut self,
+        op: HirExprBinaryOp,
+        operand: &HirType,
+        span: Span,
+    ) -> Option<&'static str> {
+        let operator = match op {
+            HirExprBinaryOp::Add => "+",
+            HirExprBinaryOp::Sub => "-",
+            HirExprBinaryOp::Mul => "*",
+            HirExprBinaryOp::Div => "/",
+            HirExprBinaryOp::Mod if operand.is_float() => {
+                self.unsupported("the remainder of floats", span);
+                return None;
+            }
+            HirExprBinaryOp::Mod => "%",
+            HirExprBinaryOp::Pow => {
+                self.unsupported("the ** operator", span);
+                return None;
+            }
+            HirExprBinaryOp::BinaryAnd => "&",
+            HirExprBinaryOp::BinaryOr => "|",
+            HirExprBinaryOp::BinaryXor => "^",
+            HirExprBinaryOp::BinaryShl => "<<",
+            HirExprBinaryOp::BinaryShr => ">>",
+            HirExprBinaryOp::BoolAnd => "&&",
+            HirExprBinaryOp::BoolOr => "||",
+            HirExprBinaryOp::Eq | HirExprBinaryOp::NotEq
+                if matches!(operand, HirType::Struct(_)) =>
+            {
+                self.unsupported("comparing structs", span);
+                return None;
+            }
+            HirExprBinaryOp::Eq => "==",
+            HirExprBinaryOp::NotEq => "!=",
+            HirExprBinaryOp::Less => "<",
+            HirExprBinaryOp::LessEq => "<=",
+            HirExprBinaryOp::Greater => ">",
+            HirExprBinaryOp::GreaterEq => ">=",
+        };
+        Some(operator)
+    }
+
+    fn literal(
+        &mut self,
+        literal: &HirLiteralKind,
+        ty: &HirType,
+        negated: bool,
+        span: Span,
+    ) -> String {
+        let integer = |value: BigInt| {
+            let value = if negated { -value } else { value };
+            integer_literal(&value, ty)
+        };
+
+        match literal {
+            HirLiteralKind::Bool(value) => value.to_string(),
+            HirLiteralKind::I8(value) => integer((*value).into()),
+            HirLiteralKind::I16(value) => integer((*value).into()),
+            HirLiteralKind::I32(value) => integer((*value).into()),
+            HirLiteralKind::I64(value) => integer((*value).into()),
+            HirLiteralKind::ISize(value) => integer((*value).into()),
+            HirLiteralKind::U8(value) => integer((*value).into()),
+            HirLiteralKind::U16(value) => integer((*value).into()),
+            HirLiteralKind::U32(value) => integer((*value).into()),
+            HirLiteralKind::U64(value) => integer((*value).into()),
+            HirLiteralKind::USize(value) => integer((*value).into()),
+            HirLiteralKind::Int(value) if ty.is_float() => {
+                let sign = if negated { "-" } else { "" };
+                let suffix = if *ty == HirType::F32 { ".0f" } else { ".0" };
+                format!("{}{}{}", sign, value, suffix)
+            }
+            HirLiteralKind::Int(value) => integer(value.clone().into()),
+            HirLiteralKind::F32(value) => {
+                let value = if negated { -value } else { *value };
+                format!("{:?}f", value)
+            }
+            HirLiteralKind::F64(value) => {
+                let value = if negated { -value } else { *value };
+                format!("{:?}", value)
+            }
+            HirLiteralKind::Char(value) if value.is_ascii_graphic() && *value != '\'' => {
+                format!("'{}'", value)
+            }
+            HirLiteralKind::Char(value) => format!("UINT32_C({:#x})", *value as u32),
+            HirLiteralKind::I128(_) | HirLiteralKind::U128(_) => {
+                self.unsupported_expr("128-bit integers", span)
+            }
+            HirLiteralKind::String(_) => self.unsupported_expr("strings", span),
+            HirLiteralKind::Bytes(_) => self.unsupported_expr("byte strings", span),
+        }
+    }
+
+    fn call(&mut self, callee: HirExprId, arguments: &[HirExprId], span: Span) -> String {
+        match &self.function.get_expr(callee).kind {
+            HirExprKind::LoadFunction(name) => {
+                let name = &self.callees[self.module.get_string(*name)];
+                let arguments: Vec<String> = arguments
+                    .iter()
+                    .map(|argument| self.expr(*argument))
+                    .collect();
+                format!("{}({})", name, arguments.join(", "))
+            }
+            HirExprKind::LoadStruct(name) => {
+                let arguments: Vec<String> = arguments
+                    .iter()
+                    .map(|argument| self.expr(*argument))
+                    .collect();
+                format!(
+                    "(struct {}){{{}}}",
+                    mangle(self.module.get_string(*name)),
+                    arguments.join(", ")
+                )
+            }
+            HirExprKind::LoadBuiltin(HirBuiltin::Assert | HirBuiltin::AssertEq) => {
+                self.unsupported_expr("assertions within other expressions", span)
+            }
+            HirExprKind::LoadBuiltin(builtin @ (HirBuiltin::Print | HirBuiltin::Println)) => {
+                let newline = *builtin == HirBuiltin::Println;
+                self.print(arguments[0], newline)
+            }
+            HirExprKind::LoadBuiltin(_) => self.unsupported_expr("format strings", span),
+            HirExprKind::Unresolved(_) => self.unsupported_expr("calls to other modules", span),
+            _ => self.unsupported_expr("calls to closures and function values", span),
+        }
+    }
+
+    // Failed assertions describe what failed on standard error before aborting, as the
+    // interpreter does.
+    fn assertion(&mut self, builtin: HirBuiltin, arguments: &[HirExprId], span: Span) {
+        let source = |id: HirExprId| self.source.text_of(self.function.get_expr(id).span);
+        let (condition, message) = if builtin == HirBuiltin::Assert {
+            let message = source(arguments[0]).to_string();
+            (format!("!{}", self.operand(arguments[0])), message)
+        } else {
+            let message = format!("{} == {}", source(arguments[0]), source(arguments[1]));
+            let ty = self.types.type_of_expr(arguments[0]);
+            if self
+                .binary_operator(HirExprBinaryOp::NotEq, ty, span)
+                .is_none()
+            {
+                return;
+            }
+            let left = self.operand(arguments[0]);
+            let right = self.operand(arguments[1]);
+            (format!("{} != {}", left, right), message)
+        };
+
+        self.headers.extend(["stdio.h", "stdlib.h"]);
+        let message = c_string(&format!("assertion failed: {}\n", message));
+        self.line_at(&format!("if ({}) {{", condition), span);
+        self.depth += 1;
+        self.line(&format!("fputs({}, stderr);", message));
+        self.line("abort();");
+        self.depth -= 1;
+        self.line("}");
+    }
+
+    fn print(&mut self, format: HirExprId, newline: bool) -> String {
+        self.headers.extend(["inttypes.h", "stdio.h"]);
+
+        let mut pieces = Vec::new();
+        let mut text = String::new();
+        let mut values = Vec::new();
+        let parts = match &self.function.get_expr(format).kind {
+            HirExprKind::Format(parts) => parts.clone(),
+            HirExprKind::LoadLiteral(HirLiteral {
+                kind: HirLiteralKind::String(text),
+                ..
+            }) => vec![HirFormatPart::Text(*text)],
+            _ => return self.unsupported_expr("format strings", self.span_of(format)),
+        };
+
+        for part in parts {
+            match part {
+                HirFormatPart::Text(id) => {
+                    text.push_str(&self.module.get_string(id).replace('%', "%%"))
+                }
+                HirFormatPart::Value(value) => {
+                    let ty = self.types.type_of_expr(value);
+                    let specifier = match ty {
+                        HirType::Bool => {
+                            text.push_str("%s");
+                            values.push(format!("{} ? \"true\" : \"false\"", self.operand(value)));
+                            continue;
+                        }
+                        HirType::I8 => "PRId8",
+                        HirType::I16 => "PRId16",
+                        HirType::I32 => "PRId32",
+                        HirType::I64 => "PRId64",
+                        HirType::ISize => "PRIdPTR",
+                        HirType::U8 => "PRIu8",
+                        HirType::U16 => "PRIu16",
+                        HirType::U32 => "PRIu32",
+                        HirType::U64 => "PRIu64",
+                        HirType::USize => "PRIuPTR",
+                        other => {
+                            let feature = format!("formatting values of type {}", other);
+                            return self.unsupported_expr(feature, self.span_of(value));
+                        }
+                    };
+                    text.push('%');
+                    pieces.push(c_string(&std::mem::take(&mut text)));
+                    pieces.push(specifier.to_string());
+                    values.push(self.expr(value));
+                }
+            }
+        }
+
+        if newline {
+            text.push('\n');
+        }
+        if !text.is_empty() || pieces.is_empty() {
+            pieces.push(c_string(&text));
+        }
+
+        let mut arguments = vec![pieces.join(" ")];
+        arguments.extend(values);
+        format!("printf({})", arguments.join(", "))
+    }
+
+    // Give a variable a name that no other variable in the function has, as C does not let
+    // a function's parameters be shadowed by its outermost block.
+    fn declare(&mut self, variable: HirVariableId) -> String {
+        let mangled = mangle(
+            self.module
+                .get_string(self.function.get_variable(variable).name),
+        );
+        let mut name = mangled.clone();
+        let mut suffix = 2;
+        while !self.used_names.insert(name.clone()) {
+            name = format!("{}_{}", mangled, suffix);
+            suffix += 1;
+        }
+        self.names.insert(variable, name.clone());
+        name
+    }
+
+    fn variable_name(&mut self, variable: HirVariableId) -> String {
+        match self.names.get(&variable) {
+            Some(name) => name.clone(),
+            None => self.declare(variable),
+        }
+    }
+
+    fn c_type(&mut self, ty: &HirType, span: Span) -> String {
+        c_type(ty).unwrap_or_else(|feature| self.unsupported_expr(feature, span))
+    }
+
+    fn span_of(&self, id: HirExprId) -> Span {
+        self.function.get_expr(id).span
+    }
+
+    // Write a line that comes from the given part of the source file, noting where it came
+    // from first if the compiler would otherwise attribute it to another line.
+    fn line_at(&mut self, text: &str, span: Span) {
+        if self.source.line_directives {
+            let line = self.source.line_of(span);
+            if self.next_line != Some(line) {
+                let directive = self.source.line_directive(line);
+                writeln!(self.output, "{}", directive).unwrap();
+                self.next_line = Some(line);
+            }
+        }
+        self.line(text);
+    }
+
+    fn line(&mut self, text: &str) {
+        writeln!(self.output, "{}{}", INDENT.repeat(self.depth), text).unwrap();
+        self.next_line = self.next_line.map(|line| line + 1);
+    }
+
+    fn unsupported(&mut self, feature: impl Into<String>, span: Span) {
+        self.errors.push(unsupported(feature, span));
+    }
+
+    // Report something as unsupported where an expression was expected, producing a
+    // placeholder in its place.
+    fn unsupported_expr(&mut self, feature: impl Into<String>, span: Span) -> String {
+        self.unsupported(feature, span);
+        String::new()
+    }
+}
+
+fn unsupported(feature: impl Into<String>, span: Span) -> Spanned<CompilerError> {
+    Spanned::new(
+        CompilerError::UnsupportedByTarget {
+            feature: feature.into(),
+            target: TARGET.to_string(),
+        },
+        span,
+    )
+}
+
+/// The C type that values of a type are held in. Types that have no C equivalent yet are
+/// described in the error.
+pub fn c_type(ty: &HirType) -> Result<String, String> {
+    let name = match ty {
+        HirType::Unit => "void",
+        HirType::Bool => "bool",
+        HirType::I8 => "int8_t",
+        HirType::I16 => "int16_t",
+        HirType::I32 => "int32_t",
+        HirType::I64 => "int64_t",
+        HirType::ISize => "intptr_t",
+        HirType::U8 => "uint8_t",
+        HirType::U16 => "uint16_t",
+        HirType::U32 => "uint32_t",
+        HirType::U64 => "uint64_t",
+        HirType::USize => "uintptr_t",
+        HirType::F32 => "float",
+        HirType::F64 => "double",
+        // Characters are held as their code points.
+        HirType::Char => "uint32_t",
+        HirType::Struct(name) if name.contains("::") => {
+            return Err("structs from other modules".to_string());
+        }
+        HirType::Struct(name) => return Ok(format!("struct {}", mangle(name))),
+        // There is no runtime to manage their memory yet.
+        HirType::String => return Err("strings".to_string()),
+        HirType::Bytes => return Err("byte strings".to_string()),
+        // Big integers are only provided by the interpreter's runtime so far.
+        HirType::BigInt => return Err("big integers".to_string()),
+        HirType::Unknown => return Err("values of an unknown type".to_string()),
+        other => return Err(format!("values of type {}", other)),
+    };
+    Ok(name.to_string())
+}
+
+// Integer literals are written so that they have the right type whatever the size of an
+// int is. The smallest values of the signed types cannot be written as negated literals, as
+// the literal itself would be out of range, so they are written with their macros instead.
+fn integer_literal(value: &BigInt, ty: &HirType) -> String {
+    if ty
+        .integer_range()
+        .is_some_and(|(lowest, _)| *value == lowest)
+    {
+        match ty {
+            HirType::I8 => return "INT8_MIN".to_string(),
+            HirType::I16 => return "INT16_MIN".to_string(),
+            HirType::I32 => return "INT32_MIN".to_string(),
+            HirType::I64 => return "INT64_MIN".to_string(),
+            HirType::ISize => return "INTPTR_MIN".to_string(),
+            _ => {}
+        }
+    }
+
+    match ty {
+        HirType::I64 => format!("INT64_C({})", value),
+        HirType::ISize => format!("(intptr_t) {}", value),
+        HirType::U8 | HirType::U16 | HirType::U32 => format!("{}u", value),
+        HirType::U64 => format!("UINT64_C({})", value),
+        HirType::USize => format!("(uintptr_t) {}u", value),
+        _ => value.to_string(),
+    }
+}
+
+// Write a C string literal. Anything that is not printable ASCII is escaped byte by byte,
+// using octal escapes as they cannot run on into the next character.
+fn c_string(text: &str) -> String {
+    let mut literal = String::from("\"");
+    for byte in text.bytes() {
+        match byte {
+            b'"' => literal.push_str("\\\""),
+            b'\\' => literal.push_str("\\\\"),
+            b'\n' => literal.push_str("\\n"),
+            b'\t' => literal.push_str("\\t"),
+            b' '..=b'~' => literal.push(byte as char),
+            _ => write!(literal, "\\{:03o}", byte).unwrap(),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::process::{Command, Output};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use test_case::test_case;
+
+    fn compile(source: &str, line_directives: bool) -> String {
+        let mut db = Database::new();
+        let file = db.add_file("main.hkl", source);
+        compile_module(&mut db, file, line_directives).unwrap()
+    }
+
+    fn compile_errors(source: &str) -> Vec<String> {
+        let mut db = Database::new();
+        let file = db.add_file("main.hkl", source);
+        compile_module(&mut db, file, false)
+            .unwrap_err()
+            .iter()
+            .map(|error| error.value().to_string())
+            .collect()
+    }
+
+    // Each test gets a directory of its own, as they run in parallel.
+    fn scratch_directory() -> PathBuf {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let directory = std::env::temp_dir().join(format!(
+            "haikulang-c-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    // Build the generated code with the system C compiler, along with any extra C code
+    // that it needs, returning what the compiler said and the directory it was built in.
+    fn build(generated: &str, extra: &str) -> (PathBuf, Output) {
+        let directory = scratch_directory();
+        std::fs::write(directory.join("main.c"), generated).unwrap();
+        std::fs::write(directory.join("extra.c"), extra).unwrap();
+        let output = Command::new("cc")
+            .current_dir(&directory)
+            .args([
+                "-std=c99", "-Wall", "-Wextra", "-o", "main", "main.c", "extra.c",
+            ])
+            .output()
+            .expect("a C compiler should be installed as cc");
+        (directory, output)
+    }
+
+    fn run(source: &str, extra: &str) -> Output {
+        let (directory, build) = build(&compile(source, true), extra);
+        assert!(
+            build.status.success() && build.stderr.is_empty(),
+            "{}",
+            String::from_utf8_lossy(&build.stderr)
+        );
+        let output = Command::new(directory.join("main")).output().unwrap();
+        std::fs::remove_dir_all(directory).unwrap();
+        output
+    }
+
+    fn stdout(output: &Output) -> String {
+        String::from_utf8(output.stdout.clone()).unwrap()
+    }
+
+    #[test]
+    fn modules_are_written_as_readable_c() {
+        // Given
+        let source = concat!(
+            "pub struct Point { x: i32; y: i32; }\n",
+            "#[link_name(\"report\")]\n",
+            "extern fn log(value: i64);\n",
+            "fn square(n: i32) -> i32 { return n * n; }\n",
+            "pub fn distance(p: Point) -> i32 {\n",
+            "    let total: i64 = 0;\n",
+            "    if (p.x > 0) { total = 1; } else if (p.y > 0) { total = 2; } else { total = -3; }\n",
+            "    log(total);\n",
+            "    return square(p.x) + square(p.y);\n",
+            "}\n",
+        );
+
+        // When
+        let output = compile(source, false);
+
+        // Then
+        assert_eq!(
+            output,
+            concat!(
+                "/* Generated by haikulang from main.hkl. */\n",
+                "#include <inttypes.h>\n",
+                "#include <stdbool.h>\n",
+                "#include <stdint.h>\n",
+                "#include <stdio.h>\n",
+                "#include <stdlib.h>\n",
+                "\n",
+                "struct Point {\n",
+                "    int32_t x;\n",
+                "    int32_t y;\n",
+                "};\n",
+                "\n",
+                "void report(int64_t value);\n",
+                "\n",
+                "static int32_t square(int32_t n);\n",
+                "int32_t distance(struct Point p);\n",
+                "\n",
+                "static int32_t hk_add_i32(int32_t a, int32_t b) {\n",
+                "    int32_t result;\n",
+                "    if (__builtin_add_overflow(a, b, &result)) {\n",
+                "        fprintf(stderr, \"arithmetic overflow: %\" PRId32 \" Add %\" PRId32 \"\\n\", a, b);\n",
+                "        abort();\n",
+                "    }\n",
+                "    return result;\n",
+                "}\n",
+                "\n",
+                "static int32_t hk_mul_i32(int32_t a, int32_t b) {\n",
+                "    int32_t result;\n",
+                "    if (__builtin_mul_overflow(a, b, &result)) {\n",
+                "        fprintf(stderr, \"arithmetic overflow: %\" PRId32 \" Mul %\" PRId32 \"\\n\", a, b);\n",
+                "        abort();\n",
+                "    }\n",
+                "    return result;\n",
+                "}\n",
+                "\n",
+                "static int32_t square(int32_t n) {\n",
+                "    return hk_mul_i32(n, n);\n",
+                "}\n",
+                "\n",
+                "int32_t distance(struct Point p) {\n",
+                "    int64_t total = INT64_C(0);\n",
+                "    if (p.x > 0) {\n",
+                "        total = INT64_C(1);\n",
+                "    } else if (p.y > 0) {\n",
+                "        total = INT64_C(2);\n",
+                "    } else {\n",
+                "        total = INT64_C(-3);\n",
+                "    }\n",
+                "    report(total);\n",
+                "    return hk_add_i32(square(p.x), square(p.y));\n",
+                "}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn programs_run_once_compiled() {
+        // Given
+        let source = concat!(
+            "fn fib(n: u64) -> u64 {\n",
+            "    let a: u64 = 0;\n",
+            "    let b: u64 = 1;\n",
+            "    let i: u64 = 0;\n",
+            "    while (i < n) { let next = a + b; a = b; b = next; i += 1; }\n",
+            "    return a;\n",
+            "}\n",
+            "fn main() {\n",
+            "    let i: u64 = 0;\n",
+            "    while (true) {\n",
+            "        i += 1;\n",
+            "        if (i > 90) { break; }\n",
+            "        if (i % 30 != 0) { continue; }\n",
+            "        println(\"fib({}) = {}, 100% {}\", i, fib(i), i == 90);\n",
+            "    }\n",
+            "}\n",
+        );
+
+        // When
+        let output = run(source, "");
+
+        // Then
+        assert!(output.status.success());
+        assert_eq!(
+            stdout(&output),
+            concat!(
+                "fib(30) = 832040, 100% false\n",
+                "fib(60) = 1548008755920, 100% false\n",
+                "fib(90) = 2880067194370816120, 100% true\n",
+            )
+        );
+    }
+
+    #[test]
+    fn structs_are_passed_and_updated_by_value() {
+        // Given
+        let source = concat!(
+            "struct Inner { value: i32; }\n",
+            "struct Outer { inner: Inner; scale: i32; }\n",
+            "fn bump(outer: Outer) -> Outer { outer.inner.value += 1; return outer; }\n",
+            "fn main() {\n",
+            "    let outer = Outer(Inner(20), 2);\n",
+            "    let bumped = bump(outer);\n",
+            "    println(\"{} {}\", outer.inner.value, bumped.inner.value * bumped.scale);\n",
+            "}\n",
+        );
+
+        // When
+        let output = run(source, "");
+
+        // Then
+        assert_eq!(stdout(&output), "20 42\n");
+    }
+
+    #[test]
+    fn names_that_clash_with_c_are_mangled() {
+        // Given
+        let source = concat!(
+            "#[link_name(\"twice\")]\n",
+            "extern fn double(value: i32) -> i32;\n",
+            "struct short { int: i32; }\n",
+            "fn char(unsigned: i32) -> i32 { let signed = double(unsigned); return signed; }\n",
+            "fn main() {\n",
+            "    let char2 = short(char(4));\n",
+            "    let char = char2.int;\n",
+            "    println(\"{}\", char);\n",
+            "}\n",
+        );
+
+        // When
+        let output = run(
+            source,
+            "#include <stdint.h>\nint32_t twice(int32_t x) { return 2 * x; }\n",
+        );
+
+        // Then
+        assert_eq!(stdout(&output), "8\n");
+    }
+
+    #[test]
+    fn extreme_integer_literals_keep_their_types() {
+        // Given
+        let source = concat!(
+            "fn main() {\n",
+            "    let a: i32 = -2147483648;\n",
+            "    let b: i64 = -9223372036854775808;\n",
+            "    let c: u64 = 18446744073709551615;\n",
+            "    let d: u8 = 255;\n",
+            "    println(\"{} {} {} {}\", a, b, c, d);\n",
+            "}\n",
+        );
+
+        // When
+        let output = run(source, "");
+
+        // Then
+        assert_eq!(
+            stdout(&output),
+            "-2147483648 -9223372036854775808 18446744073709551615 255\n"
+        );
+    }
+
+    #[test]
+    fn failed_assertions_abort_with_a_message() {
+        // Given
+        let source = "fn main() { let x = 1; assert(x > 0); assert_eq(x + 1, 3); }";
+
+        // When
+        let output = run(source, "");
+
+        // Then
+        assert!(!output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "assertion failed: x + 1 == 3\n"
+        );
+    }
+
+    #[test_case("let a: u8 = 255; let b: u8 = 1; a + b;",   "arithmetic overflow: 255 Add 1"                 ; "unsigned add")]
+    #[test_case("let a: u32 = 1; let b: u32 = 2; a - b;",   "arithmetic overflow: 1 Sub 2"                   ; "unsigned sub")]
+    #[test_case("let a: i64 = 1 << 62; a *= 2;",            "arithmetic overflow: 4611686018427387904 Mul 2" ; "compound mul")]
+    #[test_case("let a: i32 = 1; let b: i32 = 0; a / b;",   "attempted to divide by zero"                    ; "div by zero")]
+    #[test_case("let a: u16 = 1; let b: u16 = 0; a % b;",   "attempted to divide by zero"                    ; "mod by zero")]
+    #[test_case("let a: i8 = -128; let b: i8 = -1; a / b;", "arithmetic overflow: -128 Div -1"               ; "min div minus one")]
+    #[test_case("let a: i8 = -128; -a;",                    "arithmetic overflow: Negate -128"               ; "negate min")]
+    fn integer_overflow_aborts_with_a_message(body: &str, message: &str) {
+        // Given
+        let source = format!("fn main() {{ {} }}", body);
+
+        // When
+        let output = run(&source, "");
+
+        // Then
+        assert!(!output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            format!("{}\n", message)
+        );
+    }
+
+    #[test]
+    fn line_directives_point_the_c_compiler_at_the_original_source() {
+        // Given
+        let source = concat!(
+            "pub fn f() {\n",
+            "    let used = 1;\n",
+            "    if (used > 0) {\n",
+            "        let unused = 2;\n",
+            "    }\n",
+            "}\n",
+        );
+
+        // When
+        let (directory, output) = build(&compile(source, true), "");
+        std::fs::remove_dir_all(directory).unwrap();
+
+        // Then
+        let diagnostics = String::from_utf8_lossy(&output.stderr);
+        assert!(diagnostics.contains("main.hkl:4:"), "{}", diagnostics);
+        assert!(diagnostics.contains("unused variable"), "{}", diagnostics);
+    }
+
+    #[test]
+    fn line_directives_are_only_written_when_needed() {
+        // Given
+        let source =
+            "pub fn f() -> i32 {\n    let x = 1;\n    let y = 2;\n\n    return x + y;\n}\n";
+
+        // When
+        let output = compile(source, true);
+
+        // Then
+        assert!(output.ends_with(concat!(
+            "#line 1 \"main.hkl\"\n",
+            "int32_t f(void) {\n",
+            "    int32_t x = 1;\n",
+            "    int32_t y = 2;\n",
+            "#line 5 \"main.hkl\"\n",
+            "    return hk_add_i32(x, y);\n",
+            "}\n",
+        )));
+    }
+
+    #[test_case("pub fn f() { let s = \"hi\"; }",             "strings"                        ; "strings")]
+    #[test_case("pub fn f() { let s = b\"hi\"; }",            "byte strings"                   ; "byte strings")]
+    #[test_case("pub fn f(a: std::BigInt) {}",                "big integers"                   ; "big integers")]
+    #[test_case("pub fn f() { let a: std::BigInt = 1; }",     "big integers"                   ; "big integer literals")]
+    #[test_case("pub fn f(t: (i32, bool)) {}",                "values of type (i32, bool)"     ; "tuples")]
+    #[test_case("pub fn f() { |x: i32| x; }",                 "closures"                       ; "closures")]
+    #[test_case("pub fn f(a: i32) -> i32 { return a ** 2; }", "the ** operator"                ; "pow")]
+    #[test_case("pub fn f(a: f64) { println(\"{}\", a); }",    "formatting values of type f64"  ; "formatting floats")]
+    #[test_case("struct S { s: S; }",                         "structs that contain themselves" ; "recursive structs")]
+    #[test_case("extern fn g(s: string);",                    "strings"                        ; "extern parameters")]
+    fn unsupported_features_are_reported(source: &str, feature: &str) {
+        // When
+        let errors = compile_errors(source);
+
+        // Then
+        assert_eq!(
+            errors,
+            vec![format!("{} cannot be compiled to C yet", feature)]
+        );
+    }
+}
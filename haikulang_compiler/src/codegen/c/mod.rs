@@ -0,0 +1,16 @@
+//! A backend producing C source code, so that programs can be built anywhere that has a C
+//! compiler.
+//!
+//! The generated code is meant to be read. Structs become C structs, variables become
+//! locals and control flow is written out as the equivalent C statements. Extern functions
+//! become prototypes for their link names, and functions that are not public are static.
+//! A function named main with no parameters is called from the C entry point. Only values
+//! that C can hold directly are supported so far, which are booleans, characters, integers
+//! of up to 64 bits, floats and structs of those.
+//!
+//! Integer arithmetic is checked, so overflow and division by zero abort the program with the
+//! same message that the interpreter gives. This relies on the overflow builtins of GCC and
+//! Clang.
+pub mod checked;
+pub mod emit;
+pub mod names;
@@ -0,0 +1,147 @@
+//! Mangling of haikulang names into C identifiers.
+use std::fmt::Write;
+
+/// The prefix given to names that cannot be used as they are.
+pub const PREFIX: &str = "hk_";
+
+// Keywords from every C standard up to C23, along with the names that the generated code
+// relies on from the headers that it includes.
+const RESERVED: &[&str] = &[
+    "_Alignas",
+    "_Alignof",
+    "_Atomic",
+    "_BitInt",
+    "_Bool",
+    "_Complex",
+    "_Decimal128",
+    "_Decimal32",
+    "_Decimal64",
+    "_Generic",
+    "_Imaginary",
+    "_Noreturn",
+    "_Static_assert",
+    "_Thread_local",
+    "abort",
+    "alignas",
+    "alignof",
+    "auto",
+    "bool",
+    "break",
+    "case",
+    "char",
+    "const",
+    "constexpr",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extern",
+    "false",
+    "float",
+    "for",
+    "fprintf",
+    "fputs",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "int16_t",
+    "int32_t",
+    "int64_t",
+    "int8_t",
+    "intptr_t",
+    "long",
+    "main",
+    "nullptr",
+    "printf",
+    "register",
+    "restrict",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "static_assert",
+    "stderr",
+    "struct",
+    "switch",
+    "thread_local",
+    "true",
+    "typedef",
+    "typeof",
+    "typeof_unqual",
+    "uint16_t",
+    "uint32_t",
+    "uint64_t",
+    "uint8_t",
+    "uintptr_t",
+    "union",
+    "unsigned",
+    "void",
+    "volatile",
+    "while",
+];
+
+/// Turn a haikulang identifier into a C identifier.
+///
+/// Names are kept as they are where possible, so that the generated code stays readable.
+/// Anything that clashes with a C keyword, with a name that C reserves, or with a name the
+/// generated code uses is given a prefix instead. Names that already start with the prefix
+/// get another one, so two different names can never be mangled into the same identifier.
+/// Characters outside of ASCII are written as universal character names.
+pub fn mangle(name: &str) -> String {
+    let reserved = RESERVED.binary_search(&name).is_ok()
+        || name.starts_with(PREFIX)
+        || name.starts_with("__")
+        || (name.starts_with('_') && name[1..].starts_with(|c: char| c.is_ascii_uppercase()))
+        // Macros such as INT32_C and INT32_MAX come from the integer headers.
+        || name.starts_with("INT")
+        || name.starts_with("UINT")
+        || name.starts_with("PRI");
+
+    let mut mangled = String::new();
+    if reserved {
+        mangled.push_str(PREFIX);
+    }
+    for c in name.chars() {
+        match c as u32 {
+            0..0x80 => mangled.push(c),
+            code @ 0x80..0x10000 => write!(mangled, "\\u{:04X}", code).unwrap(),
+            code => write!(mangled, "\\U{:08X}", code).unwrap(),
+        }
+    }
+    mangled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test]
+    fn reserved_names_are_sorted() {
+        // Then
+        assert!(RESERVED.is_sorted());
+    }
+
+    #[test_case("total",     "total"         ; "ordinary name")]
+    #[test_case("int",       "hk_int"        ; "keyword")]
+    #[test_case("main",      "hk_main"       ; "entry point")]
+    #[test_case("printf",    "hk_printf"     ; "used by generated code")]
+    #[test_case("hk_int",    "hk_hk_int"     ; "already prefixed")]
+    #[test_case("__x",       "hk___x"        ; "double underscore")]
+    #[test_case("_Foo",      "hk__Foo"       ; "underscore and capital")]
+    #[test_case("_foo",      "_foo"          ; "underscore and lowercase")]
+    #[test_case("INT8_MAX",  "hk_INT8_MAX"   ; "integer macro")]
+    #[test_case("café",      "caf\\u00E9"    ; "non-ascii")]
+    #[test_case("x😀",      "x\\U0001F600"  ; "outside the basic plane")]
+    fn names_are_mangled(name: &str, expected: &str) {
+        // When
+        let mangled = mangle(name);
+
+        // Then
+        assert_eq!(mangled, expected);
+    }
+}
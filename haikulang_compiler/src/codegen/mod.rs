@@ -0,0 +1,3 @@
+//! Backends that turn type checked HIR into code that can run without the interpreter.
+pub mod c;
+pub mod wasm;
@@ -0,0 +1,1308 @@
+//! Lowering of type checked HIR into WebAssembly functions.
+use crate::codegen::wasm::module::{FuncType, Function, Import, Instruction, ValType, WasmModule};
+use crate::db::database::{Database, FileId, FunctionId};
+use crate::db::item_tree::ItemKind;
+use crate::error::CompilerError;
+use crate::hir::arena::ArenaMap;
+use crate::hir::context::HirModuleContext;
+use crate::hir::nodes::*;
+use crate::hir::ty::HirType;
+use crate::hir::typeck::HirTypeckResult;
+use haikulang_parser::span::{Span, Spanned};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+const TARGET: &str = "WebAssembly";
+
+/// The module that extern functions are imported from.
+pub const IMPORT_MODULE: &str = "env";
+
+/// Compile every function in a file into a WebAssembly module.
+///
+/// Extern functions become imports from [`IMPORT_MODULE`], followed by one function per
+/// declared function. Public functions and `main` are exported. Each value is lowered to
+/// the WebAssembly value type that holds it, and the errors returned here name any types or
+/// expressions that have no such lowering. Checking needs to have passed beforehand, as the
+/// value types are taken from the types that it inferred.
+pub fn compile_module(
+    db: &mut Database,
+    file: FileId,
+) -> Result<WasmModule, Vec<Spanned<CompilerError>>> {
+    let item_tree = db.item_tree(file);
+    let mut functions = Vec::new();
+    for item in item_tree.functions() {
+        let id = FunctionId::new(file, &item.name);
+        if let (Some(function), Some(types)) = (db.lower_function(&id), db.type_of(&id)) {
+            functions.push((function, types));
+        }
+    }
+
+    let context = db.module_context(file);
+    let mut module = WasmModule::new();
+    let mut errors = Vec::new();
+
+    for item in &item_tree.items {
+        if item.kind != ItemKind::ExternFunction {
+            continue;
+        }
+        let Some(header) = context.lookup_function_by_name(&item.name) else {
+            continue;
+        };
+        if let Some(ty) = extern_type(context, header, &mut errors) {
+            module.imports.push(Import {
+                module: IMPORT_MODULE.to_string(),
+                name: header
+                    .attributes
+                    .link_name
+                    .clone()
+                    .unwrap_or_else(|| item.name.clone()),
+                local_name: item.name.clone(),
+                ty,
+            });
+        }
+    }
+
+    // WebAssembly numbers imports before defined functions, and a call instruction names its
+    // callee by that number, so the whole index space is laid out before lowering any body.
+    let indices: HashMap<String, u32> = module
+        .imports
+        .iter()
+        .map(|import| import.local_name.clone())
+        .chain(
+            functions
+                .iter()
+                .map(|(function, _)| context.get_string(function.header.name).clone()),
+        )
+        .zip(0..)
+        .collect();
+
+    for (function, types) in &functions {
+        let lowerer = FunctionLowerer::new(context, function, types, &indices);
+        match lowerer.lower() {
+            Ok(function) => module.functions.push(function),
+            Err(function_errors) => errors.extend(function_errors),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(module)
+    } else {
+        Err(errors)
+    }
+}
+
+fn extern_type(
+    context: &HirModuleContext,
+    header: &HirFunctionHeader,
+    errors: &mut Vec<Spanned<CompilerError>>,
+) -> Option<FuncType> {
+    let resolve = |type_ref: &HirTypeRef| {
+        let ty = context.resolve_type(type_ref).unwrap_or(HirType::Unknown);
+        value_type(&ty).map_err(|feature| unsupported(feature, type_ref.span))
+    };
+
+    let mut parameters = Vec::new();
+    for parameter in &header.parameters {
+        match resolve(&parameter.type_ref) {
+            Ok(Some(ty)) => parameters.push(ty),
+            Ok(None) => errors.push(unsupported("parameters of the unit type", parameter.span)),
+            Err(error) => errors.push(error),
+        }
+    }
+
+    let results = match header.return_type.as_ref().map(resolve) {
+        Some(Ok(result)) => result.into_iter().collect(),
+        Some(Err(error)) => {
+            errors.push(error);
+            return None;
+        }
+        None => Vec::new(),
+    };
+
+    if parameters.len() != header.parameters.len() {
+        return None;
+    }
+    Some(FuncType {
+        parameters,
+        results,
+    })
+}
+
+// The blocks that branches can refer to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Label {
+    Break,
+    Continue,
+    Other,
+}
+
+struct FunctionLowerer<'a> {
+    module: &'a HirModuleContext,
+    function: &'a HirFunctionData,
+    types: &'a HirTypeckResult,
+    indices: &'a HashMap<String, u32>,
+    locals: ArenaMap<HirVariableId, u32>,
+    names: HashMap<String, usize>,
+    parameter_names: Vec<String>,
+    extra_locals: Vec<(String, ValType)>,
+    body: Vec<Instruction>,
+    labels: Vec<Label>,
+    errors: Vec<Spanned<CompilerError>>,
+}
+
+impl<'a> FunctionLowerer<'a> {
+    fn new(
+        module: &'a HirModuleContext,
+        function: &'a Rc<HirFunctionData>,
+        types: &'a Rc<HirTypeckResult>,
+        indices: &'a HashMap<String, u32>,
+    ) -> Self {
+        Self {
+            module,
+            function,
+            types,
+            indices,
+            locals: ArenaMap::new(),
+            names: HashMap::new(),
+            parameter_names: Vec::new(),
+            extra_locals: Vec::new(),
+            body: Vec::new(),
+            labels: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    fn lower(mut self) -> Result<Function, Vec<Spanned<CompilerError>>> {
+        let header = &self.function.header;
+        let name = self.module.get_string(header.name).clone();
+        let signature = &self.types.signature;
+
+        let mut parameters = Vec::new();
+        for (variable, ty) in self.function.parameters.iter().zip(&signature.parameters) {
+            let location = self.function.get_variable(*variable).location;
+            match value_type(ty) {
+                Ok(Some(ty)) => {
+                    let name = self.unique_name(*variable);
+                    self.locals.insert(*variable, parameters.len() as u32);
+                    self.parameter_names.push(name);
+                    parameters.push(ty);
+                }
+                Ok(None) => self.unsupported("parameters of the unit type", location),
+                Err(feature) => self.unsupported(feature, location),
+            }
+        }
+
+        let results = match value_type(&signature.return_type) {
+            Ok(result) => result.into_iter().collect(),
+            Err(feature) => {
+                let span = header
+                    .return_type
+                    .as_ref()
+                    .map_or(header.span, |ty| ty.span);
+                self.unsupported(feature, span);
+                Vec::new()
+            }
+        };
+
+        self.statement(self.function.root_statement);
+
+        // Falling off the end of a function that returns a value is a bug that the type
+        // checker does not catch yet, so we trap if that happens.
+        if !results.is_empty() && self.body.last() != Some(&Instruction::Return) {
+            self.body.push(Instruction::Unreachable);
+        }
+
+        if !self.errors.is_empty() {
+            return Err(self.errors);
+        }
+
+        // Hosts need an entry point to call, whether or not main was marked as public.
+        let export = header.is_pub || name == "main";
+        Ok(Function {
+            name,
+            ty: FuncType {
+                parameters,
+                results,
+            },
+            parameter_names: self.parameter_names,
+            locals: self.extra_locals,
+            body: self.body,
+            export,
+        })
+    }
+
+    fn statement(&mut self, id: HirStatementId) {
+        let statement = self.function.get_statement(id);
+        let span = statement.span;
+
+        match &statement.kind {
+            HirStatementKind::Empty => {}
+            HirStatementKind::VarDecl { variable, expr } => {
+                // The variable is declared first, so that a value that cannot be held in it
+                // is only reported once.
+                let errors = self.errors.len();
+                let local = self.local(*variable);
+                if let Some(expr) = expr
+                    && self.errors.len() == errors
+                {
+                    self.expr(*expr);
+                    if let Some(local) = local {
+                        self.body.push(Instruction::LocalSet(local));
+                    }
+                }
+            }
+            HirStatementKind::Destructure { .. } => self.unsupported("destructuring", span),
+            HirStatementKind::Expr(expr) => {
+                self.expr(*expr);
+                if let Ok(Some(_)) = value_type(self.types.type_of_expr(*expr)) {
+                    self.body.push(Instruction::Drop);
+                }
+            }
+            HirStatementKind::Return(expr) => {
+                if let Some(expr) = expr {
+                    self.expr(*expr);
+                }
+                self.body.push(Instruction::Return);
+            }
+            HirStatementKind::Continue => self.branch(Label::Continue, span),
+            HirStatementKind::Break => self.branch(Label::Break, span),
+            HirStatementKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.expr(*condition);
+                self.open(Instruction::If(None), Label::Other);
+                self.statement(*then);
+                if let Some(otherwise) = otherwise {
+                    self.body.push(Instruction::Else);
+                    self.statement(*otherwise);
+                }
+                self.close();
+            }
+            HirStatementKind::While { condition, body } => {
+                // Breaking leaves the outer block, and continuing goes back to the start of
+                // the loop where the condition is checked again.
+                self.open(Instruction::Block, Label::Break);
+                self.open(Instruction::Loop, Label::Continue);
+                self.expr(*condition);
+                self.body
+                    .push(Instruction::Numeric(ValType::I32, "eqz", 0x45));
+                self.body.push(Instruction::BrIf(1));
+                self.statement(*body);
+                self.body.push(Instruction::Br(0));
+                self.close();
+                self.close();
+            }
+            HirStatementKind::Block(statements) => {
+                for statement in statements {
+                    self.statement(*statement);
+                }
+            }
+        }
+    }
+
+    fn expr(&mut self, id: HirExprId) {
+        let expr = self.function.get_expr(id);
+        let span = expr.span;
+        let ty = self.types.type_of_expr(id);
+
+        match &expr.kind {
+            HirExprKind::LoadLiteral(literal) => self.literal(&literal.kind, ty, span),
+            HirExprKind::LoadVariable(variable) => {
+                if let Some(local) = self.local(*variable) {
+                    self.body.push(Instruction::LocalGet(local));
+                }
+            }
+            HirExprKind::LoadFunction(_) => self.unsupported("function values", span),
+            HirExprKind::LoadStruct(_) => self.unsupported("structs", span),
+            HirExprKind::LoadBuiltin(_) => self.unsupported("builtin function values", span),
+            HirExprKind::BinaryOp {
+                left,
+                op: HirExprBinaryOp::BoolAnd,
+                right,
+            } => {
+                self.expr(*left);
+                self.open(Instruction::If(Some(ValType::I32)), Label::Other);
+                self.expr(*right);
+                self.body.push(Instruction::Else);
+                self.body.push(Instruction::I32Const(0));
+                self.close();
+            }
+            HirExprKind::BinaryOp {
+                left,
+                op: HirExprBinaryOp::BoolOr,
+                right,
+            } => {
+                self.expr(*left);
+                self.open(Instruction::If(Some(ValType::I32)), Label::Other);
+                self.body.push(Instruction::I32Const(1));
+                self.body.push(Instruction::Else);
+                self.expr(*right);
+                self.close();
+            }
+            HirExprKind::BinaryOp { left, op, right } => {
+                self.expr(*left);
+                self.expr(*right);
+                self.binary_op(*op, *left, *right, ty, span);
+            }
+            HirExprKind::UnaryOp { op, value } => self.unary_op(*op, *value, ty, span),
+            HirExprKind::Assign { target, op, value } => {
+                let HirExprKind::LoadVariable(variable) = &self.function.get_expr(*target).kind
+                else {
+                    self.unsupported("assignments to anything but variables", span);
+                    return;
+                };
+                let Some(local) = self.local(*variable) else {
+                    return;
+                };
+
+                if let Some(op) = op {
+                    let target_type = self.types.type_of_expr(*target);
+                    self.body.push(Instruction::LocalGet(local));
+                    self.expr(*value);
+                    self.binary_op(*op, *target, *value, target_type, span);
+                } else {
+                    self.expr(*value);
+                }
+                self.body.push(Instruction::LocalSet(local));
+            }
+            HirExprKind::MemberAccess { .. } => self.unsupported("member access", span),
+            HirExprKind::Index { .. } => self.unsupported("indexing", span),
+            HirExprKind::Call { callee, arguments } => self.call(*callee, arguments, span),
+            HirExprKind::Closure(_) => self.unsupported("closures", span),
+            HirExprKind::Tuple(_) => self.unsupported("tuples", span),
+            HirExprKind::Format(_) => self.unsupported("format strings", span),
+            HirExprKind::Unresolved(_) => self.unsupported("calls to other modules", span),
+        }
+    }
+
+    fn literal(&mut self, literal: &HirLiteralKind, ty: &HirType, span: Span) {
+        let value_type = match value_type(ty) {
+            Ok(Some(value_type)) => value_type,
+            Ok(None) => return,
+            Err(feature) => return self.unsupported(feature, span),
+        };
+
+        let instruction = match literal {
+            HirLiteralKind::Bool(value) => Instruction::I32Const(*value as i32),
+            HirLiteralKind::I8(value) => Instruction::I32Const(*value as i32),
+            HirLiteralKind::I16(value) => Instruction::I32Const(*value as i32),
+            HirLiteralKind::I32(value) => Instruction::I32Const(*value),
+            HirLiteralKind::I64(value) => Instruction::I64Const(*value),
+            HirLiteralKind::ISize(value) => Instruction::I32Const(*value as i32),
+            HirLiteralKind::U8(value) => Instruction::I32Const(*value as i32),
+            HirLiteralKind::U16(value) => Instruction::I32Const(*value as i32),
+            HirLiteralKind::U32(value) => Instruction::I32Const(*value as i32),
+            HirLiteralKind::U64(value) => Instruction::I64Const(*value as i64),
+            HirLiteralKind::USize(value) => Instruction::I32Const(*value as i32),
+            HirLiteralKind::F32(value) => Instruction::F32Const(*value),
+            HirLiteralKind::F64(value) => Instruction::F64Const(*value),
+            HirLiteralKind::Char(value) => Instruction::I32Const(*value as i32),
+            HirLiteralKind::Int(value) => {
+                // The type checker has already made sure that the literal fits in its type,
+                // so only the lowest bits matter.
+                let bits = value.iter_u64_digits().next().unwrap_or(0);
+                match value_type {
+                    ValType::I32 => Instruction::I32Const(bits as i32),
+                    ValType::I64 => Instruction::I64Const(bits as i64),
+                    ValType::F32 => Instruction::F32Const(bits as f32),
+                    ValType::F64 => Instruction::F64Const(bits as f64),
+                }
+            }
+            // Anything else has a type that value_type rejects.
+            HirLiteralKind::I128(_)
+            | HirLiteralKind::U128(_)
+            | HirLiteralKind::String(_)
+            | HirLiteralKind::Bytes(_) => unreachable!("{:?} has no value type", literal),
+        };
+        self.body.push(instruction);
+    }
+
+    // Apply an operator to the two operands on top of the stack.
+    fn binary_op(
+        &mut self,
+        op: HirExprBinaryOp,
+        left: HirExprId,
+        right: HirExprId,
+        result: &HirType,
+        span: Span,
+    ) {
+        let left_type = self.types.type_of_expr(left);
+        let Ok(Some(operand)) = value_type(left_type) else {
+            return self.unsupported(format!("operators on values of type {}", left_type), span);
+        };
+
+        // Shift amounts can be of any integer type, so they are converted to the type of
+        // the value being shifted.
+        if matches!(op, HirExprBinaryOp::BinaryShl | HirExprBinaryOp::BinaryShr) {
+            match (operand, value_type(self.types.type_of_expr(right))) {
+                (ValType::I32, Ok(Some(ValType::I64))) => {
+                    self.body
+                        .push(Instruction::Numeric(ValType::I32, "wrap_i64", 0xa7))
+                }
+                (ValType::I64, Ok(Some(ValType::I32))) => {
+                    self.body
+                        .push(Instruction::Numeric(ValType::I64, "extend_i32_u", 0xad))
+                }
+                _ => {}
+            }
+        }
+
+        if self.checked_arithmetic(op, left_type) {
+            return;
+        }
+        if matches!(op, HirExprBinaryOp::Div | HirExprBinaryOp::Mod) {
+            self.check_division(left_type, operand);
+        }
+
+        match binary_instruction(op, operand, left_type.is_signed_integer()) {
+            Some(instruction) => {
+                self.body.push(instruction);
+                self.wrap(result);
+            }
+            None => self.unsupported(
+                format!("operator {:?} on values of type {}", op, left_type),
+                span,
+            ),
+        }
+    }
+
+    fn unary_op(&mut self, op: HirExprUnaryOp, value: HirExprId, ty: &HirType, span: Span) {
+        let Ok(Some(operand)) = value_type(ty) else {
+            return self.unsupported(format!("operators on values of type {}", ty), span);
+        };
+
+        match (op, operand) {
+            // Negative literals are written as they are, as negating the largest literal
+            // that fits in a type would overflow it.
+            (HirExprUnaryOp::Negate, ValType::I32 | ValType::I64)
+                if let HirExprKind::LoadLiteral(literal) = &self.function.get_expr(value).kind =>
+            {
+                self.literal(&literal.kind, ty, span);
+                match self.body.last_mut() {
+                    Some(Instruction::I32Const(value)) => *value = value.wrapping_neg(),
+                    Some(Instruction::I64Const(value)) => *value = value.wrapping_neg(),
+                    _ => {}
+                }
+            }
+            (HirExprUnaryOp::Negate, ValType::I32 | ValType::I64) => {
+                let zero = match operand {
+                    ValType::I32 => Instruction::I32Const(0),
+                    _ => Instruction::I64Const(0),
+                };
+                self.body.push(zero);
+                self.expr(value);
+                if !self.checked_arithmetic(HirExprBinaryOp::Sub, ty) {
+                    self.body.push(
+                        binary_instruction(HirExprBinaryOp::Sub, operand, false)
+                            .expect("integers can be subtracted"),
+                    );
+                }
+            }
+            (HirExprUnaryOp::Negate, ValType::F32) => {
+                self.expr(value);
+                self.body
+                    .push(Instruction::Numeric(ValType::F32, "neg", 0x8c));
+            }
+            (HirExprUnaryOp::Negate, ValType::F64) => {
+                self.expr(value);
+                self.body
+                    .push(Instruction::Numeric(ValType::F64, "neg", 0x9a));
+            }
+            (HirExprUnaryOp::Not, _) => {
+                self.expr(value);
+                self.body
+                    .push(Instruction::Numeric(ValType::I32, "eqz", 0x45));
+            }
+            (HirExprUnaryOp::Invert, ValType::I32) => {
+                self.expr(value);
+                self.body.push(Instruction::I32Const(-1));
+                self.body
+                    .push(Instruction::Numeric(ValType::I32, "xor", 0x73));
+            }
+            (HirExprUnaryOp::Invert, _) => {
+                self.expr(value);
+                self.body.push(Instruction::I64Const(-1));
+                self.body
+                    .push(Instruction::Numeric(ValType::I64, "xor", 0x85));
+            }
+        }
+        self.wrap(ty);
+    }
+
+    fn call(&mut self, callee: HirExprId, arguments: &[HirExprId], span: Span) {
+        match &self.function.get_expr(callee).kind {
+            HirExprKind::LoadFunction(name) => {
+                for argument in arguments {
+                    self.expr(*argument);
+                }
+                let index = self.indices[self.module.get_string(*name)];
+                self.body.push(Instruction::Call(index));
+            }
+            // Failed assertions trap, as there is nowhere to report them to.
+            HirExprKind::LoadBuiltin(HirBuiltin::Assert) => {
+                self.expr(arguments[0]);
+                self.body
+                    .push(Instruction::Numeric(ValType::I32, "eqz", 0x45));
+                self.trap_if();
+            }
+            HirExprKind::LoadBuiltin(HirBuiltin::AssertEq) => {
+                self.expr(arguments[0]);
+                self.expr(arguments[1]);
+                let ty = self.types.type_of_expr(arguments[0]);
+                match value_type(ty) {
+                    Ok(Some(operand)) => {
+                        let not_equal = binary_instruction(HirExprBinaryOp::NotEq, operand, false)
+                            .expect("every value type can be compared");
+                        self.body.push(not_equal);
+                        self.trap_if();
+                    }
+                    _ => self.unsupported(format!("comparing values of type {}", ty), span),
+                }
+            }
+            HirExprKind::LoadBuiltin(_) => self.unsupported("printing and formatting", span),
+            HirExprKind::LoadStruct(_) => self.unsupported("structs", span),
+            HirExprKind::Unresolved(_) => self.unsupported("calls to other modules", span),
+            _ => self.unsupported("calls to closures and function values", span),
+        }
+    }
+
+    // Apply an arithmetic operator to the two integers on top of the stack, trapping when the
+    // result does not fit in their type, as the interpreter stops the program then. Returns
+    // false without doing anything for other operators and types.
+    fn checked_arithmetic(&mut self, op: HirExprBinaryOp, ty: &HirType) -> bool {
+        if !matches!(
+            op,
+            HirExprBinaryOp::Add | HirExprBinaryOp::Sub | HirExprBinaryOp::Mul
+        ) {
+            return false;
+        }
+        let Some((min, max)) = integer_range(ty) else {
+            return false;
+        };
+        let signed = ty.is_signed_integer();
+        let instruction = |ty| binary_instruction(op, ty, signed).expect("integer arithmetic");
+
+        match ty {
+            // Integers narrower than 32 bits cannot overflow an i32, so the result only has to
+            // be checked against the range of their type.
+            HirType::I8 | HirType::I16 | HirType::U8 | HirType::U16 => {
+                self.body.push(instruction(ValType::I32));
+                self.check_range(ValType::I32, min, max, signed);
+            }
+            // Neither can 32-bit integers overflow an i64.
+            HirType::I32 | HirType::ISize | HirType::U32 | HirType::USize => {
+                let extend = if signed {
+                    Instruction::Numeric(ValType::I64, "extend_i32_s", 0xac)
+                } else {
+                    Instruction::Numeric(ValType::I64, "extend_i32_u", 0xad)
+                };
+                let right = self.scratch("right", ValType::I64);
+                self.body.push(extend.clone());
+                self.body.push(Instruction::LocalSet(right));
+                self.body.push(extend);
+                self.body.push(Instruction::LocalGet(right));
+                self.body.push(instruction(ValType::I64));
+                self.check_range(ValType::I64, min, max, signed);
+                self.body
+                    .push(Instruction::Numeric(ValType::I32, "wrap_i64", 0xa7));
+            }
+            // There is nothing wider than 64 bits, so overflow is detected from the operands
+            // and the wrapped result instead.
+            _ => {
+                let left = self.scratch("left", ValType::I64);
+                let right = self.scratch("right", ValType::I64);
+                let result = self.scratch("result", ValType::I64);
+                self.body.push(Instruction::LocalSet(right));
+                self.body.push(Instruction::LocalSet(left));
+                self.body.push(Instruction::LocalGet(left));
+                self.body.push(Instruction::LocalGet(right));
+                self.body.push(instruction(ValType::I64));
+                self.body.push(Instruction::LocalSet(result));
+                self.check_64_bit_overflow(op, signed, left, right, result);
+                self.body.push(Instruction::LocalGet(result));
+            }
+        }
+        true
+    }
+
+    // Trap if the wrapped result of a 64-bit operation is not the true result.
+    fn check_64_bit_overflow(
+        &mut self,
+        op: HirExprBinaryOp,
+        signed: bool,
+        left: u32,
+        right: u32,
+        result: u32,
+    ) {
+        let i64 = |op, signed| binary_instruction(op, ValType::I64, signed).expect("i64 operator");
+        match (op, signed) {
+            // Adding two numbers of the same sign overflowed if the result has the other sign,
+            // and so did subtracting numbers of different signs.
+            (HirExprBinaryOp::Add | HirExprBinaryOp::Sub, true) => {
+                let (first, second) = if op == HirExprBinaryOp::Add {
+                    ((left, result), (right, result))
+                } else {
+                    ((left, right), (left, result))
+                };
+                for (a, b) in [first, second] {
+                    self.body.push(Instruction::LocalGet(a));
+                    self.body.push(Instruction::LocalGet(b));
+                    self.body.push(i64(HirExprBinaryOp::BinaryXor, true));
+                }
+                self.body.push(i64(HirExprBinaryOp::BinaryAnd, true));
+                self.body.push(Instruction::I64Const(0));
+                self.body.push(i64(HirExprBinaryOp::Less, true));
+            }
+            (HirExprBinaryOp::Add, false) => {
+                self.body.push(Instruction::LocalGet(result));
+                self.body.push(Instruction::LocalGet(left));
+                self.body.push(i64(HirExprBinaryOp::Less, false));
+            }
+            (HirExprBinaryOp::Sub, false) => {
+                self.body.push(Instruction::LocalGet(left));
+                self.body.push(Instruction::LocalGet(right));
+                self.body.push(i64(HirExprBinaryOp::Less, false));
+            }
+            // A product overflowed if dividing it by one factor does not give the other. The
+            // division itself traps for the one signed product that it cannot undo.
+            _ => {
+                self.body.push(Instruction::LocalGet(left));
+                self.body.push(Instruction::I64Const(0));
+                self.body.push(i64(HirExprBinaryOp::NotEq, signed));
+                self.open(Instruction::If(None), Label::Other);
+                self.body.push(Instruction::LocalGet(result));
+                self.body.push(Instruction::LocalGet(left));
+                self.body.push(i64(HirExprBinaryOp::Div, signed));
+                self.body.push(Instruction::LocalGet(right));
+                self.body.push(i64(HirExprBinaryOp::NotEq, signed));
+                self.trap_if();
+                self.close();
+                return;
+            }
+        }
+        self.trap_if();
+    }
+
+    // Trap unless the value on top of the stack lies within the given range, leaving it there.
+    fn check_range(&mut self, ty: ValType, min: i64, max: i64, signed: bool) {
+        let constant = |value: i64| match ty {
+            ValType::I32 => Instruction::I32Const(value as i32),
+            _ => Instruction::I64Const(value),
+        };
+        let compare = |op| binary_instruction(op, ty, signed).expect("integer comparison");
+
+        let result = self.scratch("result", ty);
+        self.body.push(Instruction::LocalSet(result));
+        self.body.push(Instruction::LocalGet(result));
+        self.body.push(constant(max));
+        self.body.push(compare(HirExprBinaryOp::Greater));
+        if signed {
+            self.body.push(Instruction::LocalGet(result));
+            self.body.push(constant(min));
+            self.body.push(compare(HirExprBinaryOp::Less));
+            self.body
+                .push(binary_instruction(HirExprBinaryOp::BinaryOr, ValType::I32, false).unwrap());
+        }
+        self.trap_if();
+        self.body.push(Instruction::LocalGet(result));
+    }
+
+    // Trap before dividing the two integers on top of the stack if the quotient of the
+    // smallest value and -1 would not fit in their type. WebAssembly already traps on
+    // division by zero, but not on this for every type.
+    fn check_division(&mut self, ty: &HirType, operand: ValType) {
+        let (Some((min, _)), true) = (integer_range(ty), ty.is_signed_integer()) else {
+            return;
+        };
+        let constant = |value: i64| match operand {
+            ValType::I32 => Instruction::I32Const(value as i32),
+            _ => Instruction::I64Const(value),
+        };
+        let equal = binary_instruction(HirExprBinaryOp::Eq, operand, true).unwrap();
+
+        let left = self.scratch("left", operand);
+        let right = self.scratch("right", operand);
+        self.body.push(Instruction::LocalSet(right));
+        self.body.push(Instruction::LocalSet(left));
+        self.body.push(Instruction::LocalGet(left));
+        self.body.push(constant(min));
+        self.body.push(equal.clone());
+        self.body.push(Instruction::LocalGet(right));
+        self.body.push(constant(-1));
+        self.body.push(equal);
+        self.body
+            .push(binary_instruction(HirExprBinaryOp::BinaryAnd, ValType::I32, false).unwrap());
+        self.trap_if();
+        self.body.push(Instruction::LocalGet(left));
+        self.body.push(Instruction::LocalGet(right));
+    }
+
+    // A local for holding intermediate values, which is shared by everything in the function
+    // that needs one with the same name and type. Variable names cannot contain a dot
+    // followed by a letter, so these never clash with them.
+    fn scratch(&mut self, name: &str, ty: ValType) -> u32 {
+        let name = format!("{}.{}", name, ty);
+        let index = match self.extra_locals.iter().position(|local| local.0 == name) {
+            Some(index) => index,
+            None => {
+                self.extra_locals.push((name, ty));
+                self.extra_locals.len() - 1
+            }
+        };
+        (self.parameter_names.len() + index) as u32
+    }
+
+    fn trap_if(&mut self) {
+        self.open(Instruction::If(None), Label::Other);
+        self.body.push(Instruction::Unreachable);
+        self.close();
+    }
+
+    // Integers narrower than 32 bits are held in an i32, so after operators that are not
+    // checked, such as shifts, they have to be brought back into range.
+    fn wrap(&mut self, ty: &HirType) {
+        match ty {
+            HirType::I8 => self
+                .body
+                .push(Instruction::Numeric(ValType::I32, "extend8_s", 0xc0)),
+            HirType::I16 => self
+                .body
+                .push(Instruction::Numeric(ValType::I32, "extend16_s", 0xc1)),
+            HirType::U8 | HirType::U16 => {
+                let mask = if *ty == HirType::U8 { 0xff } else { 0xffff };
+                self.body.push(Instruction::I32Const(mask));
+                self.body
+                    .push(Instruction::Numeric(ValType::I32, "and", 0x71));
+            }
+            _ => {}
+        }
+    }
+
+    fn branch(&mut self, label: Label, span: Span) {
+        match self.labels.iter().rev().position(|open| *open == label) {
+            Some(depth) => self.body.push(Instruction::Br(depth as u32)),
+            None => self.unsupported("break and continue outside of loops", span),
+        }
+    }
+
+    fn open(&mut self, instruction: Instruction, label: Label) {
+        self.body.push(instruction);
+        self.labels.push(label);
+    }
+
+    fn close(&mut self) {
+        self.body.push(Instruction::End);
+        self.labels.pop();
+    }
+
+    // The local holding a variable, which is declared the first time that it is used.
+    // Variables of the unit type have no local, as there is nothing to store.
+    fn local(&mut self, variable: HirVariableId) -> Option<u32> {
+        if let Some(local) = self.locals.get(variable) {
+            return Some(*local);
+        }
+
+        let location = self.function.get_variable(variable).location;
+        match value_type(self.types.type_of_variable(variable)) {
+            Ok(Some(ty)) => {
+                let local = (self.parameter_names.len() + self.extra_locals.len()) as u32;
+                let name = self.unique_name(variable);
+                self.extra_locals.push((name, ty));
+                self.locals.insert(variable, local);
+                Some(local)
+            }
+            Ok(None) => None,
+            Err(feature) => {
+                self.unsupported(feature, location);
+                None
+            }
+        }
+    }
+
+    // Variables in different scopes can share a name, but locals cannot.
+    fn unique_name(&mut self, variable: HirVariableId) -> String {
+        let name = self
+            .module
+            .get_string(self.function.get_variable(variable).name);
+        let count = self.names.entry(name.clone()).or_insert(0);
+        *count += 1;
+        if *count == 1 {
+            name.clone()
+        } else {
+            format!("{}.{}", name, *count - 1)
+        }
+    }
+
+    fn unsupported(&mut self, feature: impl Into<String>, span: Span) {
+        self.errors.push(unsupported(feature, span));
+    }
+}
+
+fn unsupported(feature: impl Into<String>, span: Span) -> Spanned<CompilerError> {
+    Spanned::new(
+        CompilerError::UnsupportedByTarget {
+            feature: feature.into(),
+            target: TARGET.to_string(),
+        },
+        span,
+    )
+}
+
+// The smallest and largest values of an integer type that WebAssembly can hold. The
+// largest value of u64 does not fit in an i64, but it is never needed.
+fn integer_range(ty: &HirType) -> Option<(i64, i64)> {
+    match ty {
+        HirType::I8 => Some((i8::MIN.into(), i8::MAX.into())),
+        HirType::I16 => Some((i16::MIN.into(), i16::MAX.into())),
+        HirType::I32 | HirType::ISize => Some((i32::MIN.into(), i32::MAX.into())),
+        HirType::I64 => Some((i64::MIN, i64::MAX)),
+        HirType::U8 => Some((0, u8::MAX.into())),
+        HirType::U16 => Some((0, u16::MAX.into())),
+        HirType::U32 | HirType::USize => Some((0, u32::MAX.into())),
+        HirType::U64 => Some((0, i64::MAX)),
+        _ => None,
+    }
+}
+
+/// The WebAssembly type that values of a type are held in, or None for the unit type,
+/// which has no values to hold. Types that cannot be held in any of them yet are described
+/// in the error.
+///
+/// Pointers are 32 bits wide, so isize and usize are too.
+pub fn value_type(ty: &HirType) -> Result<Option<ValType>, String> {
+    match ty {
+        HirType::Unit => Ok(None),
+        HirType::Bool
+        | HirType::Char
+        | HirType::I8
+        | HirType::I16
+        | HirType::I32
+        | HirType::ISize
+        | HirType::U8
+        | HirType::U16
+        | HirType::U32
+        | HirType::USize => Ok(Some(ValType::I32)),
+        HirType::I64 | HirType::U64 => Ok(Some(ValType::I64)),
+        HirType::F32 => Ok(Some(ValType::F32)),
+        HirType::F64 => Ok(Some(ValType::F64)),
+        // There is no allocator in the generated modules to keep them in yet.
+        HirType::String => Err("strings".to_string()),
+        HirType::Bytes => Err("byte strings".to_string()),
+        // Big integers are only provided by the interpreter's runtime so far.
+        HirType::BigInt => Err("big integers".to_string()),
+        HirType::Unknown => Err("values of an unknown type".to_string()),
+        other => Err(format!("values of type {}", other)),
+    }
+}
+
+// The instruction applying an operator to two values of the given type.
+fn binary_instruction(op: HirExprBinaryOp, ty: ValType, signed: bool) -> Option<Instruction> {
+    // Each operator has the same name for both sizes of integer and of float, only with a
+    // different opcode.
+    let (name, opcodes) = match (ty, op) {
+        (ValType::I32 | ValType::I64, op) => match op {
+            HirExprBinaryOp::Add => ("add", (0x6a, 0x7c)),
+            HirExprBinaryOp::Sub => ("sub", (0x6b, 0x7d)),
+            HirExprBinaryOp::Mul => ("mul", (0x6c, 0x7e)),
+            HirExprBinaryOp::Div if signed => ("div_s", (0x6d, 0x7f)),
+            HirExprBinaryOp::Div => ("div_u", (0x6e, 0x80)),
+            HirExprBinaryOp::Mod if signed => ("rem_s", (0x6f, 0x81)),
+            HirExprBinaryOp::Mod => ("rem_u", (0x70, 0x82)),
+            HirExprBinaryOp::BinaryAnd => ("and", (0x71, 0x83)),
+            HirExprBinaryOp::BinaryOr => ("or", (0x72, 0x84)),
+            HirExprBinaryOp::BinaryXor => ("xor", (0x73, 0x85)),
+            HirExprBinaryOp::BinaryShl => ("shl", (0x74, 0x86)),
+            HirExprBinaryOp::BinaryShr if signed => ("shr_s", (0x75, 0x87)),
+            HirExprBinaryOp::BinaryShr => ("shr_u", (0x76, 0x88)),
+            HirExprBinaryOp::Eq => ("eq", (0x46, 0x51)),
+            HirExprBinaryOp::NotEq => ("ne", (0x47, 0x52)),
+            HirExprBinaryOp::Less if signed => ("lt_s", (0x48, 0x53)),
+            HirExprBinaryOp::Less => ("lt_u", (0x49, 0x54)),
+            HirExprBinaryOp::Greater if signed => ("gt_s", (0x4a, 0x55)),
+            HirExprBinaryOp::Greater => ("gt_u", (0x4b, 0x56)),
+            HirExprBinaryOp::LessEq if signed => ("le_s", (0x4c, 0x57)),
+            HirExprBinaryOp::LessEq => ("le_u", (0x4d, 0x58)),
+            HirExprBinaryOp::GreaterEq if signed => ("ge_s", (0x4e, 0x59)),
+            HirExprBinaryOp::GreaterEq => ("ge_u", (0x4f, 0x5a)),
+            HirExprBinaryOp::Pow | HirExprBinaryOp::BoolAnd | HirExprBinaryOp::BoolOr => {
+                return None;
+            }
+        },
+        (ValType::F32 | ValType::F64, op) => match op {
+            HirExprBinaryOp::Add => ("add", (0x92, 0xa0)),
+            HirExprBinaryOp::Sub => ("sub", (0x93, 0xa1)),
+            HirExprBinaryOp::Mul => ("mul", (0x94, 0xa2)),
+            HirExprBinaryOp::Div => ("div", (0x95, 0xa3)),
+            HirExprBinaryOp::Eq => ("eq", (0x5b, 0x61)),
+            HirExprBinaryOp::NotEq => ("ne", (0x5c, 0x62)),
+            HirExprBinaryOp::Less => ("lt", (0x5d, 0x63)),
+            HirExprBinaryOp::Greater => ("gt", (0x5e, 0x64)),
+            HirExprBinaryOp::LessEq => ("le", (0x5f, 0x65)),
+            HirExprBinaryOp::GreaterEq => ("ge", (0x60, 0x66)),
+            _ => return None,
+        },
+    };
+
+    let opcode = match ty {
+        ValType::I32 | ValType::F32 => opcodes.0,
+        ValType::I64 | ValType::F64 => opcodes.1,
+    };
+    Some(Instruction::Numeric(ty, name, opcode))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+    use wasmi::{Caller, Engine, Linker, Module, Store, WasmParams, WasmResults};
+
+    fn compile(source: &str) -> WasmModule {
+        let mut db = Database::new();
+        let file = db.add_file("main.hkl", source);
+        compile_module(&mut db, file).unwrap()
+    }
+
+    fn compile_errors(source: &str) -> Vec<String> {
+        let mut db = Database::new();
+        let file = db.add_file("main.hkl", source);
+        compile_module(&mut db, file)
+            .unwrap_err()
+            .iter()
+            .map(|error| error.value().to_string())
+            .collect()
+    }
+
+    // Run an exported function from a module, with an import that records what it is given.
+    fn run<Params: WasmParams, Results: WasmResults>(
+        binary: &[u8],
+        name: &str,
+        arguments: Params,
+    ) -> Result<(Results, Vec<i32>), wasmi::Error> {
+        let engine = Engine::default();
+        let module = Module::new(&engine, binary)?;
+        let mut store = Store::new(&engine, Vec::new());
+        let mut linker = <Linker<Vec<i32>>>::new(&engine);
+        linker.func_wrap(
+            IMPORT_MODULE,
+            "record",
+            |mut caller: Caller<'_, Vec<i32>>, value: i32| caller.data_mut().push(value),
+        )?;
+        let instance = linker.instantiate(&mut store, &module)?.start(&mut store)?;
+        let function = instance.get_typed_func::<Params, Results>(&store, name)?;
+        let results = function.call(&mut store, arguments)?;
+        Ok((results, store.into_data()))
+    }
+
+    #[test_case("a + b",      7,  3,   10 ; "add")]
+    #[test_case("a - b",      7,  3,    4 ; "subtract")]
+    #[test_case("a * b",      7,  3,   21 ; "multiply")]
+    #[test_case("a / b",     -7,  2,   -3 ; "divide")]
+    #[test_case("a % b",     -7,  2,   -1 ; "remainder")]
+    #[test_case("a & b",      6,  3,    2 ; "binary and")]
+    #[test_case("a | b",      6,  3,    7 ; "binary or")]
+    #[test_case("a ^ b",      6,  3,    5 ; "binary xor")]
+    #[test_case("a << b",     1,  4,   16 ; "shift left")]
+    #[test_case("a >> b",   -16,  2,   -4 ; "shift right")]
+    #[test_case("-a",         5,  0,   -5 ; "negate")]
+    #[test_case("~a",         5,  0,   -6 ; "invert")]
+    fn integer_operators_match_the_interpreter(expr: &str, a: i32, b: i32, expected: i32) {
+        // Given
+        let source = format!("pub fn f(a: i32, b: i32) -> i32 {{ return {}; }}", expr);
+        let module = compile(&source);
+
+        // When
+        let (result, _) = run::<(i32, i32), i32>(&module.to_binary(), "f", (a, b)).unwrap();
+
+        // Then
+        assert_eq!(result, expected);
+    }
+
+    #[test_case("a < b",  1, 2, 1 ; "less")]
+    #[test_case("a <= b", 2, 2, 1 ; "less or equal")]
+    #[test_case("a > b",  1, 2, 0 ; "greater")]
+    #[test_case("a >= b", 1, 2, 0 ; "greater or equal")]
+    #[test_case("a == b", 2, 2, 1 ; "equal")]
+    #[test_case("a != b", 2, 2, 0 ; "not equal")]
+    fn comparisons_produce_bools(expr: &str, a: i32, b: i32, expected: i32) {
+        // Given
+        let source = format!("pub fn f(a: i32, b: i32) -> bool {{ return {}; }}", expr);
+        let module = compile(&source);
+
+        // When
+        let (result, _) = run::<(i32, i32), i32>(&module.to_binary(), "f", (a, b)).unwrap();
+
+        // Then
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn unsigned_comparisons_treat_the_top_bit_as_a_value() {
+        // Given
+        let module = compile("pub fn f(a: u32, b: u32) -> bool { return a > b; }");
+
+        // When
+        let (result, _) = run::<(i32, i32), i32>(&module.to_binary(), "f", (-1, 1)).unwrap();
+
+        // Then
+        assert_eq!(result, 1);
+    }
+
+    #[test_case("i8",  "127",                  "1",                    "a + b"  ; "i8 add")]
+    #[test_case("u8",  "0",                    "1",                    "a - b"  ; "u8 sub")]
+    #[test_case("i16", "200",                  "200",                  "a * b"  ; "i16 mul")]
+    #[test_case("u16", "300",                  "300",                  "a * b"  ; "u16 mul")]
+    #[test_case("i32", "2147483647",           "1",                    "a + b"  ; "i32 add")]
+    #[test_case("u32", "0",                    "1",                    "a - b"  ; "u32 sub")]
+    #[test_case("i32", "65536",                "65536",                "a * b"  ; "i32 mul")]
+    #[test_case("i64", "9223372036854775807",  "1",                    "a + b"  ; "i64 add")]
+    #[test_case("i64", "-9223372036854775808", "1",                    "a - b"  ; "i64 sub")]
+    #[test_case("u64", "18446744073709551615", "1",                    "a + b"  ; "u64 add")]
+    #[test_case("u64", "0",                    "1",                    "a - b"  ; "u64 sub")]
+    #[test_case("u64", "4294967296",           "4294967296",           "a * b"  ; "u64 mul")]
+    #[test_case("i64", "-9223372036854775808", "-1",                   "a * b"  ; "i64 mul by minus one")]
+    #[test_case("i64", "-1",                   "-9223372036854775808", "a * b"  ; "i64 minus one mul")]
+    #[test_case("i8",  "-128",                 "-1",                   "a / b"  ; "i8 div")]
+    #[test_case("i32", "-2147483648",          "-1",                   "a % b"  ; "i32 rem")]
+    #[test_case("i32", "1",                    "0",                    "a / b"  ; "division by zero")]
+    #[test_case("i8",  "-128",                 "0",                    "-a"     ; "i8 negate")]
+    #[test_case("i64", "-9223372036854775808", "0",                    "-a"     ; "i64 negate")]
+    #[test_case("u8",  "255",                  "1",                    "a += b" ; "compound add")]
+    fn integer_overflow_traps(ty: &str, a: &str, b: &str, expr: &str) {
+        // Given
+        let source = format!(
+            "pub fn f() {{ let a: {0} = {1}; let b: {0} = {2}; {3}; }}",
+            ty, a, b, expr
+        );
+        let module = compile(&source);
+
+        // When
+        let result = run::<(), ()>(&module.to_binary(), "f", ());
+
+        // Then
+        assert!(result.is_err(), "{} did not trap", expr);
+    }
+
+    #[test_case("i8",  "127",                  "-128",       "a + b", "-1"                   ; "i8 add")]
+    #[test_case("u8",  "200",                  "55",         "a + b", "255"                  ; "u8 add")]
+    #[test_case("u16", "255",                  "257",        "a * b", "65535"                ; "u16 mul")]
+    #[test_case("i32", "-2147483648",          "2147483647", "a + b", "-1"                   ; "i32 add")]
+    #[test_case("u32", "65535",                "65537",      "a * b", "4294967295"           ; "u32 mul")]
+    #[test_case("i64", "-9223372036854775807", "1",          "a - b", "-9223372036854775808" ; "i64 sub")]
+    #[test_case("i64", "-3037000499",          "3037000499", "a * b", "-9223372030926249001" ; "i64 mul")]
+    #[test_case("u64", "18446744073709551614", "1",          "a + b", "18446744073709551615" ; "u64 add")]
+    #[test_case("u64", "4294967295",           "4294967297", "a * b", "18446744073709551615" ; "u64 mul")]
+    #[test_case("i32", "-2147483648",          "1",          "a / b", "-2147483648"          ; "i32 div")]
+    #[test_case("i64", "-9223372036854775808", "2",          "a % b", "0"                    ; "i64 rem")]
+    #[test_case("i16", "-32767",               "0",          "-a",    "32767"                ; "i16 negate")]
+    fn integer_arithmetic_up_to_the_limits_does_not_trap(
+        ty: &str,
+        a: &str,
+        b: &str,
+        expr: &str,
+        expected: &str,
+    ) {
+        // Given
+        let source = format!(
+            "pub fn f() -> bool {{ let a: {0} = {1}; let b: {0} = {2}; let e: {0} = {4}; return {3} == e; }}",
+            ty, a, b, expr, expected
+        );
+        let module = compile(&source);
+
+        // When
+        let (result, _) = run::<(), i32>(&module.to_binary(), "f", ()).unwrap();
+
+        // Then
+        assert_eq!(result, 1);
+    }
+
+    #[test]
+    fn wide_integers_and_floats_use_their_own_types() {
+        // Given
+        let module = compile(concat!(
+            "pub fn f(a: i64, b: f64) -> f64 {\n",
+            "    let big: i64 = a * 1000000000;\n",
+            "    if (big > 1000000000000) { return b * 2.0; }\n",
+            "    return -b;\n",
+            "}\n",
+        ));
+
+        // When
+        let (big, _) = run::<(i64, f64), f64>(&module.to_binary(), "f", (5000, 1.5)).unwrap();
+        let (small, _) = run::<(i64, f64), f64>(&module.to_binary(), "f", (1, 1.5)).unwrap();
+
+        // Then
+        assert_eq!(big, 3.0);
+        assert_eq!(small, -1.5);
+    }
+
+    #[test]
+    fn loops_support_break_and_continue() {
+        // Given
+        let module = compile(concat!(
+            "pub fn sum_odd(limit: i32) -> i32 {\n",
+            "    let total = 0;\n",
+            "    let i = 0;\n",
+            "    while (true) {\n",
+            "        i += 1;\n",
+            "        if (i > limit) { break; }\n",
+            "        if (i % 2 == 0) { continue; }\n",
+            "        total += i;\n",
+            "    }\n",
+            "    return total;\n",
+            "}\n",
+        ));
+
+        // When
+        let (result, _) = run::<i32, i32>(&module.to_binary(), "sum_odd", 10).unwrap();
+
+        // Then
+        assert_eq!(result, 25);
+    }
+
+    #[test]
+    fn boolean_operators_short_circuit() {
+        // Given
+        let module = compile(concat!(
+            "extern fn record(value: i32);\n",
+            "fn noisy(value: bool) -> bool { record(1); return value; }\n",
+            "pub fn f(a: bool) -> bool { return (a && noisy(true)) || noisy(false); }\n",
+        ));
+
+        // When
+        let (taken, taken_calls) = run::<i32, i32>(&module.to_binary(), "f", 1).unwrap();
+        let (skipped, skipped_calls) = run::<i32, i32>(&module.to_binary(), "f", 0).unwrap();
+
+        // Then
+        assert_eq!((taken, taken_calls), (1, vec![1]));
+        assert_eq!((skipped, skipped_calls), (0, vec![1]));
+    }
+
+    #[test]
+    fn externs_are_imported_by_their_link_names() {
+        // Given
+        let module = compile(concat!(
+            "#[link_name(\"record\")]\n",
+            "extern fn log(value: i32);\n",
+            "pub fn main() { let i = 0; while (i < 3) { log(i * i); i += 1; } }\n",
+        ));
+
+        // When
+        let (_, calls) = run::<(), ()>(&module.to_binary(), "main", ()).unwrap();
+
+        // Then
+        assert_eq!(module.imports[0].name, "record");
+        assert_eq!(module.imports[0].local_name, "log");
+        assert_eq!(calls, vec![0, 1, 4]);
+    }
+
+    #[test]
+    fn functions_can_call_functions_declared_after_them() {
+        // Given
+        let module = compile(concat!(
+            "pub fn factorial(n: u64) -> u64 { if (n == 0) { return 1; } return n * helper(n); }\n",
+            "fn helper(n: u64) -> u64 { return factorial(n - 1); }\n",
+        ));
+
+        // When
+        let (result, _) = run::<i64, i64>(&module.to_binary(), "factorial", 20).unwrap();
+
+        // Then
+        assert_eq!(result, 2432902008176640000);
+    }
+
+    #[test]
+    fn only_public_functions_are_exported() {
+        // Given
+        let module = compile("fn hidden() {} pub fn shown() { hidden(); }");
+
+        // When
+        let result = run::<(), ()>(&module.to_binary(), "hidden", ());
+
+        // Then
+        assert!(result.is_err());
+        assert!(run::<(), ()>(&module.to_binary(), "shown", ()).is_ok());
+    }
+
+    #[test]
+    fn main_is_always_exported() {
+        // Given
+        let module = compile("fn main() -> i32 { return 42; }");
+
+        // When
+        let (result, _) = run::<(), i32>(&module.to_binary(), "main", ()).unwrap();
+
+        // Then
+        assert_eq!(result, 42);
+    }
+
+    #[test]
+    fn failed_assertions_trap() {
+        // Given
+        let module = compile("pub fn f(a: i32) { assert(a > 0); assert_eq(a, 1); }");
+
+        // When
+        let passed = run::<i32, ()>(&module.to_binary(), "f", 1);
+        let failed = run::<i32, ()>(&module.to_binary(), "f", 2);
+
+        // Then
+        assert!(passed.is_ok());
+        assert!(failed.unwrap_err().to_string().contains("unreachable"));
+    }
+
+    #[test]
+    fn the_text_format_describes_the_same_module() {
+        // Given
+        let module = compile(concat!(
+            "extern fn record(value: i32);\n",
+            "pub fn f(n: i32) -> i32 {\n",
+            "    let x = 0;\n",
+            "    { let x = 2.5; }\n",
+            "    while (x < n) { record(x); x += 1; }\n",
+            "    return x;\n",
+            "}\n",
+        ));
+
+        // When
+        let binary = wat::parse_str(module.to_text()).unwrap();
+        let (result, calls) = run::<i32, i32>(&binary, "f", 3).unwrap();
+
+        // Then
+        assert_eq!(result, 3);
+        assert_eq!(calls, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn shadowed_variables_get_their_own_locals() {
+        // Given
+        let module = compile("pub fn f(x: i32) -> i32 { { let x = true; } return x; }");
+
+        // When
+        let function = &module.functions[0];
+
+        // Then
+        assert_eq!(function.parameter_names, vec!["x"]);
+        assert_eq!(function.locals, vec![("x.1".to_string(), ValType::I32)]);
+    }
+
+    #[test_case("pub fn f() { let s = \"hi\"; }",              "strings"                 ; "strings")]
+    #[test_case("pub fn f() { let s = b\"hi\"; }",             "byte strings"            ; "byte strings")]
+    #[test_case("pub fn f(a: std::BigInt) {}",                 "big integers"            ; "big integers")]
+    #[test_case("pub fn f() { let a: std::BigInt = 1; }",      "big integers"            ; "big integer literals")]
+    #[test_case("pub fn f(t: (i32, bool)) {}",                 "values of type (i32, bool)" ; "tuples")]
+    #[test_case("pub fn f() { println(\"hi\"); }",             "printing and formatting" ; "printing")]
+    #[test_case("pub fn f() { |x: i32| x; }",                  "closures"                ; "closures")]
+    #[test_case("pub fn f(a: i32) -> i32 { return a ** 2; }",  "operator Pow on values of type i32" ; "pow")]
+    #[test_case("extern fn g(s: string);",                     "strings"                 ; "extern parameters")]
+    fn unsupported_features_are_reported(source: &str, feature: &str) {
+        // When
+        let errors = compile_errors(source);
+
+        // Then
+        assert_eq!(
+            errors,
+            vec![format!("{} cannot be compiled to WebAssembly yet", feature)]
+        );
+    }
+}
@@ -0,0 +1,14 @@
+//! A backend producing WebAssembly modules, so that programs can run inside of sandboxes
+//! without needing a native toolchain.
+//!
+//! Every function in a file becomes a WebAssembly function, and its variables become
+//! locals. Extern functions are imported from the host under their link names, and public
+//! functions are exported under their own names, as is `main` so that hosts always have an
+//! entry point to call. Only values that WebAssembly can hold directly are supported so far,
+//! which are booleans, characters, integers of up to 64 bits and floats.
+//!
+//! Integer arithmetic that overflows traps where the interpreter would stop the program, as
+//! do failed assertions, division by zero and falling off the end of a function that should
+//! return a value.
+pub mod lower;
+pub mod module;
@@ -0,0 +1,597 @@
+//! An in-memory WebAssembly module, which can be written out in either the text or the
+//! binary format.
+//!
+//! Only the parts of WebAssembly that the backend needs are modelled here. Functions are
+//! numbered with imports first, followed by the functions defined by the module, as they are
+//! in WebAssembly itself.
+use std::fmt::{Debug, Display, Formatter, Write};
+
+const MAGIC: &[u8] = b"\0asm";
+const VERSION: &[u8] = &[1, 0, 0, 0];
+
+const TYPE_SECTION: u8 = 1;
+const IMPORT_SECTION: u8 = 2;
+const FUNCTION_SECTION: u8 = 3;
+const EXPORT_SECTION: u8 = 7;
+const CODE_SECTION: u8 = 10;
+
+const FUNCTION_TYPE: u8 = 0x60;
+const FUNCTION_KIND: u8 = 0x00;
+const EMPTY_BLOCK_TYPE: u8 = 0x40;
+
+/// The types that WebAssembly values can have.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ValType {
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+impl ValType {
+    fn code(&self) -> u8 {
+        match self {
+            Self::I32 => 0x7f,
+            Self::I64 => 0x7e,
+            Self::F32 => 0x7d,
+            Self::F64 => 0x7c,
+        }
+    }
+}
+
+impl Display for ValType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::I32 => write!(f, "i32"),
+            Self::I64 => write!(f, "i64"),
+            Self::F32 => write!(f, "f32"),
+            Self::F64 => write!(f, "f64"),
+        }
+    }
+}
+
+/// The parameters and results of a function.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct FuncType {
+    pub parameters: Vec<ValType>,
+    pub results: Vec<ValType>,
+}
+
+/// A function that the host has to provide when the module is instantiated.
+#[derive(Clone, Debug)]
+pub struct Import {
+    pub module: String,
+    pub name: String,
+    // The name the function is known by within the module.
+    pub local_name: String,
+    pub ty: FuncType,
+}
+
+/// A function defined by the module.
+#[derive(Clone, Debug)]
+pub struct Function {
+    pub name: String,
+    pub ty: FuncType,
+    // Names of the parameters, followed by the names and types of every other local.
+    pub parameter_names: Vec<String>,
+    pub locals: Vec<(String, ValType)>,
+    pub body: Vec<Instruction>,
+    pub export: bool,
+}
+
+/// A single instruction within the body of a function.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Instruction {
+    Unreachable,
+    Block,
+    Loop,
+    // The type of the value that the if produces, if any.
+    If(Option<ValType>),
+    Else,
+    End,
+    // Branches refer to enclosing blocks by how many blocks lie between them.
+    Br(u32),
+    BrIf(u32),
+    Return,
+    Call(u32),
+    Drop,
+    LocalGet(u32),
+    LocalSet(u32),
+    I32Const(i32),
+    I64Const(i64),
+    F32Const(f32),
+    F64Const(f64),
+    // Any numeric instruction that takes no immediates, such as i32.add. The name is
+    // written after the type, so i32.add has a name of "add".
+    Numeric(ValType, &'static str, u8),
+}
+
+/// A WebAssembly module.
+#[derive(Clone, Debug, Default)]
+pub struct WasmModule {
+    pub imports: Vec<Import>,
+    pub functions: Vec<Function>,
+}
+
+impl WasmModule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write the module out in the text format.
+    pub fn to_text(&self) -> String {
+        let names: Vec<String> = self
+            .imports
+            .iter()
+            .map(|import| &import.local_name)
+            .chain(self.functions.iter().map(|function| &function.name))
+            .enumerate()
+            .map(|(index, name)| text_id(name, "func", index))
+            .collect();
+
+        let mut text = String::from("(module\n");
+        for (import, name) in self.imports.iter().zip(&names) {
+            writeln!(
+                text,
+                "  (import {:?} {:?} (func {}{}))",
+                import.module,
+                import.name,
+                name,
+                text_signature(&import.ty, None)
+            )
+            .unwrap();
+        }
+
+        for (function, name) in self.functions.iter().zip(&names[self.imports.len()..]) {
+            let locals: Vec<String> = function
+                .parameter_names
+                .iter()
+                .chain(function.locals.iter().map(|(name, _)| name))
+                .enumerate()
+                .map(|(index, name)| text_id(name, "local", index))
+                .collect();
+
+            write!(text, "  (func {}", name).unwrap();
+            if function.export {
+                write!(text, " (export {:?})", function.name).unwrap();
+            }
+            text.push_str(&text_signature(&function.ty, Some(&locals)));
+            text.push('\n');
+            for ((_, ty), name) in function
+                .locals
+                .iter()
+                .zip(&locals[function.parameter_names.len()..])
+            {
+                writeln!(text, "    (local {} {})", name, ty).unwrap();
+            }
+
+            let mut depth = 2;
+            for instruction in &function.body {
+                if matches!(instruction, Instruction::Else | Instruction::End) {
+                    depth -= 1;
+                }
+                text.push_str(&"  ".repeat(depth));
+                write_text_instruction(&mut text, instruction, &names, &locals);
+                text.push('\n');
+                if matches!(
+                    instruction,
+                    Instruction::Block | Instruction::Loop | Instruction::If(_) | Instruction::Else
+                ) {
+                    depth += 1;
+                }
+            }
+            text.push_str("  )\n");
+        }
+
+        text.push_str(")\n");
+        text
+    }
+
+    /// Write the module out in the binary format.
+    pub fn to_binary(&self) -> Vec<u8> {
+        // Functions with the same signature share a single type.
+        let mut types: Vec<&FuncType> = Vec::new();
+        let mut type_index = |ty| match types.iter().position(|existing| *existing == ty) {
+            Some(index) => index as u32,
+            None => {
+                types.push(ty);
+                types.len() as u32 - 1
+            }
+        };
+        let import_types: Vec<u32> = self
+            .imports
+            .iter()
+            .map(|import| type_index(&import.ty))
+            .collect();
+        let function_types: Vec<u32> = self
+            .functions
+            .iter()
+            .map(|function| type_index(&function.ty))
+            .collect();
+
+        let mut binary = Vec::new();
+        binary.extend_from_slice(MAGIC);
+        binary.extend_from_slice(VERSION);
+
+        write_section(&mut binary, TYPE_SECTION, &types, |out, ty| {
+            out.push(FUNCTION_TYPE);
+            write_vec(out, &ty.parameters, |out, ty| out.push(ty.code()));
+            write_vec(out, &ty.results, |out, ty| out.push(ty.code()));
+        });
+
+        let imports: Vec<_> = self.imports.iter().zip(import_types).collect();
+        write_section(
+            &mut binary,
+            IMPORT_SECTION,
+            &imports,
+            |out, (import, ty)| {
+                write_name(out, &import.module);
+                write_name(out, &import.name);
+                out.push(FUNCTION_KIND);
+                write_u32(out, *ty);
+            },
+        );
+
+        write_section(&mut binary, FUNCTION_SECTION, &function_types, |out, ty| {
+            write_u32(out, *ty);
+        });
+
+        let exports: Vec<(u32, &Function)> = (self.imports.len() as u32..)
+            .zip(&self.functions)
+            .filter(|(_, function)| function.export)
+            .collect();
+        write_section(
+            &mut binary,
+            EXPORT_SECTION,
+            &exports,
+            |out, (index, function)| {
+                write_name(out, &function.name);
+                out.push(FUNCTION_KIND);
+                write_u32(out, *index);
+            },
+        );
+
+        write_section(
+            &mut binary,
+            CODE_SECTION,
+            &self.functions,
+            |out, function| {
+                let mut code = Vec::new();
+
+                // Locals are declared in runs of the same type.
+                let mut runs: Vec<(u32, ValType)> = Vec::new();
+                for (_, ty) in &function.locals {
+                    match runs.last_mut() {
+                        Some((count, last)) if last == ty => *count += 1,
+                        _ => runs.push((1, *ty)),
+                    }
+                }
+                write_vec(&mut code, &runs, |out, (count, ty)| {
+                    write_u32(out, *count);
+                    out.push(ty.code());
+                });
+
+                for instruction in &function.body {
+                    write_binary_instruction(&mut code, instruction);
+                }
+                code.push(0x0b);
+
+                write_u32(out, code.len() as u32);
+                out.extend(code);
+            },
+        );
+
+        binary
+    }
+}
+
+// Identifiers in the text format are restricted to printable ASCII, so anything else is
+// named after its index instead.
+fn text_id(name: &str, kind: &str, index: usize) -> String {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-./:<=>?@\\^_`|~".contains(c));
+    if valid {
+        format!("${}", name)
+    } else {
+        format!("${}{}", kind, index)
+    }
+}
+
+fn text_signature(ty: &FuncType, parameter_names: Option<&[String]>) -> String {
+    let mut text = String::new();
+    for (index, parameter) in ty.parameters.iter().enumerate() {
+        match parameter_names {
+            Some(names) => write!(text, " (param {} {})", names[index], parameter).unwrap(),
+            None => write!(text, " (param {})", parameter).unwrap(),
+        }
+    }
+    for result in &ty.results {
+        write!(text, " (result {})", result).unwrap();
+    }
+    text
+}
+
+fn write_text_instruction(
+    text: &mut String,
+    instruction: &Instruction,
+    functions: &[String],
+    locals: &[String],
+) {
+    match instruction {
+        Instruction::Unreachable => text.push_str("unreachable"),
+        Instruction::Block => text.push_str("block"),
+        Instruction::Loop => text.push_str("loop"),
+        Instruction::If(None) => text.push_str("if"),
+        Instruction::If(Some(ty)) => write!(text, "if (result {})", ty).unwrap(),
+        Instruction::Else => text.push_str("else"),
+        Instruction::End => text.push_str("end"),
+        Instruction::Br(depth) => write!(text, "br {}", depth).unwrap(),
+        Instruction::BrIf(depth) => write!(text, "br_if {}", depth).unwrap(),
+        Instruction::Return => text.push_str("return"),
+        Instruction::Call(index) => write!(text, "call {}", functions[*index as usize]).unwrap(),
+        Instruction::Drop => text.push_str("drop"),
+        Instruction::LocalGet(index) => {
+            write!(text, "local.get {}", locals[*index as usize]).unwrap()
+        }
+        Instruction::LocalSet(index) => {
+            write!(text, "local.set {}", locals[*index as usize]).unwrap()
+        }
+        Instruction::I32Const(value) => write!(text, "i32.const {}", value).unwrap(),
+        Instruction::I64Const(value) => write!(text, "i64.const {}", value).unwrap(),
+        Instruction::F32Const(value) => write!(text, "f32.const {}", text_float(value)).unwrap(),
+        Instruction::F64Const(value) => write!(text, "f64.const {}", text_float(value)).unwrap(),
+        Instruction::Numeric(ty, name, _) => write!(text, "{}.{}", ty, name).unwrap(),
+    }
+}
+
+// Floats are written as the shortest decimal that reads back as the same value, which the
+// text format accepts as it is, apart from how not-a-number is spelt.
+fn text_float(value: impl Debug) -> String {
+    match format!("{:?}", value) {
+        text if text == "NaN" => "nan".to_string(),
+        text => text,
+    }
+}
+
+fn write_binary_instruction(out: &mut Vec<u8>, instruction: &Instruction) {
+    match instruction {
+        Instruction::Unreachable => out.push(0x00),
+        Instruction::Block => out.extend([0x02, EMPTY_BLOCK_TYPE]),
+        Instruction::Loop => out.extend([0x03, EMPTY_BLOCK_TYPE]),
+        Instruction::If(None) => out.extend([0x04, EMPTY_BLOCK_TYPE]),
+        Instruction::If(Some(ty)) => out.extend([0x04, ty.code()]),
+        Instruction::Else => out.push(0x05),
+        Instruction::End => out.push(0x0b),
+        Instruction::Br(depth) => {
+            out.push(0x0c);
+            write_u32(out, *depth);
+        }
+        Instruction::BrIf(depth) => {
+            out.push(0x0d);
+            write_u32(out, *depth);
+        }
+        Instruction::Return => out.push(0x0f),
+        Instruction::Call(index) => {
+            out.push(0x10);
+            write_u32(out, *index);
+        }
+        Instruction::Drop => out.push(0x1a),
+        Instruction::LocalGet(index) => {
+            out.push(0x20);
+            write_u32(out, *index);
+        }
+        Instruction::LocalSet(index) => {
+            out.push(0x21);
+            write_u32(out, *index);
+        }
+        Instruction::I32Const(value) => {
+            out.push(0x41);
+            write_i64(out, *value as i64);
+        }
+        Instruction::I64Const(value) => {
+            out.push(0x42);
+            write_i64(out, *value);
+        }
+        Instruction::F32Const(value) => {
+            out.push(0x43);
+            out.extend(value.to_le_bytes());
+        }
+        Instruction::F64Const(value) => {
+            out.push(0x44);
+            out.extend(value.to_le_bytes());
+        }
+        Instruction::Numeric(_, _, opcode) => out.push(*opcode),
+    }
+}
+
+fn write_section<T>(
+    out: &mut Vec<u8>,
+    id: u8,
+    items: &[T],
+    write_item: impl FnMut(&mut Vec<u8>, &T),
+) {
+    // Empty sections can be left out entirely.
+    if items.is_empty() {
+        return;
+    }
+
+    let mut contents = Vec::new();
+    write_vec(&mut contents, items, write_item);
+    out.push(id);
+    write_u32(out, contents.len() as u32);
+    out.extend(contents);
+}
+
+fn write_vec<T>(out: &mut Vec<u8>, items: &[T], mut write_item: impl FnMut(&mut Vec<u8>, &T)) {
+    write_u32(out, items.len() as u32);
+    for item in items {
+        write_item(out, item);
+    }
+}
+
+fn write_name(out: &mut Vec<u8>, name: &str) {
+    write_u32(out, name.len() as u32);
+    out.extend_from_slice(name.as_bytes());
+}
+
+// Unsigned LEB128.
+fn write_u32(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+// Signed LEB128. Constants of both integer types use this, as an i32 has the same encoding
+// as the i64 with the same value.
+fn write_i64(out: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        let sign_bit = byte & 0x40 != 0;
+        if (value == 0 && !sign_bit) || (value == -1 && sign_bit) {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(0,          &[0x00]             ; "zero")]
+    #[test_case(127,        &[0x7f]             ; "largest single byte")]
+    #[test_case(128,        &[0x80, 0x01]       ; "smallest two bytes")]
+    #[test_case(624485,     &[0xe5, 0x8e, 0x26] ; "three bytes")]
+    fn unsigned_integers_are_leb128_encoded(value: u32, expected: &[u8]) {
+        // Given
+        let mut out = Vec::new();
+
+        // When
+        write_u32(&mut out, value);
+
+        // Then
+        assert_eq!(out, expected);
+    }
+
+    #[test_case(0,       &[0x00]             ; "zero")]
+    #[test_case(63,      &[0x3f]             ; "largest single byte")]
+    #[test_case(64,      &[0xc0, 0x00]       ; "needs a sign byte")]
+    #[test_case(-1,      &[0x7f]             ; "minus one")]
+    #[test_case(-64,     &[0x40]             ; "smallest single byte")]
+    #[test_case(-123456, &[0xc0, 0xbb, 0x78] ; "three bytes")]
+    fn signed_integers_are_leb128_encoded(value: i64, expected: &[u8]) {
+        // Given
+        let mut out = Vec::new();
+
+        // When
+        write_i64(&mut out, value);
+
+        // Then
+        assert_eq!(out, expected);
+    }
+
+    #[test]
+    fn modules_are_written_as_text() {
+        // Given
+        let module = WasmModule {
+            imports: vec![Import {
+                module: "env".to_string(),
+                name: "log_i32".to_string(),
+                local_name: "log".to_string(),
+                ty: FuncType {
+                    parameters: vec![ValType::I32],
+                    results: vec![],
+                },
+            }],
+            functions: vec![Function {
+                name: "twice".to_string(),
+                ty: FuncType {
+                    parameters: vec![ValType::I32],
+                    results: vec![ValType::I32],
+                },
+                parameter_names: vec!["x".to_string()],
+                locals: vec![("y".to_string(), ValType::I32)],
+                body: vec![
+                    Instruction::LocalGet(0),
+                    Instruction::I32Const(2),
+                    Instruction::Numeric(ValType::I32, "mul", 0x6c),
+                    Instruction::LocalSet(1),
+                    Instruction::LocalGet(1),
+                    Instruction::Call(0),
+                    Instruction::LocalGet(1),
+                ],
+                export: true,
+            }],
+        };
+
+        // When
+        let text = module.to_text();
+
+        // Then
+        assert_eq!(
+            text,
+            concat!(
+                "(module\n",
+                "  (import \"env\" \"log_i32\" (func $log (param i32)))\n",
+                "  (func $twice (export \"twice\") (param $x i32) (result i32)\n",
+                "    (local $y i32)\n",
+                "    local.get $x\n",
+                "    i32.const 2\n",
+                "    i32.mul\n",
+                "    local.set $y\n",
+                "    local.get $y\n",
+                "    call $log\n",
+                "    local.get $y\n",
+                "  )\n",
+                ")\n",
+            )
+        );
+    }
+
+    #[test]
+    fn functions_with_the_same_signature_share_a_type() {
+        // Given
+        let function = |name: &str| Function {
+            name: name.to_string(),
+            ty: FuncType {
+                parameters: vec![],
+                results: vec![],
+            },
+            parameter_names: vec![],
+            locals: vec![],
+            body: vec![],
+            export: false,
+        };
+        let module = WasmModule {
+            imports: vec![],
+            functions: vec![function("a"), function("b")],
+        };
+
+        // When
+        let binary = module.to_binary();
+
+        // Then
+        assert_eq!(
+            binary,
+            [
+                MAGIC,
+                VERSION,
+                &[TYPE_SECTION, 4, 1, FUNCTION_TYPE, 0, 0],
+                &[FUNCTION_SECTION, 3, 2, 0, 0],
+                &[CODE_SECTION, 7, 2, 2, 0, 0x0b, 2, 0, 0x0b],
+            ]
+            .concat()
+        );
+    }
+}
@@ -0,0 +1,976 @@
+use crate::db::item_tree::ItemTree;
+use crate::error::{CompilerError, CompilerWarning};
+use crate::hir::context::{HirFunctionContext, HirModuleContext, path_to_string};
+use crate::hir::nodes::{
+    HirExprKind, HirFunctionData, HirStatementKind, HirTypeRef, HirTypeRefKind,
+};
+use crate::hir::ty::{HirFunctionType, HirType};
+use crate::hir::typeck::{HirImports, HirTypeckResult, check_function, resolve_signature};
+use haikulang_parser::ast::func::{FunctionDecl, ParameterDecl};
+use haikulang_parser::ast::types::TypeName;
+use haikulang_parser::ast::unit::{CompilationUnit, CompilationUnitMember};
+use haikulang_parser::error::{Diagnostics, ParserError, ParserWarning};
+use haikulang_parser::lexer::token_stream::TokenStream;
+use haikulang_parser::parser::core::Parser;
+use haikulang_parser::span::Spanned;
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Handle to a file that has been registered with the database.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct FileId(u32);
+
+/// Handle to a function declared in a registered file. Functions are identified by name
+/// rather than by position so that the handle stays valid while the file is being edited.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct FunctionId {
+    pub file: FileId,
+    pub name: String,
+}
+
+impl FunctionId {
+    pub fn new(file: FileId, name: &str) -> Self {
+        Self {
+            file,
+            name: name.to_string(),
+        }
+    }
+}
+
+/// The outcome of parsing a file.
+#[derive(Debug)]
+pub struct ParsedFile {
+    pub unit: Option<CompilationUnit>,
+    pub errors: Vec<Spanned<ParserError>>,
+    pub warnings: Vec<Spanned<ParserWarning>>,
+}
+
+impl ParsedFile {
+    pub fn function_decl(&self, name: &str) -> Option<FunctionDecl> {
+        self.unit
+            .as_ref()?
+            .members
+            .iter()
+            .find_map(|member| match member.value() {
+                CompilationUnitMember::Function(function)
+                    if function.name.value().value == name =>
+                {
+                    Some(*function)
+                }
+                _ => None,
+            })
+    }
+}
+
+/// Counters describing how many times each query was actually recomputed rather than
+/// being served from the cache.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct QueryStats {
+    pub parses: usize,
+    pub item_trees: usize,
+    pub module_scans: usize,
+    pub lowerings: usize,
+    pub type_checks: usize,
+}
+
+/// Memoized, query-based compilation database.
+///
+/// Each query caches its result alongside a fingerprint of the inputs it was computed from,
+/// and only recomputes when that fingerprint changes. Function-level queries depend on the
+/// source text of that function and the signatures of the items in the file, so editing the
+/// body of one function only causes that function to be lowered and checked again.
+#[derive(Default)]
+pub struct Database {
+    files: Vec<SourceFile>,
+    parses: HashMap<FileId, Memo<Rc<ParsedFile>>>,
+    item_trees: HashMap<FileId, Memo<Rc<ItemTree>>>,
+    modules: HashMap<FileId, Memo<HirModuleContext>>,
+    lowered_functions: HashMap<FunctionId, PositionedMemo<HirFunctionData>>,
+    typeck_results: HashMap<FunctionId, PositionedMemo<HirTypeckResult>>,
+    stats: QueryStats,
+}
+
+struct SourceFile {
+    path: PathBuf,
+    text: Rc<str>,
+    hash: u64,
+    removed: bool,
+}
+
+struct Memo<T> {
+    fingerprint: u64,
+    value: T,
+}
+
+// Memo for something that contains spans, recording where it was when it was computed, so
+// that it can be moved rather than recomputed if only the code before it changes.
+struct PositionedMemo<T> {
+    fingerprint: u64,
+    offset: usize,
+    value: Rc<T>,
+}
+
+impl Database {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a new file with the given content.
+    pub fn add_file(&mut self, path: impl Into<PathBuf>, text: &str) -> FileId {
+        let id = FileId(self.files.len() as u32);
+        self.files.push(SourceFile {
+            path: path.into(),
+            text: Rc::from(text),
+            hash: content_hash(text),
+            removed: false,
+        });
+        id
+    }
+
+    /// Forget a file that no longer exists, along with everything cached about it. It is left
+    /// out of [`Database::files`] from then on, so other modules can no longer refer to it.
+    /// The ID is never reused, so a file that comes back has to be added again.
+    pub fn remove_file(&mut self, file: FileId) {
+        let source_file = &mut self.files[file.0 as usize];
+        source_file.removed = true;
+        source_file.text = Rc::from("");
+        source_file.hash = content_hash("");
+
+        self.parses.remove(&file);
+        self.item_trees.remove(&file);
+        self.modules.remove(&file);
+        self.lowered_functions
+            .retain(|function, _| function.file != file);
+        self.typeck_results
+            .retain(|function, _| function.file != file);
+    }
+
+    /// Replace the content of an existing file. Cached results are invalidated lazily the next
+    /// time they are queried.
+    pub fn set_file_text(&mut self, file: FileId, text: &str) {
+        let source_file = &mut self.files[file.0 as usize];
+        let hash = content_hash(text);
+        if source_file.hash != hash {
+            source_file.text = Rc::from(text);
+            source_file.hash = hash;
+        }
+    }
+
+    pub fn file_path(&self, file: FileId) -> &Path {
+        &self.files[file.0 as usize].path
+    }
+
+    pub fn file_text(&self, file: FileId) -> Rc<str> {
+        self.files[file.0 as usize].text.clone()
+    }
+
+    /// The name of the module that the given file declares, which is the name of the file
+    /// without its extension.
+    pub fn module_name(&self, file: FileId) -> String {
+        let path = self.file_path(file);
+        path.file_stem()
+            .unwrap_or(path.as_os_str())
+            .to_string_lossy()
+            .to_string()
+    }
+
+    /// Find the file that declares the module with the given name.
+    pub fn find_module(&self, name: &str) -> Option<FileId> {
+        self.files().find(|file| self.module_name(*file) == name)
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = FileId> + use<> {
+        let files: Vec<FileId> = (0..self.files.len() as u32)
+            .map(FileId)
+            .filter(|file| !self.files[file.0 as usize].removed)
+            .collect();
+        files.into_iter()
+    }
+
+    pub fn stats(&self) -> QueryStats {
+        self.stats
+    }
+
+    /// Parse the given file.
+    pub fn parse(&mut self, file: FileId) -> Rc<ParsedFile> {
+        let fingerprint = self.files[file.0 as usize].hash;
+
+        if let Some(memo) = self.parses.get(&file)
+            && memo.fingerprint == fingerprint
+        {
+            return memo.value.clone();
+        }
+
+        let source_file = &self.files[file.0 as usize];
+        let mut diagnostics = Diagnostics::default();
+        let unit = {
+            let mut parser = Parser::new(
+                TokenStream::new(&source_file.text),
+                &source_file.path,
+                &mut diagnostics,
+            );
+            parser.parse().ok().map(|unit| unit.value())
+        };
+
+        self.stats.parses += 1;
+        let value = Rc::new(ParsedFile {
+            unit,
+            errors: diagnostics.errors,
+            warnings: diagnostics.warnings,
+        });
+        self.parses.insert(
+            file,
+            Memo {
+                fingerprint,
+                value: value.clone(),
+            },
+        );
+        value
+    }
+
+    /// Summarise the top-level items declared in the given file.
+    pub fn item_tree(&mut self, file: FileId) -> Rc<ItemTree> {
+        let fingerprint = self.files[file.0 as usize].hash;
+
+        if let Some(memo) = self.item_trees.get(&file)
+            && memo.fingerprint == fingerprint
+        {
+            return memo.value.clone();
+        }
+
+        let parsed = self.parse(file);
+        let text = self.file_text(file);
+        let item_tree = match &parsed.unit {
+            Some(unit) => ItemTree::new(unit, &text),
+            None => ItemTree::empty(),
+        };
+
+        self.stats.item_trees += 1;
+        let value = Rc::new(item_tree);
+        self.item_trees.insert(
+            file,
+            Memo {
+                fingerprint,
+                value: value.clone(),
+            },
+        );
+        value
+    }
+
+    /// Get the module context describing the declarations in the given file.
+    pub fn module_context(&mut self, file: FileId) -> &HirModuleContext {
+        self.ensure_module_context(file);
+        &self.modules[&file].value
+    }
+
+    /// Errors in the top-level declarations of the given file, not including any errors
+    /// within function bodies.
+    pub fn module_errors(&mut self, file: FileId) -> Vec<Spanned<CompilerError>> {
+        let mut errors = self.module_context(file).errors().to_vec();
+        let parsed = self.parse(file);
+        let mut paths = Vec::new();
+
+        for member in parsed.unit.iter().flat_map(|unit| unit.members.iter()) {
+            match member.value_ref() {
+                CompilationUnitMember::Use(use_decl) => {
+                    if !use_decl.path.value_ref().qualifier.is_empty() {
+                        paths.push(Spanned::new(
+                            path_to_string(use_decl.path.value_ref()),
+                            use_decl.path.span(),
+                        ));
+                    }
+                }
+                CompilationUnitMember::ExternFunction(function) => {
+                    parameter_paths(function.parameters.value_ref(), &mut paths);
+                    if let Some(return_type) = &function.return_type {
+                        type_name_paths(return_type, &mut paths);
+                    }
+                }
+                // Function signatures are checked along with the function bodies.
+                CompilationUnitMember::Function(_) => {}
+                CompilationUnitMember::Struct(struct_decl) => {
+                    for member in struct_decl.members.iter() {
+                        type_name_paths(&member.value_ref().type_name, &mut paths);
+                    }
+                }
+            }
+        }
+
+        errors.extend(paths.iter().filter_map(|path| self.check_path(file, path)));
+        errors
+    }
+
+    /// Warnings for the top-level declarations of the given file, not including any warnings
+    /// within function bodies.
+    pub fn module_warnings(&mut self, file: FileId) -> Vec<Spanned<CompilerWarning>> {
+        self.module_context(file).warnings().to_vec()
+    }
+
+    /// Lower the given function to HIR, or return None if no such function exists.
+    pub fn lower_function(&mut self, function: &FunctionId) -> Option<Rc<HirFunctionData>> {
+        let (fingerprint, offset) = self.function_fingerprint(function)?;
+        self.ensure_module_context(function.file);
+
+        let module = &mut self.modules.get_mut(&function.file).unwrap().value;
+        let name_id = module.intern(&function.name);
+        let header = module.lookup_function(name_id)?.clone();
+
+        if let Some(memo) = self.lowered_functions.get(function)
+            && memo.fingerprint == fingerprint
+        {
+            if memo.offset == offset {
+                return Some(memo.value.clone());
+            }
+
+            let mut shifted = memo.value.shifted(offset as isize - memo.offset as isize);
+            shifted.header = header;
+            let value = Rc::new(shifted);
+            self.lowered_functions.insert(
+                function.clone(),
+                PositionedMemo {
+                    fingerprint,
+                    offset,
+                    value: value.clone(),
+                },
+            );
+            return Some(value);
+        }
+
+        let parsed = self.parse(function.file);
+        let function_decl = parsed.function_decl(&function.name)?;
+        let source = self.file_text(function.file);
+        let module = &mut self.modules.get_mut(&function.file).unwrap().value;
+        let data = HirFunctionContext::new(module, &source).lower_function(header, &function_decl);
+
+        self.stats.lowerings += 1;
+        let value = Rc::new(data);
+        self.lowered_functions.insert(
+            function.clone(),
+            PositionedMemo {
+                fingerprint,
+                offset,
+                value: value.clone(),
+            },
+        );
+        Some(value)
+    }
+
+    /// Type check the given function, or return None if no such function exists.
+    pub fn type_of(&mut self, function: &FunctionId) -> Option<Rc<HirTypeckResult>> {
+        let data = self.lower_function(function)?;
+        let (fingerprint, offset) = self.function_fingerprint(function)?;
+        let fingerprint = content_hash(&(fingerprint, self.workspace_fingerprint()));
+
+        if let Some(memo) = self.typeck_results.get(function)
+            && memo.fingerprint == fingerprint
+        {
+            if memo.offset == offset {
+                return Some(memo.value.clone());
+            }
+
+            let value = Rc::new(memo.value.shifted(offset as isize - memo.offset as isize));
+            self.typeck_results.insert(
+                function.clone(),
+                PositionedMemo {
+                    fingerprint,
+                    offset,
+                    value: value.clone(),
+                },
+            );
+            return Some(value);
+        }
+
+        let paths = function_paths(&self.modules[&function.file].value, &data);
+        let imports = self.imports(function.file, &paths);
+        let module = &self.modules[&function.file].value;
+        let mut result = check_function(module, &imports, &data);
+        result.errors.extend(
+            paths
+                .iter()
+                .filter_map(|path| self.check_path(function.file, path)),
+        );
+
+        self.stats.type_checks += 1;
+        let value = Rc::new(result);
+        self.typeck_results.insert(
+            function.clone(),
+            PositionedMemo {
+                fingerprint,
+                offset,
+                value: value.clone(),
+            },
+        );
+        Some(value)
+    }
+
+    /*
+     * Helpers
+     */
+
+    // A function depends on its own source text and on the signatures of everything else in
+    // the same file, since those determine what its body can refer to.
+    fn function_fingerprint(&mut self, function: &FunctionId) -> Option<(u64, usize)> {
+        let item_tree = self.item_tree(function.file);
+        let item = item_tree.function(&function.name)?;
+        let fingerprint = content_hash(&(item.text_hash, item_tree.signature_hash));
+        Some((fingerprint, item.span.start()))
+    }
+
+    // Qualified paths can refer to any other module, so type checking a function also depends
+    // on the signatures of every other file.
+    fn workspace_fingerprint(&mut self) -> u64 {
+        let signatures = self
+            .files()
+            .map(|file| (self.module_name(file), self.item_tree(file).signature_hash))
+            .collect::<Vec<_>>();
+        content_hash(&signatures)
+    }
+
+    // Check that a qualified path refers to an item that can be used from the given file.
+    // Paths into modules that the database does not know about are left alone, since those
+    // modules may be provided from elsewhere, as are built-in types with qualified names.
+    // Modules do not nest, so a longer path that starts with a known module, such as
+    // std::BigInt::from_i64, cannot refer to anything and is reported.
+    fn check_path(
+        &mut self,
+        file: FileId,
+        path: &Spanned<String>,
+    ) -> Option<Spanned<CompilerError>> {
+        if HirType::primitive(path.value_ref()).is_some() {
+            return None;
+        }
+
+        let (module_name, item_name) = path.value_ref().rsplit_once("::")?;
+        let Some(module_file) = self.find_module(module_name) else {
+            let (root, _) = module_name.split_once("::")?;
+            self.find_module(root)?;
+            let error = CompilerError::UnresolvedName(path.value());
+            return Some(Spanned::new(error, path.span()));
+        };
+        let module = self.module_context(module_file);
+
+        let is_pub = if let Some(function) = module.lookup_function_by_name(item_name) {
+            function.is_pub
+        } else if let Some(struct_header) = module.lookup_struct_by_name(item_name) {
+            struct_header.is_pub
+        } else {
+            let error = CompilerError::UnresolvedName(path.value());
+            return Some(Spanned::new(error, path.span()));
+        };
+
+        if is_pub || module_file == file {
+            None
+        } else {
+            let error = CompilerError::PrivateItem {
+                name: path.value(),
+                module: module_name.to_string(),
+            };
+            Some(Spanned::new(error, path.span()))
+        }
+    }
+
+    // Resolve the items in other modules that the given paths refer to. Paths that do not
+    // refer to anything are left out here, and reported by check_path instead.
+    fn imports(&mut self, file: FileId, paths: &[Spanned<String>]) -> HirImports {
+        let mut imports = HirImports::new();
+
+        for path in paths {
+            let Some((module_name, item_name)) = path.value_ref().rsplit_once("::") else {
+                continue;
+            };
+            let Some(module_file) = self.find_module(module_name) else {
+                continue;
+            };
+
+            // Structs are only named by their qualified names when used from other modules.
+            let qualifier = (module_file != file).then_some(module_name);
+            let module = self.module_context(module_file);
+
+            if let Some(header) = module.lookup_function_by_name(item_name) {
+                let signature =
+                    resolve_signature(module, &HirImports::new(), header, &mut Vec::new());
+                let signature = HirFunctionType {
+                    parameters: signature
+                        .parameters
+                        .into_iter()
+                        .map(|parameter| qualify_type(qualifier, parameter))
+                        .collect(),
+                    return_type: qualify_type(qualifier, signature.return_type),
+                };
+                imports.add_function(path.value(), signature);
+            } else if module.lookup_struct_by_name(item_name).is_some() {
+                imports.add_struct(path.value());
+            }
+        }
+
+        imports
+    }
+
+    fn ensure_module_context(&mut self, file: FileId) {
+        let fingerprint = self.files[file.0 as usize].hash;
+
+        if let Some(memo) = self.modules.get(&file)
+            && memo.fingerprint == fingerprint
+        {
+            return;
+        }
+
+        let parsed = self.parse(file);
+
+        // Keep hold of the existing interned strings, so that any IDs referenced from the
+        // functions we already lowered remain valid.
+        let mut module = self
+            .modules
+            .remove(&file)
+            .map(|memo| memo.value)
+            .unwrap_or_default();
+        module.reset();
+
+        if let Some(unit) = &parsed.unit {
+            module.pre_scan(unit);
+        }
+
+        self.stats.module_scans += 1;
+        self.modules.insert(
+            file,
+            Memo {
+                fingerprint,
+                value: module,
+            },
+        );
+    }
+}
+
+// Find every qualified path that a lowered function refers to, including within its signature.
+fn function_paths(module: &HirModuleContext, data: &HirFunctionData) -> Vec<Spanned<String>> {
+    let mut paths = Vec::new();
+    let mut type_refs: Vec<&HirTypeRef> = data.header.return_type.iter().collect();
+
+    for (_, expr) in data.expr_arena.iter() {
+        match &expr.kind {
+            HirExprKind::Unresolved(name) if name.contains("::") => {
+                paths.push(Spanned::new(name.clone(), expr.span));
+            }
+            HirExprKind::Closure(closure) => type_refs.extend(closure.return_type.as_ref()),
+            _ => {}
+        }
+    }
+
+    for (_, statement) in data.statement_arena.iter() {
+        if let HirStatementKind::Destructure { type_ref, .. } = &statement.kind {
+            type_refs.extend(type_ref.as_ref());
+        }
+    }
+
+    for (_, variable) in data.variable_arena.iter() {
+        type_refs.extend(variable.type_ref.as_ref());
+    }
+
+    for type_ref in type_refs {
+        type_ref_paths(module, type_ref, &mut paths);
+    }
+
+    paths
+}
+
+// Name any structs within a type by their qualified names, if they belong to another module.
+fn qualify_type(qualifier: Option<&str>, ty: HirType) -> HirType {
+    let Some(qualifier) = qualifier else {
+        return ty;
+    };
+
+    match ty {
+        HirType::Struct(name) => HirType::Struct(format!("{}::{}", qualifier, name)),
+        HirType::Function(function) => HirType::Function(Box::new(HirFunctionType {
+            parameters: function
+                .parameters
+                .into_iter()
+                .map(|parameter| qualify_type(Some(qualifier), parameter))
+                .collect(),
+            return_type: qualify_type(Some(qualifier), function.return_type),
+        })),
+        HirType::Tuple(elements) => HirType::Tuple(
+            elements
+                .into_iter()
+                .map(|element| qualify_type(Some(qualifier), element))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+fn type_ref_paths(
+    module: &HirModuleContext,
+    type_ref: &HirTypeRef,
+    paths: &mut Vec<Spanned<String>>,
+) {
+    match &type_ref.kind {
+        HirTypeRefKind::Named(name) => {
+            let name = module.get_string(*name);
+            if name.contains("::") {
+                paths.push(Spanned::new(name.clone(), type_ref.span));
+            }
+        }
+        HirTypeRefKind::Function {
+            parameters,
+            return_type,
+        } => {
+            for type_ref in parameters.iter().chain(return_type.as_deref()) {
+                type_ref_paths(module, type_ref, paths);
+            }
+        }
+        HirTypeRefKind::Tuple(elements) => {
+            for element in elements {
+                type_ref_paths(module, element, paths);
+            }
+        }
+    }
+}
+
+fn parameter_paths(parameters: &[Spanned<ParameterDecl>], paths: &mut Vec<Spanned<String>>) {
+    for parameter in parameters {
+        type_name_paths(&parameter.value_ref().type_name, paths);
+    }
+}
+
+fn type_name_paths(type_name: &Spanned<TypeName>, paths: &mut Vec<Spanned<String>>) {
+    match type_name.value_ref() {
+        TypeName::Path(path) if !path.qualifier.is_empty() => {
+            paths.push(Spanned::new(path_to_string(path), type_name.span()));
+        }
+        TypeName::Path(_) => {}
+        TypeName::Function(function) => {
+            for parameter in function.parameters.value_ref().iter() {
+                type_name_paths(parameter, paths);
+            }
+            if let Some(return_type) = &function.return_type {
+                type_name_paths(return_type, paths);
+            }
+        }
+        TypeName::Tuple(tuple) => {
+            for element in tuple.elements.iter() {
+                type_name_paths(element, paths);
+            }
+        }
+    }
+}
+
+/// Compute a fingerprint of the given content.
+pub fn content_hash<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CompilerError;
+    use crate::hir::ty::HirType;
+
+    const SOURCE: &str = "
+        fn first(a: i32) -> i32 {
+            return a + 1;
+        }
+
+        fn second(b: i32) -> i32 {
+            return first(b) * 2;
+        }
+    ";
+
+    #[test]
+    fn unchanged_files_are_not_recomputed() {
+        // Given
+        let mut db = Database::new();
+        let file = db.add_file("test.hkl", SOURCE);
+        let function = FunctionId::new(file, "second");
+
+        // When
+        let first_result = db.type_of(&function).unwrap();
+        db.set_file_text(file, SOURCE);
+        let second_result = db.type_of(&function).unwrap();
+
+        // Then
+        assert!(Rc::ptr_eq(&first_result, &second_result));
+        assert_eq!(db.stats().parses, 1);
+        assert_eq!(db.stats().lowerings, 1);
+        assert_eq!(db.stats().type_checks, 1);
+    }
+
+    #[test]
+    fn editing_one_function_body_only_relowers_that_function() {
+        // Given
+        let mut db = Database::new();
+        let file = db.add_file("test.hkl", SOURCE);
+        let first = FunctionId::new(file, "first");
+        let second = FunctionId::new(file, "second");
+        db.lower_function(&first).unwrap();
+        let original_second = db.lower_function(&second).unwrap();
+
+        // When
+        let edited = SOURCE.replace("return a + 1;", "return a + 1000;");
+        db.set_file_text(file, &edited);
+        db.lower_function(&first).unwrap();
+        let shifted_second = db.lower_function(&second).unwrap();
+
+        // Then
+        assert_eq!(db.stats().parses, 2);
+        assert_eq!(db.stats().lowerings, 3);
+
+        let original_span = original_second
+            .get_statement(original_second.root_statement)
+            .span;
+        let shifted_span = shifted_second
+            .get_statement(shifted_second.root_statement)
+            .span;
+        assert_eq!(shifted_span.start(), original_span.start() + 3);
+        assert_eq!(shifted_span.end(), original_span.end() + 3);
+        assert_eq!(
+            &edited[shifted_span.range()],
+            &SOURCE[original_span.range()]
+        );
+    }
+
+    #[test]
+    fn editing_a_signature_relowers_dependent_functions() {
+        // Given
+        let mut db = Database::new();
+        let file = db.add_file("test.hkl", SOURCE);
+        let second = FunctionId::new(file, "second");
+        db.type_of(&second).unwrap();
+
+        // When
+        db.set_file_text(
+            file,
+            &SOURCE.replace("fn first(a: i32)", "fn first(a: i64)"),
+        );
+        let result = db.type_of(&second).unwrap();
+
+        // Then
+        assert_eq!(db.stats().lowerings, 2);
+        assert_eq!(db.stats().type_checks, 2);
+        assert_eq!(
+            result
+                .errors
+                .iter()
+                .map(|error| error.value())
+                .collect::<Vec<_>>(),
+            vec![CompilerError::TypeMismatch {
+                expected: "i64".to_string(),
+                actual: "i32".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn type_of_resolves_function_signatures() {
+        // Given
+        let mut db = Database::new();
+        let file = db.add_file("test.hkl", SOURCE);
+
+        // When
+        let result = db.type_of(&FunctionId::new(file, "first")).unwrap();
+
+        // Then
+        assert_eq!(result.signature.parameters, vec![HirType::I32]);
+        assert_eq!(result.signature.return_type, HirType::I32);
+        assert!(
+            result.errors.is_empty(),
+            "unexpected errors: {:?}",
+            result.errors
+        );
+    }
+
+    #[test]
+    fn missing_functions_produce_no_result() {
+        // Given
+        let mut db = Database::new();
+        let file = db.add_file("test.hkl", SOURCE);
+
+        // Then
+        assert!(db.lower_function(&FunctionId::new(file, "third")).is_none());
+        assert!(db.type_of(&FunctionId::new(file, "third")).is_none());
+    }
+
+    const SHAPES: &str = "
+        pub struct Square {
+            pub side: i32;
+        }
+
+        struct Secret {}
+
+        pub fn area(s: Square) -> i32 {
+            return s.side * s.side;
+        }
+
+        fn helper() = 1;
+    ";
+
+    fn error_values(errors: &[Spanned<CompilerError>]) -> Vec<CompilerError> {
+        errors.iter().map(|error| error.value()).collect()
+    }
+
+    #[test]
+    fn public_items_can_be_used_from_other_modules() {
+        // Given
+        let mut db = Database::new();
+        db.add_file("shapes.hkl", SHAPES);
+        let file = db.add_file(
+            "main.hkl",
+            "use shapes::area; fn main(s: shapes::Square) { shapes::area(s); std::println(); }",
+        );
+
+        // When
+        let result = db.type_of(&FunctionId::new(file, "main")).unwrap();
+
+        // Then
+        assert_eq!(error_values(&result.errors), vec![]);
+        assert_eq!(error_values(&db.module_errors(file)), vec![]);
+    }
+
+    #[test]
+    fn private_items_cannot_be_used_from_other_modules() {
+        // Given
+        let mut db = Database::new();
+        db.add_file("shapes.hkl", SHAPES);
+        let source = "fn main() { let s: (shapes::Secret,) = f(); let h = shapes::helper; }";
+        let file = db.add_file("main.hkl", source);
+
+        // When
+        let result = db.type_of(&FunctionId::new(file, "main")).unwrap();
+
+        // Then
+        assert_eq!(
+            error_values(&result.errors),
+            vec![
+                CompilerError::UnresolvedName("f".to_string()),
+                CompilerError::PrivateItem {
+                    name: "shapes::helper".to_string(),
+                    module: "shapes".to_string(),
+                },
+                CompilerError::PrivateItem {
+                    name: "shapes::Secret".to_string(),
+                    module: "shapes".to_string(),
+                },
+            ]
+        );
+        assert_eq!(&source[result.errors[1].span().range()], "shapes::helper");
+        assert_eq!(&source[result.errors[2].span().range()], "shapes::Secret");
+    }
+
+    #[test]
+    fn private_items_can_be_used_from_their_own_module() {
+        // Given
+        let mut db = Database::new();
+        let file = db.add_file("shapes.hkl", "fn a() = 1; fn b() = shapes::a();");
+
+        // When
+        let result = db.type_of(&FunctionId::new(file, "b")).unwrap();
+
+        // Then
+        assert_eq!(error_values(&result.errors), vec![]);
+    }
+
+    #[test]
+    fn declarations_cannot_refer_to_private_or_missing_items() {
+        // Given
+        let mut db = Database::new();
+        db.add_file("shapes.hkl", SHAPES);
+        let file = db.add_file(
+            "main.hkl",
+            "use shapes::helper; use shapes; struct S { s: shapes::Secret; } extern fn f(a: shapes::Circle);",
+        );
+
+        // When
+        let errors = db.module_errors(file);
+
+        // Then
+        assert_eq!(
+            error_values(&errors),
+            vec![
+                CompilerError::PrivateItem {
+                    name: "shapes::helper".to_string(),
+                    module: "shapes".to_string(),
+                },
+                CompilerError::PrivateItem {
+                    name: "shapes::Secret".to_string(),
+                    module: "shapes".to_string(),
+                },
+                CompilerError::UnresolvedName("shapes::Circle".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn paths_within_items_of_known_modules_are_reported() {
+        // Given
+        let mut db = Database::new();
+        db.add_file("shapes.hkl", SHAPES);
+        let source = "fn main() { let s = shapes::Square::new(1); let o = other::Thing::new(); }";
+        let file = db.add_file("main.hkl", source);
+
+        // When
+        let result = db.type_of(&FunctionId::new(file, "main")).unwrap();
+
+        // Then
+        assert_eq!(
+            error_values(&result.errors),
+            vec![CompilerError::UnresolvedName(
+                "shapes::Square::new".to_string()
+            )]
+        );
+        assert_eq!(
+            &source[result.errors[0].span().range()],
+            "shapes::Square::new"
+        );
+    }
+
+    #[test]
+    fn removed_modules_are_no_longer_resolved() {
+        // Given
+        let mut db = Database::new();
+        let shapes = db.add_file("shapes.hkl", SHAPES);
+        let file = db.add_file("main.hkl", "fn main() -> i32 { return shapes::area(1); }");
+        let main = FunctionId::new(file, "main");
+        let before = db.type_of(&main).unwrap();
+
+        // When
+        db.remove_file(shapes);
+        let after = db.type_of(&main).unwrap();
+
+        // Then
+        assert_eq!(
+            error_values(&before.errors),
+            vec![CompilerError::TypeMismatch {
+                expected: "shapes::Square".to_string(),
+                actual: "i32".to_string(),
+            }]
+        );
+        // Paths into unknown modules are left alone, as with any other missing module.
+        assert_eq!(error_values(&after.errors), vec![]);
+        assert_eq!(db.find_module("shapes"), None);
+        assert_eq!(db.files().collect::<Vec<_>>(), vec![file]);
+    }
+
+    #[test]
+    fn changing_visibility_in_another_module_rechecks_dependent_functions() {
+        // Given
+        let mut db = Database::new();
+        let shapes = db.add_file("shapes.hkl", SHAPES);
+        let file = db.add_file("main.hkl", "fn main() = shapes::helper();");
+        let main = FunctionId::new(file, "main");
+        let before = db.type_of(&main).unwrap();
+
+        // When
+        db.set_file_text(shapes, &SHAPES.replace("fn helper", "pub fn helper"));
+        let after = db.type_of(&main).unwrap();
+
+        // Then
+        assert_eq!(before.errors.len(), 1);
+        assert_eq!(error_values(&after.errors), vec![]);
+        assert_eq!(db.stats().lowerings, 1);
+        assert_eq!(db.stats().type_checks, 2);
+    }
+}
@@ -0,0 +1,186 @@
+//! Summaries of the top-level items declared in a file.
+//!
+//! Item trees deliberately omit function bodies. This means that they only change when a
+//! declaration is added, removed, or has its signature changed, which lets us avoid redoing
+//! work for every other item when a single function body is edited.
+use crate::db::database::content_hash;
+use crate::hir::context::path_to_string;
+use haikulang_parser::ast::attr::Attribute;
+use haikulang_parser::ast::func::ParameterDecl;
+use haikulang_parser::ast::printer::{print_attribute, print_type_name};
+use haikulang_parser::ast::types::TypeName;
+use haikulang_parser::ast::unit::{CompilationUnit, CompilationUnitMember};
+use haikulang_parser::ast::visibility::Visibility;
+use haikulang_parser::span::{Span, Spanned};
+
+/// The top-level items declared in a single file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ItemTree {
+    pub items: Vec<Item>,
+    /// Fingerprint of every signature in the file, ignoring function bodies and positions.
+    pub signature_hash: u64,
+}
+
+/// A single top-level item.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Item {
+    pub kind: ItemKind,
+    pub name: String,
+    pub signature: String,
+    pub span: Span,
+    /// Fingerprint of the full source text of the item, including any function body.
+    pub text_hash: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ItemKind {
+    Use,
+    ExternFunction,
+    Function,
+    Struct,
+}
+
+impl ItemTree {
+    pub fn new(unit: &CompilationUnit, source: &str) -> Self {
+        let items: Vec<Item> = unit
+            .members
+            .iter()
+            .map(|member| {
+                let (kind, name, signature) = describe_member(&member.value());
+                Item {
+                    kind,
+                    name,
+                    signature,
+                    span: member.span(),
+                    text_hash: content_hash(&source[member.span().range()]),
+                }
+            })
+            .collect();
+
+        let signature_hash = content_hash(
+            &items
+                .iter()
+                .map(|item| item.signature.as_str())
+                .collect::<Vec<_>>(),
+        );
+
+        Self {
+            items,
+            signature_hash,
+        }
+    }
+
+    /// An item tree for a file that could not be parsed.
+    pub fn empty() -> Self {
+        Self {
+            items: Vec::new(),
+            signature_hash: content_hash(&()),
+        }
+    }
+
+    pub fn functions(&self) -> impl Iterator<Item = &Item> {
+        self.items
+            .iter()
+            .filter(|item| item.kind == ItemKind::Function)
+    }
+
+    pub fn function(&self, name: &str) -> Option<&Item> {
+        self.functions().find(|item| item.name == name)
+    }
+}
+
+fn describe_member(member: &CompilationUnitMember) -> (ItemKind, String, String) {
+    match member {
+        CompilationUnitMember::Use(use_decl) => {
+            let path = path_to_string(&use_decl.path.value());
+            (ItemKind::Use, path.clone(), format!("use {}", path))
+        }
+        CompilationUnitMember::ExternFunction(function) => {
+            let name = function.name.value().value;
+            let signature = format!(
+                "{}{}extern fn {}{}",
+                attributes(&function.attributes),
+                visibility(function.visibility),
+                name,
+                describe_signature(&function.parameters.value(), &function.return_type)
+            );
+            (ItemKind::ExternFunction, name, signature)
+        }
+        CompilationUnitMember::Function(function) => {
+            let name = function.name.value().value;
+            let signature = format!(
+                "{}{}fn {}{}",
+                attributes(&function.attributes),
+                visibility(function.visibility),
+                name,
+                describe_signature(&function.parameters.value(), &function.return_type)
+            );
+            (ItemKind::Function, name, signature)
+        }
+        CompilationUnitMember::Struct(struct_decl) => {
+            let name = struct_decl.identifier.value().value;
+            let members = struct_decl
+                .members
+                .iter()
+                .map(|member| {
+                    format!(
+                        "{}{}{}: {};",
+                        attributes(&member.value_ref().attributes),
+                        visibility(member.value_ref().visibility),
+                        member.value().identifier.value().value,
+                        print_type_name(member.value_ref().type_name.value_ref())
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            let signature = format!(
+                "{}{}struct {} {{ {} }}",
+                attributes(&struct_decl.attributes),
+                visibility(struct_decl.visibility),
+                name,
+                members
+            );
+            (ItemKind::Struct, name, signature)
+        }
+    }
+}
+
+fn attributes(attributes: &[Spanned<Attribute>]) -> String {
+    attributes
+        .iter()
+        .map(|attribute| print_attribute(attribute.value_ref()) + " ")
+        .collect()
+}
+
+fn visibility(visibility: Visibility) -> &'static str {
+    match visibility {
+        Visibility::Public => "pub ",
+        Visibility::Private => "",
+    }
+}
+
+fn describe_signature(
+    parameters: &[Spanned<ParameterDecl>],
+    return_type: &Option<Spanned<TypeName>>,
+) -> String {
+    let parameters = parameters
+        .iter()
+        .map(|param| {
+            format!(
+                "{}: {}",
+                param.value().name.value().value,
+                print_type_name(param.value_ref().type_name.value_ref())
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    match return_type {
+        Some(return_type) => format!(
+            "({}) -> {}",
+            parameters,
+            print_type_name(return_type.value_ref())
+        ),
+        None => format!("({})", parameters),
+    }
+}
@@ -0,0 +1,8 @@
+//! Incremental, query-based compilation database.
+//!
+//! Rather than running every stage of compilation across every file each time something
+//! changes, tools ask the database questions such as "what does this file parse to?" or
+//! "what types does this function use?". Answers are memoized and only recomputed when the
+//! inputs they were derived from have actually changed.
+pub mod database;
+pub mod item_tree;
@@ -0,0 +1,349 @@
+//! Static HTML documentation.
+//!
+//! Each module is rendered to its own page, alongside an index page that lists every module
+//! and allows searching for items. Everything needed to browse the documentation is written
+//! to the output directory, so no network access is required.
+use crate::doc::search::search_index;
+use crate::doc::{DocField, DocItem, DocItemKind, DocModule, Documentation, OutputFile};
+use std::fmt::Write;
+use std::path::PathBuf;
+
+const STYLESHEET: &str = "\
+body { font-family: sans-serif; max-width: 60em; margin: 0 auto; padding: 1em; }
+pre.signature { background: #f4f4f4; padding: 0.5em; overflow-x: auto; }
+.item { margin-bottom: 2em; }
+.kind { color: #777; font-size: 0.8em; text-transform: uppercase; }
+#results li { margin: 0.25em 0; }
+";
+
+// Filters the search index as the user types. The index is loaded from a script rather than
+// fetched so that searching still works when the pages are opened directly from disk.
+const SEARCH_SCRIPT: &str = "\
+const input = document.getElementById('search');
+const results = document.getElementById('results');
+input.addEventListener('input', () => {
+  const query = input.value.trim().toLowerCase();
+  results.replaceChildren();
+  if (query === '') { return; }
+  for (const item of window.SEARCH_INDEX) {
+    if (!item.name.toLowerCase().includes(query)) { continue; }
+    const entry = document.createElement('li');
+    const link = document.createElement('a');
+    link.href = item.link;
+    link.textContent = item.module + '::' + item.name;
+    entry.append(link, ' (' + item.kind + ') ' + item.summary);
+    results.append(entry);
+  }
+});
+";
+
+pub fn render(docs: &Documentation) -> Vec<OutputFile> {
+    let index = search_index(docs, item_link);
+
+    let mut files = vec![
+        output("index.html", render_index(docs)),
+        output("style.css", STYLESHEET.to_string()),
+        output(
+            "search-index.js",
+            format!("window.SEARCH_INDEX = {};", index.trim_end()),
+        ),
+        output("search-index.json", index),
+    ];
+
+    for module in &docs.modules {
+        files.push(output(&module_file(module), render_module(docs, module)));
+    }
+
+    files
+}
+
+fn render_index(docs: &Documentation) -> String {
+    let mut body = String::new();
+    body.push_str("<h1>Documentation</h1>\n");
+    body.push_str("<input id=\"search\" type=\"search\" placeholder=\"Search...\">\n");
+    body.push_str("<ul id=\"results\"></ul>\n");
+    body.push_str("<h2>Modules</h2>\n<ul>\n");
+    for module in &docs.modules {
+        writeln!(
+            body,
+            "<li><a href=\"{}\">{}</a></li>",
+            escape(&module_file(module)),
+            escape(&module.name)
+        )
+        .unwrap();
+    }
+    body.push_str("</ul>\n");
+    body.push_str("<script src=\"search-index.js\"></script>\n");
+    writeln!(body, "<script>\n{}</script>", SEARCH_SCRIPT).unwrap();
+
+    page("Documentation", &body)
+}
+
+fn render_module(docs: &Documentation, module: &DocModule) -> String {
+    let mut body = String::new();
+    body.push_str("<p><a href=\"index.html\">Index</a></p>\n");
+    writeln!(body, "<h1>Module {}</h1>", escape(&module.name)).unwrap();
+    body.push_str(&doc_text(&module.doc));
+
+    for item in &module.items {
+        writeln!(
+            body,
+            "<div class=\"item\" id=\"{}\">\n<span class=\"kind\">{}</span>\n<h2>{}</h2>",
+            escape(&item.anchor()),
+            escape(item.kind_name()),
+            escape(&item.name)
+        )
+        .unwrap();
+        writeln!(
+            body,
+            "<pre class=\"signature\"><code>{}</code></pre>",
+            signature(docs, module, item)
+        )
+        .unwrap();
+        body.push_str(&doc_text(&item.doc));
+
+        match item.kind {
+            DocItemKind::Struct => fields(&mut body, docs, module, "Members", &item.members),
+            _ => fields(&mut body, docs, module, "Parameters", &item.parameters),
+        }
+
+        body.push_str("</div>\n");
+    }
+
+    page(&format!("Module {}", module.name), &body)
+}
+
+fn signature(docs: &Documentation, module: &DocModule, item: &DocItem) -> String {
+    let mut text = String::new();
+    if item.visibility.is_public() {
+        text.push_str("pub ");
+    }
+
+    match item.kind {
+        DocItemKind::Struct => {
+            writeln!(text, "struct {} {{", escape(&item.name)).unwrap();
+            for member in &item.members {
+                let visibility = if member.visibility.is_public() {
+                    "pub "
+                } else {
+                    ""
+                };
+                writeln!(
+                    text,
+                    "    {}{}: {};",
+                    visibility,
+                    escape(&member.name),
+                    type_link(docs, module, &member.type_name)
+                )
+                .unwrap();
+            }
+            text.push('}');
+        }
+        DocItemKind::Function | DocItemKind::ExternFunction => {
+            if item.kind == DocItemKind::ExternFunction {
+                text.push_str("extern ");
+            }
+            write!(text, "fn {}(", escape(&item.name)).unwrap();
+            for (index, parameter) in item.parameters.iter().enumerate() {
+                if index > 0 {
+                    text.push_str(", ");
+                }
+                write!(
+                    text,
+                    "{}: {}",
+                    escape(&parameter.name),
+                    type_link(docs, module, &parameter.type_name)
+                )
+                .unwrap();
+            }
+            text.push(')');
+            if let Some(return_type) = &item.return_type {
+                write!(text, " -&gt; {}", type_link(docs, module, return_type)).unwrap();
+            }
+        }
+    }
+
+    text
+}
+
+fn fields(
+    body: &mut String,
+    docs: &Documentation,
+    module: &DocModule,
+    heading: &str,
+    fields: &[DocField],
+) {
+    if fields.iter().all(|field| field.doc.is_none()) {
+        return;
+    }
+
+    writeln!(body, "<h3>{}</h3>\n<dl>", heading).unwrap();
+    for field in fields {
+        writeln!(
+            body,
+            "<dt><code>{}: {}</code></dt>\n<dd>{}</dd>",
+            escape(&field.name),
+            type_link(docs, module, &field.type_name),
+            doc_text(&field.doc)
+        )
+        .unwrap();
+    }
+    body.push_str("</dl>\n");
+}
+
+fn type_link(docs: &Documentation, module: &DocModule, type_name: &str) -> String {
+    match docs.resolve_type(&module.name, type_name) {
+        Some((target, item)) => format!(
+            "<a href=\"{}\">{}</a>",
+            escape(&item_link(target, item)),
+            escape(type_name)
+        ),
+        None => escape(type_name),
+    }
+}
+
+fn doc_text(doc: &Option<String>) -> String {
+    let mut html = String::new();
+    if let Some(doc) = doc {
+        for paragraph in doc.split("\n\n") {
+            writeln!(html, "<p>{}</p>", escape(paragraph)).unwrap();
+        }
+    }
+    html
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n\
+         <html>\n\
+         <head>\n\
+         <meta charset=\"utf-8\">\n\
+         <title>{}</title>\n\
+         <link rel=\"stylesheet\" href=\"style.css\">\n\
+         </head>\n\
+         <body>\n\
+         {}\
+         </body>\n\
+         </html>\n",
+        escape(title),
+        body
+    )
+}
+
+fn module_file(module: &DocModule) -> String {
+    format!("{}.html", module.name)
+}
+
+fn item_link(module: &DocModule, item: &DocItem) -> String {
+    format!("{}#{}", module_file(module), item.anchor())
+}
+
+fn output(path: &str, content: String) -> OutputFile {
+    OutputFile {
+        path: PathBuf::from(path),
+        content,
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doc::tests::documentation;
+    use std::path::Path;
+
+    fn file<'a>(files: &'a [OutputFile], path: &str) -> &'a str {
+        &files
+            .iter()
+            .find(|file| file.path == Path::new(path))
+            .unwrap_or_else(|| panic!("no file named {}", path))
+            .content
+    }
+
+    #[test]
+    fn all_files_are_produced() {
+        // Given
+        let docs = documentation(&[("a.hkl", "fn foo() {}"), ("b.hkl", "fn bar() {}")]);
+
+        // When
+        let files = render(&docs);
+
+        // Then
+        let paths: Vec<_> = files.iter().map(|file| file.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("index.html"),
+                PathBuf::from("style.css"),
+                PathBuf::from("search-index.js"),
+                PathBuf::from("search-index.json"),
+                PathBuf::from("a.html"),
+                PathBuf::from("b.html"),
+            ]
+        );
+        assert!(file(&files, "index.html").contains("<a href=\"a.html\">a</a>"));
+        assert!(file(&files, "search-index.js").starts_with("window.SEARCH_INDEX = ["));
+    }
+
+    #[test]
+    fn signatures_link_to_types() {
+        // Given
+        let docs = documentation(&[
+            ("shapes.hkl", "pub struct Point { pub x: i32; }"),
+            (
+                "main.hkl",
+                "//! Moving.\n/// Moves <things>.\n///\n/// Second paragraph.\npub fn shift(p: Point) -> Point { return p; }",
+            ),
+        ]);
+
+        // When
+        let files = render(&docs);
+
+        // Then
+        let page = file(&files, "main.html");
+        assert!(page.contains(
+            "<pre class=\"signature\"><code>pub fn shift(p: <a href=\"shapes.html#struct.Point\">Point</a>) -&gt; <a href=\"shapes.html#struct.Point\">Point</a></code></pre>"
+        ));
+        assert!(page.contains("<h1>Module main</h1>\n<p>Moving.</p>\n"));
+        assert!(page.contains("<p>Moves &lt;things&gt;.</p>\n<p>Second paragraph.</p>"));
+        assert!(page.contains("<div class=\"item\" id=\"fn.shift\">"));
+        assert!(
+            file(&files, "shapes.html")
+                .contains("<code>pub struct Point {\n    pub x: i32;\n}</code>")
+        );
+    }
+
+    #[test]
+    fn documented_members_are_listed() {
+        // Given
+        let docs = documentation(&[(
+            "m.hkl",
+            "struct Pair { /// The left.\n left: i32; right: Pair; }",
+        )]);
+
+        // When
+        let files = render(&docs);
+
+        // Then
+        let page = file(&files, "m.html");
+        assert!(page.contains("<h3>Members</h3>"));
+        assert!(page.contains("<dt><code>left: i32</code></dt>\n<dd><p>The left.</p>\n</dd>"));
+        assert!(page.contains(
+            "<dt><code>right: <a href=\"m.html#struct.Pair\">Pair</a></code></dt>\n<dd></dd>"
+        ));
+    }
+}
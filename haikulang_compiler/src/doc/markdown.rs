@@ -0,0 +1,279 @@
+//! Markdown documentation.
+//!
+//! Each module is rendered to its own file, alongside an index file that lists every module
+//! and item. Since Markdown cannot link from within code blocks, types are cross-linked in
+//! the parameter and member lists that follow each signature instead.
+use crate::doc::search::search_index;
+use crate::doc::{DocField, DocItem, DocItemKind, DocModule, Documentation, OutputFile};
+use haikulang_parser::ast::visibility::Visibility;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+pub fn render(docs: &Documentation) -> Vec<OutputFile> {
+    let mut files = vec![
+        output("index.md", render_index(docs)),
+        output("search-index.json", search_index(docs, item_link)),
+    ];
+
+    for module in &docs.modules {
+        files.push(output(&module_file(module), render_module(docs, module)));
+    }
+
+    files
+}
+
+fn render_index(docs: &Documentation) -> String {
+    let mut text = String::from("# Documentation\n");
+
+    for module in &docs.modules {
+        write!(
+            text,
+            "\n## [{}]({})\n\n",
+            escape(&module.name),
+            module_file(module)
+        )
+        .unwrap();
+        for item in &module.items {
+            write!(
+                text,
+                "- [`{}`]({}) ({})",
+                item.name,
+                item_link(module, item),
+                item.kind_name()
+            )
+            .unwrap();
+            let summary = item.summary();
+            if !summary.is_empty() {
+                write!(text, ": {}", summary).unwrap();
+            }
+            text.push('\n');
+        }
+    }
+
+    text
+}
+
+fn render_module(docs: &Documentation, module: &DocModule) -> String {
+    let mut text = String::new();
+    writeln!(
+        text,
+        "[Index](index.md)\n\n# Module {}",
+        escape(&module.name)
+    )
+    .unwrap();
+
+    if let Some(doc) = &module.doc {
+        write!(text, "\n{}\n", doc).unwrap();
+    }
+
+    for item in &module.items {
+        // Explicit anchors keep links identical to those used by the HTML output.
+        write!(
+            text,
+            "\n<a id=\"{}\"></a>\n\n## {} {}\n\n```\n{}\n```\n",
+            item.anchor(),
+            capitalize(item.kind_name()),
+            escape(&item.name),
+            signature(item)
+        )
+        .unwrap();
+
+        if let Some(doc) = &item.doc {
+            write!(text, "\n{}\n", doc).unwrap();
+        }
+
+        match item.kind {
+            DocItemKind::Struct => fields(&mut text, docs, module, "Members", &item.members),
+            _ => {
+                fields(&mut text, docs, module, "Parameters", &item.parameters);
+                if let Some(return_type) = &item.return_type {
+                    write!(
+                        text,
+                        "\n**Returns:** {}\n",
+                        type_link(docs, module, return_type)
+                    )
+                    .unwrap();
+                }
+            }
+        }
+    }
+
+    text
+}
+
+fn signature(item: &DocItem) -> String {
+    match item.kind {
+        DocItemKind::Struct => {
+            let mut text = format!("{}struct {} {{\n", visibility(item.visibility), item.name);
+            for member in &item.members {
+                writeln!(
+                    text,
+                    "    {}{}: {};",
+                    visibility(member.visibility),
+                    member.name,
+                    member.type_name
+                )
+                .unwrap();
+            }
+            text.push('}');
+            text
+        }
+        DocItemKind::Function | DocItemKind::ExternFunction => {
+            let parameters = item
+                .parameters
+                .iter()
+                .map(|parameter| format!("{}: {}", parameter.name, parameter.type_name))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let prefix = match item.kind {
+                DocItemKind::ExternFunction => "extern fn",
+                _ => "fn",
+            };
+            let prefix = format!("{}{}", visibility(item.visibility), prefix);
+            match &item.return_type {
+                Some(return_type) => {
+                    format!(
+                        "{} {}({}) -> {}",
+                        prefix, item.name, parameters, return_type
+                    )
+                }
+                None => format!("{} {}({})", prefix, item.name, parameters),
+            }
+        }
+    }
+}
+
+fn visibility(visibility: Visibility) -> &'static str {
+    if visibility.is_public() { "pub " } else { "" }
+}
+
+fn fields(
+    text: &mut String,
+    docs: &Documentation,
+    module: &DocModule,
+    heading: &str,
+    fields: &[DocField],
+) {
+    if fields.is_empty() {
+        return;
+    }
+
+    write!(text, "\n**{}:**\n\n", heading).unwrap();
+    for field in fields {
+        write!(
+            text,
+            "- `{}`: {}",
+            field.name,
+            type_link(docs, module, &field.type_name)
+        )
+        .unwrap();
+        if let Some(doc) = &field.doc {
+            write!(text, " - {}", doc.replace('\n', " ")).unwrap();
+        }
+        text.push('\n');
+    }
+}
+
+fn type_link(docs: &Documentation, module: &DocModule, type_name: &str) -> String {
+    match docs.resolve_type(&module.name, type_name) {
+        Some((target, item)) => format!("[`{}`]({})", type_name, item_link(target, item)),
+        None => format!("`{}`", type_name),
+    }
+}
+
+fn module_file(module: &DocModule) -> String {
+    format!("{}.md", module.name)
+}
+
+fn item_link(module: &DocModule, item: &DocItem) -> String {
+    format!("{}#{}", module_file(module), item.anchor())
+}
+
+fn output(path: &str, content: String) -> OutputFile {
+    OutputFile {
+        path: PathBuf::from(path),
+        content,
+    }
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// Escape characters that Markdown would otherwise interpret as formatting.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doc::tests::documentation;
+
+    #[test]
+    fn modules_are_rendered() {
+        // Given
+        let docs = documentation(&[
+            (
+                "shapes.hkl",
+                "/// A point.\npub struct Point { /// Across.\n pub x: i32; }",
+            ),
+            (
+                "main_module.hkl",
+                "//! Moving things.\n/// Moves a point.\npub fn shift(p: Point, by: i32) -> Point { return p; }",
+            ),
+        ]);
+
+        // When
+        let files = render(&docs);
+
+        // Then
+        let paths: Vec<_> = files.iter().map(|file| file.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("index.md"),
+                PathBuf::from("search-index.json"),
+                PathBuf::from("shapes.md"),
+                PathBuf::from("main_module.md"),
+            ]
+        );
+
+        assert_eq!(
+            files[0].content,
+            "# Documentation\n\
+             \n## [shapes](shapes.md)\n\n\
+             - [`Point`](shapes.md#struct.Point) (struct): A point.\n\
+             \n## [main\\_module](main_module.md)\n\n\
+             - [`shift`](main_module.md#fn.shift) (function): Moves a point.\n"
+        );
+
+        assert_eq!(
+            files[3].content,
+            "[Index](index.md)\n\n# Module main\\_module\n\
+             \nMoving things.\n\
+             \n<a id=\"fn.shift\"></a>\n\n## Function shift\n\n\
+             ```\npub fn shift(p: Point, by: i32) -> Point\n```\n\
+             \nMoves a point.\n\
+             \n**Parameters:**\n\n\
+             - `p`: [`Point`](shapes.md#struct.Point)\n\
+             - `by`: `i32`\n\
+             \n**Returns:** [`Point`](shapes.md#struct.Point)\n"
+        );
+
+        assert!(files[2].content.contains(
+            "```\npub struct Point {\n    pub x: i32;\n}\n```\n\nA point.\n\n**Members:**\n\n- `x`: `i32` - Across.\n"
+        ));
+    }
+}
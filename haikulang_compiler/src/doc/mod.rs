@@ -0,0 +1,356 @@
+//! Documentation generation.
+//!
+//! Documentation is collected from the doc comments attached to declarations in each
+//! compilation unit, and then rendered as a set of static files that can be browsed without
+//! any network access.
+pub mod html;
+pub mod markdown;
+pub mod search;
+
+use haikulang_parser::ast::doc::DocComment;
+use haikulang_parser::ast::func::ParameterDecl;
+use haikulang_parser::ast::printer::print_type_name;
+use haikulang_parser::ast::types::TypeName;
+use haikulang_parser::ast::unit::{CompilationUnit, CompilationUnitMember};
+use haikulang_parser::ast::visibility::Visibility;
+use haikulang_parser::span::Spanned;
+use std::path::PathBuf;
+
+/// The documented items across all modules.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Documentation {
+    pub modules: Vec<DocModule>,
+}
+
+/// The documented items within a single compilation unit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DocModule {
+    pub name: String,
+    pub doc: Option<String>,
+    pub items: Vec<DocItem>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DocItem {
+    pub kind: DocItemKind,
+    pub visibility: Visibility,
+    pub name: String,
+    pub doc: Option<String>,
+    pub parameters: Vec<DocField>,
+    pub return_type: Option<String>,
+    pub members: Vec<DocField>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DocItemKind {
+    Function,
+    ExternFunction,
+    Struct,
+}
+
+/// A parameter of a function, or a member of a struct.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DocField {
+    /// Always private for parameters, which cannot be marked as public.
+    pub visibility: Visibility,
+    pub name: String,
+    pub type_name: String,
+    pub doc: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DocFormat {
+    Html,
+    Markdown,
+}
+
+/// A file produced when rendering documentation, relative to the output directory.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutputFile {
+    pub path: PathBuf,
+    pub content: String,
+}
+
+impl Documentation {
+    pub fn new(units: &[CompilationUnit]) -> Self {
+        Self {
+            modules: units.iter().map(DocModule::new).collect(),
+        }
+    }
+
+    /// Render the documentation in the given format.
+    pub fn render(&self, format: DocFormat) -> Vec<OutputFile> {
+        match format {
+            DocFormat::Html => html::render(self),
+            DocFormat::Markdown => markdown::render(self),
+        }
+    }
+
+    /// Find the struct that a type name refers to, when used within the given module.
+    ///
+    /// Qualified names such as `foo::Bar` refer to the module named `foo`. Unqualified names
+    /// refer to the struct in the current module if there is one, or otherwise to a struct
+    /// in any other module with that name.
+    pub fn resolve_type(&self, module: &str, type_name: &str) -> Option<(&DocModule, &DocItem)> {
+        fn find<'a>(module: &'a DocModule, name: &str) -> Option<&'a DocItem> {
+            module
+                .items
+                .iter()
+                .find(|item| item.kind == DocItemKind::Struct && item.name == name)
+        }
+
+        if let Some((module_name, name)) = type_name.rsplit_once("::") {
+            let module = self.module(module_name)?;
+            return find(module, name).map(|item| (module, item));
+        }
+
+        let current = self.module(module).into_iter();
+        let others = self.modules.iter().filter(|other| other.name != module);
+        current
+            .chain(others)
+            .find_map(|module| find(module, type_name).map(|item| (module, item)))
+    }
+
+    fn module(&self, name: &str) -> Option<&DocModule> {
+        self.modules.iter().find(|module| module.name == name)
+    }
+}
+
+impl DocModule {
+    pub fn new(unit: &CompilationUnit) -> Self {
+        let name = unit
+            .path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| unit.name.clone());
+
+        let items = unit
+            .members
+            .iter()
+            .filter_map(|member| DocItem::new(&member.value()))
+            .collect();
+
+        Self {
+            name,
+            doc: doc_text(&unit.doc),
+            items,
+        }
+    }
+}
+
+impl DocItem {
+    fn new(member: &CompilationUnitMember) -> Option<Self> {
+        let item = match member {
+            CompilationUnitMember::Use(_) => return None,
+            CompilationUnitMember::ExternFunction(function) => Self {
+                kind: DocItemKind::ExternFunction,
+                visibility: function.visibility,
+                name: function.name.value().value,
+                doc: doc_text(&function.doc),
+                parameters: parameters(&function.parameters.value()),
+                return_type: return_type(&function.return_type),
+                members: Vec::new(),
+            },
+            CompilationUnitMember::Function(function) => Self {
+                kind: DocItemKind::Function,
+                visibility: function.visibility,
+                name: function.name.value().value,
+                doc: doc_text(&function.doc),
+                parameters: parameters(&function.parameters.value()),
+                return_type: return_type(&function.return_type),
+                members: Vec::new(),
+            },
+            CompilationUnitMember::Struct(struct_decl) => Self {
+                kind: DocItemKind::Struct,
+                visibility: struct_decl.visibility,
+                name: struct_decl.identifier.value().value,
+                doc: doc_text(&struct_decl.doc),
+                parameters: Vec::new(),
+                return_type: None,
+                members: struct_decl
+                    .members
+                    .iter()
+                    .map(|member| {
+                        let member = member.value();
+                        DocField {
+                            visibility: member.visibility,
+                            name: member.identifier.value().value,
+                            type_name: print_type_name(member.type_name.value_ref()),
+                            doc: doc_text(&member.doc),
+                        }
+                    })
+                    .collect(),
+            },
+        };
+
+        Some(item)
+    }
+
+    /// The identifier used to link to this item within its module page.
+    pub fn anchor(&self) -> String {
+        match self.kind {
+            DocItemKind::Function | DocItemKind::ExternFunction => format!("fn.{}", self.name),
+            DocItemKind::Struct => format!("struct.{}", self.name),
+        }
+    }
+
+    pub fn kind_name(&self) -> &'static str {
+        match self.kind {
+            DocItemKind::Function => "function",
+            DocItemKind::ExternFunction => "extern function",
+            DocItemKind::Struct => "struct",
+        }
+    }
+
+    /// The first paragraph of the documentation, if there is any.
+    pub fn summary(&self) -> String {
+        self.doc
+            .as_ref()
+            .map(|text| DocComment { text: text.clone() }.summary())
+            .unwrap_or_default()
+    }
+}
+
+fn doc_text(doc: &Option<Spanned<DocComment>>) -> Option<String> {
+    doc.as_ref()
+        .map(|doc| doc.value().text)
+        .filter(|text| !text.is_empty())
+}
+
+fn parameters(parameters: &[Spanned<ParameterDecl>]) -> Vec<DocField> {
+    parameters
+        .iter()
+        .map(|parameter| {
+            let parameter = parameter.value();
+            DocField {
+                visibility: Visibility::Private,
+                name: parameter.name.value().value,
+                type_name: print_type_name(parameter.type_name.value_ref()),
+                doc: doc_text(&parameter.doc),
+            }
+        })
+        .collect()
+}
+
+fn return_type(return_type: &Option<Spanned<TypeName>>) -> Option<String> {
+    return_type
+        .as_ref()
+        .map(|return_type| print_type_name(return_type.value_ref()))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use haikulang_parser::error::ParserError;
+    use haikulang_parser::lexer::token_stream::TokenStream;
+    use haikulang_parser::parser::core::Parser;
+    use std::path::Path;
+
+    pub(crate) fn documentation(sources: &[(&str, &str)]) -> Documentation {
+        let units: Vec<CompilationUnit> = sources
+            .iter()
+            .map(|(path, source)| {
+                let mut errors: Vec<Spanned<ParserError>> = Vec::new();
+                let mut parser =
+                    Parser::new(TokenStream::new(source), Path::new(path), &mut errors);
+                parser.parse().unwrap().value()
+            })
+            .collect();
+        Documentation::new(&units)
+    }
+
+    #[test]
+    fn items_are_collected_from_declarations() {
+        // When
+        let docs = documentation(&[(
+            "geometry.hkl",
+            "use std;\n\
+             /// A point.\n\
+             pub struct Point { /// Across.\n pub x: i32; y: i32; }\n\
+             /// Make a point.\n\
+             fn origin(/// Unused.\n z: i32) -> Point { return Point(0, 0); }\n\
+             extern fn print(fmt: string);",
+        )]);
+
+        // Then
+        assert_eq!(docs.modules.len(), 1);
+        let module = &docs.modules[0];
+        assert_eq!(module.name, "geometry");
+        assert_eq!(module.doc, None);
+        assert_eq!(
+            module.items,
+            vec![
+                DocItem {
+                    kind: DocItemKind::Struct,
+                    visibility: Visibility::Public,
+                    name: "Point".to_string(),
+                    doc: Some("A point.".to_string()),
+                    parameters: vec![],
+                    return_type: None,
+                    members: vec![
+                        DocField {
+                            visibility: Visibility::Public,
+                            name: "x".to_string(),
+                            type_name: "i32".to_string(),
+                            doc: Some("Across.".to_string()),
+                        },
+                        DocField {
+                            visibility: Visibility::Private,
+                            name: "y".to_string(),
+                            type_name: "i32".to_string(),
+                            doc: None,
+                        },
+                    ],
+                },
+                DocItem {
+                    kind: DocItemKind::Function,
+                    visibility: Visibility::Private,
+                    name: "origin".to_string(),
+                    doc: Some("Make a point.".to_string()),
+                    parameters: vec![DocField {
+                        visibility: Visibility::Private,
+                        name: "z".to_string(),
+                        type_name: "i32".to_string(),
+                        doc: Some("Unused.".to_string()),
+                    }],
+                    return_type: Some("Point".to_string()),
+                    members: vec![],
+                },
+                DocItem {
+                    kind: DocItemKind::ExternFunction,
+                    visibility: Visibility::Private,
+                    name: "print".to_string(),
+                    doc: None,
+                    parameters: vec![DocField {
+                        visibility: Visibility::Private,
+                        name: "fmt".to_string(),
+                        type_name: "string".to_string(),
+                        doc: None,
+                    }],
+                    return_type: None,
+                    members: vec![],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn types_resolve_across_modules() {
+        // Given
+        let docs = documentation(&[
+            ("a.hkl", "struct Shared {} struct Local {}"),
+            ("b.hkl", "struct Local {}"),
+        ]);
+
+        // Then
+        let resolve = |module, name| {
+            docs.resolve_type(module, name)
+                .map(|(module, item)| format!("{}::{}", module.name, item.name))
+        };
+        assert_eq!(resolve("b", "Shared"), Some("a::Shared".to_string()));
+        assert_eq!(resolve("b", "Local"), Some("b::Local".to_string()));
+        assert_eq!(resolve("b", "a::Local"), Some("a::Local".to_string()));
+        assert_eq!(resolve("b", "i32"), None);
+        assert_eq!(resolve("b", "c::Local"), None);
+    }
+}
@@ -0,0 +1,104 @@
+//! Search index covering every documented item.
+//!
+//! The index is a JSON array of objects with the following fields:
+//!
+//! - `name` - the name of the item.
+//! - `kind` - one of `function`, `extern function` or `struct`.
+//! - `module` - the name of the module declaring the item.
+//! - `link` - the path to the item, relative to the output directory.
+//! - `summary` - the first paragraph of the item documentation, or an empty string.
+use crate::doc::{DocItem, DocModule, Documentation};
+use std::fmt::Write;
+
+/// Produce the JSON search index, using the given function to produce links to items.
+pub fn search_index(docs: &Documentation, link: impl Fn(&DocModule, &DocItem) -> String) -> String {
+    let mut json = String::from("[");
+
+    for (index, (module, item)) in docs
+        .modules
+        .iter()
+        .flat_map(|module| module.items.iter().map(move |item| (module, item)))
+        .enumerate()
+    {
+        if index > 0 {
+            json.push(',');
+        }
+
+        write!(
+            json,
+            "\n  {{\"name\": {}, \"kind\": {}, \"module\": {}, \"link\": {}, \"summary\": {}}}",
+            json_string(&item.name),
+            json_string(item.kind_name()),
+            json_string(&module.name),
+            json_string(&link(module, item)),
+            json_string(&item.summary()),
+        )
+        .unwrap();
+    }
+
+    json.push_str("\n]\n");
+    json
+}
+
+pub(crate) fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            // Escaping these means the index can also be embedded safely within a script tag.
+            '<' | '>' | '&' => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c if c.is_control() => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::doc::tests::documentation;
+    use test_case::test_case;
+
+    #[test_case(          "foo",               "\"foo\"" ; "plain text")]
+    #[test_case(     "a \"b\" c",       "\"a \\\"b\\\" c\"" ; "quotes")]
+    #[test_case(     "a\\b\nc\t",     "\"a\\\\b\\nc\\t\"" ; "escapes")]
+    #[test_case(    "</script>", "\"\\u003c/script\\u003e\"" ; "markup")]
+    #[test_case(         "\u{1}",            "\"\\u0001\"" ; "control characters")]
+    #[test_case(           "ü",                   "\"ü\"" ; "unicode")]
+    fn strings_are_escaped(value: &str, expected: &str) {
+        // Then
+        assert_eq!(json_string(value), expected);
+    }
+
+    #[test]
+    fn index_contains_every_item() {
+        // Given
+        let docs = documentation(&[(
+            "m.hkl",
+            "/// Does things.\n///\n/// More detail.\nfn run() {}\nstruct Data {}",
+        )]);
+
+        // When
+        let index = search_index(&docs, |module, item| {
+            format!("{}#{}", module.name, item.anchor())
+        });
+
+        // Then
+        assert_eq!(
+            index,
+            "[\n  \
+             {\"name\": \"run\", \"kind\": \"function\", \"module\": \"m\", \"link\": \"m#fn.run\", \"summary\": \"Does things.\"},\n  \
+             {\"name\": \"Data\", \"kind\": \"struct\", \"module\": \"m\", \"link\": \"m#struct.Data\", \"summary\": \"\"}\
+             \n]\n"
+        );
+    }
+}
@@ -0,0 +1,450 @@
+use haikulang_parser::span::Spanned;
+use std::fmt::{Display, Formatter};
+
+pub type CompilerResult<T> = Result<T, Spanned<CompilerError>>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CompilerError {
+    // Name resolution issues.
+    DuplicateDefinition(String),
+    UnresolvedName(String),
+    UnknownType(String),
+    CapturedVariableAssignment(String),
+    PrivateItem {
+        name: String,
+        module: String,
+    },
+
+    // Declaration issues.
+    InvalidAttribute {
+        name: String,
+        reason: String,
+    },
+
+    // Type checking issues.
+    TypeMismatch {
+        expected: String,
+        actual: String,
+    },
+    // A variable that only got its type from the default for unsuffixed integer literals was
+    // used where another integer type was expected.
+    DefaultedIntegerMismatch {
+        variable: String,
+        expected: String,
+    },
+    IntLiteralOutOfRange {
+        literal: String,
+        ty: String,
+    },
+    InvalidOperand(String),
+    InvalidAssignmentTarget,
+    ArgumentCountMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    NotCallable(String),
+    UnknownMember {
+        owner: String,
+        member: String,
+    },
+    TypeAnnotationNeeded(String),
+
+    // Format string issues.
+    InvalidFormatString(String),
+    FormatArgumentCountMismatch {
+        placeholders: usize,
+        arguments: usize,
+    },
+    NotFormattable(String),
+
+    // Code generation issues.
+    UnsupportedByTarget {
+        feature: String,
+        target: String,
+    },
+}
+
+impl Display for CompilerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::DuplicateDefinition(name) => write!(f, "{} is already defined", name),
+            Self::UnresolvedName(name) => write!(f, "cannot find {} in this scope", name),
+            Self::UnknownType(name) => write!(f, "unknown type {}", name),
+            Self::CapturedVariableAssignment(name) => {
+                write!(
+                    f,
+                    "cannot assign to {}, as it is captured by a closure",
+                    name
+                )
+            }
+            Self::PrivateItem { name, module } => {
+                write!(f, "{} is private to module {}", name, module)
+            }
+            Self::InvalidAttribute { name, reason } => {
+                write!(f, "invalid attribute #[{}], as {}", name, reason)
+            }
+            Self::TypeMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "mismatched types: expected {}, found {}",
+                    expected, actual
+                )
+            }
+            Self::DefaultedIntegerMismatch { variable, expected } => write!(
+                f,
+                "mismatched types: expected {}, found i32, as {} was initialized with an \
+                 unsuffixed integer literal, which defaults to i32; add a suffix to the literal \
+                 or give {} a type",
+                expected, variable, variable
+            ),
+            Self::IntLiteralOutOfRange { literal, ty } => {
+                write!(f, "integer literal {} is out of range for {}", literal, ty)
+            }
+            Self::InvalidOperand(text) => write!(f, "invalid operand: {}", text),
+            Self::InvalidAssignmentTarget => write!(f, "invalid left-hand side of assignment"),
+            Self::ArgumentCountMismatch { expected, actual } => write!(
+                f,
+                "wrong number of arguments: expected {}, found {}",
+                expected, actual
+            ),
+            Self::NotCallable(name) => write!(f, "{} is not callable", name),
+            Self::UnknownMember { owner, member } => {
+                write!(f, "type {} has no member named {}", owner, member)
+            }
+            Self::TypeAnnotationNeeded(name) => {
+                write!(
+                    f,
+                    "cannot infer the type of {}, so it needs a type annotation",
+                    name
+                )
+            }
+            Self::InvalidFormatString(reason) => write!(f, "invalid format string: {}", reason),
+            Self::FormatArgumentCountMismatch {
+                placeholders,
+                arguments,
+            } => write!(
+                f,
+                "the format string has {} placeholder(s), but {} argument(s) were given",
+                placeholders, arguments
+            ),
+            Self::NotFormattable(ty) => write!(f, "values of type {} cannot be formatted", ty),
+            Self::UnsupportedByTarget { feature, target } => {
+                write!(f, "{} cannot be compiled to {} yet", feature, target)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CompilerWarning {
+    UnknownAttribute(String),
+    Deprecated { name: String, note: Option<String> },
+}
+
+impl Display for CompilerWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownAttribute(name) => {
+                write!(f, "unknown attribute #[{}] will be ignored", name)
+            }
+            Self::Deprecated { name, note: None } => write!(f, "{} is deprecated", name),
+            Self::Deprecated {
+                name,
+                note: Some(note),
+            } => write!(f, "{} is deprecated: {}", name, note),
+        }
+    }
+}
+
+pub type RuntimeResult<T> = Result<T, Spanned<RuntimeError>>;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RuntimeError {
+    ArithmeticOverflow(String),
+    AssertionFailed(String),
+    DivisionByZero,
+    Exit(i32),
+    IndexOutOfBounds { index: String, length: usize },
+    MissingKey(String),
+    UnknownFunction(String),
+    StackOverflow,
+    Unsupported(String),
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ArithmeticOverflow(text) => write!(f, "arithmetic overflow: {}", text),
+            Self::AssertionFailed(text) => write!(f, "assertion failed: {}", text),
+            Self::DivisionByZero => write!(f, "attempted to divide by zero"),
+            Self::Exit(code) => write!(f, "the program exited with code {}", code),
+            Self::IndexOutOfBounds { index, length } => write!(
+                f,
+                "index {} is out of bounds for a length of {}",
+                index, length
+            ),
+            Self::MissingKey(key) => write!(f, "no entry exists for the key {:?}", key),
+            Self::UnknownFunction(name) => write!(f, "no function named {} is available", name),
+            Self::StackOverflow => write!(f, "stack overflow"),
+            Self::Unsupported(text) => write!(f, "unsupported operation: {}", text),
+        }
+    }
+}
+
+/// Problems with a bytecode program, found while reading it or verifying it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BytecodeError {
+    BadMagic,
+    UnsupportedVersion(u16),
+    UnexpectedEnd,
+    TrailingData,
+    InvalidOpcode(u8),
+    InvalidConstantTag(u8),
+    InvalidText,
+    /// The program could be read, but running it could misbehave. The location describes
+    /// the part of the program that is wrong, such as an instruction within a function.
+    Invalid {
+        location: String,
+        reason: String,
+    },
+}
+
+impl Display for BytecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BadMagic => write!(f, "not a haikulang bytecode file"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported bytecode format version {}", version)
+            }
+            Self::UnexpectedEnd => write!(f, "the bytecode ended unexpectedly"),
+            Self::TrailingData => write!(f, "unexpected data after the end of the bytecode"),
+            Self::InvalidOpcode(opcode) => write!(f, "invalid opcode 0x{:02x}", opcode),
+            Self::InvalidConstantTag(tag) => write!(f, "invalid constant tag 0x{:02x}", tag),
+            Self::InvalidText => write!(f, "the bytecode contains text that is not valid"),
+            Self::Invalid { location, reason } => {
+                write!(f, "invalid bytecode in {}: {}", location, reason)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(
+        CompilerError::DuplicateDefinition("foo".to_string()),
+        "foo is already defined"
+        ; "DuplicateDefinition"
+    )]
+    #[test_case(
+        CompilerError::UnresolvedName("foo".to_string()),
+        "cannot find foo in this scope"
+        ; "UnresolvedName"
+    )]
+    #[test_case(
+        CompilerError::UnknownType("Foo".to_string()),
+        "unknown type Foo"
+        ; "UnknownType"
+    )]
+    #[test_case(
+        CompilerError::CapturedVariableAssignment("x".to_string()),
+        "cannot assign to x, as it is captured by a closure"
+        ; "CapturedVariableAssignment"
+    )]
+    #[test_case(
+        CompilerError::PrivateItem { name: "shapes::area".to_string(), module: "shapes".to_string() },
+        "shapes::area is private to module shapes"
+        ; "PrivateItem"
+    )]
+    #[test_case(
+        CompilerError::InvalidAttribute { name: "test".to_string(), reason: "it cannot be applied to structs".to_string() },
+        "invalid attribute #[test], as it cannot be applied to structs"
+        ; "InvalidAttribute"
+    )]
+    #[test_case(
+        CompilerError::TypeMismatch { expected: "i32".to_string(), actual: "bool".to_string() },
+        "mismatched types: expected i32, found bool"
+        ; "TypeMismatch"
+    )]
+    #[test_case(
+        CompilerError::DefaultedIntegerMismatch { variable: "x".to_string(), expected: "i64".to_string() },
+        "mismatched types: expected i64, found i32, as x was initialized with an unsuffixed integer literal, which defaults to i32; add a suffix to the literal or give x a type"
+        ; "DefaultedIntegerMismatch"
+    )]
+    #[test_case(
+        CompilerError::IntLiteralOutOfRange { literal: "256".to_string(), ty: "u8".to_string() },
+        "integer literal 256 is out of range for u8"
+        ; "IntLiteralOutOfRange"
+    )]
+    #[test_case(
+        CompilerError::InvalidOperand("cannot negate bool".to_string()),
+        "invalid operand: cannot negate bool"
+        ; "InvalidOperand"
+    )]
+    #[test_case(
+        CompilerError::InvalidAssignmentTarget,
+        "invalid left-hand side of assignment"
+        ; "InvalidAssignmentTarget"
+    )]
+    #[test_case(
+        CompilerError::ArgumentCountMismatch { expected: 2, actual: 3 },
+        "wrong number of arguments: expected 2, found 3"
+        ; "ArgumentCountMismatch"
+    )]
+    #[test_case(
+        CompilerError::NotCallable("i32".to_string()),
+        "i32 is not callable"
+        ; "NotCallable"
+    )]
+    #[test_case(
+        CompilerError::UnknownMember { owner: "Foo".to_string(), member: "bar".to_string() },
+        "type Foo has no member named bar"
+        ; "UnknownMember"
+    )]
+    #[test_case(
+        CompilerError::TypeAnnotationNeeded("x".to_string()),
+        "cannot infer the type of x, so it needs a type annotation"
+        ; "TypeAnnotationNeeded"
+    )]
+    #[test_case(
+        CompilerError::InvalidFormatString("unmatched }".to_string()),
+        "invalid format string: unmatched }"
+        ; "InvalidFormatString"
+    )]
+    #[test_case(
+        CompilerError::FormatArgumentCountMismatch { placeholders: 2, arguments: 1 },
+        "the format string has 2 placeholder(s), but 1 argument(s) were given"
+        ; "FormatArgumentCountMismatch"
+    )]
+    #[test_case(
+        CompilerError::NotFormattable("fn()".to_string()),
+        "values of type fn() cannot be formatted"
+        ; "NotFormattable"
+    )]
+    #[test_case(
+        CompilerError::UnsupportedByTarget { feature: "strings".to_string(), target: "WebAssembly".to_string() },
+        "strings cannot be compiled to WebAssembly yet"
+        ; "UnsupportedByTarget"
+    )]
+    fn test_compiler_error_formats_correctly(error: CompilerError, expected: &str) {
+        // Then
+        assert_eq!(format!("{}", error), expected);
+    }
+
+    #[test_case(
+        CompilerWarning::UnknownAttribute("frobnicate".to_string()),
+        "unknown attribute #[frobnicate] will be ignored"
+        ; "UnknownAttribute"
+    )]
+    #[test_case(
+        CompilerWarning::Deprecated { name: "foo".to_string(), note: None },
+        "foo is deprecated"
+        ; "Deprecated"
+    )]
+    #[test_case(
+        CompilerWarning::Deprecated { name: "foo".to_string(), note: Some("use bar".to_string()) },
+        "foo is deprecated: use bar"
+        ; "Deprecated with a note"
+    )]
+    fn test_compiler_warning_formats_correctly(warning: CompilerWarning, expected: &str) {
+        // Then
+        assert_eq!(format!("{}", warning), expected);
+    }
+
+    #[test_case(
+        RuntimeError::ArithmeticOverflow("2147483647 + 1".to_string()),
+        "arithmetic overflow: 2147483647 + 1"
+        ; "ArithmeticOverflow"
+    )]
+    #[test_case(
+        RuntimeError::AssertionFailed("x > 1".to_string()),
+        "assertion failed: x > 1"
+        ; "AssertionFailed"
+    )]
+    #[test_case(
+        RuntimeError::DivisionByZero,
+        "attempted to divide by zero"
+        ; "DivisionByZero"
+    )]
+    #[test_case(
+        RuntimeError::Exit(3),
+        "the program exited with code 3"
+        ; "Exit"
+    )]
+    #[test_case(
+        RuntimeError::IndexOutOfBounds { index: "3".to_string(), length: 2 },
+        "index 3 is out of bounds for a length of 2"
+        ; "IndexOutOfBounds"
+    )]
+    #[test_case(
+        RuntimeError::MissingKey("foo".to_string()),
+        "no entry exists for the key \"foo\""
+        ; "MissingKey"
+    )]
+    #[test_case(
+        RuntimeError::UnknownFunction("foo".to_string()),
+        "no function named foo is available"
+        ; "UnknownFunction"
+    )]
+    #[test_case(
+        RuntimeError::StackOverflow,
+        "stack overflow"
+        ; "StackOverflow"
+    )]
+    #[test_case(
+        RuntimeError::Unsupported("indexing".to_string()),
+        "unsupported operation: indexing"
+        ; "Unsupported"
+    )]
+    fn test_runtime_error_formats_correctly(error: RuntimeError, expected: &str) {
+        // Then
+        assert_eq!(format!("{}", error), expected);
+    }
+
+    #[test_case(
+        BytecodeError::BadMagic,
+        "not a haikulang bytecode file"
+        ; "BadMagic"
+    )]
+    #[test_case(
+        BytecodeError::UnsupportedVersion(9),
+        "unsupported bytecode format version 9"
+        ; "UnsupportedVersion"
+    )]
+    #[test_case(
+        BytecodeError::UnexpectedEnd,
+        "the bytecode ended unexpectedly"
+        ; "UnexpectedEnd"
+    )]
+    #[test_case(
+        BytecodeError::TrailingData,
+        "unexpected data after the end of the bytecode"
+        ; "TrailingData"
+    )]
+    #[test_case(
+        BytecodeError::InvalidOpcode(0xfe),
+        "invalid opcode 0xfe"
+        ; "InvalidOpcode"
+    )]
+    #[test_case(
+        BytecodeError::InvalidConstantTag(0x7f),
+        "invalid constant tag 0x7f"
+        ; "InvalidConstantTag"
+    )]
+    #[test_case(
+        BytecodeError::InvalidText,
+        "the bytecode contains text that is not valid"
+        ; "InvalidText"
+    )]
+    #[test_case(
+        BytecodeError::Invalid { location: "fn main at 3".to_string(), reason: "the stack underflows".to_string() },
+        "invalid bytecode in fn main at 3: the stack underflows"
+        ; "Invalid"
+    )]
+    fn test_bytecode_error_formats_correctly(error: BytecodeError, expected: &str) {
+        // Then
+        assert_eq!(format!("{}", error), expected);
+    }
+}
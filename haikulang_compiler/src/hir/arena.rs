@@ -1,3 +1,4 @@
+use std::borrow::Borrow;
 use std::collections::HashMap;
 use std::hash::Hash;
 
@@ -11,6 +12,12 @@ pub type Arena<T> = la_arena::Arena<T>;
 /// Alias to the arena ID implementation so we can more easily swap it out later.
 pub type Id<T> = la_arena::Idx<T>;
 
+/// Alias to the arena map implementation so we can more easily swap it out later.
+///
+/// This associates additional data with IDs from an existing arena, such as the types we
+/// infer for each expression.
+pub type ArenaMap<K, V> = la_arena::ArenaMap<K, V>;
+
 /// Helper data structure that enables interning special types like Strings so we only keep them
 /// in memory once. This also allows us to only declare special values in data sections later once
 /// when their values may be duplicated.
@@ -47,4 +54,24 @@ impl<T: Clone + Eq + Hash> InterningArena<T> {
     pub fn get(&self, id: Id<T>) -> &T {
         &self.arena[id]
     }
+
+    /// Find the ID of a value that was previously interned, without interning it.
+    pub fn lookup<Q>(&self, value: &Q) -> Option<Id<T>>
+    where
+        T: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.id_mapping.get(value).copied()
+    }
+
+    /// Iterate over every interned value, in the order that they were first interned.
+    pub fn iter(&self) -> impl Iterator<Item = (Id<T>, &T)> {
+        self.arena.iter()
+    }
+}
+
+impl<T: Clone + Eq + Hash> Default for InterningArena<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
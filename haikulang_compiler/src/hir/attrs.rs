@@ -0,0 +1,298 @@
+//! The attributes that the compiler understands.
+//!
+//! Each known attribute is described by an entry in a registry, stating which declarations it
+//! can be applied to and which arguments it takes. Attributes that are not in the registry are
+//! reported as warnings and otherwise ignored.
+use crate::error::{CompilerError, CompilerWarning};
+use haikulang_parser::ast::attr::Attribute;
+use haikulang_parser::ast::expr::Expr;
+use haikulang_parser::span::Spanned;
+use std::fmt::{Display, Formatter};
+
+/// The kinds of declaration that attributes can be applied to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HirAttributeTarget {
+    Function,
+    ExternFunction,
+    Struct,
+    StructMember,
+}
+
+impl Display for HirAttributeTarget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Function => write!(f, "functions"),
+            Self::ExternFunction => write!(f, "extern functions"),
+            Self::Struct => write!(f, "structs"),
+            Self::StructMember => write!(f, "struct members"),
+        }
+    }
+}
+
+/// The known attributes applied to a declaration.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HirAttributes {
+    /// Set by `#[test]`, marking a function to be run by the test runner.
+    pub test: bool,
+    /// Set by `#[inline]`, hinting that calls to a function should be inlined.
+    pub inline: bool,
+    /// Set by `#[deprecated]` or `#[deprecated("note")]`.
+    pub deprecated: Option<HirDeprecation>,
+    /// Set by `#[link_name("name")]`, giving the name of the symbol that an extern function
+    /// refers to when it differs from the name of the function.
+    pub link_name: Option<String>,
+}
+
+/// Details of a deprecated declaration.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HirDeprecation {
+    /// Explanation of what to use instead, if one was given.
+    pub note: Option<String>,
+}
+
+// The arguments that an attribute accepts.
+enum Arguments {
+    None,
+    OptionalString,
+    String,
+}
+
+struct AttributeSpec {
+    name: &'static str,
+    targets: &'static [HirAttributeTarget],
+    arguments: Arguments,
+}
+
+const REGISTRY: &[AttributeSpec] = &[
+    AttributeSpec {
+        name: "test",
+        targets: &[HirAttributeTarget::Function],
+        arguments: Arguments::None,
+    },
+    AttributeSpec {
+        name: "inline",
+        targets: &[HirAttributeTarget::Function],
+        arguments: Arguments::None,
+    },
+    AttributeSpec {
+        name: "deprecated",
+        targets: &[
+            HirAttributeTarget::Function,
+            HirAttributeTarget::ExternFunction,
+            HirAttributeTarget::Struct,
+            HirAttributeTarget::StructMember,
+        ],
+        arguments: Arguments::OptionalString,
+    },
+    AttributeSpec {
+        name: "link_name",
+        targets: &[HirAttributeTarget::ExternFunction],
+        arguments: Arguments::String,
+    },
+];
+
+/// Validate the attributes applied to a declaration against the registry, collecting the
+/// ones that are understood.
+pub fn lower_attributes(
+    attributes: &[Spanned<Attribute>],
+    target: HirAttributeTarget,
+    errors: &mut Vec<Spanned<CompilerError>>,
+    warnings: &mut Vec<Spanned<CompilerWarning>>,
+) -> HirAttributes {
+    let mut lowered = HirAttributes::default();
+    let mut seen = Vec::new();
+
+    for attribute in attributes {
+        let span = attribute.span();
+        let name = attribute.value_ref().name.value_ref().value.as_str();
+        let invalid = |reason: String| {
+            let error = CompilerError::InvalidAttribute {
+                name: name.to_string(),
+                reason,
+            };
+            Spanned::new(error, span)
+        };
+
+        let Some(spec) = REGISTRY.iter().find(|spec| spec.name == name) else {
+            warnings.push(Spanned::new(
+                CompilerWarning::UnknownAttribute(name.to_string()),
+                span,
+            ));
+            continue;
+        };
+
+        if !spec.targets.contains(&target) {
+            errors.push(invalid(format!("it cannot be applied to {}", target)));
+            continue;
+        }
+
+        if seen.contains(&name) {
+            errors.push(invalid("it is applied more than once".to_string()));
+            continue;
+        }
+        seen.push(name);
+
+        // Attributes without arguments reject any at all, whatever kind of value they are.
+        let has_arguments = attribute
+            .value_ref()
+            .arguments
+            .as_ref()
+            .is_some_and(|arguments| !arguments.value_ref().is_empty());
+        if matches!(spec.arguments, Arguments::None) && has_arguments {
+            errors.push(invalid("it does not take any arguments".to_string()));
+            continue;
+        }
+
+        let Some(arguments) = string_arguments(attribute.value_ref()) else {
+            errors.push(invalid("its arguments must be string literals".to_string()));
+            continue;
+        };
+
+        let argument = match (&spec.arguments, arguments.as_slice()) {
+            (Arguments::None, []) => None,
+            (Arguments::OptionalString, [] | [_]) => arguments.into_iter().next(),
+            (Arguments::String, [_]) => arguments.into_iter().next(),
+            (Arguments::None, _) => {
+                errors.push(invalid("it does not take any arguments".to_string()));
+                continue;
+            }
+            (Arguments::OptionalString, _) => {
+                errors.push(invalid("it takes at most one argument".to_string()));
+                continue;
+            }
+            (Arguments::String, _) => {
+                errors.push(invalid("it takes exactly one argument".to_string()));
+                continue;
+            }
+        };
+
+        match spec.name {
+            "test" => lowered.test = true,
+            "inline" => lowered.inline = true,
+            "deprecated" => lowered.deprecated = Some(HirDeprecation { note: argument }),
+            "link_name" => lowered.link_name = argument,
+            _ => unreachable!("every registered attribute is handled"),
+        }
+    }
+
+    lowered
+}
+
+// The arguments of an attribute, or None if any of them are not string literals.
+fn string_arguments(attribute: &Attribute) -> Option<Vec<String>> {
+    let Some(arguments) = &attribute.arguments else {
+        return Some(Vec::new());
+    };
+
+    arguments
+        .value_ref()
+        .iter()
+        .map(|argument| match argument.value_ref() {
+            Expr::String(string) => Some(string.value.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use haikulang_parser::ast::unit::CompilationUnitMember;
+    use haikulang_parser::error::Diagnostics;
+    use haikulang_parser::lexer::token_stream::TokenStream;
+    use haikulang_parser::parser::core::Parser;
+    use std::path::Path;
+    use test_case::test_case;
+
+    fn lower(attributes: &str) -> (HirAttributes, Vec<CompilerError>, Vec<CompilerWarning>) {
+        let source = format!("{} fn f() {{}}", attributes);
+        let mut diagnostics = Diagnostics::default();
+        let unit = Parser::new(
+            TokenStream::new(&source),
+            Path::new("test.hkl"),
+            &mut diagnostics,
+        )
+        .parse()
+        .unwrap()
+        .value();
+        let CompilationUnitMember::Function(function) = unit.members[0].value() else {
+            panic!("expected a function");
+        };
+
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        let lowered = lower_attributes(
+            &function.attributes,
+            HirAttributeTarget::Function,
+            &mut errors,
+            &mut warnings,
+        );
+
+        (
+            lowered,
+            errors.iter().map(Spanned::value).collect(),
+            warnings.iter().map(Spanned::value).collect(),
+        )
+    }
+
+    #[test]
+    fn known_attributes_are_collected() {
+        // When
+        let (lowered, errors, warnings) = lower("#[test] #[inline()] #[deprecated(\"use g\")]");
+
+        // Then
+        assert_eq!(
+            lowered,
+            HirAttributes {
+                test: true,
+                inline: true,
+                deprecated: Some(HirDeprecation {
+                    note: Some("use g".to_string())
+                }),
+                link_name: None,
+            }
+        );
+        assert_eq!(errors, vec![]);
+        assert_eq!(warnings, vec![]);
+    }
+
+    #[test]
+    fn unknown_attributes_are_warnings() {
+        // When
+        let (lowered, errors, warnings) = lower("#[deprecated] #[frobnicate(1, 2)]");
+
+        // Then
+        assert_eq!(lowered.deprecated, Some(HirDeprecation { note: None }));
+        assert_eq!(errors, vec![]);
+        assert_eq!(
+            warnings,
+            vec![CompilerWarning::UnknownAttribute("frobnicate".to_string())]
+        );
+    }
+
+    #[test_case(      "#[link_name(\"f\")]", "it cannot be applied to functions" ; "wrong target")]
+    #[test_case(          "#[test] #[test]",    "it is applied more than once" ; "duplicate")]
+    #[test_case(          "#[deprecated(1)]", "its arguments must be string literals" ; "non-string argument")]
+    #[test_case(        "#[inline(\"yes\")]",   "it does not take any arguments" ; "unexpected argument")]
+    #[test_case(              "#[inline(3)]",   "it does not take any arguments" ; "unexpected non-string argument")]
+    #[test_case("#[deprecated(\"a\", \"b\")]",    "it takes at most one argument" ; "too many arguments")]
+    fn invalid_attributes_are_errors(attributes: &str, reason: &str) {
+        // When
+        let (_, errors, warnings) = lower(attributes);
+
+        // Then
+        let name = attributes[2..]
+            .split(['(', ']'])
+            .next()
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            errors,
+            vec![CompilerError::InvalidAttribute {
+                name,
+                reason: reason.to_string()
+            }]
+        );
+        assert_eq!(warnings, vec![]);
+    }
+}
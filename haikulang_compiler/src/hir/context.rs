@@ -1,38 +1,284 @@
+use crate::error::{CompilerError, CompilerWarning};
 use crate::hir::arena::{Arena, InterningArena};
+use crate::hir::attrs::{HirAttributeTarget, HirAttributes, lower_attributes};
 use crate::hir::nodes::{
-    HirExpr, HirFunctionHeader, HirStatement, HirString, HirStringId, HirVariable, HirVariableId,
+    HirExpr, HirFunctionHeader, HirParameter, HirStatement, HirString, HirStringId,
+    HirStructHeader, HirStructMember, HirTypeRef, HirTypeRefKind, HirVariable, HirVariableId,
 };
 use crate::hir::sym::SymbolTable;
-use haikulang_parser::ast::func::FunctionDecl;
+use crate::hir::ty::{HirFunctionType, HirType};
+use haikulang_parser::ast::attr::Attribute;
+use haikulang_parser::ast::func::ParameterDecl;
+use haikulang_parser::ast::ident::IdentifierPath;
+use haikulang_parser::ast::types::TypeName;
+use haikulang_parser::ast::unit::{CompilationUnit, CompilationUnitMember};
+use haikulang_parser::span::{Span, Spanned};
 
 /// Module-level context for global symbols and interning.
 #[derive(Debug)]
 pub struct HirModuleContext {
     pub(crate) string_interner: InterningArena<HirString>,
     pub(crate) function_table: SymbolTable<HirStringId, HirFunctionHeader>,
-    pub(crate) function_arena: Arena<HirFunctionHeader>,
+    pub(crate) struct_table: SymbolTable<HirStringId, HirStructHeader>,
+    pub(crate) errors: Vec<Spanned<CompilerError>>,
+    pub(crate) warnings: Vec<Spanned<CompilerWarning>>,
 }
 
 impl HirModuleContext {
     pub fn new() -> Self {
+        let mut function_table = SymbolTable::new();
+        function_table.push();
+        let mut struct_table = SymbolTable::new();
+        struct_table.push();
+
         Self {
             string_interner: InterningArena::new(),
-            function_table: SymbolTable::new(),
-            function_arena: Arena::new(),
+            function_table,
+            struct_table,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Inject the functions and structs into this module context so we are aware of them ahead
+    /// of time, allowing us to refer to things further down the AST later.
+    pub fn pre_scan(&mut self, unit: &CompilationUnit) {
+        for member in &unit.members {
+            match member.value() {
+                CompilationUnitMember::Use(_) => {}
+                CompilationUnitMember::ExternFunction(function) => {
+                    let mut header = self.function_header(
+                        &function.name.value().value,
+                        &function.parameters.value(),
+                        function.return_type.as_ref(),
+                        true,
+                        function.visibility.is_public(),
+                        member.span(),
+                    );
+                    header.attributes =
+                        self.attributes(&function.attributes, HirAttributeTarget::ExternFunction);
+                    self.declare_function(header);
+                }
+                CompilationUnitMember::Function(function) => {
+                    let mut header = self.function_header(
+                        &function.name.value().value,
+                        &function.parameters.value(),
+                        function.return_type.as_ref(),
+                        false,
+                        function.visibility.is_public(),
+                        member.span(),
+                    );
+                    header.attributes =
+                        self.attributes(&function.attributes, HirAttributeTarget::Function);
+
+                    // The test runner calls tests without any arguments.
+                    if header.attributes.test && !header.parameters.is_empty() {
+                        let error = CompilerError::InvalidAttribute {
+                            name: "test".to_string(),
+                            reason: "test functions cannot take parameters".to_string(),
+                        };
+                        self.errors.push(Spanned::new(error, member.span()));
+                    }
+
+                    self.declare_function(header);
+                }
+                CompilationUnitMember::Struct(struct_decl) => {
+                    let name = self.intern(&struct_decl.identifier.value().value);
+                    let members = struct_decl
+                        .members
+                        .iter()
+                        .map(|member| HirStructMember {
+                            name: self.intern(&member.value().identifier.value().value),
+                            type_ref: self.type_ref(&member.value().type_name),
+                            is_pub: member.value_ref().visibility.is_public(),
+                            attributes: self.attributes(
+                                &member.value_ref().attributes,
+                                HirAttributeTarget::StructMember,
+                            ),
+                            span: member.span(),
+                        })
+                        .collect();
+                    let header = HirStructHeader {
+                        name,
+                        members,
+                        is_pub: struct_decl.visibility.is_public(),
+                        attributes: self
+                            .attributes(&struct_decl.attributes, HirAttributeTarget::Struct),
+                        span: member.span(),
+                    };
+
+                    if let Err(rejected) = self.struct_table.declare(name, header) {
+                        self.report_duplicate(rejected.name, rejected.span);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Forget all declared symbols and errors, ready to scan the module again. Interned strings
+    /// are kept, so any IDs that were already handed out remain valid.
+    pub fn reset(&mut self) {
+        self.function_table = SymbolTable::new();
+        self.function_table.push();
+        self.struct_table = SymbolTable::new();
+        self.struct_table.push();
+        self.errors.clear();
+        self.warnings.clear();
+    }
+
+    /// Errors that were found while scanning the module.
+    pub fn errors(&self) -> &[Spanned<CompilerError>] {
+        &self.errors
+    }
+
+    /// Warnings that were found while scanning the module.
+    pub fn warnings(&self) -> &[Spanned<CompilerWarning>] {
+        &self.warnings
+    }
+
+    pub fn intern(&mut self, value: &str) -> HirStringId {
+        self.string_interner.intern(value.to_string())
+    }
+
+    pub fn get_string(&self, id: HirStringId) -> &HirString {
+        self.string_interner.get(id)
+    }
+
+    pub fn lookup_function(&self, name: HirStringId) -> Option<&HirFunctionHeader> {
+        self.function_table.lookup(&name)
+    }
+
+    pub fn lookup_function_by_name(&self, name: &str) -> Option<&HirFunctionHeader> {
+        self.lookup_function(self.string_interner.lookup(name)?)
+    }
+
+    /// Every function declared in the module, in no particular order.
+    pub fn functions(&self) -> impl Iterator<Item = &HirFunctionHeader> {
+        self.function_table.values()
+    }
+
+    pub fn lookup_struct(&self, name: HirStringId) -> Option<&HirStructHeader> {
+        self.struct_table.lookup(&name)
+    }
+
+    pub fn lookup_struct_by_name(&self, name: &str) -> Option<&HirStructHeader> {
+        self.lookup_struct(self.string_interner.lookup(name)?)
+    }
+
+    /// Resolve a written type to the type it describes, if it and every type within it
+    /// are known.
+    pub fn resolve_type(&self, type_ref: &HirTypeRef) -> Option<HirType> {
+        match &type_ref.kind {
+            HirTypeRefKind::Named(name_id) => {
+                let name = self.get_string(*name_id);
+                HirType::primitive(name).or_else(|| {
+                    self.lookup_struct(*name_id)
+                        .map(|_| HirType::Struct(name.clone()))
+                })
+            }
+            HirTypeRefKind::Function {
+                parameters,
+                return_type,
+            } => Some(HirType::Function(Box::new(HirFunctionType {
+                parameters: parameters
+                    .iter()
+                    .map(|parameter| self.resolve_type(parameter))
+                    .collect::<Option<_>>()?,
+                return_type: match return_type {
+                    Some(return_type) => self.resolve_type(return_type)?,
+                    None => HirType::Unit,
+                },
+            }))),
+            HirTypeRefKind::Tuple(elements) => Some(HirType::tuple(
+                elements
+                    .iter()
+                    .map(|element| self.resolve_type(element))
+                    .collect::<Option<_>>()?,
+            )),
+        }
+    }
+
+    pub(crate) fn type_ref(&mut self, type_name: &Spanned<TypeName>) -> HirTypeRef {
+        let kind = match type_name.value_ref() {
+            TypeName::Path(path) => HirTypeRefKind::Named(self.intern(&path_to_string(path))),
+            TypeName::Function(function) => HirTypeRefKind::Function {
+                parameters: function
+                    .parameters
+                    .value_ref()
+                    .iter()
+                    .map(|parameter| self.type_ref(parameter))
+                    .collect(),
+                return_type: function
+                    .return_type
+                    .as_ref()
+                    .map(|return_type| Box::new(self.type_ref(return_type))),
+            },
+            TypeName::Tuple(tuple) => HirTypeRefKind::Tuple(
+                tuple
+                    .elements
+                    .iter()
+                    .map(|element| self.type_ref(element))
+                    .collect(),
+            ),
+        };
+        HirTypeRef {
+            kind,
+            span: type_name.span(),
+        }
+    }
+
+    fn function_header(
+        &mut self,
+        name: &str,
+        parameters: &[Spanned<ParameterDecl>],
+        return_type: Option<&Spanned<TypeName>>,
+        is_extern: bool,
+        is_pub: bool,
+        span: Span,
+    ) -> HirFunctionHeader {
+        HirFunctionHeader {
+            name: self.intern(name),
+            parameters: parameters
+                .iter()
+                .map(|param| HirParameter {
+                    name: self.intern(&param.value().name.value().value),
+                    type_ref: self.type_ref(&param.value().type_name),
+                    span: param.span(),
+                })
+                .collect(),
+            return_type: return_type.map(|return_type| self.type_ref(return_type)),
+            is_extern,
+            is_pub,
+            attributes: HirAttributes::default(),
+            span,
         }
     }
 
-    /// Inject the functions into this module context so we are aware of them ahead of time, allowing
-    /// us to refer to things further down the AST later.
-    pub fn pre_scan(&mut self, functions: &[FunctionDecl]) {
-        for function in functions {
-            let identifier_id = self.string_interner.intern(function.name.value().value);
-            let function = HirFunctionHeader {
-                name: identifier_id,
-            };
-            self.function_table.declare(identifier_id, function);
+    fn attributes(
+        &mut self,
+        attributes: &[Spanned<Attribute>],
+        target: HirAttributeTarget,
+    ) -> HirAttributes {
+        lower_attributes(attributes, target, &mut self.errors, &mut self.warnings)
+    }
+
+    fn declare_function(&mut self, header: HirFunctionHeader) {
+        if let Err(rejected) = self.function_table.declare(header.name, header) {
+            self.report_duplicate(rejected.name, rejected.span);
         }
     }
+
+    fn report_duplicate(&mut self, name: HirStringId, span: Span) {
+        let name = self.get_string(name).clone();
+        self.errors
+            .push(Spanned::new(CompilerError::DuplicateDefinition(name), span));
+    }
+}
+
+impl Default for HirModuleContext {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 /// Translates Haikulang ASTs to a flattened intermediate language representation that can be
@@ -40,8 +286,32 @@ impl HirModuleContext {
 #[derive(Debug)]
 pub struct HirFunctionContext<'module> {
     pub(crate) module_context: &'module mut HirModuleContext,
+    // The text of the file that the function is in, which format strings are checked
+    // against so that errors point at the right place within them.
+    pub(crate) source: &'module str,
     pub(crate) symbol_table: SymbolTable<HirStringId, HirVariableId>,
     pub(crate) expr_arena: Arena<HirExpr>,
     pub(crate) statement_arena: Arena<HirStatement>,
     pub(crate) variable_arena: Arena<HirVariable>,
+    pub(crate) closure_scopes: Vec<HirClosureScope>,
+    pub(crate) errors: Vec<Spanned<CompilerError>>,
+}
+
+/// A closure that is being lowered, and the variables from outside of it that it refers to.
+#[derive(Debug)]
+pub(crate) struct HirClosureScope {
+    // The index of the first symbol table scope that belongs to the closure. Variables
+    // declared in any scope before this one must be captured.
+    pub(crate) first_scope: usize,
+    pub(crate) captures: Vec<HirVariableId>,
+}
+
+/// Render an identifier path in the way it would be written in source code.
+pub(crate) fn path_to_string(path: &IdentifierPath) -> String {
+    path.qualifier
+        .iter()
+        .chain(std::iter::once(&path.local_name))
+        .map(|identifier| identifier.value().value)
+        .collect::<Vec<_>>()
+        .join("::")
 }
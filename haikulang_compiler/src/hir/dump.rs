@@ -0,0 +1,270 @@
+//! Rendering of lowered functions as indented trees, for inspecting what the lowerer and the
+//! type checker made of some code.
+//!
+//! Each line shows one statement or expression, followed by the type inferred for it where
+//! there is one. Anything that a line refers to is shown beneath it, indented one level.
+use crate::hir::context::HirModuleContext;
+use crate::hir::nodes::*;
+use crate::hir::typeck::HirTypeckResult;
+use std::fmt::Write;
+
+const INDENT: &str = "  ";
+
+/// Render the body of a lowered function.
+pub fn dump_function(
+    module: &HirModuleContext,
+    function: &HirFunctionData,
+    types: &HirTypeckResult,
+) -> String {
+    let mut dumper = Dumper {
+        module,
+        function,
+        types,
+        output: String::new(),
+        depth: 0,
+    };
+    dumper.statement(function.root_statement);
+    dumper.output
+}
+
+struct Dumper<'a> {
+    module: &'a HirModuleContext,
+    function: &'a HirFunctionData,
+    types: &'a HirTypeckResult,
+    output: String,
+    depth: usize,
+}
+
+impl Dumper<'_> {
+    fn statement(&mut self, id: HirStatementId) {
+        match &self.function.get_statement(id).kind {
+            HirStatementKind::Empty => self.line("empty"),
+            HirStatementKind::VarDecl { variable, expr } => {
+                self.line(&format!("let {}", self.variable(*variable)));
+                self.nested(|dumper| expr.iter().for_each(|expr| dumper.expr(*expr)));
+            }
+            HirStatementKind::Destructure { pattern, expr, .. } => {
+                self.line(&format!("let {}", self.pattern(pattern)));
+                self.nested(|dumper| dumper.expr(*expr));
+            }
+            HirStatementKind::Expr(expr) => {
+                self.line("expr");
+                self.nested(|dumper| dumper.expr(*expr));
+            }
+            HirStatementKind::Return(expr) => {
+                self.line("return");
+                self.nested(|dumper| expr.iter().for_each(|expr| dumper.expr(*expr)));
+            }
+            HirStatementKind::Continue => self.line("continue"),
+            HirStatementKind::Break => self.line("break"),
+            HirStatementKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.line("if");
+                self.nested(|dumper| {
+                    dumper.expr(*condition);
+                    dumper.statement(*then);
+                    if let Some(otherwise) = otherwise {
+                        dumper.line("else");
+                        dumper.nested(|dumper| dumper.statement(*otherwise));
+                    }
+                });
+            }
+            HirStatementKind::While { condition, body } => {
+                self.line("while");
+                self.nested(|dumper| {
+                    dumper.expr(*condition);
+                    dumper.statement(*body);
+                });
+            }
+            HirStatementKind::Block(statements) => {
+                self.line("block");
+                self.nested(|dumper| {
+                    for statement in statements {
+                        dumper.statement(*statement);
+                    }
+                });
+            }
+        }
+    }
+
+    fn expr(&mut self, id: HirExprId) {
+        let mut children = Vec::new();
+        let label = match &self.function.get_expr(id).kind {
+            HirExprKind::LoadLiteral(literal) => self.literal(&literal.kind),
+            HirExprKind::LoadVariable(variable) => format!("variable {}", self.name(*variable)),
+            HirExprKind::LoadFunction(name) => format!("function {}", self.string(*name)),
+            HirExprKind::LoadStruct(name) => format!("struct {}", self.string(*name)),
+            HirExprKind::LoadBuiltin(builtin) => format!("builtin {}", builtin.name()),
+            HirExprKind::BinaryOp { left, op, right } => {
+                children.extend([*left, *right]);
+                format!("{:?}", op)
+            }
+            HirExprKind::UnaryOp { op, value } => {
+                children.push(*value);
+                format!("{:?}", op)
+            }
+            HirExprKind::Assign { target, op, value } => {
+                children.extend([*target, *value]);
+                match op {
+                    Some(op) => format!("assign {:?}", op),
+                    None => "assign".to_string(),
+                }
+            }
+            HirExprKind::MemberAccess { owner, member } => {
+                children.push(*owner);
+                format!("member {}", self.string(*member))
+            }
+            HirExprKind::Index { owner, index } => {
+                children.extend([*owner, *index]);
+                "index".to_string()
+            }
+            HirExprKind::Call { callee, arguments } => {
+                children.push(*callee);
+                children.extend(arguments);
+                "call".to_string()
+            }
+            HirExprKind::Closure(closure) => {
+                let parameters: Vec<String> = closure
+                    .parameters
+                    .iter()
+                    .map(|parameter| self.variable(*parameter))
+                    .collect();
+                let label = format!("closure |{}|", parameters.join(", "));
+                self.line(&self.typed(&label, id));
+                self.nested(|dumper| match closure.body {
+                    HirClosureBody::Expr(expr) => dumper.expr(expr),
+                    HirClosureBody::Block(body) => dumper.statement(body),
+                });
+                return;
+            }
+            HirExprKind::Tuple(elements) => {
+                children.extend(elements);
+                "tuple".to_string()
+            }
+            HirExprKind::Format(parts) => {
+                self.line(&self.typed("format", id));
+                self.nested(|dumper| {
+                    for part in parts {
+                        match part {
+                            HirFormatPart::Text(text) => {
+                                dumper.line(&format!("{:?}", dumper.string(*text)));
+                            }
+                            HirFormatPart::Value(value) => dumper.expr(*value),
+                        }
+                    }
+                });
+                return;
+            }
+            HirExprKind::Unresolved(name) => format!("unresolved {}", name),
+        };
+
+        self.line(&self.typed(&label, id));
+        self.nested(|dumper| {
+            for child in children {
+                dumper.expr(child);
+            }
+        });
+    }
+
+    fn literal(&self, literal: &HirLiteralKind) -> String {
+        match literal {
+            HirLiteralKind::String(id) => format!("{:?}", self.string(*id)),
+            HirLiteralKind::Int(value) => value.to_string(),
+            other => format!("{:?}", other),
+        }
+    }
+
+    fn pattern(&self, pattern: &HirPattern) -> String {
+        match pattern {
+            HirPattern::Variable(variable) => self.variable(*variable),
+            HirPattern::Tuple { elements, .. } => {
+                let elements: Vec<String> = elements
+                    .iter()
+                    .map(|element| self.pattern(element))
+                    .collect();
+                format!("({})", elements.join(", "))
+            }
+        }
+    }
+
+    // A variable's name along with its type.
+    fn variable(&self, id: HirVariableId) -> String {
+        match self.types.variable_types.get(id) {
+            Some(ty) => format!("{}: {}", self.name(id), ty),
+            None => self.name(id).to_string(),
+        }
+    }
+
+    fn name(&self, id: HirVariableId) -> &str {
+        self.string(self.function.get_variable(id).name)
+    }
+
+    fn string(&self, id: HirStringId) -> &str {
+        self.module.get_string(id)
+    }
+
+    fn typed(&self, label: &str, id: HirExprId) -> String {
+        match self.types.expr_types.get(id) {
+            Some(ty) => format!("{}: {}", label, ty),
+            None => label.to_string(),
+        }
+    }
+
+    fn line(&mut self, text: &str) {
+        writeln!(self.output, "{}{}", INDENT.repeat(self.depth), text).unwrap();
+    }
+
+    fn nested(&mut self, action: impl FnOnce(&mut Self)) {
+        self.depth += 1;
+        action(self);
+        self.depth -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::database::{Database, FunctionId};
+
+    #[test]
+    fn functions_are_dumped_as_trees() {
+        // Given
+        let mut db = Database::new();
+        let file = db.add_file(
+            "main.hkl",
+            "fn f(x: i32) { let y = x * 2; if (y > 3) { println(\"{}!\", y); } }",
+        );
+        let id = FunctionId::new(file, "f");
+        let function = db.lower_function(&id).unwrap();
+        let types = db.type_of(&id).unwrap();
+
+        // When
+        let dump = dump_function(db.module_context(file), &function, &types);
+
+        // Then
+        assert_eq!(
+            dump,
+            concat!(
+                "block\n",
+                "  let y: i32\n",
+                "    Mul: i32\n",
+                "      variable x: i32\n",
+                "      2: i32\n",
+                "  if\n",
+                "    Greater: bool\n",
+                "      variable y: i32\n",
+                "      3: i32\n",
+                "    block\n",
+                "      expr\n",
+                "        call: ()\n",
+                "          builtin println\n",
+                "          format: string\n",
+                "            variable y: i32\n",
+                "            \"!\"\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,147 @@
+//! Parsing of the format strings given to `format`, `print` and `println`.
+//!
+//! Each `{}` within a format string is a placeholder for the next argument. Braces that are
+//! not part of a placeholder are written twice, as `{{` and `}}`.
+use std::ops::Range;
+
+/// Part of a format string.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FormatSegment {
+    Text(String),
+    /// A placeholder, along with where it appears within the format string.
+    Placeholder(Range<usize>),
+}
+
+/// Split a format string into text and placeholders. On failure, returns the reason along
+/// with where the problem is within the format string.
+pub fn parse_format_string(format: &str) -> Result<Vec<FormatSegment>, (String, Range<usize>)> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut chars = format.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        match (c, chars.peek().map(|(_, next)| *next)) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                text.push(c);
+            }
+            ('{', Some('}')) => {
+                chars.next();
+                if !text.is_empty() {
+                    segments.push(FormatSegment::Text(std::mem::take(&mut text)));
+                }
+                segments.push(FormatSegment::Placeholder(index..index + 2));
+            }
+            ('{', _) => {
+                let reason = "expected } after {, or {{ for a literal brace".to_string();
+                return Err((reason, index..index + 1));
+            }
+            ('}', _) => {
+                let reason = "unmatched }, use }} for a literal brace".to_string();
+                return Err((reason, index..index + 1));
+            }
+            (c, _) => text.push(c),
+        }
+    }
+
+    if !text.is_empty() {
+        segments.push(FormatSegment::Text(text));
+    }
+    Ok(segments)
+}
+
+/// Find where a range within the value of a string literal was written within the source of
+/// that literal. Returns None for multi-line strings, since their indentation is removed so
+/// their value no longer lines up with their source.
+pub fn literal_source_range(
+    source: &str,
+    value: &str,
+    range: Range<usize>,
+) -> Option<Range<usize>> {
+    let start = literal_source_offset(source, value, range.start)?;
+    let end = literal_source_offset(source, value, range.end)?;
+    Some(start..end)
+}
+
+fn literal_source_offset(source: &str, value: &str, offset: usize) -> Option<usize> {
+    if source.starts_with("\"\"\"") {
+        return None;
+    }
+
+    // Raw strings have no escape sequences, so their value is exactly what was written.
+    if source.starts_with('r') {
+        return Some(source.find('"')? + 1 + offset);
+    }
+
+    // Every escape sequence stands for a single character, so we can step through the value
+    // and the source together, skipping the opening quote.
+    let mut position = 1;
+    for (index, c) in value.char_indices() {
+        if index >= offset {
+            break;
+        }
+        position += escape_length(source.get(position..)?).unwrap_or(c.len_utf8());
+    }
+    Some(position)
+}
+
+// The length of the escape sequence at the start of the text, if there is one.
+fn escape_length(text: &str) -> Option<usize> {
+    let mut chars = text.strip_prefix('\\')?.chars();
+    match chars.next()? {
+        'x' => Some(4),
+        'u' => text.find('}').map(|index| index + 1),
+        c => Some(1 + c.len_utf8()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn text(value: &str) -> FormatSegment {
+        FormatSegment::Text(value.to_string())
+    }
+
+    #[test_case(     "hello", vec![text("hello")] ; "no placeholders")]
+    #[test_case(   "x = {}!", vec![text("x = "), FormatSegment::Placeholder(4..6), text("!")] ; "single placeholder")]
+    #[test_case(      "{}{}", vec![FormatSegment::Placeholder(0..2), FormatSegment::Placeholder(2..4)] ; "adjacent placeholders")]
+    #[test_case("{{a}} = {}", vec![text("{a} = "), FormatSegment::Placeholder(8..10)] ; "escaped braces")]
+    #[test_case(          "", vec![] ; "empty")]
+    fn format_strings_are_split_into_segments(format: &str, expected: Vec<FormatSegment>) {
+        // Then
+        assert_eq!(parse_format_string(format), Ok(expected));
+    }
+
+    #[test_case("a { b", 2..3 ; "unclosed placeholder")]
+    #[test_case(  "{x}", 0..1 ; "named placeholder")]
+    #[test_case("a } b", 2..3 ; "unmatched closing brace")]
+    fn invalid_format_strings_report_where_they_fail(format: &str, expected: Range<usize>) {
+        // When
+        let (_, range) = parse_format_string(format).unwrap_err();
+
+        // Then
+        assert_eq!(range, expected);
+    }
+
+    #[test_case(              r#""ab{}""#,     "ab{}", Some(3..5) ; "plain string")]
+    #[test_case(         r#""\t\u{e9}{}""#,   "\té{}", Some(9..11) ; "escape sequences")]
+    #[test_case(           r#""\x41é{}""#,     "Aé{}", Some(7..9) ; "multi-byte characters")]
+    #[test_case(          r##"r#"a{}"#"##,      "a{}", Some(4..6) ; "raw string")]
+    #[test_case("\"\"\"\n  {}\n\"\"\"",       "{}",       None ; "multi-line string")]
+    fn literal_ranges_are_found_in_the_source(
+        source: &str,
+        value: &str,
+        expected: Option<Range<usize>>,
+    ) {
+        // When
+        let start = value.find("{}").unwrap();
+
+        // Then
+        assert_eq!(
+            literal_source_range(source, value, start..start + 2),
+            expected
+        );
+    }
+}
@@ -1,27 +1,37 @@
+use crate::error::CompilerError;
 use crate::hir::arena::Arena;
-use crate::hir::context::{HirFunctionContext, HirModuleContext};
+use crate::hir::context::{HirClosureScope, HirFunctionContext, HirModuleContext, path_to_string};
+use crate::hir::format::{FormatSegment, literal_source_range, parse_format_string};
 use crate::hir::nodes::*;
 use crate::hir::sym::SymbolTable;
-use haikulang_parser::ast::expr::Expr;
+use crate::stdlib::STD_MODULE;
+use haikulang_parser::ast::expr::{AssignmentExpr, BinaryOp, ClosureExpr, Expr, UnaryOp};
 use haikulang_parser::ast::func::FunctionDecl;
+use haikulang_parser::ast::ident::{Identifier, IdentifierPath};
 use haikulang_parser::ast::stmt::{
-    BlockStatement, IfStatement, ReturnStatement, Statement, VarDeclStatement, WhileStatement,
+    BlockStatement, IfStatement, Pattern, ReturnStatement, Statement, VarDeclStatement,
+    WhileStatement,
 };
-use haikulang_parser::span::Span;
+use haikulang_parser::lexer::literals::{FloatLit, IntLit};
+use haikulang_parser::span::{Span, Spanned};
+use std::ops::Range;
 
 impl<'module> HirFunctionContext<'module> {
-    pub fn new(module_context: &'module mut HirModuleContext) -> Self {
+    pub fn new(module_context: &'module mut HirModuleContext, source: &'module str) -> Self {
         Self {
             module_context,
+            source,
             symbol_table: SymbolTable::new(),
             expr_arena: Arena::new(),
             statement_arena: Arena::new(),
             variable_arena: Arena::new(),
+            closure_scopes: Vec::new(),
+            errors: Vec::new(),
         }
     }
 
     pub fn get_string(&self, id: HirStringId) -> &HirString {
-        self.module_context.string_interner.get(id)
+        self.module_context.get_string(id)
     }
 
     pub fn get_expr(&self, id: HirExprId) -> &HirExpr {
@@ -40,19 +50,23 @@ impl<'module> HirFunctionContext<'module> {
         mut self,
         function_header: HirFunctionHeader,
         function_decl: &FunctionDecl,
-    ) -> HirFunctionData<'module> {
+    ) -> HirFunctionData {
         self.symbol_table.push();
 
         // Declare our parameters.
+        let mut parameters: Vec<HirVariableId> = Vec::new();
         for param in &function_decl.parameters.value() {
             let param_name = param.value().name.value().value;
-            let param_name_id = self.module_context.string_interner.intern(param_name);
+            let param_name_id = self.module_context.intern(&param_name);
+            let type_ref = self.module_context.type_ref(&param.value().type_name);
             let variable = HirVariable {
                 name: param_name_id,
+                type_ref: Some(type_ref),
                 location: param.span(),
             };
             let variable_id = self.variable_arena.alloc(variable);
-            self.symbol_table.declare(param_name_id, variable_id);
+            self.declare_variable(param_name_id, variable_id);
+            parameters.push(variable_id);
         }
 
         let body = self.lower_statement(&function_decl.body.value(), function_decl.body.span());
@@ -60,9 +74,13 @@ impl<'module> HirFunctionContext<'module> {
         self.symbol_table.pop();
 
         HirFunctionData {
-            context: self,
             header: function_header,
+            parameters,
+            expr_arena: self.expr_arena,
+            statement_arena: self.statement_arena,
+            variable_arena: self.variable_arena,
             root_statement: body,
+            errors: self.errors,
         }
     }
 
@@ -88,25 +106,67 @@ impl<'module> HirFunctionContext<'module> {
         var_decl_statement: &VarDeclStatement,
         span: Span,
     ) -> HirStatementKind {
-        let identifier = var_decl_statement.identifier.value().value;
-        let identifier_id = self.module_context.string_interner.intern(identifier);
-        let variable = HirVariable {
-            name: identifier_id,
-            location: span,
-        };
-        let variable_id = self.variable_arena.alloc(variable);
-        self.symbol_table.declare(identifier_id, variable_id);
-
+        // Lower the initializer first, so that it cannot refer to the variable being declared.
         let expr = var_decl_statement
             .expr
             .as_ref()
             .map(|expr| self.lower_expr(&expr.value(), expr.span()));
-        HirStatementKind::VarDecl {
-            variable: variable_id,
-            expr,
+        let type_ref = var_decl_statement
+            .type_name
+            .as_ref()
+            .map(|type_name| self.module_context.type_ref(type_name));
+
+        match var_decl_statement.pattern.value_ref() {
+            Pattern::Identifier(identifier) => {
+                let variable_id = self.declare_pattern_variable(identifier, type_ref, span);
+                HirStatementKind::VarDecl {
+                    variable: variable_id,
+                    expr,
+                }
+            }
+            // The parser only accepts tuple patterns that are given a value.
+            Pattern::Tuple(_) => HirStatementKind::Destructure {
+                pattern: self.lower_pattern(&var_decl_statement.pattern),
+                type_ref,
+                expr: expr.expect("tuple patterns are always given a value"),
+            },
         }
     }
 
+    fn lower_pattern(&mut self, pattern: &Spanned<Pattern>) -> HirPattern {
+        match pattern.value_ref() {
+            Pattern::Identifier(identifier) => HirPattern::Variable(self.declare_pattern_variable(
+                identifier,
+                None,
+                pattern.span(),
+            )),
+            Pattern::Tuple(elements) => HirPattern::Tuple {
+                elements: elements
+                    .iter()
+                    .map(|element| self.lower_pattern(element))
+                    .collect(),
+                span: pattern.span(),
+            },
+        }
+    }
+
+    fn declare_pattern_variable(
+        &mut self,
+        identifier: &Identifier,
+        type_ref: Option<HirTypeRef>,
+        location: Span,
+    ) -> HirVariableId {
+        let identifier_id = self.module_context.intern(&identifier.value);
+        let variable = HirVariable {
+            name: identifier_id,
+            type_ref,
+            location,
+        };
+        let variable_id = self.variable_arena.alloc(variable);
+        self.declare_variable(identifier_id, variable_id);
+        variable_id
+    }
+
     fn lower_return_statement(&mut self, return_statement: &ReturnStatement) -> HirStatementKind {
         let expr = return_statement
             .expr
@@ -156,20 +216,396 @@ impl<'module> HirFunctionContext<'module> {
 
     fn lower_expr(&mut self, expr: &Expr, span: Span) -> HirExprId {
         let kind = match expr {
-            Expr::Binary(_) => todo!(),
-            Expr::Unary(_) => todo!(),
-            Expr::Assignment(_) => todo!(),
-            Expr::MemberAccess(_) => todo!(),
-            Expr::Index(_) => todo!(),
-            Expr::FunctionCall(_) => todo!(),
-            Expr::Float(_) => todo!(),
-            Expr::Int(_) => todo!(),
-            Expr::Bool(_) => todo!(),
-            Expr::String(_) => todo!(),
-            Expr::IdentifierPath(_) => todo!(),
+            Expr::Binary(binary_expr) => HirExprKind::BinaryOp {
+                left: self.lower_spanned_expr(&binary_expr.left),
+                op: lower_binary_op(&binary_expr.op),
+                right: self.lower_spanned_expr(&binary_expr.right),
+            },
+            Expr::Unary(unary_expr) => {
+                let op = match unary_expr.op {
+                    // Unary plus has no effect, so we can drop it entirely.
+                    UnaryOp::Plus => return self.lower_spanned_expr(&unary_expr.value),
+                    UnaryOp::Minus => HirExprUnaryOp::Negate,
+                    UnaryOp::Not => HirExprUnaryOp::Not,
+                    UnaryOp::Invert => HirExprUnaryOp::Invert,
+                };
+                HirExprKind::UnaryOp {
+                    op,
+                    value: self.lower_spanned_expr(&unary_expr.value),
+                }
+            }
+            Expr::Assignment(assignment_expr) => self.lower_assignment_expr(assignment_expr),
+            Expr::MemberAccess(member_access_expr) => HirExprKind::MemberAccess {
+                owner: self.lower_spanned_expr(&member_access_expr.owner),
+                member: self
+                    .module_context
+                    .intern(&member_access_expr.member.value().value),
+            },
+            Expr::Index(index_expr) => HirExprKind::Index {
+                owner: self.lower_spanned_expr(&index_expr.owner),
+                index: self.lower_spanned_expr(&index_expr.index),
+            },
+            Expr::FunctionCall(function_call_expr) => {
+                let callee = self.lower_spanned_expr(&function_call_expr.identity);
+                let arguments = function_call_expr.arguments.value_ref();
+                match self.expr_arena[callee].kind {
+                    HirExprKind::LoadBuiltin(builtin) if builtin.takes_format_string() => {
+                        self.lower_format_call(builtin, callee, arguments, span)
+                    }
+                    _ => HirExprKind::Call {
+                        callee,
+                        arguments: arguments
+                            .iter()
+                            .map(|argument| self.lower_spanned_expr(argument))
+                            .collect(),
+                    },
+                }
+            }
+            Expr::Closure(closure_expr) => self.lower_closure_expr(closure_expr),
+            Expr::Tuple(tuple_expr) => HirExprKind::Tuple(
+                tuple_expr
+                    .elements
+                    .iter()
+                    .map(|element| self.lower_spanned_expr(element))
+                    .collect(),
+            ),
+            Expr::Float(float_expr) => HirExprKind::LoadLiteral(HirLiteral {
+                kind: match float_expr.value {
+                    FloatLit::F32(value) => HirLiteralKind::F32(value),
+                    FloatLit::F64(value) => HirLiteralKind::F64(value),
+                    // If we have no suffix, we treat it as a f64 for now.
+                    FloatLit::Untyped(value) => HirLiteralKind::F64(value),
+                },
+                span,
+            }),
+            Expr::Int(int_expr) => HirExprKind::LoadLiteral(HirLiteral {
+                kind: match int_expr.value.clone() {
+                    IntLit::I8(value) => HirLiteralKind::I8(value),
+                    IntLit::I16(value) => HirLiteralKind::I16(value),
+                    IntLit::I32(value) => HirLiteralKind::I32(value),
+                    IntLit::I64(value) => HirLiteralKind::I64(value),
+                    IntLit::I128(value) => HirLiteralKind::I128(value),
+                    IntLit::ISize(value) => HirLiteralKind::ISize(value),
+                    IntLit::U8(value) => HirLiteralKind::U8(value),
+                    IntLit::U16(value) => HirLiteralKind::U16(value),
+                    IntLit::U32(value) => HirLiteralKind::U32(value),
+                    IntLit::U64(value) => HirLiteralKind::U64(value),
+                    IntLit::U128(value) => HirLiteralKind::U128(value),
+                    IntLit::USize(value) => HirLiteralKind::USize(value),
+                    IntLit::Untyped(value) => HirLiteralKind::Int(value),
+                },
+                span,
+            }),
+            Expr::Bool(bool_expr) => HirExprKind::LoadLiteral(HirLiteral {
+                kind: HirLiteralKind::Bool(bool_expr.value),
+                span,
+            }),
+            Expr::Char(char_expr) => HirExprKind::LoadLiteral(HirLiteral {
+                kind: HirLiteralKind::Char(char_expr.value),
+                span,
+            }),
+            Expr::Byte(byte_expr) => HirExprKind::LoadLiteral(HirLiteral {
+                kind: HirLiteralKind::U8(byte_expr.value),
+                span,
+            }),
+            Expr::ByteString(bytes_expr) => HirExprKind::LoadLiteral(HirLiteral {
+                kind: HirLiteralKind::Bytes(bytes_expr.value.clone()),
+                span,
+            }),
+            Expr::String(str_expr) => HirExprKind::LoadLiteral(HirLiteral {
+                kind: HirLiteralKind::String(self.module_context.intern(&str_expr.value)),
+                span,
+            }),
+            Expr::IdentifierPath(identifier_path) => self.lower_identifier_path(identifier_path),
         };
 
         let expr = HirExpr { kind, span };
         self.expr_arena.alloc(expr)
     }
+
+    fn lower_assignment_expr(&mut self, assignment_expr: &AssignmentExpr) -> HirExprKind {
+        let target = self.lower_spanned_expr(&assignment_expr.lvalue);
+
+        // Closures capture variables by value, so assigning to a captured variable would
+        // only ever change the copy held by the closure.
+        let mut root = target;
+        loop {
+            match &self.expr_arena[root].kind {
+                HirExprKind::MemberAccess { owner, .. } | HirExprKind::Index { owner, .. } => {
+                    root = *owner;
+                }
+                HirExprKind::LoadVariable(variable) => {
+                    let variable = *variable;
+                    if let Some(scope) = self.closure_scopes.last()
+                        && scope.captures.contains(&variable)
+                    {
+                        let name = self
+                            .module_context
+                            .get_string(self.variable_arena[variable].name);
+                        self.errors.push(Spanned::new(
+                            CompilerError::CapturedVariableAssignment(name.clone()),
+                            assignment_expr.lvalue.span(),
+                        ));
+                    }
+                    break;
+                }
+                _ => break,
+            }
+        }
+
+        HirExprKind::Assign {
+            target,
+            op: assignment_expr.op.as_ref().map(lower_binary_op),
+            value: self.lower_spanned_expr(&assignment_expr.rvalue),
+        }
+    }
+
+    fn lower_closure_expr(&mut self, closure_expr: &ClosureExpr) -> HirExprKind {
+        self.symbol_table.push();
+        self.closure_scopes.push(HirClosureScope {
+            first_scope: self.symbol_table.depth() - 1,
+            captures: Vec::new(),
+        });
+
+        let mut parameters: Vec<HirVariableId> = Vec::new();
+        for param in closure_expr.parameters.value_ref().iter() {
+            let param_name_id = self
+                .module_context
+                .intern(&param.value_ref().name.value_ref().value);
+            let type_ref = param
+                .value_ref()
+                .type_name
+                .as_ref()
+                .map(|type_name| self.module_context.type_ref(type_name));
+            let variable = HirVariable {
+                name: param_name_id,
+                type_ref,
+                location: param.span(),
+            };
+            let variable_id = self.variable_arena.alloc(variable);
+            self.declare_variable(param_name_id, variable_id);
+            parameters.push(variable_id);
+        }
+
+        let return_type = closure_expr
+            .return_type
+            .as_ref()
+            .map(|return_type| self.module_context.type_ref(return_type));
+        let body = match closure_expr.body.value_ref() {
+            Statement::Expr(expr) => {
+                HirClosureBody::Expr(self.lower_expr(expr, closure_expr.body.span()))
+            }
+            body => HirClosureBody::Block(self.lower_statement(body, closure_expr.body.span())),
+        };
+
+        let scope = self.closure_scopes.pop().expect("closure scope underflow");
+        self.symbol_table.pop();
+
+        HirExprKind::Closure(Box::new(HirClosure {
+            parameters,
+            return_type,
+            captures: scope.captures,
+            body,
+        }))
+    }
+
+    fn lower_spanned_expr(&mut self, expr: &Spanned<Expr>) -> HirExprId {
+        self.lower_expr(&expr.value(), expr.span())
+    }
+
+    // Format strings are split up while lowering, so that they do not need to be parsed each
+    // time they are used. Printing is then just a case of printing the formatted string.
+    fn lower_format_call(
+        &mut self,
+        builtin: HirBuiltin,
+        callee: HirExprId,
+        arguments: &[Spanned<Expr>],
+        span: Span,
+    ) -> HirExprKind {
+        // Like in other languages, println can be called on its own to end a line.
+        let parts = if builtin == HirBuiltin::Println && arguments.is_empty() {
+            Vec::new()
+        } else {
+            self.lower_format(arguments, span)
+        };
+        if builtin == HirBuiltin::Format {
+            return HirExprKind::Format(parts);
+        }
+
+        let format = self.expr_arena.alloc(HirExpr {
+            kind: HirExprKind::Format(parts),
+            span,
+        });
+        HirExprKind::Call {
+            callee,
+            arguments: vec![format],
+        }
+    }
+
+    fn lower_format(&mut self, arguments: &[Spanned<Expr>], span: Span) -> Vec<HirFormatPart> {
+        let Some((format, values)) = arguments.split_first() else {
+            let error = CompilerError::ArgumentCountMismatch {
+                expected: 1,
+                actual: 0,
+            };
+            self.errors.push(Spanned::new(error, span));
+            return Vec::new();
+        };
+
+        let values: Vec<HirExprId> = values
+            .iter()
+            .map(|value| self.lower_spanned_expr(value))
+            .collect();
+        // If the format string is unusable, still check the values so that their errors are
+        // reported too.
+        let fallback = || values.iter().copied().map(HirFormatPart::Value).collect();
+
+        let Expr::String(literal) = format.value_ref() else {
+            let reason = "the format string must be a string literal".to_string();
+            let error = CompilerError::InvalidFormatString(reason);
+            self.errors.push(Spanned::new(error, format.span()));
+            return fallback();
+        };
+
+        let segments = match parse_format_string(&literal.value) {
+            Ok(segments) => segments,
+            Err((reason, range)) => {
+                let error = CompilerError::InvalidFormatString(reason);
+                let span = self.format_span(format, &literal.value, range);
+                self.errors.push(Spanned::new(error, span));
+                return fallback();
+            }
+        };
+
+        let placeholders: Vec<Range<usize>> = segments
+            .iter()
+            .filter_map(|segment| match segment {
+                FormatSegment::Placeholder(range) => Some(range.clone()),
+                FormatSegment::Text(_) => None,
+            })
+            .collect();
+
+        // Point at the first placeholder without a value, or the first value without a
+        // placeholder.
+        if placeholders.len() != values.len() {
+            let error = CompilerError::FormatArgumentCountMismatch {
+                placeholders: placeholders.len(),
+                arguments: values.len(),
+            };
+            let span = match placeholders.get(values.len()) {
+                Some(range) => self.format_span(format, &literal.value, range.clone()),
+                None => self.expr_arena[values[placeholders.len()]].span,
+            };
+            self.errors.push(Spanned::new(error, span));
+            return fallback();
+        }
+
+        let mut values = values.into_iter();
+        segments
+            .into_iter()
+            .map(|segment| match segment {
+                FormatSegment::Text(text) => HirFormatPart::Text(self.module_context.intern(&text)),
+                FormatSegment::Placeholder(_) => HirFormatPart::Value(values.next().unwrap()),
+            })
+            .collect()
+    }
+
+    // Find where part of a format string is within the source, or fall back to the whole
+    // format string if that cannot be worked out.
+    fn format_span(&self, format: &Spanned<Expr>, value: &str, range: Range<usize>) -> Span {
+        let format_span = format.span();
+        self.source
+            .get(format_span.range())
+            .and_then(|source| literal_source_range(source, value, range))
+            .map(|range| {
+                Span::new(
+                    format_span.start() + range.start,
+                    format_span.start() + range.end,
+                )
+            })
+            .unwrap_or(format_span)
+    }
+
+    fn lower_identifier_path(&mut self, identifier_path: &IdentifierPath) -> HirExprKind {
+        let name = path_to_string(identifier_path);
+
+        // Qualified paths refer to other modules, which we do not know anything about yet,
+        // apart from the builtins that are also part of the standard library.
+        if !identifier_path.qualifier.is_empty() {
+            let builtin = name
+                .strip_prefix(STD_MODULE)
+                .and_then(|name| name.strip_prefix("::"))
+                .and_then(HirBuiltin::from_name)
+                .filter(HirBuiltin::takes_format_string);
+            return match builtin {
+                Some(builtin) => HirExprKind::LoadBuiltin(builtin),
+                None => HirExprKind::Unresolved(name),
+            };
+        }
+
+        let name_id = self.module_context.intern(&name);
+
+        if let Some((scope, variable_id)) = self.symbol_table.lookup_with_scope(&name_id) {
+            let variable_id = *variable_id;
+            self.capture_variable(scope, variable_id);
+            HirExprKind::LoadVariable(variable_id)
+        } else if self.module_context.lookup_function(name_id).is_some() {
+            HirExprKind::LoadFunction(name_id)
+        } else if self.module_context.lookup_struct(name_id).is_some() {
+            HirExprKind::LoadStruct(name_id)
+        } else if let Some(builtin) = HirBuiltin::from_name(&name) {
+            // Builtins are only used if nothing in the module has the same name.
+            HirExprKind::LoadBuiltin(builtin)
+        } else {
+            HirExprKind::Unresolved(name)
+        }
+    }
+
+    // Record a variable declared in the given scope as a capture of every closure that is
+    // being lowered within that scope.
+    fn capture_variable(&mut self, scope: usize, variable: HirVariableId) {
+        for closure_scope in self.closure_scopes.iter_mut().rev() {
+            if closure_scope.first_scope <= scope {
+                break;
+            }
+            if !closure_scope.captures.contains(&variable) {
+                closure_scope.captures.push(variable);
+            }
+        }
+    }
+
+    fn declare_variable(&mut self, name: HirStringId, variable: HirVariableId) {
+        if let Err(rejected) = self.symbol_table.declare(name, variable) {
+            let location = self.variable_arena[rejected].location;
+            let name = self.module_context.get_string(name).clone();
+            self.errors.push(Spanned::new(
+                CompilerError::DuplicateDefinition(name),
+                location,
+            ));
+        }
+    }
+}
+
+fn lower_binary_op(op: &BinaryOp) -> HirExprBinaryOp {
+    match op {
+        BinaryOp::Add => HirExprBinaryOp::Add,
+        BinaryOp::Sub => HirExprBinaryOp::Sub,
+        BinaryOp::Mul => HirExprBinaryOp::Mul,
+        BinaryOp::Div => HirExprBinaryOp::Div,
+        BinaryOp::Mod => HirExprBinaryOp::Mod,
+        BinaryOp::Pow => HirExprBinaryOp::Pow,
+        BinaryOp::BinaryAnd => HirExprBinaryOp::BinaryAnd,
+        BinaryOp::BinaryOr => HirExprBinaryOp::BinaryOr,
+        BinaryOp::BinaryXor => HirExprBinaryOp::BinaryXor,
+        BinaryOp::BinaryShl => HirExprBinaryOp::BinaryShl,
+        BinaryOp::BinaryShr => HirExprBinaryOp::BinaryShr,
+        BinaryOp::BoolAnd => HirExprBinaryOp::BoolAnd,
+        BinaryOp::BoolOr => HirExprBinaryOp::BoolOr,
+        BinaryOp::Eq => HirExprBinaryOp::Eq,
+        BinaryOp::NotEq => HirExprBinaryOp::NotEq,
+        BinaryOp::Less => HirExprBinaryOp::Less,
+        BinaryOp::LessEq => HirExprBinaryOp::LessEq,
+        BinaryOp::Greater => HirExprBinaryOp::Greater,
+        BinaryOp::GreaterEq => HirExprBinaryOp::GreaterEq,
+    }
 }
@@ -1,8 +1,11 @@
 //! Definitions of common HIR node types describing various instructions and
 //! language constructs.
+use crate::error::CompilerError;
 use crate::hir::arena;
-use crate::hir::context::HirFunctionContext;
-use haikulang_parser::span::Span;
+use crate::hir::arena::Arena;
+use crate::hir::attrs::HirAttributes;
+use haikulang_parser::span::{Span, Spanned};
+use num_bigint::BigUint;
 
 /// Holder of a literal value.
 #[derive(Clone, Debug)]
@@ -19,13 +22,23 @@ pub enum HirLiteralKind {
     I16(i16),
     I32(i32),
     I64(i64),
+    I128(i128),
+    ISize(isize),
     U8(u8),
     U16(u16),
     U32(u32),
     U64(u64),
+    U128(u128),
+    USize(usize),
     F32(f32),
     F64(f64),
     String(HirStringId),
+    Char(char),
+    Bytes(Box<[u8]>),
+
+    // An integer literal without a suffix. The type checker decides which integer type
+    // it has from the context it is used in.
+    Int(BigUint),
 }
 
 /// The type for string literals or a variable name.
@@ -34,10 +47,49 @@ pub type HirString = String;
 /// Reference to a String literal that is interned.
 pub type HirStringId = arena::Id<HirString>;
 
+/// Reference to a type, as written in the source code. These are resolved to concrete
+/// types during type checking.
+#[derive(Clone, Debug)]
+pub struct HirTypeRef {
+    pub kind: HirTypeRefKind,
+    pub span: Span,
+}
+
+/// The variant of a type reference.
+#[derive(Clone, Debug)]
+pub enum HirTypeRefKind {
+    Named(HirStringId),
+    Function {
+        parameters: Vec<HirTypeRef>,
+        return_type: Option<Box<HirTypeRef>>,
+    },
+    Tuple(Vec<HirTypeRef>),
+}
+
+impl HirTypeRef {
+    fn shift(&mut self, delta: isize) {
+        self.span = self.span.shifted(delta);
+        match &mut self.kind {
+            HirTypeRefKind::Named(_) => {}
+            HirTypeRefKind::Function {
+                parameters,
+                return_type,
+            } => parameters
+                .iter_mut()
+                .chain(return_type.as_deref_mut())
+                .for_each(|type_ref| type_ref.shift(delta)),
+            HirTypeRefKind::Tuple(elements) => elements
+                .iter_mut()
+                .for_each(|type_ref| type_ref.shift(delta)),
+        }
+    }
+}
+
 /// Representation of a local variable.
 #[derive(Clone, Debug)]
 pub struct HirVariable {
     pub name: HirStringId,
+    pub type_ref: Option<HirTypeRef>,
     pub location: Span,
 }
 
@@ -59,6 +111,9 @@ pub type HirExprId = arena::Id<HirExpr>;
 pub enum HirExprKind {
     LoadLiteral(HirLiteral),
     LoadVariable(HirVariableId),
+    LoadFunction(HirStringId),
+    LoadStruct(HirStringId),
+    LoadBuiltin(HirBuiltin),
     BinaryOp {
         left: HirExprId,
         op: HirExprBinaryOp,
@@ -68,13 +123,111 @@ pub enum HirExprKind {
         op: HirExprUnaryOp,
         value: HirExprId,
     },
+    Assign {
+        target: HirExprId,
+        op: Option<HirExprBinaryOp>,
+        value: HirExprId,
+    },
+    MemberAccess {
+        owner: HirExprId,
+        member: HirStringId,
+    },
+    Index {
+        owner: HirExprId,
+        index: HirExprId,
+    },
+    Call {
+        callee: HirExprId,
+        arguments: Vec<HirExprId>,
+    },
+    Closure(Box<HirClosure>),
+    Tuple(Vec<HirExprId>),
+    // A format string that has already been split up, which produces a string by joining
+    // its parts together.
+    Format(Vec<HirFormatPart>),
 
     // Something probably in an outside scope, since it is definitely not in this scope.
     Unresolved(HirString),
 }
 
-/// Operators that can be used in binary expressions.
+/// Functions that are built into the language itself rather than declared in a module.
+///
+/// These can only be called directly, as they have access to the source code of their
+/// arguments so that they can describe them when they fail.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HirBuiltin {
+    /// `assert(condition)` fails if the condition is false.
+    Assert,
+    /// `assert_eq(left, right)` fails if the two values are not equal.
+    AssertEq,
+    /// `format("...", values...)` fills in the placeholders of a format string.
+    Format,
+    /// `print("...", values...)` writes a format string to standard output.
+    Print,
+    /// `println("...", values...)` writes a format string to standard output, followed by
+    /// a new line.
+    Println,
+}
+
+impl HirBuiltin {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "assert" => Some(Self::Assert),
+            "assert_eq" => Some(Self::AssertEq),
+            "format" => Some(Self::Format),
+            "print" => Some(Self::Print),
+            "println" => Some(Self::Println),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Assert => "assert",
+            Self::AssertEq => "assert_eq",
+            Self::Format => "format",
+            Self::Print => "print",
+            Self::Println => "println",
+        }
+    }
+
+    /// Whether the first argument is a format string. These builtins are also part of the
+    /// standard library, so they can be used in the same way as its other functions.
+    pub fn takes_format_string(&self) -> bool {
+        matches!(self, Self::Format | Self::Print | Self::Println)
+    }
+}
+
+/// Part of a format string, once its placeholders have been matched up with their values.
 #[derive(Clone, Debug)]
+pub enum HirFormatPart {
+    Text(HirStringId),
+    Value(HirExprId),
+}
+
+/// Representation of a closure expression.
+///
+/// Closures share the arenas of the function that they are declared in. Any variables from
+/// enclosing scopes that the body refers to are captured by value when the closure is created.
+#[derive(Clone, Debug)]
+pub struct HirClosure {
+    pub parameters: Vec<HirVariableId>,
+    pub return_type: Option<HirTypeRef>,
+    pub captures: Vec<HirVariableId>,
+    pub body: HirClosureBody,
+}
+
+/// The body of a closure.
+#[derive(Clone, Copy, Debug)]
+pub enum HirClosureBody {
+    // The closure produces the value of the expression.
+    Expr(HirExprId),
+    // The closure produces whatever the block returns.
+    Block(HirStatementId),
+}
+
+/// Operators that can be used in binary expressions.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum HirExprBinaryOp {
     Add,
     Sub,
@@ -98,7 +251,7 @@ pub enum HirExprBinaryOp {
 }
 
 /// Operators that can be used in unary expressions.
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum HirExprUnaryOp {
     Negate,
     Not,
@@ -123,6 +276,13 @@ pub enum HirStatementKind {
         variable: HirVariableId,
         expr: Option<HirExprId>,
     },
+    // Declares the variables within a pattern, taking their values from the matching parts
+    // of the value of the expression.
+    Destructure {
+        pattern: HirPattern,
+        type_ref: Option<HirTypeRef>,
+        expr: HirExprId,
+    },
     Expr(HirExprId),
     Return(Option<HirExprId>),
     Continue,
@@ -139,18 +299,146 @@ pub enum HirStatementKind {
     Block(HirBlock),
 }
 
+/// The variables declared by a destructuring declaration.
+#[derive(Clone, Debug)]
+pub enum HirPattern {
+    Variable(HirVariableId),
+    Tuple {
+        elements: Vec<HirPattern>,
+        span: Span,
+    },
+}
+
+impl HirPattern {
+    pub fn span(&self, function: &HirFunctionData) -> Span {
+        match self {
+            Self::Variable(variable) => function.get_variable(*variable).location,
+            Self::Tuple { span, .. } => *span,
+        }
+    }
+
+    fn shift(&mut self, delta: isize) {
+        if let Self::Tuple { elements, span } = self {
+            *span = span.shifted(delta);
+            elements.iter_mut().for_each(|element| element.shift(delta));
+        }
+    }
+}
+
 /// Representation of a function prototype which we have not yet lowered.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct HirFunctionHeader {
     pub name: HirStringId,
+    pub parameters: Vec<HirParameter>,
+    pub return_type: Option<HirTypeRef>,
+    pub is_extern: bool,
+    pub is_pub: bool,
+    pub attributes: HirAttributes,
+    pub span: Span,
+}
+
+/// Representation of a parameter within a function prototype.
+#[derive(Clone, Debug)]
+pub struct HirParameter {
+    pub name: HirStringId,
+    pub type_ref: HirTypeRef,
+    pub span: Span,
+}
+
+/// Representation of a struct declaration.
+#[derive(Clone, Debug)]
+pub struct HirStructHeader {
+    pub name: HirStringId,
+    pub members: Vec<HirStructMember>,
+    pub is_pub: bool,
+    pub attributes: HirAttributes,
+    pub span: Span,
+}
+
+/// Representation of a member within a struct declaration.
+#[derive(Clone, Debug)]
+pub struct HirStructMember {
+    pub name: HirStringId,
+    pub type_ref: HirTypeRef,
+    pub is_pub: bool,
+    pub attributes: HirAttributes,
+    pub span: Span,
 }
 
 /// Representation of a function body for a lowered function prototype.
-#[derive(Debug)]
-pub struct HirFunctionData<'context> {
-    pub context: HirFunctionContext<'context>,
+///
+/// This owns the arenas that were populated while lowering the function, so it can be
+/// kept around after the lowering context has been discarded.
+#[derive(Clone, Debug)]
+pub struct HirFunctionData {
     pub header: HirFunctionHeader,
+    pub parameters: Vec<HirVariableId>,
+    pub expr_arena: Arena<HirExpr>,
+    pub statement_arena: Arena<HirStatement>,
+    pub variable_arena: Arena<HirVariable>,
     pub root_statement: HirStatementId,
+    pub errors: Vec<Spanned<CompilerError>>,
+}
+
+impl HirFunctionData {
+    pub fn get_expr(&self, id: HirExprId) -> &HirExpr {
+        &self.expr_arena[id]
+    }
+
+    pub fn get_statement(&self, id: HirStatementId) -> &HirStatement {
+        &self.statement_arena[id]
+    }
+
+    pub fn get_variable(&self, id: HirVariableId) -> &HirVariable {
+        &self.variable_arena[id]
+    }
+
+    /// Produce a copy of this function with every span moved by the given number of bytes.
+    /// This lets us reuse a lowered function when only the code before it has changed.
+    pub fn shifted(&self, delta: isize) -> Self {
+        let mut shifted = self.clone();
+
+        for (_, expr) in shifted.expr_arena.iter_mut() {
+            expr.span = expr.span.shifted(delta);
+            match &mut expr.kind {
+                HirExprKind::LoadLiteral(literal) => literal.span = literal.span.shifted(delta),
+                HirExprKind::Closure(closure) => {
+                    if let Some(return_type) = &mut closure.return_type {
+                        return_type.shift(delta);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for (_, statement) in shifted.statement_arena.iter_mut() {
+            statement.span = statement.span.shifted(delta);
+            if let HirStatementKind::Destructure {
+                pattern, type_ref, ..
+            } = &mut statement.kind
+            {
+                pattern.shift(delta);
+                if let Some(type_ref) = type_ref {
+                    type_ref.shift(delta);
+                }
+            }
+        }
+
+        for (_, variable) in shifted.variable_arena.iter_mut() {
+            variable.location = variable.location.shifted(delta);
+            if let Some(type_ref) = &mut variable.type_ref {
+                type_ref.shift(delta);
+            }
+        }
+
+        shifted.errors = shifted
+            .errors
+            .iter()
+            .map(|error| Spanned::new(error.value(), error.span().shifted(delta)))
+            .collect();
+
+        shifted
+    }
 }
 
 /// Representation of a block body.
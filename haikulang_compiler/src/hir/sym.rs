@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::collections::hash_map::Entry;
 use std::fmt::Debug;
 use std::hash::Hash;
 
@@ -30,37 +31,47 @@ impl<Key: Clone + Debug + Eq + Hash, Value> SymbolTable<Key, Value> {
 
     /// Declare an item in the current scope.
     ///
-    /// This panics if no scope exists, or if the variable was already defined in
-    /// the current scope. We do however allow shadowing outer scopes.
-    pub fn declare(&mut self, key: Key, value: Value) {
+    /// This panics if no scope exists. If the item was already defined in the current
+    /// scope, then the existing definition is kept and the rejected value is handed back
+    /// so the caller can report it. We do however allow shadowing outer scopes.
+    pub fn declare(&mut self, key: Key, value: Value) -> Result<(), Value> {
         let last_frame = self.stack.last_mut().expect("no frame in scope");
 
-        // TODO(ascopes): handle already-defined-symbols as a Result type rather than
-        // panicking. We can emit Error types in the HIR nodes to describe these issues
-        // and report them to an error collector.
-
-        // Once try_insert is stable, we can use that instead of doing two operations here
-        // (i.e. contains_key followed by insert).
-        if last_frame.contains_key(&key) {
-            panic!("Variable {:?} was already defined", key)
-        } else {
-            last_frame.insert(key, value);
+        match last_frame.entry(key) {
+            Entry::Occupied(_) => Err(value),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                Ok(())
+            }
         }
     }
 
+    /// The number of scopes currently on the stack.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Attempt to fetch the definition for the current variable, along with the index of the
+    /// scope that defines it. The outermost scope has an index of zero.
+    pub fn lookup_with_scope(&self, key: &Key) -> Option<(usize, &Value)> {
+        self.stack
+            .iter()
+            .enumerate()
+            // Start at the top of the stack and work our way down, stopping at the
+            // first scope that defines the key.
+            .rev()
+            .find_map(|(index, m)| m.get(key).map(|value| (index, value)))
+    }
+
     /// Attempt to fetch the definition for the current variable. This checks each
     /// scope in reverse from the top of the stack to the bottom, returning None if
     /// no definition was found.
     pub fn lookup(&self, key: &Key) -> Option<&Value> {
-        self.stack
-            .iter()
-            // Start at the top of the stack and work our way down.
-            .rev()
-            // Fetch the variable from each scope.
-            .map(|m| m.get(key))
-            // Continue until we find the first non-None match, and then stop.
-            .skip_while(Option::is_none)
-            .next()
-            .flatten()
+        self.lookup_with_scope(key).map(|(_, value)| value)
+    }
+
+    /// Iterate over every definition in every scope, in no particular order.
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.stack.iter().flat_map(|frame| frame.values())
     }
 }
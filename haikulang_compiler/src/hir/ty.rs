@@ -0,0 +1,354 @@
+//! Types that values may take once HIR has been type checked.
+use crate::hir::nodes::HirLiteralKind;
+use num_bigint::BigInt;
+use std::fmt::{Display, Formatter};
+
+/// A resolved type.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HirType {
+    Unit,
+    Bool,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    ISize,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    USize,
+    F32,
+    F64,
+    String,
+    Char,
+    Bytes,
+    // An arbitrary-precision integer provided by the standard library's runtime.
+    BigInt,
+    // A reference to an object on the heap that the standard library's runtime manages.
+    Handle,
+    Struct(String),
+    Function(Box<HirFunctionType>),
+    // Always has at least one element, as the empty tuple is the unit type.
+    Tuple(Vec<HirType>),
+
+    // The type could not be determined, usually because of an earlier error. This is
+    // compatible with every other type so that we do not cascade errors.
+    Unknown,
+}
+
+/// The signature of a function or closure, once all of the types it mentions have been
+/// resolved.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HirFunctionType {
+    pub parameters: Vec<HirType>,
+    pub return_type: HirType,
+}
+
+impl HirType {
+    /// Look up a built-in type by the name it is written as in source code.
+    pub fn primitive(name: &str) -> Option<Self> {
+        match name {
+            "bool" => Some(Self::Bool),
+            "i8" => Some(Self::I8),
+            "i16" => Some(Self::I16),
+            "i32" => Some(Self::I32),
+            "i64" => Some(Self::I64),
+            "i128" => Some(Self::I128),
+            "isize" => Some(Self::ISize),
+            "u8" => Some(Self::U8),
+            "u16" => Some(Self::U16),
+            "u32" => Some(Self::U32),
+            "u64" => Some(Self::U64),
+            "u128" => Some(Self::U128),
+            "usize" => Some(Self::USize),
+            "f32" => Some(Self::F32),
+            "f64" => Some(Self::F64),
+            "string" => Some(Self::String),
+            "char" => Some(Self::Char),
+            "bytes" => Some(Self::Bytes),
+            "std::BigInt" => Some(Self::BigInt),
+            "std::Handle" => Some(Self::Handle),
+            _ => None,
+        }
+    }
+
+    /// Make a tuple type from the types of its elements.
+    pub fn tuple(elements: Vec<HirType>) -> Self {
+        if elements.is_empty() {
+            Self::Unit
+        } else {
+            Self::Tuple(elements)
+        }
+    }
+
+    /// Determine the type of a literal. Integer literals without a suffix are given their
+    /// type from context by the type checker, so this is only their default type.
+    pub fn of_literal(literal: &HirLiteralKind) -> Self {
+        match literal {
+            HirLiteralKind::Bool(_) => Self::Bool,
+            HirLiteralKind::I8(_) => Self::I8,
+            HirLiteralKind::I16(_) => Self::I16,
+            HirLiteralKind::I32(_) => Self::I32,
+            HirLiteralKind::I64(_) => Self::I64,
+            HirLiteralKind::I128(_) => Self::I128,
+            HirLiteralKind::ISize(_) => Self::ISize,
+            HirLiteralKind::U8(_) => Self::U8,
+            HirLiteralKind::U16(_) => Self::U16,
+            HirLiteralKind::U32(_) => Self::U32,
+            HirLiteralKind::U64(_) => Self::U64,
+            HirLiteralKind::U128(_) => Self::U128,
+            HirLiteralKind::USize(_) => Self::USize,
+            HirLiteralKind::F32(_) => Self::F32,
+            HirLiteralKind::F64(_) => Self::F64,
+            HirLiteralKind::String(_) => Self::String,
+            HirLiteralKind::Char(_) => Self::Char,
+            HirLiteralKind::Bytes(_) => Self::Bytes,
+            HirLiteralKind::Int(_) => Self::I32,
+        }
+    }
+
+    pub fn is_unknown(&self) -> bool {
+        *self == Self::Unknown
+    }
+
+    pub fn is_signed_integer(&self) -> bool {
+        matches!(
+            self,
+            Self::I8 | Self::I16 | Self::I32 | Self::I64 | Self::I128 | Self::ISize
+        )
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.is_signed_integer()
+            || matches!(
+                self,
+                Self::U8 | Self::U16 | Self::U32 | Self::U64 | Self::U128 | Self::USize
+            )
+    }
+
+    /// The smallest and largest values that an integer type can hold.
+    pub fn integer_range(&self) -> Option<(BigInt, BigInt)> {
+        let range = match self {
+            Self::I8 => (i8::MIN.into(), i8::MAX.into()),
+            Self::I16 => (i16::MIN.into(), i16::MAX.into()),
+            Self::I32 => (i32::MIN.into(), i32::MAX.into()),
+            Self::I64 => (i64::MIN.into(), i64::MAX.into()),
+            Self::I128 => (i128::MIN.into(), i128::MAX.into()),
+            Self::ISize => (isize::MIN.into(), isize::MAX.into()),
+            Self::U8 => (u8::MIN.into(), u8::MAX.into()),
+            Self::U16 => (u16::MIN.into(), u16::MAX.into()),
+            Self::U32 => (u32::MIN.into(), u32::MAX.into()),
+            Self::U64 => (u64::MIN.into(), u64::MAX.into()),
+            Self::U128 => (u128::MIN.into(), u128::MAX.into()),
+            Self::USize => (usize::MIN.into(), usize::MAX.into()),
+            _ => return None,
+        };
+        Some(range)
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, Self::F32 | Self::F64)
+    }
+
+    /// Whether arithmetic and comparison operators can be applied to the type. Unlike the
+    /// other numeric types, big integers are never a fixed width, so they are not counted as
+    /// integers.
+    pub fn is_numeric(&self) -> bool {
+        self.is_integer() || self.is_float() || *self == Self::BigInt
+    }
+
+    /// Whether values of the type can be written into a format string. Only types with a
+    /// single obvious textual form can be, which are booleans, characters, strings, bytes,
+    /// numbers and tuples of those. Structs cannot declare how they are written out yet, so
+    /// they are rejected along with functions, handles and the unit type.
+    pub fn is_formattable(&self) -> bool {
+        match self {
+            Self::Tuple(elements) => elements.iter().all(Self::is_formattable),
+            Self::Unit | Self::Handle | Self::Struct(_) | Self::Function(_) => false,
+            _ => true,
+        }
+    }
+
+    /// Determine whether a value of the given type can be used where this type is expected.
+    pub fn accepts(&self, other: &Self) -> bool {
+        match (self, other) {
+            // Function and tuple types are compared one part at a time, so that an unknown type within
+            // one of them does not cascade into another error.
+            (Self::Function(expected), Self::Function(actual)) => {
+                expected.parameters.len() == actual.parameters.len()
+                    && expected
+                        .parameters
+                        .iter()
+                        .zip(&actual.parameters)
+                        .all(|(expected, actual)| expected.accepts(actual))
+                    && expected.return_type.accepts(&actual.return_type)
+            }
+            (Self::Tuple(expected), Self::Tuple(actual)) => {
+                expected.len() == actual.len()
+                    && expected
+                        .iter()
+                        .zip(actual)
+                        .all(|(expected, actual)| expected.accepts(actual))
+            }
+            _ => self.is_unknown() || other.is_unknown() || self == other,
+        }
+    }
+}
+
+impl Display for HirType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unit => write!(f, "()"),
+            Self::Bool => write!(f, "bool"),
+            Self::I8 => write!(f, "i8"),
+            Self::I16 => write!(f, "i16"),
+            Self::I32 => write!(f, "i32"),
+            Self::I64 => write!(f, "i64"),
+            Self::I128 => write!(f, "i128"),
+            Self::ISize => write!(f, "isize"),
+            Self::U8 => write!(f, "u8"),
+            Self::U16 => write!(f, "u16"),
+            Self::U32 => write!(f, "u32"),
+            Self::U64 => write!(f, "u64"),
+            Self::U128 => write!(f, "u128"),
+            Self::USize => write!(f, "usize"),
+            Self::F32 => write!(f, "f32"),
+            Self::F64 => write!(f, "f64"),
+            Self::String => write!(f, "string"),
+            Self::Char => write!(f, "char"),
+            Self::Bytes => write!(f, "bytes"),
+            Self::BigInt => write!(f, "std::BigInt"),
+            Self::Handle => write!(f, "std::Handle"),
+            Self::Struct(name) => write!(f, "{}", name),
+            Self::Function(function) => {
+                write!(f, "fn(")?;
+                for (index, parameter) in function.parameters.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", parameter)?;
+                }
+                write!(f, ")")?;
+                if function.return_type != Self::Unit {
+                    write!(f, " -> {}", function.return_type)?;
+                }
+                Ok(())
+            }
+            Self::Tuple(elements) => {
+                write!(f, "(")?;
+                for (index, element) in elements.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                if elements.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            }
+            Self::Unknown => write!(f, "{{unknown}}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn function(parameters: Vec<HirType>, return_type: HirType) -> HirType {
+        HirType::Function(Box::new(HirFunctionType {
+            parameters,
+            return_type,
+        }))
+    }
+
+    #[test_case(       "bool",   Some(HirType::Bool) ; "bool")]
+    #[test_case(         "i8",     Some(HirType::I8) ; "i8")]
+    #[test_case(        "u64",    Some(HirType::U64) ; "u64")]
+    #[test_case(       "i128",   Some(HirType::I128) ; "i128")]
+    #[test_case(      "usize",  Some(HirType::USize) ; "usize")]
+    #[test_case(        "f32",    Some(HirType::F32) ; "f32")]
+    #[test_case(     "string", Some(HirType::String) ; "string")]
+    #[test_case(       "char",   Some(HirType::Char) ; "char")]
+    #[test_case(      "bytes",  Some(HirType::Bytes) ; "bytes")]
+    #[test_case("std::BigInt", Some(HirType::BigInt) ; "big integer")]
+    #[test_case("std::Handle", Some(HirType::Handle) ; "heap handle")]
+    #[test_case(        "Foo",                  None ; "not a primitive")]
+    fn primitive_types_resolve_by_name(name: &str, expected: Option<HirType>) {
+        // Then
+        assert_eq!(HirType::primitive(name), expected);
+    }
+
+    #[test_case(              HirType::I32,               HirType::I32,  true ; "same types")]
+    #[test_case(              HirType::I32,               HirType::I64, false ; "different types")]
+    #[test_case(          HirType::Unknown,              HirType::Bool,  true ; "unknown expected type")]
+    #[test_case(             HirType::Bool,           HirType::Unknown,  true ; "unknown actual type")]
+    #[test_case(HirType::Struct("A".into()), HirType::Struct("B".into()), false ; "different structs")]
+    #[test_case(function(vec![HirType::I32], HirType::Bool), function(vec![HirType::I32], HirType::Bool),  true ; "same function types")]
+    #[test_case(function(vec![HirType::I32], HirType::Bool),  function(vec![HirType::I32], HirType::I32), false ; "different return types")]
+    #[test_case(function(vec![HirType::I32], HirType::Bool),             function(vec![], HirType::Bool), false ; "different parameter counts")]
+    #[test_case(function(vec![HirType::I32], HirType::Bool), function(vec![HirType::Unknown], HirType::Bool),  true ; "unknown parameter types")]
+    #[test_case(HirType::Tuple(vec![HirType::I32, HirType::Bool]), HirType::Tuple(vec![HirType::I32, HirType::Unknown]),  true ; "unknown element types")]
+    #[test_case(HirType::Tuple(vec![HirType::I32, HirType::Bool]),               HirType::Tuple(vec![HirType::I32]), false ; "different element counts")]
+    fn types_accept_compatible_types(expected: HirType, actual: HirType, accepts: bool) {
+        // Then
+        assert_eq!(expected.accepts(&actual), accepts);
+    }
+
+    #[test_case(                 HirType::Unit,        "()" ; "unit type")]
+    #[test_case(                  HirType::U16,       "u16" ; "u16")]
+    #[test_case(  HirType::Struct("Foo".into()),       "Foo" ; "struct type")]
+    #[test_case(function(vec![HirType::I32, HirType::Bool], HirType::I32), "fn(i32, bool) -> i32" ; "function type")]
+    #[test_case(                       function(vec![], HirType::Unit),                 "fn()" ; "function type without a return type")]
+    #[test_case(HirType::Tuple(vec![HirType::I32, HirType::Bool]), "(i32, bool)" ; "tuple type")]
+    #[test_case(                       HirType::Tuple(vec![HirType::I32]),      "(i32,)" ; "tuple type with one element")]
+    #[test_case(              HirType::Unknown, "{unknown}" ; "unknown")]
+    #[test_case(                HirType::ISize,     "isize" ; "isize")]
+    #[test_case(               HirType::BigInt, "std::BigInt" ; "big integer")]
+    #[test_case(               HirType::Handle, "std::Handle" ; "heap handle")]
+    fn types_format_correctly(ty: HirType, expected: &str) {
+        // Then
+        assert_eq!(format!("{}", ty), expected);
+    }
+
+    #[test_case(                                     HirType::String,  true ; "strings")]
+    #[test_case(                                     HirType::BigInt,  true ; "big integers")]
+    #[test_case(                                    HirType::Unknown,  true ; "unknown")]
+    #[test_case(                                       HirType::Unit, false ; "unit")]
+    #[test_case(                                     HirType::Handle, false ; "handles")]
+    #[test_case(                         HirType::Struct("P".into()), false ; "structs")]
+    #[test_case(                     function(vec![], HirType::Unit), false ; "functions")]
+    #[test_case(    HirType::Tuple(vec![HirType::I32, HirType::Bool]),  true ; "tuples")]
+    #[test_case(  HirType::Tuple(vec![function(vec![], HirType::Unit)]), false ; "tuples of functions")]
+    #[test_case(HirType::Tuple(vec![HirType::I32, HirType::Struct("P".into())]), false ; "tuples of structs")]
+    fn formattable_types_are_identified(ty: HirType, expected: bool) {
+        // Then
+        assert_eq!(ty.is_formattable(), expected);
+    }
+
+    #[test_case(  HirType::I8,   "-128",  "127" ; "i8")]
+    #[test_case( HirType::U16,      "0", "65535" ; "u16")]
+    #[test_case(HirType::U128,      "0", "340282366920938463463374607431768211455" ; "u128")]
+    #[test_case(HirType::I128, "-170141183460469231731687303715884105728", "170141183460469231731687303715884105727" ; "i128")]
+    fn integer_types_have_ranges(ty: HirType, min: &str, max: &str) {
+        // When
+        let (actual_min, actual_max) = ty.integer_range().unwrap();
+
+        // Then
+        assert_eq!(actual_min.to_string(), min);
+        assert_eq!(actual_max.to_string(), max);
+    }
+
+    #[test_case(  HirType::Bool ; "bool")]
+    #[test_case(   HirType::F64 ; "f64")]
+    #[test_case(HirType::BigInt ; "big integer")]
+    fn non_integer_types_have_no_range(ty: HirType) {
+        // Then
+        assert_eq!(ty.integer_range(), None);
+    }
+}
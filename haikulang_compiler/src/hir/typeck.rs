@@ -0,0 +1,1339 @@
+//! Type checking for lowered functions.
+use crate::error::{CompilerError, CompilerWarning};
+use crate::hir::arena::ArenaMap;
+use crate::hir::attrs::HirAttributes;
+use crate::hir::context::HirModuleContext;
+use crate::hir::nodes::*;
+use crate::hir::ty::{HirFunctionType, HirType};
+use haikulang_parser::span::{Span, Spanned};
+use num_bigint::{BigInt, BigUint};
+use std::collections::{HashMap, HashSet};
+
+/// The outcome of type checking a single function.
+#[derive(Clone, Debug)]
+pub struct HirTypeckResult {
+    pub signature: HirFunctionType,
+    pub expr_types: ArenaMap<HirExprId, HirType>,
+    pub variable_types: ArenaMap<HirVariableId, HirType>,
+    pub errors: Vec<Spanned<CompilerError>>,
+    pub warnings: Vec<Spanned<CompilerWarning>>,
+}
+
+impl HirTypeckResult {
+    pub fn type_of_expr(&self, id: HirExprId) -> &HirType {
+        self.expr_types.get(id).unwrap_or(&HirType::Unknown)
+    }
+
+    pub fn type_of_variable(&self, id: HirVariableId) -> &HirType {
+        self.variable_types.get(id).unwrap_or(&HirType::Unknown)
+    }
+
+    /// Produce a copy of this result with every error and warning span moved by the given
+    /// number of bytes.
+    pub fn shifted(&self, delta: isize) -> Self {
+        let mut shifted = self.clone();
+        shifted.errors = shifted
+            .errors
+            .iter()
+            .map(|error| Spanned::new(error.value(), error.span().shifted(delta)))
+            .collect();
+        shifted.warnings = shifted
+            .warnings
+            .iter()
+            .map(|warning| Spanned::new(warning.value(), warning.span().shifted(delta)))
+            .collect();
+        shifted
+    }
+}
+
+/// The items from other modules that a function refers to by their qualified names.
+///
+/// Any structs mentioned by these items are named by their qualified names too, so that they
+/// cannot be confused with structs of the same name in the module being checked.
+#[derive(Clone, Debug, Default)]
+pub struct HirImports {
+    functions: HashMap<String, HirFunctionType>,
+    structs: HashSet<String>,
+}
+
+impl HirImports {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_function(&mut self, path: String, signature: HirFunctionType) {
+        self.functions.insert(path, signature);
+    }
+
+    pub fn add_struct(&mut self, path: String) {
+        self.structs.insert(path);
+    }
+
+    pub fn function(&self, path: &str) -> Option<&HirFunctionType> {
+        self.functions.get(path)
+    }
+
+    pub fn has_struct(&self, path: &str) -> bool {
+        self.structs.contains(path)
+    }
+}
+
+/// Resolve the signature of the given function header.
+pub fn resolve_signature(
+    module: &HirModuleContext,
+    imports: &HirImports,
+    header: &HirFunctionHeader,
+    errors: &mut Vec<Spanned<CompilerError>>,
+) -> HirFunctionType {
+    HirFunctionType {
+        parameters: header
+            .parameters
+            .iter()
+            .map(|param| resolve_type_ref(module, imports, &param.type_ref, errors))
+            .collect(),
+        return_type: header
+            .return_type
+            .as_ref()
+            .map(|type_ref| resolve_type_ref(module, imports, type_ref, errors))
+            .unwrap_or(HirType::Unit),
+    }
+}
+
+/// Type check the body of the given function. Any errors found while lowering the function
+/// are included in the result, so that callers have a single place to look for diagnostics.
+pub fn check_function(
+    module: &HirModuleContext,
+    imports: &HirImports,
+    function: &HirFunctionData,
+) -> HirTypeckResult {
+    let mut errors = function.errors.clone();
+    let signature = resolve_signature(module, imports, &function.header, &mut errors);
+
+    let mut checker = TypeChecker {
+        module,
+        imports,
+        function,
+        return_type: signature.return_type.clone(),
+        expr_types: ArenaMap::new(),
+        variable_types: ArenaMap::new(),
+        defaulted_variables: HashSet::new(),
+        errors,
+        warnings: Vec::new(),
+    };
+
+    for (variable_id, param_type) in function.parameters.iter().zip(&signature.parameters) {
+        checker
+            .variable_types
+            .insert(*variable_id, param_type.clone());
+    }
+
+    checker.check_statement(function.root_statement);
+
+    HirTypeckResult {
+        signature,
+        expr_types: checker.expr_types,
+        variable_types: checker.variable_types,
+        errors: checker.errors,
+        warnings: checker.warnings,
+    }
+}
+
+fn resolve_type_ref(
+    module: &HirModuleContext,
+    imports: &HirImports,
+    type_ref: &HirTypeRef,
+    errors: &mut Vec<Spanned<CompilerError>>,
+) -> HirType {
+    match &type_ref.kind {
+        HirTypeRefKind::Named(name) => module.resolve_type(type_ref).unwrap_or_else(|| {
+            let name = module.get_string(*name).clone();
+            if imports.has_struct(&name) {
+                return HirType::Struct(name);
+            }
+            // Qualified names refer to other modules, which are resolved by the database.
+            if !name.contains("::") {
+                errors.push(Spanned::new(
+                    CompilerError::UnknownType(name),
+                    type_ref.span,
+                ));
+            }
+            HirType::Unknown
+        }),
+        // Resolve each part separately, so that errors point at the unknown part.
+        HirTypeRefKind::Function {
+            parameters,
+            return_type,
+        } => HirType::Function(Box::new(HirFunctionType {
+            parameters: parameters
+                .iter()
+                .map(|parameter| resolve_type_ref(module, imports, parameter, errors))
+                .collect(),
+            return_type: return_type
+                .as_ref()
+                .map(|return_type| resolve_type_ref(module, imports, return_type, errors))
+                .unwrap_or(HirType::Unit),
+        })),
+        HirTypeRefKind::Tuple(elements) => HirType::tuple(
+            elements
+                .iter()
+                .map(|element| resolve_type_ref(module, imports, element, errors))
+                .collect(),
+        ),
+    }
+}
+
+struct TypeChecker<'a> {
+    module: &'a HirModuleContext,
+    imports: &'a HirImports,
+    function: &'a HirFunctionData,
+    return_type: HirType,
+    expr_types: ArenaMap<HirExprId, HirType>,
+    variable_types: ArenaMap<HirVariableId, HirType>,
+    // Variables declared without a type whose only initializer was an unsuffixed integer
+    // literal. Their type is fixed as i32 there and then, rather than being inferred from
+    // later uses, so mismatches involving them explain where the i32 came from.
+    defaulted_variables: HashSet<HirVariableId>,
+    errors: Vec<Spanned<CompilerError>>,
+    warnings: Vec<Spanned<CompilerWarning>>,
+}
+
+impl<'a> TypeChecker<'a> {
+    fn check_statement(&mut self, id: HirStatementId) {
+        let statement = self.function.get_statement(id);
+        let span = statement.span;
+
+        match &statement.kind {
+            HirStatementKind::Empty | HirStatementKind::Break | HirStatementKind::Continue => {}
+            HirStatementKind::VarDecl { variable, expr } => {
+                let declared_type =
+                    self.function
+                        .get_variable(*variable)
+                        .type_ref
+                        .as_ref()
+                        .map(|type_ref| {
+                            resolve_type_ref(self.module, self.imports, type_ref, &mut self.errors)
+                        });
+                let actual_type = expr.map(|expr| {
+                    (
+                        self.check_expr_with_hint(expr, declared_type.as_ref()),
+                        expr,
+                    )
+                });
+
+                let variable_type = match (declared_type, actual_type) {
+                    (Some(declared_type), Some((actual_type, expr))) => {
+                        self.expect_expr(&declared_type, &actual_type, expr);
+                        declared_type
+                    }
+                    (Some(declared_type), None) => declared_type,
+                    (None, Some((actual_type, expr))) => {
+                        if self.is_untyped_int(expr) {
+                            self.defaulted_variables.insert(*variable);
+                        }
+                        actual_type
+                    }
+                    (None, None) => HirType::Unknown,
+                };
+
+                self.variable_types.insert(*variable, variable_type);
+            }
+            HirStatementKind::Destructure {
+                pattern,
+                type_ref,
+                expr,
+            } => {
+                let declared_type = type_ref.as_ref().map(|type_ref| {
+                    resolve_type_ref(self.module, self.imports, type_ref, &mut self.errors)
+                });
+                let actual_type = self.check_expr_with_hint(*expr, declared_type.as_ref());
+
+                let value_type = match declared_type {
+                    Some(declared_type) => {
+                        self.expect_expr(&declared_type, &actual_type, *expr);
+                        declared_type
+                    }
+                    None => actual_type,
+                };
+
+                self.bind_pattern(pattern, value_type);
+            }
+            HirStatementKind::Expr(expr) => {
+                self.check_expr(*expr);
+            }
+            HirStatementKind::Return(expr) => {
+                let return_type = self.return_type.clone();
+                match expr {
+                    Some(expr) => {
+                        let actual_type = self.check_expr_with_hint(*expr, Some(&return_type));
+                        self.expect_expr(&return_type, &actual_type, *expr);
+                    }
+                    None => self.expect(&return_type, &HirType::Unit, span),
+                }
+            }
+            HirStatementKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                self.check_condition(*condition);
+                self.check_statement(*then);
+                if let Some(otherwise) = otherwise {
+                    self.check_statement(*otherwise);
+                }
+            }
+            HirStatementKind::While { condition, body } => {
+                self.check_condition(*condition);
+                self.check_statement(*body);
+            }
+            HirStatementKind::Block(statements) => {
+                for statement in statements {
+                    self.check_statement(*statement);
+                }
+            }
+        }
+    }
+
+    fn check_condition(&mut self, condition: HirExprId) {
+        let condition_type = self.check_expr(condition);
+        self.expect(&HirType::Bool, &condition_type, self.expr_span(condition));
+    }
+
+    fn check_expr(&mut self, id: HirExprId) -> HirType {
+        self.check_expr_with_hint(id, None)
+    }
+
+    // Check an expression, using the type that its context expects it to have to decide
+    // the type of any unsuffixed integer literals within it.
+    fn check_expr_with_hint(&mut self, id: HirExprId, hint: Option<&HirType>) -> HirType {
+        let expr = self.function.get_expr(id);
+        let span = expr.span;
+
+        let expr_type = match &expr.kind {
+            HirExprKind::LoadLiteral(literal) => match &literal.kind {
+                HirLiteralKind::Int(value) => self.check_int_literal(value, false, hint, span),
+                kind => HirType::of_literal(kind),
+            },
+            HirExprKind::LoadVariable(variable) => self
+                .variable_types
+                .get(*variable)
+                .cloned()
+                .unwrap_or(HirType::Unknown),
+            HirExprKind::LoadFunction(name) => {
+                let header = self
+                    .module
+                    .lookup_function(*name)
+                    .expect("lowered function references always refer to declared functions");
+                self.warn_if_deprecated(self.module.get_string(*name), &header.attributes, span);
+                // Problems with the signature are reported when checking the function itself.
+                let signature =
+                    resolve_signature(self.module, self.imports, header, &mut Vec::new());
+                HirType::Function(Box::new(signature))
+            }
+            HirExprKind::LoadStruct(name) => {
+                let name = self.module.get_string(*name).clone();
+                self.error(
+                    CompilerError::InvalidOperand(format!(
+                        "struct {} cannot be used as a value",
+                        name
+                    )),
+                    span,
+                );
+                HirType::Unknown
+            }
+            HirExprKind::LoadBuiltin(builtin) => {
+                self.error(
+                    CompilerError::InvalidOperand(format!(
+                        "builtin {} can only be called directly",
+                        builtin.name()
+                    )),
+                    span,
+                );
+                HirType::Unknown
+            }
+            HirExprKind::BinaryOp { left, op, right } => {
+                let (left_type, right_type) = self.check_binary_operands(*left, *op, *right, hint);
+                self.check_binary_op(*op, left_type, right_type, span)
+            }
+            HirExprKind::UnaryOp { op, value } => {
+                let value_hint = if *op == HirExprUnaryOp::Not {
+                    None
+                } else {
+                    hint
+                };
+                let value_type = match self.untyped_int_literal(*value) {
+                    // Negative literals can hold one more value than positive ones, so we
+                    // have to check the negated value as a whole.
+                    Some(literal) if *op == HirExprUnaryOp::Negate => {
+                        let literal_type = self.check_int_literal(literal, true, value_hint, span);
+                        self.expr_types.insert(*value, literal_type.clone());
+                        literal_type
+                    }
+                    _ => self.check_expr_with_hint(*value, value_hint),
+                };
+                self.check_unary_op(*op, value_type, span)
+            }
+            HirExprKind::Assign { target, op, value } => {
+                self.check_assignment(*target, *op, *value);
+                HirType::Unit
+            }
+            HirExprKind::MemberAccess { owner, member } => {
+                let owner_type = self.check_expr(*owner);
+                self.check_member_access(owner_type, *member, span)
+            }
+            HirExprKind::Index { owner, index } => {
+                let owner_type = self.check_expr(*owner);
+                let index_type = self.check_expr_with_hint(*index, Some(&HirType::USize));
+                self.check_index(owner_type, index_type, self.expr_span(*index), span)
+            }
+            HirExprKind::Call { callee, arguments } => self.check_call(*callee, arguments, span),
+            HirExprKind::Closure(closure) => self.check_closure(closure, hint),
+            HirExprKind::Tuple(elements) => {
+                // Only pass on the hint if it has a type for every element.
+                let hints = match hint {
+                    Some(HirType::Tuple(hints)) if hints.len() == elements.len() => {
+                        Some(hints.as_slice())
+                    }
+                    _ => None,
+                };
+                HirType::tuple(
+                    elements
+                        .iter()
+                        .enumerate()
+                        .map(|(index, element)| {
+                            let hint = hints.map(|hints| &hints[index]);
+                            self.check_expr_with_hint(*element, hint)
+                        })
+                        .collect(),
+                )
+            }
+            HirExprKind::Format(parts) => {
+                self.check_format(parts);
+                HirType::String
+            }
+            HirExprKind::Unresolved(name) => match self.imports.function(name) {
+                Some(signature) => HirType::Function(Box::new(signature.clone())),
+                None => {
+                    // Qualified names that were not imported either refer to modules that
+                    // the database does not know about, or are reported when their paths
+                    // are checked.
+                    if !name.contains("::") {
+                        self.error(CompilerError::UnresolvedName(name.clone()), span);
+                    }
+                    HirType::Unknown
+                }
+            },
+        };
+
+        self.expr_types.insert(id, expr_type.clone());
+        expr_type
+    }
+
+    fn check_format(&mut self, parts: &[HirFormatPart]) {
+        for part in parts {
+            if let HirFormatPart::Value(value) = part {
+                let value_type = self.check_expr(*value);
+                if !value_type.is_formattable() {
+                    self.error(
+                        CompilerError::NotFormattable(value_type.to_string()),
+                        self.expr_span(*value),
+                    );
+                }
+            }
+        }
+    }
+
+    fn check_int_literal(
+        &mut self,
+        value: &BigUint,
+        negated: bool,
+        hint: Option<&HirType>,
+        span: Span,
+    ) -> HirType {
+        // Without any other context, integer literals are i32 values. Variables initialized
+        // this way keep that type, however they are used afterwards.
+        let literal_type = match hint {
+            Some(HirType::Unknown) => return HirType::Unknown,
+            Some(hint) if hint.is_integer() || *hint == HirType::BigInt => hint.clone(),
+            _ => HirType::I32,
+        };
+
+        let mut value = BigInt::from(value.clone());
+        if negated && (literal_type.is_signed_integer() || literal_type == HirType::BigInt) {
+            value = -value;
+        }
+
+        // Big integers have no range, so any literal fits within them.
+        if let Some((min, max)) = literal_type.integer_range()
+            && (value < min || value > max)
+        {
+            self.error(
+                CompilerError::IntLiteralOutOfRange {
+                    literal: value.to_string(),
+                    ty: literal_type.to_string(),
+                },
+                span,
+            );
+        }
+
+        literal_type
+    }
+
+    // Check both operands of a binary operator. Operands made up only of unsuffixed integer
+    // literals take the type of the other operand, so that side is checked first.
+    fn check_binary_operands(
+        &mut self,
+        left: HirExprId,
+        op: HirExprBinaryOp,
+        right: HirExprId,
+        hint: Option<&HirType>,
+    ) -> (HirType, HirType) {
+        // Operators that produce a value of the same type as their operands pass on the hint.
+        let operand_hint = match op {
+            HirExprBinaryOp::BoolAnd
+            | HirExprBinaryOp::BoolOr
+            | HirExprBinaryOp::Eq
+            | HirExprBinaryOp::NotEq
+            | HirExprBinaryOp::Less
+            | HirExprBinaryOp::LessEq
+            | HirExprBinaryOp::Greater
+            | HirExprBinaryOp::GreaterEq => None,
+            _ => hint,
+        };
+
+        match op {
+            // The shift amount is independent of the type of the value being shifted.
+            HirExprBinaryOp::BinaryShl | HirExprBinaryOp::BinaryShr => {
+                let left_type = self.check_expr_with_hint(left, operand_hint);
+                let right_type = self.check_expr(right);
+                (left_type, right_type)
+            }
+            _ if self.is_untyped_int(left) && !self.is_untyped_int(right) => {
+                let right_type = self.check_expr_with_hint(right, operand_hint);
+                let left_type = self.check_expr_with_hint(left, Some(&right_type));
+                (left_type, right_type)
+            }
+            _ => {
+                let left_type = self.check_expr_with_hint(left, operand_hint);
+                let right_type = self.check_expr_with_hint(right, Some(&left_type));
+                (left_type, right_type)
+            }
+        }
+    }
+
+    fn check_binary_op(
+        &mut self,
+        op: HirExprBinaryOp,
+        left: HirType,
+        right: HirType,
+        span: Span,
+    ) -> HirType {
+        let is_bool = |ty: &HirType| *ty == HirType::Bool;
+
+        // Fixed width integers are widened when they meet a big integer in arithmetic or a
+        // comparison, as every value that they can hold fits in one.
+        let widens = !matches!(
+            op,
+            HirExprBinaryOp::BinaryAnd
+                | HirExprBinaryOp::BinaryOr
+                | HirExprBinaryOp::BinaryXor
+                | HirExprBinaryOp::BinaryShl
+                | HirExprBinaryOp::BinaryShr
+                | HirExprBinaryOp::BoolAnd
+                | HirExprBinaryOp::BoolOr
+        );
+        let (left, right) = match (&left, &right) {
+            (HirType::BigInt, other) | (other, HirType::BigInt) if widens && other.is_integer() => {
+                (HirType::BigInt, HirType::BigInt)
+            }
+            _ => (left, right),
+        };
+
+        let (valid, result) = match op {
+            // Adding strings joins them together.
+            HirExprBinaryOp::Add => (
+                (left.is_numeric() || left == HirType::String) && left == right,
+                left.clone(),
+            ),
+            HirExprBinaryOp::Sub
+            | HirExprBinaryOp::Mul
+            | HirExprBinaryOp::Div
+            | HirExprBinaryOp::Mod
+            | HirExprBinaryOp::Pow => (left.is_numeric() && left == right, left.clone()),
+            HirExprBinaryOp::BinaryAnd | HirExprBinaryOp::BinaryOr | HirExprBinaryOp::BinaryXor => {
+                (left.is_integer() && left == right, left.clone())
+            }
+            HirExprBinaryOp::BinaryShl | HirExprBinaryOp::BinaryShr => {
+                (left.is_integer() && right.is_integer(), left.clone())
+            }
+            HirExprBinaryOp::BoolAnd | HirExprBinaryOp::BoolOr => {
+                (is_bool(&left) && is_bool(&right), HirType::Bool)
+            }
+            HirExprBinaryOp::Eq | HirExprBinaryOp::NotEq => (left == right, HirType::Bool),
+            HirExprBinaryOp::Less
+            | HirExprBinaryOp::LessEq
+            | HirExprBinaryOp::Greater
+            | HirExprBinaryOp::GreaterEq => (
+                (left.is_numeric() || left == HirType::Char || left == HirType::String)
+                    && left == right,
+                HirType::Bool,
+            ),
+        };
+
+        if left.is_unknown() || right.is_unknown() {
+            // Comparisons are always boolean, even if we do not know what we compared.
+            return if result == HirType::Bool {
+                result
+            } else {
+                HirType::Unknown
+            };
+        }
+
+        if !valid {
+            self.error(
+                CompilerError::InvalidOperand(format!(
+                    "operator {:?} cannot be applied to {} and {}",
+                    op, left, right
+                )),
+                span,
+            );
+            return HirType::Unknown;
+        }
+
+        result
+    }
+
+    fn check_unary_op(&mut self, op: HirExprUnaryOp, value: HirType, span: Span) -> HirType {
+        let valid = match op {
+            HirExprUnaryOp::Negate => {
+                value.is_signed_integer() || value.is_float() || value == HirType::BigInt
+            }
+            HirExprUnaryOp::Not => value == HirType::Bool,
+            HirExprUnaryOp::Invert => value.is_integer(),
+        };
+
+        if value.is_unknown() {
+            HirType::Unknown
+        } else if valid {
+            value
+        } else {
+            self.error(
+                CompilerError::InvalidOperand(format!(
+                    "operator {:?} cannot be applied to {}",
+                    op, value
+                )),
+                span,
+            );
+            HirType::Unknown
+        }
+    }
+
+    fn check_index(
+        &mut self,
+        owner: HirType,
+        index: HirType,
+        index_span: Span,
+        span: Span,
+    ) -> HirType {
+        match owner {
+            // Strings are indexed by byte rather than by character, as this does not need
+            // to scan the string. The standard library can find characters instead.
+            HirType::Bytes | HirType::String => {
+                if !index.is_integer() && !index.is_unknown() {
+                    self.error(
+                        CompilerError::InvalidOperand(format!(
+                            "{} cannot be indexed by {}",
+                            owner, index
+                        )),
+                        index_span,
+                    );
+                }
+                HirType::U8
+            }
+            HirType::Unknown => HirType::Unknown,
+            owner => {
+                self.error(
+                    CompilerError::InvalidOperand(format!("type {} cannot be indexed", owner)),
+                    span,
+                );
+                HirType::Unknown
+            }
+        }
+    }
+
+    fn check_assignment(
+        &mut self,
+        target: HirExprId,
+        op: Option<HirExprBinaryOp>,
+        value: HirExprId,
+    ) {
+        let target_span = self.expr_span(target);
+        let is_place = matches!(
+            self.function.get_expr(target).kind,
+            HirExprKind::LoadVariable(_)
+                | HirExprKind::MemberAccess { .. }
+                | HirExprKind::Index { .. }
+        );
+
+        if !is_place {
+            self.error(CompilerError::InvalidAssignmentTarget, target_span);
+        }
+
+        let target_type = self.check_expr(target);
+        let value_type = self.check_expr_with_hint(value, Some(&target_type));
+        let value_span = self.expr_span(value);
+
+        let value_type = match op {
+            Some(op) => self.check_binary_op(
+                op,
+                target_type.clone(),
+                value_type,
+                target_span.to(value_span),
+            ),
+            None => value_type,
+        };
+
+        if op.is_some() {
+            self.expect(&target_type, &value_type, value_span);
+        } else {
+            self.expect_expr(&target_type, &value_type, value);
+        }
+    }
+
+    fn check_member_access(&mut self, owner: HirType, member: HirStringId, span: Span) -> HirType {
+        let member_name = self.module.get_string(member);
+
+        // Tuple elements are named after their position.
+        if let HirType::Tuple(elements) = &owner
+            && let Some(element) = member_name
+                .parse::<usize>()
+                .ok()
+                .and_then(|position| elements.get(position))
+        {
+            return element.clone();
+        }
+
+        // Structs from other modules are not found here, as none of their members can be
+        // used from outside of the module that declares them yet.
+        if let HirType::Struct(struct_name) = &owner
+            && let Some(struct_header) = self.module.lookup_struct_by_name(struct_name)
+            && let Some(struct_member) = struct_header
+                .members
+                .iter()
+                .find(|struct_member| struct_member.name == member)
+        {
+            let name = format!("{}.{}", struct_name, member_name);
+            self.warn_if_deprecated(&name, &struct_member.attributes, span);
+            return resolve_type_ref(
+                self.module,
+                self.imports,
+                &struct_member.type_ref,
+                &mut self.errors,
+            );
+        }
+
+        if !owner.is_unknown() {
+            self.error(
+                CompilerError::UnknownMember {
+                    owner: owner.to_string(),
+                    member: member_name.clone(),
+                },
+                span,
+            );
+        }
+
+        HirType::Unknown
+    }
+
+    // Give each variable within a pattern the type of the part of the value that it takes.
+    fn bind_pattern(&mut self, pattern: &HirPattern, value_type: HirType) {
+        match (pattern, value_type) {
+            (HirPattern::Variable(variable), value_type) => {
+                self.variable_types.insert(*variable, value_type);
+            }
+            (HirPattern::Tuple { elements, .. }, HirType::Tuple(element_types))
+                if elements.len() == element_types.len() =>
+            {
+                for (element, element_type) in elements.iter().zip(element_types) {
+                    self.bind_pattern(element, element_type);
+                }
+            }
+            (HirPattern::Tuple { elements, .. }, HirType::Unit) if elements.is_empty() => {}
+            (HirPattern::Tuple { elements, span }, value_type) => {
+                if !value_type.is_unknown() {
+                    self.error(
+                        CompilerError::TypeMismatch {
+                            expected: pattern_shape(elements.len()),
+                            actual: value_type.to_string(),
+                        },
+                        *span,
+                    );
+                }
+                for element in elements {
+                    self.bind_pattern(element, HirType::Unknown);
+                }
+            }
+        }
+    }
+
+    fn check_call(&mut self, callee: HirExprId, arguments: &[HirExprId], span: Span) -> HirType {
+        // Calling a struct by name constructs it from its members in declaration order.
+        if let HirExprKind::LoadStruct(name) = &self.function.get_expr(callee).kind {
+            let struct_header = self
+                .module
+                .lookup_struct(*name)
+                .expect("lowered struct references always refer to declared structs");
+            let callee_span = self.expr_span(callee);
+            self.warn_if_deprecated(
+                self.module.get_string(*name),
+                &struct_header.attributes,
+                callee_span,
+            );
+            let member_types: Vec<HirType> = struct_header
+                .members
+                .iter()
+                .map(|member| {
+                    resolve_type_ref(
+                        self.module,
+                        self.imports,
+                        &member.type_ref,
+                        &mut self.errors,
+                    )
+                })
+                .collect();
+            self.check_arguments(&member_types, arguments, span);
+            let struct_type = HirType::Struct(self.module.get_string(*name).clone());
+            self.expr_types.insert(callee, struct_type.clone());
+            return struct_type;
+        }
+
+        if let HirExprKind::LoadBuiltin(builtin) = &self.function.get_expr(callee).kind {
+            return self.check_builtin_call(*builtin, arguments, span);
+        }
+
+        match self.check_expr(callee) {
+            HirType::Function(signature) => {
+                self.check_arguments(&signature.parameters, arguments, span);
+                signature.return_type
+            }
+            HirType::Unknown => {
+                for argument in arguments {
+                    self.check_expr(*argument);
+                }
+                HirType::Unknown
+            }
+            other => {
+                for argument in arguments {
+                    self.check_expr(*argument);
+                }
+                self.error(CompilerError::NotCallable(other.to_string()), span);
+                HirType::Unknown
+            }
+        }
+    }
+
+    fn check_builtin_call(
+        &mut self,
+        builtin: HirBuiltin,
+        arguments: &[HirExprId],
+        span: Span,
+    ) -> HirType {
+        match (builtin, arguments) {
+            (HirBuiltin::Assert, [condition]) => self.check_condition(*condition),
+            // Format strings are checked as they are lowered, leaving a single formatted
+            // string to print.
+            (HirBuiltin::Print | HirBuiltin::Println, [text]) => {
+                let text_type = self.check_expr(*text);
+                self.expect(&HirType::String, &text_type, self.expr_span(*text));
+            }
+            // Both sides are compared in the same way as the == operator would compare them.
+            (HirBuiltin::AssertEq, [left, right]) => {
+                let (left_type, right_type) =
+                    self.check_binary_operands(*left, HirExprBinaryOp::Eq, *right, None);
+                self.check_binary_op(HirExprBinaryOp::Eq, left_type, right_type, span);
+            }
+            _ => {
+                let expected = match builtin {
+                    HirBuiltin::Assert | HirBuiltin::Print | HirBuiltin::Println => 1,
+                    HirBuiltin::AssertEq => 2,
+                    HirBuiltin::Format => unreachable!("format is lowered to a format string"),
+                };
+                for argument in arguments {
+                    self.check_expr(*argument);
+                }
+                self.error(
+                    CompilerError::ArgumentCountMismatch {
+                        expected,
+                        actual: arguments.len(),
+                    },
+                    span,
+                );
+            }
+        }
+
+        HirType::Unit
+    }
+
+    // Check a closure. Parameters without a declared type take their type from the function
+    // type that the context expects, if there is one.
+    fn check_closure(&mut self, closure: &HirClosure, hint: Option<&HirType>) -> HirType {
+        let expected = match hint {
+            Some(HirType::Function(expected)) => Some(expected.as_ref()),
+            _ => None,
+        };
+
+        let mut parameters = Vec::with_capacity(closure.parameters.len());
+        for (index, parameter) in closure.parameters.iter().enumerate() {
+            let variable = self.function.get_variable(*parameter);
+            let expected_type = expected.and_then(|expected| expected.parameters.get(index));
+            let parameter_type = match (&variable.type_ref, expected_type) {
+                (Some(type_ref), _) => {
+                    resolve_type_ref(self.module, self.imports, type_ref, &mut self.errors)
+                }
+                (None, Some(expected_type)) => expected_type.clone(),
+                (None, None) => {
+                    let name = self.module.get_string(variable.name).clone();
+                    self.error(CompilerError::TypeAnnotationNeeded(name), variable.location);
+                    HirType::Unknown
+                }
+            };
+            self.variable_types
+                .insert(*parameter, parameter_type.clone());
+            parameters.push(parameter_type);
+        }
+
+        let declared_return_type = closure.return_type.as_ref().map(|type_ref| {
+            resolve_type_ref(self.module, self.imports, type_ref, &mut self.errors)
+        });
+        let expected_return_type = expected.map(|expected| expected.return_type.clone());
+
+        let return_type = match closure.body {
+            HirClosureBody::Expr(expr) => {
+                self.check_expr_with_hint(expr, expected_return_type.as_ref())
+            }
+            HirClosureBody::Block(body) => {
+                let return_type = declared_return_type
+                    .or(expected_return_type)
+                    .unwrap_or(HirType::Unit);
+                let outer_return_type = std::mem::replace(&mut self.return_type, return_type);
+                self.check_statement(body);
+                std::mem::replace(&mut self.return_type, outer_return_type)
+            }
+        };
+
+        HirType::Function(Box::new(HirFunctionType {
+            parameters,
+            return_type,
+        }))
+    }
+
+    fn check_arguments(&mut self, parameters: &[HirType], arguments: &[HirExprId], span: Span) {
+        if parameters.len() != arguments.len() {
+            self.error(
+                CompilerError::ArgumentCountMismatch {
+                    expected: parameters.len(),
+                    actual: arguments.len(),
+                },
+                span,
+            );
+        }
+
+        for (index, argument) in arguments.iter().enumerate() {
+            let argument_type = self.check_expr_with_hint(*argument, parameters.get(index));
+            if let Some(parameter_type) = parameters.get(index) {
+                self.expect_expr(parameter_type, &argument_type, *argument);
+            }
+        }
+    }
+
+    /*
+     * Helpers
+     */
+
+    fn expect(&mut self, expected: &HirType, actual: &HirType, span: Span) {
+        if !expected.accepts(actual) {
+            self.error(
+                CompilerError::TypeMismatch {
+                    expected: expected.to_string(),
+                    actual: actual.to_string(),
+                },
+                span,
+            );
+        }
+    }
+
+    // Like expect, but explaining mismatches that come from a variable whose type was fixed by
+    // the default for unsuffixed integer literals.
+    fn expect_expr(&mut self, expected: &HirType, actual: &HirType, expr: HirExprId) {
+        if let HirExprKind::LoadVariable(variable) = &self.function.get_expr(expr).kind
+            && self.defaulted_variables.contains(variable)
+            && expected.is_integer()
+            && !expected.accepts(actual)
+        {
+            let name = self.function.get_variable(*variable).name;
+            self.error(
+                CompilerError::DefaultedIntegerMismatch {
+                    variable: self.module.get_string(name).clone(),
+                    expected: expected.to_string(),
+                },
+                self.expr_span(expr),
+            );
+            return;
+        }
+        self.expect(expected, actual, self.expr_span(expr));
+    }
+
+    // Whether the type of an expression comes only from unsuffixed integer literals, in which
+    // case it should take the type of whatever it is combined with.
+    fn is_untyped_int(&self, id: HirExprId) -> bool {
+        match &self.function.get_expr(id).kind {
+            HirExprKind::LoadLiteral(_) => self.untyped_int_literal(id).is_some(),
+            HirExprKind::UnaryOp { op, value } => {
+                *op != HirExprUnaryOp::Not && self.is_untyped_int(*value)
+            }
+            HirExprKind::BinaryOp { left, op, right } => match op {
+                HirExprBinaryOp::BinaryShl | HirExprBinaryOp::BinaryShr => {
+                    self.is_untyped_int(*left)
+                }
+                HirExprBinaryOp::Add
+                | HirExprBinaryOp::Sub
+                | HirExprBinaryOp::Mul
+                | HirExprBinaryOp::Div
+                | HirExprBinaryOp::Mod
+                | HirExprBinaryOp::Pow
+                | HirExprBinaryOp::BinaryAnd
+                | HirExprBinaryOp::BinaryOr
+                | HirExprBinaryOp::BinaryXor => {
+                    self.is_untyped_int(*left) && self.is_untyped_int(*right)
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    fn untyped_int_literal(&self, id: HirExprId) -> Option<&'a BigUint> {
+        match &self.function.get_expr(id).kind {
+            HirExprKind::LoadLiteral(HirLiteral {
+                kind: HirLiteralKind::Int(value),
+                ..
+            }) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn expr_span(&self, id: HirExprId) -> Span {
+        self.function.get_expr(id).span
+    }
+
+    fn error(&mut self, error: CompilerError, span: Span) {
+        self.errors.push(Spanned::new(error, span));
+    }
+
+    fn warn_if_deprecated(&mut self, name: &str, attributes: &HirAttributes, span: Span) {
+        if let Some(deprecation) = &attributes.deprecated {
+            let warning = CompilerWarning::Deprecated {
+                name: name.to_string(),
+                note: deprecation.note.clone(),
+            };
+            self.warnings.push(Spanned::new(warning, span));
+        }
+    }
+}
+
+// Describe the tuples that a pattern with the given number of elements can take apart.
+fn pattern_shape(length: usize) -> String {
+    match length {
+        1 => "(_,)".to_string(),
+        _ => format!("({})", vec!["_"; length].join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::database::{Database, FunctionId};
+    use crate::error::{CompilerError, CompilerWarning};
+    use test_case::test_case;
+
+    fn check(body: &str) -> Vec<CompilerError> {
+        let source = format!(
+            "struct Point {{ x: i32; y: i32; }}
+             fn helper(a: i32, b: bool) -> i32 {{ return a; }}
+             fn main() {{ {} }}",
+            body
+        );
+        let mut db = Database::new();
+        let file = db.add_file("test.hkl", &source);
+        let result = db.type_of(&FunctionId::new(file, "main")).unwrap();
+        result.errors.iter().map(|error| error.value()).collect()
+    }
+
+    fn defaulted(variable: &str, expected: &str) -> CompilerError {
+        CompilerError::DefaultedIntegerMismatch {
+            variable: variable.to_string(),
+            expected: expected.to_string(),
+        }
+    }
+
+    fn mismatch(expected: &str, actual: &str) -> CompilerError {
+        CompilerError::TypeMismatch {
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        }
+    }
+
+    #[test_case("let x = 1; let y: i32 = x + 2;" ; "arithmetic on matching types")]
+    #[test_case("let p = Point(1, 2); let x: i32 = p.x;" ; "struct construction and member access")]
+    #[test_case("let x = helper(1, true);" ; "function call")]
+    #[test_case("let x = 1; while (x < 10) { x += 1; }" ; "compound assignment in loop")]
+    #[test_case("if (!(1 == 2) && true) { return; }" ; "boolean conditions")]
+    #[test_case("std::println(\"hello\");" ; "qualified names are not checked yet")]
+    #[test_case("let x: u64 = 4294967296;" ; "literal typed by variable declaration")]
+    #[test_case("let x: u128 = 0xffff_ffff_ffff_ffff_ffff_ffff_ffff_ffff;" ; "max u128 literal")]
+    #[test_case("let x: i8 = -128;" ; "negative literal at the minimum")]
+    #[test_case("let x: i64 = -(9223372036854775807 + 1);" ; "literal typed through arithmetic")]
+    #[test_case("let x: u64 = 1; let y = 18446744073709551615 & x;" ; "literal typed by other operand")]
+    #[test_case("let x: usize = 1 << 40;" ; "shifted literal typed by context")]
+    #[test_case("let c: char = 'a'; let b: bool = c < 'z';" ; "character comparisons")]
+    #[test_case("let b: u8 = b'a'; let s: bytes = b\"abc\"; let c: u8 = s[0] + b;" ; "bytes")]
+    #[test_case("let x: isize = 1; x += 9000000000;" ; "literal typed by assignment target")]
+    #[test_case("let f: fn(i32, bool) -> i32 = helper; let x: i32 = f(1, true);" ; "function values")]
+    #[test_case("let f: fn(i64) -> i64 = |x| x * 4294967296; let y: i64 = f(1);" ; "closure types from context")]
+    #[test_case("let f = |x: u8, y: u8| x + y; let z: u8 = f(1, 2);" ; "annotated closures")]
+    #[test_case("let f = |x: i32| -> bool { return x > 1; }; let b: bool = f(2);" ; "closures with blocks")]
+    #[test_case("let n = 1; let f = || n + 1; let m: i32 = f();" ; "closures capturing variables")]
+    #[test_case("let f: fn(i32) -> fn(i32) -> i32 = |x| |y| x + y; let z: i32 = f(1)(2);" ; "closures returning closures")]
+    #[test_case("let t: (u8, (bool, i64)) = (1, (true, 4294967296)); let x: i64 = (t.1).1;" ; "tuples typed by context")]
+    #[test_case("let f = || (1, true); let (a, b) = f(); let c: bool = b && a > 0;" ; "destructuring returned tuples")]
+    #[test_case("let (a, (b,), ()) = (1u8, ('c',), ()); let c: char = b; let d: u8 = a;" ; "nested patterns")]
+    #[test_case("let p: (i32, i32) = (1, 2); p.0 = 3;" ; "assigning to tuple elements")]
+    #[test_case("let s: shapes::Square = shapes::square(2);" ; "qualified names")]
+    #[test_case("assert(1 < 2); assert_eq(1u8, 1); assert_eq((1, 'a'), (1, 'a'));" ; "assertions")]
+    #[test_case("let s: string = format(\"{} and {}\", 1, (true, 'c')); println(\"{}{{}}\", s); std::print(r\"{}\", 2u8); println();" ; "format strings")]
+    #[test_case("let s: string = \"a\" + \"b\"; s += \"c\"; let b: u8 = s[0]; let c: bool = s < \"b\";" ; "strings")]
+    #[test_case("let a: std::BigInt = 340282366920938463463374607431768211456; let b = -a * 2 + a % 3; let c: bool = b < a;" ; "big integers")]
+    #[test_case("let a: std::BigInt = 1; let n = 3; let b: std::BigInt = n * a - n; let c: bool = a < n; a *= n;" ; "big integers mixed with fixed width integers")]
+    fn valid_functions_have_no_errors(body: &str) {
+        // Then
+        assert_eq!(check(body), vec![]);
+    }
+
+    fn out_of_range(literal: &str, ty: &str) -> CompilerError {
+        CompilerError::IntLiteralOutOfRange {
+            literal: literal.to_string(),
+            ty: ty.to_string(),
+        }
+    }
+
+    #[test_case("let x: bool = 1;", mismatch("bool", "i32") ; "variable initializer")]
+    #[test_case("if (1) {}", mismatch("bool", "i32") ; "if condition")]
+    #[test_case("return 1;", mismatch("()", "i32") ; "return value")]
+    #[test_case("helper(1, 2);", mismatch("bool", "i32") ; "function argument")]
+    #[test_case(
+        "helper(1);",
+        CompilerError::ArgumentCountMismatch { expected: 2, actual: 1 }
+        ; "argument count"
+    )]
+    #[test_case("1 = 2;", CompilerError::InvalidAssignmentTarget ; "assignment to literal")]
+    #[test_case("foo();", CompilerError::UnresolvedName("foo".to_string()) ; "unresolved name")]
+    #[test_case("let x: Nope = 1;", CompilerError::UnknownType("Nope".to_string()) ; "unknown type")]
+    #[test_case(
+        "let p = Point(1, 2); p.z;",
+        CompilerError::UnknownMember { owner: "Point".to_string(), member: "z".to_string() }
+        ; "unknown member"
+    )]
+    #[test_case("let x = 1; x();", CompilerError::NotCallable("i32".to_string()) ; "calling a non-function")]
+    #[test_case("let c: u8 = 'a';", mismatch("u8", "char") ; "characters are not bytes")]
+    #[test_case(
+        "let s = \"abc\"; s[true];",
+        CompilerError::InvalidOperand("string cannot be indexed by bool".to_string())
+        ; "indexing a string with a bool"
+    )]
+    #[test_case(
+        "let x = 1; x[0];",
+        CompilerError::InvalidOperand("type i32 cannot be indexed".to_string())
+        ; "indexing an integer"
+    )]
+    #[test_case(
+        "let s = \"abc\" - \"c\";",
+        CompilerError::InvalidOperand("operator Sub cannot be applied to string and string".to_string())
+        ; "subtracting strings"
+    )]
+    #[test_case(
+        "let s = b\"abc\"; s[true];",
+        CompilerError::InvalidOperand("bytes cannot be indexed by bool".to_string())
+        ; "indexing bytes with a bool"
+    )]
+    #[test_case(
+        "let a: std::BigInt = 1; a & a;",
+        CompilerError::InvalidOperand("operator BinaryAnd cannot be applied to std::BigInt and std::BigInt".to_string())
+        ; "bitwise operators on big integers"
+    )]
+    #[test_case("let a: std::BigInt = 1; let b: i64 = a;", mismatch("i64", "std::BigInt") ; "big integers are not fixed width integers")]
+    #[test_case("let a: std::BigInt = 1; let n = 3; n *= a;", mismatch("i32", "std::BigInt") ; "big integers are not narrowed")]
+    #[test_case("let x = 1; let y: i64 = x;", defaulted("x", "i64") ; "variables from unsuffixed literals default to i32")]
+    #[test_case("let x = 1 + 2; let y: u8 = 0; y = x;", defaulted("x", "u8") ; "defaulted variables in assignments")]
+    #[test_case(
+        "let x = 1; let x = 2;",
+        CompilerError::DuplicateDefinition("x".to_string())
+        ; "duplicate variable"
+    )]
+    #[test_case("let x: u8 = 256;", out_of_range("256", "u8") ; "too large for declared type")]
+    #[test_case("let x: i8 = -129;", out_of_range("-129", "i8") ; "too small for declared type")]
+    #[test_case("let x = 2147483648;", out_of_range("2147483648", "i32") ; "too large for default type")]
+    #[test_case("let x: u16 = 1; x = x + 65536;", out_of_range("65536", "u16") ; "too large for other operand")]
+    #[test_case("helper(3000000000, true);", out_of_range("3000000000", "i32") ; "too large for parameter")]
+    #[test_case("let f: fn(i32) -> i32 = helper;", mismatch("fn(i32) -> i32", "fn(i32, bool) -> i32") ; "mismatched function values")]
+    #[test_case(
+        "let f: fn(bool) = |x| x + 1;",
+        CompilerError::InvalidOperand("operator Add cannot be applied to bool and i32".to_string())
+        ; "closure parameters typed by context"
+    )]
+    #[test_case("let f: fn(i32) -> bool = |x: i32| x;", mismatch("fn(i32) -> bool", "fn(i32) -> i32") ; "closure return types")]
+    #[test_case("let f = |x: i32| -> bool { return x; };", mismatch("bool", "i32") ; "closure return statements")]
+    #[test_case("let f = |x| 1;", CompilerError::TypeAnnotationNeeded("x".to_string()) ; "closure parameters without types")]
+    #[test_case("let f = |x: i32| x; f(true);", mismatch("i32", "bool") ; "closure arguments")]
+    #[test_case(
+        "let n = 1; let f = || { n = 2; };",
+        CompilerError::CapturedVariableAssignment("n".to_string())
+        ; "assigning to captured variables"
+    )]
+    #[test_case("let t: (i32, bool) = (1, 2);", mismatch("(i32, bool)", "(i32, i32)") ; "tuple elements")]
+    #[test_case("let t: (i32, bool) = (1,);", mismatch("(i32, bool)", "(i32,)") ; "tuple lengths")]
+    #[test_case(
+        "let t = (1, true); t.2;",
+        CompilerError::UnknownMember { owner: "(i32, bool)".to_string(), member: "2".to_string() }
+        ; "tuple positions out of range"
+    )]
+    #[test_case("let (a, b) = (1, 2, 3);", mismatch("(_, _)", "(i32, i32, i32)") ; "destructuring the wrong length")]
+    #[test_case("let (a,) = 1;", mismatch("(_,)", "i32") ; "destructuring a non-tuple")]
+    #[test_case("let (a, b): (i32, bool) = (1, true); let c: i32 = b;", mismatch("i32", "bool") ; "destructured types")]
+    #[test_case("assert(1);", mismatch("bool", "i32") ; "assert with a non-bool")]
+    #[test_case(
+        "assert_eq(1, true);",
+        CompilerError::InvalidOperand("operator Eq cannot be applied to i32 and bool".to_string())
+        ; "assert_eq with different types"
+    )]
+    #[test_case(
+        "assert_eq(1, 2, 3);",
+        CompilerError::ArgumentCountMismatch { expected: 2, actual: 3 }
+        ; "assert_eq argument count"
+    )]
+    #[test_case(
+        "let f = assert;",
+        CompilerError::InvalidOperand("builtin assert can only be called directly".to_string())
+        ; "builtins as values"
+    )]
+    #[test_case(
+        "println(\"{} {}\", 1);",
+        CompilerError::FormatArgumentCountMismatch { placeholders: 2, arguments: 1 }
+        ; "too few format arguments"
+    )]
+    #[test_case(
+        "std::println(\"{}\", 1, 2);",
+        CompilerError::FormatArgumentCountMismatch { placeholders: 1, arguments: 2 }
+        ; "too many format arguments"
+    )]
+    #[test_case(
+        "print(\"{x}\", 1);",
+        CompilerError::InvalidFormatString("expected } after {, or {{ for a literal brace".to_string())
+        ; "invalid placeholder"
+    )]
+    #[test_case(
+        "let f = \"{}\"; let s = format(f, 1);",
+        CompilerError::InvalidFormatString("the format string must be a string literal".to_string())
+        ; "format string that is not a literal"
+    )]
+    #[test_case("println(\"{}\", helper);", CompilerError::NotFormattable("fn(i32, bool) -> i32".to_string()) ; "formatting a function")]
+    #[test_case("println(\"{}\", Point(1, 2));", CompilerError::NotFormattable("Point".to_string()) ; "formatting a struct")]
+    #[test_case("println(\"{}\", (1, Point(1, 2)));", CompilerError::NotFormattable("(i32, Point)".to_string()) ; "formatting a tuple containing a struct")]
+    #[test_case("let s: i32 = format(\"\");", mismatch("i32", "string") ; "format returns a string")]
+    #[test_case(
+        "format();",
+        CompilerError::ArgumentCountMismatch { expected: 1, actual: 0 }
+        ; "format without a format string"
+    )]
+    fn invalid_functions_report_errors(body: &str, expected: CompilerError) {
+        // Then
+        assert_eq!(check(body), vec![expected]);
+    }
+
+    #[test]
+    fn out_of_range_literals_are_reported_at_the_literal() {
+        // Given
+        let source = "fn main() { let x: u8 = 1 + 300; }";
+        let mut db = Database::new();
+        let file = db.add_file("test.hkl", source);
+
+        // When
+        let result = db.type_of(&FunctionId::new(file, "main")).unwrap();
+
+        // Then
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(&source[result.errors[0].span().range()], "300");
+    }
+
+    #[test_case(  r#"println("{} {}", 1);"#, "{}" ; "placeholder without a value")]
+    #[test_case( r#"println("\t{}", 1, 2);"#,  "2" ; "value without a placeholder")]
+    #[test_case(   r#"println("\u{e9} }");"#,  "}" ; "after an escape sequence")]
+    #[test_case(r##"println(r#"a{b"#, 1);"##,  "{" ; "within a raw string")]
+    fn format_string_errors_point_within_the_literal(body: &str, expected: &str) {
+        // Given
+        let source = format!("fn main() {{ {} }}", body);
+        let mut db = Database::new();
+        let file = db.add_file("test.hkl", &source);
+
+        // When
+        let result = db.type_of(&FunctionId::new(file, "main")).unwrap();
+
+        // Then
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(&source[result.errors[0].span().range()], expected);
+    }
+
+    #[test]
+    fn deprecated_items_are_reported_as_warnings() {
+        // Given
+        let source = "#[deprecated(\"use Vec2\")] struct P { #[deprecated] x: i32; y: i32; }
+                      #[deprecated] fn old() -> i32 { return 1; }
+                      fn main() { let p = P(old(), 2); p.y = p.x; }";
+        let mut db = Database::new();
+        let file = db.add_file("test.hkl", source);
+
+        // When
+        let result = db.type_of(&FunctionId::new(file, "main")).unwrap();
+
+        // Then
+        assert_eq!(result.errors, vec![]);
+        let warnings: Vec<(CompilerWarning, &str)> = result
+            .warnings
+            .iter()
+            .map(|warning| (warning.value(), &source[warning.span().range()]))
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                (
+                    CompilerWarning::Deprecated {
+                        name: "P".to_string(),
+                        note: Some("use Vec2".to_string())
+                    },
+                    "P"
+                ),
+                (
+                    CompilerWarning::Deprecated {
+                        name: "old".to_string(),
+                        note: None
+                    },
+                    "old"
+                ),
+                (
+                    CompilerWarning::Deprecated {
+                        name: "P.x".to_string(),
+                        note: None
+                    },
+                    "p.x"
+                ),
+            ]
+        );
+    }
+}
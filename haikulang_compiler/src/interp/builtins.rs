@@ -0,0 +1,340 @@
+//! Functions provided by the interpreter itself, which programs can access by declaring
+//! them as `extern fn`.
+//!
+//! Most of these exist to back the standard library, which gives them friendlier signatures.
+use crate::error::RuntimeError;
+use crate::interp::heap::Heap;
+use crate::interp::value::Value;
+use num_bigint::BigInt;
+use std::io::Write;
+use std::rc::Rc;
+
+const BUILTINS: &[&str] = &[
+    "print",
+    "println",
+    "exit",
+    "i64_to_string",
+    "i32_to_string",
+    "u64_to_string",
+    "u32_to_string",
+    "usize_to_string",
+    "f64_to_string",
+    "bool_to_string",
+    "char_to_string",
+    "bigint_to_string",
+    "parse_i64",
+    "string_len",
+    "string_concat",
+    "string_contains",
+    "string_starts_with",
+    "string_ends_with",
+    "string_slice",
+    "string_char_count",
+    "string_char_at",
+    "string_to_upper",
+    "string_to_lower",
+    "string_trim",
+    "sqrt",
+    "floor",
+    "ceil",
+    "bigint_from_i64",
+    "bigint_parse",
+    "bigint_to_i64",
+    "array_new",
+    "array_len",
+    "array_push",
+    "array_pop",
+    "array_get",
+    "array_set",
+    "map_new",
+    "map_len",
+    "map_insert",
+    "map_get",
+    "map_contains",
+    "map_remove",
+    "heap_objects",
+];
+
+/// Attempt to call a builtin function with the given name. Returns None if no builtin
+/// exists with that name.
+pub fn call_builtin(
+    name: &str,
+    arguments: &[Value],
+    output: &mut dyn Write,
+    heap: &mut Heap,
+) -> Option<Result<Value, RuntimeError>> {
+    if !BUILTINS.contains(&name) {
+        return None;
+    }
+
+    let result = match (name, arguments) {
+        ("print", _) => write_formatted(arguments, output, false),
+        ("println", _) => write_formatted(arguments, output, true),
+        ("exit", [Value::I32(code)]) => Err(RuntimeError::Exit(*code)),
+
+        ("i64_to_string", [value @ Value::I64(_)])
+        | ("i32_to_string", [value @ Value::I32(_)])
+        | ("u64_to_string", [value @ Value::U64(_)])
+        | ("u32_to_string", [value @ Value::U32(_)])
+        | ("usize_to_string", [value @ Value::USize(_)])
+        | ("f64_to_string", [value @ Value::F64(_)])
+        | ("bool_to_string", [value @ Value::Bool(_)])
+        | ("char_to_string", [value @ Value::Char(_)])
+        | ("bigint_to_string", [value @ Value::BigInt(_)]) => Ok(string(value.to_string())),
+        ("parse_i64", [Value::String(text)]) => {
+            let parsed = text.trim().parse::<i64>();
+            Ok(Value::Tuple(Box::new([
+                Value::Bool(parsed.is_ok()),
+                Value::I64(parsed.unwrap_or(0)),
+            ])))
+        }
+
+        ("string_len", [Value::String(text)]) => Ok(Value::USize(text.len())),
+        ("string_concat", [Value::String(left), Value::String(right)]) => {
+            Ok(string(format!("{}{}", left, right)))
+        }
+        ("string_contains", [Value::String(text), Value::String(pattern)]) => {
+            Ok(Value::Bool(text.contains(&**pattern)))
+        }
+        ("string_starts_with", [Value::String(text), Value::String(prefix)]) => {
+            Ok(Value::Bool(text.starts_with(&**prefix)))
+        }
+        ("string_ends_with", [Value::String(text), Value::String(suffix)]) => {
+            Ok(Value::Bool(text.ends_with(&**suffix)))
+        }
+        ("string_slice", [Value::String(text), Value::USize(start), Value::USize(end)]) => text
+            .get(*start..*end)
+            .map(|slice| string(slice.to_string()))
+            .ok_or_else(|| RuntimeError::IndexOutOfBounds {
+                index: format!("{}..{}", start, end),
+                length: text.len(),
+            }),
+        ("string_char_count", [Value::String(text)]) => Ok(Value::USize(text.chars().count())),
+        ("string_char_at", [Value::String(text), Value::USize(index)]) => text
+            .chars()
+            .nth(*index)
+            .map(Value::Char)
+            .ok_or_else(|| out_of_bounds(*index, text.chars().count())),
+        ("string_to_upper", [Value::String(text)]) => Ok(string(text.to_uppercase())),
+        ("string_to_lower", [Value::String(text)]) => Ok(string(text.to_lowercase())),
+        ("string_trim", [Value::String(text)]) => Ok(string(text.trim().to_string())),
+
+        ("sqrt", [Value::F64(value)]) => Ok(Value::F64(value.sqrt())),
+        ("floor", [Value::F64(value)]) => Ok(Value::F64(value.floor())),
+        ("ceil", [Value::F64(value)]) => Ok(Value::F64(value.ceil())),
+
+        ("bigint_from_i64", [Value::I64(value)]) => Ok(big_integer(BigInt::from(*value))),
+        ("bigint_parse", [Value::String(text)]) => {
+            let parsed = text.trim().parse::<BigInt>();
+            let ok = parsed.is_ok();
+            Ok(Value::tuple(vec![
+                Value::Bool(ok),
+                big_integer(parsed.unwrap_or_default()),
+            ]))
+        }
+        ("bigint_to_i64", [Value::BigInt(value)]) => {
+            let converted = i64::try_from(&**value);
+            Ok(Value::tuple(vec![
+                Value::Bool(converted.is_ok()),
+                Value::I64(converted.unwrap_or(0)),
+            ]))
+        }
+
+        ("array_new", []) => Ok(heap.new_array()),
+        ("array_len", [array]) => heap.array(array).map(|array| Value::USize(array.len())),
+        ("array_push", [array, Value::I64(value)]) => heap.array(array).map(|mut array| {
+            array.push(*value);
+            Value::Unit
+        }),
+        ("array_pop", [array]) => heap.array(array).and_then(|mut array| {
+            array.pop().map(Value::I64).ok_or_else(|| {
+                RuntimeError::Unsupported("cannot pop from an empty array".to_string())
+            })
+        }),
+        ("array_get", [array, Value::USize(index)]) => heap.array(array).and_then(|array| {
+            array
+                .get(*index)
+                .map(|value| Value::I64(*value))
+                .ok_or_else(|| out_of_bounds(*index, array.len()))
+        }),
+        ("array_set", [array, Value::USize(index), Value::I64(value)]) => {
+            heap.array(array).and_then(|mut array| {
+                let length = array.len();
+                let element = array
+                    .get_mut(*index)
+                    .ok_or_else(|| out_of_bounds(*index, length))?;
+                *element = *value;
+                Ok(Value::Unit)
+            })
+        }
+
+        ("map_new", []) => Ok(heap.new_map()),
+        ("map_len", [map]) => heap.map(map).map(|map| Value::USize(map.len())),
+        ("map_insert", [map, Value::String(key), Value::I64(value)]) => {
+            heap.map(map).map(|mut map| {
+                map.insert(key.clone(), *value);
+                Value::Unit
+            })
+        }
+        ("map_get", [map, Value::String(key)]) => heap.map(map).and_then(|map| {
+            map.get(key)
+                .map(|value| Value::I64(*value))
+                .ok_or_else(|| RuntimeError::MissingKey(key.to_string()))
+        }),
+        ("map_contains", [map, Value::String(key)]) => {
+            heap.map(map).map(|map| Value::Bool(map.contains_key(key)))
+        }
+        ("map_remove", [map, Value::String(key)]) => heap
+            .map(map)
+            .map(|mut map| Value::Bool(map.remove(key).is_some())),
+
+        ("heap_objects", []) => Ok(Value::USize(heap.live_objects())),
+
+        _ => Err(RuntimeError::Unsupported(format!(
+            "invalid arguments for builtin {}",
+            name
+        ))),
+    };
+    Some(result)
+}
+
+fn string(text: String) -> Value {
+    Value::String(Rc::from(text))
+}
+
+fn big_integer(value: BigInt) -> Value {
+    Value::BigInt(Rc::new(value))
+}
+
+fn out_of_bounds(index: usize, length: usize) -> RuntimeError {
+    RuntimeError::IndexOutOfBounds {
+        index: index.to_string(),
+        length,
+    }
+}
+
+// The first argument is a format string. Each `{}` within it is replaced with the next
+// argument in turn.
+fn write_formatted(
+    arguments: &[Value],
+    output: &mut dyn Write,
+    newline: bool,
+) -> Result<Value, RuntimeError> {
+    let text = match arguments.split_first() {
+        Some((Value::String(format), values)) => format_string(format, values),
+        Some(_) => {
+            return Err(RuntimeError::Unsupported(
+                "the first argument must be a format string".to_string(),
+            ));
+        }
+        None => String::new(),
+    };
+
+    let result = if newline {
+        writeln!(output, "{}", text)
+    } else {
+        write!(output, "{}", text)
+    };
+
+    result
+        .map(|_| Value::Unit)
+        .map_err(|err| RuntimeError::Unsupported(format!("failed to write output: {}", err)))
+}
+
+fn format_string(format: &str, values: &[Value]) -> String {
+    let mut values = values.iter();
+    let mut result = String::new();
+    let mut rest = format;
+
+    while let Some(index) = rest.find("{}") {
+        result.push_str(&rest[..index]);
+        match values.next() {
+            Some(value) => result.push_str(&value.to_string()),
+            None => result.push_str("{}"),
+        }
+        rest = &rest[index + 2..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(                  "hello", vec![],                                 "hello" ; "no placeholders")]
+    #[test_case(               "x = {}!", vec![Value::I32(3)],                     "x = 3!" ; "single placeholder")]
+    #[test_case(                "{}, {}", vec![Value::Bool(true), Value::U8(9)],  "true, 9" ; "multiple placeholders")]
+    #[test_case(                "{}, {}", vec![Value::Bool(true)],                "true, {}" ; "missing values")]
+    fn format_strings_substitute_values(format: &str, values: Vec<Value>, expected: &str) {
+        // Then
+        assert_eq!(format_string(format, &values), expected);
+    }
+
+    #[test]
+    fn println_writes_a_line() {
+        // Given
+        let mut output: Vec<u8> = Vec::new();
+        let arguments = vec![
+            Value::String(Rc::from("a{}c")),
+            Value::String(Rc::from("b")),
+        ];
+
+        // When
+        let result = call_builtin("println", &arguments, &mut output, &mut Heap::new());
+
+        // Then
+        assert_eq!(result, Some(Ok(Value::Unit)));
+        assert_eq!(String::from_utf8(output).unwrap(), "abc\n");
+    }
+
+    #[test_case("12345678901234567890123", "(true, 12345678901234567890123)" ; "valid big integer")]
+    #[test_case(                  " -42 ",                     "(true, -42)" ; "surrounding whitespace")]
+    #[test_case(                 "4.5e10",                      "(false, 0)" ; "not an integer")]
+    fn big_integers_can_be_parsed(text: &str, expected: &str) {
+        // When
+        let result = call_builtin(
+            "bigint_parse",
+            &[Value::String(Rc::from(text))],
+            &mut Vec::new(),
+            &mut Heap::new(),
+        );
+
+        // Then
+        assert_eq!(result.unwrap().unwrap().to_string(), expected);
+    }
+
+    #[test]
+    fn collections_only_hold_integers() {
+        // Given
+        let mut heap = Heap::new();
+        let array = heap.new_array();
+
+        // When
+        let result = call_builtin(
+            "array_push",
+            &[array.clone(), array],
+            &mut Vec::new(),
+            &mut heap,
+        );
+
+        // Then
+        assert_eq!(
+            result,
+            Some(Err(RuntimeError::Unsupported(
+                "invalid arguments for builtin array_push".to_string()
+            )))
+        );
+    }
+
+    #[test]
+    fn unknown_builtins_are_not_found() {
+        // Then
+        assert_eq!(
+            call_builtin("nope", &[], &mut Vec::new(), &mut Heap::new()),
+            None
+        );
+    }
+}
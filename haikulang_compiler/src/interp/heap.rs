@@ -0,0 +1,204 @@
+//! Storage for the collections that the standard library provides.
+//!
+//! Programs refer to each object on the heap through a handle, which the standard library
+//! wraps in a struct so that arrays and maps are not mixed up. Handles are reference counted,
+//! so an object is freed as soon as the last handle to it is dropped.
+//!
+//! Arrays and maps only hold i64 values, so objects never refer to each other and there are
+//! no cycles that reference counting could miss. Only the interpreter and the bytecode VM
+//! have a heap so far.
+use crate::error::RuntimeError;
+use crate::interp::value::Value;
+use std::cell::{RefCell, RefMut};
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::rc::{Rc, Weak};
+
+/// The objects created by a program while it runs.
+#[derive(Debug, Default)]
+pub struct Heap {
+    // Only kept so that the objects which are still alive can be counted.
+    objects: Vec<Weak<HeapObject>>,
+}
+
+/// An object on the heap, which values refer to through `Value::Handle`.
+pub struct HeapObject {
+    contents: RefCell<Object>,
+}
+
+enum Object {
+    Array(Vec<i64>),
+    Map(HashMap<Rc<str>, i64>),
+}
+
+impl Heap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty array, returning its handle.
+    pub fn new_array(&mut self) -> Value {
+        self.allocate(Object::Array(Vec::new()))
+    }
+
+    pub fn array<'v>(&self, handle: &'v Value) -> Result<RefMut<'v, Vec<i64>>, RuntimeError> {
+        contents(handle, "array", |object| match object {
+            Object::Array(array) => Some(array),
+            _ => None,
+        })
+    }
+
+    /// Create an empty map, returning its handle.
+    pub fn new_map(&mut self) -> Value {
+        self.allocate(Object::Map(HashMap::new()))
+    }
+
+    pub fn map<'v>(
+        &self,
+        handle: &'v Value,
+    ) -> Result<RefMut<'v, HashMap<Rc<str>, i64>>, RuntimeError> {
+        contents(handle, "map", |object| match object {
+            Object::Map(map) => Some(map),
+            _ => None,
+        })
+    }
+
+    /// The number of objects that are still alive.
+    pub fn live_objects(&self) -> usize {
+        self.objects
+            .iter()
+            .filter(|object| object.strong_count() > 0)
+            .count()
+    }
+
+    fn allocate(&mut self, contents: Object) -> Value {
+        // Forget freed objects before growing, so that the list stays proportional to the
+        // number of live ones.
+        if self.objects.len() == self.objects.capacity() {
+            self.objects.retain(|object| object.strong_count() > 0);
+        }
+
+        let object = Rc::new(HeapObject {
+            contents: RefCell::new(contents),
+        });
+        self.objects.push(Rc::downgrade(&object));
+        Value::Handle(object)
+    }
+}
+
+impl HeapObject {
+    /// What kind of object this is, for display purposes.
+    pub fn kind(&self) -> &'static str {
+        match *self.contents.borrow() {
+            Object::Array(_) => "array",
+            Object::Map(_) => "map",
+        }
+    }
+}
+
+// Objects are only equal to themselves, as changes made through one handle are seen through
+// every other handle to the same object.
+impl PartialEq for HeapObject {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Debug for HeapObject {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HeapObject")
+            .field("kind", &self.kind())
+            .finish_non_exhaustive()
+    }
+}
+
+fn contents<'v, T>(
+    handle: &'v Value,
+    kind: &str,
+    select: impl FnOnce(&mut Object) -> Option<&mut T>,
+) -> Result<RefMut<'v, T>, RuntimeError> {
+    let Value::Handle(object) = handle else {
+        return Err(invalid_handle(kind, handle));
+    };
+
+    RefMut::filter_map(object.contents.borrow_mut(), select).map_err(|contents| {
+        // The handle cannot be displayed while its contents are borrowed.
+        drop(contents);
+        invalid_handle(kind, handle)
+    })
+}
+
+fn invalid_handle(kind: &str, handle: &Value) -> RuntimeError {
+    RuntimeError::Unsupported(format!("{} is not a valid {} handle", handle, kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collections_are_found_by_their_handles() {
+        // Given
+        let mut heap = Heap::new();
+        let first = heap.new_array();
+        let second = heap.new_array();
+
+        // When
+        heap.array(&second).unwrap().push(1);
+
+        // Then
+        assert_eq!(heap.array(&first).unwrap().len(), 0);
+        assert_eq!(heap.array(&second).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn unknown_handles_are_rejected() {
+        // Given
+        let mut heap = Heap::new();
+        let array = heap.new_array();
+
+        // When
+        let result = heap.map(&array).map(|_| ());
+
+        // Then
+        assert_eq!(
+            result,
+            Err(RuntimeError::Unsupported(
+                "<array> is not a valid map handle".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn objects_are_freed_when_their_last_handle_is_dropped() {
+        // Given
+        let mut heap = Heap::new();
+        let array = heap.new_array();
+        let copy = array.clone();
+        let map = heap.new_map();
+
+        // When
+        drop(array);
+        let before = heap.live_objects();
+        drop((copy, map));
+
+        // Then
+        assert_eq!(before, 2);
+        assert_eq!(heap.live_objects(), 0);
+    }
+
+    #[test]
+    fn freed_objects_are_forgotten_as_more_are_allocated() {
+        // Given
+        let mut heap = Heap::new();
+
+        // When
+        for _ in 0..1000 {
+            heap.new_array();
+        }
+
+        // Then
+        assert_eq!(heap.live_objects(), 0);
+        assert!(heap.objects.len() < 1000);
+    }
+}
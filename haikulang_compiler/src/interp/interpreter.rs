@@ -0,0 +1,1225 @@
+use crate::db::database::{Database, FileId, FunctionId};
+use crate::error::{RuntimeError, RuntimeResult};
+use crate::hir::arena::ArenaMap;
+use crate::hir::nodes::*;
+use crate::hir::typeck::HirTypeckResult;
+use crate::interp::builtins::call_builtin;
+use crate::interp::heap::Heap;
+use crate::interp::value::{ClosureValue, StructValue, Value};
+use haikulang_parser::span::{Span, Spanned};
+use num_bigint::BigInt;
+use std::cmp::Ordering;
+use std::io::Write;
+use std::rc::Rc;
+
+// Each interpreted call uses several native stack frames, so we need to give up long before
+// the native stack would be exhausted.
+const MAX_CALL_DEPTH: usize = 128;
+
+/// Tree-walking interpreter that executes lowered functions directly.
+///
+/// Functions are lowered lazily through the database the first time they are called.
+pub struct Interpreter<'a> {
+    db: &'a mut Database,
+    file: FileId,
+    output: &'a mut dyn Write,
+    heap: Heap,
+    depth: usize,
+}
+
+// A function that is being executed, along with the types that were inferred for it.
+struct Frame {
+    function: Rc<HirFunctionData>,
+    types: Rc<HirTypeckResult>,
+}
+
+// The outcome of executing a statement.
+enum Flow {
+    Normal,
+    Break,
+    Continue,
+    Return(Value),
+}
+
+type Variables = ArenaMap<HirVariableId, Value>;
+
+impl<'a> Interpreter<'a> {
+    pub fn new(db: &'a mut Database, file: FileId, output: &'a mut dyn Write) -> Self {
+        Self {
+            db,
+            file,
+            output,
+            heap: Heap::new(),
+            depth: 0,
+        }
+    }
+
+    /// Use an existing heap, so that objects can be shared with values from an earlier run.
+    pub fn with_heap(mut self, heap: Heap) -> Self {
+        self.heap = heap;
+        self
+    }
+
+    /// Take the heap back once the interpreter is no longer needed.
+    pub fn into_heap(self) -> Heap {
+        self.heap
+    }
+
+    /// Call the function with the given name, returning the value it produced.
+    ///
+    /// Qualified names refer to functions in other modules.
+    pub fn call(&mut self, name: &str, arguments: Vec<Value>, span: Span) -> RuntimeResult<Value> {
+        if let Some((module_name, item_name)) = name.rsplit_once("::") {
+            return self.call_in_module(module_name, item_name, arguments, span);
+        }
+
+        let header = self
+            .db
+            .module_context(self.file)
+            .lookup_function_by_name(name)
+            .ok_or_else(|| Spanned::new(RuntimeError::UnknownFunction(name.to_string()), span))?;
+
+        // Only keep what we need from the header, as this is part of every interpreted call.
+        if header.is_extern {
+            let link_name = header
+                .attributes
+                .link_name
+                .clone()
+                .unwrap_or_else(|| name.to_string());
+            return match call_builtin(&link_name, &arguments, self.output, &mut self.heap) {
+                Some(result) => result.map_err(|err| Spanned::new(err, span)),
+                None => Err(Spanned::new(
+                    RuntimeError::UnknownFunction(link_name.to_string()),
+                    span,
+                )),
+            };
+        }
+
+        let id = FunctionId::new(self.file, name);
+        let unknown_function =
+            || Spanned::new(RuntimeError::UnknownFunction(name.to_string()), span);
+        let function = self.db.lower_function(&id).ok_or_else(unknown_function)?;
+        let types = self.db.type_of(&id).ok_or_else(unknown_function)?;
+
+        if function.parameters.len() != arguments.len() {
+            return Err(Spanned::new(
+                RuntimeError::Unsupported(format!(
+                    "{} expects {} arguments but was given {}",
+                    name,
+                    function.parameters.len(),
+                    arguments.len()
+                )),
+                span,
+            ));
+        }
+
+        let mut variables = Variables::new();
+        for (parameter, argument) in function.parameters.iter().zip(arguments) {
+            variables.insert(*parameter, argument);
+        }
+
+        let body = HirClosureBody::Block(function.root_statement);
+        self.invoke(Frame { function, types }, variables, body, span)
+    }
+
+    /// Call a function in this module, returning the final value of every variable that it
+    /// assigned rather than what it returned. Interactive sessions use this to carry
+    /// variables over from one input to the next.
+    pub fn call_keeping_variables(
+        &mut self,
+        name: &str,
+        arguments: Vec<Value>,
+    ) -> RuntimeResult<Vec<(HirVariableId, Value)>> {
+        let id = FunctionId::new(self.file, name);
+        let unknown_function =
+            || Spanned::new(RuntimeError::UnknownFunction(name.to_string()), Span::UNSET);
+        let function = self.db.lower_function(&id).ok_or_else(unknown_function)?;
+        let types = self.db.type_of(&id).ok_or_else(unknown_function)?;
+
+        let mut variables = Variables::new();
+        for (parameter, argument) in function.parameters.iter().zip(arguments) {
+            variables.insert(*parameter, argument);
+        }
+
+        let body = function.root_statement;
+        self.execute(&Frame { function, types }, &mut variables, body)?;
+        Ok(variables
+            .iter()
+            .map(|(variable, value)| (variable, value.clone()))
+            .collect())
+    }
+
+    // Functions run in the context of the module that declares them. Their spans only make
+    // sense within that module, so any error is reported at the call that led to it instead.
+    fn call_in_module(
+        &mut self,
+        module_name: &str,
+        name: &str,
+        arguments: Vec<Value>,
+        span: Span,
+    ) -> RuntimeResult<Value> {
+        let file = self.db.find_module(module_name).ok_or_else(|| {
+            let name = format!("{}::{}", module_name, name);
+            Spanned::new(RuntimeError::UnknownFunction(name), span)
+        })?;
+
+        self.in_module(file, span, |interpreter| {
+            interpreter.call(name, arguments, span)
+        })
+    }
+
+    fn in_module(
+        &mut self,
+        file: FileId,
+        span: Span,
+        action: impl FnOnce(&mut Self) -> RuntimeResult<Value>,
+    ) -> RuntimeResult<Value> {
+        if file == self.file {
+            return action(self);
+        }
+
+        let caller = std::mem::replace(&mut self.file, file);
+        let result = action(self);
+        self.file = caller;
+        result.map_err(|err| Spanned::new(err.value(), span))
+    }
+
+    fn call_closure(
+        &mut self,
+        closure: &ClosureValue,
+        arguments: Vec<Value>,
+        span: Span,
+    ) -> RuntimeResult<Value> {
+        let HirExprKind::Closure(hir_closure) = &closure.function.get_expr(closure.closure).kind
+        else {
+            unreachable!("closure values always refer to closure expressions");
+        };
+
+        if hir_closure.parameters.len() != arguments.len() {
+            return Err(Spanned::new(
+                RuntimeError::Unsupported(format!(
+                    "closure expects {} arguments but was given {}",
+                    hir_closure.parameters.len(),
+                    arguments.len()
+                )),
+                span,
+            ));
+        }
+
+        let mut variables = Variables::new();
+        for (variable, value) in &closure.environment {
+            variables.insert(*variable, value.clone());
+        }
+        for (parameter, argument) in hir_closure.parameters.iter().zip(arguments) {
+            variables.insert(*parameter, argument);
+        }
+
+        let frame = Frame {
+            function: closure.function.clone(),
+            types: closure.types.clone(),
+        };
+        self.in_module(closure.file, span, |interpreter| {
+            interpreter.invoke(frame, variables, hir_closure.body, span)
+        })
+    }
+
+    // Run the body of a function or closure, with its parameters already assigned.
+    fn invoke(
+        &mut self,
+        frame: Frame,
+        mut variables: Variables,
+        body: HirClosureBody,
+        span: Span,
+    ) -> RuntimeResult<Value> {
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(Spanned::new(RuntimeError::StackOverflow, span));
+        }
+
+        self.depth += 1;
+        let result = match body {
+            HirClosureBody::Expr(expr) => self.evaluate(&frame, &mut variables, expr),
+            HirClosureBody::Block(body) => {
+                self.execute(&frame, &mut variables, body)
+                    .map(|flow| match flow {
+                        Flow::Return(value) => value,
+                        _ => Value::Unit,
+                    })
+            }
+        };
+        self.depth -= 1;
+
+        result
+    }
+
+    fn execute(
+        &mut self,
+        frame: &Frame,
+        variables: &mut Variables,
+        id: HirStatementId,
+    ) -> RuntimeResult<Flow> {
+        let statement = frame.function.get_statement(id);
+
+        match &statement.kind {
+            HirStatementKind::Empty => Ok(Flow::Normal),
+            HirStatementKind::VarDecl { variable, expr } => {
+                let value = match expr {
+                    Some(expr) => self.evaluate(frame, variables, *expr)?,
+                    None => Value::Unit,
+                };
+                variables.insert(*variable, value);
+                Ok(Flow::Normal)
+            }
+            HirStatementKind::Destructure { pattern, expr, .. } => {
+                let value = self.evaluate(frame, variables, *expr)?;
+                self.bind_pattern(variables, pattern, value)?;
+                Ok(Flow::Normal)
+            }
+            HirStatementKind::Expr(expr) => {
+                self.evaluate(frame, variables, *expr)?;
+                Ok(Flow::Normal)
+            }
+            HirStatementKind::Return(expr) => {
+                let value = match expr {
+                    Some(expr) => self.evaluate(frame, variables, *expr)?,
+                    None => Value::Unit,
+                };
+                Ok(Flow::Return(value))
+            }
+            HirStatementKind::Continue => Ok(Flow::Continue),
+            HirStatementKind::Break => Ok(Flow::Break),
+            HirStatementKind::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                if self.evaluate_condition(frame, variables, *condition)? {
+                    self.execute(frame, variables, *then)
+                } else if let Some(otherwise) = otherwise {
+                    self.execute(frame, variables, *otherwise)
+                } else {
+                    Ok(Flow::Normal)
+                }
+            }
+            HirStatementKind::While { condition, body } => {
+                while self.evaluate_condition(frame, variables, *condition)? {
+                    match self.execute(frame, variables, *body)? {
+                        Flow::Break => break,
+                        Flow::Return(value) => return Ok(Flow::Return(value)),
+                        Flow::Normal | Flow::Continue => {}
+                    }
+                }
+                Ok(Flow::Normal)
+            }
+            HirStatementKind::Block(statements) => {
+                for statement in statements {
+                    match self.execute(frame, variables, *statement)? {
+                        Flow::Normal => {}
+                        other => return Ok(other),
+                    }
+                }
+                Ok(Flow::Normal)
+            }
+        }
+    }
+
+    fn evaluate_condition(
+        &mut self,
+        frame: &Frame,
+        variables: &mut Variables,
+        id: HirExprId,
+    ) -> RuntimeResult<bool> {
+        match self.evaluate(frame, variables, id)? {
+            Value::Bool(value) => Ok(value),
+            other => Err(self.unsupported(
+                format!("expected a bool condition, found {}", other),
+                frame.function.get_expr(id).span,
+            )),
+        }
+    }
+
+    fn evaluate(
+        &mut self,
+        frame: &Frame,
+        variables: &mut Variables,
+        id: HirExprId,
+    ) -> RuntimeResult<Value> {
+        let expr = frame.function.get_expr(id);
+        let span = expr.span;
+
+        match &expr.kind {
+            HirExprKind::LoadLiteral(literal) => match &literal.kind {
+                HirLiteralKind::Int(value) => {
+                    self.int_literal(frame, id, BigInt::from(value.clone()), span)
+                }
+                kind => Ok(self.literal(kind)),
+            },
+            HirExprKind::LoadVariable(variable) => {
+                variables.get(*variable).cloned().ok_or_else(|| {
+                    self.unsupported("variable used before being assigned".to_string(), span)
+                })
+            }
+            HirExprKind::LoadFunction(name) => Ok(self.function_value(*name)),
+            HirExprKind::LoadStruct(name) => {
+                let name = self.string(*name);
+                Err(self.unsupported(format!("struct {} cannot be used as a value", name), span))
+            }
+            HirExprKind::LoadBuiltin(builtin) => Err(self.unsupported(
+                format!("builtin {} can only be called directly", builtin.name()),
+                span,
+            )),
+            HirExprKind::BinaryOp { left, op, right } => {
+                let left = self.evaluate(frame, variables, *left)?;
+
+                // Boolean operators short-circuit, so only evaluate the right side if needed.
+                match (op, &left) {
+                    (HirExprBinaryOp::BoolAnd, Value::Bool(false)) => return Ok(left),
+                    (HirExprBinaryOp::BoolOr, Value::Bool(true)) => return Ok(left),
+                    _ => {}
+                }
+
+                let right = self.evaluate(frame, variables, *right)?;
+                binary_op(*op, &left, &right).map_err(|err| Spanned::new(err, span))
+            }
+            HirExprKind::UnaryOp { op, value } => {
+                // Negative literals may only fit in their type once negated, so we negate
+                // them before converting them.
+                if *op == HirExprUnaryOp::Negate
+                    && let HirExprKind::LoadLiteral(HirLiteral {
+                        kind: HirLiteralKind::Int(literal),
+                        ..
+                    }) = &frame.function.get_expr(*value).kind
+                {
+                    return self.int_literal(frame, id, -BigInt::from(literal.clone()), span);
+                }
+
+                let value = self.evaluate(frame, variables, *value)?;
+                unary_op(*op, &value).map_err(|err| Spanned::new(err, span))
+            }
+            HirExprKind::Assign { target, op, value } => {
+                let mut value = self.evaluate(frame, variables, *value)?;
+
+                if let Some(op) = op {
+                    let current = self.evaluate(frame, variables, *target)?;
+                    value =
+                        binary_op(*op, &current, &value).map_err(|err| Spanned::new(err, span))?;
+                }
+
+                *self.place(frame, variables, *target)? = value;
+                Ok(Value::Unit)
+            }
+            HirExprKind::MemberAccess { owner, member } => {
+                let owner = self.evaluate(frame, variables, *owner)?;
+                self.member(owner, *member, span)
+            }
+            HirExprKind::Index { owner, index } => {
+                let owner = self.evaluate(frame, variables, *owner)?;
+                let index = self.evaluate(frame, variables, *index)?;
+                index_value(&owner, &index).map_err(|err| Spanned::new(err, span))
+            }
+            HirExprKind::Call { callee, arguments } => {
+                self.evaluate_call(frame, variables, *callee, arguments, span)
+            }
+            HirExprKind::Closure(closure) => self.closure(frame, variables, id, closure, span),
+            HirExprKind::Tuple(elements) => {
+                let mut values = Vec::with_capacity(elements.len());
+                for element in elements {
+                    values.push(self.evaluate(frame, variables, *element)?);
+                }
+                Ok(Value::tuple(values))
+            }
+            HirExprKind::Format(parts) => self.format(frame, variables, parts),
+            // Qualified names refer to functions in other modules, which are looked up when
+            // they are called.
+            HirExprKind::Unresolved(name) if name.contains("::") => {
+                Ok(Value::Function(name.clone()))
+            }
+            HirExprKind::Unresolved(name) => Err(Spanned::new(
+                RuntimeError::UnknownFunction(name.clone()),
+                span,
+            )),
+        }
+    }
+
+    // The following are kept out of evaluate, as every interpreted call passes through it
+    // several times and its stack frame has to stay small.
+
+    fn member(&mut self, owner: Value, member: HirStringId, span: Span) -> RuntimeResult<Value> {
+        let member = self.string(member);
+        match &owner {
+            Value::Struct(value) => value.member(&member).cloned(),
+            Value::Tuple(elements) => tuple_position(&member)
+                .and_then(|position| elements.get(position))
+                .cloned(),
+            _ => None,
+        }
+        .ok_or_else(|| self.unsupported(format!("{} has no member named {}", owner, member), span))
+    }
+
+    fn closure(
+        &mut self,
+        frame: &Frame,
+        variables: &Variables,
+        id: HirExprId,
+        closure: &HirClosure,
+        span: Span,
+    ) -> RuntimeResult<Value> {
+        let mut environment = Vec::with_capacity(closure.captures.len());
+        for capture in &closure.captures {
+            let value = variables.get(*capture).cloned().ok_or_else(|| {
+                self.unsupported("variable used before being assigned".to_string(), span)
+            })?;
+            environment.push((*capture, value));
+        }
+
+        Ok(Value::Closure(Rc::new(ClosureValue {
+            file: self.file,
+            function: frame.function.clone(),
+            types: frame.types.clone(),
+            closure: id,
+            environment,
+        })))
+    }
+
+    // Assign each variable within a pattern the part of the value that it matches.
+    fn bind_pattern(
+        &mut self,
+        variables: &mut Variables,
+        pattern: &HirPattern,
+        value: Value,
+    ) -> RuntimeResult<()> {
+        match (pattern, value) {
+            (HirPattern::Variable(variable), value) => {
+                variables.insert(*variable, value);
+                Ok(())
+            }
+            (HirPattern::Tuple { elements, .. }, Value::Tuple(values))
+                if elements.len() == values.len() =>
+            {
+                for (element, value) in elements.iter().zip(values) {
+                    self.bind_pattern(variables, element, value)?;
+                }
+                Ok(())
+            }
+            (HirPattern::Tuple { elements, .. }, Value::Unit) if elements.is_empty() => Ok(()),
+            (HirPattern::Tuple { span, .. }, value) => {
+                Err(self.unsupported(format!("{} does not match the pattern", value), *span))
+            }
+        }
+    }
+
+    fn evaluate_call(
+        &mut self,
+        frame: &Frame,
+        variables: &mut Variables,
+        callee: HirExprId,
+        arguments: &[HirExprId],
+        span: Span,
+    ) -> RuntimeResult<Value> {
+        let mut values = Vec::with_capacity(arguments.len());
+        for argument in arguments {
+            values.push(self.evaluate(frame, variables, *argument)?);
+        }
+
+        // Calling a struct by name constructs it from its members in declaration order.
+        if let HirExprKind::LoadStruct(name) = &frame.function.get_expr(callee).kind {
+            let module = self.db.module_context(self.file);
+            let header = module
+                .lookup_struct(*name)
+                .expect("lowered struct references always refer to declared structs");
+            let members = header
+                .members
+                .iter()
+                .map(|member| module.get_string(member.name).clone())
+                .zip(values)
+                .collect();
+            return Ok(Value::Struct(Box::new(StructValue {
+                name: module.get_string(*name).clone(),
+                members,
+            })));
+        }
+
+        match &frame.function.get_expr(callee).kind {
+            HirExprKind::LoadBuiltin(builtin) => {
+                return self.call_language_builtin(frame, *builtin, arguments, &values, span);
+            }
+            // Functions in the same module can be called without going through a value.
+            HirExprKind::LoadFunction(name) => {
+                let name = self.string(*name);
+                return self.call(&name, values, span);
+            }
+            _ => {}
+        }
+
+        match self.evaluate(frame, variables, callee)? {
+            Value::Function(name) => self.call(&name, values, span),
+            Value::Closure(closure) => self.call_closure(&closure, values, span),
+            other => Err(self.unsupported(format!("{} is not callable", other), span)),
+        }
+    }
+
+    // Builtins describe the expressions that they were given when they fail, so they are
+    // passed the arguments as well as their values.
+    fn call_language_builtin(
+        &mut self,
+        frame: &Frame,
+        builtin: HirBuiltin,
+        arguments: &[HirExprId],
+        values: &[Value],
+        span: Span,
+    ) -> RuntimeResult<Value> {
+        let failure = match (builtin, values) {
+            (HirBuiltin::Print, [Value::String(text)]) => {
+                return self.write_output(format_args!("{}", text), span);
+            }
+            (HirBuiltin::Println, [Value::String(text)]) => {
+                return self.write_output(format_args!("{}\n", text), span);
+            }
+            (HirBuiltin::Assert, [Value::Bool(true)]) => None,
+            (HirBuiltin::Assert, [Value::Bool(false)]) => {
+                Some(self.source_text(frame, arguments[0]))
+            }
+            (HirBuiltin::AssertEq, [left, right]) => {
+                match binary_op(HirExprBinaryOp::Eq, left, right) {
+                    Ok(Value::Bool(true)) => None,
+                    Ok(_) => Some(format!(
+                        "{} == {} (left: {}, right: {})",
+                        self.source_text(frame, arguments[0]),
+                        self.source_text(frame, arguments[1]),
+                        left,
+                        right
+                    )),
+                    Err(err) => return Err(Spanned::new(err, span)),
+                }
+            }
+            _ => {
+                return Err(self.unsupported(
+                    format!("invalid arguments for builtin {}", builtin.name()),
+                    span,
+                ));
+            }
+        };
+
+        match failure {
+            Some(text) => Err(Spanned::new(RuntimeError::AssertionFailed(text), span)),
+            None => Ok(Value::Unit),
+        }
+    }
+
+    fn write_output(&mut self, text: std::fmt::Arguments, span: Span) -> RuntimeResult<Value> {
+        self.output
+            .write_fmt(text)
+            .map(|_| Value::Unit)
+            .map_err(|err| self.unsupported(format!("failed to write output: {}", err), span))
+    }
+
+    fn format(
+        &mut self,
+        frame: &Frame,
+        variables: &mut Variables,
+        parts: &[HirFormatPart],
+    ) -> RuntimeResult<Value> {
+        let mut text = String::new();
+        for part in parts {
+            match part {
+                HirFormatPart::Text(id) => {
+                    text.push_str(self.db.module_context(self.file).get_string(*id))
+                }
+                HirFormatPart::Value(value) => {
+                    let value = self.evaluate(frame, variables, *value)?;
+                    text.push_str(&value.to_string());
+                }
+            }
+        }
+        Ok(Value::String(Rc::from(text)))
+    }
+
+    // The source code of an expression, as it was written.
+    fn source_text(&self, frame: &Frame, id: HirExprId) -> String {
+        let range = frame.function.get_expr(id).span.range();
+        self.db
+            .file_text(self.file)
+            .get(range)
+            .unwrap_or("<unknown>")
+            .to_string()
+    }
+
+    // Find the storage location that an assignment target refers to.
+    fn place<'v>(
+        &mut self,
+        frame: &Frame,
+        variables: &'v mut Variables,
+        id: HirExprId,
+    ) -> RuntimeResult<&'v mut Value> {
+        let expr = frame.function.get_expr(id);
+        let span = expr.span;
+
+        match &expr.kind {
+            HirExprKind::LoadVariable(variable) => {
+                Ok(variables.entry(*variable).or_insert(Value::Unit))
+            }
+            HirExprKind::MemberAccess { owner, member } => {
+                let member = self.string(*member);
+                match self.place(frame, variables, *owner)? {
+                    Value::Struct(value) => value.member_mut(&member).ok_or_else(|| {
+                        Spanned::new(
+                            RuntimeError::Unsupported(format!("no member named {}", member)),
+                            span,
+                        )
+                    }),
+                    Value::Tuple(elements) => tuple_position(&member)
+                        .and_then(|position| elements.get_mut(position))
+                        .ok_or_else(|| {
+                            Spanned::new(
+                                RuntimeError::Unsupported(format!("no member named {}", member)),
+                                span,
+                            )
+                        }),
+                    _ => Err(Spanned::new(
+                        RuntimeError::Unsupported(format!("no member named {}", member)),
+                        span,
+                    )),
+                }
+            }
+            _ => Err(self.unsupported("invalid assignment target".to_string(), span)),
+        }
+    }
+
+    /*
+     * Helpers
+     */
+
+    fn literal(&mut self, literal: &HirLiteralKind) -> Value {
+        match literal {
+            HirLiteralKind::Bool(value) => Value::Bool(*value),
+            HirLiteralKind::I8(value) => Value::I8(*value),
+            HirLiteralKind::I16(value) => Value::I16(*value),
+            HirLiteralKind::I32(value) => Value::I32(*value),
+            HirLiteralKind::I64(value) => Value::I64(*value),
+            HirLiteralKind::I128(value) => Value::I128(*value),
+            HirLiteralKind::ISize(value) => Value::ISize(*value),
+            HirLiteralKind::U8(value) => Value::U8(*value),
+            HirLiteralKind::U16(value) => Value::U16(*value),
+            HirLiteralKind::U32(value) => Value::U32(*value),
+            HirLiteralKind::U64(value) => Value::U64(*value),
+            HirLiteralKind::U128(value) => Value::U128(*value),
+            HirLiteralKind::USize(value) => Value::USize(*value),
+            HirLiteralKind::F32(value) => Value::F32(*value),
+            HirLiteralKind::F64(value) => Value::F64(*value),
+            HirLiteralKind::String(id) => Value::String(Rc::from(self.string(*id).as_str())),
+            HirLiteralKind::Char(value) => Value::Char(*value),
+            HirLiteralKind::Bytes(value) => Value::Bytes(Rc::from(value.as_ref())),
+            HirLiteralKind::Int(value) => unreachable!("untyped literal {} needs a type", value),
+        }
+    }
+
+    // Convert an unsuffixed integer literal to the type that the type checker gave it.
+    fn int_literal(
+        &self,
+        frame: &Frame,
+        id: HirExprId,
+        value: BigInt,
+        span: Span,
+    ) -> RuntimeResult<Value> {
+        let ty = frame.types.type_of_expr(id);
+        Value::from_integer(&value, ty).ok_or_else(|| {
+            self.unsupported(
+                format!("integer literal {} cannot be used as {}", value, ty),
+                span,
+            )
+        })
+    }
+
+    // Function values can be passed to other modules, so they always use the qualified name
+    // of the function.
+    fn function_value(&mut self, name: HirStringId) -> Value {
+        let name = self.string(name);
+        Value::Function(format!("{}::{}", self.db.module_name(self.file), name))
+    }
+
+    fn string(&mut self, id: HirStringId) -> HirString {
+        self.db.module_context(self.file).get_string(id).clone()
+    }
+
+    fn unsupported(&self, message: String, span: Span) -> Spanned<RuntimeError> {
+        Spanned::new(RuntimeError::Unsupported(message), span)
+    }
+}
+
+macro_rules! integer_binary_op {
+    ($op:expr, $left:expr, $right:expr, $($variant:ident),*) => {
+        match ($left, $right) {
+            $(
+                (Value::$variant(a), Value::$variant(b)) => {
+                    let (a, b) = (*a, *b);
+                    let result = match $op {
+                        HirExprBinaryOp::Div | HirExprBinaryOp::Mod if b == 0 => {
+                            return Err(RuntimeError::DivisionByZero)
+                        }
+                        HirExprBinaryOp::Add => a.checked_add(b),
+                        HirExprBinaryOp::Sub => a.checked_sub(b),
+                        HirExprBinaryOp::Mul => a.checked_mul(b),
+                        HirExprBinaryOp::Div => a.checked_div(b),
+                        HirExprBinaryOp::Mod => a.checked_rem(b),
+                        HirExprBinaryOp::Pow => u32::try_from(b).ok().and_then(|b| a.checked_pow(b)),
+                        HirExprBinaryOp::BinaryAnd => Some(a & b),
+                        HirExprBinaryOp::BinaryOr => Some(a | b),
+                        HirExprBinaryOp::BinaryXor => Some(a ^ b),
+                        _ => return Err(invalid_operands($op, $left, $right)),
+                    };
+                    return result.map(Value::$variant).ok_or_else(|| {
+                        RuntimeError::ArithmeticOverflow(format!("{} {:?} {}", a, $op, b))
+                    });
+                }
+            )*
+            _ => {}
+        }
+    };
+}
+
+macro_rules! float_binary_op {
+    ($op:expr, $left:expr, $right:expr, $($variant:ident),*) => {
+        match ($left, $right) {
+            $(
+                (Value::$variant(a), Value::$variant(b)) => {
+                    let (a, b) = (*a, *b);
+                    return match $op {
+                        HirExprBinaryOp::Add => Ok(Value::$variant(a + b)),
+                        HirExprBinaryOp::Sub => Ok(Value::$variant(a - b)),
+                        HirExprBinaryOp::Mul => Ok(Value::$variant(a * b)),
+                        HirExprBinaryOp::Div => Ok(Value::$variant(a / b)),
+                        HirExprBinaryOp::Mod => Ok(Value::$variant(a % b)),
+                        HirExprBinaryOp::Pow => Ok(Value::$variant(a.powf(b))),
+                        _ => Err(invalid_operands($op, $left, $right)),
+                    };
+                }
+            )*
+            _ => {}
+        }
+    };
+}
+
+macro_rules! shift_op {
+    ($op:expr, $left:expr, $amount:expr, $($variant:ident),*) => {
+        match $left {
+            $(
+                Value::$variant(a) => {
+                    let result = match $op {
+                        HirExprBinaryOp::BinaryShl => a.checked_shl($amount),
+                        _ => a.checked_shr($amount),
+                    };
+                    return result.map(Value::$variant).ok_or_else(|| {
+                        RuntimeError::ArithmeticOverflow(format!("{} {:?} {}", a, $op, $amount))
+                    });
+                }
+            )*
+            _ => {}
+        }
+    };
+}
+
+macro_rules! compare_values {
+    ($left:expr, $right:expr, $($variant:ident),*) => {
+        match ($left, $right) {
+            $((Value::$variant(a), Value::$variant(b)) => a.partial_cmp(b),)*
+            _ => None,
+        }
+    };
+}
+
+pub(crate) fn binary_op(
+    op: HirExprBinaryOp,
+    left: &Value,
+    right: &Value,
+) -> Result<Value, RuntimeError> {
+    // The type checker allows fixed width integers to meet big integers, widening them first.
+    if let Some((left, right)) = widen_to_big_integers(left, right) {
+        return binary_op(op, &left, &right);
+    }
+
+    match op {
+        HirExprBinaryOp::Eq => return Ok(Value::Bool(left == right)),
+        HirExprBinaryOp::NotEq => return Ok(Value::Bool(left != right)),
+        HirExprBinaryOp::Less
+        | HirExprBinaryOp::LessEq
+        | HirExprBinaryOp::Greater
+        | HirExprBinaryOp::GreaterEq => {
+            let ordering = compare_values!(
+                left, right, Bool, I8, I16, I32, I64, I128, ISize, U8, U16, U32, U64, U128, USize,
+                F32, F64, String, Char, BigInt
+            )
+            .ok_or_else(|| invalid_operands(op, left, right))?;
+            return Ok(Value::Bool(match op {
+                HirExprBinaryOp::Less => ordering == Ordering::Less,
+                HirExprBinaryOp::LessEq => ordering != Ordering::Greater,
+                HirExprBinaryOp::Greater => ordering == Ordering::Greater,
+                _ => ordering != Ordering::Less,
+            }));
+        }
+        HirExprBinaryOp::BoolAnd | HirExprBinaryOp::BoolOr => {
+            return match (left, right) {
+                (Value::Bool(_), Value::Bool(right)) => Ok(Value::Bool(*right)),
+                _ => Err(invalid_operands(op, left, right)),
+            };
+        }
+        HirExprBinaryOp::BinaryShl | HirExprBinaryOp::BinaryShr => {
+            let amount = shift_amount(right).ok_or_else(|| invalid_operands(op, left, right))?;
+            shift_op!(
+                op, left, amount, I8, I16, I32, I64, I128, ISize, U8, U16, U32, U64, U128, USize
+            );
+            return Err(invalid_operands(op, left, right));
+        }
+        _ => {}
+    }
+
+    if let (HirExprBinaryOp::Add, Value::String(a), Value::String(b)) = (op, left, right) {
+        return Ok(Value::String(Rc::from([&**a, &**b].concat())));
+    }
+    integer_binary_op!(
+        op, left, right, I8, I16, I32, I64, I128, ISize, U8, U16, U32, U64, U128, USize
+    );
+    float_binary_op!(op, left, right, F32, F64);
+    if let (Value::BigInt(a), Value::BigInt(b)) = (left, right) {
+        return big_integer_binary_op(op, a, b)
+            .unwrap_or_else(|| Err(invalid_operands(op, left, right)));
+    }
+    Err(invalid_operands(op, left, right))
+}
+
+// Big integers never overflow, so only division by zero and huge powers can fail. Returns None
+// if the operator cannot be applied to big integers at all.
+fn big_integer_binary_op(
+    op: HirExprBinaryOp,
+    a: &BigInt,
+    b: &BigInt,
+) -> Option<Result<Value, RuntimeError>> {
+    let result = match op {
+        HirExprBinaryOp::Div | HirExprBinaryOp::Mod if *b == BigInt::ZERO => {
+            return Some(Err(RuntimeError::DivisionByZero));
+        }
+        HirExprBinaryOp::Add => a + b,
+        HirExprBinaryOp::Sub => a - b,
+        HirExprBinaryOp::Mul => a * b,
+        HirExprBinaryOp::Div => a / b,
+        HirExprBinaryOp::Mod => a % b,
+        HirExprBinaryOp::Pow => match u32::try_from(b) {
+            Ok(exponent) => a.pow(exponent),
+            Err(_) => {
+                let message = format!("{} {:?} {}", a, op, b);
+                return Some(Err(RuntimeError::ArithmeticOverflow(message)));
+            }
+        },
+        _ => return None,
+    };
+    Some(Ok(Value::BigInt(Rc::new(result))))
+}
+
+fn widen_to_big_integers(left: &Value, right: &Value) -> Option<(Value, Value)> {
+    match (left, right) {
+        (Value::BigInt(_), Value::BigInt(_)) => None,
+        (Value::BigInt(_), other) => Some((left.clone(), big_integer(other)?)),
+        (other, Value::BigInt(_)) => Some((big_integer(other)?, right.clone())),
+        _ => None,
+    }
+}
+
+fn big_integer(value: &Value) -> Option<Value> {
+    let value = match value {
+        Value::I8(a) => BigInt::from(*a),
+        Value::I16(a) => BigInt::from(*a),
+        Value::I32(a) => BigInt::from(*a),
+        Value::I64(a) => BigInt::from(*a),
+        Value::I128(a) => BigInt::from(*a),
+        Value::ISize(a) => BigInt::from(*a),
+        Value::U8(a) => BigInt::from(*a),
+        Value::U16(a) => BigInt::from(*a),
+        Value::U32(a) => BigInt::from(*a),
+        Value::U64(a) => BigInt::from(*a),
+        Value::U128(a) => BigInt::from(*a),
+        Value::USize(a) => BigInt::from(*a),
+        _ => return None,
+    };
+    Some(Value::BigInt(Rc::new(value)))
+}
+
+pub(crate) fn unary_op(op: HirExprUnaryOp, value: &Value) -> Result<Value, RuntimeError> {
+    let overflow = || RuntimeError::ArithmeticOverflow(format!("{:?} {}", op, value));
+
+    match (op, value) {
+        (HirExprUnaryOp::Negate, Value::I8(a)) => {
+            a.checked_neg().map(Value::I8).ok_or_else(overflow)
+        }
+        (HirExprUnaryOp::Negate, Value::I16(a)) => {
+            a.checked_neg().map(Value::I16).ok_or_else(overflow)
+        }
+        (HirExprUnaryOp::Negate, Value::I32(a)) => {
+            a.checked_neg().map(Value::I32).ok_or_else(overflow)
+        }
+        (HirExprUnaryOp::Negate, Value::I64(a)) => {
+            a.checked_neg().map(Value::I64).ok_or_else(overflow)
+        }
+        (HirExprUnaryOp::Negate, Value::I128(a)) => {
+            a.checked_neg().map(Value::I128).ok_or_else(overflow)
+        }
+        (HirExprUnaryOp::Negate, Value::ISize(a)) => {
+            a.checked_neg().map(Value::ISize).ok_or_else(overflow)
+        }
+        (HirExprUnaryOp::Negate, Value::F32(a)) => Ok(Value::F32(-a)),
+        (HirExprUnaryOp::Negate, Value::F64(a)) => Ok(Value::F64(-a)),
+        (HirExprUnaryOp::Negate, Value::BigInt(a)) => Ok(Value::BigInt(Rc::new(-&**a))),
+        (HirExprUnaryOp::Not, Value::Bool(a)) => Ok(Value::Bool(!a)),
+        (HirExprUnaryOp::Invert, Value::I8(a)) => Ok(Value::I8(!a)),
+        (HirExprUnaryOp::Invert, Value::I16(a)) => Ok(Value::I16(!a)),
+        (HirExprUnaryOp::Invert, Value::I32(a)) => Ok(Value::I32(!a)),
+        (HirExprUnaryOp::Invert, Value::I64(a)) => Ok(Value::I64(!a)),
+        (HirExprUnaryOp::Invert, Value::I128(a)) => Ok(Value::I128(!a)),
+        (HirExprUnaryOp::Invert, Value::ISize(a)) => Ok(Value::ISize(!a)),
+        (HirExprUnaryOp::Invert, Value::U8(a)) => Ok(Value::U8(!a)),
+        (HirExprUnaryOp::Invert, Value::U16(a)) => Ok(Value::U16(!a)),
+        (HirExprUnaryOp::Invert, Value::U32(a)) => Ok(Value::U32(!a)),
+        (HirExprUnaryOp::Invert, Value::U64(a)) => Ok(Value::U64(!a)),
+        (HirExprUnaryOp::Invert, Value::U128(a)) => Ok(Value::U128(!a)),
+        (HirExprUnaryOp::Invert, Value::USize(a)) => Ok(Value::USize(!a)),
+        _ => Err(RuntimeError::Unsupported(format!(
+            "operator {:?} cannot be applied to {}",
+            op, value
+        ))),
+    }
+}
+
+fn shift_amount(value: &Value) -> Option<u32> {
+    match value {
+        Value::I8(a) => u32::try_from(*a).ok(),
+        Value::I16(a) => u32::try_from(*a).ok(),
+        Value::I32(a) => u32::try_from(*a).ok(),
+        Value::I64(a) => u32::try_from(*a).ok(),
+        Value::I128(a) => u32::try_from(*a).ok(),
+        Value::ISize(a) => u32::try_from(*a).ok(),
+        Value::U8(a) => Some(u32::from(*a)),
+        Value::U16(a) => Some(u32::from(*a)),
+        Value::U32(a) => Some(*a),
+        Value::U64(a) => u32::try_from(*a).ok(),
+        Value::U128(a) => u32::try_from(*a).ok(),
+        Value::USize(a) => u32::try_from(*a).ok(),
+        _ => None,
+    }
+}
+
+// Tuple elements are accessed as members named after their position.
+pub(crate) fn index_value(owner: &Value, index: &Value) -> Result<Value, RuntimeError> {
+    match (owner, index_position(index)) {
+        (Value::Bytes(bytes), position) => index_byte(bytes, index, position),
+        (Value::String(text), position) => index_byte(text.as_bytes(), index, position),
+        _ => Err(RuntimeError::Unsupported(format!(
+            "{} cannot be indexed by {}",
+            owner, index
+        ))),
+    }
+}
+
+fn index_byte(bytes: &[u8], index: &Value, position: Option<usize>) -> Result<Value, RuntimeError> {
+    position
+        .and_then(|position| bytes.get(position))
+        .map(|byte| Value::U8(*byte))
+        .ok_or_else(|| RuntimeError::IndexOutOfBounds {
+            index: index.to_string(),
+            length: bytes.len(),
+        })
+}
+
+fn tuple_position(member: &str) -> Option<usize> {
+    member.parse().ok()
+}
+
+fn index_position(value: &Value) -> Option<usize> {
+    match value {
+        Value::I8(a) => usize::try_from(*a).ok(),
+        Value::I16(a) => usize::try_from(*a).ok(),
+        Value::I32(a) => usize::try_from(*a).ok(),
+        Value::I64(a) => usize::try_from(*a).ok(),
+        Value::I128(a) => usize::try_from(*a).ok(),
+        Value::ISize(a) => usize::try_from(*a).ok(),
+        Value::U8(a) => Some(usize::from(*a)),
+        Value::U16(a) => Some(usize::from(*a)),
+        Value::U32(a) => usize::try_from(*a).ok(),
+        Value::U64(a) => usize::try_from(*a).ok(),
+        Value::U128(a) => usize::try_from(*a).ok(),
+        Value::USize(a) => Some(*a),
+        _ => None,
+    }
+}
+
+fn invalid_operands(op: HirExprBinaryOp, left: &Value, right: &Value) -> RuntimeError {
+    RuntimeError::Unsupported(format!(
+        "operator {:?} cannot be applied to {} and {}",
+        op, left, right
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn run(source: &str) -> (RuntimeResult<Value>, String) {
+        let mut db = Database::new();
+        let file = db.add_file("test.hkl", source);
+        let mut output: Vec<u8> = Vec::new();
+        let result = Interpreter::new(&mut db, file, &mut output).call("main", vec![], Span::UNSET);
+        (result, String::from_utf8(output).unwrap())
+    }
+
+    #[test_case("fn main() -> i32 { return 1 + 2 * 3; }", Value::I32(7) ; "arithmetic precedence")]
+    #[test_case("fn main() -> i32 { return 2 ** 3 ** 2; }", Value::I32(512) ; "right associative power")]
+    #[test_case("fn main() -> bool { return 1 < 2 && !(3 <= 2); }", Value::Bool(true) ; "comparisons")]
+    #[test_case("fn main() -> u8 { return ~0u8 >> 4u8; }", Value::U8(15) ; "bitwise operators")]
+    #[test_case("fn main() -> f64 { return -1.5 * 2.0; }", Value::F64(-3.0) ; "floats")]
+    #[test_case(
+        "fn main() -> i32 { let x = 0; let i = 0; while (true) { i += 1; if (i > 10) { break; } if (i % 2 == 0) { continue; } x += i; } return x; }",
+        Value::I32(25)
+        ; "loops with break and continue"
+    )]
+    #[test_case(
+        "fn fib(n: i32) -> i32 { if (n < 2) { return n; } return fib(n - 1) + fib(n - 2); } fn main() -> i32 { return fib(15); }",
+        Value::I32(610)
+        ; "recursion"
+    )]
+    #[test_case(
+        "struct P { x: i32; y: i32; } fn main() -> i32 { let p = P(1, 2); p.y = 40; return p.x + p.y + 1; }",
+        Value::I32(42)
+        ; "struct members"
+    )]
+    #[test_case(
+        "fn twice(x: i32) -> i32 { return x * 2; } fn main() -> i32 { let f = twice; return f(21); }",
+        Value::I32(42)
+        ; "function values"
+    )]
+    #[test_case("fn main() -> u64 { return 0xdead_beef_cafe; }", Value::U64(0xdead_beef_cafe) ; "wide literals")]
+    #[test_case("fn main() -> i8 { return -128; }", Value::I8(-128) ; "minimum negative literal")]
+    #[test_case(
+        "fn main() -> u128 { let h: u128 = 0xcbf29ce484222325; return (h ^ 97) * 1099511628211 & 0xffff_ffff_ffff_ffff_ffff; }",
+        Value::U128(0xe5de_af63_dc4c_8601_ec8c)
+        ; "hashing with u128"
+    )]
+    #[test_case("fn main() -> isize { return -(1isize << 40); }", Value::ISize(-(1 << 40)) ; "isize")]
+    #[test_case("fn main() -> char { return '\\u{263A}'; }", Value::Char('\u{263A}') ; "characters")]
+    #[test_case("fn main() -> bool { return 'a' < 'b'; }", Value::Bool(true) ; "character comparisons")]
+    #[test_case("fn main() -> u8 { let s = b\"hi\\n\"; return s[1] + s[2] - b'\\n'; }", Value::U8(b'i') ; "indexing bytes")]
+    #[test_case(
+        "fn apply(f: fn(i32) -> i32, x: i32) -> i32 { return f(x); } fn main() -> i32 { return apply(|x| x * 2, 21); }",
+        Value::I32(42)
+        ; "closures as callbacks"
+    )]
+    #[test_case(
+        "fn main() -> i32 { let n = 40; let add = |x: i32| x + n; n = 0; return add(2); }",
+        Value::I32(42)
+        ; "closures capture by value"
+    )]
+    #[test_case(
+        "fn adder(n: i32) -> fn(i32) -> i32 { return |x| x + n; } fn main() -> i32 { let add = adder(40); return add(2); }",
+        Value::I32(42)
+        ; "closures outliving their scope"
+    )]
+    #[test_case(
+        "fn main() -> i32 { let a = 1; let f = |b: i32| |c: i32| a + b + c; return f(10)(100); }",
+        Value::I32(111)
+        ; "nested closures"
+    )]
+    #[test_case(
+        "fn main() -> i32 { let f = |n: i32| -> i32 { let total = 0; while (n > 0) { total += n; n -= 1; } return total; }; return f(4); }",
+        Value::I32(10)
+        ; "closures with blocks"
+    )]
+    #[test_case(
+        "fn inc(x: i32) -> i32 { return x + 1; } fn twice(f: fn(i32) -> i32, x: i32) -> i32 { return f(f(x)); } fn main() -> i32 { return twice(inc, 40); }",
+        Value::I32(42)
+        ; "passing named functions"
+    )]
+    #[test_case(
+        "fn divmod(a: i32, b: i32) -> (i32, i32) { return (a / b, a % b); } fn main() -> i32 { let (q, r) = divmod(47, 5); return q * 10 + r; }",
+        Value::I32(92)
+        ; "multiple return values"
+    )]
+    #[test_case(
+        "fn main() -> (bool, (u8,)) { let t = (1u8, (true, 2u8)); let (a, (b, c)) = t; t.0 = 5; return (b, (c + t.0 + a,)); }",
+        Value::Tuple(Box::new([Value::Bool(true), Value::Tuple(Box::new([Value::U8(8)]))]))
+        ; "tuples"
+    )]
+    #[test_case("fn main() -> bool { return (1, 'a') == (1, 'a') && () == (); }", Value::Bool(true) ; "tuple equality")]
+    #[test_case("fn main() -> string { let s = \"hé\"; s += \"llo\"; return s + \"!\"; }", Value::String(Rc::from("héllo!")) ; "string concatenation")]
+    #[test_case("fn main() -> u8 { let s = \"héllo\"; return s[2]; }", Value::U8(0xa9) ; "indexing strings by byte")]
+    #[test_case("fn main() -> bool { return \"apple\" < \"banana\" && \"b\" >= \"abc\" && \"a\" == \"a\"; }", Value::Bool(true) ; "string comparisons")]
+    #[test_case(
+        "fn main() -> std::BigInt { let a: std::BigInt = 18446744073709551616; return (a * a - 1) / 3 % 1000000000000000000000; }",
+        Value::BigInt(Rc::new("154458202477256070485".parse().unwrap()))
+        ; "big integer arithmetic"
+    )]
+    #[test_case("fn main() -> bool { let a: std::BigInt = -2; return a ** 100 == (-a) ** 100 && -a > a; }", Value::Bool(true) ; "big integer comparisons")]
+    #[test_case("fn main() -> bool { let a: std::BigInt = 1; let n = 1; while (n <= 30) { a *= n; n += 1; } return a == 265252859812191058636308480000000 && n < a; }", Value::Bool(true) ; "big integers mixed with fixed width integers")]
+    fn programs_evaluate_correctly(source: &str, expected: Value) {
+        // When
+        let (result, _) = run(source);
+
+        // Then
+        assert_eq!(result, Ok(expected));
+    }
+
+    #[test_case("fn main() -> i32 { return 2147483647 + 1; }", RuntimeError::ArithmeticOverflow("2147483647 Add 1".to_string()) ; "overflow")]
+    #[test_case("fn main() -> i32 { return 1 / 0; }", RuntimeError::DivisionByZero ; "division by zero")]
+    #[test_case("fn main() -> std::BigInt { let a: std::BigInt = 1; return a % 0; }", RuntimeError::DivisionByZero ; "big integer division by zero")]
+    #[test_case("fn main() -> std::BigInt { let a: std::BigInt = 2; return a ** 4294967296; }", RuntimeError::ArithmeticOverflow("2 Pow 4294967296".to_string()) ; "big integer power too large")]
+    #[test_case("fn main() { main(); }", RuntimeError::StackOverflow ; "unbounded recursion")]
+    #[test_case("extern fn nope(); fn main() { nope(); }", RuntimeError::UnknownFunction("nope".to_string()) ; "missing builtin")]
+    #[test_case(
+        "fn main() -> u8 { return b\"hi\"[2]; }",
+        RuntimeError::IndexOutOfBounds { index: "2".to_string(), length: 2 }
+        ; "index out of bounds"
+    )]
+    #[test_case(
+        "fn main() -> u8 { return \"hi\"[5]; }",
+        RuntimeError::IndexOutOfBounds { index: "5".to_string(), length: 2 }
+        ; "string index out of bounds"
+    )]
+    fn programs_report_runtime_errors(source: &str, expected: RuntimeError) {
+        // When
+        let (result, _) = run(source);
+
+        // Then
+        assert_eq!(result.map_err(|err| err.value()), Err(expected));
+    }
+
+    #[test]
+    fn format_strings_are_filled_in() {
+        // When
+        let (result, output) = run(
+            "fn main() -> string { let x = 2; println(\"x = {}, {{x}}\", x * 3); print(\"{}\", 'a'); println(); return format(\"{}-{}\", true, (1, \"{}\")); }",
+        );
+
+        // Then
+        assert_eq!(result, Ok(Value::String(Rc::from("true-(1, {})"))));
+        assert_eq!(output, "x = 6, {x}\na\n");
+    }
+
+    #[test]
+    fn externs_call_builtins() {
+        // When
+        let (result, output) = run(
+            "extern fn println(fmt: string, value: i32); fn main() { println(\"value = {}\", 12); }",
+        );
+
+        // Then
+        assert_eq!(result, Ok(Value::Unit));
+        assert_eq!(output, "value = 12\n");
+    }
+
+    #[test]
+    fn externs_can_be_linked_to_builtins_with_other_names() {
+        // When
+        let (result, output) = run(
+            "#[link_name(\"println\")] extern fn show(fmt: string, value: i32); fn main() { show(\"{}!\", 3); }",
+        );
+
+        // Then
+        assert_eq!(result, Ok(Value::Unit));
+        assert_eq!(output, "3!\n");
+    }
+}
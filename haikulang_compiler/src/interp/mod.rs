@@ -0,0 +1,9 @@
+//! Interpreter that executes HIR directly, without compiling it first.
+//!
+//! This is primarily intended for quickly trying out programs during development.
+pub mod builtins;
+pub mod heap;
+pub mod interpreter;
+pub mod repl;
+pub mod testing;
+pub mod value;
@@ -0,0 +1,544 @@
+//! Interactive sessions, which run code as it is entered.
+//!
+//! Each input is either some items, such as functions and structs, some statements, or a
+//! single expression. Items are added to a module that belongs to the session, so its module
+//! context persists from one input to the next. Statements and expressions are run within a
+//! function in that module, which takes every variable declared so far as a parameter, so
+//! that variables carry over between inputs too.
+use crate::db::database::{Database, FileId, FunctionId};
+use crate::hir::dump::dump_function;
+use crate::hir::nodes::{HirFunctionData, HirPattern, HirStatementKind, HirVariableId};
+use crate::hir::ty::HirType;
+use crate::interp::heap::Heap;
+use crate::interp::interpreter::Interpreter;
+use crate::interp::value::Value;
+use crate::stdlib::add_std;
+use haikulang_parser::ast::expr::Expr;
+use haikulang_parser::ast::stmt::Statement;
+use haikulang_parser::ast::unit::CompilationUnit;
+use haikulang_parser::error::{Diagnostics, ParserError};
+use haikulang_parser::lexer::token::Token;
+use haikulang_parser::lexer::token_stream::TokenStream;
+use haikulang_parser::parser::core::Parser;
+use haikulang_parser::span::{Span, Spanned};
+use std::io::Write;
+use std::path::Path;
+
+const SESSION_PATH: &str = "repl.hkl";
+
+// Statements and expressions are run within a function with this name. The value of an
+// expression is assigned to a variable, so that it can be read back along with the others.
+const INPUT_FUNCTION: &str = "__repl_input";
+const VALUE_VARIABLE: &str = "__repl_value";
+
+/// A session that keeps the items and variables declared by each input.
+pub struct ReplSession {
+    db: Database,
+    file: FileId,
+    items: String,
+    variables: Vec<ReplVariable>,
+    heap: Heap,
+}
+
+/// A variable declared by an earlier input.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplVariable {
+    pub name: String,
+    pub ty: HirType,
+    pub value: Value,
+}
+
+/// What happened when an input was entered.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReplOutcome {
+    /// The input is not finished yet, such as when a brace has not been closed. It should be
+    /// entered again once more has been added to it.
+    Incomplete,
+    /// The input could not be compiled, or failed while running. The spans are relative to
+    /// the start of the input.
+    Errors(Vec<Spanned<String>>),
+    /// Items were declared, or statements were run.
+    Done,
+    /// An expression was evaluated.
+    Value { value: Value, ty: HirType },
+}
+
+/// An input once it has been parsed.
+#[derive(Clone, Debug)]
+pub enum ReplInput {
+    Items(CompilationUnit),
+    Statements(Box<[Spanned<Statement>]>),
+    Expr(Expr),
+}
+
+impl ReplSession {
+    pub fn new() -> Self {
+        let mut db = Database::new();
+        add_std(&mut db);
+        let file = db.add_file(SESSION_PATH, "");
+
+        Self {
+            db,
+            file,
+            items: String::new(),
+            variables: Vec::new(),
+            heap: Heap::new(),
+        }
+    }
+
+    /// The variables declared so far, in the order that they were last assigned.
+    pub fn variables(&self) -> &[ReplVariable] {
+        &self.variables
+    }
+
+    /// Compile and run an input, keeping anything that it declares. Anything the input prints
+    /// is written to the output.
+    pub fn eval(&mut self, input: &str, output: &mut dyn Write) -> ReplOutcome {
+        let parsed = match parse_input(input) {
+            Ok(parsed) => parsed,
+            Err(outcome) => return outcome,
+        };
+
+        let (source, offset) = self.source_for(&parsed, input);
+        self.db.set_file_text(self.file, &source);
+        let errors = self.errors(offset, input.len());
+        let outcome = if !errors.is_empty() {
+            ReplOutcome::Errors(errors)
+        } else if let ReplInput::Items(_) = parsed {
+            self.items = source.clone();
+            ReplOutcome::Done
+        } else {
+            self.run(offset, input.len(), output)
+        };
+
+        // Only items are kept in the session's module. Everything else is run once.
+        self.db.set_file_text(self.file, &self.items);
+        outcome
+    }
+
+    /// Show the tokens that an input is made up of, one per line.
+    pub fn tokens(&self, input: &str) -> String {
+        let mut stream = TokenStream::new(input);
+        let mut lines = Vec::new();
+        loop {
+            match stream.current() {
+                Ok(token) if token.value_ref() == &Token::Eof => break,
+                Ok(token) => lines.push(format!("{}: {:?}", token.span(), token.value())),
+                Err(err) => lines.push(format!("{}: error: {}", err.span(), err.value())),
+            }
+            stream.advance();
+        }
+        lines.join("\n")
+    }
+
+    /// Show the syntax tree of an input.
+    pub fn ast(&self, input: &str) -> Result<String, ReplOutcome> {
+        Ok(match parse_input(input)? {
+            ReplInput::Items(unit) => format!("{:#?}", unit.members),
+            ReplInput::Statements(statements) => format!("{:#?}", statements),
+            ReplInput::Expr(expr) => format!("{:#?}", expr),
+        })
+    }
+
+    /// Show the lowered form of an input, along with the types inferred for it. Items are not
+    /// kept, and statements are not run.
+    pub fn hir(&mut self, input: &str) -> Result<String, ReplOutcome> {
+        let parsed = parse_input(input)?;
+        let (source, offset) = self.source_for(&parsed, input);
+        self.db.set_file_text(self.file, &source);
+
+        let names: Vec<String> = match parsed {
+            ReplInput::Items(_) => self
+                .db
+                .item_tree(self.file)
+                .functions()
+                .filter(|function| function.span.range().start >= offset)
+                .map(|function| function.name.clone())
+                .collect(),
+            _ => vec![INPUT_FUNCTION.to_string()],
+        };
+
+        let mut dumps = Vec::new();
+        for name in names {
+            let id = FunctionId::new(self.file, &name);
+            if let (Some(function), Some(types)) =
+                (self.db.lower_function(&id), self.db.type_of(&id))
+            {
+                let dump = dump_function(self.db.module_context(self.file), &function, &types);
+                dumps.push(format!("fn {}\n{}", name, dump.trim_end()));
+            }
+        }
+
+        self.db.set_file_text(self.file, &self.items);
+        Ok(dumps.join("\n"))
+    }
+
+    // The source of the session's module with the input added to it, along with where the
+    // input starts within it.
+    fn source_for(&self, parsed: &ReplInput, input: &str) -> (String, usize) {
+        let prefix = match parsed {
+            ReplInput::Items(_) => return (format!("{}{}\n", self.items, input), self.items.len()),
+            ReplInput::Statements(_) => String::new(),
+            ReplInput::Expr(_) => format!("let {} = ", VALUE_VARIABLE),
+        };
+        let suffix = if prefix.is_empty() { "" } else { ";" };
+
+        let parameters: Vec<String> = self
+            .variables
+            .iter()
+            .map(|variable| format!("{}: {}", variable.name, variable.ty))
+            .collect();
+        let header = format!(
+            "{}fn {}({}) {{\n",
+            self.items,
+            INPUT_FUNCTION,
+            parameters.join(", ")
+        );
+        let offset = header.len() + prefix.len();
+        let source = format!("{}{}{}{}\n}}\n", header, prefix, input, suffix);
+        (source, offset)
+    }
+
+    fn errors(&mut self, offset: usize, length: usize) -> Vec<Spanned<String>> {
+        let mut errors: Vec<Spanned<String>> = self
+            .db
+            .parse(self.file)
+            .errors
+            .iter()
+            .map(|error| Spanned::new(error.value_ref().to_string(), error.span()))
+            .collect();
+        for error in self.db.module_errors(self.file) {
+            errors.push(Spanned::new(error.value_ref().to_string(), error.span()));
+        }
+        for function in self.db.item_tree(self.file).functions() {
+            if let Some(result) = self.db.type_of(&FunctionId::new(self.file, &function.name)) {
+                for error in &result.errors {
+                    errors.push(Spanned::new(error.value_ref().to_string(), error.span()));
+                }
+            }
+        }
+
+        errors
+            .into_iter()
+            .map(|error| {
+                let span = relative_span(error.span(), offset, length);
+                Spanned::new(error.value(), span)
+            })
+            .collect()
+    }
+
+    fn run(&mut self, offset: usize, length: usize, output: &mut dyn Write) -> ReplOutcome {
+        let arguments = self
+            .variables
+            .iter()
+            .map(|variable| variable.value.clone())
+            .collect();
+        let heap = std::mem::take(&mut self.heap);
+        let mut interpreter = Interpreter::new(&mut self.db, self.file, output).with_heap(heap);
+        let result = interpreter.call_keeping_variables(INPUT_FUNCTION, arguments);
+        self.heap = interpreter.into_heap();
+
+        let values = match result {
+            Ok(values) => values,
+            Err(err) => {
+                let span = relative_span(err.span(), offset, length);
+                return ReplOutcome::Errors(vec![Spanned::new(err.value().to_string(), span)]);
+            }
+        };
+
+        let id = FunctionId::new(self.file, INPUT_FUNCTION);
+        let (Some(function), Some(types)) = (self.db.lower_function(&id), self.db.type_of(&id))
+        else {
+            return ReplOutcome::Done;
+        };
+
+        let mut outcome = ReplOutcome::Done;
+        for variable in top_level_variables(&function) {
+            let Some((_, value)) = values.iter().find(|(id, _)| *id == variable) else {
+                continue;
+            };
+            let name = self
+                .db
+                .module_context(self.file)
+                .get_string(function.get_variable(variable).name)
+                .clone();
+            let ty = types.variable_types.get(variable).cloned();
+            let ty = ty.unwrap_or(HirType::Unknown);
+
+            if name == VALUE_VARIABLE {
+                if ty != HirType::Unit {
+                    outcome = ReplOutcome::Value {
+                        value: value.clone(),
+                        ty,
+                    };
+                }
+            } else {
+                self.variables.retain(|existing| existing.name != name);
+                self.variables.push(ReplVariable {
+                    name,
+                    ty,
+                    value: value.clone(),
+                });
+            }
+        }
+        outcome
+    }
+}
+
+impl Default for ReplSession {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parse an input, working out whether it is made up of items, statements or a single
+/// expression.
+pub fn parse_input(input: &str) -> Result<ReplInput, ReplOutcome> {
+    let path = Path::new(SESSION_PATH);
+    let mut diagnostics = Diagnostics::default();
+
+    let result = if starts_with_item(input) {
+        Parser::new(TokenStream::new(input), path, &mut diagnostics)
+            .parse()
+            .map(|unit| ReplInput::Items(unit.value()))
+    } else {
+        let mut expr_diagnostics = Diagnostics::default();
+        let expr = Parser::new(TokenStream::new(input), path, &mut expr_diagnostics)
+            .parse_standalone_expr();
+        match expr {
+            Ok(expr) if expr_diagnostics.errors.is_empty() => Ok(ReplInput::Expr(expr.value())),
+            _ => Parser::new(TokenStream::new(input), path, &mut diagnostics)
+                .parse_statements()
+                .map(|statements| ReplInput::Statements(statements.value())),
+        }
+    };
+
+    if diagnostics
+        .errors
+        .iter()
+        .any(|error| is_incomplete(input, error))
+    {
+        return Err(ReplOutcome::Incomplete);
+    }
+    if !diagnostics.errors.is_empty() {
+        let errors = diagnostics
+            .errors
+            .iter()
+            .map(|error| Spanned::new(error.value_ref().to_string(), error.span()))
+            .collect();
+        return Err(ReplOutcome::Errors(errors));
+    }
+
+    result
+        .map_err(|err| ReplOutcome::Errors(vec![Spanned::new(err.value().to_string(), err.span())]))
+}
+
+// Items are the only inputs that can start with these tokens.
+fn starts_with_item(input: &str) -> bool {
+    let mut stream = TokenStream::new(input);
+    while let Ok(token) = stream.current() {
+        match token.value() {
+            Token::InlineComment(_)
+            | Token::MultilineComment(_)
+            | Token::InlineDocComment(_)
+            | Token::MultilineDocComment(_)
+            | Token::InlineInnerDocComment(_)
+            | Token::MultilineInnerDocComment(_) => stream.advance(),
+            Token::Fn | Token::Struct | Token::Use | Token::Extern | Token::Pub | Token::Hash => {
+                return true;
+            }
+            _ => return false,
+        }
+    }
+    false
+}
+
+// Input is incomplete when the parser ran out of tokens before it was finished, which is
+// reported at the end of the input, or when a string or comment was left open.
+fn is_incomplete(input: &str, error: &Spanned<ParserError>) -> bool {
+    matches!(
+        error.value_ref(),
+        ParserError::UnclosedStringLit(_) | ParserError::UnterminatedBlockComment
+    ) || error.span().range().start >= input.len()
+}
+
+// Variables declared directly within a function's body, after its parameters. Those declared
+// in nested blocks go out of scope before the input finishes.
+fn top_level_variables(function: &HirFunctionData) -> Vec<HirVariableId> {
+    let mut variables = function.parameters.clone();
+    if let HirStatementKind::Block(statements) =
+        &function.get_statement(function.root_statement).kind
+    {
+        for statement in statements {
+            match &function.get_statement(*statement).kind {
+                HirStatementKind::VarDecl { variable, .. } => variables.push(*variable),
+                HirStatementKind::Destructure { pattern, .. } => {
+                    pattern_variables(pattern, &mut variables)
+                }
+                _ => {}
+            }
+        }
+    }
+    variables
+}
+
+fn pattern_variables(pattern: &HirPattern, variables: &mut Vec<HirVariableId>) {
+    match pattern {
+        HirPattern::Variable(variable) => variables.push(*variable),
+        HirPattern::Tuple { elements, .. } => {
+            for element in elements {
+                pattern_variables(element, variables);
+            }
+        }
+    }
+}
+
+// Move a span within the session's module so that it is relative to the input. Anything
+// outside of the input is reported against the whole input instead.
+fn relative_span(span: Span, offset: usize, length: usize) -> Span {
+    let range = span.range();
+    if range.start >= offset && range.end <= offset + length {
+        Span::new(range.start - offset, range.end - offset)
+    } else {
+        Span::new(0, length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn eval_all(inputs: &[&str]) -> (ReplOutcome, String) {
+        let mut session = ReplSession::new();
+        let mut output: Vec<u8> = Vec::new();
+        let mut outcome = ReplOutcome::Done;
+        for input in inputs {
+            outcome = session.eval(input, &mut output);
+        }
+        (outcome, String::from_utf8(output).unwrap())
+    }
+
+    fn value(value: Value, ty: HirType) -> ReplOutcome {
+        ReplOutcome::Value { value, ty }
+    }
+
+    #[test_case(&["1 + 2"],                                   value(Value::I32(3), HirType::I32) ; "expression")]
+    #[test_case(&["let x = 2;", "x * 21"],                   value(Value::I32(42), HirType::I32) ; "variables")]
+    #[test_case(&["let n: i32 = 1;", "n += 1;", "n"],          value(Value::I32(2), HirType::I32) ; "reassigned variables")]
+    #[test_case(&["let (a, b) = (1, true);", "b"],         value(Value::Bool(true), HirType::Bool) ; "destructured variables")]
+    #[test_case(&["let x = 1;", "let x = \"one\";", "x"], value(Value::String("one".into()), HirType::String) ; "shadowed variables")]
+    #[test_case(&["fn double(x: i32) -> i32 { return x * 2; }", "double(4)"], value(Value::I32(8), HirType::I32) ; "functions")]
+    #[test_case(&["struct P { x: i32; }", "let p = P(3);", "p.x"], value(Value::I32(3), HirType::I32) ; "structs")]
+    #[test_case(&["let a = std::array_new();", "std::array_push(a, 3);", "std::array_len(a)"], value(Value::USize(1), HirType::USize) ; "arrays")]
+    #[test_case(&["let x = 1;"],                                                     ReplOutcome::Done ; "statements")]
+    #[test_case(&["std::println(\"hi\")"],                                           ReplOutcome::Done ; "unit expression")]
+    fn inputs_build_on_each_other(inputs: &[&str], expected: ReplOutcome) {
+        // When
+        let (outcome, _) = eval_all(inputs);
+
+        // Then
+        assert_eq!(outcome, expected);
+    }
+
+    #[test_case("fn f() {"          ; "unclosed function")]
+    #[test_case("if (true) { x = 1;" ; "unclosed block")]
+    #[test_case("let s = \"abc"      ; "unclosed string")]
+    #[test_case("1 +"                ; "unfinished expression")]
+    #[test_case("/* comment"         ; "unclosed comment")]
+    fn unfinished_inputs_are_incomplete(input: &str) {
+        // When
+        let (outcome, _) = eval_all(&[input]);
+
+        // Then
+        assert_eq!(outcome, ReplOutcome::Incomplete);
+    }
+
+    #[test_case(           "let y: bool = 1;",        "1" ; "type error")]
+    #[test_case(                     "1 / 0",     "1 / 0" ; "runtime error")]
+    #[test_case(          "let x = ) + 1;",           ")" ; "syntax error")]
+    #[test_case("fn f() -> bool { return 1; }",       "1" ; "error in an item")]
+    fn errors_are_reported_within_the_input(input: &str, expected: &str) {
+        // When
+        let (outcome, _) = eval_all(&[input]);
+
+        // Then
+        let ReplOutcome::Errors(errors) = outcome else {
+            panic!("expected errors, but got {:?}", outcome);
+        };
+        assert_eq!(&input[errors[0].span().range()], expected);
+    }
+
+    #[test]
+    fn failed_inputs_are_not_kept() {
+        // Given
+        let mut session = ReplSession::new();
+        session.eval("let x = 1;", &mut Vec::new());
+
+        // When
+        session.eval("let y = 2; x = true;", &mut Vec::new());
+        session.eval("fn f() -> bool { return 1; }", &mut Vec::new());
+        let outcome = session.eval("fn f() {}", &mut Vec::new());
+
+        // Then
+        let names: Vec<&str> = session
+            .variables()
+            .iter()
+            .map(|variable| variable.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["x"]);
+        assert_eq!(outcome, ReplOutcome::Done);
+    }
+
+    #[test]
+    fn variables_in_nested_blocks_are_not_kept() {
+        // Given
+        let mut session = ReplSession::new();
+
+        // When
+        session.eval("let x = 1; { let y = 2; }", &mut Vec::new());
+
+        // Then
+        assert_eq!(session.variables().len(), 1);
+        assert_eq!(session.variables()[0].name, "x");
+    }
+
+    #[test]
+    fn output_is_written_as_inputs_run() {
+        // When
+        let (_, output) = eval_all(&["let x = 3;", "std::println(\"x = {}\", x);"]);
+
+        // Then
+        assert_eq!(output, "x = 3\n");
+    }
+
+    #[test]
+    fn stages_of_an_input_can_be_shown() {
+        // Given
+        let mut session = ReplSession::new();
+        session.eval("let x: i32 = 1;", &mut Vec::new());
+
+        // When
+        let tokens = session.tokens("x + 1");
+        let ast = session.ast("x + 1").unwrap();
+        let hir = session.hir("x + 1").unwrap();
+
+        // Then
+        assert_eq!(
+            tokens,
+            "0:1: Identifier(\"x\")\n2:3: Add\n4:5: IntLit(Untyped(1))"
+        );
+        assert!(ast.starts_with("Binary("));
+        assert_eq!(
+            hir,
+            concat!(
+                "fn __repl_input\n",
+                "block\n",
+                "  let __repl_value: i32\n",
+                "    Add: i32\n",
+                "      variable x: i32\n",
+                "      1: i32",
+            )
+        );
+    }
+}
@@ -0,0 +1,207 @@
+//! Discovery and execution of functions marked with `#[test]`.
+//!
+//! Each test is run in a fresh interpreter with its own output buffer, so tests cannot
+//! affect each other and their output can be shown alongside any failure.
+use crate::db::database::{Database, FileId};
+use crate::error::RuntimeError;
+use crate::interp::interpreter::Interpreter;
+use haikulang_parser::span::{Span, Spanned};
+
+/// A function marked with `#[test]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestCase {
+    pub file: FileId,
+    pub name: String,
+    pub span: Span,
+}
+
+/// How a test finished.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TestOutcome {
+    Passed,
+    /// An assertion within the test did not hold.
+    Failed(Spanned<RuntimeError>),
+    /// The test stopped because of any other runtime error.
+    Panicked(Spanned<RuntimeError>),
+}
+
+/// The outcome of a test, along with everything that it printed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestReport {
+    pub outcome: TestOutcome,
+    pub output: String,
+}
+
+/// Find the tests declared in a file, in the order that they are declared.
+pub fn discover_tests(db: &mut Database, file: FileId) -> Vec<TestCase> {
+    let module = db.module_context(file);
+    let mut tests: Vec<TestCase> = module
+        .functions()
+        .filter(|header| header.attributes.test)
+        .map(|header| TestCase {
+            file,
+            name: module.get_string(header.name).clone(),
+            span: header.span,
+        })
+        .collect();
+
+    tests.sort_by_key(|test| test.span.range().start);
+    tests
+}
+
+/// Run a single test, returning how it finished.
+pub fn run_test(db: &mut Database, test: &TestCase) -> TestReport {
+    let mut output: Vec<u8> = Vec::new();
+    let result =
+        Interpreter::new(db, test.file, &mut output).call(&test.name, Vec::new(), test.span);
+
+    let outcome = match result {
+        Ok(_) => TestOutcome::Passed,
+        Err(err) if matches!(err.value_ref(), RuntimeError::AssertionFailed(_)) => {
+            TestOutcome::Failed(err)
+        }
+        Err(err) => TestOutcome::Panicked(err),
+    };
+
+    TestReport {
+        outcome,
+        output: String::from_utf8_lossy(&output).into_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CompilerError;
+    use test_case::test_case;
+
+    fn run_all(source: &str) -> Vec<(String, TestOutcome)> {
+        let mut db = Database::new();
+        let file = db.add_file("test.hkl", source);
+        discover_tests(&mut db, file)
+            .iter()
+            .map(|test| (test.name.clone(), run_test(&mut db, test).outcome))
+            .collect()
+    }
+
+    fn failure(source: &str) -> RuntimeError {
+        match run_all(source).remove(0).1 {
+            TestOutcome::Failed(err) => err.value(),
+            other => panic!(
+                "expected the test to fail, but it finished with {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn tests_are_discovered_in_declaration_order() {
+        // Given
+        let source = "#[test] fn b() {} fn helper() {} #[test] fn a() {} #[inline] fn c() {}";
+
+        // When
+        let names: Vec<String> = run_all(source).into_iter().map(|(name, _)| name).collect();
+
+        // Then
+        assert_eq!(names, vec!["b", "a"]);
+    }
+
+    #[test]
+    fn tests_cannot_take_parameters() {
+        // Given
+        let mut db = Database::new();
+        let file = db.add_file("test.hkl", "#[test] fn t(x: i32) {}");
+
+        // When
+        let errors: Vec<CompilerError> =
+            db.module_errors(file).iter().map(Spanned::value).collect();
+
+        // Then
+        assert_eq!(
+            errors,
+            vec![CompilerError::InvalidAttribute {
+                name: "test".to_string(),
+                reason: "test functions cannot take parameters".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn outcomes_are_reported() {
+        // Given
+        let source = "#[test] fn passes() { assert(1 + 1 == 2); }\
+            #[test] fn fails() { assert(1 > 2); }\
+            #[test] fn panics() { let x = 1 / 0; }";
+
+        // When
+        let outcomes: Vec<(String, &str)> = run_all(source)
+            .into_iter()
+            .map(|(name, outcome)| {
+                let kind = match outcome {
+                    TestOutcome::Passed => "passed",
+                    TestOutcome::Failed(_) => "failed",
+                    TestOutcome::Panicked(_) => "panicked",
+                };
+                (name, kind)
+            })
+            .collect();
+
+        // Then
+        assert_eq!(
+            outcomes,
+            vec![
+                ("passes".to_string(), "passed"),
+                ("fails".to_string(), "failed"),
+                ("panics".to_string(), "panicked"),
+            ]
+        );
+    }
+
+    #[test_case("#[test] fn t() { let x = 3; assert(x  <  2); }",                        "x  <  2" ; "assert")]
+    #[test_case("#[test] fn t() { let x = 3; assert_eq(x * 2, 7); }",    "x * 2 == 7 (left: 6, right: 7)" ; "assert_eq")]
+    #[test_case("#[test] fn t() { assert_eq((1, true), (1, false)); }", "(1, true) == (1, false) (left: (1, true), right: (1, false))" ; "assert_eq with tuples")]
+    fn failed_assertions_report_the_expression(source: &str, expected: &str) {
+        // Then
+        assert_eq!(
+            failure(source),
+            RuntimeError::AssertionFailed(expected.to_string())
+        );
+    }
+
+    #[test]
+    fn failed_assertions_point_at_the_assertion() {
+        // Given
+        let source = "#[test] fn t() { assert(false); }";
+        let mut db = Database::new();
+        let file = db.add_file("test.hkl", source);
+        let test = discover_tests(&mut db, file).remove(0);
+
+        // When
+        let report = run_test(&mut db, &test);
+
+        // Then
+        let TestOutcome::Failed(err) = report.outcome else {
+            panic!("expected the test to fail");
+        };
+        assert_eq!(&source[err.span().range()], "assert(false)");
+    }
+
+    #[test]
+    fn output_is_captured_for_each_test() {
+        // Given
+        let source = "extern fn println(fmt: string, value: i32);\
+            #[test] fn one() { println(\"one {}\", 1); }\
+            #[test] fn two() { println(\"two {}\", 2); }";
+        let mut db = Database::new();
+        let file = db.add_file("test.hkl", source);
+
+        // When
+        let outputs: Vec<String> = discover_tests(&mut db, file)
+            .iter()
+            .map(|test| run_test(&mut db, test).output)
+            .collect();
+
+        // Then
+        assert_eq!(outputs, vec!["one 1\n", "two 2\n"]);
+    }
+}
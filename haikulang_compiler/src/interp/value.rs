@@ -0,0 +1,224 @@
+use crate::db::database::FileId;
+use crate::hir::nodes::{HirExprId, HirFunctionData, HirString, HirVariableId};
+use crate::hir::ty::HirType;
+use crate::hir::typeck::HirTypeckResult;
+use crate::interp::heap::HeapObject;
+use num_bigint::BigInt;
+use std::fmt::{Debug, Display, Formatter};
+use std::rc::Rc;
+
+/// A value produced while interpreting a program.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Unit,
+    Bool(bool),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    I128(i128),
+    ISize(isize),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    USize(usize),
+    F32(f32),
+    F64(f64),
+    // Strings are immutable UTF-8 text, so copies of a string can share the same buffer.
+    String(Rc<str>),
+    Char(char),
+    Bytes(Rc<[u8]>),
+    BigInt(Rc<BigInt>),
+    Handle(Rc<HeapObject>),
+    Struct(Box<StructValue>),
+    // Always has at least one element, as the empty tuple is the unit value.
+    Tuple(Box<[Value]>),
+    Function(HirString),
+    Closure(Rc<ClosureValue>),
+    // Closures that were compiled to bytecode can only be called by the virtual machine.
+    CompiledClosure(Rc<CompiledClosure>),
+}
+
+impl Value {
+    /// Make a tuple value from its elements.
+    pub fn tuple(elements: Vec<Value>) -> Self {
+        if elements.is_empty() {
+            Self::Unit
+        } else {
+            Self::Tuple(elements.into_boxed_slice())
+        }
+    }
+
+    /// Convert an integer to a value of the given integer type, if it is within range.
+    pub fn from_integer(value: &BigInt, ty: &HirType) -> Option<Self> {
+        match ty {
+            HirType::I8 => i8::try_from(value).ok().map(Self::I8),
+            HirType::I16 => i16::try_from(value).ok().map(Self::I16),
+            HirType::I32 => i32::try_from(value).ok().map(Self::I32),
+            HirType::I64 => i64::try_from(value).ok().map(Self::I64),
+            HirType::I128 => i128::try_from(value).ok().map(Self::I128),
+            HirType::ISize => isize::try_from(value).ok().map(Self::ISize),
+            HirType::U8 => u8::try_from(value).ok().map(Self::U8),
+            HirType::U16 => u16::try_from(value).ok().map(Self::U16),
+            HirType::U32 => u32::try_from(value).ok().map(Self::U32),
+            HirType::U64 => u64::try_from(value).ok().map(Self::U64),
+            HirType::U128 => u128::try_from(value).ok().map(Self::U128),
+            HirType::USize => usize::try_from(value).ok().map(Self::USize),
+            HirType::BigInt => Some(Self::BigInt(Rc::new(value.clone()))),
+            _ => None,
+        }
+    }
+}
+
+/// An instance of a struct. Members are kept in declaration order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StructValue {
+    pub name: HirString,
+    pub members: Vec<(HirString, Value)>,
+}
+
+impl StructValue {
+    pub fn member(&self, name: &str) -> Option<&Value> {
+        self.members
+            .iter()
+            .find(|(member_name, _)| member_name == name)
+            .map(|(_, value)| value)
+    }
+
+    pub fn member_mut(&mut self, name: &str) -> Option<&mut Value> {
+        self.members
+            .iter_mut()
+            .find(|(member_name, _)| member_name == name)
+            .map(|(_, value)| value)
+    }
+}
+
+/// A closure, along with the function that declared it and the values that it captured.
+pub struct ClosureValue {
+    pub file: FileId,
+    pub function: Rc<HirFunctionData>,
+    pub types: Rc<HirTypeckResult>,
+    pub closure: HirExprId,
+    pub environment: Vec<(HirVariableId, Value)>,
+}
+
+// Closures are only equal to themselves, since there is no way to compare their bodies.
+impl PartialEq for ClosureValue {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Debug for ClosureValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClosureValue")
+            .field("closure", &self.closure)
+            .field("environment", &self.environment)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A closure that was compiled to bytecode, along with the values that it captured.
+#[derive(Debug)]
+pub struct CompiledClosure {
+    pub function: u32,
+    pub captures: Vec<Value>,
+}
+
+impl PartialEq for CompiledClosure {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unit => write!(f, "()"),
+            Self::Bool(value) => write!(f, "{}", value),
+            Self::I8(value) => write!(f, "{}", value),
+            Self::I16(value) => write!(f, "{}", value),
+            Self::I32(value) => write!(f, "{}", value),
+            Self::I64(value) => write!(f, "{}", value),
+            Self::I128(value) => write!(f, "{}", value),
+            Self::ISize(value) => write!(f, "{}", value),
+            Self::U8(value) => write!(f, "{}", value),
+            Self::U16(value) => write!(f, "{}", value),
+            Self::U32(value) => write!(f, "{}", value),
+            Self::U64(value) => write!(f, "{}", value),
+            Self::U128(value) => write!(f, "{}", value),
+            Self::USize(value) => write!(f, "{}", value),
+            Self::F32(value) => write!(f, "{}", value),
+            Self::F64(value) => write!(f, "{}", value),
+            Self::String(value) => write!(f, "{}", value),
+            Self::Char(value) => write!(f, "{}", value),
+            Self::Bytes(value) => write!(f, "{}", value.escape_ascii()),
+            Self::BigInt(value) => write!(f, "{}", value),
+            Self::Handle(object) => write!(f, "<{}>", object.kind()),
+            Self::Struct(value) => {
+                write!(f, "{} {{ ", value.name)?;
+                for (index, (name, member)) in value.members.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", name, member)?;
+                }
+                write!(f, " }}")
+            }
+            Self::Tuple(elements) => {
+                write!(f, "(")?;
+                for (index, element) in elements.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                if elements.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            }
+            Self::Function(name) => write!(f, "fn {}", name),
+            Self::Closure(_) | Self::CompiledClosure(_) => write!(f, "closure"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(                  Value::Unit,    "()" ; "unit value")]
+    #[test_case(            Value::Bool(true),  "true" ; "bool value")]
+    #[test_case(             Value::I32(-123),  "-123" ; "i32 value")]
+    #[test_case(              Value::F64(1.5),   "1.5" ; "f64 value")]
+    #[test_case(Value::String(Rc::from("hi")),    "hi" ; "string value")]
+    #[test_case(               Value::Char('☺'),     "☺" ; "char value")]
+    #[test_case(Value::Bytes(Rc::from(*b"a\n\xff")), "a\\n\\xff" ; "bytes value")]
+    #[test_case(Value::BigInt(Rc::new(BigInt::from(-7) << 70)), "-8264141345021879123968" ; "big integer value")]
+    #[test_case(Value::Function("foo".into()), "fn foo" ; "function value")]
+    #[test_case(Value::Tuple(Box::new([Value::I32(1), Value::Bool(false)])), "(1, false)" ; "tuple value")]
+    #[test_case(                   Value::Tuple(Box::new([Value::Char('a')])),       "(a,)" ; "tuple value with one element")]
+    fn values_format_correctly(value: Value, expected: &str) {
+        // Then
+        assert_eq!(format!("{}", value), expected);
+    }
+
+    #[test]
+    fn struct_values_format_correctly() {
+        // Given
+        let value = Value::Struct(Box::new(StructValue {
+            name: "Point".to_string(),
+            members: vec![
+                ("x".to_string(), Value::I32(1)),
+                ("y".to_string(), Value::I32(2)),
+            ],
+        }));
+
+        // Then
+        assert_eq!(format!("{}", value), "Point { x: 1, y: 2 }");
+    }
+}
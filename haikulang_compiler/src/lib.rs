@@ -1 +1,8 @@
+pub mod bytecode;
+pub mod codegen;
+pub mod db;
+pub mod doc;
+pub mod error;
 pub mod hir;
+pub mod interp;
+pub mod stdlib;
@@ -0,0 +1,214 @@
+//! The standard library, which is bundled into the compiler so that it is available without
+//! installing anything else.
+use crate::db::database::{Database, FileId};
+use std::path::Path;
+
+/// The name that programs use to refer to the standard library.
+pub const STD_MODULE: &str = "std";
+
+const STD_SOURCE: &str = include_str!("../std/std.hkl");
+
+/// Register the standard library with the database, so that programs can use it.
+pub fn add_std(db: &mut Database) -> FileId {
+    // The path is only used when reporting errors, as the source is embedded in the binary.
+    let path = Path::new("<bundled>").join(format!("{}.hkl", STD_MODULE));
+    db.add_file(path, STD_SOURCE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::database::FunctionId;
+    use crate::error::RuntimeError;
+    use crate::interp::interpreter::Interpreter;
+    use crate::interp::testing::{TestOutcome, discover_tests, run_test};
+    use crate::interp::value::Value;
+    use haikulang_parser::span::Span;
+    use std::rc::Rc;
+    use test_case::test_case;
+
+    fn run(source: &str) -> (Result<Value, RuntimeError>, String) {
+        let mut db = Database::new();
+        add_std(&mut db);
+        let file = db.add_file("main.hkl", source);
+        let mut output: Vec<u8> = Vec::new();
+        let result = Interpreter::new(&mut db, file, &mut output)
+            .call("main", vec![], Span::UNSET)
+            .map_err(|err| err.value());
+        (result, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn std_has_no_errors() {
+        // Given
+        let mut db = Database::new();
+        let file = add_std(&mut db);
+
+        // When
+        let mut errors: Vec<String> = db
+            .module_errors(file)
+            .iter()
+            .map(|error| error.value().to_string())
+            .collect();
+        for function in db.item_tree(file).functions() {
+            let result = db.type_of(&FunctionId::new(file, &function.name)).unwrap();
+            errors.extend(result.errors.iter().map(|error| error.value().to_string()));
+        }
+
+        // Then
+        assert_eq!(db.parse(file).errors, vec![]);
+        assert_eq!(errors, Vec::<String>::new());
+    }
+
+    #[test]
+    fn std_tests_pass() {
+        // Given
+        let mut db = Database::new();
+        let file = add_std(&mut db);
+
+        // When
+        let failures: Vec<(String, TestOutcome)> = discover_tests(&mut db, file)
+            .iter()
+            .map(|test| (test.name.clone(), run_test(&mut db, test).outcome))
+            .filter(|(_, outcome)| *outcome != TestOutcome::Passed)
+            .collect();
+
+        // Then
+        assert_eq!(failures, vec![]);
+    }
+
+    #[test]
+    fn programs_can_use_std() {
+        // When
+        let (result, output) = run("
+            use std;
+
+            fn main() -> i64 {
+                let squares = std::array_new();
+                let i: i64 = 0;
+                while (i < 4) {
+                    std::array_push(squares, i * i);
+                    i += 1;
+                }
+                std::println(\"last: {}\", std::array_get(squares, 3));
+                return std::max(std::array_pop(squares), 100);
+            }
+        ");
+
+        // Then
+        assert_eq!(result, Ok(Value::I64(100)));
+        assert_eq!(output, "last: 9\n");
+    }
+
+    #[test]
+    fn programs_can_use_big_integers() {
+        // When
+        let (result, output) = run("
+            use std;
+
+            struct Factorial {
+                count: i64;
+                value: std::BigInt;
+            }
+
+            fn factorial(n: i64) -> Factorial {
+                let value = std::bigint(1);
+                let count: i64 = 1;
+                while (count < n) {
+                    count += 1;
+                    value = value * std::bigint(count);
+                }
+                return Factorial(count, value);
+            }
+
+            fn main() -> std::BigInt {
+                let result = factorial(30);
+                std::println(\"{}\", result.value);
+                return result.value / 1000000;
+            }
+        ");
+
+        // Then
+        assert_eq!(
+            result,
+            Ok(Value::BigInt(Rc::new(
+                "265252859812191058636308480".parse().unwrap()
+            )))
+        );
+        assert_eq!(output, "265252859812191058636308480000000\n");
+    }
+
+    #[test_case("fn main() { std::exit(3); }", RuntimeError::Exit(3) ; "exit")]
+    #[test_case(
+        "fn main() { let a = std::array_new(); std::array_get(a, 0); }",
+        RuntimeError::IndexOutOfBounds { index: "0".to_string(), length: 0 }
+        ; "array index out of bounds"
+    )]
+    #[test_case(
+        "fn main() { std::map_get(std::map_new(), \"k\"); }",
+        RuntimeError::MissingKey("k".to_string())
+        ; "missing map key"
+    )]
+    #[test_case(
+        "fn main() { std::char_at(\"héllo\", 5); }",
+        RuntimeError::IndexOutOfBounds { index: "5".to_string(), length: 5 }
+        ; "character index out of bounds"
+    )]
+    #[test_case(
+        "fn main() { std::substring(\"héllo\", 0, 2); }",
+        RuntimeError::IndexOutOfBounds { index: "0..2".to_string(), length: 6 }
+        ; "slice within a character"
+    )]
+    fn std_reports_runtime_errors(source: &str, expected: RuntimeError) {
+        // When
+        let (result, _) = run(source);
+
+        // Then
+        assert_eq!(result, Err(expected));
+    }
+
+    #[test]
+    fn std_functions_are_type_checked() {
+        // Given
+        let mut db = Database::new();
+        add_std(&mut db);
+        let file = db.add_file(
+            "main.hkl",
+            "fn main() { let n: string = std::len(\"abc\"); let a: std::Array = std::map_new(); }",
+        );
+
+        // When
+        let result = db.type_of(&FunctionId::new(file, "main")).unwrap();
+
+        // Then
+        let errors: Vec<String> = result
+            .errors
+            .iter()
+            .map(|error| error.value().to_string())
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                "mismatched types: expected string, found usize",
+                "mismatched types: expected std::Array, found std::Map",
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_within_std_are_reported_at_the_call() {
+        // Given
+        let source = "fn main() { let a = std::array_new(); std::array_pop(a); }";
+        let mut db = Database::new();
+        add_std(&mut db);
+        let file = db.add_file("main.hkl", source);
+
+        // When
+        let result =
+            Interpreter::new(&mut db, file, &mut Vec::new()).call("main", vec![], Span::UNSET);
+
+        // Then
+        let err = result.unwrap_err();
+        assert_eq!(&source[err.span().range()], "std::array_pop(a)");
+    }
+}
@@ -0,0 +1,48 @@
+//! Parses arbitrary input. The parser must never panic, the lossless syntax tree must
+//! reproduce the input exactly, and anything that parses successfully must print and reparse
+//! to the same tree.
+#![no_main]
+
+use haikulang_parser::ast::printer::print_compilation_unit;
+use haikulang_parser::cst::parse_lossless;
+use haikulang_parser::error::ParserError;
+use haikulang_parser::lexer::token_stream::TokenStream;
+use haikulang_parser::parser::core::Parser;
+use haikulang_parser::span::Spanned;
+use libfuzzer_sys::fuzz_target;
+use std::path::Path;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(source) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    let path = Path::new("fuzz.hkl");
+    let mut errors: Vec<Spanned<ParserError>> = Vec::new();
+    let result = Parser::new(TokenStream::new(source), path, &mut errors).parse();
+
+    for error in &errors {
+        assert!(
+            error.span().end() <= source.len(),
+            "error at {} is out of bounds",
+            error.span()
+        );
+    }
+
+    let tree = parse_lossless(source, path, &mut Vec::new());
+    assert_eq!(tree.text(), source);
+
+    if let Ok(unit) = result {
+        let printed = print_compilation_unit(unit.value_ref());
+        let reparsed = Parser::new(TokenStream::new(&printed), path, &mut Vec::new())
+            .parse()
+            .unwrap_or_else(|err| {
+                panic!(
+                    "printed program failed to parse: {}\n{}",
+                    err.value(),
+                    printed
+                )
+            });
+        assert_eq!(print_compilation_unit(reparsed.value_ref()), printed);
+    }
+});
@@ -0,0 +1,36 @@
+//! Lexes arbitrary input, checking that every token lies within the input, falls on character
+//! boundaries, and appears in order without overlapping the previous token.
+#![no_main]
+
+use haikulang_parser::lexer::token::Token;
+use haikulang_parser::lexer::token_stream::TokenStream;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(source) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    let mut stream = TokenStream::new(source);
+    let mut previous_end = 0;
+
+    loop {
+        let span = match stream.current() {
+            Ok(token) if token.value() == Token::Eof => break,
+            Ok(token) => token.span(),
+            Err(err) => err.span(),
+        };
+
+        assert!(span.start() >= previous_end, "token at {} overlaps", span);
+        assert!(span.start() < span.end(), "token at {} is empty", span);
+        assert!(
+            span.end() <= source.len(),
+            "token at {} is out of bounds",
+            span
+        );
+        assert!(source.is_char_boundary(span.start()) && source.is_char_boundary(span.end()));
+
+        previous_end = span.end();
+        stream.advance();
+    }
+});
@@ -0,0 +1,14 @@
+use crate::ast::expr::Expr;
+use crate::ast::ident::Identifier;
+use crate::span::Spanned;
+
+/// An attribute attached to a declaration, written as `#[name]` or `#[name(arguments)]`.
+///
+/// The parser accepts any name and arguments. It is up to the compiler to decide which
+/// attributes it understands.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attribute {
+    pub name: Spanned<Identifier>,
+    // None if the attribute has no parentheses at all, which is distinct from an empty list.
+    pub arguments: Option<Spanned<Box<[Spanned<Expr>]>>>,
+}
@@ -0,0 +1,80 @@
+/// Documentation attached to a declaration, written using `///` or `/** */` comments, or to
+/// a compilation unit, written using `//!` or `/*! */` comments.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DocComment {
+    pub text: String,
+}
+
+impl DocComment {
+    // Produce the documentation text for the content of a "///" or "//!" comment.
+    pub(crate) fn inline_doc_line(content: &str) -> String {
+        let line = content.trim_end_matches(['\r', '\n']);
+        line.strip_prefix(' ').unwrap_or(line).to_string()
+    }
+
+    // Produce the documentation text for the content of a "/** */" or "/*! */" comment,
+    // removing the leading asterisks that are conventionally placed at the start of each line.
+    pub(crate) fn multiline_doc_text(content: &str) -> String {
+        let lines: Vec<&str> = content
+            .lines()
+            .map(|line| {
+                let line = line.trim();
+                let line = line.strip_prefix('*').unwrap_or(line);
+                line.strip_prefix(' ').unwrap_or(line)
+            })
+            .collect();
+
+        let first = lines.iter().position(|line| !line.is_empty());
+        let last = lines.iter().rposition(|line| !line.is_empty());
+
+        match (first, last) {
+            (Some(first), Some(last)) => lines[first..=last].join("\n"),
+            _ => String::new(),
+        }
+    }
+
+    /// The first paragraph of the documentation, useful for summaries.
+    pub fn summary(&self) -> String {
+        self.text
+            .split("\n\n")
+            .next()
+            .unwrap_or_default()
+            .replace('\n', " ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(     " foo bar\n",  "foo bar" ; "line with newline")]
+    #[test_case(   " foo bar\r\n",  "foo bar" ; "line with carriage return")]
+    #[test_case(       "foo bar",   "foo bar" ; "line without space")]
+    #[test_case(    "   indented", "  indented" ; "line with indentation")]
+    fn inline_doc_lines_are_normalized(content: &str, expected: &str) {
+        // Then
+        assert_eq!(DocComment::inline_doc_line(content), expected);
+    }
+
+    #[test_case(                        " foo ",          "foo" ; "single line")]
+    #[test_case(    "\n * foo\n *\n * bar\n ", "foo\n\nbar" ; "leading asterisks")]
+    #[test_case(          "\n   foo\n   bar\n", "foo\nbar" ; "no leading asterisks")]
+    #[test_case(                        "\n\n",            "" ; "blank")]
+    fn multiline_doc_text_is_normalized(content: &str, expected: &str) {
+        // Then
+        assert_eq!(DocComment::multiline_doc_text(content), expected);
+    }
+
+    #[test_case(            "foo\nbar",     "foo bar" ; "single paragraph")]
+    #[test_case( "foo\nbar\n\nbaz bork",    "foo bar" ; "multiple paragraphs")]
+    fn summary_is_the_first_paragraph(text: &str, expected: &str) {
+        // Given
+        let doc = DocComment {
+            text: text.to_string(),
+        };
+
+        // Then
+        assert_eq!(doc.summary(), expected);
+    }
+}
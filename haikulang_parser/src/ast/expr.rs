@@ -1,5 +1,7 @@
 use crate::ast::ident::{Identifier, IdentifierPath};
-use crate::lexer::literals::{FloatLit, IntLit, StrLit};
+use crate::ast::stmt::Statement;
+use crate::ast::types::TypeName;
+use crate::lexer::literals::{ByteStrLit, FloatLit, IntLit, StrLit};
 use crate::span::Spanned;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -10,10 +12,15 @@ pub enum Expr {
     MemberAccess(Box<MemberAccessExpr>),
     Index(Box<IndexExpr>),
     FunctionCall(Box<FunctionCallExpr>),
+    Closure(Box<ClosureExpr>),
+    Tuple(Box<TupleExpr>),
     Float(Box<FloatLitExpr>),
     Int(Box<IntLitExpr>),
     Bool(Box<BoolLitExpr>),
     String(Box<StrLitExpr>),
+    Char(Box<CharLitExpr>),
+    Byte(Box<ByteLitExpr>),
+    ByteString(Box<ByteStrLitExpr>),
     IdentifierPath(Box<IdentifierPath>),
 }
 
@@ -86,6 +93,25 @@ pub struct FunctionCallExpr {
     pub arguments: Spanned<Box<[Spanned<Expr>]>>,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClosureExpr {
+    pub parameters: Spanned<Box<[Spanned<ClosureParameter>]>>,
+    pub return_type: Option<Spanned<TypeName>>,
+    // Either a block, or an expression statement for closures with an expression body.
+    pub body: Spanned<Statement>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClosureParameter {
+    pub name: Spanned<Identifier>,
+    pub type_name: Option<Spanned<TypeName>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TupleExpr {
+    pub elements: Box<[Spanned<Expr>]>,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct FloatLitExpr {
     pub value: FloatLit,
@@ -106,6 +132,21 @@ pub struct StrLitExpr {
     pub value: StrLit,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct CharLitExpr {
+    pub value: char,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ByteLitExpr {
+    pub value: u8,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ByteStrLitExpr {
+    pub value: ByteStrLit,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,24 +1,35 @@
-use crate::ast::ident::{Identifier, IdentifierPath};
+use crate::ast::attr::Attribute;
+use crate::ast::doc::DocComment;
+use crate::ast::ident::Identifier;
 use crate::ast::stmt::Statement;
+use crate::ast::types::TypeName;
+use crate::ast::visibility::Visibility;
 use crate::span::Spanned;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct ExternFunctionDecl {
+    pub doc: Option<Spanned<DocComment>>,
+    pub attributes: Box<[Spanned<Attribute>]>,
+    pub visibility: Visibility,
     pub name: Spanned<Identifier>,
     pub parameters: Spanned<Box<[Spanned<ParameterDecl>]>>,
-    pub return_type: Option<Spanned<IdentifierPath>>,
+    pub return_type: Option<Spanned<TypeName>>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct FunctionDecl {
+    pub doc: Option<Spanned<DocComment>>,
+    pub attributes: Box<[Spanned<Attribute>]>,
+    pub visibility: Visibility,
     pub name: Spanned<Identifier>,
     pub parameters: Spanned<Box<[Spanned<ParameterDecl>]>>,
-    pub return_type: Option<Spanned<IdentifierPath>>,
+    pub return_type: Option<Spanned<TypeName>>,
     pub body: Spanned<Statement>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct ParameterDecl {
+    pub doc: Option<Spanned<DocComment>>,
     pub name: Spanned<Identifier>,
-    pub type_name: Spanned<IdentifierPath>,
+    pub type_name: Spanned<TypeName>,
 }
@@ -1,6 +1,11 @@
+pub mod attr;
+pub mod doc;
 pub mod expr;
 pub mod func;
 pub mod ident;
+pub mod printer;
 pub mod stmt;
 pub mod structs;
+pub mod types;
 pub mod unit;
+pub mod visibility;
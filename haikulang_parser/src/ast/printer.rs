@@ -0,0 +1,743 @@
+//! Printing of the AST back into source code.
+//!
+//! The output is in a canonical form: comments other than documentation are not kept, and
+//! parentheses are only used where the precedence or associativity of operators requires
+//! them. Printing and then parsing an AST gives back the same AST.
+use crate::ast::attr::Attribute;
+use crate::ast::doc::DocComment;
+use crate::ast::expr::{BinaryOp, Expr, UnaryOp};
+use crate::ast::func::{ExternFunctionDecl, FunctionDecl, ParameterDecl};
+use crate::ast::ident::IdentifierPath;
+use crate::ast::stmt::{Pattern, Statement};
+use crate::ast::structs::StructDecl;
+use crate::ast::types::TypeName;
+use crate::ast::unit::{CompilationUnit, CompilationUnitMember};
+use crate::ast::visibility::Visibility;
+use crate::lexer::literals::{FloatLit, IntLit};
+use crate::span::Spanned;
+use std::fmt::Write;
+
+const INDENT: &str = "    ";
+
+pub fn print_compilation_unit(unit: &CompilationUnit) -> String {
+    let mut printer = Printer::default();
+    if let Some(doc) = &unit.doc {
+        printer.doc_lines("//! ", doc.value_ref());
+        if !unit.members.is_empty() {
+            printer.output.push('\n');
+        }
+    }
+    for (index, member) in unit.members.iter().enumerate() {
+        if index > 0 {
+            printer.output.push('\n');
+        }
+        printer.member(member.value_ref());
+    }
+    printer.output
+}
+
+pub fn print_statement(statement: &Statement) -> String {
+    let mut printer = Printer::default();
+    printer.statement(statement);
+    printer.output
+}
+
+pub fn print_expr(expr: &Expr) -> String {
+    let mut printer = Printer::default();
+    printer.expr(expr);
+    printer.output
+}
+
+pub fn print_type_name(type_name: &TypeName) -> String {
+    let mut printer = Printer::default();
+    printer.type_name(type_name);
+    printer.output
+}
+
+pub fn print_attribute(attribute: &Attribute) -> String {
+    let mut printer = Printer::default();
+    printer.attribute(attribute);
+    printer.output
+}
+
+#[derive(Default)]
+struct Printer {
+    output: String,
+    depth: usize,
+}
+
+impl Printer {
+    /*
+     * Declarations
+     */
+
+    fn member(&mut self, member: &CompilationUnitMember) {
+        match member {
+            CompilationUnitMember::Use(use_decl) => {
+                self.output.push_str("use ");
+                self.path(use_decl.path.value_ref());
+                self.output.push_str(";\n");
+            }
+            CompilationUnitMember::ExternFunction(function) => self.extern_function(function),
+            CompilationUnitMember::Function(function) => self.function(function),
+            CompilationUnitMember::Struct(struct_decl) => self.struct_decl(struct_decl),
+        }
+    }
+
+    fn extern_function(&mut self, function: &ExternFunctionDecl) {
+        self.doc(&function.doc);
+        self.attributes(&function.attributes);
+        self.visibility(function.visibility);
+        self.output.push_str("extern fn ");
+        self.output.push_str(&function.name.value_ref().value);
+        self.parameters(function.parameters.value_ref());
+        self.return_type(&function.return_type);
+        self.output.push_str(";\n");
+    }
+
+    fn function(&mut self, function: &FunctionDecl) {
+        self.doc(&function.doc);
+        self.attributes(&function.attributes);
+        self.visibility(function.visibility);
+        self.output.push_str("fn ");
+        self.output.push_str(&function.name.value_ref().value);
+        self.parameters(function.parameters.value_ref());
+
+        // Expression functions have an expression statement as their body rather than a block.
+        if let Statement::Expr(expr) = function.body.value_ref() {
+            self.output.push_str(" = ");
+            self.expr(expr);
+            self.output.push_str(";\n");
+            return;
+        }
+
+        self.return_type(&function.return_type);
+        self.output.push(' ');
+        self.statement(function.body.value_ref());
+        self.output.push('\n');
+    }
+
+    fn parameters(&mut self, parameters: &[Spanned<ParameterDecl>]) {
+        // Documented parameters are placed on their own lines, since the documentation
+        // comments run to the end of the line.
+        let multiline = parameters
+            .iter()
+            .any(|parameter| parameter.value_ref().doc.is_some());
+
+        self.output.push('(');
+        self.depth += 1;
+        for (index, parameter) in parameters.iter().enumerate() {
+            let parameter = parameter.value_ref();
+            if index > 0 {
+                self.output.push(',');
+                if !multiline {
+                    self.output.push(' ');
+                }
+            }
+            if multiline {
+                self.output.push('\n');
+                self.doc(&parameter.doc);
+                self.indent();
+            }
+            self.output.push_str(&parameter.name.value_ref().value);
+            self.output.push_str(": ");
+            self.type_name(parameter.type_name.value_ref());
+        }
+        self.depth -= 1;
+        if multiline {
+            self.output.push('\n');
+            self.indent();
+        }
+        self.output.push(')');
+    }
+
+    fn return_type(&mut self, return_type: &Option<Spanned<TypeName>>) {
+        if let Some(return_type) = return_type {
+            self.output.push_str(" -> ");
+            self.type_name(return_type.value_ref());
+        }
+    }
+
+    fn struct_decl(&mut self, struct_decl: &StructDecl) {
+        self.doc(&struct_decl.doc);
+        self.attributes(&struct_decl.attributes);
+        self.visibility(struct_decl.visibility);
+        self.output.push_str("struct ");
+        self.output
+            .push_str(&struct_decl.identifier.value_ref().value);
+
+        if struct_decl.members.is_empty() {
+            self.output.push_str(" {}\n");
+            return;
+        }
+
+        self.output.push_str(" {\n");
+        self.depth += 1;
+        for member in struct_decl.members.iter() {
+            let member = member.value_ref();
+            self.doc(&member.doc);
+            self.attributes(&member.attributes);
+            self.indent();
+            self.visibility(member.visibility);
+            self.output.push_str(&member.identifier.value_ref().value);
+            self.output.push_str(": ");
+            self.type_name(member.type_name.value_ref());
+            self.output.push_str(";\n");
+        }
+        self.depth -= 1;
+        self.output.push_str("}\n");
+    }
+
+    // Each attribute is placed on its own line before the declaration.
+    fn attributes(&mut self, attributes: &[Spanned<Attribute>]) {
+        for attribute in attributes {
+            self.indent();
+            self.attribute(attribute.value_ref());
+            self.output.push('\n');
+        }
+    }
+
+    fn attribute(&mut self, attribute: &Attribute) {
+        self.output.push_str("#[");
+        self.output.push_str(&attribute.name.value_ref().value);
+        if let Some(arguments) = &attribute.arguments {
+            self.output.push('(');
+            for (index, argument) in arguments.value_ref().iter().enumerate() {
+                if index > 0 {
+                    self.output.push_str(", ");
+                }
+                self.expr(argument.value_ref());
+            }
+            self.output.push(')');
+        }
+        self.output.push(']');
+    }
+
+    fn visibility(&mut self, visibility: Visibility) {
+        if visibility.is_public() {
+            self.output.push_str("pub ");
+        }
+    }
+
+    fn doc(&mut self, doc: &Option<Spanned<DocComment>>) {
+        if let Some(doc) = doc {
+            self.doc_lines("/// ", doc.value_ref());
+        }
+    }
+
+    fn doc_lines(&mut self, prefix: &str, doc: &DocComment) {
+        // Inline comments end at a carriage return as well as a line feed.
+        for line in doc.text.split(['\r', '\n']) {
+            self.indent();
+            self.output.push_str(prefix);
+            self.output.push_str(line);
+            self.output.push('\n');
+        }
+    }
+
+    fn path(&mut self, path: &IdentifierPath) {
+        for qualifier in path.qualifier.iter() {
+            self.output.push_str(&qualifier.value_ref().value);
+            self.output.push_str("::");
+        }
+        self.output.push_str(&path.local_name.value_ref().value);
+    }
+
+    fn type_name(&mut self, type_name: &TypeName) {
+        match type_name {
+            TypeName::Path(path) => self.path(path),
+            TypeName::Function(function) => {
+                self.output.push_str("fn(");
+                for (index, parameter) in function.parameters.value_ref().iter().enumerate() {
+                    if index > 0 {
+                        self.output.push_str(", ");
+                    }
+                    self.type_name(parameter.value_ref());
+                }
+                self.output.push(')');
+                self.return_type(&function.return_type);
+            }
+            TypeName::Tuple(tuple) => self.tuple(&tuple.elements, Self::type_name),
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Identifier(identifier) => self.output.push_str(&identifier.value),
+            Pattern::Tuple(elements) => self.tuple(elements, Self::pattern),
+        }
+    }
+
+    // Tuples of one element need a trailing comma, or they would be read back as something
+    // within parentheses.
+    fn tuple<T: Clone>(&mut self, elements: &[Spanned<T>], mut element: impl FnMut(&mut Self, &T)) {
+        self.output.push('(');
+        for (index, value) in elements.iter().enumerate() {
+            if index > 0 {
+                self.output.push_str(", ");
+            }
+            element(self, value.value_ref());
+        }
+        if elements.len() == 1 {
+            self.output.push(',');
+        }
+        self.output.push(')');
+    }
+
+    /*
+     * Statements
+     */
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Empty => self.output.push(';'),
+            Statement::Expr(expr) => {
+                self.expr(expr);
+                self.output.push(';');
+            }
+            Statement::VarDecl(var_decl) => {
+                self.output.push_str("let ");
+                self.pattern(var_decl.pattern.value_ref());
+                if let Some(type_name) = &var_decl.type_name {
+                    self.output.push_str(": ");
+                    self.type_name(type_name.value_ref());
+                }
+                if let Some(expr) = &var_decl.expr {
+                    self.output.push_str(" = ");
+                    self.expr(expr.value_ref());
+                }
+                self.output.push(';');
+            }
+            Statement::If(if_statement) => {
+                self.output.push_str("if (");
+                self.expr(if_statement.condition.value_ref());
+                self.output.push_str(") ");
+                self.statement(if_statement.body.value_ref());
+                if let Some(otherwise) = &if_statement.otherwise {
+                    self.output.push_str(" else ");
+                    self.statement(otherwise.value_ref());
+                }
+            }
+            Statement::While(while_statement) => {
+                self.output.push_str("while (");
+                self.expr(while_statement.condition.value_ref());
+                self.output.push_str(") ");
+                self.statement(while_statement.body.value_ref());
+            }
+            Statement::Block(block) => {
+                if block.statements.is_empty() {
+                    self.output.push_str("{}");
+                    return;
+                }
+
+                self.output.push_str("{\n");
+                self.depth += 1;
+                for statement in block.statements.iter() {
+                    self.indent();
+                    self.statement(statement.value_ref());
+                    self.output.push('\n');
+                }
+                self.depth -= 1;
+                self.indent();
+                self.output.push('}');
+            }
+            Statement::Break => self.output.push_str("break;"),
+            Statement::Continue => self.output.push_str("continue;"),
+            Statement::Return(return_statement) => match &return_statement.expr {
+                Some(expr) => {
+                    self.output.push_str("return ");
+                    self.expr(expr.value_ref());
+                    self.output.push(';');
+                }
+                None => self.output.push_str("return;"),
+            },
+        }
+    }
+
+    /*
+     * Expressions
+     */
+
+    fn expr(&mut self, expr: &Expr) {
+        self.expr_within(expr, Precedence::Assignment);
+    }
+
+    // Print an expression in a position that requires at least the given precedence, adding
+    // parentheses if the expression binds less tightly than that.
+    fn expr_within(&mut self, expr: &Expr, minimum: Precedence) {
+        let precedence = precedence(expr);
+        if precedence < minimum {
+            self.output.push('(');
+            self.expr_within(expr, Precedence::Assignment);
+            self.output.push(')');
+            return;
+        }
+
+        match expr {
+            Expr::Binary(binary) => {
+                // Powers are right-associative and only allow primary expressions on their
+                // left. Everything else is left-associative.
+                let (left, right) = if binary.op == BinaryOp::Pow {
+                    (Precedence::Primary, Precedence::Pow)
+                } else {
+                    (precedence, precedence.next())
+                };
+                self.expr_within(binary.left.value_ref(), left);
+                write!(self.output, " {} ", binary_op(&binary.op)).unwrap();
+                self.expr_within(binary.right.value_ref(), right);
+            }
+            Expr::Unary(unary) => {
+                self.output.push_str(unary_op(&unary.op));
+                self.expr_within(unary.value.value_ref(), Precedence::Unary);
+            }
+            Expr::Assignment(assignment) => {
+                self.expr_within(assignment.lvalue.value_ref(), Precedence::BoolOr);
+                match &assignment.op {
+                    Some(op) => write!(self.output, " {}= ", binary_op(op)).unwrap(),
+                    None => self.output.push_str(" = "),
+                }
+                self.expr_within(assignment.rvalue.value_ref(), Precedence::Assignment);
+            }
+            Expr::MemberAccess(member_access) => {
+                let member = &member_access.member.value_ref().value;
+                let owner = member_access.owner.value_ref();
+
+                // Tuple positions after a number would be read back as a float, such as the
+                // "0.1" in "pair.0.1", so the number needs parentheses.
+                if is_tuple_position(member) && ends_with_number(owner) {
+                    self.output.push('(');
+                    self.expr(owner);
+                    self.output.push(')');
+                } else {
+                    self.expr_within(owner, Precedence::Primary);
+                }
+                self.output.push('.');
+                self.output
+                    .push_str(&member_access.member.value_ref().value);
+            }
+            Expr::Index(index) => {
+                self.expr_within(index.owner.value_ref(), Precedence::Primary);
+                self.output.push('[');
+                self.expr(index.index.value_ref());
+                self.output.push(']');
+            }
+            Expr::FunctionCall(call) => {
+                self.expr_within(call.identity.value_ref(), Precedence::Primary);
+                self.output.push('(');
+                for (index, argument) in call.arguments.value_ref().iter().enumerate() {
+                    if index > 0 {
+                        self.output.push_str(", ");
+                    }
+                    self.expr(argument.value_ref());
+                }
+                self.output.push(')');
+            }
+            Expr::Closure(closure) => {
+                self.output.push('|');
+                for (index, parameter) in closure.parameters.value_ref().iter().enumerate() {
+                    let parameter = parameter.value_ref();
+                    if index > 0 {
+                        self.output.push_str(", ");
+                    }
+                    self.output.push_str(&parameter.name.value_ref().value);
+                    if let Some(type_name) = &parameter.type_name {
+                        self.output.push_str(": ");
+                        self.type_name(type_name.value_ref());
+                    }
+                }
+                self.output.push_str("| ");
+
+                match closure.body.value_ref() {
+                    Statement::Expr(expr) => self.expr(expr),
+                    body => {
+                        if let Some(return_type) = &closure.return_type {
+                            self.output.push_str("-> ");
+                            self.type_name(return_type.value_ref());
+                            self.output.push(' ');
+                        }
+                        self.statement(body);
+                    }
+                }
+            }
+            Expr::Tuple(tuple) => self.tuple(&tuple.elements, Self::expr),
+            Expr::Float(float) => match float.value {
+                FloatLit::F32(value) => write!(self.output, "{:?}f32", value).unwrap(),
+                FloatLit::F64(value) => write!(self.output, "{:?}f64", value).unwrap(),
+                FloatLit::Untyped(value) => write!(self.output, "{:?}", value).unwrap(),
+            },
+            Expr::Int(int) => match &int.value {
+                IntLit::I8(value) => write!(self.output, "{}i8", value).unwrap(),
+                IntLit::I16(value) => write!(self.output, "{}i16", value).unwrap(),
+                IntLit::I32(value) => write!(self.output, "{}i32", value).unwrap(),
+                IntLit::I64(value) => write!(self.output, "{}i64", value).unwrap(),
+                IntLit::I128(value) => write!(self.output, "{}i128", value).unwrap(),
+                IntLit::ISize(value) => write!(self.output, "{}isize", value).unwrap(),
+                IntLit::U8(value) => write!(self.output, "{}u8", value).unwrap(),
+                IntLit::U16(value) => write!(self.output, "{}u16", value).unwrap(),
+                IntLit::U32(value) => write!(self.output, "{}u32", value).unwrap(),
+                IntLit::U64(value) => write!(self.output, "{}u64", value).unwrap(),
+                IntLit::U128(value) => write!(self.output, "{}u128", value).unwrap(),
+                IntLit::USize(value) => write!(self.output, "{}usize", value).unwrap(),
+                IntLit::Untyped(value) => write!(self.output, "{}", value).unwrap(),
+            },
+            Expr::Bool(bool) => self
+                .output
+                .push_str(if bool.value { "true" } else { "false" }),
+            Expr::String(string) => self.string(&string.value),
+            Expr::Char(char) => write!(self.output, "'{}'", char.value.escape_default()).unwrap(),
+            Expr::Byte(byte) => write!(self.output, "b'{}'", byte.value.escape_ascii()).unwrap(),
+            Expr::ByteString(bytes) => {
+                write!(self.output, "b\"{}\"", bytes.value.escape_ascii()).unwrap()
+            }
+            Expr::IdentifierPath(path) => self.path(path),
+        }
+    }
+
+    fn string(&mut self, value: &str) {
+        self.output.push('"');
+        for c in value.chars() {
+            match c {
+                '\\' => self.output.push_str("\\\\"),
+                '"' => self.output.push_str("\\\""),
+                '\n' => self.output.push_str("\\n"),
+                '\r' => self.output.push_str("\\r"),
+                '\t' => self.output.push_str("\\t"),
+                c if c.is_control() => write!(self.output, "\\u{{{:x}}}", c as u32).unwrap(),
+                c => self.output.push(c),
+            }
+        }
+        self.output.push('"');
+    }
+
+    fn indent(&mut self) {
+        for _ in 0..self.depth {
+            self.output.push_str(INDENT);
+        }
+    }
+}
+
+// How tightly each kind of expression binds, from loosest to tightest. This mirrors the
+// expression grammar in the parser.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+enum Precedence {
+    Assignment,
+    BoolOr,
+    BoolAnd,
+    BinaryOr,
+    BinaryXor,
+    BinaryAnd,
+    Equality,
+    Comparison,
+    Shift,
+    Additive,
+    Multiplicative,
+    Unary,
+    Pow,
+    Primary,
+}
+
+impl Precedence {
+    fn next(self) -> Self {
+        match self {
+            Self::Assignment => Self::BoolOr,
+            Self::BoolOr => Self::BoolAnd,
+            Self::BoolAnd => Self::BinaryOr,
+            Self::BinaryOr => Self::BinaryXor,
+            Self::BinaryXor => Self::BinaryAnd,
+            Self::BinaryAnd => Self::Equality,
+            Self::Equality => Self::Comparison,
+            Self::Comparison => Self::Shift,
+            Self::Shift => Self::Additive,
+            Self::Additive => Self::Multiplicative,
+            Self::Multiplicative => Self::Unary,
+            Self::Unary => Self::Pow,
+            Self::Pow | Self::Primary => Self::Primary,
+        }
+    }
+}
+
+fn precedence(expr: &Expr) -> Precedence {
+    match expr {
+        // Closure bodies extend as far to the right as possible, so closures need parentheses
+        // anywhere an assignment would.
+        Expr::Assignment(_) | Expr::Closure(_) => Precedence::Assignment,
+        Expr::Unary(_) => Precedence::Unary,
+        Expr::Binary(binary) => match binary.op {
+            BinaryOp::BoolOr => Precedence::BoolOr,
+            BinaryOp::BoolAnd => Precedence::BoolAnd,
+            BinaryOp::BinaryOr => Precedence::BinaryOr,
+            BinaryOp::BinaryXor => Precedence::BinaryXor,
+            BinaryOp::BinaryAnd => Precedence::BinaryAnd,
+            BinaryOp::Eq | BinaryOp::NotEq => Precedence::Equality,
+            BinaryOp::Less | BinaryOp::LessEq | BinaryOp::Greater | BinaryOp::GreaterEq => {
+                Precedence::Comparison
+            }
+            BinaryOp::BinaryShl | BinaryOp::BinaryShr => Precedence::Shift,
+            BinaryOp::Add | BinaryOp::Sub => Precedence::Additive,
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => Precedence::Multiplicative,
+            BinaryOp::Pow => Precedence::Pow,
+        },
+        _ => Precedence::Primary,
+    }
+}
+
+fn is_tuple_position(member: &str) -> bool {
+    member.starts_with(|c: char| c.is_ascii_digit())
+}
+
+fn ends_with_number(expr: &Expr) -> bool {
+    match expr {
+        Expr::Int(_) | Expr::Float(_) => true,
+        Expr::MemberAccess(member_access) => {
+            is_tuple_position(&member_access.member.value_ref().value)
+        }
+        _ => false,
+    }
+}
+
+fn binary_op(op: &BinaryOp) -> &'static str {
+    match op {
+        BinaryOp::Add => "+",
+        BinaryOp::Sub => "-",
+        BinaryOp::Mul => "*",
+        BinaryOp::Div => "/",
+        BinaryOp::Mod => "%",
+        BinaryOp::Pow => "**",
+        BinaryOp::BinaryAnd => "&",
+        BinaryOp::BinaryOr => "|",
+        BinaryOp::BinaryXor => "^",
+        BinaryOp::BinaryShl => "<<",
+        BinaryOp::BinaryShr => ">>",
+        BinaryOp::BoolAnd => "&&",
+        BinaryOp::BoolOr => "||",
+        BinaryOp::Eq => "==",
+        BinaryOp::NotEq => "!=",
+        BinaryOp::Less => "<",
+        BinaryOp::LessEq => "<=",
+        BinaryOp::Greater => ">",
+        BinaryOp::GreaterEq => ">=",
+    }
+}
+
+fn unary_op(op: &UnaryOp) -> &'static str {
+    match op {
+        UnaryOp::Plus => "+",
+        UnaryOp::Minus => "-",
+        UnaryOp::Not => "!",
+        UnaryOp::Invert => "~",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ParserError;
+    use crate::lexer::token_stream::TokenStream;
+    use crate::parser::core::Parser;
+    use std::path::Path;
+    use test_case::test_case;
+
+    fn reprint(source: &str) -> String {
+        let mut errors: Vec<Spanned<ParserError>> = Vec::new();
+        let mut parser = Parser::new(TokenStream::new(source), Path::new("test.hkl"), &mut errors);
+        print_compilation_unit(parser.parse().unwrap().value_ref())
+    }
+
+    #[test_case(
+        "use   foo :: bar ;",
+        "use foo::bar;\n"
+        ; "use declaration"
+    )]
+    #[test_case(
+        "extern fn puts(s: string) -> i32;",
+        "extern fn puts(s: string) -> i32;\n"
+        ; "extern function"
+    )]
+    #[test_case(
+        "fn double(x: i32) = x * 2;",
+        "fn double(x: i32) = x * 2;\n"
+        ; "expression function"
+    )]
+    #[test_case(
+        "struct Empty {} struct Point { x: i32; y: i32; }",
+        "struct Empty {}\n\nstruct Point {\n    x: i32;\n    y: i32;\n}\n"
+        ; "struct declarations"
+    )]
+    #[test_case(
+        "/// Doc.\npub   fn f() {} pub extern fn g(); pub struct S { pub x: i32; y: i32; }",
+        "/// Doc.\npub fn f() {}\n\npub extern fn g();\n\npub struct S {\n    pub x: i32;\n    y: i32;\n}\n"
+        ; "public declarations"
+    )]
+    #[test_case(
+        "#[test]#[inline()] fn f() {} struct S { #[deprecated(\"old\", 1 + 2,)] x: i32; }",
+        "#[test]\n#[inline()]\nfn f() {}\n\nstruct S {\n    #[deprecated(\"old\", 1 + 2)]\n    x: i32;\n}\n"
+        ; "attributes"
+    )]
+    #[test_case(
+        "fn f() { let x = 1; if (x) { return; } else ; while (true) { break; continue; } }",
+        "fn f() {\n    let x = 1;\n    if (x) {\n        return;\n    } else ;\n    while (true) {\n        break;\n        continue;\n    }\n}\n"
+        ; "statements"
+    )]
+    #[test_case(
+        "fn apply(f: fn(i32,fn()) -> fn() -> i32, g: fn()) {}",
+        "fn apply(f: fn(i32, fn()) -> fn() -> i32, g: fn()) {}\n"
+        ; "function types"
+    )]
+    #[test_case(
+        "fn swap(p: (i32,(bool,)), q: ( )) -> (i32) { let (a, (b,)) = p; }",
+        "fn swap(p: (i32, (bool,)), q: ()) -> i32 {\n    let (a, (b,)) = p;\n}\n"
+        ; "tuples"
+    )]
+    #[test_case(
+        "/// Adds.\nfn add(/// Left.\n a: i32, b: i32) -> i32 {}",
+        "/// Adds.\nfn add(\n    /// Left.\n    a: i32,\n    b: i32\n) -> i32 {}\n"
+        ; "documentation"
+    )]
+    #[test_case(
+        "/** a\rb */ struct S {}",
+        "/// a\n/// b\nstruct S {}\n"
+        ; "documentation with carriage returns"
+    )]
+    #[test_case(
+        "/*! Shapes.\n */ //! Really.\n struct S {}",
+        "//! Shapes.\n//! Really.\n\nstruct S {}\n"
+        ; "compilation unit documentation"
+    )]
+    fn declarations_are_printed(source: &str, expected: &str) {
+        // Then
+        assert_eq!(reprint(source), expected);
+    }
+
+    #[test_case(           "1 + (2 * 3)",               "1 + 2 * 3" ; "redundant parentheses")]
+    #[test_case(           "(1 + 2) * 3",             "(1 + 2) * 3" ; "precedence")]
+    #[test_case(         "(1 - 2) - 3",               "1 - 2 - 3" ; "left associativity")]
+    #[test_case(           "1 - (2 - 3)",             "1 - (2 - 3)" ; "left associativity with grouping")]
+    #[test_case(         "2 ** (3 ** 4)",             "2 ** 3 ** 4" ; "right associativity")]
+    #[test_case(           "(2 ** 3) ** 4",           "(2 ** 3) ** 4" ; "right associativity with grouping")]
+    #[test_case(                "(-2) ** 2",               "(-2) ** 2" ; "unary operands of powers")]
+    #[test_case(               "a = b += 1",              "a = b += 1" ; "assignments")]
+    #[test_case(             "(a = b) = c",             "(a = b) = c" ; "nested assignment targets")]
+    #[test_case(                 "- -!~x",                  "--!~x" ; "unary operators")]
+    #[test_case(     "(a + b).c[d](e, f)(g)", "(a + b).c[d](e, f)(g)" ; "postfix operators")]
+    #[test_case( "1i8 + 2u64 + 1.5f32 + 1e10", "1i8 + 2u64 + 1.5f32 + 10000000000.0" ; "literal suffixes")]
+    #[test_case(   r#""a\"b\\\n\u0001é""#, r#""a\"b\\\n\u{1}é""# ; "string escapes")]
+    #[test_case(r##"r#"raw "string""#"##, r#""raw \"string\"""# ; "raw strings")]
+    #[test_case(r#"'a' + '\'' + '\u{1F600}' + '\0'"#, r#"'a' + '\'' + '\u{1f600}' + '\u{0}'"# ; "characters")]
+    #[test_case(          r#"b'a' + b'\xFF'"#,           r#"b'a' + b'\xff'"# ; "bytes")]
+    #[test_case(       r#"b"a\"\x00\xff""#,        r#"b"a\"\x00\xff""# ; "byte strings")]
+    #[test_case(             "|x,y:i32|x+y",          "|x, y: i32| x + y" ; "closures")]
+    #[test_case(    "||->i32{return 1;}",      "|| -> i32 {\n    return 1;\n}" ; "closures with blocks")]
+    #[test_case(           "(|x| x)(1) + 2",          "(|x| x)(1) + 2" ; "closures as operands")]
+    #[test_case(           "a = (|x| x)",                "a = |x| x" ; "closures as assignments")]
+    #[test_case(             "|x| (|y| y)",                "|x| |y| y" ; "nested closures")]
+    #[test_case(           "( ) + (1,) + (1 , 2 ,)",    "() + (1,) + (1, 2)" ; "tuples")]
+    #[test_case(             "(p.0).1 + x.12",             "(p.0).1 + x.12" ; "tuple positions")]
+    #[test_case(                  "(1).0 + (1.5).0",        "(1).0 + (1.5).0" ; "tuple positions of numbers")]
+    fn expressions_are_printed(source: &str, expected: &str) {
+        // Given
+        let source = format!("fn f() = {};", source);
+
+        // Then
+        assert_eq!(reprint(&source), format!("fn f() = {};\n", expected));
+    }
+}
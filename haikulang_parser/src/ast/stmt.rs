@@ -1,5 +1,6 @@
 use crate::ast::expr::Expr;
-use crate::ast::ident::{Identifier, IdentifierPath};
+use crate::ast::ident::Identifier;
+use crate::ast::types::TypeName;
 use crate::span::Spanned;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -17,11 +18,18 @@ pub enum Statement {
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct VarDeclStatement {
-    pub identifier: Spanned<Identifier>,
-    pub type_name: Option<Spanned<IdentifierPath>>,
+    pub pattern: Spanned<Pattern>,
+    pub type_name: Option<Spanned<TypeName>>,
     pub expr: Option<Spanned<Expr>>,
 }
 
+// The names that a variable declaration binds, which may destructure a tuple.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Pattern {
+    Identifier(Box<Identifier>),
+    Tuple(Box<[Spanned<Pattern>]>),
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct IfStatement {
     pub condition: Spanned<Expr>,
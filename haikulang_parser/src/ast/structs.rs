@@ -1,14 +1,24 @@
-use crate::ast::ident::{Identifier, IdentifierPath};
+use crate::ast::attr::Attribute;
+use crate::ast::doc::DocComment;
+use crate::ast::ident::Identifier;
+use crate::ast::types::TypeName;
+use crate::ast::visibility::Visibility;
 use crate::span::Spanned;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct StructDecl {
+    pub doc: Option<Spanned<DocComment>>,
+    pub attributes: Box<[Spanned<Attribute>]>,
+    pub visibility: Visibility,
     pub identifier: Spanned<Identifier>,
     pub members: Box<[Spanned<StructMemberDecl>]>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct StructMemberDecl {
+    pub doc: Option<Spanned<DocComment>>,
+    pub attributes: Box<[Spanned<Attribute>]>,
+    pub visibility: Visibility,
     pub identifier: Spanned<Identifier>,
-    pub type_name: Spanned<IdentifierPath>,
+    pub type_name: Spanned<TypeName>,
 }
@@ -0,0 +1,40 @@
+use crate::ast::ident::IdentifierPath;
+use crate::span::Spanned;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypeName {
+    Path(Box<IdentifierPath>),
+    Function(Box<FunctionTypeName>),
+    Tuple(Box<TupleTypeName>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FunctionTypeName {
+    pub parameters: Spanned<Box<[Spanned<TypeName>]>>,
+    pub return_type: Option<Spanned<TypeName>>,
+}
+
+// The empty tuple is the unit type, and a tuple of one element is written with a trailing
+// comma to tell it apart from a type within parentheses.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TupleTypeName {
+    pub elements: Box<[Spanned<TypeName>]>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn type_name_enum_size_is_not_too_large() {
+        let desired_max_size = 16;
+        let size = size_of::<TypeName>();
+
+        assert!(
+            size <= desired_max_size,
+            "TypeName enum size is too large (wanted <= {} bytes, was {} bytes), consider boxing elements to reduce the size.",
+            desired_max_size,
+            size
+        )
+    }
+}
@@ -1,3 +1,4 @@
+use crate::ast::doc::DocComment;
 use crate::ast::func::{ExternFunctionDecl, FunctionDecl};
 use crate::ast::ident::IdentifierPath;
 use crate::ast::structs::StructDecl;
@@ -8,6 +9,8 @@ use std::path::PathBuf;
 pub struct CompilationUnit {
     pub path: PathBuf,
     pub name: String,
+    // Documentation for the unit itself, written using `//!` or `/*! */` comments.
+    pub doc: Option<Spanned<DocComment>>,
     pub members: Box<[Spanned<CompilationUnitMember>]>,
 }
 
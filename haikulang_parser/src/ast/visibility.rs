@@ -0,0 +1,15 @@
+/// Whether a declaration can be used from outside the module that declares it.
+///
+/// Declarations are private unless they are marked with the `pub` keyword.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Visibility {
+    #[default]
+    Private,
+    Public,
+}
+
+impl Visibility {
+    pub fn is_public(self) -> bool {
+        self == Self::Public
+    }
+}
@@ -0,0 +1,118 @@
+use crate::cst::event::Event;
+use crate::cst::green::{GreenElement, GreenNode, GreenToken};
+use crate::cst::kind::SyntaxKind;
+use crate::error::ParserError;
+use crate::lexer::token::Token;
+use std::ops::Range;
+use std::rc::Rc;
+
+/// Interleave the tokens that the lexer produced with the whitespace between them, so that
+/// every byte of the source is covered by exactly one token.
+fn with_trivia(
+    source: &str,
+    lexed: &[(Result<Token, ParserError>, Range<usize>)],
+) -> Vec<(SyntaxKind, Range<usize>)> {
+    let mut tokens = Vec::new();
+    let mut end = 0;
+
+    for (result, range) in lexed {
+        if range.start > end {
+            tokens.push((SyntaxKind::Whitespace, end..range.start));
+        }
+
+        let kind = match result {
+            Ok(token) => SyntaxKind::of_token(token),
+            Err(_) => SyntaxKind::Error,
+        };
+
+        end = range.end;
+        tokens.push((kind, range.clone()));
+    }
+
+    if end < source.len() {
+        tokens.push((SyntaxKind::Whitespace, end..source.len()));
+    }
+
+    tokens
+}
+
+/// Builds a green tree from the nodes that the parser recorded.
+///
+/// Each node covers the span that the parser gave it. Tokens that fall between the children
+/// of a node, such as punctuation and trivia, are attached to that node.
+pub(crate) struct TreeBuilder<'src> {
+    source: &'src str,
+    tokens: Vec<(SyntaxKind, Range<usize>)>,
+    position: usize,
+    stack: Vec<(SyntaxKind, Vec<GreenElement>)>,
+}
+
+impl<'src> TreeBuilder<'src> {
+    pub(crate) fn new(
+        source: &'src str,
+        lexed: &[(Result<Token, ParserError>, Range<usize>)],
+    ) -> Self {
+        Self {
+            source,
+            tokens: with_trivia(source, lexed),
+            position: 0,
+            stack: Vec::new(),
+        }
+    }
+
+    /// Build a tree from the events that the parser recorded. Nodes that were never finished
+    /// because parsing gave up part way through them run to the end of the input.
+    pub(crate) fn build(mut self, events: &[Event]) -> GreenNode {
+        self.start(SyntaxKind::CompilationUnit, 0);
+
+        for event in events {
+            match *event {
+                Event::Start { kind, offset } => self.start(kind, offset),
+                Event::Finish { offset } => {
+                    self.finish(offset);
+                }
+            }
+        }
+
+        loop {
+            let node = self.finish(self.source.len());
+            if self.stack.is_empty() {
+                return node;
+            }
+        }
+    }
+
+    /*
+     * Helpers
+     */
+
+    // Start a new node. Any tokens before the node are attached to the current node first.
+    fn start(&mut self, kind: SyntaxKind, offset: usize) {
+        self.take_tokens_before(offset);
+        self.stack.push((kind, Vec::new()));
+    }
+
+    // Finish the current node, taking any remaining tokens that end before the given offset.
+    fn finish(&mut self, end: usize) -> GreenNode {
+        self.take_tokens_before(end);
+        let (kind, children) = self.stack.pop().expect("unbalanced syntax tree nodes");
+        let node = GreenNode::new(kind, children);
+
+        if let Some((_, parent)) = self.stack.last_mut() {
+            parent.push(GreenElement::Node(Rc::new(node.clone())));
+        }
+
+        node
+    }
+
+    fn take_tokens_before(&mut self, offset: usize) {
+        while let Some((kind, range)) = self.tokens.get(self.position)
+            && range.start < offset
+        {
+            let token = GreenToken::new(*kind, &self.source[range.clone()]);
+            let (_, children) = self.stack.last_mut().expect("tokens must be within a node");
+            children.push(GreenElement::Token(Rc::new(token)));
+            self.position += 1;
+        }
+    }
+}
@@ -0,0 +1,12 @@
+use crate::cst::kind::SyntaxKind;
+
+/// A step towards building a lossless syntax tree, as recorded by the parser.
+///
+/// The parser records each node once it has parsed it, by inserting its start before the
+/// events of its children. Every start has a matching finish, other than that of the error
+/// node around a declaration that could not be parsed, which runs to the end of the input.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Event {
+    Start { kind: SyntaxKind, offset: usize },
+    Finish { offset: usize },
+}
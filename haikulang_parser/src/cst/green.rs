@@ -0,0 +1,153 @@
+//! Green trees are immutable, position-independent and cheap to share.
+//!
+//! Each node only knows its kind, its children and the length of the text it covers. This
+//! means that identical subtrees can be reused when the tree is edited.
+use crate::cst::kind::SyntaxKind;
+use std::rc::Rc;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct GreenNode {
+    kind: SyntaxKind,
+    text_len: usize,
+    children: Box<[GreenElement]>,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct GreenToken {
+    kind: SyntaxKind,
+    text: Box<str>,
+}
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum GreenElement {
+    Node(Rc<GreenNode>),
+    Token(Rc<GreenToken>),
+}
+
+impl GreenNode {
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> Self {
+        let text_len = children.iter().map(GreenElement::text_len).sum();
+        Self {
+            kind,
+            text_len,
+            children: children.into_boxed_slice(),
+        }
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    pub fn text_len(&self) -> usize {
+        self.text_len
+    }
+
+    pub fn children(&self) -> &[GreenElement] {
+        &self.children
+    }
+
+    /// Produce a new node with the child at the given index replaced.
+    pub fn replace_child(&self, index: usize, child: GreenElement) -> Self {
+        let mut children = self.children.to_vec();
+        children[index] = child;
+        Self::new(self.kind, children)
+    }
+
+    /// Write the text covered by this node.
+    pub fn write_text(&self, text: &mut String) {
+        for child in self.children.iter() {
+            match child {
+                GreenElement::Node(node) => node.write_text(text),
+                GreenElement::Token(token) => text.push_str(token.text()),
+            }
+        }
+    }
+}
+
+impl GreenToken {
+    pub fn new(kind: SyntaxKind, text: &str) -> Self {
+        Self {
+            kind,
+            text: Box::from(text),
+        }
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+impl GreenElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            Self::Node(node) => node.kind(),
+            Self::Token(token) => token.kind(),
+        }
+    }
+
+    pub fn text_len(&self) -> usize {
+        match self {
+            Self::Node(node) => node.text_len(),
+            Self::Token(token) => token.text().len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(kind: SyntaxKind, text: &str) -> GreenElement {
+        GreenElement::Token(Rc::new(GreenToken::new(kind, text)))
+    }
+
+    #[test]
+    fn nodes_measure_their_children() {
+        // Given
+        let path = GreenNode::new(
+            SyntaxKind::IdentifierPath,
+            vec![token(SyntaxKind::Identifier, "foo")],
+        );
+
+        // When
+        let node = GreenNode::new(
+            SyntaxKind::UseDecl,
+            vec![
+                token(SyntaxKind::Use, "use"),
+                token(SyntaxKind::Whitespace, " "),
+                GreenElement::Node(Rc::new(path)),
+                token(SyntaxKind::Semicolon, ";"),
+            ],
+        );
+
+        // Then
+        assert_eq!(node.text_len(), 8);
+        let mut text = String::new();
+        node.write_text(&mut text);
+        assert_eq!(text, "use foo;");
+    }
+
+    #[test]
+    fn children_can_be_replaced() {
+        // Given
+        let node = GreenNode::new(
+            SyntaxKind::IdentifierPath,
+            vec![token(SyntaxKind::Identifier, "foo")],
+        );
+
+        // When
+        let replaced = node.replace_child(0, token(SyntaxKind::Identifier, "quux"));
+
+        // Then
+        assert_eq!(node.text_len(), 3);
+        assert_eq!(replaced.text_len(), 4);
+        assert_eq!(
+            replaced.children()[0],
+            token(SyntaxKind::Identifier, "quux")
+        );
+    }
+}
@@ -0,0 +1,239 @@
+use crate::lexer::token::Token;
+
+/// The kind of a node or token within a lossless syntax tree.
+///
+/// Token kinds mirror the variants of [Token], without any parsed value. Trivia kinds cover
+/// the input that the parser otherwise discards.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SyntaxKind {
+    // Trivia.
+    Whitespace,
+    InlineComment,
+    InlineDocComment,
+    InlineInnerDocComment,
+    MultilineComment,
+    MultilineDocComment,
+    MultilineInnerDocComment,
+    // Input that could not be tokenized, or a node holding input that could not be parsed.
+    Error,
+
+    // Tokens.
+    Identifier,
+    StringLit,
+    CharLit,
+    ByteLit,
+    ByteStringLit,
+    IntLit,
+    FloatLit,
+    True,
+    False,
+    Extern,
+    Fn,
+    Pub,
+    Struct,
+    Return,
+    Continue,
+    Break,
+    If,
+    Else,
+    For,
+    While,
+    Let,
+    Use,
+    Semicolon,
+    Hash,
+    LeftBrace,
+    RightBrace,
+    LeftParen,
+    RightParen,
+    LeftBracket,
+    RightBracket,
+    Period,
+    Comma,
+    Colon,
+    DoubleColon,
+    Arrow,
+    Assign,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Pow,
+    AddAssign,
+    SubAssign,
+    MulAssign,
+    DivAssign,
+    ModAssign,
+    PowAssign,
+    BinaryAnd,
+    BinaryOr,
+    BinaryXor,
+    BinaryNot,
+    BinaryShl,
+    BinaryShr,
+    BinaryAndAssign,
+    BinaryOrAssign,
+    BinaryXorAssign,
+    BinaryShlAssign,
+    BinaryShrAssign,
+    BoolAnd,
+    BoolOr,
+    BoolNot,
+    Eq,
+    NotEq,
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+
+    // Nodes.
+    CompilationUnit,
+    UseDecl,
+    Attribute,
+    AttributeArgumentList,
+    ExternFunctionDecl,
+    FunctionDecl,
+    ParameterList,
+    ParameterDecl,
+    StructDecl,
+    StructMemberDecl,
+    IdentifierPath,
+    FunctionType,
+    FunctionTypeParameterList,
+    TupleType,
+    EmptyStatement,
+    ExprStatement,
+    VarDeclStatement,
+    IdentifierPattern,
+    TuplePattern,
+    IfStatement,
+    WhileStatement,
+    BlockStatement,
+    BreakStatement,
+    ContinueStatement,
+    ReturnStatement,
+    BinaryExpr,
+    UnaryExpr,
+    AssignmentExpr,
+    MemberAccessExpr,
+    IndexExpr,
+    FunctionCallExpr,
+    ArgumentList,
+    ClosureExpr,
+    ClosureParameterList,
+    ClosureParameter,
+    TupleExpr,
+    LiteralExpr,
+    PathExpr,
+}
+
+impl SyntaxKind {
+    /// Whether this kind only affects the layout of the source, rather than its meaning.
+    pub fn is_trivia(self) -> bool {
+        matches!(
+            self,
+            Self::Whitespace
+                | Self::InlineComment
+                | Self::InlineDocComment
+                | Self::InlineInnerDocComment
+                | Self::MultilineComment
+                | Self::MultilineDocComment
+                | Self::MultilineInnerDocComment
+        )
+    }
+
+    /// Whether this kind is documentation. Doc comments are trivia as far as the layout of
+    /// the tree is concerned, but the parser attaches them to the declarations that follow.
+    pub fn is_doc_comment(self) -> bool {
+        matches!(
+            self,
+            Self::InlineDocComment
+                | Self::InlineInnerDocComment
+                | Self::MultilineDocComment
+                | Self::MultilineInnerDocComment
+        )
+    }
+
+    /// The kind of token that represents the given lexer token.
+    pub fn of_token(token: &Token) -> Self {
+        match token {
+            // We never store the EOF marker in the tree, as it has no text.
+            Token::Eof => unreachable!("EOF markers are not part of the syntax tree"),
+            Token::InlineComment(_) => Self::InlineComment,
+            Token::InlineDocComment(_) => Self::InlineDocComment,
+            Token::InlineInnerDocComment(_) => Self::InlineInnerDocComment,
+            Token::MultilineComment(_) => Self::MultilineComment,
+            Token::MultilineDocComment(_) => Self::MultilineDocComment,
+            Token::MultilineInnerDocComment(_) => Self::MultilineInnerDocComment,
+            Token::Identifier(_) => Self::Identifier,
+            Token::StringLit(_) => Self::StringLit,
+            Token::CharLit(_) => Self::CharLit,
+            Token::ByteLit(_) => Self::ByteLit,
+            Token::ByteStringLit(_) => Self::ByteStringLit,
+            Token::IntLit(_) => Self::IntLit,
+            Token::FloatLit(_) => Self::FloatLit,
+            Token::True => Self::True,
+            Token::False => Self::False,
+            Token::Extern => Self::Extern,
+            Token::Fn => Self::Fn,
+            Token::Pub => Self::Pub,
+            Token::Struct => Self::Struct,
+            Token::Return => Self::Return,
+            Token::Continue => Self::Continue,
+            Token::Break => Self::Break,
+            Token::If => Self::If,
+            Token::Else => Self::Else,
+            Token::For => Self::For,
+            Token::While => Self::While,
+            Token::Let => Self::Let,
+            Token::Use => Self::Use,
+            Token::Semicolon => Self::Semicolon,
+            Token::Hash => Self::Hash,
+            Token::LeftBrace => Self::LeftBrace,
+            Token::RightBrace => Self::RightBrace,
+            Token::LeftParen => Self::LeftParen,
+            Token::RightParen => Self::RightParen,
+            Token::LeftBracket => Self::LeftBracket,
+            Token::RightBracket => Self::RightBracket,
+            Token::Period => Self::Period,
+            Token::Comma => Self::Comma,
+            Token::Colon => Self::Colon,
+            Token::DoubleColon => Self::DoubleColon,
+            Token::Arrow => Self::Arrow,
+            Token::Assign => Self::Assign,
+            Token::Add => Self::Add,
+            Token::Sub => Self::Sub,
+            Token::Mul => Self::Mul,
+            Token::Div => Self::Div,
+            Token::Mod => Self::Mod,
+            Token::Pow => Self::Pow,
+            Token::AddAssign => Self::AddAssign,
+            Token::SubAssign => Self::SubAssign,
+            Token::MulAssign => Self::MulAssign,
+            Token::DivAssign => Self::DivAssign,
+            Token::ModAssign => Self::ModAssign,
+            Token::PowAssign => Self::PowAssign,
+            Token::BinaryAnd => Self::BinaryAnd,
+            Token::BinaryOr => Self::BinaryOr,
+            Token::BinaryXor => Self::BinaryXor,
+            Token::BinaryNot => Self::BinaryNot,
+            Token::BinaryShl => Self::BinaryShl,
+            Token::BinaryShr => Self::BinaryShr,
+            Token::BinaryAndAssign => Self::BinaryAndAssign,
+            Token::BinaryOrAssign => Self::BinaryOrAssign,
+            Token::BinaryXorAssign => Self::BinaryXorAssign,
+            Token::BinaryShlAssign => Self::BinaryShlAssign,
+            Token::BinaryShrAssign => Self::BinaryShrAssign,
+            Token::BoolAnd => Self::BoolAnd,
+            Token::BoolOr => Self::BoolOr,
+            Token::BoolNot => Self::BoolNot,
+            Token::Eq => Self::Eq,
+            Token::NotEq => Self::NotEq,
+            Token::Less => Self::Less,
+            Token::LessEq => Self::LessEq,
+            Token::Greater => Self::Greater,
+            Token::GreaterEq => Self::GreaterEq,
+        }
+    }
+}
@@ -0,0 +1,262 @@
+//! Lossless concrete syntax trees.
+//!
+//! Unlike the AST, these trees represent every byte of the input, including whitespace and
+//! comments, so that tools such as formatters and refactorings can rewrite source code
+//! without losing its layout. Trees are split into two layers:
+//!
+//! - green trees, which are immutable and position-independent, making them cheap to share
+//!   between edits;
+//! - red trees, which wrap green trees with absolute positions and parent pointers for
+//!   navigation.
+pub mod builder;
+pub(crate) mod event;
+pub mod green;
+pub mod kind;
+pub mod red;
+
+use crate::ast::unit::CompilationUnit;
+use crate::cst::builder::TreeBuilder;
+use crate::cst::red::SyntaxNode;
+use crate::error::{ErrorReporter, ParserResult};
+use crate::lexer::token::Token;
+use crate::lexer::token_stream::TokenStream;
+use crate::parser::core::Parser;
+use crate::span::Spanned;
+use logos::Logos;
+use std::path::Path;
+use std::rc::Rc;
+
+/// Parse the source into a lossless syntax tree.
+///
+/// If the source cannot be parsed, errors are reported and the tree keeps the nodes that
+/// were parsed before the error. The declaration that could not be parsed becomes an error
+/// node holding the rest of the input, so that the tree still covers all of it.
+pub fn parse_lossless(
+    source: &str,
+    path: &Path,
+    error_reporter: &mut impl ErrorReporter,
+) -> SyntaxNode {
+    let lexed: Vec<_> = Token::lexer(source).spanned().collect();
+    let builder = TreeBuilder::new(source, &lexed);
+    let stream = TokenStream::from_lexed(lexed, source.len());
+    let mut parser = Parser::new(stream, path, error_reporter);
+    parser.record_syntax_tree();
+
+    // Any errors have already been reported, and the events hold whatever was parsed.
+    let _ = parser.parse();
+    let green = builder.build(&parser.take_syntax_events());
+
+    SyntaxNode::new_root(Rc::new(green))
+}
+
+impl SyntaxNode {
+    /// Derive the AST from the tokens within this tree, ignoring any trivia other than doc
+    /// comments.
+    pub fn to_compilation_unit(
+        &self,
+        path: &Path,
+        error_reporter: &mut impl ErrorReporter,
+    ) -> ParserResult<CompilationUnit> {
+        let mut tokens = Vec::new();
+
+        for token in self.descendant_tokens() {
+            if token.kind().is_trivia() && !token.kind().is_doc_comment() {
+                continue;
+            }
+
+            match Token::lexer(token.text()).next() {
+                Some(Ok(value)) => tokens.push(Spanned::new(value, token.span())),
+                Some(Err(err)) => {
+                    let err = Spanned::new(err, token.span());
+                    error_reporter.report(&err);
+                    return Err(err);
+                }
+                None => unreachable!("tokens within syntax trees are never empty"),
+            }
+        }
+
+        let stream = TokenStream::from_tokens(tokens, self.span().end());
+        Parser::new(stream, path, error_reporter).parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cst::kind::SyntaxKind;
+    use crate::error::ParserError;
+
+    const SOURCE: &str = "\
+/** Docs. */
+use std;
+
+struct Point { x: i32; /* y */ y: i32; }
+
+// Entry point.
+fn main(argc: i32) -> i32 {
+    let p = Point(1,  2);
+    if (p.x < 2) { return f(p.x) [0]; } else { p.x += 1; }
+    while (true) { break; }
+    return -p.y ** 2;
+}
+";
+
+    fn parse(source: &str) -> SyntaxNode {
+        let mut errors: Vec<Spanned<ParserError>> = Vec::new();
+        parse_lossless(source, Path::new("test.hkl"), &mut errors)
+    }
+
+    #[test]
+    fn trees_reproduce_the_source_exactly() {
+        // When
+        let tree = parse(SOURCE);
+
+        // Then
+        assert_eq!(tree.text(), SOURCE);
+        assert_eq!(tree.span().range(), 0..SOURCE.len());
+    }
+
+    #[test]
+    fn trees_reproduce_unparseable_source_exactly() {
+        // Given
+        let source = "fn main( { \"unterminated";
+
+        // When
+        let tree = parse(source);
+
+        // Then
+        assert_eq!(tree.text(), source);
+        let kinds: Vec<_> = tree.children().iter().map(|node| node.kind()).collect();
+        assert_eq!(kinds, vec![SyntaxKind::Error]);
+        assert_eq!(tree.children()[0].text(), source);
+    }
+
+    #[test]
+    fn trees_keep_what_parsed_before_an_error() {
+        // Given
+        let source = "use a;
+fn main() {
+    let x = f(1);
+    x = ;
+}
+";
+
+        // When
+        let tree = parse(source);
+
+        // Then
+        assert_eq!(tree.text(), source);
+        let kinds: Vec<_> = tree.children().iter().map(|node| node.kind()).collect();
+        assert_eq!(kinds, vec![SyntaxKind::UseDecl, SyntaxKind::Error]);
+
+        let error = &tree.children()[1];
+        assert_eq!(error.text(), &source[7..]);
+        let kinds: Vec<_> = error.children().iter().map(|node| node.kind()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                SyntaxKind::ParameterList,
+                SyntaxKind::VarDeclStatement,
+                SyntaxKind::PathExpr
+            ]
+        );
+        assert_eq!(error.children()[1].text(), "let x = f(1);");
+    }
+
+    #[test]
+    fn ast_can_be_derived_from_the_tree() {
+        // Given
+        let mut errors: Vec<Spanned<ParserError>> = Vec::new();
+        let tree = parse(SOURCE);
+
+        // When
+        let derived = tree.to_compilation_unit(Path::new("test.hkl"), &mut errors);
+
+        // Then
+        let expected =
+            Parser::new(TokenStream::new(SOURCE), Path::new("test.hkl"), &mut errors).parse();
+        assert_eq!(derived, expected);
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn derived_asts_keep_doc_comments() {
+        // Given
+        let source = "//! Unit docs.\n\n/// Docs.\nfn main(/** Count. */ argc: i32) {}\n";
+        let mut errors: Vec<Spanned<ParserError>> = Vec::new();
+        let tree = parse(source);
+
+        // When
+        let derived = tree.to_compilation_unit(Path::new("test.hkl"), &mut errors);
+
+        // Then
+        let expected =
+            Parser::new(TokenStream::new(source), Path::new("test.hkl"), &mut errors).parse();
+        assert_eq!(derived, expected);
+        assert!(derived.unwrap().value_ref().doc.is_some());
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn nodes_mirror_declarations() {
+        // When
+        let tree = parse(SOURCE);
+
+        // Then
+        let kinds: Vec<_> = tree.children().iter().map(|node| node.kind()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                SyntaxKind::UseDecl,
+                SyntaxKind::StructDecl,
+                SyntaxKind::FunctionDecl
+            ]
+        );
+
+        let use_decl = &tree.children()[0];
+        assert_eq!(use_decl.text(), "use std;");
+        assert_eq!(use_decl.parent(), Some(&tree));
+
+        let members = tree.children()[1].children();
+        assert_eq!(members[0].text(), "x: i32;");
+        assert_eq!(members[1].text(), "y: i32;");
+    }
+
+    #[test]
+    fn trivia_is_attached_to_the_enclosing_node() {
+        // When
+        let tree = parse(SOURCE);
+
+        // Then
+        let comment = tree
+            .token_at_offset(SOURCE.find("/* y */").unwrap())
+            .unwrap();
+        assert_eq!(comment.kind(), SyntaxKind::MultilineComment);
+        assert_eq!(comment.parent().kind(), SyntaxKind::StructDecl);
+
+        let doc = tree.token_at_offset(0).unwrap();
+        assert_eq!(doc.kind(), SyntaxKind::MultilineDocComment);
+        assert_eq!(doc.parent().kind(), SyntaxKind::CompilationUnit);
+    }
+
+    #[test]
+    fn debug_trees_show_structure() {
+        // When
+        let tree = parse("use a::b;\n");
+
+        // Then
+        assert_eq!(
+            tree.debug_tree(),
+            "CompilationUnit@0:10\n  \
+               UseDecl@0:9\n    \
+                 Use@0:3 \"use\"\n    \
+                 Whitespace@3:4 \" \"\n    \
+                 IdentifierPath@4:8\n      \
+                   Identifier@4:5 \"a\"\n      \
+                   DoubleColon@5:7 \"::\"\n      \
+                   Identifier@7:8 \"b\"\n    \
+                 Semicolon@8:9 \";\"\n  \
+               Whitespace@9:10 \"\\n\"\n"
+        );
+    }
+}
@@ -0,0 +1,188 @@
+//! Red trees are views over green trees that know their absolute position in the source
+//! and their parent. They are created on demand while navigating the tree.
+use crate::cst::green::{GreenElement, GreenNode, GreenToken};
+use crate::cst::kind::SyntaxKind;
+use crate::span::Span;
+use std::fmt::{Display, Formatter, Write};
+use std::rc::Rc;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+#[derive(Debug, PartialEq)]
+struct NodeData {
+    green: Rc<GreenNode>,
+    parent: Option<SyntaxNode>,
+    offset: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyntaxToken {
+    green: Rc<GreenToken>,
+    parent: SyntaxNode,
+    offset: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxNode {
+    pub fn new_root(green: Rc<GreenNode>) -> Self {
+        Self(Rc::new(NodeData {
+            green,
+            parent: None,
+            offset: 0,
+        }))
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.green.kind()
+    }
+
+    pub fn green(&self) -> &Rc<GreenNode> {
+        &self.0.green
+    }
+
+    pub fn span(&self) -> Span {
+        Span::new(self.0.offset, self.0.offset + self.0.green.text_len())
+    }
+
+    pub fn parent(&self) -> Option<&SyntaxNode> {
+        self.0.parent.as_ref()
+    }
+
+    /// The exact source text covered by this node, including any trivia.
+    pub fn text(&self) -> String {
+        let mut text = String::with_capacity(self.0.green.text_len());
+        self.0.green.write_text(&mut text);
+        text
+    }
+
+    pub fn children_with_tokens(&self) -> Vec<SyntaxElement> {
+        let mut offset = self.0.offset;
+        self.0
+            .green
+            .children()
+            .iter()
+            .map(|child| {
+                let element = match child {
+                    GreenElement::Node(node) => SyntaxElement::Node(Self(Rc::new(NodeData {
+                        green: node.clone(),
+                        parent: Some(self.clone()),
+                        offset,
+                    }))),
+                    GreenElement::Token(token) => SyntaxElement::Token(SyntaxToken {
+                        green: token.clone(),
+                        parent: self.clone(),
+                        offset,
+                    }),
+                };
+                offset += child.text_len();
+                element
+            })
+            .collect()
+    }
+
+    pub fn children(&self) -> Vec<SyntaxNode> {
+        self.children_with_tokens()
+            .into_iter()
+            .filter_map(|element| match element {
+                SyntaxElement::Node(node) => Some(node),
+                SyntaxElement::Token(_) => None,
+            })
+            .collect()
+    }
+
+    /// Every token within this node, in source order.
+    pub fn descendant_tokens(&self) -> Vec<SyntaxToken> {
+        let mut tokens = Vec::new();
+        self.collect_tokens(&mut tokens);
+        tokens
+    }
+
+    fn collect_tokens(&self, tokens: &mut Vec<SyntaxToken>) {
+        for element in self.children_with_tokens() {
+            match element {
+                SyntaxElement::Node(node) => node.collect_tokens(tokens),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+    }
+
+    /// Find the token that covers the given byte offset, if there is one.
+    pub fn token_at_offset(&self, offset: usize) -> Option<SyntaxToken> {
+        for element in self.children_with_tokens() {
+            match element {
+                SyntaxElement::Node(node) if node.span().range().contains(&offset) => {
+                    return node.token_at_offset(offset);
+                }
+                SyntaxElement::Token(token) if token.span().range().contains(&offset) => {
+                    return Some(token);
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Render the structure of the tree for debugging, one element per line.
+    pub fn debug_tree(&self) -> String {
+        let mut text = String::new();
+        self.write_debug_tree(&mut text, 0);
+        text
+    }
+
+    fn write_debug_tree(&self, text: &mut String, depth: usize) {
+        writeln!(
+            text,
+            "{:indent$}{:?}@{}",
+            "",
+            self.kind(),
+            self.span(),
+            indent = depth * 2
+        )
+        .unwrap();
+        for element in self.children_with_tokens() {
+            match element {
+                SyntaxElement::Node(node) => node.write_debug_tree(text, depth + 1),
+                SyntaxElement::Token(token) => writeln!(
+                    text,
+                    "{:indent$}{:?}@{} {:?}",
+                    "",
+                    token.kind(),
+                    token.span(),
+                    token.text(),
+                    indent = (depth + 1) * 2
+                )
+                .unwrap(),
+            }
+        }
+    }
+}
+
+impl Display for SyntaxNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text())
+    }
+}
+
+impl SyntaxToken {
+    pub fn kind(&self) -> SyntaxKind {
+        self.green.kind()
+    }
+
+    pub fn text(&self) -> &str {
+        self.green.text()
+    }
+
+    pub fn span(&self) -> Span {
+        Span::new(self.offset, self.offset + self.green.text().len())
+    }
+
+    pub fn parent(&self) -> &SyntaxNode {
+        &self.parent
+    }
+}
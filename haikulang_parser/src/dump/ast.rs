@@ -0,0 +1,485 @@
+//! Dumping of ASTs.
+use crate::ast::attr::Attribute;
+use crate::ast::doc::DocComment;
+use crate::ast::expr::{BinaryOp, ClosureParameter, Expr, UnaryOp};
+use crate::ast::func::ParameterDecl;
+use crate::ast::ident::{Identifier, IdentifierPath};
+use crate::ast::stmt::{Pattern, Statement};
+use crate::ast::structs::StructMemberDecl;
+use crate::ast::types::TypeName;
+use crate::ast::unit::{CompilationUnit, CompilationUnitMember};
+use crate::ast::visibility::Visibility;
+use crate::dump::token::{bytes, float_lit, int_lit};
+use crate::dump::{Node, SCHEMA_VERSION, Value};
+use crate::span::{Span, Spanned};
+
+pub fn dump_compilation_unit(unit: &Spanned<CompilationUnit>) -> Node {
+    let value = unit.value_ref();
+    let members: Vec<Node> = value.members.iter().map(member).collect();
+    let unit = Node::spanned("compilation_unit", unit.span())
+        .field("path", value.path.to_string_lossy().as_ref())
+        .field("name", value.name.as_str())
+        .field("doc", value.doc.as_ref().map(doc))
+        .field("members", members);
+
+    Node::new("ast")
+        .field("version", SCHEMA_VERSION)
+        .field("unit", unit)
+}
+
+/*
+ * Declarations
+ */
+
+fn member(member: &Spanned<CompilationUnitMember>) -> Node {
+    let span = member.span();
+    match member.value_ref() {
+        CompilationUnitMember::Use(use_decl) => {
+            Node::spanned("use", span).field("path", path(&use_decl.path))
+        }
+        CompilationUnitMember::ExternFunction(function) => Node::spanned("extern_function", span)
+            .field("doc", function.doc.as_ref().map(doc))
+            .field("attributes", attributes(&function.attributes))
+            .field("visibility", visibility(function.visibility))
+            .field("name", identifier(&function.name))
+            .field("parameters", list(&function.parameters, parameter))
+            .field("parameters_span", function.parameters.span())
+            .field("return_type", function.return_type.as_ref().map(type_name)),
+        CompilationUnitMember::Function(function) => Node::spanned("function", span)
+            .field("doc", function.doc.as_ref().map(doc))
+            .field("attributes", attributes(&function.attributes))
+            .field("visibility", visibility(function.visibility))
+            .field("name", identifier(&function.name))
+            .field("parameters", list(&function.parameters, parameter))
+            .field("parameters_span", function.parameters.span())
+            .field("return_type", function.return_type.as_ref().map(type_name))
+            .field("body", statement(&function.body)),
+        CompilationUnitMember::Struct(struct_decl) => Node::spanned("struct", span)
+            .field("doc", struct_decl.doc.as_ref().map(doc))
+            .field("attributes", attributes(&struct_decl.attributes))
+            .field("visibility", visibility(struct_decl.visibility))
+            .field("name", identifier(&struct_decl.identifier))
+            .field(
+                "members",
+                struct_decl
+                    .members
+                    .iter()
+                    .map(struct_member)
+                    .collect::<Vec<_>>(),
+            ),
+    }
+}
+
+fn parameter(parameter: &Spanned<ParameterDecl>) -> Node {
+    let value = parameter.value_ref();
+    Node::spanned("parameter", parameter.span())
+        .field("doc", value.doc.as_ref().map(doc))
+        .field("name", identifier(&value.name))
+        .field("type", type_name(&value.type_name))
+}
+
+fn struct_member(member: &Spanned<StructMemberDecl>) -> Node {
+    let value = member.value_ref();
+    Node::spanned("struct_member", member.span())
+        .field("doc", value.doc.as_ref().map(doc))
+        .field("attributes", attributes(&value.attributes))
+        .field("visibility", visibility(value.visibility))
+        .field("name", identifier(&value.identifier))
+        .field("type", type_name(&value.type_name))
+}
+
+fn attributes(attributes: &[Spanned<Attribute>]) -> Vec<Node> {
+    attributes
+        .iter()
+        .map(|attribute| {
+            let value = attribute.value_ref();
+            Node::spanned("attribute", attribute.span())
+                .field("name", identifier(&value.name))
+                .field(
+                    "arguments",
+                    value
+                        .arguments
+                        .as_ref()
+                        .map(|arguments| list(arguments, expr)),
+                )
+                .field(
+                    "arguments_span",
+                    value.arguments.as_ref().map(|arguments| arguments.span()),
+                )
+        })
+        .collect()
+}
+
+fn doc(doc: &Spanned<DocComment>) -> Node {
+    Node::spanned("doc", doc.span()).field("text", doc.value_ref().text.as_str())
+}
+
+fn visibility(visibility: Visibility) -> Value {
+    match visibility {
+        Visibility::Private => Value::Symbol("private"),
+        Visibility::Public => Value::Symbol("public"),
+    }
+}
+
+fn identifier(identifier: &Spanned<Identifier>) -> Node {
+    Node::spanned("identifier", identifier.span())
+        .field("name", identifier.value_ref().value.as_str())
+}
+
+fn path(path: &Spanned<IdentifierPath>) -> Node {
+    path_fields(Node::spanned("path", path.span()), path.value_ref())
+}
+
+fn path_fields(node: Node, path: &IdentifierPath) -> Node {
+    let qualifier: Vec<Node> = path.qualifier.iter().map(identifier).collect();
+    node.field("qualifier", qualifier)
+        .field("name", identifier(&path.local_name))
+}
+
+/*
+ * Types
+ */
+
+fn type_name(type_name: &Spanned<TypeName>) -> Node {
+    let span = type_name.span();
+    match type_name.value_ref() {
+        TypeName::Path(path) => path_fields(Node::spanned("path_type", span), path),
+        TypeName::Function(function) => Node::spanned("function_type", span)
+            .field("parameters", list(&function.parameters, self::type_name))
+            .field("parameters_span", function.parameters.span())
+            .field(
+                "return_type",
+                function.return_type.as_ref().map(self::type_name),
+            ),
+        TypeName::Tuple(tuple) => Node::spanned("tuple_type", span).field(
+            "elements",
+            tuple
+                .elements
+                .iter()
+                .map(self::type_name)
+                .collect::<Vec<_>>(),
+        ),
+    }
+}
+
+/*
+ * Statements
+ */
+
+fn statement(statement: &Spanned<Statement>) -> Node {
+    let span = statement.span();
+    match statement.value_ref() {
+        Statement::Empty => Node::spanned("empty", span),
+        // The parser does not record a separate span for the expression.
+        Statement::Expr(value) => {
+            Node::spanned("expr_statement", span).field("expr", expr_node(value, Span::UNSET))
+        }
+        Statement::VarDecl(var_decl) => Node::spanned("let", span)
+            .field("pattern", pattern(&var_decl.pattern))
+            .field("type", var_decl.type_name.as_ref().map(type_name))
+            .field("value", var_decl.expr.as_ref().map(expr)),
+        Statement::If(if_statement) => Node::spanned("if", span)
+            .field("condition", expr(&if_statement.condition))
+            .field("body", self::statement(&if_statement.body))
+            .field(
+                "otherwise",
+                if_statement.otherwise.as_ref().map(self::statement),
+            ),
+        Statement::While(while_statement) => Node::spanned("while", span)
+            .field("condition", expr(&while_statement.condition))
+            .field("body", self::statement(&while_statement.body)),
+        Statement::Block(block) => Node::spanned("block", span).field(
+            "statements",
+            block
+                .statements
+                .iter()
+                .map(self::statement)
+                .collect::<Vec<_>>(),
+        ),
+        Statement::Break => Node::spanned("break", span),
+        Statement::Continue => Node::spanned("continue", span),
+        Statement::Return(return_statement) => {
+            Node::spanned("return", span).field("value", return_statement.expr.as_ref().map(expr))
+        }
+    }
+}
+
+fn pattern(pattern: &Spanned<Pattern>) -> Node {
+    let span = pattern.span();
+    match pattern.value_ref() {
+        Pattern::Identifier(name) => {
+            Node::spanned("name_pattern", span).field("name", name.value.as_str())
+        }
+        Pattern::Tuple(elements) => Node::spanned("tuple_pattern", span).field(
+            "elements",
+            elements.iter().map(self::pattern).collect::<Vec<_>>(),
+        ),
+    }
+}
+
+/*
+ * Expressions
+ */
+
+fn expr(expr: &Spanned<Expr>) -> Node {
+    expr_node(expr.value_ref(), expr.span())
+}
+
+fn expr_node(value: &Expr, span: Span) -> Node {
+    match value {
+        Expr::Binary(binary) => Node::spanned("binary", span)
+            .field("op", binary_op(&binary.op))
+            .field("left", expr(&binary.left))
+            .field("right", expr(&binary.right)),
+        Expr::Unary(unary) => Node::spanned("unary", span)
+            .field("op", unary_op(&unary.op))
+            .field("value", expr(&unary.value)),
+        Expr::Assignment(assignment) => Node::spanned("assignment", span)
+            .field("op", assignment.op.as_ref().map(binary_op))
+            .field("target", expr(&assignment.lvalue))
+            .field("value", expr(&assignment.rvalue)),
+        Expr::MemberAccess(access) => Node::spanned("member_access", span)
+            .field("owner", expr(&access.owner))
+            .field("member", identifier(&access.member)),
+        Expr::Index(index) => Node::spanned("index", span)
+            .field("owner", expr(&index.owner))
+            .field("index", expr(&index.index)),
+        Expr::FunctionCall(call) => Node::spanned("call", span)
+            .field("callee", expr(&call.identity))
+            .field("arguments", list(&call.arguments, expr))
+            .field("arguments_span", call.arguments.span()),
+        Expr::Closure(closure) => Node::spanned("closure", span)
+            .field("parameters", list(&closure.parameters, closure_parameter))
+            .field("parameters_span", closure.parameters.span())
+            .field("return_type", closure.return_type.as_ref().map(type_name))
+            .field("body", statement(&closure.body)),
+        Expr::Tuple(tuple) => Node::spanned("tuple", span).field(
+            "elements",
+            tuple.elements.iter().map(expr).collect::<Vec<_>>(),
+        ),
+        Expr::Float(literal) => float_lit(Node::spanned("float", span), literal.value),
+        Expr::Int(literal) => int_lit(Node::spanned("int", span), &literal.value),
+        Expr::Bool(literal) => Node::spanned("bool", span).field("value", literal.value),
+        Expr::String(literal) => {
+            Node::spanned("string", span).field("value", literal.value.as_ref())
+        }
+        Expr::Char(literal) => {
+            Node::spanned("char", span).field("value", literal.value.to_string())
+        }
+        Expr::Byte(literal) => Node::spanned("byte", span).field("value", literal.value as u64),
+        Expr::ByteString(literal) => {
+            Node::spanned("byte_string", span).field("value", bytes(&literal.value))
+        }
+        Expr::IdentifierPath(path) => path_fields(Node::spanned("path", span), path),
+    }
+}
+
+fn closure_parameter(parameter: &Spanned<ClosureParameter>) -> Node {
+    let value = parameter.value_ref();
+    Node::spanned("closure_parameter", parameter.span())
+        .field("name", identifier(&value.name))
+        .field("type", value.type_name.as_ref().map(type_name))
+}
+
+fn binary_op(op: &BinaryOp) -> Value {
+    Value::Symbol(match op {
+        BinaryOp::Add => "add",
+        BinaryOp::Sub => "sub",
+        BinaryOp::Mul => "mul",
+        BinaryOp::Div => "div",
+        BinaryOp::Mod => "mod",
+        BinaryOp::Pow => "pow",
+        BinaryOp::BinaryAnd => "binary_and",
+        BinaryOp::BinaryOr => "binary_or",
+        BinaryOp::BinaryXor => "binary_xor",
+        BinaryOp::BinaryShl => "binary_shl",
+        BinaryOp::BinaryShr => "binary_shr",
+        BinaryOp::BoolAnd => "bool_and",
+        BinaryOp::BoolOr => "bool_or",
+        BinaryOp::Eq => "eq",
+        BinaryOp::NotEq => "not_eq",
+        BinaryOp::Less => "less",
+        BinaryOp::LessEq => "less_eq",
+        BinaryOp::Greater => "greater",
+        BinaryOp::GreaterEq => "greater_eq",
+    })
+}
+
+fn unary_op(op: &UnaryOp) -> Value {
+    Value::Symbol(match op {
+        UnaryOp::Plus => "plus",
+        UnaryOp::Minus => "minus",
+        UnaryOp::Not => "not",
+        UnaryOp::Invert => "invert",
+    })
+}
+
+fn list<T: Clone>(items: &Spanned<Box<[Spanned<T>]>>, f: fn(&Spanned<T>) -> Node) -> Vec<Node> {
+    items.value_ref().iter().map(f).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::token_stream::TokenStream;
+    use crate::parser::core::Parser;
+    use crate::span::Spanned;
+    use std::path::Path;
+    use test_case::test_case;
+
+    fn parse(source: &str) -> Spanned<CompilationUnit> {
+        let mut errors: Vec<Spanned<crate::error::ParserError>> = Vec::new();
+        let mut parser = Parser::new(TokenStream::new(source), Path::new("test.hkl"), &mut errors);
+        parser.parse().unwrap()
+    }
+
+    #[test]
+    fn units_are_dumped_as_json() {
+        // Given
+        let unit = parse("fn f(x: i32) = -x;");
+
+        // When
+        let json = dump_compilation_unit(&unit).to_json();
+
+        // Then
+        assert_eq!(
+            json,
+            r#"{
+  "kind": "ast",
+  "version": 1,
+  "unit": {
+    "kind": "compilation_unit",
+    "span": [0, 18],
+    "path": "test.hkl",
+    "name": "test.hkl",
+    "doc": null,
+    "members": [
+      {
+        "kind": "function",
+        "span": [0, 18],
+        "doc": null,
+        "attributes": [],
+        "visibility": "private",
+        "name": {
+          "kind": "identifier",
+          "span": [3, 4],
+          "name": "f"
+        },
+        "parameters": [
+          {
+            "kind": "parameter",
+            "span": [5, 11],
+            "doc": null,
+            "name": {
+              "kind": "identifier",
+              "span": [5, 6],
+              "name": "x"
+            },
+            "type": {
+              "kind": "path_type",
+              "span": [8, 11],
+              "qualifier": [],
+              "name": {
+                "kind": "identifier",
+                "span": [8, 11],
+                "name": "i32"
+              }
+            }
+          }
+        ],
+        "parameters_span": [4, 12],
+        "return_type": null,
+        "body": {
+          "kind": "expr_statement",
+          "span": [15, 17],
+          "expr": {
+            "kind": "unary",
+            "span": null,
+            "op": "minus",
+            "value": {
+              "kind": "path",
+              "span": [16, 17],
+              "qualifier": [],
+              "name": {
+                "kind": "identifier",
+                "span": [16, 17],
+                "name": "x"
+              }
+            }
+          }
+        }
+      }
+    ]
+  }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn units_are_dumped_as_sexprs() {
+        // Given
+        let unit = parse("use a::b; struct P { pub x: (i32, fn(u8) -> bool); }");
+
+        // When
+        let sexpr = dump_compilation_unit(&unit).to_sexpr();
+
+        // Then
+        assert_eq!(
+            sexpr,
+            r#"(ast :version 1
+  :unit (compilation_unit :span (0 52) :path "test.hkl" :name "test.hkl" :doc nil
+    :members (
+      (use :span (0 8)
+        :path (path :span (4 8)
+          :qualifier (
+            (identifier :span (4 5) :name "a"))
+          :name (identifier :span (7 8) :name "b")))
+      (struct :span (10 52) :doc nil :attributes () :visibility private
+        :name (identifier :span (17 18) :name "P")
+        :members (
+          (struct_member :span (21 49) :doc nil :attributes () :visibility public
+            :name (identifier :span (25 26) :name "x")
+            :type (tuple_type :span (28 49)
+              :elements (
+                (path_type :span (29 32) :qualifier ()
+                  :name (identifier :span (29 32) :name "i32"))
+                (function_type :span (34 48)
+                  :parameters (
+                    (path_type :span (37 39) :qualifier ()
+                      :name (identifier :span (37 39) :name "u8")))
+                  :parameters_span (36 40)
+                  :return_type (path_type :span (44 48) :qualifier ()
+                    :name (identifier :span (44 48) :name "bool")))))))))))
+"#
+        );
+    }
+
+    // Between them, these cover every kind of declaration, statement, pattern and expression.
+    #[test_case(                                                              "use a::b;",                                                 &["use", "path", "identifier"] ; "use declarations")]
+    #[test_case(                    "/// d\n#[link_name(\"e\")] pub extern fn e(x: i32);",                &["extern_function", "doc", "attribute", "parameter", "string"] ; "extern functions")]
+    #[test_case(                                      "#[test] fn f(g: fn(i32)) -> () {}",             &["function", "attribute", "function_type", "tuple_type", "block"] ; "functions")]
+    #[test_case(                                                   "struct S { x: i32; }",                                      &["struct", "struct_member", "path_type"] ; "structs")]
+    #[test_case(                          "fn f() { ; let (a, b): (i32, i32) = (1, 2); }",             &["empty", "let", "tuple_pattern", "name_pattern", "tuple", "int"] ; "declarations")]
+    #[test_case(                     "fn f() { if (true) { break; } else { continue; } }",                                           &["if", "bool", "break", "continue"] ; "conditionals")]
+    #[test_case(                       "fn f() { while (false) { return; } return 1.5; }",                                                  &["while", "return", "float"] ; "loops")]
+    #[test_case(                                          "fn f() { a.b[0] += !c ** 2; }", &["expr_statement", "assignment", "member_access", "index", "unary", "binary"] ; "operators")]
+    #[test_case("fn f() = g(|x: u8| -> i32 { return x; }, |y| y, \'c\', b\'d\', b\"e\");",       &["call", "closure", "closure_parameter", "char", "byte", "byte_string"] ; "calls")]
+    fn every_kind_is_dumped(source: &str, kinds: &[&str]) {
+        // Given
+        let unit = parse(source);
+
+        // When
+        let json = dump_compilation_unit(&unit).to_json();
+
+        // Then
+        for kind in kinds {
+            assert!(
+                json.contains(&format!("\"kind\": \"{}\"", kind)),
+                "expected a {} node in {}",
+                kind,
+                json
+            );
+        }
+    }
+}
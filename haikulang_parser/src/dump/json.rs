@@ -0,0 +1,158 @@
+//! Writing dumps as JSON, indented by two spaces so that they diff well as test snapshots.
+use crate::dump::{Node, Value};
+use std::fmt::Write;
+
+const INDENT: &str = "  ";
+
+pub fn render(node: &Node) -> String {
+    let mut output = String::new();
+    write_node(&mut output, node, 0);
+    output.push('\n');
+    output
+}
+
+fn write_node(output: &mut String, node: &Node, depth: usize) {
+    output.push_str("{\n");
+    indent(output, depth + 1);
+    output.push_str("\"kind\": ");
+    write_string(output, node.kind());
+    for (name, value) in node.fields() {
+        output.push_str(",\n");
+        indent(output, depth + 1);
+        write_string(output, name);
+        output.push_str(": ");
+        write_value(output, value, depth + 1);
+    }
+    output.push('\n');
+    indent(output, depth);
+    output.push('}');
+}
+
+fn write_value(output: &mut String, value: &Value, depth: usize) {
+    match value {
+        Value::Null => output.push_str("null"),
+        Value::Bool(value) => write!(output, "{}", value).unwrap(),
+        Value::Integer(value) => write!(output, "{}", value).unwrap(),
+        Value::String(value) => write_string(output, value),
+        Value::Symbol(value) => write_string(output, value),
+        Value::List(values) if values.is_empty() => output.push_str("[]"),
+        Value::List(values) if value.is_simple() => {
+            output.push('[');
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    output.push_str(", ");
+                }
+                write_value(output, value, depth);
+            }
+            output.push(']');
+        }
+        Value::List(values) => {
+            output.push_str("[\n");
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    output.push_str(",\n");
+                }
+                indent(output, depth + 1);
+                write_value(output, value, depth + 1);
+            }
+            output.push('\n');
+            indent(output, depth);
+            output.push(']');
+        }
+        Value::Node(node) => write_node(output, node, depth),
+    }
+}
+
+fn write_string(output: &mut String, value: &str) {
+    output.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if c.is_control() => {
+                // Characters outside of the basic multilingual plane would need a surrogate
+                // pair, but none of them are control characters.
+                write!(output, "\\u{:04x}", c as u32).unwrap();
+            }
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+}
+
+fn indent(output: &mut String, depth: usize) {
+    for _ in 0..depth {
+        output.push_str(INDENT);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::span::Span;
+    use test_case::test_case;
+
+    #[test]
+    fn nodes_are_rendered() {
+        // Given
+        let node = Node::new("root")
+            .field("version", 1u64)
+            .field(
+                "children",
+                vec![
+                    Node::spanned("leaf", Span::new(0, 3)).field("name", "foo"),
+                    Node::spanned("leaf", Span::UNSET).field("op", Value::Symbol("add")),
+                ],
+            )
+            .field("empty", Vec::<Node>::new())
+            .field("missing", Option::<Node>::None)
+            .field("flag", true);
+
+        // When
+        let json = render(&node);
+
+        // Then
+        assert_eq!(
+            json,
+            r#"{
+  "kind": "root",
+  "version": 1,
+  "children": [
+    {
+      "kind": "leaf",
+      "span": [0, 3],
+      "name": "foo"
+    },
+    {
+      "kind": "leaf",
+      "span": null,
+      "op": "add"
+    }
+  ],
+  "empty": [],
+  "missing": null,
+  "flag": true
+}
+"#
+        );
+    }
+
+    #[test_case(          "plain",               "\"plain\"" ; "plain text")]
+    #[test_case(    "say \"hi\"",       "\"say \\\"hi\\\"\"" ; "quotes")]
+    #[test_case(        "a\\b\n",           "\"a\\\\b\\n\"" ; "backslash and line feed")]
+    #[test_case(       "\t\r\u{7}",  "\"\\t\\r\\u0007\"" ; "control characters")]
+    #[test_case(            "é😀",                "\"é😀\"" ; "unicode")]
+    fn strings_are_escaped(value: &str, expected: &str) {
+        // Given
+        let mut output = String::new();
+
+        // When
+        write_string(&mut output, value);
+
+        // Then
+        assert_eq!(output, expected);
+    }
+}
@@ -0,0 +1,260 @@
+//! Machine-readable dumps of tokens and ASTs, for tools and test snapshots to consume.
+//!
+//! Unlike the `Debug` output of the AST, the shape of a dump is a documented schema that is
+//! versioned by [`SCHEMA_VERSION`]. Renaming or removing a node kind or field, or changing
+//! what a field holds, increments the version. Adding a new kind or field does not, so tools
+//! should ignore anything that they do not recognise.
+//!
+//! # Encodings
+//!
+//! A dump is a tree of nodes that can be written either as JSON or as an S-expression.
+//!
+//! In JSON, each node is an object. Its `"kind"` member comes first, followed by the other
+//! fields of that kind in the order that they are listed below. Lists are arrays, absent
+//! values are `null`, and enumerated values such as operators are strings.
+//!
+//! In an S-expression, each node is a list that starts with its kind as a symbol, followed
+//! by each field as a keyword and its value, such as `(identifier :span (4 7) :name "foo")`.
+//! Lists are written in parentheses, absent values are `nil`, booleans are `true` and
+//! `false`, and enumerated values are bare symbols. Strings are quoted, escaping `"` and `\`
+//! with a backslash, line feeds, carriage returns and tabs as `\n`, `\r` and `\t`, and any
+//! other control character as `\u{...}`.
+//!
+//! Every span is a list of two integers, the byte offsets of the start and the end of the
+//! source that the node covers. A span is only absent where the parser does not record one,
+//! which is for the expression of an expression statement. That expression covers the same
+//! source as the statement does.
+//!
+//! # Tokens
+//!
+//! The root is a `tokens` node, with the fields `version` and `tokens`, which lists every
+//! token in the order that it appears in the source, ending with an `eof` token.
+//!
+//! Every token has a `span`. Keywords and punctuation have no other fields, and are named
+//! after the token in snake case, such as `fn`, `left_brace` or `binary_shl_assign`. The
+//! remaining tokens also have a `value`:
+//!
+//! | Kind                          | Value                                             |
+//! |-------------------------------|---------------------------------------------------|
+//! | `identifier`                  | the normalized name                               |
+//! | `*_comment`                   | the text of the comment, without its delimiters   |
+//! | `string_lit`                  | the string, with escape sequences resolved        |
+//! | `char_lit`                    | a string holding the character                    |
+//! | `byte_lit`                    | the byte as an integer                            |
+//! | `byte_string_lit`             | a list of the bytes as integers                   |
+//! | `int_lit`, `float_lit`        | the number as a decimal string                    |
+//!
+//! Number tokens also have a `type` between `span` and `value`, which is the suffix of the
+//! literal, such as `i32` or `f64`, or `untyped` for literals without a suffix.
+//!
+//! # ASTs
+//!
+//! The root is an `ast` node, with the fields `version` and `unit`, which holds the
+//! compilation unit. Every other node has a `span` as its first field. Fields that are marked
+//! as optional may be absent.
+//!
+//! Declarations:
+//!
+//! | Kind                | Fields                                                                 |
+//! |---------------------|------------------------------------------------------------------------|
+//! | `compilation_unit`  | `path`, `name`, `doc`?, `members`                                      |
+//! | `use`               | `path`                                                                 |
+//! | `extern_function`   | `doc`?, `attributes`, `visibility`, `name`, `parameters`, `parameters_span`, `return_type`? |
+//! | `function`          | `doc`?, `attributes`, `visibility`, `name`, `parameters`, `parameters_span`, `return_type`?, `body` |
+//! | `parameter`         | `doc`?, `name`, `type`                                                 |
+//! | `struct`            | `doc`?, `attributes`, `visibility`, `name`, `members`                  |
+//! | `struct_member`     | `doc`?, `attributes`, `visibility`, `name`, `type`                     |
+//! | `attribute`         | `name`, `arguments`?, `arguments_span`?                                |
+//! | `doc`               | `text`                                                                 |
+//! | `identifier`        | `name`, as a string                                                    |
+//! | `path`              | `qualifier`, a list of identifiers, and `name`, an identifier         |
+//!
+//! Names are `identifier` nodes, and a `visibility` is either `public` or `private`. The
+//! arguments of an attribute are absent when it has no parentheses at all. Fields ending in
+//! `_span` hold the span of the list before them, including its delimiters.
+//!
+//! Types:
+//!
+//! | Kind             | Fields                                              |
+//! |------------------|-----------------------------------------------------|
+//! | `path_type`      | `qualifier`, `name`, as for a `path`                |
+//! | `function_type`  | `parameters`, `parameters_span`, `return_type`?     |
+//! | `tuple_type`     | `elements`                                          |
+//!
+//! Statements:
+//!
+//! | Kind              | Fields                                    |
+//! |-------------------|-------------------------------------------|
+//! | `empty`           |                                           |
+//! | `expr_statement`  | `expr`                                    |
+//! | `let`             | `pattern`, `type`?, `value`?              |
+//! | `if`              | `condition`, `body`, `otherwise`?         |
+//! | `while`           | `condition`, `body`                       |
+//! | `block`           | `statements`                              |
+//! | `break`           |                                           |
+//! | `continue`        |                                           |
+//! | `return`          | `value`?                                  |
+//! | `name_pattern`    | `name`, as a string                       |
+//! | `tuple_pattern`   | `elements`                                |
+//!
+//! Expressions:
+//!
+//! | Kind                | Fields                                                       |
+//! |---------------------|--------------------------------------------------------------|
+//! | `binary`            | `op`, `left`, `right`                                        |
+//! | `unary`             | `op`, `value`                                                |
+//! | `assignment`        | `op`?, `target`, `value`                                     |
+//! | `member_access`     | `owner`, `member`                                            |
+//! | `index`             | `owner`, `index`                                             |
+//! | `call`              | `callee`, `arguments`, `arguments_span`                      |
+//! | `closure`           | `parameters`, `parameters_span`, `return_type`?, `body`      |
+//! | `closure_parameter` | `name`, `type`?                                              |
+//! | `tuple`             | `elements`                                                   |
+//! | `int`               | `type`, `value`, as for an `int_lit` token                   |
+//! | `float`             | `type`, `value`, as for a `float_lit` token                  |
+//! | `bool`              | `value`                                                      |
+//! | `string`            | `value`                                                      |
+//! | `char`              | `value`                                                      |
+//! | `byte`              | `value`                                                      |
+//! | `byte_string`       | `value`                                                      |
+//! | `path`              | `qualifier`, `name`                                          |
+//!
+//! The `op` of a binary expression is one of `add`, `sub`, `mul`, `div`, `mod`, `pow`,
+//! `binary_and`, `binary_or`, `binary_xor`, `binary_shl`, `binary_shr`, `bool_and`,
+//! `bool_or`, `eq`, `not_eq`, `less`, `less_eq`, `greater` or `greater_eq`. Compound
+//! assignments such as `+=` use the same operators, and plain assignments have no `op`. The
+//! `op` of a unary expression is one of `plus`, `minus`, `not` or `invert`. The body of an
+//! expression function or closure is an `expr_statement`.
+pub mod ast;
+pub mod json;
+pub mod sexpr;
+pub mod token;
+
+use crate::span::Span;
+
+/// The version of the schema that dumps follow.
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// A node within a dump, which can be written in any of the supported encodings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    kind: &'static str,
+    fields: Vec<(&'static str, Value)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Integer(u64),
+    String(String),
+    // An enumerated value, such as an operator, which is written as a bare symbol where the
+    // encoding has them.
+    Symbol(&'static str),
+    List(Vec<Value>),
+    Node(Node),
+}
+
+impl Node {
+    pub fn new(kind: &'static str) -> Self {
+        Self {
+            kind,
+            fields: Vec::new(),
+        }
+    }
+
+    // Create a node that starts with a span field.
+    pub fn spanned(kind: &'static str, span: Span) -> Self {
+        Self::new(kind).field("span", span)
+    }
+
+    pub fn field(mut self, name: &'static str, value: impl Into<Value>) -> Self {
+        self.fields.push((name, value.into()));
+        self
+    }
+
+    pub fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    pub fn fields(&self) -> &[(&'static str, Value)] {
+        &self.fields
+    }
+
+    pub fn to_json(&self) -> String {
+        json::render(self)
+    }
+
+    pub fn to_sexpr(&self) -> String {
+        sexpr::render(self)
+    }
+}
+
+impl Value {
+    // Whether the value can be written on a single line without hiding the structure of
+    // the tree, which is true of anything other than a node or a list holding one.
+    pub(crate) fn is_simple(&self) -> bool {
+        match self {
+            Value::Node(_) => false,
+            Value::List(values) => values
+                .iter()
+                .all(|value| !matches!(value, Value::Node(_) | Value::List(_))),
+            _ => true,
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<u64> for Value {
+    fn from(value: u64) -> Self {
+        Value::Integer(value)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl From<Node> for Value {
+    fn from(value: Node) -> Self {
+        Value::Node(value)
+    }
+}
+
+impl From<Span> for Value {
+    fn from(span: Span) -> Self {
+        if span.is_unset() {
+            Value::Null
+        } else {
+            Value::List(vec![
+                Value::Integer(span.start() as u64),
+                Value::Integer(span.end() as u64),
+            ])
+        }
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(values: Vec<T>) -> Self {
+        Value::List(values.into_iter().map(Into::into).collect())
+    }
+}
@@ -0,0 +1,161 @@
+//! Writing dumps as S-expressions.
+//!
+//! Fields holding plain values stay on the line of their node until the first field that
+//! holds other nodes. Every field from then on starts a new line, indented beneath the node.
+use crate::dump::{Node, Value};
+use std::fmt::Write;
+
+const INDENT: &str = "  ";
+
+pub fn render(node: &Node) -> String {
+    let mut output = String::new();
+    write_node(&mut output, node, 0);
+    output.push('\n');
+    output
+}
+
+fn write_node(output: &mut String, node: &Node, depth: usize) {
+    output.push('(');
+    output.push_str(node.kind());
+    let mut wrapping = false;
+    for (name, value) in node.fields() {
+        wrapping |= !value.is_simple();
+        if wrapping {
+            output.push('\n');
+            indent(output, depth + 1);
+        } else {
+            output.push(' ');
+        }
+        output.push(':');
+        output.push_str(name);
+        output.push(' ');
+        write_value(output, value, depth + 1);
+    }
+    output.push(')');
+}
+
+fn write_value(output: &mut String, value: &Value, depth: usize) {
+    match value {
+        Value::Null => output.push_str("nil"),
+        Value::Bool(value) => write!(output, "{}", value).unwrap(),
+        Value::Integer(value) => write!(output, "{}", value).unwrap(),
+        Value::String(value) => write_string(output, value),
+        Value::Symbol(value) => output.push_str(value),
+        Value::List(values) if value.is_simple() => {
+            output.push('(');
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    output.push(' ');
+                }
+                write_value(output, value, depth);
+            }
+            output.push(')');
+        }
+        Value::List(values) => {
+            output.push('(');
+            for value in values {
+                output.push('\n');
+                indent(output, depth + 1);
+                write_value(output, value, depth + 1);
+            }
+            output.push(')');
+        }
+        Value::Node(node) => write_node(output, node, depth),
+    }
+}
+
+fn write_string(output: &mut String, value: &str) {
+    output.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if c.is_control() => write!(output, "\\u{{{:x}}}", c as u32).unwrap(),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+}
+
+fn indent(output: &mut String, depth: usize) {
+    for _ in 0..depth {
+        output.push_str(INDENT);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::span::Span;
+    use test_case::test_case;
+
+    #[test]
+    fn nodes_are_rendered() {
+        // Given
+        let node = Node::new("root")
+            .field("version", 1u64)
+            .field(
+                "children",
+                vec![
+                    Node::spanned("leaf", Span::new(0, 3)).field("name", "foo"),
+                    Node::spanned("leaf", Span::UNSET).field("op", Value::Symbol("add")),
+                ],
+            )
+            .field("empty", Vec::<Node>::new())
+            .field("missing", Option::<Node>::None)
+            .field("flag", true);
+
+        // When
+        let sexpr = render(&node);
+
+        // Then
+        assert_eq!(
+            sexpr,
+            r#"(root :version 1
+  :children (
+    (leaf :span (0 3) :name "foo")
+    (leaf :span nil :op add))
+  :empty ()
+  :missing nil
+  :flag true)
+"#
+        );
+    }
+
+    #[test]
+    fn nested_nodes_are_indented() {
+        // Given
+        let node = Node::new("outer").field(
+            "inner",
+            Node::new("middle").field("leaf", Node::new("leaf").field("value", 2u64)),
+        );
+
+        // When
+        let sexpr = render(&node);
+
+        // Then
+        assert_eq!(
+            sexpr,
+            "(outer\n  :inner (middle\n    :leaf (leaf :value 2)))\n"
+        );
+    }
+
+    #[test_case(          "plain",               "\"plain\"" ; "plain text")]
+    #[test_case(    "say \"hi\"",       "\"say \\\"hi\\\"\"" ; "quotes")]
+    #[test_case(        "a\\b\n",           "\"a\\\\b\\n\"" ; "backslash and line feed")]
+    #[test_case(       "\t\r\u{7}",   "\"\\t\\r\\u{7}\"" ; "control characters")]
+    #[test_case(            "é😀",                "\"é😀\"" ; "unicode")]
+    fn strings_are_escaped(value: &str, expected: &str) {
+        // Given
+        let mut output = String::new();
+
+        // When
+        write_string(&mut output, value);
+
+        // Then
+        assert_eq!(output, expected);
+    }
+}
@@ -0,0 +1,221 @@
+//! Dumping of token streams.
+use crate::dump::{Node, SCHEMA_VERSION, Value};
+use crate::lexer::literals::{FloatLit, IntLit};
+use crate::lexer::token::Token;
+use crate::span::Spanned;
+
+pub fn dump_tokens(tokens: &[Spanned<Token>]) -> Node {
+    let tokens: Vec<Node> = tokens.iter().map(token).collect();
+    Node::new("tokens")
+        .field("version", SCHEMA_VERSION)
+        .field("tokens", tokens)
+}
+
+fn token(token: &Spanned<Token>) -> Node {
+    let node = Node::spanned(token_kind(token.value_ref()), token.span());
+    match token.value_ref() {
+        Token::InlineComment(value)
+        | Token::InlineDocComment(value)
+        | Token::InlineInnerDocComment(value)
+        | Token::MultilineComment(value)
+        | Token::MultilineDocComment(value)
+        | Token::MultilineInnerDocComment(value)
+        | Token::Identifier(value)
+        | Token::StringLit(value) => node.field("value", value.as_ref()),
+        Token::CharLit(value) => node.field("value", value.to_string()),
+        Token::ByteLit(value) => node.field("value", *value as u64),
+        Token::ByteStringLit(value) => node.field("value", bytes(value)),
+        Token::IntLit(value) => int_lit(node, value),
+        Token::FloatLit(value) => float_lit(node, *value),
+        _ => node,
+    }
+}
+
+// The name of each token is part of the schema, so these are spelled out rather than being
+// derived from the names of the variants.
+fn token_kind(token: &Token) -> &'static str {
+    match token {
+        Token::Eof => "eof",
+        Token::InlineComment(_) => "inline_comment",
+        Token::InlineDocComment(_) => "inline_doc_comment",
+        Token::InlineInnerDocComment(_) => "inline_inner_doc_comment",
+        Token::MultilineComment(_) => "multiline_comment",
+        Token::MultilineDocComment(_) => "multiline_doc_comment",
+        Token::MultilineInnerDocComment(_) => "multiline_inner_doc_comment",
+        Token::Identifier(_) => "identifier",
+        Token::StringLit(_) => "string_lit",
+        Token::CharLit(_) => "char_lit",
+        Token::ByteLit(_) => "byte_lit",
+        Token::ByteStringLit(_) => "byte_string_lit",
+        Token::IntLit(_) => "int_lit",
+        Token::FloatLit(_) => "float_lit",
+        Token::True => "true",
+        Token::False => "false",
+        Token::Extern => "extern",
+        Token::Fn => "fn",
+        Token::Pub => "pub",
+        Token::Struct => "struct",
+        Token::Return => "return",
+        Token::Continue => "continue",
+        Token::Break => "break",
+        Token::If => "if",
+        Token::Else => "else",
+        Token::For => "for",
+        Token::While => "while",
+        Token::Let => "let",
+        Token::Use => "use",
+        Token::Semicolon => "semicolon",
+        Token::Hash => "hash",
+        Token::LeftBrace => "left_brace",
+        Token::RightBrace => "right_brace",
+        Token::LeftParen => "left_paren",
+        Token::RightParen => "right_paren",
+        Token::LeftBracket => "left_bracket",
+        Token::RightBracket => "right_bracket",
+        Token::Period => "period",
+        Token::Comma => "comma",
+        Token::Colon => "colon",
+        Token::DoubleColon => "double_colon",
+        Token::Arrow => "arrow",
+        Token::Assign => "assign",
+        Token::Add => "add",
+        Token::Sub => "sub",
+        Token::Mul => "mul",
+        Token::Div => "div",
+        Token::Mod => "mod",
+        Token::Pow => "pow",
+        Token::AddAssign => "add_assign",
+        Token::SubAssign => "sub_assign",
+        Token::MulAssign => "mul_assign",
+        Token::DivAssign => "div_assign",
+        Token::ModAssign => "mod_assign",
+        Token::PowAssign => "pow_assign",
+        Token::BinaryAnd => "binary_and",
+        Token::BinaryOr => "binary_or",
+        Token::BinaryXor => "binary_xor",
+        Token::BinaryNot => "binary_not",
+        Token::BinaryShl => "binary_shl",
+        Token::BinaryShr => "binary_shr",
+        Token::BinaryAndAssign => "binary_and_assign",
+        Token::BinaryOrAssign => "binary_or_assign",
+        Token::BinaryXorAssign => "binary_xor_assign",
+        Token::BinaryShlAssign => "binary_shl_assign",
+        Token::BinaryShrAssign => "binary_shr_assign",
+        Token::BoolAnd => "bool_and",
+        Token::BoolOr => "bool_or",
+        Token::BoolNot => "bool_not",
+        Token::Eq => "eq",
+        Token::NotEq => "not_eq",
+        Token::Less => "less",
+        Token::LessEq => "less_eq",
+        Token::Greater => "greater",
+        Token::GreaterEq => "greater_eq",
+    }
+}
+
+// Add the type and value of an integer literal to a node. Values are written as strings,
+// as untyped literals can be larger than any integer that JSON parsers handle exactly.
+pub(crate) fn int_lit(node: Node, value: &IntLit) -> Node {
+    let (ty, value) = match value {
+        IntLit::I8(value) => ("i8", value.to_string()),
+        IntLit::I16(value) => ("i16", value.to_string()),
+        IntLit::I32(value) => ("i32", value.to_string()),
+        IntLit::I64(value) => ("i64", value.to_string()),
+        IntLit::I128(value) => ("i128", value.to_string()),
+        IntLit::ISize(value) => ("isize", value.to_string()),
+        IntLit::U8(value) => ("u8", value.to_string()),
+        IntLit::U16(value) => ("u16", value.to_string()),
+        IntLit::U32(value) => ("u32", value.to_string()),
+        IntLit::U64(value) => ("u64", value.to_string()),
+        IntLit::U128(value) => ("u128", value.to_string()),
+        IntLit::USize(value) => ("usize", value.to_string()),
+        IntLit::Untyped(value) => ("untyped", value.to_string()),
+    };
+    node.field("type", Value::Symbol(ty)).field("value", value)
+}
+
+// Add the type and value of a float literal to a node, as the shortest decimal string that
+// reads back as the same value.
+pub(crate) fn float_lit(node: Node, value: FloatLit) -> Node {
+    let (ty, value) = match value {
+        FloatLit::F32(value) => ("f32", value.to_string()),
+        FloatLit::F64(value) => ("f64", value.to_string()),
+        FloatLit::Untyped(value) => ("untyped", value.to_string()),
+    };
+    node.field("type", Value::Symbol(ty)).field("value", value)
+}
+
+pub(crate) fn bytes(value: &[u8]) -> Vec<Value> {
+    value
+        .iter()
+        .map(|byte| Value::Integer(*byte as u64))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::token_stream::TokenStream;
+
+    fn lex(source: &str) -> Vec<Spanned<Token>> {
+        let mut stream = TokenStream::new(source);
+        let mut tokens = Vec::new();
+        loop {
+            let token = stream.current().unwrap();
+            tokens.push(token.clone());
+            if token.value() == Token::Eof {
+                return tokens;
+            }
+            stream.advance();
+        }
+    }
+
+    #[test]
+    fn tokens_are_dumped() {
+        // Given
+        let tokens = lex("let x = 1_000u16 + 2.5; // done\nb\"hi\" 'c'");
+
+        // When
+        let sexpr = dump_tokens(&tokens).to_sexpr();
+
+        // Then
+        assert_eq!(
+            sexpr,
+            r#"(tokens :version 1
+  :tokens (
+    (let :span (0 3))
+    (identifier :span (4 5) :value "x")
+    (assign :span (6 7))
+    (int_lit :span (8 16) :type u16 :value "1000")
+    (add :span (17 18))
+    (float_lit :span (19 22) :type untyped :value "2.5")
+    (semicolon :span (22 23))
+    (inline_comment :span (24 32) :value " done\n")
+    (byte_string_lit :span (32 37) :value (104 105))
+    (char_lit :span (38 41) :value "c")
+    (eof :span (41 41))))
+"#
+        );
+    }
+
+    #[test]
+    fn every_token_has_a_distinct_kind() {
+        // Given
+        let source = "true false extern fn pub struct return continue break if else for while \
+            let use ; # { } ( ) [ ] . , : :: -> = + - * / % ** += -= *= /= %= **= & | ^ ~ << >> \
+            &= |= ^= <<= >>= && || ! == != < <= > >=";
+        let tokens = lex(source);
+
+        // When
+        let mut kinds: Vec<&str> = tokens
+            .iter()
+            .map(|token| token_kind(token.value_ref()))
+            .collect();
+        let count = kinds.len();
+        kinds.sort();
+        kinds.dedup();
+
+        // Then
+        assert_eq!(kinds.len(), count);
+    }
+}
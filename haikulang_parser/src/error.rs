@@ -1,5 +1,6 @@
 use crate::span::Spanned;
 use std::fmt::{Display, Formatter};
+use std::ops::Range;
 
 pub type ParserResult<T> = Result<Spanned<T>, Spanned<ParserError>>;
 
@@ -10,9 +11,17 @@ pub enum ParserError {
 
     // Lexer issues.
     InvalidStringLit(String),
+    // The range is relative to the start of the string literal, so that the error can point
+    // at the escape sequence itself rather than the whole literal.
+    InvalidEscapeSequence {
+        message: String,
+        range: Range<usize>,
+    },
+    InvalidCharLit(String),
     InvalidIntLit(String),
     InvalidFloatLit(String),
     UnclosedStringLit(String),
+    UnterminatedBlockComment,
     UnknownToken(String),
 
     // Emitted by Logos if we hit an unexpected issue.
@@ -25,17 +34,86 @@ impl Display for ParserError {
         match self {
             Self::SyntaxError(text) => write!(f, "syntax error in file: {}", text),
             Self::InvalidStringLit(text) => write!(f, "invalid string literal: {}", text),
+            Self::InvalidEscapeSequence { message, .. } => {
+                write!(f, "invalid escape sequence: {}", message)
+            }
+            Self::InvalidCharLit(text) => write!(f, "invalid character literal: {}", text),
             Self::InvalidIntLit(text) => write!(f, "invalid int literal: {}", text),
             Self::InvalidFloatLit(text) => write!(f, "invalid float literal: {}", text),
             Self::UnclosedStringLit(text) => write!(f, "unclosed string literal: {}", text),
+            Self::UnterminatedBlockComment => write!(f, "unterminated block comment"),
             Self::UnknownToken(value) => write!(f, "unknown token in input: {}", value),
             Self::UnknownError => write!(f, "unknown error"),
         }
     }
 }
 
+impl ParserError {
+    /// The part of the token that this error applies to, relative to the start of the
+    /// token, if it only applies to part of it.
+    pub fn range_within_token(&self) -> Option<Range<usize>> {
+        match self {
+            Self::InvalidEscapeSequence { range, .. } => Some(range.clone()),
+            // Point at the opening "/*", as the comment otherwise runs to the end of the file.
+            Self::UnterminatedBlockComment => Some(0..2),
+            _ => None,
+        }
+    }
+}
+
+/// Issues that do not stop the input from being parsed, but that are probably mistakes.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParserWarning {
+    MixedScriptIdentifier(String),
+    ConfusableIdentifiers { identifier: String, other: String },
+}
+
+impl Display for ParserWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MixedScriptIdentifier(name) => write!(
+                f,
+                "identifier {} mixes characters from different scripts",
+                name
+            ),
+            Self::ConfusableIdentifiers { identifier, other } => write!(
+                f,
+                "identifier {} looks similar to {}, so they may be confused",
+                identifier, other
+            ),
+        }
+    }
+}
+
 pub trait ErrorReporter {
     fn report(&mut self, error: &Spanned<ParserError>);
+
+    // Warnings do not affect the outcome of parsing, so reporters can choose to ignore them.
+    fn warn(&mut self, _warning: &Spanned<ParserWarning>) {}
+}
+
+// Simple reporter that collects errors so they can be inspected later.
+impl ErrorReporter for Vec<Spanned<ParserError>> {
+    fn report(&mut self, error: &Spanned<ParserError>) {
+        self.push(error.clone());
+    }
+}
+
+/// Reporter that collects both errors and warnings so they can be inspected later.
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    pub errors: Vec<Spanned<ParserError>>,
+    pub warnings: Vec<Spanned<ParserWarning>>,
+}
+
+impl ErrorReporter for Diagnostics {
+    fn report(&mut self, error: &Spanned<ParserError>) {
+        self.errors.push(error.clone());
+    }
+
+    fn warn(&mut self, warning: &Spanned<ParserWarning>) {
+        self.warnings.push(warning.clone());
+    }
 }
 
 #[cfg(test)]
@@ -53,6 +131,21 @@ mod tests {
         "invalid string literal: bad words used"
         ; "InvalidStringLit"
     )]
+    #[test_case(
+        ParserError::InvalidEscapeSequence { message: "unknown escape sequence: \\q".to_string(), range: 1..3 },
+        "invalid escape sequence: unknown escape sequence: \\q"
+        ; "InvalidEscapeSequence"
+    )]
+    #[test_case(
+        ParserError::InvalidCharLit("'ab' must contain exactly one character".to_string()),
+        "invalid character literal: 'ab' must contain exactly one character"
+        ; "InvalidCharLit"
+    )]
+    #[test_case(
+        ParserError::UnterminatedBlockComment,
+        "unterminated block comment"
+        ; "UnterminatedBlockComment"
+    )]
     #[test_case(
         ParserError::InvalidIntLit("0f1d is not a valid int".to_string()),
         "invalid int literal: 0f1d is not a valid int"
@@ -82,4 +175,22 @@ mod tests {
         // Then
         assert_eq!(format!("{}", error), expected);
     }
+
+    #[test_case(
+        ParserWarning::MixedScriptIdentifier("p\u{0430}ypal".to_string()),
+        "identifier p\u{0430}ypal mixes characters from different scripts"
+        ; "MixedScriptIdentifier"
+    )]
+    #[test_case(
+        ParserWarning::ConfusableIdentifiers {
+            identifier: "\u{0430}pple".to_string(),
+            other: "apple".to_string(),
+        },
+        "identifier \u{0430}pple looks similar to apple, so they may be confused"
+        ; "ConfusableIdentifiers"
+    )]
+    fn test_parser_warning_formats_correctly(warning: ParserWarning, expected: &str) {
+        // Then
+        assert_eq!(format!("{}", warning), expected);
+    }
 }
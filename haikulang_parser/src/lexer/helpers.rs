@@ -1,13 +1,16 @@
 use crate::error::ParserError;
-use crate::lexer::literals::{FloatLit, IntLit, StrLit};
+use crate::lexer::literals::{ByteStrLit, FloatLit, IntLit, StrLit};
 use crate::lexer::token::Token;
+use num_bigint::BigUint;
+use num_traits::Num;
+use std::fmt::Display;
 use std::str::FromStr;
+use unicode_normalization::{IsNormalized, UnicodeNormalization, is_nfc_quick};
 
 type HelperResult<T> = Result<T, ParserError>;
 
 pub fn parse_unknown_input(lex: &mut logos::Lexer<Token>) -> ParserError {
-    let text = &lex.slice()[lex.span()];
-    ParserError::UnknownToken(text.to_string())
+    ParserError::UnknownToken(lex.slice().to_string())
 }
 
 pub fn parse_inline_comment(lex: &mut logos::Lexer<Token>) -> StrLit {
@@ -16,91 +19,439 @@ pub fn parse_inline_comment(lex: &mut logos::Lexer<Token>) -> StrLit {
     text[2..].to_string().into_boxed_str()
 }
 
-pub fn parse_multiline_comment(lex: &mut logos::Lexer<Token>) -> StrLit {
+pub fn parse_inline_doc_comment(lex: &mut logos::Lexer<Token>) -> StrLit {
+    let text = lex.slice();
+    // Remove the leading "///" or "//!"
+    text[3..].to_string().into_boxed_str()
+}
+
+pub fn parse_multiline_comment(lex: &mut logos::Lexer<Token>) -> HelperResult<StrLit> {
+    skip_nested_comments(lex)?;
     let text = lex.slice();
     // Remove the leading "/*" and trailing "*/"
-    text[2..text.len() - 2].to_string().into_boxed_str()
+    Ok(text[2..text.len() - 2].to_string().into_boxed_str())
+}
+
+pub fn parse_multiline_doc_comment(lex: &mut logos::Lexer<Token>) -> HelperResult<StrLit> {
+    // The regex for "/**" comments also matches the character after the opening, so that
+    // "/**/" and "/***" are lexed as regular comments instead. That character is never a
+    // "*" or "/", so it cannot be part of a nested comment or the closing "*/".
+    skip_nested_comments(lex)?;
+    let text = lex.slice();
+    // Remove the leading "/**" or "/*!" and trailing "*/"
+    Ok(text[3..text.len() - 2].to_string().into_boxed_str())
+}
+
+// Consume the rest of a block comment, up to and including the "*/" that closes it. Block
+// comments nest, so that code containing comments can itself be commented out.
+fn skip_nested_comments(lex: &mut logos::Lexer<Token>) -> HelperResult<()> {
+    let remainder = lex.remainder().as_bytes();
+    let mut depth = 1usize;
+    let mut index = 0;
+
+    while index + 1 < remainder.len() {
+        match &remainder[index..index + 2] {
+            b"/*" => {
+                depth += 1;
+                index += 2;
+            }
+            b"*/" => {
+                depth -= 1;
+                index += 2;
+                if depth == 0 {
+                    lex.bump(index);
+                    return Ok(());
+                }
+            }
+            _ => index += 1,
+        }
+    }
+
+    // Consume the rest of the input, so that we do not try to lex the commented out code.
+    lex.bump(remainder.len());
+    Err(ParserError::UnterminatedBlockComment)
 }
 
 pub fn parse_identifier(lex: &mut logos::Lexer<Token>) -> StrLit {
-    Box::from(lex.slice())
+    let text = lex.slice();
+    // Identifiers are compared in NFC, so that composed and decomposed forms of the same
+    // characters refer to the same symbol.
+    match is_nfc_quick(text.chars()) {
+        IsNormalized::Yes => Box::from(text),
+        _ => text.nfc().collect::<String>().into_boxed_str(),
+    }
 }
 
 pub fn parse_string(lex: &mut logos::Lexer<Token>) -> HelperResult<StrLit> {
     let unparsed = lex.slice();
 
-    if !unparsed.ends_with('"') {
-        return Err(ParserError::UnclosedStringLit(unparsed.to_string()));
+    // Skip the opening quote. The regex guarantees that it is present.
+    match parse_quoted(
+        unparsed,
+        1,
+        '"',
+        Escapes::Text,
+        ParserError::InvalidStringLit,
+    )? {
+        (parsed, true) => Ok(parsed.into_boxed_str()),
+        (_, false) => Err(ParserError::UnclosedStringLit(unparsed.to_string())),
     }
+}
 
-    let mut parsed = String::new();
+pub fn parse_byte_string(lex: &mut logos::Lexer<Token>) -> HelperResult<ByteStrLit> {
+    let unparsed = lex.slice();
+
+    // Skip the 'b' and the opening quote.
+    match parse_quoted(
+        unparsed,
+        2,
+        '"',
+        Escapes::Bytes,
+        ParserError::InvalidStringLit,
+    )? {
+        (parsed, true) => Ok(parsed.chars().map(|c| c as u8).collect()),
+        (_, false) => Err(ParserError::UnclosedStringLit(unparsed.to_string())),
+    }
+}
+
+pub fn parse_char(lex: &mut logos::Lexer<Token>) -> HelperResult<char> {
+    parse_single_char(lex.slice(), 1, Escapes::Text)
+}
+
+pub fn parse_byte(lex: &mut logos::Lexer<Token>) -> HelperResult<u8> {
+    parse_single_char(lex.slice(), 2, Escapes::Bytes).map(|c| c as u8)
+}
+
+fn parse_single_char(unparsed: &str, start: usize, escapes: Escapes) -> HelperResult<char> {
+    let (parsed, closed) =
+        parse_quoted(unparsed, start, '\'', escapes, ParserError::InvalidCharLit)?;
+
+    if !closed {
+        return Err(ParserError::InvalidCharLit(format!(
+            "missing closing quote: {}",
+            unparsed
+        )));
+    }
 
-    // Start at 1, end at len-1 to remove open and close quotes.
-    let mut offset = 1usize;
+    let mut chars = parsed.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(ParserError::InvalidCharLit(format!(
+            "{} must contain exactly one character",
+            unparsed
+        ))),
+    }
+}
 
-    while offset < unparsed.len() - 1 {
-        match &unparsed[offset..offset + 1] {
-            "\\" => {
-                offset += 1;
-                let (parsed_escape, length) = parse_string_escape_char(unparsed, offset)?;
-                parsed.push_str(parsed_escape);
-                offset += length;
+// Which escape sequences a literal supports, and which characters it can hold.
+#[derive(Clone, Copy, PartialEq)]
+enum Escapes {
+    // Any unicode scalar value.
+    Text,
+    // Only bytes, written as ASCII characters or escape sequences up to \xff.
+    Bytes,
+}
+
+// Parse the content of a quoted literal that cannot span multiple lines, starting just after
+// the opening quote. Returns the parsed content, and whether the closing quote was found.
+fn parse_quoted(
+    unparsed: &str,
+    start: usize,
+    quote: char,
+    escapes: Escapes,
+    invalid: fn(String) -> ParserError,
+) -> HelperResult<(String, bool)> {
+    let mut parsed = String::new();
+    let mut index = start;
+
+    while let Some(c) = unparsed[index..].chars().next() {
+        match c {
+            c if c == quote => return Ok((parsed, true)),
+            '\\' => {
+                let (escaped, length) = parse_escape_sequence(&unparsed[index..], index, escapes)?;
+                parsed.push(escaped);
+                index += length;
+                continue;
             }
-            c => match c.chars().nth(0).unwrap() {
-                '\n' => {
-                    return Err(ParserError::InvalidStringLit(
-                        "unexpected line feed encountered".to_string(),
-                    ));
-                }
-                '\r' => {
-                    return Err(ParserError::InvalidStringLit(
-                        "unexpected carriage return encountered".to_string(),
-                    ));
-                }
-                c if c.is_control() => {
-                    return Err(ParserError::InvalidStringLit(format!(
-                        "unexpected control byte sequence encountered: {}",
-                        c.escape_unicode(),
-                    )));
+            '\n' if escapes == Escapes::Text && quote == '"' => {
+                return Err(invalid(
+                    "unexpected line feed encountered (use \"\"\" for multi-line strings)"
+                        .to_string(),
+                ));
+            }
+            '\n' => return Err(invalid("unexpected line feed encountered".to_string())),
+            '\r' => {
+                return Err(invalid(
+                    "unexpected carriage return encountered".to_string(),
+                ));
+            }
+            c if c.is_control() => return Err(unexpected_control_char(c, invalid)),
+            c if escapes == Escapes::Bytes && !c.is_ascii() => {
+                return Err(invalid(format!(
+                    "{:?} is not an ASCII character, so must be written as escaped bytes",
+                    c
+                )));
+            }
+            c => parsed.push(c),
+        }
+
+        index += c.len_utf8();
+    }
+
+    // We ran out of input before finding an unescaped closing quote.
+    Ok((parsed, false))
+}
+
+pub fn parse_raw_string(lex: &mut logos::Lexer<Token>) -> HelperResult<StrLit> {
+    // So far, we have only matched the 'r', any hashes, and the opening quote. The string
+    // ends at the first quote followed by the same number of hashes.
+    let closing = format!("\"{}", "#".repeat(lex.slice().len() - 2));
+
+    match lex.remainder().find(&closing) {
+        Some(length) => {
+            let content = Box::from(&lex.remainder()[..length]);
+            lex.bump(length + closing.len());
+            Ok(content)
+        }
+        None => {
+            lex.bump(lex.remainder().len());
+            Err(ParserError::UnclosedStringLit(lex.slice().to_string()))
+        }
+    }
+}
+
+pub fn parse_multiline_string(lex: &mut logos::Lexer<Token>) -> HelperResult<StrLit> {
+    // So far, we have only matched the opening quotes. Look for the closing quotes, skipping
+    // over anything that is escaped.
+    let remainder = lex.remainder();
+    let mut index = 0;
+
+    while index < remainder.len() {
+        if remainder[index..].starts_with("\"\"\"") {
+            lex.bump(index + 3);
+            return parse_multiline_string_content(&remainder[..index], 3);
+        }
+
+        let mut chars = remainder[index..].chars();
+        index += match chars.next() {
+            Some('\\') => 1 + chars.next().map_or(0, char::len_utf8),
+            other => other.map_or(1, char::len_utf8),
+        };
+    }
+
+    lex.bump(remainder.len());
+    Err(ParserError::UnclosedStringLit(lex.slice().to_string()))
+}
+
+// Multi-line strings begin on the line after the opening quotes. Indentation that is common
+// to every line is removed, along with the line that the closing quotes are on if there is
+// nothing else on it. The offset is where the content starts within the token.
+fn parse_multiline_string_content(content: &str, offset: usize) -> HelperResult<StrLit> {
+    let is_blank = |line: &str| line.chars().all(|c| c == ' ' || c == '\t');
+
+    let body_start = content
+        .find('\n')
+        .filter(|&index| is_blank(content[..index].trim_end_matches('\r')))
+        .map(|index| index + 1)
+        .ok_or_else(|| {
+            ParserError::InvalidStringLit(
+                "multi-line strings must start on the line after the opening quotes".to_string(),
+            )
+        })?;
+
+    let mut lines = Vec::new();
+    let mut line_start = body_start;
+    for line in content[body_start..].split('\n') {
+        lines.push((line_start, line.strip_suffix('\r').unwrap_or(line)));
+        line_start += line.len() + 1;
+    }
+
+    // The indentation of the closing quotes counts, even though that line is discarded.
+    let closing_line_is_blank = lines.last().is_some_and(|(_, line)| is_blank(line));
+    let indentation = lines
+        .iter()
+        .enumerate()
+        .filter(|(index, (_, line))| {
+            !is_blank(line) || (closing_line_is_blank && *index == lines.len() - 1)
+        })
+        .map(|(_, (_, line))| &line[..line.len() - line.trim_start_matches([' ', '\t']).len()])
+        .reduce(|common, indentation| {
+            let length = common
+                .bytes()
+                .zip(indentation.bytes())
+                .take_while(|(a, b)| a == b)
+                .count();
+            &common[..length]
+        })
+        .unwrap_or("");
+
+    if closing_line_is_blank {
+        lines.pop();
+    }
+
+    let mut parsed = String::new();
+    for (index, (line_start, line)) in lines.into_iter().enumerate() {
+        if index > 0 {
+            parsed.push('\n');
+        }
+
+        // Blank lines may be indented less than the rest of the string.
+        if is_blank(line) {
+            continue;
+        }
+
+        let line_offset = offset + line_start + indentation.len();
+        let line = &line[indentation.len()..];
+        let mut index = 0;
+
+        while let Some(c) = line[index..].chars().next() {
+            match c {
+                '\\' => {
+                    let (escaped, length) =
+                        parse_escape_sequence(&line[index..], line_offset + index, Escapes::Text)?;
+                    parsed.push(escaped);
+                    index += length;
+                    continue;
                 }
-                _ => {
-                    offset += 1;
-                    parsed.push_str(c);
+                c if c.is_control() && c != '\t' => {
+                    return Err(unexpected_control_char(c, ParserError::InvalidStringLit));
                 }
-            },
+                c => parsed.push(c),
+            }
+
+            index += c.len_utf8();
         }
     }
 
     Ok(parsed.into_boxed_str())
 }
 
-fn parse_string_escape_char(text: &str, offset: usize) -> HelperResult<(&str, usize)> {
-    // In theory this could be malformed, but we validate the format for the most part in the regex
-    // before getting this far, so we can make assumptions here.
-    match &text[offset..offset + 1] {
-        "\\" => Ok(("\\", 1)),
-        "\"" => Ok(("\"", 1)),
-        "n" => Ok(("\n", 1)),
-        "r" => Ok(("\r", 1)),
-        "t" => Ok(("\t", 1)),
-        "u" => {
-            let codepoint_string = &text[offset + 1..offset + 5];
-            let bytes = codepoint_string.as_bytes();
-            match str::from_utf8(bytes) {
-                Ok(utf8_char) => Ok((utf8_char, 5)),
-                Err(err) => Err(ParserError::InvalidStringLit(format!(
-                    "failed to parse invalid unicode codepoint \\u{}: {}",
-                    codepoint_string, err
-                ))),
+// Parse the escape sequence at the start of the given text, returning the character that it
+// represents and its length in bytes. The offset is where the escape sequence starts within
+// the token, so that errors can point at the escape sequence itself. Escaped bytes are
+// returned as the character with the same value.
+fn parse_escape_sequence(
+    text: &str,
+    offset: usize,
+    escapes: Escapes,
+) -> HelperResult<(char, usize)> {
+    let invalid = |message: String, length: usize| ParserError::InvalidEscapeSequence {
+        message,
+        range: offset..offset + length,
+    };
+
+    // Skip the backslash, which the caller has already seen.
+    let Some(kind) = text[1..].chars().next() else {
+        return Err(invalid(
+            "incomplete escape sequence at end of string".to_string(),
+            1,
+        ));
+    };
+
+    match kind {
+        '\\' => Ok(('\\', 2)),
+        '"' => Ok(('"', 2)),
+        '\'' => Ok(('\'', 2)),
+        '0' => Ok(('\0', 2)),
+        'n' => Ok(('\n', 2)),
+        'r' => Ok(('\r', 2)),
+        't' => Ok(('\t', 2)),
+        'x' => {
+            // \x7f
+            let digits = count_hex_digits(&text[2..], 2);
+            if digits < 2 {
+                return Err(invalid(
+                    "\\x must be followed by two hex digits".to_string(),
+                    2 + digits,
+                ));
+            }
+
+            let value = u8::from_str_radix(&text[2..4], 16).unwrap();
+            if value > 0x7f && escapes == Escapes::Text {
+                return Err(invalid(
+                    format!(
+                        "\\x{} is not an ASCII character (the maximum is \\x7f)",
+                        &text[2..4]
+                    ),
+                    4,
+                ));
             }
+            Ok((char::from(value), 4))
         }
-        other => Err(ParserError::InvalidStringLit(format!(
-            "unknown escape sequence in string: \\{}",
-            other.escape_default()
-        ))),
+        'u' if escapes == Escapes::Bytes => Err(invalid(
+            "unicode escape sequences cannot be used for bytes".to_string(),
+            2,
+        )),
+        'u' if text[2..].starts_with('{') => {
+            // \u{1F600}
+            let digits = count_hex_digits(&text[3..], usize::MAX);
+            if !text[3 + digits..].starts_with('}') {
+                return Err(invalid(
+                    "unicode escape sequence is missing a closing brace".to_string(),
+                    3 + digits,
+                ));
+            }
+
+            let length = 4 + digits;
+            let value = &text[3..3 + digits];
+            match digits {
+                0 => Err(invalid(
+                    "unicode escape sequence is empty".to_string(),
+                    length,
+                )),
+                1..=6 => u32::from_str_radix(value, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .map(|c| (c, length))
+                    .ok_or_else(|| {
+                        invalid(format!("{} is not a unicode scalar value", value), length)
+                    }),
+                _ => Err(invalid(
+                    "unicode escape sequences can have at most 6 hex digits".to_string(),
+                    length,
+                )),
+            }
+        }
+        'u' => {
+            // \u263a
+            let digits = count_hex_digits(&text[2..], 4);
+            if digits < 4 {
+                return Err(invalid(
+                    "\\u must be followed by four hex digits or a braced value like \\u{1F600}"
+                        .to_string(),
+                    2 + digits,
+                ));
+            }
+
+            let value = &text[2..6];
+            u32::from_str_radix(value, 16)
+                .ok()
+                .and_then(char::from_u32)
+                .map(|c| (c, 6))
+                .ok_or_else(|| invalid(format!("{} is not a unicode scalar value", value), 6))
+        }
+        other => Err(invalid(
+            format!("unknown escape sequence: \\{}", other.escape_default()),
+            1 + other.len_utf8(),
+        )),
     }
 }
 
+// Count how many hex digits the text starts with, up to the given maximum.
+fn count_hex_digits(text: &str, maximum: usize) -> usize {
+    text.bytes()
+        .take(maximum)
+        .take_while(u8::is_ascii_hexdigit)
+        .count()
+}
+
+fn unexpected_control_char(c: char, invalid: fn(String) -> ParserError) -> ParserError {
+    invalid(format!(
+        "unexpected control byte sequence encountered: {}",
+        c.escape_unicode(),
+    ))
+}
+
 pub fn parse_int_lit(lex: &mut logos::Lexer<Token>) -> HelperResult<IntLit> {
     let text = lex.slice();
 
@@ -111,7 +462,7 @@ pub fn parse_int_lit(lex: &mut logos::Lexer<Token>) -> HelperResult<IntLit> {
     } else if text.starts_with("0x") || text.starts_with("0X") {
         parse_int_lit_radix(&text[2..text.len()], 16)
     } else {
-        parse_int_lit_radix(&text, 10)
+        parse_int_lit_radix(text, 10)
     }
 }
 
@@ -122,8 +473,8 @@ fn parse_int_lit_radix(text: &str, radix: u32) -> HelperResult<IntLit> {
     // Split on any suffix if present.
     let (number, suffix) = sanitised_text.split_at(
         sanitised_text
-            .find(|c| matches!(c, 'i' | 'u'))
-            .unwrap_or_else(|| sanitised_text.len()),
+            .find(['i', 'u'])
+            .unwrap_or(sanitised_text.len()),
     );
 
     // Map to the expected type.
@@ -132,23 +483,31 @@ fn parse_int_lit_radix(text: &str, radix: u32) -> HelperResult<IntLit> {
         "i16" => i16::from_str_radix(number, radix).map(IntLit::I16),
         "i32" => i32::from_str_radix(number, radix).map(IntLit::I32),
         "i64" => i64::from_str_radix(number, radix).map(IntLit::I64),
+        "i128" => i128::from_str_radix(number, radix).map(IntLit::I128),
+        "isize" => isize::from_str_radix(number, radix).map(IntLit::ISize),
         "u8" => u8::from_str_radix(number, radix).map(IntLit::U8),
         "u16" => u16::from_str_radix(number, radix).map(IntLit::U16),
         "u32" => u32::from_str_radix(number, radix).map(IntLit::U32),
         "u64" => u64::from_str_radix(number, radix).map(IntLit::U64),
-        // If we have no suffix, we treat it as an i32 for now.
-        _ => i32::from_str_radix(number, radix).map(IntLit::Untyped),
+        "u128" => u128::from_str_radix(number, radix).map(IntLit::U128),
+        "usize" => usize::from_str_radix(number, radix).map(IntLit::USize),
+        // If we have no suffix, the type checker decides the type later on, so we
+        // keep the full value regardless of how large it is.
+        _ => {
+            return BigUint::from_str_radix(number, radix)
+                .map(IntLit::Untyped)
+                .map_err(|err| invalid_int_lit(text, radix, "int", err));
+        }
     };
 
-    result.map_err(|err| {
-        ParserError::InvalidIntLit(format!(
-            "failed to parse base-{} {} value {:?}: {}",
-            radix,
-            if suffix.is_empty() { "int" } else { suffix },
-            text,
-            err
-        ))
-    })
+    result.map_err(|err| invalid_int_lit(text, radix, suffix, err))
+}
+
+fn invalid_int_lit(text: &str, radix: u32, type_name: &str, err: impl Display) -> ParserError {
+    ParserError::InvalidIntLit(format!(
+        "failed to parse base-{} {} value {:?}: {}",
+        radix, type_name, text, err
+    ))
 }
 
 pub fn parse_float_lit(lex: &mut logos::Lexer<Token>) -> HelperResult<FloatLit> {
@@ -156,11 +515,8 @@ pub fn parse_float_lit(lex: &mut logos::Lexer<Token>) -> HelperResult<FloatLit>
     let sanitised_text = lex.slice().replace("_", "");
 
     // Split on any suffix if present.
-    let (number, suffix) = sanitised_text.split_at(
-        sanitised_text
-            .find(|c| matches!(c, 'f'))
-            .unwrap_or_else(|| sanitised_text.len()),
-    );
+    let (number, suffix) =
+        sanitised_text.split_at(sanitised_text.find('f').unwrap_or(sanitised_text.len()));
 
     // Map to the expected type.
     let result = match suffix {
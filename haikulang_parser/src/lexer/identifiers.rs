@@ -0,0 +1,113 @@
+//! Checks for identifiers that are valid, but likely to confuse readers.
+//!
+//! These follow the recommendations of UAX #39. Identifiers that mix characters from
+//! different scripts are reported, as are distinct identifiers that look the same as one
+//! another once confusable characters have been replaced.
+use crate::error::ParserWarning;
+use crate::lexer::literals::StrLit;
+use crate::span::Spanned;
+use std::collections::{HashMap, HashSet};
+use unicode_security::{MixedScript, skeleton};
+
+/// Produce warnings for the given identifiers, in the order that they appear in the source.
+/// Each distinct identifier is only reported once, at its first occurrence.
+pub fn identifier_warnings(identifiers: &[Spanned<StrLit>]) -> Vec<Spanned<ParserWarning>> {
+    let mut warnings = Vec::new();
+    let mut seen: HashSet<&str> = HashSet::new();
+    let mut skeletons: HashMap<String, &str> = HashMap::new();
+
+    for identifier in identifiers {
+        let name = identifier.value_ref().as_ref();
+        if !seen.insert(name) {
+            continue;
+        }
+
+        if !name.is_single_script() {
+            warnings.push(Spanned::new(
+                ParserWarning::MixedScriptIdentifier(name.to_string()),
+                identifier.span(),
+            ));
+        }
+
+        // ASCII-only identifiers such as "rn" and "m" have the same skeleton, but are
+        // normal to see alongside each other, so only consider identifiers that use
+        // other characters.
+        let other = skeletons.entry(skeleton(name).collect()).or_insert(name);
+        if *other != name && !(name.is_ascii() && other.is_ascii()) {
+            warnings.push(Spanned::new(
+                ParserWarning::ConfusableIdentifiers {
+                    identifier: name.to_string(),
+                    other: other.to_string(),
+                },
+                identifier.span(),
+            ));
+        }
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::span::Span;
+    use test_case::test_case;
+
+    fn identifiers(names: &[&str]) -> Vec<Spanned<StrLit>> {
+        names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| Spanned::new(Box::from(*name), Span::new(index, index + 1)))
+            .collect()
+    }
+
+    #[test_case(&["foo", "bar", "foo"] ; "ascii")]
+    #[test_case(&["café", "naïve"] ; "latin with accents")]
+    #[test_case(&["größe", "straße"] ; "german")]
+    #[test_case(&["число", "значение"] ; "cyrillic")]
+    #[test_case(&["変数", "へんすう", "ヘンスウ"] ; "japanese")]
+    #[test_case(&["rn", "m", "l", "I"] ; "confusable ascii")]
+    fn unambiguous_identifiers_produce_no_warnings(names: &[&str]) {
+        // When
+        let warnings = identifier_warnings(&identifiers(names));
+
+        // Then
+        assert_eq!(warnings, vec![]);
+    }
+
+    #[test]
+    fn mixed_script_identifiers_are_reported_once() {
+        // When
+        let warnings = identifier_warnings(&identifiers(&["p\u{0430}ypal", "p\u{0430}ypal"]));
+
+        // Then
+        assert_eq!(
+            warnings,
+            vec![Spanned::new(
+                ParserWarning::MixedScriptIdentifier("p\u{0430}ypal".to_string()),
+                Span::new(0, 1)
+            )]
+        );
+    }
+
+    #[test]
+    fn confusable_identifiers_are_reported_against_the_first() {
+        // When
+        let warnings = identifier_warnings(&identifiers(&[
+            "scope",
+            "\u{0455}\u{0441}\u{043E}\u{0440}\u{0435}",
+        ]));
+
+        // Then
+        assert_eq!(
+            warnings,
+            vec![Spanned::new(
+                ParserWarning::ConfusableIdentifiers {
+                    identifier: "\u{0455}\u{0441}\u{043E}\u{0440}\u{0435}".to_string(),
+                    other: "scope".to_string(),
+                },
+                Span::new(1, 2)
+            )]
+        );
+    }
+}
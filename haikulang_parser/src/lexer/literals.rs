@@ -1,15 +1,23 @@
-#[derive(Clone, Copy, Debug, PartialEq)]
+use num_bigint::BigUint;
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum IntLit {
     I8(i8),
     I16(i16),
     I32(i32),
     I64(i64),
+    I128(i128),
+    ISize(isize),
     U8(u8),
     U16(u16),
     U32(u32),
     U64(u64),
+    U128(u128),
+    USize(usize),
 
-    Untyped(i32),
+    // Unsuffixed literals are kept at arbitrary precision, and are given a type from
+    // the context they are used in later on.
+    Untyped(BigUint),
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -21,3 +29,5 @@ pub enum FloatLit {
 }
 
 pub type StrLit = Box<str>;
+
+pub type ByteStrLit = Box<[u8]>;
@@ -1,4 +1,5 @@
 pub mod helpers;
+pub mod identifiers;
 pub mod literals;
 pub mod token;
 pub mod token_stream;
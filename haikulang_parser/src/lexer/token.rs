@@ -11,47 +11,101 @@ pub enum Token {
     // Used as a marker for the end of the source file within TokenStream.
     Eof,
 
-    #[regex(r"//[^\r\n]*?[\r\n]?", callback = parse_inline_comment)]
+    // Regular comments cannot start with "///" or "//!", as those are documentation, unless
+    // they start with "////".
+    #[regex(r"//([^/!\r\n][^\r\n]*?)?[\r\n]?", callback = parse_inline_comment)]
+    #[regex(r"////[^\r\n]*?[\r\n]?", callback = parse_inline_comment)]
     InlineComment(StrLit),
 
-    #[regex(
-        r#"(?x)(?ms)   # match across multiple lines
-            /\*        # opening /*
-            .*?        # any content, as little as possible
-            \*/        # closing */
-        "#,
-        callback = parse_multiline_comment
-    )]
+    // "///" documents the declaration that follows it.
+    #[regex(r"///([^/\r\n][^\r\n]*?)?[\r\n]?", callback = parse_inline_doc_comment)]
+    InlineDocComment(StrLit),
+
+    // "//!" documents the enclosing compilation unit.
+    #[regex(r"//![^\r\n]*?[\r\n]?", callback = parse_inline_doc_comment)]
+    InlineInnerDocComment(StrLit),
+
+    // Block comments nest, so their content is consumed by the callback rather than the regex.
+    #[token("/*", callback = parse_multiline_comment)]
     MultilineComment(StrLit),
 
-    #[regex(r"[A-Za-z_][A-Za-z_0-9]*", callback = parse_identifier)]
+    // "/**/" and comments starting with "/***" are regular comments.
+    #[regex(r"/\*\*[^*/]", callback = parse_multiline_doc_comment)]
+    MultilineDocComment(StrLit),
+
+    #[token("/*!", callback = parse_multiline_doc_comment)]
+    MultilineInnerDocComment(StrLit),
+
+    // Identifiers follow UAX #31, with underscores also allowed at the start.
+    #[regex(r"[\p{XID_Start}_]\p{XID_Continue}*", callback = parse_identifier)]
     Identifier(StrLit),
 
-    // STRING_LIT  ::= '"' , STRING_CHAR* , '"' ;
-    // STRING_CHAR ::= '\\n'
-    //               | '\\r'
-    //               | '\\t'
-    //               | '\\"'
-    //               | '\\\\'
-    //               | '\\u' , [A-Fa-f0-9]{4}    /* unicode escape sequence like \u123a */
-    //               | any character except ascii/unicode control sequences, carriage returns, line feeds
-    //               ;
+    //     STRING_LIT ::= '"' , STRING_CHAR* , '"'
+    //                  | 'r' , RAW_STRING
+    //                  | '"""' , [ \t]* , NEWLINE , (STRING_CHAR | NEWLINE)* , '"""'
+    //                  ;
+    //    STRING_CHAR ::= '\\n'
+    //                  | '\\r'
+    //                  | '\\t'
+    //                  | '\\0'
+    //                  | '\\"'
+    //                  | "\\'"
+    //                  | '\\\\'
+    //                  | '\\x' , [0-7] , [A-Fa-f0-9]          /* ASCII escape sequence like \x7f */
+    //                  | '\\u' , [A-Fa-f0-9]{4}               /* unicode escape sequence like \u263a */
+    //                  | '\\u{' , [A-Fa-f0-9]{1,6} , '}'      /* unicode escape sequence like \u{1F600} */
+    //                  | any character except ascii/unicode control sequences, carriage returns, line feeds
+    //                  ;
+    //     RAW_STRING ::= '"' , any character* , '"'           /* no escape sequences, may span lines */
+    //                  | '#' , RAW_STRING , '#'               /* the content cannot contain '"' followed by as many hashes */
+    //                  ;
     //
     // We do not directly consume this exact grammar here; we instead use something much more
     // lenient. Any checks for validity are all dealt with in the callback for this lexer so that
     // we can give helpful error messages.
-    // This includes validating unicode escape sequences; checking strings are not crossing multiple
+    // This includes validating escape sequences; checking strings are not crossing multiple
     // lines; ensuring the string has no control characters; ensuring the string literal has a
     // closing quote as the last character in the token.
+    //
+    // Raw and multi-line strings cannot be described by a regular expression, so we only match
+    // their opening delimiters here and let the callbacks find where they end. Multi-line strings
+    // have any indentation that is common to all of their lines removed, along with the line
+    // breaks after the opening quotes and before the closing quotes.
     #[regex(
         r#"(?x)
-            "            # Opening quote
-            ([^"]|\\")*  # Any character that is not a closing quote (treat \" as an escape).
-            "?           # Closing quote, optional. If we don't match it, we raise an error.
+            "                   # Opening quote
+            ([^"\\]|\\(.|\n))*  # Any character other than a quote or backslash, or an escape pair.
+            "?                  # Closing quote, optional. If we don't match it, we raise an error.
         "#, callback = parse_string
     )]
+    #[regex(r#"r#*""#, callback = parse_raw_string)]
+    #[token(r#"""""#, callback = parse_multiline_string)]
     StringLit(StrLit),
 
+    //        CHAR_LIT ::= "'" , (STRING_CHAR | '"') , "'" ;
+    //        BYTE_LIT ::= "b'" , (BYTE_CHAR | '"') , "'" ;
+    // BYTE_STRING_LIT ::= 'b"' , BYTE_CHAR* , '"' ;
+    //       BYTE_CHAR ::= '\\n'
+    //                   | '\\r'
+    //                   | '\\t'
+    //                   | '\\0'
+    //                   | '\\"'
+    //                   | "\\'"
+    //                   | '\\\\'
+    //                   | '\\x' , [A-Fa-f0-9]{2}    /* any byte value like \xff */
+    //                   | any ASCII character except control sequences, carriage returns, line feeds
+    //                   ;
+    //
+    // Like strings, these are matched leniently and validated by their callbacks.
+    #[regex(r"'([^'\\\n]|\\.)*'?", callback = parse_char)]
+    CharLit(char),
+
+    #[regex(r"b'([^'\\\n]|\\.)*'?", callback = parse_byte)]
+    ByteLit(u8),
+
+    #[regex(r#"b"([^"\\]|\\(.|\n))*"?"#, callback = parse_byte_string)]
+    ByteStringLit(ByteStrLit),
+
     //         INT_LIT ::= RAW_INT_LIT , INT_TYPE_SUFFIX? ;
     //
     //     RAW_INT_LIT ::= INT_BINARY_LIT
@@ -64,8 +118,8 @@ pub enum Token {
     //     INT_HEX_LIT ::= ('0x' | '0X') , [0-9A-Fa-f] , ([0-9A-Fa-f_]* , [0-9A-Fa-f])? ;
     //     INT_DEC_LIT ::= [0-9] , ([0-9_]* , [0-9])? ;
     //
-    // INT_TYPE_SUFFIX ::= 'i8' | 'i16' | 'i32' | 'i64'
-    //                   | 'u8' | 'u16' | 'u32' | 'u64'
+    // INT_TYPE_SUFFIX ::= 'i8' | 'i16' | 'i32' | 'i64' | 'i128' | 'isize'
+    //                   | 'u8' | 'u16' | 'u32' | 'u64' | 'u128' | 'usize'
     //                   ;
     //
     #[regex(
@@ -81,7 +135,7 @@ pub enum Token {
                 ([0-9]([0-9_]*[0-9])?)                             # INT_DEC_LIT
             )
             # optional INT_TYPE_SUFFIX
-            ([iu](8|16|32|64))?
+            ([iu](8|16|32|64|128|size))?
         ",
         callback = parse_int_lit
     )]
@@ -132,6 +186,9 @@ pub enum Token {
     #[token("fn")]
     Fn,
 
+    #[token("pub")]
+    Pub,
+
     #[token("struct")]
     Struct,
 
@@ -165,6 +222,9 @@ pub enum Token {
     #[token(";")]
     Semicolon,
 
+    #[token("#")]
+    Hash,
+
     #[token("{")]
     LeftBrace,
 
@@ -301,6 +361,9 @@ pub enum Token {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use num_bigint::BigUint;
+    use std::ops::Range;
+    use std::str::FromStr;
     use test_case::test_case;
 
     #[test_case(                   "//",                "", false ; "empty comment")]
@@ -349,6 +412,9 @@ mod tests {
     #[test_case(             "/*foo bar*/",         "foo bar" ; "simple comment")]
     #[test_case(           "/* foo bar */",       " foo bar " ; "simple comment with leading and trailing whitespace")]
     #[test_case(     "/*\n foo\n bar\n */", "\n foo\n bar\n " ; "multi-line comment")]
+    #[test_case(        "/*** foo **/",      "** foo *" ; "comment with extra asterisks")]
+    #[test_case(   "/* a /* b */ c */", " a /* b */ c " ; "nested comment")]
+    #[test_case(          "/*/**/*/",          "/**/" ; "nested comments without spacing")]
     fn multiline_comments_parse_as_expected(input: &str, expected_content: &str) {
         // Given
         let mut lexer = Token::lexer(input);
@@ -371,6 +437,59 @@ mod tests {
         );
     }
 
+    #[test]
+    fn multiline_comments_end_at_the_first_terminator() {
+        // Given
+        let mut lexer = Token::lexer("/* foo */ bar /* baz */");
+
+        // Then
+        assert_eq!(
+            lexer.next(),
+            Some(Ok(Token::MultilineComment(Box::from(" foo "))))
+        );
+        assert_eq!(lexer.next(), Some(Ok(Token::Identifier(Box::from("bar")))));
+        assert_eq!(
+            lexer.next(),
+            Some(Ok(Token::MultilineComment(Box::from(" baz "))))
+        );
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test_case(      "/// foo\n", Token::InlineDocComment(Box::from(" foo\n")) ; "inline doc comment")]
+    #[test_case(            "///", Token::InlineDocComment(Box::from("")) ; "empty inline doc comment")]
+    #[test_case(      "//! foo\n", Token::InlineInnerDocComment(Box::from(" foo\n")) ; "inline inner doc comment")]
+    #[test_case(        "//// foo", Token::InlineComment(Box::from("// foo")) ; "four slashes")]
+    #[test_case(      "/** foo */", Token::MultilineDocComment(Box::from(" foo ")) ; "multiline doc comment")]
+    #[test_case("/** a /* b */ */", Token::MultilineDocComment(Box::from(" a /* b */ ")) ; "nested multiline doc comment")]
+    #[test_case(      "/*! foo */", Token::MultilineInnerDocComment(Box::from(" foo ")) ; "multiline inner doc comment")]
+    #[test_case(           "/**/", Token::MultilineComment(Box::from("")) ; "empty multiline comment")]
+    #[test_case(          "/*!*/", Token::MultilineInnerDocComment(Box::from("")) ; "empty inner doc comment")]
+    fn doc_comments_have_distinct_tokens(input: &str, expected: Token) {
+        // Given
+        let mut lexer = Token::lexer(input);
+
+        // Then
+        assert_eq!(lexer.next(), Some(Ok(expected)));
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test_case(              "/*" ; "opening only")]
+    #[test_case(      "/* foo * /" ; "no terminator")]
+    #[test_case(   "/* /* foo */" ; "nested comment left open")]
+    #[test_case(   "/** foo /* */" ; "doc comment left open")]
+    #[test_case(            "/*!" ; "inner doc comment left open")]
+    fn unterminated_block_comments_are_rejected(input: &str) {
+        // Given
+        let mut lexer = Token::lexer(input);
+
+        // Then
+        assert_eq!(
+            lexer.next(),
+            Some(Err(ParserError::UnterminatedBlockComment))
+        );
+        assert_eq!(lexer.next(), None);
+    }
+
     #[test_case(                   "i" ; "single-character lowercase identifier")]
     #[test_case(                 "foo" ; "multi-character lowercase identifier")]
     #[test_case(                   "I" ; "single-character uppercase identifier")]
@@ -387,6 +506,11 @@ mod tests {
     #[test_case(              "http11" ; "lowercase with trailing numbers")]
     #[test_case(               "HTTP2" ; "uppercase with trailing numbers")]
     #[test_case(        "http3session" ; "numbers in the middle of identifiers")]
+    #[test_case(                "café" ; "latin with accents")]
+    #[test_case(               "größe" ; "latin with a sharp s")]
+    #[test_case(               "число" ; "cyrillic")]
+    #[test_case(                "変数" ; "kanji")]
+    #[test_case(            "_значение2" ; "non-latin with underscores and numbers")]
     fn identifiers_are_parsed_correctly(identifier: &str) {
         // Given
         let mut lexer = Token::lexer(identifier);
@@ -409,51 +533,83 @@ mod tests {
         );
     }
 
+    #[test_case(    "cafe\u{301}",   "caf\u{e9}" ; "combining accent is composed")]
+    #[test_case(     "caf\u{e9}",   "caf\u{e9}" ; "precomposed accent is unchanged")]
+    #[test_case("\u{212B}ngstr\u{f6}m", "\u{c5}ngstr\u{f6}m" ; "angstrom sign becomes a letter")]
+    fn identifiers_are_normalized_to_nfc(identifier: &str, expected: &str) {
+        // Given
+        let mut lexer = Token::lexer(identifier);
+
+        // Then
+        assert_eq!(
+            lexer.next(),
+            Some(Ok(Token::Identifier(Box::from(expected))))
+        );
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test_case("\u{263A}" ; "symbol")]
+    #[test_case("\u{301}" ; "combining accent on its own")]
+    #[test_case("\u{665}" ; "non-ascii digit")]
+    fn characters_that_cannot_start_identifiers_are_rejected(input: &str) {
+        // Given
+        let mut lexer = Token::lexer(input);
+
+        // Then
+        assert_eq!(
+            lexer.next(),
+            Some(Err(ParserError::UnknownToken(input.to_string())))
+        );
+    }
+
     // untyped, and common formatting scenarios
-    #[test_case(                                 "0b0",                   IntLit::Untyped(0) ; "0: base 2 lowercase prefix, no type")]
-    #[test_case(                                 "0b1",                   IntLit::Untyped(1) ; "1: base 2 lowercase prefix, no type")]
-    #[test_case(                           "0b1100100",                 IntLit::Untyped(100) ; "100: base 2 lowercase prefix, no type")]
-    #[test_case(                       "0b111110_1000",                IntLit::Untyped(1000) ; "1000: base 2 lowercase prefix, no type, underscores")]
-    #[test_case(                    "0b11__1110__1000",                IntLit::Untyped(1000) ; "1000: base 2 lowercase prefix, no type, multiple underscores")]
-    #[test_case(   "0b1111111111111111111111111111111",          IntLit::Untyped(2147483647) ; "2147483647: max i32 value, base 2 lowercase prefix, no type")]
-    #[test_case(                                 "0B0",                   IntLit::Untyped(0) ; "0: base 2 uppercase prefix, no type")]
-    #[test_case(                                 "0B1",                   IntLit::Untyped(1) ; "1: base 2 uppercase prefix, no type")]
-    #[test_case(                           "0B1100100",                 IntLit::Untyped(100) ; "100: base 2 uppercase prefix, no type")]
-    #[test_case(                       "0B111110_1000",                IntLit::Untyped(1000) ; "1000: base 2 uppercase prefix, no type, underscores")]
-    #[test_case(                    "0B11__1110__1000",                IntLit::Untyped(1000) ; "1000: base 2 uppercase prefix, no type, multiple underscores")]
-    #[test_case(   "0B1111111111111111111111111111111",          IntLit::Untyped(2147483647) ; "2147483647: max i32 value, base 2 uppercase prefix, no type")]
-    #[test_case(                                 "0o0",                   IntLit::Untyped(0) ; "0: base 8 lowercase prefix, no type")]
-    #[test_case(                                 "0o1",                   IntLit::Untyped(1) ; "1: base 8 lowercase prefix, no type")]
-    #[test_case(                               "0o144",                 IntLit::Untyped(100) ; "100: base 8 lowercase prefix, no type")]
-    #[test_case(                             "0o17_50",                IntLit::Untyped(1000) ; "1000: base 8 lowercase prefix, no type, underscores")]
-    #[test_case(                           "0o1_75__0",                IntLit::Untyped(1000) ; "1000: base 8 lowercase prefix, no type, multiple underscores")]
-    #[test_case(                       "0o17777777777",          IntLit::Untyped(2147483647) ; "2147483647: max i32 value, base 8 lowercase prefix, no type")]
-    #[test_case(                                 "0O0",                   IntLit::Untyped(0) ; "0: base 8 uppercase prefix, no type")]
-    #[test_case(                                 "0O1",                   IntLit::Untyped(1) ; "1: base 8 uppercase prefix, no type")]
-    #[test_case(                               "0O144",                 IntLit::Untyped(100) ; "100: base 8 uppercase prefix, no type")]
-    #[test_case(                             "0O17_50",                IntLit::Untyped(1000) ; "1000: base 8 uppercase prefix, no type, underscores")]
-    #[test_case(                           "0O1_75__0",                IntLit::Untyped(1000) ; "1000: base 8 uppercase prefix, no type, multiple underscores")]
-    #[test_case(                       "0O17777777777",          IntLit::Untyped(2147483647) ; "2147483647: max i32 value, base 8 uppercase prefix, no type")]
-    #[test_case(                                   "0",                   IntLit::Untyped(0) ; "0: base 10, no type")]
-    #[test_case(                                   "1",                   IntLit::Untyped(1) ; "1: base 10, no type")]
-    #[test_case(                                 "100",                 IntLit::Untyped(100) ; "100: base 10, no type")]
-    #[test_case(                               "1_000",                IntLit::Untyped(1000) ; "1000: base 10, no type, underscores")]
-    #[test_case(                             "1__00_0",                IntLit::Untyped(1000) ; "1000: base 10, no type, multiple underscores")]
-    #[test_case(                          "2147483647",          IntLit::Untyped(2147483647) ; "2147483647: max i32 value, base 10, no type")]
-    #[test_case(                                 "0x0",                   IntLit::Untyped(0) ; "0: base 16 lowercase prefix, no type")]
-    #[test_case(                                 "0x1",                   IntLit::Untyped(1) ; "1: base 16 lowercase prefix, no type")]
-    #[test_case(                                "0x64",                 IntLit::Untyped(100) ; "100: base 16 lowercase prefix, no type")]
-    #[test_case(                              "0x3_e8",                IntLit::Untyped(1000) ; "1000: base 16 lowercase prefix, no type, underscores")]
-    #[test_case(                            "0x3__e_8",                IntLit::Untyped(1000) ; "1000: base 16 lowercase prefix, no type, multiple underscores")]
-    #[test_case(                          "0x7fffffff",          IntLit::Untyped(2147483647) ; "2147483647: max i32 value, base 16 lowercase prefix, no type")]
-    #[test_case(                          "0x7ffFFffF",          IntLit::Untyped(2147483647) ; "2147483647: max i32 value, base 16 lowercase prefix, mixed case, no type")]
-    #[test_case(                                 "0X0",                   IntLit::Untyped(0) ; "0: base 16 uppercase prefix, no type")]
-    #[test_case(                                 "0X1",                   IntLit::Untyped(1) ; "1: base 16 uppercase prefix, no type")]
-    #[test_case(                                "0X64",                 IntLit::Untyped(100) ; "100: base 16 uppercase prefix, no type")]
-    #[test_case(                              "0X3_E8",                IntLit::Untyped(1000) ; "1000: base 16 uppercase prefix, no type, underscores")]
-    #[test_case(                            "0X3__E_8",                IntLit::Untyped(1000) ; "1000: base 16 uppercase prefix, no type, multiple underscores")]
-    #[test_case(                          "0X7FFFFFFF",          IntLit::Untyped(2147483647) ; "2147483647: max i32 value, base 16 uppercase prefix, no type")]
-    #[test_case(                          "0X7ffFFffF",          IntLit::Untyped(2147483647) ; "2147483647: max i32 value, base 16 uppercase prefix, mixed case, no type")]
+    #[test_case(                                 "0b0",                           untyped(0) ; "0: base 2 lowercase prefix, no type")]
+    #[test_case(                                 "0b1",                           untyped(1) ; "1: base 2 lowercase prefix, no type")]
+    #[test_case(                           "0b1100100",                         untyped(100) ; "100: base 2 lowercase prefix, no type")]
+    #[test_case(                       "0b111110_1000",                        untyped(1000) ; "1000: base 2 lowercase prefix, no type, underscores")]
+    #[test_case(                    "0b11__1110__1000",                        untyped(1000) ; "1000: base 2 lowercase prefix, no type, multiple underscores")]
+    #[test_case(   "0b1111111111111111111111111111111",                  untyped(2147483647) ; "2147483647: max i32 value, base 2 lowercase prefix, no type")]
+    #[test_case(                                 "0B0",                           untyped(0) ; "0: base 2 uppercase prefix, no type")]
+    #[test_case(                                 "0B1",                           untyped(1) ; "1: base 2 uppercase prefix, no type")]
+    #[test_case(                           "0B1100100",                         untyped(100) ; "100: base 2 uppercase prefix, no type")]
+    #[test_case(                       "0B111110_1000",                        untyped(1000) ; "1000: base 2 uppercase prefix, no type, underscores")]
+    #[test_case(                    "0B11__1110__1000",                        untyped(1000) ; "1000: base 2 uppercase prefix, no type, multiple underscores")]
+    #[test_case(   "0B1111111111111111111111111111111",                  untyped(2147483647) ; "2147483647: max i32 value, base 2 uppercase prefix, no type")]
+    #[test_case(                                 "0o0",                           untyped(0) ; "0: base 8 lowercase prefix, no type")]
+    #[test_case(                                 "0o1",                           untyped(1) ; "1: base 8 lowercase prefix, no type")]
+    #[test_case(                               "0o144",                         untyped(100) ; "100: base 8 lowercase prefix, no type")]
+    #[test_case(                             "0o17_50",                        untyped(1000) ; "1000: base 8 lowercase prefix, no type, underscores")]
+    #[test_case(                           "0o1_75__0",                        untyped(1000) ; "1000: base 8 lowercase prefix, no type, multiple underscores")]
+    #[test_case(                       "0o17777777777",                  untyped(2147483647) ; "2147483647: max i32 value, base 8 lowercase prefix, no type")]
+    #[test_case(                                 "0O0",                           untyped(0) ; "0: base 8 uppercase prefix, no type")]
+    #[test_case(                                 "0O1",                           untyped(1) ; "1: base 8 uppercase prefix, no type")]
+    #[test_case(                               "0O144",                         untyped(100) ; "100: base 8 uppercase prefix, no type")]
+    #[test_case(                             "0O17_50",                        untyped(1000) ; "1000: base 8 uppercase prefix, no type, underscores")]
+    #[test_case(                           "0O1_75__0",                        untyped(1000) ; "1000: base 8 uppercase prefix, no type, multiple underscores")]
+    #[test_case(                       "0O17777777777",                  untyped(2147483647) ; "2147483647: max i32 value, base 8 uppercase prefix, no type")]
+    #[test_case(                                   "0",                           untyped(0) ; "0: base 10, no type")]
+    #[test_case(                                   "1",                           untyped(1) ; "1: base 10, no type")]
+    #[test_case(                                 "100",                         untyped(100) ; "100: base 10, no type")]
+    #[test_case(                               "1_000",                        untyped(1000) ; "1000: base 10, no type, underscores")]
+    #[test_case(                             "1__00_0",                        untyped(1000) ; "1000: base 10, no type, multiple underscores")]
+    #[test_case(                          "2147483647",                  untyped(2147483647) ; "2147483647: max i32 value, base 10, no type")]
+    #[test_case(                                 "0x0",                           untyped(0) ; "0: base 16 lowercase prefix, no type")]
+    #[test_case(                                 "0x1",                           untyped(1) ; "1: base 16 lowercase prefix, no type")]
+    #[test_case(                                "0x64",                         untyped(100) ; "100: base 16 lowercase prefix, no type")]
+    #[test_case(                              "0x3_e8",                        untyped(1000) ; "1000: base 16 lowercase prefix, no type, underscores")]
+    #[test_case(                            "0x3__e_8",                        untyped(1000) ; "1000: base 16 lowercase prefix, no type, multiple underscores")]
+    #[test_case(                          "0x7fffffff",                  untyped(2147483647) ; "2147483647: max i32 value, base 16 lowercase prefix, no type")]
+    #[test_case(                          "0x7ffFFffF",                  untyped(2147483647) ; "2147483647: max i32 value, base 16 lowercase prefix, mixed case, no type")]
+    #[test_case(                                 "0X0",                           untyped(0) ; "0: base 16 uppercase prefix, no type")]
+    #[test_case(                                 "0X1",                           untyped(1) ; "1: base 16 uppercase prefix, no type")]
+    #[test_case(                                "0X64",                         untyped(100) ; "100: base 16 uppercase prefix, no type")]
+    #[test_case(                              "0X3_E8",                        untyped(1000) ; "1000: base 16 uppercase prefix, no type, underscores")]
+    #[test_case(                            "0X3__E_8",                        untyped(1000) ; "1000: base 16 uppercase prefix, no type, multiple underscores")]
+    #[test_case(                          "0X7FFFFFFF",                  untyped(2147483647) ; "2147483647: max i32 value, base 16 uppercase prefix, no type")]
+    #[test_case(                          "0X7ffFFffF",                  untyped(2147483647) ; "2147483647: max i32 value, base 16 uppercase prefix, mixed case, no type")]
+    #[test_case(                          "2147483648",                  untyped(2147483648) ; "2147483648: above max i32 value, base 10, no type")]
+    #[test_case(                "18446744073709551615",        untyped(18446744073709551615) ; "18446744073709551615: max u64 value, base 10, no type")]
+    #[test_case(  "0xffff_ffff_ffff_ffff_ffff_ffff_ffff_ffff",  untyped(u128::MAX) ; "340282366920938463463374607431768211455: max u128 value, base 16, no type")]
     // i8
     #[test_case(                                 "0i8",                        IntLit::I8(0) ; "0: min i8 value, base 10, i8")]
     #[test_case(                                "52i8",                       IntLit::I8(52) ; "52: base 10, i8")]
@@ -486,6 +642,19 @@ mod tests {
     #[test_case(                                "0u64",                    IntLit::U64(0u64) ; "0: min u64 value, base 10, u64")]
     #[test_case(                               "52u64",                   IntLit::U64(52u64) ; "52: base 10, u64")]
     #[test_case(             "18446744073709551615u64", IntLit::U64(18446744073709551615u64) ; "18446744073709551615: max u64 value, base 10, u64")]
+    // i128
+    #[test_case(                               "0i128",                     IntLit::I128(0) ; "0: min i128 value, base 10, i128")]
+    #[test_case(                           "0x7fi128",                   IntLit::I128(127) ; "127: base 16, i128")]
+    #[test_case( "0x7fff_ffff_ffff_ffff_ffff_ffff_ffff_ffffi128", IntLit::I128(i128::MAX) ; "max i128 value, base 16, i128")]
+    // isize
+    #[test_case(                              "0isize",                    IntLit::ISize(0) ; "0: min isize value, base 10, isize")]
+    #[test_case(                             "52isize",                   IntLit::ISize(52) ; "52: base 10, isize")]
+    // u128
+    #[test_case(                               "0u128",                     IntLit::U128(0) ; "0: min u128 value, base 10, u128")]
+    #[test_case( "340282366920938463463374607431768211455u128", IntLit::U128(u128::MAX) ; "max u128 value, base 10, u128")]
+    // usize
+    #[test_case(                              "0usize",                    IntLit::USize(0) ; "0: min usize value, base 10, usize")]
+    #[test_case(                         "0b11usize",                     IntLit::USize(3) ; "3: base 2, usize")]
     fn int_literals_are_parsed_correctly(input: &str, expected: IntLit) {
         // Given
         let mut lexer = Token::lexer(input);
@@ -508,6 +677,44 @@ mod tests {
         );
     }
 
+    #[test_case("256u8" ; "u8 overflow")]
+    #[test_case("0x1_0000_0000_0000_0000_0000_0000_0000_0000u128" ; "u128 overflow")]
+    #[test_case("170141183460469231731687303715884105728i128" ; "i128 overflow")]
+    fn out_of_range_suffixed_int_literals_are_rejected(input: &str) {
+        // Given
+        let mut lexer = Token::lexer(input);
+
+        // When
+        let result = lexer.next().unwrap();
+
+        // Then
+        assert!(
+            matches!(result, Err(ParserError::InvalidIntLit(_))),
+            "expected an invalid int literal, got {:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn untyped_int_literals_have_arbitrary_precision() {
+        // Given
+        let input = "123456789012345678901234567890123456789012345678901234567890";
+        let mut lexer = Token::lexer(input);
+
+        // When
+        let token = lexer.next().unwrap().unwrap();
+
+        // Then
+        assert_eq!(
+            token,
+            Token::IntLit(IntLit::Untyped(BigUint::from_str(input).unwrap()))
+        );
+    }
+
+    fn untyped(value: u128) -> IntLit {
+        IntLit::Untyped(BigUint::from(value))
+    }
+
     // untyped
     #[test_case(                 "0.123",       FloatLit::Untyped(0.123) ; "0.123: decimal, no exponent, no underscores, untyped")]
     #[test_case(            "32_768.123",   FloatLit::Untyped(32768.123) ; "32768.123: decimal, no exponent, underscores, untyped")]
@@ -546,6 +753,7 @@ mod tests {
     #[test_case(     "32__768e1__23f64",     FloatLit::F64(32768e123f64) ; "32768e123: no decimal, unsigned lowercase exponent, multiple underscores, f64 suffix")]
     #[test_case(    "32__768e+1__23f64",     FloatLit::F64(32768e123f64) ; "32768e123: no decimal, positive lowercase exponent, multiple underscores, f64 suffix")]
     #[test_case(    "32__768E-1__23f64",    FloatLit::F64(32768E-123f64) ; "32768e-123: no decimal, negative uppercase exponent, multiple underscores, f64 suffix")]
+    #[allow(clippy::excessive_precision)]
     fn float_literals_are_parsed_correctly(input: &str, expected: FloatLit) {
         // Given
         let mut lexer = Token::lexer(input);
@@ -572,6 +780,7 @@ mod tests {
     #[test_case(   "false",             Token::False ; "false keyword")]
     #[test_case(  "extern",            Token::Extern ; "extern keyword")]
     #[test_case(      "fn",                Token::Fn ; "fn keyword")]
+    #[test_case(     "pub",               Token::Pub ; "pub keyword")]
     #[test_case(  "struct",            Token::Struct ; "struct keyword")]
     #[test_case(  "return",            Token::Return ; "return keyword")]
     #[test_case("continue",          Token::Continue ; "continue keyword")]
@@ -583,6 +792,7 @@ mod tests {
     #[test_case(     "let",               Token::Let ; "let keyword")]
     #[test_case(     "use",               Token::Use ; "use keyword")]
     #[test_case(       ";",         Token::Semicolon ; "semicolon")]
+    #[test_case(       "#",              Token::Hash ; "hash")]
     #[test_case(       "{",         Token::LeftBrace ; "left brace")]
     #[test_case(       "}",        Token::RightBrace ; "right brace")]
     #[test_case(       "(",         Token::LeftParen ; "left parenthesis")]
@@ -643,4 +853,240 @@ mod tests {
             next_token
         );
     }
+
+    #[test_case(                   r#""""#,                "" ; "empty string")]
+    #[test_case(              r#""hello""#,           "hello" ; "simple string")]
+    #[test_case(          r#""a\"b\\c\t""#,        "a\"b\\c\t" ; "simple escapes")]
+    #[test_case(             r#""\u00e9""#,             "é" ; "unicode escape")]
+    #[test_case(             "\"日本語 ✓\"",        "日本語 ✓" ; "multibyte characters")]
+    #[test_case(              r#""é\n日""#,           "é\n日" ; "escapes between multibyte characters")]
+    #[test_case(          r#""\u{1F600}""#,              "😀" ; "braced unicode escape outside the BMP")]
+    #[test_case(               r#""\u{0}""#,            "\0" ; "braced unicode escape with one digit")]
+    #[test_case(          r#""\x41\x7f""#,        "A\u{7f}" ; "ascii escapes")]
+    #[test_case(              r#""a\0b""#,          "a\0b" ; "null escape")]
+    fn string_literals_are_parsed_correctly(input: &str, expected: &str) {
+        // Given
+        let mut lexer = Token::lexer(input);
+
+        // When
+        let token = lexer.next().unwrap().unwrap();
+
+        // Then
+        assert_eq!(Token::StringLit(Box::from(expected)), token);
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test_case(         "\"abc" ; "missing closing quote")]
+    #[test_case(     r#""abc\""# ; "escaped closing quote")]
+    #[test_case(            "\"é" ; "missing closing quote after multibyte character")]
+    fn unclosed_string_literals_are_rejected(input: &str) {
+        // Given
+        let mut lexer = Token::lexer(input);
+
+        // When
+        let result = lexer.next().unwrap();
+
+        // Then
+        assert!(
+            matches!(result, Err(ParserError::UnclosedStringLit(_))),
+            "expected unclosed string error, got {:?}",
+            result
+        );
+    }
+
+    #[test_case(   "\"a\u{7}b\"" ; "control character")]
+    #[test_case(   "\"a\nb\"" ; "line feed")]
+    #[test_case(   "\"\"\"abc\n\"\"\"" ; "multi-line string starting on the first line")]
+    #[test_case(   "\"\"\"\n\u{7}\n\"\"\"" ; "control character in multi-line string")]
+    fn invalid_string_literals_are_rejected(input: &str) {
+        // Given
+        let mut lexer = Token::lexer(input);
+
+        // When
+        let result = lexer.next().unwrap();
+
+        // Then
+        assert!(
+            matches!(result, Err(ParserError::InvalidStringLit(_))),
+            "expected invalid string error, got {:?}",
+            result
+        );
+    }
+
+    #[test_case(              r#""\q""#,   1..3 ; "unknown escape")]
+    #[test_case(         r#"" ok \q""#,   5..7 ; "unknown escape after other characters")]
+    #[test_case(             "\"\\é\"",   1..4 ; "escaped multibyte character")]
+    #[test_case(           r#""\u12""#,   1..5 ; "short unicode escape")]
+    #[test_case(         r#""\u12é4""#,   1..5 ; "unicode escape with multibyte character")]
+    #[test_case(         r#""\ud800""#,   1..7 ; "unicode escape for a surrogate")]
+    #[test_case(        r#""\u{}""#,   1..5 ; "empty braced unicode escape")]
+    #[test_case(     r#""\u{1F600""#,   1..9 ; "unclosed braced unicode escape")]
+    #[test_case(  r#""\u{0001F600}""#,   1..13 ; "too many digits in braced unicode escape")]
+    #[test_case(    r#""\u{110000}""#,   1..11 ; "braced unicode escape out of range")]
+    #[test_case(           r#""\x4""#,   1..4 ; "short ascii escape")]
+    #[test_case(          r#""\x80""#,   1..5 ; "ascii escape out of range")]
+    #[test_case( "\"\"\"\n  ab\n  c\\qd\n  \"\"\"",   12..14 ; "escape in multi-line string")]
+    fn invalid_escape_sequences_are_rejected_at_the_escape(input: &str, range: Range<usize>) {
+        // Given
+        let mut lexer = Token::lexer(input);
+
+        // When
+        let result = lexer.next().unwrap();
+
+        // Then
+        match result {
+            Err(err @ ParserError::InvalidEscapeSequence { .. }) => {
+                assert_eq!(err.range_within_token(), Some(range));
+            }
+            other => panic!("expected invalid escape sequence error, got {:?}", other),
+        }
+    }
+
+    #[test_case(                  r#"r"""#,                  "" ; "empty raw string")]
+    #[test_case(              r#"r"a\nb""#,             "a\\nb" ; "escapes are not processed")]
+    #[test_case(          r##"r#"say "hi""#"##,         "say \"hi\"" ; "quotes within hashes")]
+    #[test_case(        r###"r##"a"#b"##"###,             "a\"#b" ; "fewer hashes within more hashes")]
+    #[test_case(              "r\"a\nb\"",             "a\nb" ; "raw strings can span lines")]
+    fn raw_string_literals_are_parsed_correctly(input: &str, expected: &str) {
+        // Given
+        let mut lexer = Token::lexer(input);
+
+        // When
+        let token = lexer.next().unwrap().unwrap();
+
+        // Then
+        assert_eq!(Token::StringLit(Box::from(expected)), token);
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test_case(
+        "\"\"\"\n    hello\n      world\n    \"\"\"",
+        "hello\n  world"
+        ; "closing quotes on their own line"
+    )]
+    #[test_case(
+        "\"\"\"\n    hello\n      world\"\"\"",
+        "hello\n  world"
+        ; "closing quotes after content"
+    )]
+    #[test_case(
+        "\"\"\"\n      hello\n    \"\"\"",
+        "  hello"
+        ; "indentation of the closing quotes is removed"
+    )]
+    #[test_case(
+        "\"\"\"  \r\n  a\r\n\r\n  b\r\n  \"\"\"",
+        "a\n\nb"
+        ; "carriage returns and blank lines"
+    )]
+    #[test_case(
+        "\"\"\"\n  say \"hi\" \\\"\"\"\n  \\t\\u{1F600}\n  \"\"\"",
+        "say \"hi\" \"\"\"\n\t😀"
+        ; "quotes and escapes"
+    )]
+    #[test_case("\"\"\"\n\"\"\"", "" ; "empty string")]
+    fn multiline_string_literals_are_parsed_correctly(input: &str, expected: &str) {
+        // Given
+        let mut lexer = Token::lexer(input);
+
+        // When
+        let token = lexer.next().unwrap().unwrap();
+
+        // Then
+        assert_eq!(Token::StringLit(Box::from(expected)), token);
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test_case(    r#"r"abc"# ; "raw string")]
+    #[test_case( r##"r#"abc""##; "raw string with too few closing hashes")]
+    #[test_case( "\"\"\"\nabc\"\"" ; "multi-line string")]
+    #[test_case( "\"\"\"\nabc\\\"\"\"" ; "multi-line string with escaped closing quotes")]
+    fn unclosed_raw_and_multiline_string_literals_are_rejected(input: &str) {
+        // Given
+        let mut lexer = Token::lexer(input);
+
+        // When
+        let result = lexer.next().unwrap();
+
+        // Then
+        assert_eq!(
+            result,
+            Err(ParserError::UnclosedStringLit(input.to_string()))
+        );
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test_case(          "'a'",  Token::CharLit('a') ; "simple character")]
+    #[test_case(          "'\"'",  Token::CharLit('"') ; "double quote")]
+    #[test_case(        r"'\''", Token::CharLit('\'') ; "escaped single quote")]
+    #[test_case(        r"'\n'", Token::CharLit('\n') ; "escaped line feed")]
+    #[test_case(  r"'\u{263A}'", Token::CharLit('☺') ; "braced unicode escape")]
+    #[test_case(         "'🦀'",  Token::CharLit('🦀') ; "multibyte character")]
+    #[test_case(         "b'a'",  Token::ByteLit(b'a') ; "simple byte")]
+    #[test_case(      r"b'\''",  Token::ByteLit(b'\'') ; "escaped single quote byte")]
+    #[test_case(      r"b'\xff'",  Token::ByteLit(0xff) ; "hex escaped byte")]
+    #[test_case(   r#"b"ab\x00\xff\n""#, Token::ByteStringLit(Box::from(*b"ab\x00\xff\n")) ; "byte string")]
+    #[test_case(       r#"b"""#, Token::ByteStringLit(Box::from(*b"")) ; "empty byte string")]
+    fn char_and_byte_literals_are_parsed_correctly(input: &str, expected: Token) {
+        // Given
+        let mut lexer = Token::lexer(input);
+
+        // When
+        let token = lexer.next().unwrap().unwrap();
+
+        // Then
+        assert_eq!(token, expected);
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test_case(          "''",  ParserError::InvalidCharLit("'' must contain exactly one character".to_string()) ; "empty character")]
+    #[test_case(        "'ab'",  ParserError::InvalidCharLit("'ab' must contain exactly one character".to_string()) ; "too many characters")]
+    #[test_case(         "'ab",  ParserError::InvalidCharLit("missing closing quote: 'ab".to_string()) ; "unclosed character")]
+    #[test_case(        "b'é'",  ParserError::InvalidCharLit("'é' is not an ASCII character, so must be written as escaped bytes".to_string()) ; "non-ASCII byte")]
+    #[test_case(     "b\"aé\"",  ParserError::InvalidStringLit("'é' is not an ASCII character, so must be written as escaped bytes".to_string()) ; "non-ASCII byte string")]
+    #[test_case(      "b\"abc",  ParserError::UnclosedStringLit("b\"abc".to_string()) ; "unclosed byte string")]
+    #[test_case(   r"b'\u{61}'",  ParserError::InvalidEscapeSequence { message: "unicode escape sequences cannot be used for bytes".to_string(), range: 2..4 } ; "unicode escape in byte")]
+    #[test_case(      r"'\x80'",  ParserError::InvalidEscapeSequence { message: "\\x80 is not an ASCII character (the maximum is \\x7f)".to_string(), range: 1..5 } ; "non-ASCII escape in character")]
+    fn invalid_char_and_byte_literals_are_rejected(input: &str, expected: ParserError) {
+        // Given
+        let mut lexer = Token::lexer(input);
+
+        // When
+        let result = lexer.next().unwrap();
+
+        // Then
+        assert_eq!(result, Err(expected));
+    }
+
+    #[test]
+    fn escaped_backslashes_do_not_escape_the_closing_quote() {
+        // Given
+        let mut lexer = Token::lexer(r#""a\\" b"#);
+
+        // Then
+        assert_eq!(lexer.next(), Some(Ok(Token::StringLit(Box::from("a\\")))));
+        assert_eq!(lexer.next(), Some(Ok(Token::Identifier(Box::from("b")))));
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[test]
+    fn unknown_input_after_multibyte_characters_is_reported() {
+        // Given
+        let mut lexer = Token::lexer("☺ $ €");
+
+        // Then
+        assert_eq!(
+            lexer.next(),
+            Some(Err(ParserError::UnknownToken("☺".to_string())))
+        );
+        assert_eq!(
+            lexer.next(),
+            Some(Err(ParserError::UnknownToken("$".to_string())))
+        );
+        assert_eq!(
+            lexer.next(),
+            Some(Err(ParserError::UnknownToken("€".to_string())))
+        );
+        assert_eq!(lexer.next(), None);
+    }
 }
@@ -1,42 +1,122 @@
-use crate::error::ParserResult;
+use crate::error::{ParserError, ParserResult};
 use crate::lexer::token::Token;
 use crate::span::{Span, Spanned};
-use logos::{Logos, SpannedIter};
+use logos::Logos;
+use std::ops::Range;
+
+type TokenIter<'src> = Box<dyn Iterator<Item = (Result<Token, ParserError>, Range<usize>)> + 'src>;
 
 pub struct TokenStream<'src> {
-    iter: SpannedIter<'src, Token>,
+    iter: TokenIter<'src>,
     next: ParserResult<Token>,
+    // Where the EOF marker is positioned once all tokens have been consumed.
+    end: usize,
 }
 
 impl<'src> TokenStream<'src> {
     pub fn new(source: &'src str) -> Self {
-        let mut iter = Token::lexer(source).spanned();
-        let next = Self::take_next(&mut iter);
-        Self { iter, next }
+        Self::from_iter(Box::new(Token::lexer(source).spanned()), source.len())
+    }
+
+    /// Create a stream over tokens that have already been lexed, such as those held within
+    /// a syntax tree. Doc comments need to be kept, as the parser attaches them to declarations.
+    pub fn from_tokens(tokens: Vec<Spanned<Token>>, end: usize) -> Self {
+        let iter = tokens
+            .into_iter()
+            .map(|token| (Ok(token.value()), token.span().range()));
+        Self::from_iter(Box::new(iter), end)
+    }
+
+    /// Create a stream over the results of lexing a source, errors included, so that they can
+    /// be shared with something else that needs the same tokens.
+    pub fn from_lexed(tokens: Vec<(Result<Token, ParserError>, Range<usize>)>, end: usize) -> Self {
+        Self::from_iter(Box::new(tokens.into_iter()), end)
+    }
+
+    fn from_iter(mut iter: TokenIter<'src>, end: usize) -> Self {
+        let next = Self::take_next(&mut iter, end);
+        Self { iter, next, end }
     }
 
     pub fn advance(&mut self) {
-        self.next = Self::take_next(&mut self.iter);
+        self.next = Self::take_next(&mut self.iter, self.end);
     }
 
     pub fn current(&mut self) -> ParserResult<Token> {
         self.next.clone()
     }
 
-    fn take_next(iter: &mut SpannedIter<'src, Token>) -> ParserResult<Token> {
-        let result = iter.next();
-        match result {
+    fn take_next(iter: &mut TokenIter<'src>, end: usize) -> ParserResult<Token> {
+        match iter.next() {
             Some((result, span)) => {
                 let generic_span = Span::new(span.start, span.end);
                 match result {
                     Ok(token) => Ok(Spanned::new(token, generic_span)),
-                    Err(error) => Err(Spanned::new(error, generic_span)),
+                    Err(error) => {
+                        // Some errors only apply to part of the token, such as a bad escape
+                        // sequence within a string, so point at just that part.
+                        let error_span = match error.range_within_token() {
+                            Some(range) => {
+                                Span::new(span.start + range.start, span.start + range.end)
+                            }
+                            None => generic_span,
+                        };
+                        Err(Spanned::new(error, error_span))
+                    }
                 }
             }
-            None => {
-                let loc = iter.span().end;
-                Ok(Spanned::new(Token::Eof, Span::new(loc, loc)))
-            }
+            None => Ok(Spanned::new(Token::Eof, Span::new(end, end))),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unterminated_block_comments_are_reported_at_the_opening() {
+        // Given
+        let mut stream = TokenStream::new("x /* a /* b */ c");
+        stream.advance();
+
+        // When
+        let err = stream.current().unwrap_err();
+
+        // Then
+        assert_eq!(err.value(), ParserError::UnterminatedBlockComment);
+        assert_eq!(err.span(), Span::new(2, 4));
+    }
+
+    #[test]
+    fn bad_escape_sequences_are_reported_at_the_escape() {
+        // Given
+        let mut stream = TokenStream::new(r#"x = "abc\qdef";"#);
+        stream.advance();
+        stream.advance();
+
+        // When
+        let err = stream.current().unwrap_err();
+
+        // Then
+        assert_eq!(err.span(), Span::new(8, 10));
+    }
+
+    #[test]
+    fn other_errors_are_reported_at_the_whole_token() {
+        // Given
+        let mut stream = TokenStream::new("x = \"abc");
+        stream.advance();
+        stream.advance();
+
+        // When
+        let err = stream.current().unwrap_err();
+
+        // Then
+        assert_eq!(
+            err.value(),
+            ParserError::UnclosedStringLit("\"abc".to_string())
+        );
+        assert_eq!(err.span(), Span::new(4, 8));
+    }
+}
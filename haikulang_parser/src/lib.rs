@@ -1,4 +1,6 @@
 pub mod ast;
+pub mod cst;
+pub mod dump;
 pub mod error;
 pub mod lexer;
 pub mod parser;
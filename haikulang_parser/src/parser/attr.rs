@@ -0,0 +1,57 @@
+use crate::ast::attr::Attribute;
+use crate::ast::visibility::Visibility;
+use crate::cst::kind::SyntaxKind;
+use crate::error::{ParserError, ParserResult};
+use crate::lexer::token::Token;
+use crate::parser::core::Parser;
+use crate::span::{Span, Spanned};
+
+impl<'src, 'err> Parser<'src, 'err> {
+    // attribute_list ::= attribute* ;
+    pub(super) fn parse_attributes(
+        &mut self,
+    ) -> Result<Box<[Spanned<Attribute>]>, Spanned<ParserError>> {
+        let mut attributes = Vec::new();
+
+        while self.current()?.value() == Token::Hash {
+            attributes.push(self.parse_attribute()?);
+        }
+
+        Ok(attributes.into_boxed_slice())
+    }
+
+    // attribute ::= HASH , LEFT_BRACKET , identifier , attribute_arguments? , RIGHT_BRACKET ;
+    // attribute_arguments ::= LEFT_PAREN , ( expr , ( COMMA , expr )* , COMMA? )? , RIGHT_PAREN ;
+    fn parse_attribute(&mut self) -> ParserResult<Attribute> {
+        let marker = self.marker();
+        let start = self.eat(Token::Hash, "hash")?;
+        self.eat(Token::LeftBracket, "left bracket")?;
+        let name = self.parse_identifier()?;
+
+        let arguments = if self.current()?.value() == Token::LeftParen {
+            let arguments = self.parse_tuple_elements(|parser| parser.parse_expr())?;
+            let span = arguments.span();
+            self.node(marker, SyntaxKind::AttributeArgumentList, span);
+            Some(Spanned::new(arguments.value().0.into_boxed_slice(), span))
+        } else {
+            None
+        };
+
+        let end = self.eat(Token::RightBracket, "right bracket")?;
+        let span = start.span().to(end.span());
+        self.node(marker, SyntaxKind::Attribute, span);
+
+        Ok(Spanned::new(Attribute { name, arguments }, span))
+    }
+}
+
+// Find where a declaration starts, given the attributes and visibility that precede its
+// keyword.
+pub(super) fn declaration_start(
+    attributes: &[Spanned<Attribute>],
+    visibility: &Spanned<Visibility>,
+) -> Span {
+    attributes
+        .first()
+        .map_or(visibility.span(), |attribute| attribute.span())
+}
@@ -1,14 +1,36 @@
+use crate::ast::doc::DocComment;
+use crate::ast::expr::Expr;
+use crate::ast::stmt::Statement;
 use crate::ast::unit::CompilationUnit;
+use crate::cst::event::Event;
+use crate::cst::kind::SyntaxKind;
 use crate::error::{ErrorReporter, ParserError, ParserResult};
+use crate::lexer::identifiers::identifier_warnings;
+use crate::lexer::literals::StrLit;
 use crate::lexer::token::Token;
 use crate::lexer::token_stream::TokenStream;
-use crate::span::Spanned;
+use crate::span::{Span, Spanned};
 use std::path::Path;
 
+// How deeply statements and expressions can be nested before we give up. Each level of
+// nesting recurses through a dozen or so parser functions, so this keeps us within the 2MiB
+// stack given to spawned threads, even in debug builds.
+const MAX_NESTING_DEPTH: usize = 64;
+
 pub struct Parser<'src, 'err> {
     stream: TokenStream<'src>,
     path: &'src Path,
     error_reporter: &'err mut dyn ErrorReporter,
+    // Documentation comments that immediately precede the current token.
+    doc_lines: Vec<Spanned<String>>,
+    // Documentation comments for the compilation unit itself.
+    inner_doc_lines: Vec<Spanned<String>>,
+    // How many nested statements and expressions we are currently within.
+    depth: usize,
+    // Every identifier consumed so far, checked for confusing names once parsing completes.
+    identifiers: Vec<Spanned<StrLit>>,
+    // The nodes of the lossless syntax tree parsed so far, if we are building one.
+    events: Option<Vec<Event>>,
 }
 
 impl<'src, 'err> Parser<'src, 'err> {
@@ -21,12 +43,61 @@ impl<'src, 'err> Parser<'src, 'err> {
             stream,
             path,
             error_reporter,
+            doc_lines: Vec::new(),
+            inner_doc_lines: Vec::new(),
+            depth: 0,
+            identifiers: Vec::new(),
+            events: None,
         }
     }
 
+    /// Record the nodes of a lossless syntax tree while parsing, which can be taken once
+    /// parsing completes, whether it succeeds or not.
+    pub(crate) fn record_syntax_tree(&mut self) {
+        self.events = Some(Vec::new());
+    }
+
+    /// Take the syntax tree nodes recorded so far.
+    pub(crate) fn take_syntax_events(&mut self) -> Vec<Event> {
+        self.events.take().unwrap_or_default()
+    }
+
     pub fn parse(&mut self) -> ParserResult<CompilationUnit> {
         self.consume_comments();
-        self.parse_compilation_unit(self.path)
+        let result = self.parse_compilation_unit(self.path);
+        self.warn_about_identifiers();
+        result
+    }
+
+    /// Parse input that consists only of statements, such as the body of a function without
+    /// its braces. This is how interactive sessions run code outside of any function.
+    pub fn parse_statements(&mut self) -> ParserResult<Box<[Spanned<Statement>]>> {
+        self.consume_comments();
+        let start = self.current()?.span();
+        let mut statements = Vec::new();
+
+        while self.current()?.value() != Token::Eof {
+            statements.push(self.parse_statement()?);
+        }
+
+        let end = self.current()?.span();
+        self.warn_about_identifiers();
+        Ok(Spanned::new(statements.into_boxed_slice(), start.to(end)))
+    }
+
+    /// Parse input that consists of a single expression, failing if anything follows it.
+    pub fn parse_standalone_expr(&mut self) -> ParserResult<Expr> {
+        self.consume_comments();
+        let expr = self.parse_expr()?;
+        self.eat(Token::Eof, "end of input")?;
+        self.warn_about_identifiers();
+        Ok(expr)
+    }
+
+    fn warn_about_identifiers(&mut self) {
+        for warning in identifier_warnings(&self.identifiers) {
+            self.error_reporter.warn(&warning);
+        }
     }
 
     // Report an error.
@@ -50,20 +121,146 @@ impl<'src, 'err> Parser<'src, 'err> {
     // Advance the lexer to the next token.
     #[inline]
     pub(super) fn advance(&mut self) {
+        if let Ok(token) = self.stream.current()
+            && let Token::Identifier(name) = token.value_ref()
+        {
+            self.identifiers
+                .push(Spanned::new(name.clone(), token.span()));
+        }
+
         self.stream.advance();
+        self.doc_lines.clear();
         self.consume_comments();
     }
 
-    // Repeatedly take comments from the token stream.
-    // Eventually, we may want to inspect comments and store them if they meet certain
-    // criteria, to allow for parsing documentation. For now though, we just discard them.
+    // Mark where a syntax tree node may start, before parsing any of its children.
+    #[inline]
+    pub(super) fn marker(&self) -> usize {
+        self.events.as_ref().map_or(0, Vec::len)
+    }
+
+    // Record a syntax tree node over the given span, taking every node recorded since the
+    // marker as its children. The same marker can be used again to wrap the node within
+    // another, such as when it turns out to be the left operand of a binary expression.
+    pub(super) fn node(&mut self, marker: usize, kind: SyntaxKind, span: Span) {
+        if let Some(events) = &mut self.events {
+            let offset = span.start();
+            events.insert(marker, Event::Start { kind, offset });
+            events.push(Event::Finish { offset: span.end() });
+        }
+    }
+
+    // Stretch the syntax tree node recorded at the marker over the given span, such as to
+    // take in the parentheses around it.
+    pub(super) fn widen_node(&mut self, marker: usize, span: Span) {
+        if let Some(events) = &mut self.events {
+            if let Some(Event::Start { offset, .. }) = events.get_mut(marker) {
+                *offset = span.start();
+            }
+            if let Some(Event::Finish { offset }) = events.last_mut() {
+                *offset = span.end();
+            }
+        }
+    }
+
+    // Record a syntax tree node that starts at the given offset but could never be finished,
+    // as parsing gave up part way through it.
+    pub(super) fn unfinished_node(&mut self, marker: usize, kind: SyntaxKind, offset: usize) {
+        if let Some(events) = &mut self.events {
+            events.insert(marker, Event::Start { kind, offset });
+        }
+    }
+
+    // Parse a nested statement or expression, reporting an error rather than overflowing the
+    // stack if the input is nested too deeply.
+    pub(super) fn nested<T: Clone, F>(&mut self, func: F) -> ParserResult<T>
+    where
+        F: FnOnce(&mut Self) -> ParserResult<T>,
+    {
+        if self.depth >= MAX_NESTING_DEPTH {
+            let err = Spanned::new(
+                ParserError::SyntaxError(format!(
+                    "nesting is too deep (the maximum depth is {})",
+                    MAX_NESTING_DEPTH
+                )),
+                self.current()?.span(),
+            );
+            self.report_error(&err);
+            return Err(err);
+        }
+
+        self.depth += 1;
+        let result = func(self);
+        self.depth -= 1;
+        result
+    }
+
+    // Parse a parenthesised list of tuple elements, which may be empty and may end with a
+    // comma. Alongside the elements, this returns whether a trailing comma was present, as
+    // that is what tells a tuple of one element apart from something in parentheses.
+    pub(super) fn parse_tuple_elements<T: Clone, F>(
+        &mut self,
+        mut element: F,
+    ) -> ParserResult<(Vec<Spanned<T>>, bool)>
+    where
+        F: FnMut(&mut Self) -> ParserResult<T>,
+    {
+        let left_paren = self.eat(Token::LeftParen, "left parenthesis")?;
+        let mut elements = Vec::new();
+        let mut trailing_comma = false;
+
+        while self.current()?.value() != Token::RightParen {
+            elements.push(element(self)?);
+            trailing_comma = self.current()?.value() == Token::Comma;
+
+            if trailing_comma {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+
+        let right_paren = self.eat(Token::RightParen, "right parenthesis")?;
+        Ok(Spanned::new(
+            (elements, trailing_comma),
+            left_paren.span().to(right_paren.span()),
+        ))
+    }
+
+    // Take the documentation comment preceding the current token, if there is one.
+    // Declarations that support documentation call this before consuming their first token.
+    pub(super) fn take_doc_comment(&mut self) -> Option<Spanned<DocComment>> {
+        join_doc_lines(&mut self.doc_lines)
+    }
+
+    // Take the documentation comments for the compilation unit that have been seen so far.
+    pub(super) fn take_inner_doc_comment(&mut self) -> Option<Spanned<DocComment>> {
+        join_doc_lines(&mut self.inner_doc_lines)
+    }
+
+    // Repeatedly take comments from the token stream. Documentation comments are kept
+    // so that the next declaration or the compilation unit can claim them. Regular comments
+    // are discarded.
     fn consume_comments(&mut self) {
-        while let Ok(token) = self.current()
-            && matches!(
-                token.value(),
-                Token::InlineComment(_) | Token::MultilineComment(_)
-            )
-        {
+        while let Ok(token) = self.current() {
+            let span = token.span();
+            match token.value() {
+                Token::InlineDocComment(content) => self
+                    .doc_lines
+                    .push(Spanned::new(DocComment::inline_doc_line(&content), span)),
+                Token::MultilineDocComment(content) => self
+                    .doc_lines
+                    .push(Spanned::new(DocComment::multiline_doc_text(&content), span)),
+                Token::InlineInnerDocComment(content) => self
+                    .inner_doc_lines
+                    .push(Spanned::new(DocComment::inline_doc_line(&content), span)),
+                Token::MultilineInnerDocComment(content) => self
+                    .inner_doc_lines
+                    .push(Spanned::new(DocComment::multiline_doc_text(&content), span)),
+                Token::InlineComment(_) | Token::MultilineComment(_) => {}
+                _ => break,
+            }
+
             self.stream.advance();
         }
     }
@@ -89,6 +286,18 @@ impl<'src, 'err> Parser<'src, 'err> {
     }
 }
 
+fn join_doc_lines(lines: &mut Vec<Spanned<String>>) -> Option<Spanned<DocComment>> {
+    let first = lines.first()?.span();
+    let last = lines.last()?.span();
+    let text = lines
+        .drain(..)
+        .map(|line| line.value())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Some(Spanned::new(DocComment { text }, first.to(last)))
+}
+
 // Internal macro to allow asserting a token or AST matches a condition that should
 // always be true. Generally, it is preferred to use the 'eat' function which also advances the
 // lexer, but this is useful when splitting out preconditions.
@@ -104,3 +313,631 @@ macro_rules! debug_assert_matches {
         );
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::stmt::Pattern;
+    use crate::ast::types::TypeName;
+    use crate::ast::unit::CompilationUnitMember;
+    use crate::ast::visibility::Visibility;
+    use crate::error::{Diagnostics, ParserWarning};
+    use crate::span::Span;
+    use test_case::test_case;
+
+    fn parse(source: &str) -> CompilationUnit {
+        let mut errors: Vec<Spanned<ParserError>> = Vec::new();
+        let mut parser = Parser::new(TokenStream::new(source), Path::new("test.hkl"), &mut errors);
+        parser.parse().unwrap().value()
+    }
+
+    fn doc_text(doc: &Option<Spanned<DocComment>>) -> Option<String> {
+        doc.as_ref().map(|doc| doc.value().text)
+    }
+
+    #[test]
+    fn doc_comments_attach_to_functions_and_parameters() {
+        // Given
+        let source = "/// Adds things.\n/// Really.\nfn add(\n  /// The first.\n  a: i32,\n  b: i32\n) -> i32 { return a + b; }";
+
+        // When
+        let unit = parse(source);
+
+        // Then
+        let CompilationUnitMember::Function(function) = unit.members[0].value() else {
+            panic!("expected a function");
+        };
+        let doc = function.doc.as_ref().unwrap();
+        assert_eq!(doc.value().text, "Adds things.\nReally.");
+        assert_eq!(
+            &source[doc.span().range()],
+            "/// Adds things.\n/// Really.\n"
+        );
+
+        let parameters = function.parameters.value();
+        assert_eq!(
+            doc_text(&parameters[0].value().doc),
+            Some("The first.".to_string())
+        );
+        assert_eq!(doc_text(&parameters[1].value().doc), None);
+    }
+
+    #[test]
+    fn doc_comments_attach_to_structs_and_members() {
+        // Given
+        let source = "/**\n * A point.\n */\nstruct Point {\n  /** X. */ x: i32;\n  // Not documentation.\n  y: i32;\n}";
+
+        // When
+        let unit = parse(source);
+
+        // Then
+        let CompilationUnitMember::Struct(struct_decl) = unit.members[0].value() else {
+            panic!("expected a struct");
+        };
+        assert_eq!(doc_text(&struct_decl.doc), Some("A point.".to_string()));
+        assert_eq!(
+            doc_text(&struct_decl.members[0].value().doc),
+            Some("X.".to_string())
+        );
+        assert_eq!(doc_text(&struct_decl.members[1].value().doc), None);
+    }
+
+    #[test]
+    fn doc_comments_attach_to_extern_functions() {
+        // When
+        let unit = parse("/// Prints.\nextern fn print(fmt: string);");
+
+        // Then
+        let CompilationUnitMember::ExternFunction(function) = unit.members[0].value() else {
+            panic!("expected an extern function");
+        };
+        assert_eq!(doc_text(&function.doc), Some("Prints.".to_string()));
+    }
+
+    #[test]
+    fn inner_doc_comments_attach_to_the_compilation_unit() {
+        // Given
+        let source = "//! Shapes.\n/*! More. */\n/// A point.\nstruct Point {}";
+
+        // When
+        let unit = parse(source);
+
+        // Then
+        assert_eq!(doc_text(&unit.doc), Some("Shapes.\nMore.".to_string()));
+        assert_eq!(
+            &source[unit.doc.as_ref().unwrap().span().range()],
+            "//! Shapes.\n/*! More. */"
+        );
+
+        let CompilationUnitMember::Struct(struct_decl) = unit.members[0].value() else {
+            panic!("expected a struct");
+        };
+        assert_eq!(doc_text(&struct_decl.doc), Some("A point.".to_string()));
+    }
+
+    #[test]
+    fn regular_comments_and_unclaimed_docs_are_discarded() {
+        // When
+        let unit = parse(
+            "/// Orphaned.\nuse std;\n// Regular.\n//// Also regular.\n/* /** Nested. */ */\n/**/ /*** Regular. */\nfn main() {}",
+        );
+
+        // Then
+        let CompilationUnitMember::Function(function) = unit.members[1].value() else {
+            panic!("expected a function");
+        };
+        assert_eq!(doc_text(&function.doc), None);
+    }
+
+    #[test]
+    fn confusing_identifiers_are_reported_as_warnings() {
+        // Given
+        let source =
+            "fn scope() {} fn \u{0455}\u{0441}\u{043E}\u{0440}\u{0435}() {} fn p\u{0430}ypal() {}";
+        let mut diagnostics = Diagnostics::default();
+
+        // When
+        Parser::new(
+            TokenStream::new(source),
+            Path::new("test.hkl"),
+            &mut diagnostics,
+        )
+        .parse()
+        .unwrap();
+
+        // Then
+        assert_eq!(diagnostics.errors, vec![]);
+        assert_eq!(
+            diagnostics
+                .warnings
+                .iter()
+                .map(|warning| warning.value())
+                .collect::<Vec<_>>(),
+            vec![
+                ParserWarning::ConfusableIdentifiers {
+                    identifier: "\u{0455}\u{0441}\u{043E}\u{0440}\u{0435}".to_string(),
+                    other: "scope".to_string(),
+                },
+                ParserWarning::MixedScriptIdentifier("p\u{0430}ypal".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn identifiers_with_different_normalization_are_the_same() {
+        // When
+        let unit = parse("fn cafe\u{301}() {} fn main() { caf\u{e9}(); }");
+
+        // Then
+        let CompilationUnitMember::Function(function) = unit.members[0].value() else {
+            panic!("expected a function");
+        };
+        assert_eq!(function.name.value().value, "caf\u{e9}");
+    }
+
+    #[test_case("(", ")" ; "parentheses")]
+    #[test_case("-", "" ; "unary operators")]
+    #[test_case("2 ** ", "" ; "powers")]
+    #[test_case("x = ", "" ; "assignments")]
+    #[test_case("f(", ")" ; "function calls")]
+    fn deeply_nested_expressions_are_rejected(open: &str, close: &str) {
+        // Given
+        let source = format!("fn f() = {}1{};", open.repeat(10_000), close.repeat(10_000));
+        let mut errors: Vec<Spanned<ParserError>> = Vec::new();
+
+        // When
+        let result = Parser::new(
+            TokenStream::new(&source),
+            Path::new("test.hkl"),
+            &mut errors,
+        )
+        .parse();
+
+        // Then
+        let err = result.unwrap_err();
+        assert_eq!(
+            err.value(),
+            ParserError::SyntaxError(format!(
+                "nesting is too deep (the maximum depth is {})",
+                MAX_NESTING_DEPTH
+            ))
+        );
+        assert_eq!(errors, vec![err]);
+    }
+
+    #[test]
+    fn deeply_nested_statements_are_rejected() {
+        // Given
+        let source = format!("fn f() {}{}", "{".repeat(10_000), "}".repeat(10_000));
+        let mut errors: Vec<Spanned<ParserError>> = Vec::new();
+
+        // When
+        let result = Parser::new(
+            TokenStream::new(&source),
+            Path::new("test.hkl"),
+            &mut errors,
+        )
+        .parse();
+
+        // Then
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn nesting_within_the_limit_is_accepted() {
+        // Given
+        let source = format!("fn f() {{ return {}1{}; }}", "(".repeat(50), ")".repeat(50));
+
+        // When
+        let unit = parse(&source);
+
+        // Then
+        assert_eq!(unit.members.len(), 1);
+    }
+
+    #[test]
+    fn empty_statements_need_a_single_semicolon() {
+        // When
+        let unit = parse("fn f() { ; ; }");
+
+        // Then
+        let CompilationUnitMember::Function(function) = unit.members[0].value() else {
+            panic!("expected a function");
+        };
+        let Statement::Block(block) = function.body.value() else {
+            panic!("expected a block");
+        };
+        assert_eq!(block.statements.len(), 2);
+        assert!(
+            block
+                .statements
+                .iter()
+                .all(|statement| statement.value() == Statement::Empty)
+        );
+    }
+
+    #[test]
+    fn expression_functions_are_parsed() {
+        // When
+        let unit = parse("fn double(x: i32) = x * 2;");
+
+        // Then
+        let CompilationUnitMember::Function(function) = unit.members[0].value() else {
+            panic!("expected a function");
+        };
+        assert!(matches!(function.body.value(), Statement::Expr(_)));
+        assert_eq!(unit.members[0].span(), Span::new(0, 26));
+    }
+
+    #[test_case("f(1)(2)" ; "calls on calls")]
+    #[test_case("(f)(1)" ; "calls on parenthesised expressions")]
+    #[test_case("a[0](1)" ; "calls on index expressions")]
+    fn calls_can_be_made_on_any_expression(expr: &str) {
+        // When
+        let unit = parse(&format!("fn f() = {};", expr));
+
+        // Then
+        let CompilationUnitMember::Function(function) = unit.members[0].value() else {
+            panic!("expected a function");
+        };
+        assert_eq!(function.body.span(), Span::new(9, 9 + expr.len()));
+    }
+
+    #[test]
+    fn closures_are_parsed() {
+        // When
+        let unit = parse("fn f() = apply(|x, y: i32| x + y, 1);");
+
+        // Then
+        let CompilationUnitMember::Function(function) = unit.members[0].value() else {
+            panic!("expected a function");
+        };
+        let Statement::Expr(expr) = function.body.value() else {
+            panic!("expected an expression body");
+        };
+        let Expr::FunctionCall(call) = *expr else {
+            panic!("expected a function call");
+        };
+        let Expr::Closure(closure) = call.arguments.value_ref()[0].value() else {
+            panic!("expected a closure");
+        };
+        assert_eq!(call.arguments.value_ref()[0].span(), Span::new(15, 32));
+        assert_eq!(closure.parameters.span(), Span::new(15, 26));
+        assert!(
+            closure.parameters.value_ref()[0]
+                .value_ref()
+                .type_name
+                .is_none()
+        );
+        assert!(
+            closure.parameters.value_ref()[1]
+                .value_ref()
+                .type_name
+                .is_some()
+        );
+        assert!(matches!(closure.body.value(), Statement::Expr(_)));
+    }
+
+    #[test_case("|| 1"                  ; "without parameters")]
+    #[test_case("|| -> i32 { return 1; }" ; "with a return type")]
+    #[test_case("|x| { return x; }"     ; "with a block")]
+    #[test_case("|x| |y| x + y"         ; "returning closures")]
+    fn closure_bodies_are_parsed(expr: &str) {
+        // When
+        let unit = parse(&format!("fn f() = {};", expr));
+
+        // Then
+        let CompilationUnitMember::Function(function) = unit.members[0].value() else {
+            panic!("expected a function");
+        };
+        assert_eq!(function.body.span(), Span::new(9, 9 + expr.len()));
+    }
+
+    #[test]
+    fn function_types_are_parsed() {
+        // When
+        let unit = parse("fn f(g: fn(i32, bool) -> fn() -> i32, h: fn()) {}");
+
+        // Then
+        let CompilationUnitMember::Function(function) = unit.members[0].value() else {
+            panic!("expected a function");
+        };
+        let parameters = function.parameters.value_ref();
+        let TypeName::Function(g) = parameters[0].value_ref().type_name.value() else {
+            panic!("expected a function type");
+        };
+        assert_eq!(parameters[0].value_ref().type_name.span(), Span::new(8, 36));
+        assert_eq!(g.parameters.value_ref().len(), 2);
+        assert!(matches!(
+            g.return_type.as_ref().map(Spanned::value_ref),
+            Some(TypeName::Function(_))
+        ));
+        let TypeName::Function(h) = parameters[1].value_ref().type_name.value() else {
+            panic!("expected a function type");
+        };
+        assert!(h.parameters.value_ref().is_empty());
+        assert!(h.return_type.is_none());
+    }
+
+    #[test_case("()"      , 0 ; "empty")]
+    #[test_case("(1,)"    , 1 ; "single element")]
+    #[test_case("(1, x)"  , 2 ; "pair")]
+    #[test_case("(1, x, )", 2 ; "trailing comma")]
+    fn tuples_are_parsed(expr: &str, length: usize) {
+        // When
+        let unit = parse(&format!("fn f() = {};", expr));
+
+        // Then
+        let CompilationUnitMember::Function(function) = unit.members[0].value() else {
+            panic!("expected a function");
+        };
+        let Statement::Expr(expr) = function.body.value() else {
+            panic!("expected an expression body");
+        };
+        let Expr::Tuple(tuple) = *expr else {
+            panic!("expected a tuple");
+        };
+        assert_eq!(tuple.elements.len(), length);
+    }
+
+    #[test]
+    fn tuple_positions_are_parsed() {
+        // When
+        let unit = parse("fn f() = (pair.0).12;");
+
+        // Then
+        let CompilationUnitMember::Function(function) = unit.members[0].value() else {
+            panic!("expected a function");
+        };
+        let Statement::Expr(expr) = function.body.value() else {
+            panic!("expected an expression body");
+        };
+        let Expr::MemberAccess(outer) = *expr else {
+            panic!("expected a member access");
+        };
+        assert_eq!(outer.member.value_ref().value, "12");
+        assert_eq!(outer.member.span(), Span::new(18, 20));
+        let Expr::MemberAccess(inner) = outer.owner.value() else {
+            panic!("expected a member access");
+        };
+        assert_eq!(inner.member.value_ref().value, "0");
+        assert_eq!(outer.owner.span(), Span::new(9, 17));
+    }
+
+    #[test_case("pair.0.1",   "nested tuple elements must be accessed with parentheses, such as (pair.0).1" ; "nested positions")]
+    #[test_case("pair.0i32",  "tuple element positions cannot have a type suffix"                          ; "suffixed positions")]
+    fn invalid_tuple_positions_are_rejected(expr: &str, message: &str) {
+        // Given
+        let source = format!("fn f() = {};", expr);
+        let mut errors: Vec<Spanned<ParserError>> = Vec::new();
+
+        // When
+        let result = Parser::new(
+            TokenStream::new(&source),
+            Path::new("test.hkl"),
+            &mut errors,
+        )
+        .parse();
+
+        // Then
+        let err = result.unwrap_err();
+        assert_eq!(err.value(), ParserError::SyntaxError(message.to_string()));
+        assert_eq!(err.span(), Span::new(14, source.len() - 1));
+    }
+
+    #[test]
+    fn tuple_patterns_are_parsed() {
+        // When
+        let unit = parse("fn f() { let (a, (b,), (c)) : ((i32), (bool,), ()) = g(); }");
+
+        // Then
+        let CompilationUnitMember::Function(function) = unit.members[0].value() else {
+            panic!("expected a function");
+        };
+        let Statement::Block(block) = function.body.value() else {
+            panic!("expected a block");
+        };
+        let Statement::VarDecl(var_decl) = block.statements[0].value() else {
+            panic!("expected a variable declaration");
+        };
+        let Pattern::Tuple(elements) = var_decl.pattern.value() else {
+            panic!("expected a tuple pattern");
+        };
+        assert_eq!(var_decl.pattern.span(), Span::new(13, 27));
+        assert!(matches!(elements[0].value_ref(), Pattern::Identifier(_)));
+        assert!(matches!(elements[1].value_ref(), Pattern::Tuple(inner) if inner.len() == 1));
+        assert!(matches!(elements[2].value_ref(), Pattern::Identifier(_)));
+        assert_eq!(elements[2].span(), Span::new(23, 26));
+
+        let Some(TypeName::Tuple(tuple)) = var_decl.type_name.as_ref().map(Spanned::value) else {
+            panic!("expected a tuple type");
+        };
+        assert!(matches!(tuple.elements[0].value_ref(), TypeName::Path(_)));
+        assert!(
+            matches!(tuple.elements[1].value_ref(), TypeName::Tuple(inner) if inner.elements.len() == 1)
+        );
+        assert!(
+            matches!(tuple.elements[2].value_ref(), TypeName::Tuple(inner) if inner.elements.is_empty())
+        );
+    }
+
+    #[test]
+    fn tuple_patterns_without_values_are_rejected() {
+        // Given
+        let source = "fn f() { let (a, b): (i32, i32); }";
+        let mut errors: Vec<Spanned<ParserError>> = Vec::new();
+
+        // When
+        let result =
+            Parser::new(TokenStream::new(source), Path::new("test.hkl"), &mut errors).parse();
+
+        // Then
+        let err = result.unwrap_err();
+        assert_eq!(
+            err.value(),
+            ParserError::SyntaxError(
+                "expected assignment in variable declaration with a tuple pattern".to_string()
+            )
+        );
+        assert_eq!(err.span(), Span::new(9, 32));
+    }
+
+    #[test]
+    fn visibility_is_parsed() {
+        // Given
+        let source = "/// Doc.\npub fn f() {}\nfn g() {}\npub extern fn h();\npub struct S { pub a: i32; b: i32; }";
+
+        // When
+        let unit = parse(source);
+
+        // Then
+        let CompilationUnitMember::Function(public) = unit.members[0].value() else {
+            panic!("expected a function");
+        };
+        assert_eq!(public.visibility, Visibility::Public);
+        assert_eq!(doc_text(&public.doc), Some("Doc.".to_string()));
+        assert_eq!(&source[unit.members[0].span().range()], "pub fn f() {}");
+
+        let CompilationUnitMember::Function(private) = unit.members[1].value() else {
+            panic!("expected a function");
+        };
+        assert_eq!(private.visibility, Visibility::Private);
+
+        let CompilationUnitMember::ExternFunction(function) = unit.members[2].value() else {
+            panic!("expected an extern function");
+        };
+        assert_eq!(function.visibility, Visibility::Public);
+
+        let CompilationUnitMember::Struct(struct_decl) = unit.members[3].value() else {
+            panic!("expected a struct");
+        };
+        assert_eq!(struct_decl.visibility, Visibility::Public);
+        assert_eq!(
+            struct_decl.members[0].value().visibility,
+            Visibility::Public
+        );
+        assert_eq!(&source[struct_decl.members[0].span().range()], "pub a: i32");
+        assert_eq!(
+            struct_decl.members[1].value().visibility,
+            Visibility::Private
+        );
+    }
+
+    #[test]
+    fn public_use_declarations_are_rejected() {
+        // Given
+        let source = "pub use std;";
+        let mut errors: Vec<Spanned<ParserError>> = Vec::new();
+
+        // When
+        let result =
+            Parser::new(TokenStream::new(source), Path::new("test.hkl"), &mut errors).parse();
+
+        // Then
+        let err = result.unwrap_err();
+        assert_eq!(
+            err.value(),
+            ParserError::SyntaxError("use declarations cannot be marked as pub".to_string())
+        );
+        assert_eq!(err.span(), Span::new(0, 3));
+    }
+
+    #[test]
+    fn attributes_are_parsed() {
+        // Given
+        let source = "/// Doc.\n#[test] #[link_name(\"puts\", 1,)] pub extern fn f();\nstruct S { #[inline()] x: i32; }";
+
+        // When
+        let unit = parse(source);
+
+        // Then
+        let CompilationUnitMember::ExternFunction(function) = unit.members[0].value() else {
+            panic!("expected an extern function");
+        };
+        assert_eq!(doc_text(&function.doc), Some("Doc.".to_string()));
+        assert_eq!(
+            &source[unit.members[0].span().range()],
+            "#[test] #[link_name(\"puts\", 1,)] pub extern fn f()"
+        );
+
+        let test = function.attributes[0].value();
+        assert_eq!(test.name.value().value, "test");
+        assert!(test.arguments.is_none());
+
+        let link_name = function.attributes[1].value();
+        assert_eq!(link_name.name.value().value, "link_name");
+        let arguments = link_name.arguments.unwrap();
+        assert_eq!(&source[arguments.span().range()], "(\"puts\", 1,)");
+        assert!(matches!(
+            arguments.value_ref()[0].value_ref(),
+            Expr::String(_)
+        ));
+        assert!(matches!(arguments.value_ref()[1].value_ref(), Expr::Int(_)));
+
+        let CompilationUnitMember::Struct(struct_decl) = unit.members[1].value() else {
+            panic!("expected a struct");
+        };
+        let member = &struct_decl.members[0];
+        assert_eq!(&source[member.span().range()], "#[inline()] x: i32");
+        let inline = member.value_ref().attributes[0].value();
+        assert_eq!(inline.name.value().value, "inline");
+        assert_eq!(
+            inline.arguments.map(|arguments| arguments.value().len()),
+            Some(0)
+        );
+    }
+
+    #[test_case("#[test] use std;", "use declarations cannot have attributes", 0, 7 ; "attributes on use declarations")]
+    #[test_case(   "#[] fn f() {}",                     "expected identifier", 2, 3 ; "missing name")]
+    #[test_case( "#[test fn f() {}",                  "expected right bracket", 7, 9 ; "unclosed attribute")]
+    fn invalid_attributes_are_rejected(source: &str, message: &str, start: usize, end: usize) {
+        // Given
+        let mut errors: Vec<Spanned<ParserError>> = Vec::new();
+
+        // When
+        let result =
+            Parser::new(TokenStream::new(source), Path::new("test.hkl"), &mut errors).parse();
+
+        // Then
+        let err = result.unwrap_err();
+        assert_eq!(err.value(), ParserError::SyntaxError(message.to_string()));
+        assert_eq!(err.span(), Span::new(start, end));
+    }
+
+    #[test]
+    fn statements_can_be_parsed_on_their_own() {
+        // Given
+        let source = "// Comment.\nlet x = 1;\nif (x > 0) { x += 1; }";
+        let mut errors: Vec<Spanned<ParserError>> = Vec::new();
+
+        // When
+        let statements = Parser::new(TokenStream::new(source), Path::new("test.hkl"), &mut errors)
+            .parse_statements()
+            .unwrap()
+            .value();
+
+        // Then
+        assert_eq!(errors, vec![]);
+        let sources: Vec<&str> = statements
+            .iter()
+            .map(|statement| &source[statement.span().range()])
+            .collect();
+        assert_eq!(sources, vec!["let x = 1", "if (x > 0) { x += 1; }"]);
+    }
+
+    #[test_case(   "1 + 2",  true ; "expression")]
+    #[test_case(  "1 + 2;", false ; "statement")]
+    #[test_case("1 + 2 3", false ; "trailing input")]
+    fn standalone_expressions_must_span_the_input(source: &str, valid: bool) {
+        // Given
+        let mut errors: Vec<Spanned<ParserError>> = Vec::new();
+
+        // When
+        let result = Parser::new(TokenStream::new(source), Path::new("test.hkl"), &mut errors)
+            .parse_standalone_expr();
+
+        // Then
+        assert_eq!(result.is_ok(), valid);
+        assert_eq!(errors.is_empty(), valid);
+    }
+}
@@ -1,6 +1,9 @@
 use crate::ast::expr::*;
+use crate::ast::ident::Identifier;
+use crate::cst::kind::SyntaxKind;
 use crate::debug_assert_matches;
 use crate::error::{ParserError, ParserResult};
+use crate::lexer::literals::IntLit;
 use crate::lexer::token::Token;
 use crate::parser::core::Parser;
 use crate::span::Spanned;
@@ -11,7 +14,7 @@ impl<'src, 'err> Parser<'src, 'err> {
     //  help avoid stack overflows on heavily nested expressions.
     #[allow(dead_code)]
     pub fn parse_expr(&mut self) -> ParserResult<Expr> {
-        self.parse_assignment_expr()
+        self.nested(Self::parse_assignment_expr)
     }
 
     // assignment_expr ::= bool_or_expr , ASSIGN , assignment_expr
@@ -29,6 +32,7 @@ impl<'src, 'err> Parser<'src, 'err> {
     //                   | bool_or_expr
     //                   ;
     fn parse_assignment_expr(&mut self) -> ParserResult<Expr> {
+        let marker = self.marker();
         let lvalue = self.parse_bool_or_expr()?;
 
         let op = match self.current()?.value() {
@@ -51,9 +55,10 @@ impl<'src, 'err> Parser<'src, 'err> {
         // Verify lvalue is assignable.
         // Purposely recursive here, to force right-associativity.
         // `x = y = z = a` is parsed as `(x = (y = (z = a)))`
-        let rvalue = self.parse_assignment_expr()?;
+        let rvalue = self.nested(Self::parse_assignment_expr)?;
 
         let span = lvalue.span().to(rvalue.span());
+        self.node(marker, SyntaxKind::AssignmentExpr, span);
 
         Ok(Spanned::new(
             Expr::Assignment(Box::new(AssignmentExpr { lvalue, op, rvalue })),
@@ -213,6 +218,7 @@ impl<'src, 'err> Parser<'src, 'err> {
     //              | pow_expr
     //              ;
     fn parse_unary_expr(&mut self) -> ParserResult<Expr> {
+        let marker = self.marker();
         let first = self.current()?;
         let op = match first.value() {
             Token::Add => UnaryOp::Plus,
@@ -223,8 +229,9 @@ impl<'src, 'err> Parser<'src, 'err> {
         };
         self.advance();
 
-        let value = self.parse_unary_expr()?;
+        let value = self.nested(Self::parse_unary_expr)?;
         let span = first.span().to(value.span());
+        self.node(marker, SyntaxKind::UnaryExpr, span);
 
         Ok(Spanned::new(
             Expr::Unary(Box::new(UnaryExpr { op, value })),
@@ -236,6 +243,7 @@ impl<'src, 'err> Parser<'src, 'err> {
     //            | primary_expr
     //            ;
     fn parse_pow_expr(&mut self) -> ParserResult<Expr> {
+        let marker = self.marker();
         let left = self.parse_primary_expr()?;
 
         let op = match self.current()?.value() {
@@ -248,9 +256,10 @@ impl<'src, 'err> Parser<'src, 'err> {
         // In maths, we always say `x ** y ** z` is `(x ** (y ** z))`. This differs to
         // most of the expr grammar here, so we treat it as an edge case and do not
         // wrap it in a utility handler helper.
-        let right = self.parse_pow_expr()?;
+        let right = self.nested(Self::parse_pow_expr)?;
 
         let span = left.span().to(right.span());
+        self.node(marker, SyntaxKind::BinaryExpr, span);
 
         Ok(Spanned::new(
             Expr::Binary(Box::new(BinaryExpr { left, op, right })),
@@ -260,14 +269,16 @@ impl<'src, 'err> Parser<'src, 'err> {
 
     // primary_expr  ::= atom , ( member_access_expr | index_expr | function_call_expr )* ;
     fn parse_primary_expr(&mut self) -> ParserResult<Expr> {
+        let marker = self.marker();
         let mut expr = self.parse_atom()?;
 
-        // Consume chained calls and selectors
+        // Consume chained calls and selectors, each of which wraps the syntax tree node of
+        // the expression before it.
         loop {
             expr = match self.current()?.value() {
-                Token::Period => self.parse_member_access_expr(expr)?,
-                Token::LeftBracket => self.parse_index_expr(expr)?,
-                Token::LeftParen => self.parse_function_call(expr)?,
+                Token::Period => self.parse_member_access_expr(marker, expr)?,
+                Token::LeftBracket => self.parse_index_expr(marker, expr)?,
+                Token::LeftParen => self.parse_function_call(marker, expr)?,
                 _ => break,
             }
         }
@@ -275,25 +286,64 @@ impl<'src, 'err> Parser<'src, 'err> {
         Ok(expr)
     }
 
-    // member_access_expr ::= PERIOD , identifier ;
-    fn parse_member_access_expr(&mut self, owner: Spanned<Expr>) -> ParserResult<Expr> {
+    // member_access_expr ::= PERIOD , ( identifier | INT_LIT ) ;
+    fn parse_member_access_expr(
+        &mut self,
+        marker: usize,
+        owner: Spanned<Expr>,
+    ) -> ParserResult<Expr> {
         debug_assert_matches!(self.current()?.value(), Token::Period);
         self.advance();
-        let member = self.parse_identifier()?;
+        let member = match self.current()?.value() {
+            Token::IntLit(_) | Token::FloatLit(_) => self.parse_tuple_index()?,
+            _ => self.parse_identifier()?,
+        };
         let span = owner.span().to(member.span());
+        self.node(marker, SyntaxKind::MemberAccessExpr, span);
         Ok(Spanned::new(
             Expr::MemberAccess(Box::new(MemberAccessExpr { owner, member })),
             span,
         ))
     }
 
+    // Tuple elements are accessed by their position, such as "pair.0", which is kept as a
+    // member named after the decimal position.
+    fn parse_tuple_index(&mut self) -> ParserResult<Identifier> {
+        let current = self.current()?;
+
+        let message = match current.value() {
+            Token::IntLit(IntLit::Untyped(index)) => {
+                self.advance();
+                return Ok(Spanned::new(
+                    Identifier {
+                        value: index.to_string(),
+                    },
+                    current.span(),
+                ));
+            }
+            // "pair.0.1" lexes the "0.1" as a float, and we cannot tell how it was written.
+            Token::FloatLit(_) => {
+                "nested tuple elements must be accessed with parentheses, such as (pair.0).1"
+            }
+            _ => "tuple element positions cannot have a type suffix",
+        };
+
+        let err = Spanned::new(
+            ParserError::SyntaxError(message.to_string()),
+            current.span(),
+        );
+        self.report_error(&err);
+        Err(err)
+    }
+
     // index_expr ::= LEFT_BRACKET , expr , RIGHT_BRACKET ;
-    fn parse_index_expr(&mut self, owner: Spanned<Expr>) -> ParserResult<Expr> {
+    fn parse_index_expr(&mut self, marker: usize, owner: Spanned<Expr>) -> ParserResult<Expr> {
         debug_assert_matches!(self.current()?.value(), Token::LeftBracket);
         self.advance();
         let index = self.parse_expr()?;
         let right_sq = self.eat(Token::RightBracket, "right square bracket")?;
         let span = owner.span().to(right_sq.span());
+        self.node(marker, SyntaxKind::IndexExpr, span);
         Ok(Spanned::new(
             Expr::Index(Box::new(IndexExpr { owner, index })),
             span,
@@ -302,12 +352,8 @@ impl<'src, 'err> Parser<'src, 'err> {
 
     // function_call ::= LEFT_PAREN , arg_list , RIGHT_PAREN ;
     // arg_list      ::= expr , ( COMMA , expr )* ;
-    fn parse_function_call(&mut self, name: Spanned<Expr>) -> ParserResult<Expr> {
-        debug_assert_matches!(
-            name.value(),
-            Expr::IdentifierPath(_) | Expr::MemberAccess(_)
-        );
-
+    fn parse_function_call(&mut self, marker: usize, name: Spanned<Expr>) -> ParserResult<Expr> {
+        let arguments_marker = self.marker();
         let left_paren = self.eat(Token::LeftParen, "left parenthesis")?;
         let mut arguments = Vec::<Spanned<Expr>>::new();
 
@@ -323,15 +369,15 @@ impl<'src, 'err> Parser<'src, 'err> {
         }
 
         let right_paren = self.eat(Token::RightParen, "right parenthesis")?;
-        let span = left_paren.span().to(right_paren.span());
+        let arguments_span = left_paren.span().to(right_paren.span());
+        self.node(arguments_marker, SyntaxKind::ArgumentList, arguments_span);
+        let span = name.span().to(right_paren.span());
+        self.node(marker, SyntaxKind::FunctionCallExpr, span);
 
         Ok(Spanned::new(
             Expr::FunctionCall(Box::new(FunctionCallExpr {
                 identity: name,
-                arguments: Spanned::new(
-                    arguments.into_boxed_slice(),
-                    left_paren.span().to(right_paren.span()),
-                ),
+                arguments: Spanned::new(arguments.into_boxed_slice(), arguments_span),
             })),
             span,
         ))
@@ -343,25 +389,27 @@ impl<'src, 'err> Parser<'src, 'err> {
     //        | INT_LIT
     //        | FLOAT_LIT
     //        | STRING_LIT
-    //        | LEFT_PAREN , expr , RIGHT_PAREN
+    //        | closure_expr
+    //        | tuple_expr
     //        ;
     fn parse_atom(&mut self) -> ParserResult<Expr> {
         let first = self.current()?;
 
-        if first.value() == Token::LeftParen {
-            self.advance();
-
-            let expr = self.parse_expr()?;
-            self.eat(Token::RightParen, "right parenthesis")?;
+        if matches!(first.value(), Token::BinaryOr | Token::BoolOr) {
+            return self.parse_closure_expr();
+        }
 
-            return Ok(expr);
+        if first.value() == Token::LeftParen {
+            return self.parse_tuple_expr();
         }
 
         if matches!(first.value(), Token::Identifier(_)) {
-            let identifier_path = Box::from(self.parse_identifier_path()?.value());
+            let marker = self.marker();
+            let identifier_path = self.parse_identifier_path()?;
+            self.node(marker, SyntaxKind::PathExpr, identifier_path.span());
             return Ok(Spanned::new(
-                Expr::IdentifierPath(identifier_path),
-                first.span(),
+                Expr::IdentifierPath(Box::from(identifier_path.value())),
+                identifier_path.span(),
             ));
         }
 
@@ -383,6 +431,16 @@ impl<'src, 'err> Parser<'src, 'err> {
             Token::StringLit(value) => {
                 Spanned::new(Expr::String(Box::from(StrLitExpr { value })), first.span())
             }
+            Token::CharLit(value) => {
+                Spanned::new(Expr::Char(Box::from(CharLitExpr { value })), first.span())
+            }
+            Token::ByteLit(value) => {
+                Spanned::new(Expr::Byte(Box::from(ByteLitExpr { value })), first.span())
+            }
+            Token::ByteStringLit(value) => Spanned::new(
+                Expr::ByteString(Box::from(ByteStrLitExpr { value })),
+                first.span(),
+            ),
             _ => {
                 let err = Spanned::new(
                     ParserError::SyntaxError(
@@ -397,9 +455,113 @@ impl<'src, 'err> Parser<'src, 'err> {
         };
 
         self.advance();
+        let marker = self.marker();
+        self.node(marker, SyntaxKind::LiteralExpr, atom.span());
         Ok(atom)
     }
 
+    // tuple_expr ::= LEFT_PAREN , ( expr , ( COMMA , expr )* , COMMA? )? , RIGHT_PAREN ;
+    fn parse_tuple_expr(&mut self) -> ParserResult<Expr> {
+        let marker = self.marker();
+        let tuple = self.parse_tuple_elements(Self::parse_expr)?;
+        let span = tuple.span();
+        let (mut elements, trailing_comma) = tuple.value();
+
+        // A single expression within parentheses without a trailing comma is just a grouping.
+        // The parentheses are not kept in the AST, but they are included in the span so that
+        // the span covers all the source text of the expression.
+        if elements.len() == 1 && !trailing_comma {
+            let element = elements.pop().unwrap();
+            self.widen_node(marker, span);
+            return Ok(Spanned::new(element.value(), span));
+        }
+
+        self.node(marker, SyntaxKind::TupleExpr, span);
+        Ok(Spanned::new(
+            Expr::Tuple(Box::new(TupleExpr {
+                elements: elements.into_boxed_slice(),
+            })),
+            span,
+        ))
+    }
+
+    // closure_expr ::= BOOL_OR , closure_body
+    //                | BINARY_OR , ( closure_param , ( COMMA , closure_param )* )? , BINARY_OR , closure_body
+    //                ;
+    // closure_body ::= function_return_type , block_statement
+    //                | block_statement
+    //                | expr_statement
+    //                ;
+    fn parse_closure_expr(&mut self) -> ParserResult<Expr> {
+        let marker = self.marker();
+        let start = self.current()?;
+        self.advance();
+
+        // "||" is lexed as a single token, and is a closure without any parameters.
+        let parameters = if start.value() == Token::BoolOr {
+            Spanned::new(Box::from([]), start.span())
+        } else {
+            let mut parameters = Vec::<Spanned<ClosureParameter>>::new();
+
+            while self.current()?.value() != Token::BinaryOr {
+                parameters.push(self.parse_closure_param()?);
+
+                if self.current()?.value() == Token::Comma {
+                    self.advance();
+                } else {
+                    break;
+                }
+            }
+
+            let end = self.eat(Token::BinaryOr, "vertical bar")?;
+            Spanned::new(parameters.into_boxed_slice(), start.span().to(end.span()))
+        };
+        self.node(marker, SyntaxKind::ClosureParameterList, parameters.span());
+
+        // Closures that declare their return type must use a block for their body.
+        let (return_type, body) = match self.current()?.value() {
+            Token::Arrow => {
+                let return_type = self.parse_function_return_type()?;
+                (Some(return_type), self.parse_block_statement()?)
+            }
+            Token::LeftBrace => (None, self.parse_block_statement()?),
+            _ => (None, self.parse_expr_statement()?),
+        };
+
+        let span = start.span().to(body.span());
+        self.node(marker, SyntaxKind::ClosureExpr, span);
+
+        Ok(Spanned::new(
+            Expr::Closure(Box::new(ClosureExpr {
+                parameters,
+                return_type,
+                body,
+            })),
+            span,
+        ))
+    }
+
+    // closure_param ::= identifier , ( COLON , type_name )? ;
+    fn parse_closure_param(&mut self) -> ParserResult<ClosureParameter> {
+        let marker = self.marker();
+        let name = self.parse_identifier()?;
+
+        let type_name = if self.current()?.value() == Token::Colon {
+            self.advance();
+            Some(self.parse_type_name()?)
+        } else {
+            None
+        };
+
+        let span = type_name
+            .as_ref()
+            .map(|type_name| name.span().to(type_name.span()))
+            .unwrap_or(name.span());
+        self.node(marker, SyntaxKind::ClosureParameter, span);
+
+        Ok(Spanned::new(ClosureParameter { name, type_name }, span))
+    }
+
     /*
      * Helpers
      */
@@ -413,22 +575,20 @@ impl<'src, 'err> Parser<'src, 'err> {
         OpFn: Fn(Token) -> Option<BinaryOp>,
         ParserFn: Fn(&mut Self) -> ParserResult<Expr>,
     {
+        let marker = self.marker();
         let mut left = parser_fn(self)?;
 
-        loop {
-            if let Some(op) = op_fn(self.current()?.value()) {
-                self.advance();
-                let right = parser_fn(self)?;
+        while let Some(op) = op_fn(self.current()?.value()) {
+            self.advance();
+            let right = parser_fn(self)?;
 
-                let span = left.span().to(right.span());
+            let span = left.span().to(right.span());
+            self.node(marker, SyntaxKind::BinaryExpr, span);
 
-                left = Spanned::new(
-                    Expr::Binary(Box::from(BinaryExpr { left, op, right })),
-                    span,
-                );
-            } else {
-                break;
-            };
+            left = Spanned::new(
+                Expr::Binary(Box::from(BinaryExpr { left, op, right })),
+                span,
+            );
         }
 
         Ok(left)
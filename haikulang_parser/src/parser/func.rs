@@ -1,14 +1,25 @@
+use crate::ast::attr::Attribute;
+use crate::ast::doc::DocComment;
 use crate::ast::func::*;
-use crate::ast::ident::IdentifierPath;
+use crate::ast::types::TypeName;
+use crate::ast::visibility::Visibility;
+use crate::cst::kind::SyntaxKind;
 use crate::error::ParserResult;
 use crate::lexer::token::Token;
+use crate::parser::attr::declaration_start;
 use crate::parser::core::Parser;
 use crate::span::Spanned;
 
 impl<'src, 'err> Parser<'src, 'err> {
-    // extern_function_decl ::= FN , identifier , params , function_return_type? ;
-    pub(super) fn parse_extern_function_decl(&mut self) -> ParserResult<ExternFunctionDecl> {
-        let start = self.eat(Token::Extern, "'extern' keyword")?;
+    // extern_function_decl ::= EXTERN , FN , identifier , params , function_return_type? ;
+    pub(super) fn parse_extern_function_decl(
+        &mut self,
+        doc: Option<Spanned<DocComment>>,
+        attributes: Box<[Spanned<Attribute>]>,
+        visibility: Spanned<Visibility>,
+    ) -> ParserResult<ExternFunctionDecl> {
+        self.eat(Token::Extern, "'extern' keyword")?;
+        let start = declaration_start(&attributes, &visibility);
         self.eat(Token::Fn, "'fn' keyword")?;
         let name = self.parse_identifier()?;
         let parameters = self.parse_parameter_decls()?;
@@ -21,10 +32,13 @@ impl<'src, 'err> Parser<'src, 'err> {
             (None, parameters.span())
         };
 
-        let span = start.span().to(end_span);
+        let span = start.to(end_span);
 
         Ok(Spanned::new(
             ExternFunctionDecl {
+                doc,
+                attributes,
+                visibility: visibility.value(),
                 name,
                 parameters,
                 return_type,
@@ -36,24 +50,37 @@ impl<'src, 'err> Parser<'src, 'err> {
     // function_decl ::= FN , identifier , params , function_return_type? , block_statement     /* procedural function */
     //                 | FN , identifier , params , ASSIGN , expr_statement , semicolon         /* expression function */
     //                 ;
-    pub(super) fn parse_function_decl(&mut self) -> ParserResult<FunctionDecl> {
-        let start = self.eat(Token::Fn, "'fn' keyword")?;
+    pub(super) fn parse_function_decl(
+        &mut self,
+        doc: Option<Spanned<DocComment>>,
+        attributes: Box<[Spanned<Attribute>]>,
+        visibility: Spanned<Visibility>,
+    ) -> ParserResult<FunctionDecl> {
+        self.eat(Token::Fn, "'fn' keyword")?;
+        let start = declaration_start(&attributes, &visibility);
         let name = self.parse_identifier()?;
         let parameters = self.parse_parameter_decls()?;
 
         // We have an expression function if we have an assignment symbol.
         if self.current()?.value() == Token::Assign {
+            self.advance();
+            let marker = self.marker();
             let body = self.parse_expr_statement()?;
             let end = self.eat(Token::Semicolon, "semicolon")?;
+            let span = body.span().to(end.span());
+            self.node(marker, SyntaxKind::ExprStatement, span);
 
             return Ok(Spanned::new(
                 FunctionDecl {
+                    doc,
+                    attributes,
+                    visibility: visibility.value(),
                     name,
                     parameters,
                     return_type: None,
                     body,
                 },
-                start.span().to(end.span()),
+                start.to(end.span()),
             ));
         }
         let return_type = if self.current()?.value() == Token::Arrow {
@@ -63,10 +90,13 @@ impl<'src, 'err> Parser<'src, 'err> {
         };
 
         let body = self.parse_block_statement()?;
-        let span = start.span().to(body.span());
+        let span = start.to(body.span());
 
         Ok(Spanned::new(
             FunctionDecl {
+                doc,
+                attributes,
+                visibility: visibility.value(),
                 name,
                 parameters,
                 return_type,
@@ -76,14 +106,15 @@ impl<'src, 'err> Parser<'src, 'err> {
         ))
     }
 
-    // function_return_type ::= ARROW , identifier_path ;
-    fn parse_function_return_type(&mut self) -> ParserResult<IdentifierPath> {
+    // function_return_type ::= ARROW , type_name ;
+    pub(super) fn parse_function_return_type(&mut self) -> ParserResult<TypeName> {
         self.eat(Token::Arrow, "arrow")?;
-        self.parse_identifier_path()
+        self.parse_type_name()
     }
 
     // parameter_decl_list ::= LEFT_PAREN , ( parameter_decl , ( COMMA , parameter_decl )* )?, RIGHT_PAREN ;
     fn parse_parameter_decls(&mut self) -> ParserResult<Box<[Spanned<ParameterDecl>]>> {
+        let marker = self.marker();
         let start = self.eat(Token::LeftParen, "left parenthesis")?;
 
         let params = if self.current()?.value() != Token::RightParen {
@@ -101,20 +132,26 @@ impl<'src, 'err> Parser<'src, 'err> {
         };
 
         let end = self.eat(Token::RightParen, "right parenthesis")?;
+        let span = start.span().to(end.span());
+        self.node(marker, SyntaxKind::ParameterList, span);
 
-        Ok(Spanned::new(params, start.span().to(end.span())))
+        Ok(Spanned::new(params, span))
     }
 
-    // parameter_decl ::= identifier , COLON , identifier_path ;
+    // parameter_decl ::= identifier , COLON , type_name ;
     fn parse_parameter_decl(&mut self) -> ParserResult<ParameterDecl> {
+        let doc = self.take_doc_comment();
+        let marker = self.marker();
         let name = self.parse_identifier()?;
         self.eat(Token::Colon, "colon")?;
-        let identifier_path = self.parse_identifier_path()?;
-        let span = name.span().to(identifier_path.span());
+        let type_name = self.parse_type_name()?;
+        let span = name.span().to(type_name.span());
+        self.node(marker, SyntaxKind::ParameterDecl, span);
         Ok(Spanned::new(
             ParameterDecl {
+                doc,
                 name,
-                type_name: identifier_path,
+                type_name,
             },
             span,
         ))
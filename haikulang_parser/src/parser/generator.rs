@@ -0,0 +1,540 @@
+//! Grammar-aware generation of random programs, used by the property tests.
+//!
+//! Programs are generated as ASTs and then printed, so everything produced is valid source
+//! code. Spans within generated trees are all unset.
+use crate::ast::attr::Attribute;
+use crate::ast::doc::DocComment;
+use crate::ast::expr::*;
+use crate::ast::func::{ExternFunctionDecl, FunctionDecl, ParameterDecl};
+use crate::ast::ident::{Identifier, IdentifierPath};
+use crate::ast::stmt::*;
+use crate::ast::structs::{StructDecl, StructMemberDecl};
+use crate::ast::types::{FunctionTypeName, TupleTypeName, TypeName};
+use crate::ast::unit::{CompilationUnit, CompilationUnitMember, UseDecl};
+use crate::ast::visibility::Visibility;
+use crate::lexer::literals::{FloatLit, IntLit};
+use crate::span::{Span, Spanned};
+use num_bigint::BigUint;
+use std::path::PathBuf;
+
+// None of these are keywords, so they are always lexed as identifiers.
+const NAMES: &[&str] = &[
+    "a", "b", "x", "foo", "bar_baz", "_tmp", "x1", "Point", "std", "i32", "string",
+];
+
+// Characters used within strings and documentation, including multibyte characters.
+const TEXT: &[char] = &[
+    'a', 'z', 'Q', '0', ' ', '_', '*', '/', '"', '\\', '\'', '{', '}', 'é', 'ß', '日', '✓', '🦀',
+];
+
+// Characters that can only appear in strings in an escaped form.
+const ESCAPED: &[char] = &['\n', '\r', '\t', '\u{1}', '\u{7f}'];
+
+const BINARY_OPS: &[BinaryOp] = &[
+    BinaryOp::Add,
+    BinaryOp::Sub,
+    BinaryOp::Mul,
+    BinaryOp::Div,
+    BinaryOp::Mod,
+    BinaryOp::Pow,
+    BinaryOp::BinaryAnd,
+    BinaryOp::BinaryOr,
+    BinaryOp::BinaryXor,
+    BinaryOp::BinaryShl,
+    BinaryOp::BinaryShr,
+    BinaryOp::BoolAnd,
+    BinaryOp::BoolOr,
+    BinaryOp::Eq,
+    BinaryOp::NotEq,
+    BinaryOp::Less,
+    BinaryOp::LessEq,
+    BinaryOp::Greater,
+    BinaryOp::GreaterEq,
+];
+
+// Operators that can be combined with an assignment, such as "+=".
+const ASSIGNMENT_OPS: &[BinaryOp] = &[
+    BinaryOp::Add,
+    BinaryOp::Sub,
+    BinaryOp::Mul,
+    BinaryOp::Div,
+    BinaryOp::Mod,
+    BinaryOp::Pow,
+    BinaryOp::BinaryAnd,
+    BinaryOp::BinaryOr,
+    BinaryOp::BinaryXor,
+    BinaryOp::BinaryShl,
+    BinaryOp::BinaryShr,
+];
+
+const UNARY_OPS: &[UnaryOp] = &[UnaryOp::Plus, UnaryOp::Minus, UnaryOp::Not, UnaryOp::Invert];
+
+// How deeply expressions and statements can nest within a generated program.
+const MAX_DEPTH: usize = 4;
+
+/// A small deterministic pseudo-random number generator (xorshift64*), so that failures
+/// can be reproduced from their seed.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // The state must never be zero.
+        Self(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// A number in the range `0..bound`.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+
+    /// True with a probability of one in `n`.
+    pub fn one_in(&mut self, n: usize) -> bool {
+        self.below(n) == 0
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+pub struct Generator {
+    pub rng: Rng,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+        }
+    }
+
+    /*
+     * Declarations
+     */
+
+    pub fn compilation_unit(&mut self) -> CompilationUnit {
+        let members = (0..self.rng.below(6))
+            .map(|_| self.member())
+            .collect::<Vec<_>>();
+
+        CompilationUnit {
+            path: PathBuf::from("generated.hkl"),
+            name: "generated.hkl".to_string(),
+            doc: self.doc(),
+            members: members.into_boxed_slice(),
+        }
+    }
+
+    fn member(&mut self) -> Spanned<CompilationUnitMember> {
+        let member = match self.rng.below(4) {
+            0 => CompilationUnitMember::Use(Box::new(UseDecl { path: self.path() })),
+            1 => CompilationUnitMember::ExternFunction(Box::new(ExternFunctionDecl {
+                doc: self.doc(),
+                attributes: self.attributes(),
+                visibility: self.visibility(),
+                name: self.identifier(),
+                parameters: self.parameters(),
+                return_type: self.optional(|generator| generator.type_name(0)),
+            })),
+            2 => CompilationUnitMember::Function(Box::new(self.function())),
+            _ => CompilationUnitMember::Struct(Box::new(StructDecl {
+                doc: self.doc(),
+                attributes: self.attributes(),
+                visibility: self.visibility(),
+                identifier: self.identifier(),
+                members: (0..self.rng.below(4))
+                    .map(|_| {
+                        spanned(StructMemberDecl {
+                            doc: self.doc(),
+                            attributes: self.attributes(),
+                            visibility: self.visibility(),
+                            identifier: self.identifier(),
+                            type_name: self.type_name(0),
+                        })
+                    })
+                    .collect(),
+            })),
+        };
+        spanned(member)
+    }
+
+    fn function(&mut self) -> FunctionDecl {
+        let doc = self.doc();
+        let attributes = self.attributes();
+        let visibility = self.visibility();
+        let name = self.identifier();
+        let parameters = self.parameters();
+
+        // Expression functions cannot declare a return type.
+        let (return_type, body) = if self.rng.one_in(4) {
+            (
+                None,
+                spanned(Statement::Expr(Box::new(self.expr(0).value()))),
+            )
+        } else {
+            (
+                self.optional(|generator| generator.type_name(0)),
+                self.block(0),
+            )
+        };
+
+        FunctionDecl {
+            doc,
+            attributes,
+            visibility,
+            name,
+            parameters,
+            return_type,
+            body,
+        }
+    }
+
+    fn parameters(&mut self) -> Spanned<Box<[Spanned<ParameterDecl>]>> {
+        let parameters = (0..self.rng.below(4))
+            .map(|_| {
+                spanned(ParameterDecl {
+                    doc: self.doc(),
+                    name: self.identifier(),
+                    type_name: self.type_name(0),
+                })
+            })
+            .collect();
+        spanned(parameters)
+    }
+
+    fn attributes(&mut self) -> Box<[Spanned<Attribute>]> {
+        (0..self.rng.below(3))
+            .map(|_| {
+                spanned(Attribute {
+                    name: self.identifier(),
+                    arguments: self.optional(|generator| {
+                        spanned(
+                            (0..generator.rng.below(3))
+                                .map(|_| generator.expr(MAX_DEPTH))
+                                .collect(),
+                        )
+                    }),
+                })
+            })
+            .collect()
+    }
+
+    fn visibility(&mut self) -> Visibility {
+        if self.rng.one_in(2) {
+            Visibility::Public
+        } else {
+            Visibility::Private
+        }
+    }
+
+    fn doc(&mut self) -> Option<Spanned<DocComment>> {
+        if !self.rng.one_in(3) {
+            return None;
+        }
+
+        let lines = (0..1 + self.rng.below(3))
+            .map(|_| self.text(&[]))
+            .collect::<Vec<_>>();
+
+        Some(spanned(DocComment {
+            text: lines.join("\n"),
+        }))
+    }
+
+    /*
+     * Statements
+     */
+
+    fn statement(&mut self, depth: usize) -> Spanned<Statement> {
+        // Nested statements always use blocks for their bodies, otherwise an "else" could
+        // bind to a different "if" once printed.
+        let choice = if depth >= MAX_DEPTH {
+            self.rng.below(6)
+        } else {
+            self.rng.below(9)
+        };
+
+        let statement = match choice {
+            0 => Statement::Empty,
+            1 => Statement::Break,
+            2 => Statement::Continue,
+            3 => Statement::Return(Box::new(ReturnStatement {
+                expr: self.optional(|generator| generator.expr(depth + 1)),
+            })),
+            4 => Statement::Expr(Box::new(self.expr(depth + 1).value())),
+            5 => {
+                let pattern = self.pattern(0);
+                // Tuple patterns must always be given a value.
+                let choice = match pattern.value_ref() {
+                    Pattern::Tuple(_) => 1 + self.rng.below(2),
+                    Pattern::Identifier(_) => self.rng.below(3),
+                };
+                let (type_name, expr) = match choice {
+                    0 => (Some(self.type_name(0)), None),
+                    1 => (None, Some(self.expr(depth + 1))),
+                    _ => (Some(self.type_name(0)), Some(self.expr(depth + 1))),
+                };
+                Statement::VarDecl(Box::new(VarDeclStatement {
+                    pattern,
+                    type_name,
+                    expr,
+                }))
+            }
+            6 => Statement::If(Box::new(IfStatement {
+                condition: self.expr(depth + 1),
+                body: self.block(depth + 1),
+                otherwise: match self.rng.below(3) {
+                    0 => None,
+                    1 => Some(self.block(depth + 1)),
+                    // An "else if" chain.
+                    _ => Some(spanned(Statement::If(Box::new(IfStatement {
+                        condition: self.expr(depth + 1),
+                        body: self.block(depth + 1),
+                        otherwise: None,
+                    })))),
+                },
+            })),
+            7 => Statement::While(Box::new(WhileStatement {
+                condition: self.expr(depth + 1),
+                body: self.block(depth + 1),
+            })),
+            _ => return self.block(depth + 1),
+        };
+
+        spanned(statement)
+    }
+
+    fn block(&mut self, depth: usize) -> Spanned<Statement> {
+        let statements = (0..self.rng.below(4))
+            .map(|_| self.statement(depth + 1))
+            .collect();
+        spanned(Statement::Block(Box::new(BlockStatement { statements })))
+    }
+
+    /*
+     * Expressions
+     */
+
+    fn expr(&mut self, depth: usize) -> Spanned<Expr> {
+        let choice = if depth >= MAX_DEPTH {
+            self.rng.below(5)
+        } else {
+            self.rng.below(13)
+        };
+
+        let expr = match choice {
+            0 => Expr::Bool(Box::new(BoolLitExpr {
+                value: self.rng.one_in(2),
+            })),
+            1 => Expr::Int(Box::new(IntLitExpr { value: self.int() })),
+            2 => Expr::Float(Box::new(FloatLitExpr {
+                value: self.float(),
+            })),
+            3 => self.text_literal(),
+            4 => Expr::IdentifierPath(Box::new(self.path().value())),
+            5 => Expr::Binary(Box::new(BinaryExpr {
+                left: self.expr(depth + 1),
+                op: self.rng.pick(BINARY_OPS).clone(),
+                right: self.expr(depth + 1),
+            })),
+            6 => Expr::Unary(Box::new(UnaryExpr {
+                op: self.rng.pick(UNARY_OPS).clone(),
+                value: self.expr(depth + 1),
+            })),
+            7 => Expr::Assignment(Box::new(AssignmentExpr {
+                lvalue: self.expr(depth + 1),
+                op: self.optional(|generator| generator.rng.pick(ASSIGNMENT_OPS).clone()),
+                rvalue: self.expr(depth + 1),
+            })),
+            8 => Expr::MemberAccess(Box::new(MemberAccessExpr {
+                owner: self.expr(depth + 1),
+                member: if self.rng.one_in(4) {
+                    spanned(Identifier {
+                        value: self.rng.below(12).to_string(),
+                    })
+                } else {
+                    self.identifier()
+                },
+            })),
+            9 => Expr::Index(Box::new(IndexExpr {
+                owner: self.expr(depth + 1),
+                index: self.expr(depth + 1),
+            })),
+            10 => Expr::Closure(Box::new(self.closure(depth))),
+            11 => Expr::Tuple(Box::new(TupleExpr {
+                elements: (0..self.rng.below(4))
+                    .map(|_| self.expr(depth + 1))
+                    .collect(),
+            })),
+            _ => Expr::FunctionCall(Box::new(FunctionCallExpr {
+                identity: self.expr(depth + 1),
+                arguments: spanned(
+                    (0..self.rng.below(4))
+                        .map(|_| self.expr(depth + 1))
+                        .collect(),
+                ),
+            })),
+        };
+
+        spanned(expr)
+    }
+
+    fn closure(&mut self, depth: usize) -> ClosureExpr {
+        let parameters = (0..self.rng.below(4))
+            .map(|_| {
+                spanned(ClosureParameter {
+                    name: self.identifier(),
+                    type_name: self.optional(|generator| generator.type_name(0)),
+                })
+            })
+            .collect();
+
+        // Closures that declare their return type must use a block for their body.
+        let (return_type, body) = match self.rng.below(3) {
+            0 => (Some(self.type_name(0)), self.block(depth + 1)),
+            1 => (None, self.block(depth + 1)),
+            _ => (
+                None,
+                spanned(Statement::Expr(Box::new(self.expr(depth + 1).value()))),
+            ),
+        };
+
+        ClosureExpr {
+            parameters: spanned(parameters),
+            return_type,
+            body,
+        }
+    }
+
+    // Negative numbers are unary expressions, so literals are never negative.
+    fn int(&mut self) -> IntLit {
+        let value = self.rng.next();
+        match self.rng.below(14) {
+            0 => IntLit::I8((value % (i8::MAX as u64 + 1)) as i8),
+            1 => IntLit::I16((value % (i16::MAX as u64 + 1)) as i16),
+            2 => IntLit::I32((value % (i32::MAX as u64 + 1)) as i32),
+            3 => IntLit::I64((value % (i64::MAX as u64 + 1)) as i64),
+            4 => IntLit::I128((((value >> 1) as i128) << 64) | self.rng.next() as i128),
+            5 => IntLit::ISize((value % (isize::MAX as u64 + 1)) as isize),
+            6 => IntLit::U8(value as u8),
+            7 => IntLit::U16(value as u16),
+            8 => IntLit::U32(value as u32),
+            9 => IntLit::U64(value),
+            10 => IntLit::U128(((value as u128) << 64) | self.rng.next() as u128),
+            11 => IntLit::USize(value as usize),
+            // Untyped literals can be arbitrarily large.
+            12 => IntLit::Untyped(BigUint::from(value).pow(self.rng.below(4) as u32 + 1)),
+            _ => IntLit::Untyped(BigUint::from(value % (i32::MAX as u64 + 1))),
+        }
+    }
+
+    fn text_literal(&mut self) -> Expr {
+        let chars = if self.rng.one_in(2) { TEXT } else { ESCAPED };
+        match self.rng.below(4) {
+            0 => Expr::Char(Box::new(CharLitExpr {
+                value: *self.rng.pick(chars),
+            })),
+            1 => Expr::Byte(Box::new(ByteLitExpr {
+                value: self.rng.next() as u8,
+            })),
+            2 => Expr::ByteString(Box::new(ByteStrLitExpr {
+                value: (0..self.rng.below(8))
+                    .map(|_| self.rng.next() as u8)
+                    .collect(),
+            })),
+            _ => Expr::String(Box::new(StrLitExpr {
+                value: self.text(ESCAPED).into_boxed_str(),
+            })),
+        }
+    }
+
+    fn float(&mut self) -> FloatLit {
+        let value = *self.rng.pick(&[0.0, 0.5, 1.0, 3.25, 1e-7, 6.02e23, 1e30]);
+        match self.rng.below(3) {
+            0 => FloatLit::F32(value as f32),
+            1 => FloatLit::F64(value),
+            _ => FloatLit::Untyped(value),
+        }
+    }
+
+    /*
+     * Helpers
+     */
+
+    fn identifier(&mut self) -> Spanned<Identifier> {
+        spanned(Identifier {
+            value: self.rng.pick(NAMES).to_string(),
+        })
+    }
+
+    fn path(&mut self) -> Spanned<IdentifierPath> {
+        let qualifier = (0..self.rng.below(3)).map(|_| self.identifier()).collect();
+        spanned(IdentifierPath {
+            qualifier,
+            local_name: self.identifier(),
+        })
+    }
+
+    fn pattern(&mut self, depth: usize) -> Spanned<Pattern> {
+        if depth >= MAX_DEPTH || !self.rng.one_in(4) {
+            return spanned(Pattern::Identifier(Box::new(self.identifier().value())));
+        }
+
+        let elements = (0..self.rng.below(4))
+            .map(|_| self.pattern(depth + 1))
+            .collect();
+        spanned(Pattern::Tuple(elements))
+    }
+
+    fn type_name(&mut self, depth: usize) -> Spanned<TypeName> {
+        if depth >= MAX_DEPTH || !self.rng.one_in(4) {
+            return spanned(TypeName::Path(Box::new(self.path().value())));
+        }
+
+        if self.rng.one_in(2) {
+            let elements = (0..self.rng.below(4))
+                .map(|_| self.type_name(depth + 1))
+                .collect();
+            return spanned(TypeName::Tuple(Box::new(TupleTypeName { elements })));
+        }
+
+        let parameters = (0..self.rng.below(3))
+            .map(|_| self.type_name(depth + 1))
+            .collect();
+        spanned(TypeName::Function(Box::new(FunctionTypeName {
+            parameters: spanned(parameters),
+            return_type: self.optional(|generator| generator.type_name(depth + 1)),
+        })))
+    }
+
+    fn text(&mut self, extra: &[char]) -> String {
+        (0..self.rng.below(8))
+            .map(|_| {
+                if !extra.is_empty() && self.rng.one_in(6) {
+                    *self.rng.pick(extra)
+                } else {
+                    *self.rng.pick(TEXT)
+                }
+            })
+            .collect()
+    }
+
+    fn optional<T>(&mut self, func: impl FnOnce(&mut Self) -> T) -> Option<T> {
+        if self.rng.one_in(2) {
+            Some(func(self))
+        } else {
+            None
+        }
+    }
+}
+
+fn spanned<T: Clone>(value: T) -> Spanned<T> {
+    Spanned::new(value, Span::UNSET)
+}
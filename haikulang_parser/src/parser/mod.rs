@@ -1,7 +1,13 @@
+mod attr;
 pub mod core;
 mod expr;
 mod func;
+#[cfg(test)]
+mod generator;
 mod ident;
+#[cfg(test)]
+mod properties;
 mod stmt;
 mod structs;
+mod types;
 mod unit;
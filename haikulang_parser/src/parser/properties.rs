@@ -0,0 +1,561 @@
+//! Property tests for the lexer and parser, run over randomly generated programs.
+//!
+//! These complement the fuzz targets in the `fuzz` directory, and run as part of the normal
+//! test suite. Each seed is deterministic, so failures can be reproduced.
+use crate::ast::attr::Attribute;
+use crate::ast::expr::Expr;
+use crate::ast::func::ParameterDecl;
+use crate::ast::ident::IdentifierPath;
+use crate::ast::printer::print_compilation_unit;
+use crate::ast::stmt::{Pattern, Statement};
+use crate::ast::types::TypeName;
+use crate::ast::unit::{CompilationUnit, CompilationUnitMember};
+use crate::cst::parse_lossless;
+use crate::error::ParserError;
+use crate::lexer::token::Token;
+use crate::lexer::token_stream::TokenStream;
+use crate::parser::core::Parser;
+use crate::parser::generator::{Generator, Rng};
+use crate::span::{Span, Spanned};
+use std::path::Path;
+
+const PROGRAMS: u64 = 300;
+
+// Inserted into mutated programs to try to provoke errors in unusual places.
+const FRAGMENTS: &[&str] = &[
+    "\"", "\\", "/*", "*/", "//", "(", ")", "{", "}", ";", "0x", "1e", "é", "日", "🦀", "\0", "\r",
+    "\n", "\\u", "\\u{", "$",
+];
+
+fn parse(
+    source: &str,
+) -> (
+    Result<CompilationUnit, Spanned<ParserError>>,
+    Vec<Spanned<ParserError>>,
+) {
+    let mut errors: Vec<Spanned<ParserError>> = Vec::new();
+    let mut parser = Parser::new(TokenStream::new(source), Path::new("test.hkl"), &mut errors);
+    let result = parser.parse().map(|unit| unit.value());
+    (result, errors)
+}
+
+fn generate(seed: u64) -> String {
+    print_compilation_unit(&Generator::new(seed).compilation_unit())
+}
+
+// Randomly damage a program by deleting, duplicating and inserting text.
+fn mutate(source: &str, rng: &mut Rng) -> String {
+    let mut chars: Vec<String> = source.chars().map(String::from).collect();
+
+    for _ in 0..1 + rng.below(4) {
+        let position = rng.below(chars.len() + 1);
+        match rng.below(3) {
+            0 if position < chars.len() => {
+                chars.remove(position);
+            }
+            1 if position < chars.len() => {
+                let duplicate = chars[position].clone();
+                chars.insert(position, duplicate);
+            }
+            _ => chars.insert(position, rng.pick(FRAGMENTS).to_string()),
+        }
+    }
+
+    chars.concat()
+}
+
+fn assert_valid_span(source: &str, span: Span) {
+    assert!(
+        span.start() <= span.end() && span.end() <= source.len(),
+        "span {} is out of bounds for source of length {}",
+        span,
+        source.len()
+    );
+    assert!(
+        source.is_char_boundary(span.start()) && source.is_char_boundary(span.end()),
+        "span {} does not fall on character boundaries",
+        span
+    );
+}
+
+fn assert_tokens_are_well_formed(source: &str) {
+    let mut stream = TokenStream::new(source);
+    let mut previous_end = 0;
+
+    loop {
+        let span = match stream.current() {
+            Ok(token) if token.value() == Token::Eof => break,
+            Ok(token) => token.span(),
+            Err(err) => err.span(),
+        };
+
+        assert_valid_span(source, span);
+        assert!(
+            span.start() >= previous_end,
+            "token at {} overlaps the previous token ending at {}",
+            span,
+            previous_end
+        );
+        assert!(span.end() > span.start(), "token at {} is empty", span);
+
+        previous_end = span.end();
+        stream.advance();
+    }
+}
+
+/*
+ * Span checks for the AST. Every child must fall within its parent, and siblings must appear
+ * in source order without overlapping.
+ */
+
+struct SpanChecker<'a> {
+    source: &'a str,
+}
+
+impl SpanChecker<'_> {
+    fn children(&self, parent: Span, children: &[Span]) {
+        assert_valid_span(self.source, parent);
+        let mut previous_end = parent.start();
+
+        for child in children {
+            assert_valid_span(self.source, *child);
+            assert!(
+                child.start() >= previous_end && child.end() <= parent.end(),
+                "span {} is not within {} after {}: {:?}",
+                child,
+                parent,
+                previous_end,
+                &self.source[parent.range()]
+            );
+            previous_end = child.end();
+        }
+    }
+
+    fn unit(&self, unit: &CompilationUnit) {
+        let spans: Vec<Span> = unit.members.iter().map(Spanned::span).collect();
+        self.children(Span::new(0, self.source.len()), &spans);
+
+        let end = Span::new(self.source.len(), self.source.len());
+        self.doc(
+            spans.first().copied().unwrap_or(end),
+            unit.doc.as_ref().map(Spanned::span),
+        );
+
+        for member in unit.members.iter() {
+            let span = member.span();
+            match member.value_ref() {
+                CompilationUnitMember::Use(use_decl) => {
+                    self.children(span, &[use_decl.path.span()]);
+                    self.path(&use_decl.path);
+                }
+                CompilationUnitMember::ExternFunction(function) => {
+                    self.doc(span, function.doc.as_ref().map(Spanned::span));
+                    let mut spans = self.attributes(&function.attributes);
+                    spans.extend([function.name.span(), function.parameters.span()]);
+                    spans.extend(function.return_type.as_ref().map(Spanned::span));
+                    self.children(span, &spans);
+                    self.parameters(&function.parameters);
+                    function
+                        .return_type
+                        .iter()
+                        .for_each(|type_name| self.type_name(type_name));
+                }
+                CompilationUnitMember::Function(function) => {
+                    self.doc(span, function.doc.as_ref().map(Spanned::span));
+                    let mut spans = self.attributes(&function.attributes);
+                    spans.extend([function.name.span(), function.parameters.span()]);
+                    spans.extend(function.return_type.as_ref().map(Spanned::span));
+                    spans.push(function.body.span());
+                    self.children(span, &spans);
+                    self.parameters(&function.parameters);
+                    function
+                        .return_type
+                        .iter()
+                        .for_each(|type_name| self.type_name(type_name));
+                    self.statement(&function.body);
+                }
+                CompilationUnitMember::Struct(struct_decl) => {
+                    self.doc(span, struct_decl.doc.as_ref().map(Spanned::span));
+                    let mut spans = self.attributes(&struct_decl.attributes);
+                    spans.push(struct_decl.identifier.span());
+                    spans.extend(struct_decl.members.iter().map(Spanned::span));
+                    self.children(span, &spans);
+
+                    for member in struct_decl.members.iter() {
+                        let member_span = member.span();
+                        let member = member.value_ref();
+                        self.doc(member_span, member.doc.as_ref().map(Spanned::span));
+                        let mut spans = self.attributes(&member.attributes);
+                        spans.extend([member.identifier.span(), member.type_name.span()]);
+                        self.children(member_span, &spans);
+                        self.type_name(&member.type_name);
+                    }
+                }
+            }
+        }
+    }
+
+    // Check the attributes of a declaration, returning their spans so that they can be
+    // checked against the rest of the declaration.
+    fn attributes(&self, attributes: &[Spanned<Attribute>]) -> Vec<Span> {
+        for attribute in attributes {
+            let attribute_span = attribute.span();
+            let attribute = attribute.value_ref();
+            let mut spans = vec![attribute.name.span()];
+
+            if let Some(arguments) = &attribute.arguments {
+                spans.push(arguments.span());
+                let argument_spans: Vec<Span> =
+                    arguments.value_ref().iter().map(Spanned::span).collect();
+                self.children(arguments.span(), &argument_spans);
+                arguments
+                    .value_ref()
+                    .iter()
+                    .for_each(|argument| self.spanned_expr(argument));
+            }
+
+            self.children(attribute_span, &spans);
+        }
+
+        attributes.iter().map(Spanned::span).collect()
+    }
+
+    // Documentation comments come before the declaration that they document.
+    fn doc(&self, declaration: Span, doc: Option<Span>) {
+        if let Some(doc) = doc {
+            assert_valid_span(self.source, doc);
+            assert!(
+                doc.end() <= declaration.start(),
+                "documentation at {} does not precede {}",
+                doc,
+                declaration
+            );
+        }
+    }
+
+    fn parameters(&self, parameters: &Spanned<Box<[Spanned<ParameterDecl>]>>) {
+        let spans: Vec<Span> = parameters.value_ref().iter().map(Spanned::span).collect();
+        self.children(parameters.span(), &spans);
+
+        for parameter in parameters.value_ref().iter() {
+            let span = parameter.span();
+            let parameter = parameter.value_ref();
+            self.doc(span, parameter.doc.as_ref().map(Spanned::span));
+            self.children(span, &[parameter.name.span(), parameter.type_name.span()]);
+            self.type_name(&parameter.type_name);
+        }
+    }
+
+    fn path(&self, path: &Spanned<IdentifierPath>) {
+        let path_value = path.value_ref();
+        let mut spans: Vec<Span> = path_value.qualifier.iter().map(Spanned::span).collect();
+        spans.push(path_value.local_name.span());
+        self.children(path.span(), &spans);
+    }
+
+    fn type_name(&self, type_name: &Spanned<TypeName>) {
+        let span = type_name.span();
+
+        match type_name.value_ref() {
+            TypeName::Path(path) => self.path(&Spanned::new(path.as_ref().clone(), span)),
+            TypeName::Function(function) => {
+                let mut spans = vec![function.parameters.span()];
+                spans.extend(function.return_type.as_ref().map(Spanned::span));
+                self.children(span, &spans);
+
+                let parameters: Vec<Span> = function
+                    .parameters
+                    .value_ref()
+                    .iter()
+                    .map(Spanned::span)
+                    .collect();
+                self.children(function.parameters.span(), &parameters);
+                function
+                    .parameters
+                    .value_ref()
+                    .iter()
+                    .for_each(|parameter| self.type_name(parameter));
+                function
+                    .return_type
+                    .iter()
+                    .for_each(|return_type| self.type_name(return_type));
+            }
+            TypeName::Tuple(tuple) => {
+                let elements: Vec<Span> = tuple.elements.iter().map(Spanned::span).collect();
+                self.children(span, &elements);
+                tuple
+                    .elements
+                    .iter()
+                    .for_each(|element| self.type_name(element));
+            }
+        }
+    }
+
+    fn pattern(&self, pattern: &Spanned<Pattern>) {
+        let span = pattern.span();
+        assert_valid_span(self.source, span);
+
+        if let Pattern::Tuple(elements) = pattern.value_ref() {
+            let spans: Vec<Span> = elements.iter().map(Spanned::span).collect();
+            self.children(span, &spans);
+            elements.iter().for_each(|element| self.pattern(element));
+        }
+    }
+
+    fn statement(&self, statement: &Spanned<Statement>) {
+        let span = statement.span();
+        assert_valid_span(self.source, span);
+
+        match statement.value_ref() {
+            Statement::Empty | Statement::Break | Statement::Continue => {}
+            Statement::Expr(expr) => self.expr(expr, span),
+            Statement::VarDecl(var_decl) => {
+                let mut spans = vec![var_decl.pattern.span()];
+                spans.extend(var_decl.type_name.as_ref().map(Spanned::span));
+                spans.extend(var_decl.expr.as_ref().map(Spanned::span));
+                self.children(span, &spans);
+                self.pattern(&var_decl.pattern);
+                var_decl
+                    .type_name
+                    .iter()
+                    .for_each(|type_name| self.type_name(type_name));
+                var_decl
+                    .expr
+                    .iter()
+                    .for_each(|expr| self.spanned_expr(expr));
+            }
+            Statement::If(if_statement) => {
+                let mut spans = vec![if_statement.condition.span(), if_statement.body.span()];
+                spans.extend(if_statement.otherwise.as_ref().map(Spanned::span));
+                self.children(span, &spans);
+                self.spanned_expr(&if_statement.condition);
+                self.statement(&if_statement.body);
+                if let Some(otherwise) = &if_statement.otherwise {
+                    self.statement(otherwise);
+                }
+            }
+            Statement::While(while_statement) => {
+                self.children(
+                    span,
+                    &[
+                        while_statement.condition.span(),
+                        while_statement.body.span(),
+                    ],
+                );
+                self.spanned_expr(&while_statement.condition);
+                self.statement(&while_statement.body);
+            }
+            Statement::Block(block) => {
+                let spans: Vec<Span> = block.statements.iter().map(Spanned::span).collect();
+                self.children(span, &spans);
+                block
+                    .statements
+                    .iter()
+                    .for_each(|statement| self.statement(statement));
+            }
+            Statement::Return(return_statement) => {
+                if let Some(expr) = &return_statement.expr {
+                    self.children(span, &[expr.span()]);
+                    self.spanned_expr(expr);
+                }
+            }
+        }
+    }
+
+    fn spanned_expr(&self, expr: &Spanned<Expr>) {
+        self.expr(expr.value_ref(), expr.span());
+    }
+
+    fn expr(&self, expr: &Expr, span: Span) {
+        assert_valid_span(self.source, span);
+
+        match expr {
+            Expr::Binary(binary) => {
+                self.children(span, &[binary.left.span(), binary.right.span()]);
+                self.spanned_expr(&binary.left);
+                self.spanned_expr(&binary.right);
+            }
+            Expr::Unary(unary) => {
+                self.children(span, &[unary.value.span()]);
+                self.spanned_expr(&unary.value);
+            }
+            Expr::Assignment(assignment) => {
+                self.children(span, &[assignment.lvalue.span(), assignment.rvalue.span()]);
+                self.spanned_expr(&assignment.lvalue);
+                self.spanned_expr(&assignment.rvalue);
+            }
+            Expr::MemberAccess(member_access) => {
+                self.children(
+                    span,
+                    &[member_access.owner.span(), member_access.member.span()],
+                );
+                self.spanned_expr(&member_access.owner);
+            }
+            Expr::Index(index) => {
+                self.children(span, &[index.owner.span(), index.index.span()]);
+                self.spanned_expr(&index.owner);
+                self.spanned_expr(&index.index);
+            }
+            Expr::FunctionCall(call) => {
+                self.children(span, &[call.identity.span(), call.arguments.span()]);
+                let spans: Vec<Span> = call
+                    .arguments
+                    .value_ref()
+                    .iter()
+                    .map(Spanned::span)
+                    .collect();
+                self.children(call.arguments.span(), &spans);
+                self.spanned_expr(&call.identity);
+                call.arguments
+                    .value_ref()
+                    .iter()
+                    .for_each(|argument| self.spanned_expr(argument));
+            }
+            Expr::Tuple(tuple) => {
+                let elements: Vec<Span> = tuple.elements.iter().map(Spanned::span).collect();
+                self.children(span, &elements);
+                tuple
+                    .elements
+                    .iter()
+                    .for_each(|element| self.spanned_expr(element));
+            }
+            Expr::Closure(closure) => {
+                let mut spans = vec![closure.parameters.span()];
+                spans.extend(closure.return_type.as_ref().map(Spanned::span));
+                spans.push(closure.body.span());
+                self.children(span, &spans);
+
+                let parameters: Vec<Span> = closure
+                    .parameters
+                    .value_ref()
+                    .iter()
+                    .map(Spanned::span)
+                    .collect();
+                self.children(closure.parameters.span(), &parameters);
+
+                for parameter in closure.parameters.value_ref().iter() {
+                    let mut spans = vec![parameter.value_ref().name.span()];
+                    spans.extend(parameter.value_ref().type_name.as_ref().map(Spanned::span));
+                    self.children(parameter.span(), &spans);
+                    parameter
+                        .value_ref()
+                        .type_name
+                        .iter()
+                        .for_each(|type_name| self.type_name(type_name));
+                }
+
+                closure
+                    .return_type
+                    .iter()
+                    .for_each(|return_type| self.type_name(return_type));
+                self.statement(&closure.body);
+            }
+            Expr::IdentifierPath(path) => {
+                let mut spans: Vec<Span> = path.qualifier.iter().map(Spanned::span).collect();
+                spans.push(path.local_name.span());
+                self.children(span, &spans);
+            }
+            Expr::Float(_)
+            | Expr::Int(_)
+            | Expr::Bool(_)
+            | Expr::String(_)
+            | Expr::Char(_)
+            | Expr::Byte(_)
+            | Expr::ByteString(_) => {}
+        }
+    }
+}
+
+#[test]
+fn generated_programs_round_trip_through_the_printer() {
+    for seed in 0..PROGRAMS {
+        // Given
+        let source = generate(seed);
+
+        // When
+        let (result, errors) = parse(&source);
+
+        // Then
+        let unit = result.unwrap_or_else(|err| {
+            panic!("seed {} failed to parse: {}\n{}", seed, err.value(), source)
+        });
+        assert!(
+            errors.is_empty(),
+            "seed {} reported errors: {:?}",
+            seed,
+            errors
+        );
+        assert_eq!(
+            print_compilation_unit(&unit),
+            source,
+            "seed {} did not round trip",
+            seed
+        );
+    }
+}
+
+#[test]
+fn generated_programs_have_well_formed_spans() {
+    for seed in 0..PROGRAMS {
+        // Given
+        let source = generate(seed);
+
+        // When
+        let (result, _) = parse(&source);
+
+        // Then
+        assert_tokens_are_well_formed(&source);
+        SpanChecker { source: &source }.unit(&result.unwrap());
+    }
+}
+
+#[test]
+fn mutated_programs_do_not_panic() {
+    let mut rng = Rng::new(0);
+
+    for seed in 0..PROGRAMS {
+        // Given
+        let source = mutate(&generate(seed), &mut rng);
+
+        // When
+        let (result, errors) = parse(&source);
+        let tree = parse_lossless(&source, Path::new("test.hkl"), &mut Vec::new());
+
+        // Then
+        assert_tokens_are_well_formed(&source);
+        assert_eq!(tree.text(), source, "seed {} lost text", seed);
+        errors
+            .iter()
+            .for_each(|error| assert_valid_span(&source, error.span()));
+
+        match result {
+            Ok(unit) => SpanChecker { source: &source }.unit(&unit),
+            Err(err) => assert_valid_span(&source, err.span()),
+        }
+    }
+}
+
+#[test]
+fn arbitrary_text_does_not_panic() {
+    let mut rng = Rng::new(1);
+
+    for _ in 0..PROGRAMS {
+        // Given
+        let source: String = (0..rng.below(64))
+            .map(|_| *rng.pick(FRAGMENTS))
+            .collect::<Vec<_>>()
+            .join(if rng.one_in(2) { " " } else { "" });
+
+        // When
+        let (result, errors) = parse(&source);
+
+        // Then
+        assert_tokens_are_well_formed(&source);
+        errors
+            .iter()
+            .for_each(|error| assert_valid_span(&source, error.span()));
+        if let Err(err) = result {
+            assert_valid_span(&source, err.span());
+        }
+    }
+}
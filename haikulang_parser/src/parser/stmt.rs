@@ -1,4 +1,5 @@
 use crate::ast::stmt::*;
+use crate::cst::kind::SyntaxKind;
 use crate::error::{ParserError, ParserResult};
 use crate::lexer::token::Token;
 use crate::parser::core::Parser;
@@ -16,28 +17,42 @@ impl<'src, 'err> Parser<'src, 'err> {
     //             | expr_statement , SEMICOLON  /* fallback */
     //             ;
     pub(super) fn parse_statement(&mut self) -> ParserResult<Statement> {
-        let first = self.current()?;
-        match first.value() {
-            Token::If => self.parse_if_statement(),
-            Token::While => self.parse_while_statement(),
-            Token::LeftBrace => self.parse_block_statement(),
-            Token::Let => self.take_line_statement(Self::parse_var_decl_statement),
-            Token::Break => self.take_line_statement(Self::parse_break_statement),
-            Token::Continue => self.take_line_statement(Self::parse_continue_statement),
-            Token::Return => self.take_line_statement(Self::parse_return_statement),
-            Token::Semicolon => self.take_line_statement(Self::parse_empty_statement),
-            _ => self.take_line_statement(Self::parse_expr_statement),
-        }
+        self.nested(|parser| {
+            let first = parser.current()?;
+            match first.value() {
+                Token::If => parser.parse_if_statement(),
+                Token::While => parser.parse_while_statement(),
+                Token::LeftBrace => parser.parse_block_statement(),
+                Token::Let => parser.take_line_statement(
+                    SyntaxKind::VarDeclStatement,
+                    Self::parse_var_decl_statement,
+                ),
+                Token::Break => parser
+                    .take_line_statement(SyntaxKind::BreakStatement, Self::parse_break_statement),
+                Token::Continue => parser.take_line_statement(
+                    SyntaxKind::ContinueStatement,
+                    Self::parse_continue_statement,
+                ),
+                Token::Return => parser
+                    .take_line_statement(SyntaxKind::ReturnStatement, Self::parse_return_statement),
+                Token::Semicolon => parser.parse_empty_statement(),
+                _ => parser
+                    .take_line_statement(SyntaxKind::ExprStatement, Self::parse_expr_statement),
+            }
+        })
     }
 
     // empty_statement ::= SEMICOLON ;
     fn parse_empty_statement(&mut self) -> ParserResult<Statement> {
+        let marker = self.marker();
         let token = self.eat(Token::Semicolon, "semicolon")?;
+        self.node(marker, SyntaxKind::EmptyStatement, token.span());
         Ok(Spanned::new(Statement::Empty, token.span()))
     }
 
     // if_statement ::= IF , LEFT_PAREN , expr , RIGHT_PAREN , statement , ( ELSE , statement )? ;
     fn parse_if_statement(&mut self) -> ParserResult<Statement> {
+        let marker = self.marker();
         let if_token = self.eat(Token::If, "'if' keyword")?;
         self.eat(Token::LeftParen, "left parenthesis")?;
         let condition = self.parse_expr()?;
@@ -56,6 +71,7 @@ impl<'src, 'err> Parser<'src, 'err> {
         } else {
             if_token.span().to(body.span())
         };
+        self.node(marker, SyntaxKind::IfStatement, span);
 
         Ok(Spanned::new(
             Statement::If(Box::from(IfStatement {
@@ -69,12 +85,14 @@ impl<'src, 'err> Parser<'src, 'err> {
 
     // while_statement ::= WHILE , LEFT_PAREN , expr , RIGHT_PAREN , statement ;
     fn parse_while_statement(&mut self) -> ParserResult<Statement> {
+        let marker = self.marker();
         let while_token = self.eat(Token::While, "'while' keyword")?;
         self.eat(Token::LeftParen, "left parenthesis")?;
         let condition = self.parse_expr()?;
         self.eat(Token::RightParen, "right parenthesis")?;
         let body = self.parse_statement()?;
         let span = while_token.span().to(body.span());
+        self.node(marker, SyntaxKind::WhileStatement, span);
 
         Ok(Spanned::new(
             Statement::While(Box::from(WhileStatement { condition, body })),
@@ -84,6 +102,7 @@ impl<'src, 'err> Parser<'src, 'err> {
 
     // block_statement ::= LEFT_BRACE , statement* , RIGHT_BRACE ;
     pub(super) fn parse_block_statement(&mut self) -> ParserResult<Statement> {
+        let marker = self.marker();
         let left_brace_token = self.eat(Token::LeftBrace, "left brace")?;
         let mut statements = Vec::<Spanned<Statement>>::new();
 
@@ -93,6 +112,7 @@ impl<'src, 'err> Parser<'src, 'err> {
 
         let right_brace_token = self.eat(Token::RightBrace, "right brace")?;
         let span = left_brace_token.span().to(right_brace_token.span());
+        self.node(marker, SyntaxKind::BlockStatement, span);
 
         Ok(Spanned::new(
             Statement::Block(Box::from(BlockStatement {
@@ -102,20 +122,20 @@ impl<'src, 'err> Parser<'src, 'err> {
         ))
     }
 
-    // var_decl_statement ::= LET , identifier , COLON , identifier_path , ( ASSIGN , expr )?
-    //                      | LET , identifier , ASSIGN , expr
+    // var_decl_statement ::= LET , pattern , COLON , type_name , ( ASSIGN , expr )?
+    //                      | LET , pattern , ASSIGN , expr
     //                      ;
     fn parse_var_decl_statement(&mut self) -> ParserResult<Statement> {
         let let_token = self.eat(Token::Let, "'let' keyword")?;
-        let identifier = self.parse_identifier()?;
+        let pattern = self.parse_pattern()?;
 
-        let (identifier_path, span) = if self.current()?.value() == Token::Colon {
+        let (type_name, span) = if self.current()?.value() == Token::Colon {
             self.advance();
-            let identifier_path = self.parse_identifier_path()?;
-            let span = let_token.span().to(identifier_path.span());
-            (Some(identifier_path), span)
+            let type_name = self.parse_type_name()?;
+            let span = let_token.span().to(type_name.span());
+            (Some(type_name), span)
         } else {
-            let span = let_token.span().to(identifier.span());
+            let span = let_token.span().to(pattern.span());
             (None, span)
         };
 
@@ -125,13 +145,25 @@ impl<'src, 'err> Parser<'src, 'err> {
             let span = let_token.span().to(expr.span());
             (Some(expr), span)
         } else {
-            // Use the type name span, which might just be the identifier span if no type
+            // Use the type name span, which might just be the pattern span if no type
             // declaration was present.
             let span = let_token.span().to(span);
             (None, span)
         };
 
-        if identifier_path.is_none() && expr.is_none() {
+        // The parts of a tuple can only be declared by taking them from a value.
+        if matches!(pattern.value_ref(), Pattern::Tuple(_)) && expr.is_none() {
+            let err = Spanned::new(
+                ParserError::SyntaxError(
+                    "expected assignment in variable declaration with a tuple pattern".to_string(),
+                ),
+                let_token.span().to(self.current()?.span()),
+            );
+            self.report_error(&err);
+            return Err(err);
+        }
+
+        if type_name.is_none() && expr.is_none() {
             let span = let_token.span().to(self.current()?.span());
             let err = Spanned::new(
                 ParserError::SyntaxError(
@@ -146,14 +178,48 @@ impl<'src, 'err> Parser<'src, 'err> {
 
         Ok(Spanned::new(
             Statement::VarDecl(Box::from(VarDeclStatement {
-                identifier,
-                type_name: identifier_path,
+                pattern,
+                type_name,
                 expr,
             })),
             span,
         ))
     }
 
+    // pattern ::= identifier
+    //           | LEFT_PAREN , ( pattern , ( COMMA , pattern )* , COMMA? )? , RIGHT_PAREN
+    //           ;
+    fn parse_pattern(&mut self) -> ParserResult<Pattern> {
+        let marker = self.marker();
+
+        if self.current()?.value() != Token::LeftParen {
+            let identifier = self.parse_identifier()?;
+            let span = identifier.span();
+            self.node(marker, SyntaxKind::IdentifierPattern, span);
+            return Ok(Spanned::new(
+                Pattern::Identifier(Box::new(identifier.value())),
+                span,
+            ));
+        }
+
+        let tuple = self.nested(|parser| parser.parse_tuple_elements(Self::parse_pattern))?;
+        let span = tuple.span();
+        let (mut elements, trailing_comma) = tuple.value();
+
+        // As with expressions, a single pattern within parentheses is just that pattern.
+        if elements.len() == 1 && !trailing_comma {
+            let element = elements.pop().unwrap();
+            self.widen_node(marker, span);
+            return Ok(Spanned::new(element.value(), span));
+        }
+
+        self.node(marker, SyntaxKind::TuplePattern, span);
+        Ok(Spanned::new(
+            Pattern::Tuple(elements.into_boxed_slice()),
+            span,
+        ))
+    }
+
     // break_statement ::= BREAK ;
     fn parse_break_statement(&mut self) -> ParserResult<Statement> {
         let break_token = self.eat(Token::Break, "'break' keyword")?;
@@ -195,12 +261,16 @@ impl<'src, 'err> Parser<'src, 'err> {
      * Helpers
      */
 
-    fn take_line_statement<F>(&mut self, func: F) -> ParserResult<Statement>
+    // Parse a statement that ends with a semicolon, which belongs to the statement's node
+    // within the syntax tree, despite not being part of its span.
+    fn take_line_statement<F>(&mut self, kind: SyntaxKind, func: F) -> ParserResult<Statement>
     where
         F: FnOnce(&mut Self) -> ParserResult<Statement>,
     {
+        let marker = self.marker();
         let statement = func(self)?;
-        self.eat(Token::Semicolon, "semicolon")?;
+        let semicolon = self.eat(Token::Semicolon, "semicolon")?;
+        self.node(marker, kind, statement.span().to(semicolon.span()));
         Ok(statement)
     }
 }
@@ -1,46 +1,70 @@
+use crate::ast::attr::Attribute;
+use crate::ast::doc::DocComment;
 use crate::ast::structs::{StructDecl, StructMemberDecl};
+use crate::ast::visibility::Visibility;
+use crate::cst::kind::SyntaxKind;
 use crate::error::ParserResult;
 use crate::lexer::token::Token;
+use crate::parser::attr::declaration_start;
 use crate::parser::core::Parser;
 use crate::span::Spanned;
 
 impl<'src, 'err> Parser<'src, 'err> {
     // struct_decl ::= STRUCT , identifier , LEFT_BRACE , ( struct_member , ( COMMA , struct_member )
-    pub(super) fn parse_struct_decl(&mut self) -> ParserResult<StructDecl> {
-        let start = self.eat(Token::Struct, "'struct' keyword")?;
+    pub(super) fn parse_struct_decl(
+        &mut self,
+        doc: Option<Spanned<DocComment>>,
+        attributes: Box<[Spanned<Attribute>]>,
+        visibility: Spanned<Visibility>,
+    ) -> ParserResult<StructDecl> {
+        self.eat(Token::Struct, "'struct' keyword")?;
+        let start = declaration_start(&attributes, &visibility);
         let identifier = self.parse_identifier()?;
         let mut members: Vec<Spanned<StructMemberDecl>> = Vec::new();
 
         self.eat(Token::LeftBrace, "left brace")?;
 
         while self.current()?.value() != Token::RightBrace {
-            members.push(self.parse_struct_member()?);
-            self.eat(Token::Semicolon, "semicolon")?;
+            let marker = self.marker();
+            let member = self.parse_struct_member()?;
+            let semicolon = self.eat(Token::Semicolon, "semicolon")?;
+            let span = member.span().to(semicolon.span());
+            self.node(marker, SyntaxKind::StructMemberDecl, span);
+            members.push(member);
         }
 
         let end = self.eat(Token::RightBrace, "right brace")?;
 
         Ok(Spanned::new(
             StructDecl {
+                doc,
+                attributes,
+                visibility: visibility.value(),
                 identifier,
                 members: Box::from(members),
             },
-            start.span().to(end.span()),
+            start.to(end.span()),
         ))
     }
 
-    // struct_member ::= identifier , COLON , identifier_path ;
+    // struct_member ::= attribute_list , visibility , identifier , COLON , type_name ;
     fn parse_struct_member(&mut self) -> ParserResult<StructMemberDecl> {
+        let doc = self.take_doc_comment();
+        let attributes = self.parse_attributes()?;
+        let visibility = self.parse_visibility()?;
         let identifier = self.parse_identifier()?;
         self.eat(Token::Colon, "colon")?;
-        let identifier_path = self.parse_identifier_path()?;
+        let type_name = self.parse_type_name()?;
 
-        let span = identifier.span().to(identifier_path.span());
+        let span = declaration_start(&attributes, &visibility).to(type_name.span());
 
         Ok(Spanned::new(
             StructMemberDecl {
+                doc,
+                attributes,
+                visibility: visibility.value(),
                 identifier,
-                type_name: identifier_path,
+                type_name,
             },
             span,
         ))
@@ -0,0 +1,102 @@
+use crate::ast::types::*;
+use crate::cst::kind::SyntaxKind;
+use crate::error::ParserResult;
+use crate::lexer::token::Token;
+use crate::parser::core::Parser;
+use crate::span::Spanned;
+
+impl<'src, 'err> Parser<'src, 'err> {
+    // type_name ::= identifier_path
+    //             | function_type_name
+    //             | tuple_type_name
+    //             ;
+    pub(super) fn parse_type_name(&mut self) -> ParserResult<TypeName> {
+        match self.current()?.value() {
+            Token::Fn => return self.nested(Self::parse_function_type_name),
+            Token::LeftParen => return self.nested(Self::parse_tuple_type_name),
+            _ => {}
+        }
+
+        let marker = self.marker();
+        let identifier_path = self.parse_identifier_path()?;
+        self.node(marker, SyntaxKind::IdentifierPath, identifier_path.span());
+        Ok(Spanned::new(
+            TypeName::Path(Box::from(identifier_path.value())),
+            identifier_path.span(),
+        ))
+    }
+
+    // function_type_name ::= FN , LEFT_PAREN , ( type_name , ( COMMA , type_name )* )? , RIGHT_PAREN , ( ARROW , type_name )? ;
+    fn parse_function_type_name(&mut self) -> ParserResult<TypeName> {
+        let marker = self.marker();
+        let start = self.eat(Token::Fn, "'fn' keyword")?;
+        let left_paren = self.eat(Token::LeftParen, "left parenthesis")?;
+        let mut parameters = Vec::<Spanned<TypeName>>::new();
+
+        while self.current()?.value() != Token::RightParen {
+            parameters.push(self.parse_type_name()?);
+
+            if self.current()?.value() == Token::Comma {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+
+        let right_paren = self.eat(Token::RightParen, "right parenthesis")?;
+        let parameters = Spanned::new(
+            parameters.into_boxed_slice(),
+            left_paren.span().to(right_paren.span()),
+        );
+        self.node(
+            marker,
+            SyntaxKind::FunctionTypeParameterList,
+            parameters.span(),
+        );
+
+        let return_type = if self.current()?.value() == Token::Arrow {
+            self.advance();
+            Some(self.parse_type_name()?)
+        } else {
+            None
+        };
+
+        let end = return_type
+            .as_ref()
+            .map(Spanned::span)
+            .unwrap_or(parameters.span());
+        let span = start.span().to(end);
+        self.node(marker, SyntaxKind::FunctionType, span);
+
+        Ok(Spanned::new(
+            TypeName::Function(Box::new(FunctionTypeName {
+                parameters,
+                return_type,
+            })),
+            span,
+        ))
+    }
+
+    // tuple_type_name ::= LEFT_PAREN , ( type_name , ( COMMA , type_name )* , COMMA? )? , RIGHT_PAREN ;
+    fn parse_tuple_type_name(&mut self) -> ParserResult<TypeName> {
+        let marker = self.marker();
+        let tuple = self.parse_tuple_elements(Self::parse_type_name)?;
+        let span = tuple.span();
+        let (mut elements, trailing_comma) = tuple.value();
+
+        // A single type within parentheses without a trailing comma is just that type.
+        if elements.len() == 1 && !trailing_comma {
+            let element = elements.pop().unwrap();
+            self.widen_node(marker, span);
+            return Ok(Spanned::new(element.value(), span));
+        }
+
+        self.node(marker, SyntaxKind::TupleType, span);
+        Ok(Spanned::new(
+            TypeName::Tuple(Box::new(TupleTypeName {
+                elements: elements.into_boxed_slice(),
+            })),
+            span,
+        ))
+    }
+}
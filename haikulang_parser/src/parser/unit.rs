@@ -1,18 +1,33 @@
 use crate::ast::unit::{CompilationUnit, CompilationUnitMember, UseDecl};
+use crate::ast::visibility::Visibility;
+use crate::cst::kind::SyntaxKind;
 use crate::error::{ParserError, ParserResult};
 use crate::lexer::token::Token;
 use crate::parser::core::Parser;
-use crate::span::Spanned;
+use crate::span::{Span, Spanned};
 use std::path::Path;
 
 impl<'src, 'err> Parser<'src, 'err> {
     // compilation_unit ::= compilation_unit_member* , EOF ;
     pub(super) fn parse_compilation_unit(&mut self, path: &Path) -> ParserResult<CompilationUnit> {
         let start = self.current()?.span();
+        // Only documentation before the first member applies to the compilation unit.
+        let doc = self.take_inner_doc_comment();
         let mut members: Vec<Spanned<CompilationUnitMember>> = Vec::new();
 
         while self.current()?.value() != Token::Eof {
-            members.push(self.parse_compilation_unit_member()?);
+            let marker = self.marker();
+            let offset = self.current()?.span().start();
+
+            match self.parse_compilation_unit_member() {
+                Ok(member) => members.push(member),
+                Err(err) => {
+                    // Keep whatever did parse within the syntax tree, beneath an error node
+                    // that takes the rest of the input.
+                    self.unfinished_node(marker, SyntaxKind::Error, offset);
+                    return Err(err);
+                }
+            }
         }
 
         // No need to advance, we're already at EOF.
@@ -22,6 +37,7 @@ impl<'src, 'err> Parser<'src, 'err> {
             CompilationUnit {
                 path: path.to_path_buf(),
                 name: path.file_name().unwrap().to_string_lossy().to_string(),
+                doc,
                 members: members.into_boxed_slice(),
             },
             start.to(end),
@@ -29,38 +45,74 @@ impl<'src, 'err> Parser<'src, 'err> {
     }
 
     // compilation_unit_member ::= use_decl , SEMICOLON
-    //                           | function_decl
-    //                           | struct_decl
+    //                           | attribute_list , visibility , extern_function_decl , SEMICOLON
+    //                           | attribute_list , visibility , function_decl
+    //                           | attribute_list , visibility , struct_decl
     //                           ;
     fn parse_compilation_unit_member(&mut self) -> ParserResult<CompilationUnitMember> {
+        // Documentation comes before any attributes and the visibility, so must be taken
+        // before we advance.
+        let doc = self.take_doc_comment();
+        let marker = self.marker();
+        let attributes = self.parse_attributes()?;
+        let visibility = self.parse_visibility()?;
+
         match self.current()?.value() {
+            Token::Use if !attributes.is_empty() => {
+                let err = Spanned::new(
+                    ParserError::SyntaxError("use declarations cannot have attributes".to_string()),
+                    attributes[0].span(),
+                );
+                self.report_error(&err);
+                Err(err)
+            }
+            Token::Use if visibility.value_ref().is_public() => {
+                let err = Spanned::new(
+                    ParserError::SyntaxError(
+                        "use declarations cannot be marked as pub".to_string(),
+                    ),
+                    visibility.span(),
+                );
+                self.report_error(&err);
+                Err(err)
+            }
             Token::Use => {
-                let use_decl = self.parse_use_decl();
-                self.eat(Token::Semicolon, "semicolon")?;
-                use_decl
+                let use_decl = self.parse_use_decl()?;
+                let semicolon = self.eat(Token::Semicolon, "semicolon")?;
+                let span = use_decl.span().to(semicolon.span());
+                self.node(marker, SyntaxKind::UseDecl, span);
+                Ok(use_decl)
             }
             Token::Extern => {
-                let extern_func_decl = self.parse_extern_function_decl()?;
-                self.eat(Token::Semicolon, "semicolon")?;
+                let extern_func_decl =
+                    self.parse_extern_function_decl(doc, attributes, visibility)?;
+                let semicolon = self.eat(Token::Semicolon, "semicolon")?;
                 let span = extern_func_decl.span();
+                self.node(
+                    marker,
+                    SyntaxKind::ExternFunctionDecl,
+                    span.to(semicolon.span()),
+                );
                 Ok(Spanned::new(
                     CompilationUnitMember::ExternFunction(Box::from(extern_func_decl.value())),
                     span,
                 ))
             }
             Token::Fn => {
-                let func_decl = self.parse_function_decl()?;
+                let func_decl = self.parse_function_decl(doc, attributes, visibility)?;
                 let span = func_decl.span();
+                self.node(marker, SyntaxKind::FunctionDecl, span);
                 Ok(Spanned::new(
                     CompilationUnitMember::Function(Box::from(func_decl.value())),
                     span,
                 ))
             }
             Token::Struct => {
-                let func_decl = self.parse_struct_decl()?;
-                let span = func_decl.span();
+                let struct_decl = self.parse_struct_decl(doc, attributes, visibility)?;
+                let span = struct_decl.span();
+                self.node(marker, SyntaxKind::StructDecl, span);
                 Ok(Spanned::new(
-                    CompilationUnitMember::Struct(Box::from(func_decl.value())),
+                    CompilationUnitMember::Struct(Box::from(struct_decl.value())),
                     span,
                 ))
             }
@@ -81,7 +133,9 @@ impl<'src, 'err> Parser<'src, 'err> {
     // use_decl ::= USE , identifier_path ;
     fn parse_use_decl(&mut self) -> ParserResult<CompilationUnitMember> {
         let use_token = self.eat(Token::Use, "'use' keyword")?;
+        let marker = self.marker();
         let path = self.parse_identifier_path()?;
+        self.node(marker, SyntaxKind::IdentifierPath, path.span());
         let span = use_token.span().to(path.span());
 
         Ok(Spanned::new(
@@ -89,4 +143,19 @@ impl<'src, 'err> Parser<'src, 'err> {
             span,
         ))
     }
+
+    // visibility ::= PUB? ;
+    //
+    // Private declarations are given an empty span at the start of the declaration.
+    pub(super) fn parse_visibility(&mut self) -> ParserResult<Visibility> {
+        let current = self.current()?;
+
+        if current.value_ref() == &Token::Pub {
+            self.advance();
+            return Ok(Spanned::new(Visibility::Public, current.span()));
+        }
+
+        let start = current.span().start();
+        Ok(Spanned::new(Visibility::Private, Span::new(start, start)))
+    }
 }
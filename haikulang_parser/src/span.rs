@@ -36,6 +36,18 @@ impl Span {
     pub fn is_unset(self) -> bool {
         self == Self::UNSET
     }
+
+    // Move the span by the given number of bytes. Unset spans are left alone.
+    pub fn shifted(&self, delta: isize) -> Self {
+        if self.is_unset() {
+            *self
+        } else {
+            Self::new(
+                self.start.saturating_add_signed(delta),
+                self.end.saturating_add_signed(delta),
+            )
+        }
+    }
 }
 
 impl fmt::Display for Span {
@@ -63,6 +75,11 @@ impl<T: Clone> Spanned<T> {
         self.value.clone()
     }
 
+    // Borrow the value, for when cloning it would be expensive, such as for large subtrees.
+    pub fn value_ref(&self) -> &T {
+        &self.value
+    }
+
     pub fn span(&self) -> Span {
         self.span
     }
@@ -113,6 +130,14 @@ mod tests {
         assert_eq!(range, 19..27);
     }
 
+    #[test_case(Span::new(19, 27),  5, Span::new(24, 32) ; "shifted forwards")]
+    #[test_case(Span::new(19, 27), -5, Span::new(14, 22) ; "shifted backwards")]
+    #[test_case(      Span::UNSET,  5,       Span::UNSET ; "unset span is not shifted")]
+    fn span_shifts_correctly(span: Span, delta: isize, expected: Span) {
+        // Then
+        assert_eq!(span.shifted(delta), expected);
+    }
+
     #[test_case(Span::new(19, 27), "19:27" ; "regular span")]
     #[test_case(      Span::UNSET, "unset" ; "unset span")]
     fn span_formats_correctly(span: Span, expected: &str) {